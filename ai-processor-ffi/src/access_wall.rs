@@ -0,0 +1,121 @@
+//! Login, cookie and paywall detection
+//!
+//! Pages fetched without the user's session often come back as a sign-in
+//! form, a cookie consent wall or a paywall teaser instead of the content the
+//! user saw. Such text is short and dominated by phrases like "sign in" or
+//! "subscribe". Summarizing it produces junk, so these pages are flagged and
+//! the UI can ask the user to open them while logged in.
+
+use serde::{Deserialize, Serialize};
+
+/// Pages longer than this are treated as real content
+const MAX_WALL_WORDS: usize = 400;
+/// A wall needs at least one phrase per this many words
+const WORDS_PER_PHRASE: usize = 100;
+/// And at least this many phrases
+const MIN_PHRASES: usize = 2;
+
+const LOGIN_PHRASES: &[&str] = &[
+    "sign in", "signin", "log in", "login", "forgot password", "forgot your password", "create account",
+    "create an account", "remember me", "username", "password", "two-factor", "anmelden", "passwort",
+    "connexion", "mot de passe", "iniciar sesión", "contraseña", "войти", "пароль",
+];
+
+const COOKIE_PHRASES: &[&str] = &[
+    "cookie", "consent", "accept all", "reject all", "manage preferences", "privacy settings",
+    "privacy policy", "partners", "legitimate interest", "personalised ads", "personalized ads",
+];
+
+const PAYWALL_PHRASES: &[&str] = &[
+    "subscribe", "subscription", "subscriber", "continue reading", "keep reading", "unlock",
+    "premium", "free trial", "per month", "a month", "paywall", "abonnieren", "abonnez-vous",
+    "suscríbete", "подписк",
+];
+
+/// Kind of wall standing in for a page's content
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccessWall {
+    /// The page shows its content
+    #[default]
+    None,
+    /// Sign-in or account creation form
+    Login,
+    /// Cookie consent screen hiding the page
+    CookieWall,
+    /// Subscription teaser
+    Paywall,
+}
+
+/// Detect whether a fetched page is a wall rather than content
+///
+/// `html` is only checked for password fields and may be empty.
+pub fn detect(title: &str, text: &str, html: &str) -> AccessWall {
+    let words = text.split_whitespace().count();
+    if words > MAX_WALL_WORDS {
+        return AccessWall::None;
+    }
+
+    let page = format!("{}\n{}", title, text).to_lowercase();
+    let password_field = if html.to_lowercase().contains("type=\"password\"") { MIN_PHRASES } else { 0 };
+    // Ties go to the earlier kind: a paywall teaser usually offers to sign in too
+    let candidates = [
+        (AccessWall::Paywall, count_phrases(&page, PAYWALL_PHRASES)),
+        (AccessWall::Login, count_phrases(&page, LOGIN_PHRASES) + password_field),
+        (AccessWall::CookieWall, count_phrases(&page, COOKIE_PHRASES)),
+    ];
+
+    candidates
+        .iter()
+        .filter(|(_, hits)| *hits >= MIN_PHRASES && hits * WORDS_PER_PHRASE >= words)
+        .fold(None, |best: Option<(AccessWall, usize)>, &(wall, hits)| match best {
+            Some((_, best_hits)) if best_hits >= hits => best,
+            _ => Some((wall, hits)),
+        })
+        .map_or(AccessWall::None, |(wall, _)| wall)
+}
+
+fn count_phrases(text: &str, phrases: &[&str]) -> usize {
+    phrases.iter().map(|phrase| text.matches(phrase).count()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_walls() {
+        assert_eq!(
+            detect("Sign in", "Sign in to your account. Username Password Remember me Forgot password?", ""),
+            AccessWall::Login
+        );
+        assert_eq!(
+            detect("", "Email", "<form><input type=\"password\" name=\"pw\"></form>"),
+            AccessWall::Login
+        );
+        assert_eq!(
+            detect(
+                "Before you continue",
+                "We use cookies and data to deliver our services. Accept all or reject all, or manage preferences.",
+                ""
+            ),
+            AccessWall::CookieWall
+        );
+
+        let teaser = format!(
+            "{} Subscribe to continue reading. Already a subscriber? Sign in. Start your free trial.",
+            "The minister said the plan would be announced next week. ".repeat(10)
+        );
+        assert_eq!(detect("Budget talks stall", &teaser, ""), AccessWall::Paywall);
+    }
+
+    #[test]
+    fn test_articles_are_not_walls() {
+        let article = "Rust guarantees memory safety without a garbage collector. ".repeat(10)
+            + "Subscribe to our newsletter.";
+        assert_eq!(detect("Rust", &article, ""), AccessWall::None);
+
+        let long = format!("{} Sign in. Log in. Password.", "word ".repeat(500));
+        assert_eq!(detect("Docs", &long, ""), AccessWall::None);
+        assert_eq!(detect("", "", ""), AccessWall::None);
+    }
+}
@@ -0,0 +1,230 @@
+//! User-defined content categories learned from examples
+//!
+//! Every category keeps the mean embedding of the pages assigned to it and of
+//! the pages the user rejected for it (Rocchio-style relevance feedback). A
+//! page is scored against a category by its similarity to the accepted
+//! centroid minus a fraction of its similarity to the rejected one, so each
+//! correction moves the category without retraining anything. The model is
+//! tied to the embedder that produced its vectors and stored as JSON.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use web_page_manager_core::AIProcessingError;
+
+use crate::embedding::cosine_similarity;
+
+/// Minimum score for a page to be assigned to a category
+pub const MIN_CATEGORY_SCORE: f64 = 0.2;
+/// Weight of the similarity to rejected pages
+const REJECTION_WEIGHT: f64 = 0.5;
+
+/// Running sum of embeddings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Centroid {
+    sum: Vec<f32>,
+    count: u32,
+}
+
+impl Centroid {
+    fn add(&mut self, embedding: &[f32]) {
+        if self.sum.len() != embedding.len() {
+            self.sum = vec![0.0; embedding.len()];
+            self.count = 0;
+        }
+        for (total, value) in self.sum.iter_mut().zip(embedding) {
+            *total += value;
+        }
+        self.count += 1;
+    }
+
+    /// Cosine similarity to the mean, which equals that to the sum
+    fn similarity(&self, embedding: &[f32]) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            cosine_similarity(&self.sum, embedding)
+        }
+    }
+}
+
+/// A user-defined category
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Category {
+    accepted: Centroid,
+    rejected: Centroid,
+}
+
+impl Category {
+    fn score(&self, embedding: &[f32]) -> f64 {
+        self.accepted.similarity(embedding) - REJECTION_WEIGHT * self.rejected.similarity(embedding)
+    }
+}
+
+/// Classifier over user-defined categories
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CategoryClassifier {
+    /// Name of the embedding model the centroids were built with
+    model: String,
+    categories: BTreeMap<String, Category>,
+}
+
+impl CategoryClassifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+    }
+
+    /// Names of the defined categories, sorted
+    pub fn category_names(&self) -> Vec<String> {
+        self.categories.keys().cloned().collect()
+    }
+
+    /// Define a category (if new) and learn from example embeddings
+    ///
+    /// Switching to a different embedding model discards all learned vectors,
+    /// since vectors of different models cannot be compared.
+    pub fn define(&mut self, name: &str, model: &str, examples: &[Vec<f32>]) {
+        self.use_model(model);
+        let category = self.categories.entry(name.to_string()).or_default();
+        for example in examples {
+            category.accepted.add(example);
+        }
+    }
+
+    /// Remove a category, returning whether it existed
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.categories.remove(name).is_some()
+    }
+
+    /// Learn from the user accepting or rejecting `name` for a page
+    ///
+    /// Accepting an unknown category defines it; rejecting one is ignored.
+    pub fn record_feedback(&mut self, name: &str, model: &str, embedding: &[f32], accepted: bool) {
+        self.use_model(model);
+        if accepted {
+            self.categories.entry(name.to_string()).or_default().accepted.add(embedding);
+        } else if let Some(category) = self.categories.get_mut(name) {
+            category.rejected.add(embedding);
+        }
+    }
+
+    /// Categories matching a page, best first, with their scores
+    ///
+    /// Empty if the embedding comes from a different model than the one the
+    /// categories were learned with.
+    pub fn classify(&self, model: &str, embedding: &[f32]) -> Vec<(String, f32)> {
+        if model != self.model || embedding.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<(String, f32)> = self
+            .categories
+            .iter()
+            .map(|(name, category)| (name.clone(), category.score(embedding)))
+            .filter(|(_, score)| *score >= MIN_CATEGORY_SCORE)
+            .map(|(name, score)| (name, score as f32))
+            .collect();
+        matches.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        matches
+    }
+
+    fn use_model(&mut self, model: &str) {
+        if self.model != model {
+            if !self.model.is_empty() {
+                tracing::warn!("Embedding model changed from {} to {}, resetting learned categories", self.model, model);
+            }
+            for category in self.categories.values_mut() {
+                *category = Category::default();
+            }
+            self.model = model.to_string();
+        }
+    }
+
+    /// Load a classifier from a JSON file
+    pub fn load(path: &Path) -> Result<Self, AIProcessingError> {
+        let data = std::fs::read_to_string(path).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to read category model {}: {}", path.display(), e),
+        })?;
+        serde_json::from_str(&data).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Invalid category model {}: {}", path.display(), e),
+        })
+    }
+
+    /// Save the classifier to a JSON file
+    pub fn save(&self, path: &Path) -> Result<(), AIProcessingError> {
+        let data = serde_json::to_string(self).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to serialize category model: {}", e),
+        })?;
+        std::fs::write(path, data).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to write category model {}: {}", path.display(), e),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(values: &[f32]) -> Vec<f32> {
+        let norm = values.iter().map(|v| v * v).sum::<f32>().sqrt();
+        values.iter().map(|v| v / norm).collect()
+    }
+
+    fn classifier() -> CategoryClassifier {
+        let mut classifier = CategoryClassifier::new();
+        classifier.define("Recipes", "test", &[unit(&[1.0, 0.1, 0.0]), unit(&[0.9, 0.0, 0.1])]);
+        classifier.define("Rust", "test", &[unit(&[0.0, 1.0, 0.1])]);
+        classifier
+    }
+
+    #[test]
+    fn test_classify_picks_nearest_centroid() {
+        let classifier = classifier();
+        let matches = classifier.classify("test", &unit(&[1.0, 0.0, 0.0]));
+        assert_eq!(matches[0].0, "Recipes");
+        assert!(matches.iter().all(|(name, _)| name != "Rust"));
+
+        assert!(classifier.classify("test", &unit(&[0.0, 0.0, 1.0])).is_empty());
+        assert!(classifier.classify("other-model", &unit(&[1.0, 0.0, 0.0])).is_empty());
+    }
+
+    #[test]
+    fn test_rejection_moves_category_away() {
+        let mut classifier = classifier();
+        let page = unit(&[0.5, 0.85, 0.0]);
+        let before = classifier.classify("test", &page);
+        assert_eq!(before[0].0, "Rust");
+
+        classifier.record_feedback("Rust", "test", &page, false);
+        classifier.record_feedback("Recipes", "test", &page, true);
+        let after = classifier.classify("test", &page);
+        assert_eq!(after[0].0, "Recipes");
+        let rust_after = after.iter().find(|(name, _)| name == "Rust").map_or(0.0, |m| m.1);
+        assert!(rust_after < before[0].1);
+    }
+
+    #[test]
+    fn test_model_change_resets_vectors() {
+        let mut classifier = classifier();
+        classifier.define("Travel", "other-model", &[unit(&[1.0])]);
+        assert_eq!(classifier.category_names(), vec!["Recipes", "Rust", "Travel"]);
+        assert!(classifier.classify("other-model", &unit(&[1.0])).iter().all(|(name, _)| name == "Travel"));
+    }
+
+    #[test]
+    fn test_save_load_roundtrip() {
+        let classifier = classifier();
+        let path = std::env::temp_dir().join(format!("categories_{}.json", std::process::id()));
+        classifier.save(&path).unwrap();
+        let loaded = CategoryClassifier::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let page = unit(&[1.0, 0.0, 0.0]);
+        assert_eq!(loaded.classify("test", &page), classifier.classify("test", &page));
+    }
+}
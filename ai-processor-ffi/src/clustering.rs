@@ -0,0 +1,200 @@
+//! Agglomerative clustering of page embeddings
+//!
+//! Builds the full average-linkage dendrogram once: every page starts as its
+//! own cluster and the two most similar clusters are merged until one is
+//! left, where the similarity of two clusters is the mean cosine similarity
+//! of their pages. Cutting the dendrogram at a threshold yields the groups;
+//! lowering the threshold merges groups, raising it splits them, and the
+//! merges below a group form its sub-groups. Average linkage never merges at
+//! a higher similarity than an earlier merge, so cuts are consistent.
+//!
+//! Building takes O(n³) time over n pages, fine for a few hundred tabs.
+//! Group ids are derived from the group's pages rather than its position, so
+//! they stay the same across runs as long as the group keeps its first page.
+
+use serde::Serialize;
+
+use crate::embedding::{cosine_similarity, fnv1a};
+use crate::progress::Stage;
+
+/// Merge of two clusters; ids below the leaf count are pages, the cluster
+/// created by merge `k` has id `leaves + k`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Merge {
+    pub left: usize,
+    pub right: usize,
+    pub similarity: f64,
+    pub size: usize,
+}
+
+/// Full merge history of a set of pages
+#[derive(Debug, Clone, Default)]
+pub struct Dendrogram {
+    leaves: usize,
+    merges: Vec<Merge>,
+}
+
+/// A group of pages at some cut of the dendrogram
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Cluster {
+    /// Page indices, ascending
+    pub members: Vec<usize>,
+    /// Similarity at which the group's last merge happened
+    pub similarity: f64,
+    /// Sub-groups of at least two pages, from the merges inside this one
+    pub children: Vec<Cluster>,
+}
+
+impl Dendrogram {
+    /// Cluster page embeddings, one step of `stage` per merge
+    ///
+    /// If the stage is cancelled the dendrogram is left incomplete.
+    pub fn build(embeddings: &[Vec<f32>], stage: &Stage) -> Self {
+        let n = embeddings.len();
+        let mut similarity: Vec<Vec<f64>> = (0..n)
+            .map(|i| (0..n).map(|j| cosine_similarity(&embeddings[i], &embeddings[j])).collect())
+            .collect();
+        // Cluster id and size held in each slot; merged slots are emptied
+        let mut slots: Vec<Option<(usize, usize)>> = (0..n).map(|i| Some((i, 1))).collect();
+        let mut merges = Vec::with_capacity(n.saturating_sub(1));
+
+        for step in 0..n.saturating_sub(1) {
+            let mut best: Option<(usize, usize, f64)> = None;
+            for a in 0..n {
+                if slots[a].is_none() {
+                    continue;
+                }
+                for b in (a + 1)..n {
+                    if slots[b].is_some() && best.is_none_or(|(_, _, s)| similarity[a][b] > s) {
+                        best = Some((a, b, similarity[a][b]));
+                    }
+                }
+            }
+            let Some((a, b, merged_similarity)) = best else { break };
+            let ((left, left_size), (right, right_size)) = (slots[a].unwrap(), slots[b].unwrap());
+
+            // Average linkage: the merged row is the size-weighted mean
+            for k in 0..n {
+                if slots[k].is_some() && k != a && k != b {
+                    let value = (similarity[a][k] * left_size as f64 + similarity[b][k] * right_size as f64)
+                        / (left_size + right_size) as f64;
+                    similarity[a][k] = value;
+                    similarity[k][a] = value;
+                }
+            }
+            slots[a] = Some((n + step, left_size + right_size));
+            slots[b] = None;
+            merges.push(Merge {
+                left,
+                right,
+                similarity: merged_similarity,
+                size: left_size + right_size,
+            });
+
+            if !stage.step() {
+                break;
+            }
+        }
+
+        Self { leaves: n, merges }
+    }
+
+    pub fn merges(&self) -> &[Merge] {
+        &self.merges
+    }
+
+    /// Groups of at least two pages whose pages were merged at `threshold`
+    /// or above, ordered by their first page
+    pub fn cut(&self, threshold: f64) -> Vec<Cluster> {
+        // Roots are merges at or above the threshold not merged again above it
+        let mut is_child = vec![false; self.merges.len()];
+        for merge in self.merges.iter().filter(|m| m.similarity >= threshold) {
+            for id in [merge.left, merge.right] {
+                if id >= self.leaves {
+                    is_child[id - self.leaves] = true;
+                }
+            }
+        }
+
+        let mut clusters: Vec<Cluster> = self
+            .merges
+            .iter()
+            .enumerate()
+            .filter(|(k, merge)| merge.similarity >= threshold && !is_child[*k])
+            .map(|(k, _)| self.cluster(self.leaves + k))
+            .collect();
+        clusters.sort_by_key(|c| c.members[0]);
+        clusters
+    }
+
+    /// The cluster created by merge node `id` with its sub-groups
+    fn cluster(&self, id: usize) -> Cluster {
+        let merge = &self.merges[id - self.leaves];
+        let mut members = Vec::with_capacity(merge.size);
+        let mut children = Vec::new();
+        for child in [merge.left, merge.right] {
+            if child < self.leaves {
+                members.push(child);
+            } else {
+                let sub = self.cluster(child);
+                members.extend(&sub.members);
+                children.push(sub);
+            }
+        }
+        members.sort_unstable();
+        children.sort_by_key(|c| c.members[0]);
+        Cluster {
+            members,
+            similarity: merge.similarity.clamp(0.0, 1.0),
+            children,
+        }
+    }
+}
+
+/// Id of a group from the keys (URLs or titles) of its pages
+///
+/// Only the smallest key is used, so adding or removing other pages keeps
+/// the id.
+pub fn group_id(member_keys: &[&str]) -> String {
+    let anchor = member_keys.iter().min().copied().unwrap_or_default();
+    format!("group-{:016x}", fnv1a(anchor.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::Progress;
+
+    fn dendrogram(points: &[[f32; 2]]) -> Dendrogram {
+        let embeddings: Vec<Vec<f32>> = points.iter().map(|p| p.to_vec()).collect();
+        let progress = Progress::silent();
+        Dendrogram::build(&embeddings, &progress.stage("group", embeddings.len()))
+    }
+
+    #[test]
+    fn test_cut_threshold_splits_and_merges() {
+        // Two tight pairs pointing in nearby directions, one outlier
+        let tree = dendrogram(&[[1.0, 0.0], [1.0, 0.05], [0.8, 0.6], [0.8, 0.65], [-1.0, 0.1]]);
+        assert_eq!(tree.merges().len(), 4);
+
+        let strict: Vec<Vec<usize>> = tree.cut(0.99).into_iter().map(|c| c.members).collect();
+        assert_eq!(strict, vec![vec![0, 1], vec![2, 3]]);
+
+        let loose = tree.cut(0.7);
+        assert_eq!(loose.len(), 1);
+        assert_eq!(loose[0].members, vec![0, 1, 2, 3]);
+        let children: Vec<&Vec<usize>> = loose[0].children.iter().map(|c| &c.members).collect();
+        assert_eq!(children, vec![&vec![0, 1], &vec![2, 3]]);
+
+        assert!(tree.cut(1.01).is_empty());
+        assert_eq!(tree.cut(-1.0)[0].members.len(), 5);
+    }
+
+    #[test]
+    fn test_group_id_depends_on_smallest_key() {
+        let id = group_id(&["https://b.example", "https://a.example"]);
+        assert_eq!(id, group_id(&["https://a.example", "https://c.example"]));
+        assert_ne!(id, group_id(&["https://b.example"]));
+        assert!(Dendrogram::build(&[], &Progress::silent().stage("group", 0)).cut(0.5).is_empty());
+    }
+}
@@ -0,0 +1,264 @@
+//! Code snippet detection and language identification
+//!
+//! Documentation and articles often matter to the user because of a code
+//! example ("that page with the tokio select! example"). Code blocks are
+//! taken from `<pre>` elements; their language comes from the usual
+//! `language-*` class hints or, failing that, from characteristic syntax.
+//! The notable symbols of a block (macros, qualified paths, method calls on
+//! modules) become keywords and key points, so the example can be found again.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// Symbols reported per page
+const MAX_SYMBOLS: usize = 5;
+/// Syntax matches needed to identify a language without a class hint
+const MIN_SYNTAX_MATCHES: usize = 2;
+
+/// Class hint aliases and the language they name
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("rust", "Rust"), ("rs", "Rust"), ("python", "Python"), ("py", "Python"), ("javascript", "JavaScript"),
+    ("js", "JavaScript"), ("jsx", "JavaScript"), ("typescript", "TypeScript"), ("ts", "TypeScript"),
+    ("tsx", "TypeScript"), ("go", "Go"), ("golang", "Go"), ("java", "Java"), ("kotlin", "Kotlin"),
+    ("swift", "Swift"), ("c", "C"), ("cpp", "C++"), ("c++", "C++"), ("csharp", "C#"), ("cs", "C#"),
+    ("ruby", "Ruby"), ("rb", "Ruby"), ("php", "PHP"), ("sql", "SQL"), ("bash", "Shell"), ("sh", "Shell"),
+    ("shell", "Shell"), ("console", "Shell"), ("zsh", "Shell"), ("html", "HTML"), ("css", "CSS"),
+    ("json", "JSON"), ("yaml", "YAML"), ("yml", "YAML"), ("toml", "TOML"),
+];
+
+/// Syntax characteristic of each language, checked when there is no hint
+const LANGUAGE_SYNTAX: &[(&str, &[&str])] = &[
+    ("Rust", &["fn ", "let mut ", "impl ", "pub fn ", "use std::", "#[derive", "&mut ", "-> Result<", "::new("]),
+    ("Python", &["def ", "import ", "elif ", "self.", "__init__", "print(", "None", "lambda "]),
+    ("JavaScript", &["function ", "const ", "=> {", "console.log", "require(", "document.", "await "]),
+    ("TypeScript", &[": string", ": number", "interface ", "export type ", ": boolean"]),
+    ("Go", &["func ", "package ", ":= ", "fmt.", "go func", "err != nil"]),
+    ("Java", &["public class ", "public static void", "System.out", "import java.", "private final "]),
+    ("C++", &["#include", "std::", "cout <<", "template<", "nullptr"]),
+    ("Shell", &["$ ", "sudo ", "apt ", "cargo ", "npm ", "pip ", "echo ", "export ", "cd "]),
+    ("SQL", &["SELECT ", "FROM ", "WHERE ", "INSERT INTO", "CREATE TABLE", "JOIN "]),
+];
+
+/// Symbols too common to describe an example
+const COMMON_SYMBOLS: &[&str] = &[
+    "println!", "print!", "eprintln!", "format!", "vec!", "assert!", "assert_eq!", "panic!", "console.log",
+    "std::io", "std::fmt", "fmt.Println", "System.out",
+];
+
+/// A code block found on a page
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeSnippet {
+    pub language: Option<&'static str>,
+    pub code: String,
+    /// Notable symbols, most frequent first
+    pub symbols: Vec<String>,
+}
+
+struct Patterns {
+    pre: Regex,
+    language_class: Regex,
+    tag: Regex,
+    macro_call: Regex,
+    path: Regex,
+    module_call: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let regex = |pattern: &str| Regex::new(pattern).expect("code pattern is valid");
+        Patterns {
+            pre: regex(r"(?is)<pre\b([^>]*)>(.*?)</pre>"),
+            language_class: regex(r#"(?i)class\s*=\s*["'][^"']*\b(?:language|lang|highlight|brush:)-?([a-z0-9+#]+)"#),
+            tag: regex(r"(?s)<[^>]*>"),
+            macro_call: regex(r"\b([A-Za-z_]\w*(?:::[A-Za-z_]\w*)*!)\s*[\(\[\{]"),
+            path: regex(r"\b([A-Za-z_]\w*(?:::[A-Za-z_]\w*)+)"),
+            module_call: regex(r"\b([a-z_]\w*\.[a-z_]\w*)\("),
+        }
+    })
+}
+
+/// Extract the code blocks of a page
+pub fn extract_snippets(html: &str) -> Vec<CodeSnippet> {
+    let patterns = patterns();
+    patterns
+        .pre
+        .captures_iter(html)
+        .filter_map(|block| {
+            // The hint may sit on <pre> or on the <code> inside it
+            let hint = patterns
+                .language_class
+                .captures(&block[1])
+                .or_else(|| patterns.language_class.captures(&block[2]))
+                .and_then(|c| language_from_hint(&c[1]));
+            let code = decode_entities(&patterns.tag.replace_all(&block[2], ""));
+            if code.trim().is_empty() {
+                return None;
+            }
+            let language = hint.or_else(|| identify_language(&code));
+            Some(CodeSnippet { language, symbols: symbols(&code), code })
+        })
+        .collect()
+}
+
+/// Identify the language of a code block from its syntax
+pub fn identify_language(code: &str) -> Option<&'static str> {
+    LANGUAGE_SYNTAX
+        .iter()
+        .map(|(language, syntax)| (*language, syntax.iter().map(|s| code.matches(s).count()).sum::<usize>()))
+        .filter(|(_, matches)| *matches >= MIN_SYNTAX_MATCHES)
+        .max_by_key(|(_, matches)| *matches)
+        .map(|(language, _)| language)
+}
+
+/// Keywords for the code on a page: its languages, then notable symbols
+pub fn keywords(snippets: &[CodeSnippet]) -> Vec<String> {
+    let mut keywords: Vec<String> = Vec::new();
+    for language in snippets.iter().filter_map(|s| s.language) {
+        if !keywords.iter().any(|k| k == language) {
+            keywords.push(language.to_string());
+        }
+    }
+    keywords.extend(page_symbols(snippets));
+    keywords
+}
+
+/// One key point per language, naming the symbols its examples use
+pub fn key_points(snippets: &[CodeSnippet]) -> Vec<String> {
+    let mut by_language: Vec<(&str, Vec<&CodeSnippet>)> = Vec::new();
+    for snippet in snippets {
+        let language = snippet.language.unwrap_or("Code");
+        match by_language.iter_mut().find(|(l, _)| *l == language) {
+            Some((_, group)) => group.push(snippet),
+            None => by_language.push((language, vec![snippet])),
+        }
+    }
+
+    by_language
+        .into_iter()
+        .map(|(language, group)| {
+            let owned: Vec<CodeSnippet> = group.into_iter().cloned().collect();
+            let symbols = page_symbols(&owned);
+            let noun = if owned.len() == 1 { "example" } else { "examples" };
+            let subject = if language == "Code" { "Code".to_string() } else { format!("{} code", language) };
+            if symbols.is_empty() {
+                format!("{} {}", subject, noun)
+            } else {
+                format!("{} {} using {}", subject, noun, symbols.join(", "))
+            }
+        })
+        .collect()
+}
+
+/// Most frequent symbols across snippets
+fn page_symbols(snippets: &[CodeSnippet]) -> Vec<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut order: Vec<&str> = Vec::new();
+    for symbol in snippets.iter().flat_map(|s| &s.symbols) {
+        let count = counts.entry(symbol).or_insert(0);
+        if *count == 0 {
+            order.push(symbol);
+        }
+        *count += 1;
+    }
+    // Stable sort keeps first appearance order among equals
+    order.sort_by_key(|symbol| std::cmp::Reverse(counts[symbol]));
+    order.into_iter().take(MAX_SYMBOLS).map(str::to_string).collect()
+}
+
+/// Macros, qualified paths and module calls, most frequent first
+fn symbols(code: &str) -> Vec<String> {
+    let patterns = patterns();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut order = Vec::new();
+    let mut add = |symbol: &str| {
+        if COMMON_SYMBOLS.iter().any(|common| symbol == *common || symbol.starts_with(&format!("{}::", common))) {
+            return;
+        }
+        let count = counts.entry(symbol.to_string()).or_insert(0);
+        if *count == 0 {
+            order.push(symbol.to_string());
+        }
+        *count += 1;
+    };
+
+    for c in patterns.macro_call.captures_iter(code) {
+        add(&c[1]);
+    }
+    for c in patterns.path.captures_iter(code) {
+        // Skip the path part of a macro call already counted
+        let end = c.get(1).map_or(0, |m| m.end());
+        if code[end..].starts_with('!') {
+            continue;
+        }
+        add(&c[1]);
+    }
+    for c in patterns.module_call.captures_iter(code) {
+        if !c[1].starts_with("self.") {
+            add(&c[1]);
+        }
+    }
+
+    order.sort_by_key(|symbol| std::cmp::Reverse(counts[symbol]));
+    order.truncate(MAX_SYMBOLS);
+    order
+}
+
+fn language_from_hint(hint: &str) -> Option<&'static str> {
+    let hint = hint.to_lowercase();
+    LANGUAGE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == hint)
+        .map(|(_, language)| *language)
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKIO_PAGE: &str = r#"<p>Waiting on two futures:</p>
+        <pre><code class="language-rust">tokio::select! {
+    _ = tokio::time::sleep(duration) =&gt; {}
+    msg = rx.recv() =&gt; println!("{:?}", msg),
+}</code></pre>
+        <pre class="highlight">async fn run() {
+    let mut interval = tokio::time::interval(period);
+    tokio::select! { _ = interval.tick() =&gt; {} }
+}</pre>"#;
+
+    #[test]
+    fn test_extracts_snippets_with_language_and_symbols() {
+        let snippets = extract_snippets(TOKIO_PAGE);
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].language, Some("Rust"));
+        assert!(snippets[0].code.contains("=> {}"));
+        assert_eq!(snippets[0].symbols[0], "tokio::select!");
+        assert!(snippets[0].symbols.contains(&"tokio::time::sleep".to_string()));
+        assert!(!snippets[0].symbols.iter().any(|s| s == "println!"));
+
+        let keywords = keywords(&snippets);
+        assert_eq!(keywords[0], "Rust");
+        assert_eq!(keywords[1], "tokio::select!");
+        assert_eq!(key_points(&snippets)[0].split(", ").next(), Some("Rust code examples using tokio::select!"));
+    }
+
+    #[test]
+    fn test_identify_language_from_syntax() {
+        assert_eq!(identify_language("def greet(name):\n    print(f'hi {name}')\nimport os"), Some("Python"));
+        assert_eq!(identify_language("package main\nfunc main() {\n    x := 1\n    fmt.Println(x)\n}"), Some("Go"));
+        assert_eq!(identify_language("$ cargo build\n$ cargo test"), Some("Shell"));
+        assert_eq!(identify_language("hello world"), None);
+    }
+}
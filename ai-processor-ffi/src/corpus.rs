@@ -0,0 +1,171 @@
+//! Corpus-level term statistics for keyword weighting
+//!
+//! Keeps document frequencies for every term seen across the page library so
+//! that keyword extraction can use BM25 weighting instead of raw in-document
+//! frequency. Boilerplate words such as "page", "click" or "home" occur in
+//! most documents and therefore get a low inverse document frequency.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use web_page_manager_core::AIProcessingError;
+
+/// BM25 term frequency saturation parameter
+const BM25_K1: f64 = 1.2;
+/// BM25 document length normalization parameter
+const BM25_B: f64 = 0.75;
+
+/// Document frequency table built from the page library
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CorpusStats {
+    /// Number of documents added to the corpus
+    pub document_count: u64,
+    /// Total number of tokens across all documents
+    pub total_tokens: u64,
+    /// Number of documents containing each term
+    pub document_frequency: HashMap<String, u64>,
+}
+
+impl CorpusStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether any documents have been added
+    pub fn is_empty(&self) -> bool {
+        self.document_count == 0
+    }
+
+    /// Add one document's tokens to the statistics
+    pub fn add_document(&mut self, tokens: &[String]) {
+        self.document_count += 1;
+        self.total_tokens += tokens.len() as u64;
+
+        let unique: HashSet<&String> = tokens.iter().collect();
+        for term in unique {
+            *self.document_frequency.entry(term.clone()).or_insert(0) += 1;
+        }
+    }
+
+    /// Average document length in tokens
+    pub fn average_document_length(&self) -> f64 {
+        if self.document_count == 0 {
+            0.0
+        } else {
+            self.total_tokens as f64 / self.document_count as f64
+        }
+    }
+
+    /// BM25 inverse document frequency of a term (always positive)
+    pub fn idf(&self, term: &str) -> f64 {
+        let n = self.document_count as f64;
+        let df = self.document_frequency.get(term).copied().unwrap_or(0) as f64;
+        (1.0 + (n - df + 0.5) / (df + 0.5)).ln()
+    }
+
+    /// BM25 weight of every distinct term in a document
+    pub fn bm25_weights(&self, tokens: &[String]) -> HashMap<String, f64> {
+        let mut term_frequency: HashMap<&String, usize> = HashMap::new();
+        for token in tokens {
+            *term_frequency.entry(token).or_insert(0) += 1;
+        }
+
+        let avg_len = self.average_document_length();
+        let length_ratio = if avg_len > 0.0 {
+            tokens.len() as f64 / avg_len
+        } else {
+            1.0
+        };
+        let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * length_ratio);
+
+        term_frequency
+            .into_iter()
+            .map(|(term, tf)| {
+                let tf = tf as f64;
+                let weight = self.idf(term) * (tf * (BM25_K1 + 1.0)) / (tf + norm);
+                (term.clone(), weight)
+            })
+            .collect()
+    }
+
+    /// Load statistics from a JSON file
+    pub fn load(path: &Path) -> Result<Self, AIProcessingError> {
+        let data = std::fs::read_to_string(path).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to read corpus statistics {}: {}", path.display(), e),
+        })?;
+        serde_json::from_str(&data).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Invalid corpus statistics {}: {}", path.display(), e),
+        })
+    }
+
+    /// Save statistics to a JSON file
+    pub fn save(&self, path: &Path) -> Result<(), AIProcessingError> {
+        let data = serde_json::to_string(self).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to serialize corpus statistics: {}", e),
+        })?;
+        std::fs::write(path, data).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to write corpus statistics {}: {}", path.display(), e),
+        })
+    }
+}
+
+/// Rank the terms of a document by BM25 weight, highest first
+///
+/// Ties are broken alphabetically so results are deterministic.
+pub fn rank_terms(tokens: &[String], stats: &CorpusStats) -> Vec<(String, f64)> {
+    let mut ranked: Vec<(String, f64)> = stats.bm25_weights(tokens).into_iter().collect();
+    ranked.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(words: &str) -> Vec<String> {
+        words.split_whitespace().map(|w| w.to_string()).collect()
+    }
+
+    fn sample_corpus() -> CorpusStats {
+        let mut stats = CorpusStats::new();
+        stats.add_document(&tokens("home page click rust compiler"));
+        stats.add_document(&tokens("home page click python interpreter"));
+        stats.add_document(&tokens("home page click cooking recipes"));
+        stats
+    }
+
+    #[test]
+    fn test_common_terms_have_lower_idf() {
+        let stats = sample_corpus();
+        assert!(stats.idf("page") < stats.idf("rust"));
+        assert!(stats.idf("unseen") > stats.idf("rust"));
+        assert!(stats.idf("page") > 0.0);
+    }
+
+    #[test]
+    fn test_rank_terms_prefers_distinctive_words() {
+        let stats = sample_corpus();
+        let ranked = rank_terms(&tokens("page page page click rust compiler"), &stats);
+        assert_eq!(ranked[0].0, "compiler");
+        assert_eq!(ranked[1].0, "rust");
+        assert_eq!(ranked.last().unwrap().0, "click");
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let stats = sample_corpus();
+        let path = std::env::temp_dir().join(format!("corpus_stats_{}.json", std::process::id()));
+        stats.save(&path).unwrap();
+
+        let loaded = CorpusStats::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.document_count, 3);
+        assert_eq!(loaded.document_frequency.get("home"), Some(&3));
+    }
+}
@@ -0,0 +1,216 @@
+//! Domain-based content type signals
+//!
+//! Many sites only ever serve one kind of content, so the page's domain is a
+//! stronger signal than words in its title. A bundled map covers well-known
+//! sites and users can add or override entries. A rule matches the domain
+//! itself and all of its subdomains ("youtube.com" matches
+//! "m.youtube.com"); a rule ending in ".*" matches any top-level domain
+//! ("amazon.*" matches "www.amazon.co.uk"). The most specific rule wins and
+//! user rules win over bundled ones.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use web_page_manager_core::AIProcessingError;
+
+use crate::CContentType;
+
+/// Bundled domain rules
+const BUNDLED_RULES: &[(&str, CContentType)] = &[
+    ("youtube.com", CContentType::Video),
+    ("youtu.be", CContentType::Video),
+    ("vimeo.com", CContentType::Video),
+    ("twitch.tv", CContentType::Video),
+    ("dailymotion.com", CContentType::Video),
+    ("tiktok.com", CContentType::Video),
+    ("bilibili.com", CContentType::Video),
+    ("stackoverflow.com", CContentType::Documentation),
+    ("stackexchange.com", CContentType::Documentation),
+    ("docs.rs", CContentType::Documentation),
+    ("readthedocs.io", CContentType::Documentation),
+    ("developer.mozilla.org", CContentType::Documentation),
+    ("docs.python.org", CContentType::Documentation),
+    ("learn.microsoft.com", CContentType::Documentation),
+    ("developer.apple.com", CContentType::Documentation),
+    ("twitter.com", CContentType::SocialMedia),
+    ("x.com", CContentType::SocialMedia),
+    ("facebook.com", CContentType::SocialMedia),
+    ("instagram.com", CContentType::SocialMedia),
+    ("linkedin.com", CContentType::SocialMedia),
+    ("reddit.com", CContentType::SocialMedia),
+    ("threads.net", CContentType::SocialMedia),
+    ("mastodon.social", CContentType::SocialMedia),
+    ("weibo.com", CContentType::SocialMedia),
+    ("amazon.*", CContentType::Shopping),
+    ("ebay.*", CContentType::Shopping),
+    ("aliexpress.com", CContentType::Shopping),
+    ("etsy.com", CContentType::Shopping),
+    ("walmart.com", CContentType::Shopping),
+    ("taobao.com", CContentType::Shopping),
+    ("jd.com", CContentType::Shopping),
+    ("bbc.com", CContentType::News),
+    ("bbc.co.uk", CContentType::News),
+    ("cnn.com", CContentType::News),
+    ("nytimes.com", CContentType::News),
+    ("reuters.com", CContentType::News),
+    ("apnews.com", CContentType::News),
+    ("theguardian.com", CContentType::News),
+    ("wikipedia.org", CContentType::Reference),
+    ("wiktionary.org", CContentType::Reference),
+    ("britannica.com", CContentType::Reference),
+    ("medium.com", CContentType::Article),
+    ("substack.com", CContentType::Article),
+];
+
+/// Domain to content type map
+#[derive(Debug, Clone, Default)]
+pub struct DomainCategories {
+    user_rules: BTreeMap<String, CContentType>,
+    /// Incremented on every change so cached results can be invalidated
+    revision: u64,
+}
+
+impl DomainCategories {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Add or replace a user rule; returns false for an invalid pattern
+    pub fn set_rule(&mut self, pattern: &str, content_type: CContentType) -> bool {
+        let pattern = normalize_pattern(pattern);
+        if pattern.is_empty() || pattern == "*" {
+            return false;
+        }
+        self.user_rules.insert(pattern, content_type);
+        self.revision += 1;
+        true
+    }
+
+    /// Remove a user rule, returning whether it existed
+    pub fn remove_rule(&mut self, pattern: &str) -> bool {
+        let removed = self.user_rules.remove(&normalize_pattern(pattern)).is_some();
+        if removed {
+            self.revision += 1;
+        }
+        removed
+    }
+
+    /// Content type for a page URL and the rule that matched
+    pub fn lookup(&self, url: &str) -> Option<(CContentType, String)> {
+        let host = host_of(url)?;
+        let labels: Vec<&str> = host.split('.').collect();
+
+        // Longest suffix first: the most specific rule wins
+        for start in 0..labels.len() {
+            let suffix = labels[start..].join(".");
+            if let Some(found) = self.rule(&suffix) {
+                return Some(found);
+            }
+            if start + 1 < labels.len() {
+                if let Some(found) = self.rule(&format!("{}.*", labels[start])) {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+
+    fn rule(&self, pattern: &str) -> Option<(CContentType, String)> {
+        self.user_rules
+            .get(pattern)
+            .copied()
+            .or_else(|| {
+                BUNDLED_RULES
+                    .iter()
+                    .find(|(bundled, _)| *bundled == pattern)
+                    .map(|(_, content_type)| *content_type)
+            })
+            .map(|content_type| (content_type, pattern.to_string()))
+    }
+
+    /// Load user rules from a JSON object of pattern to content type,
+    /// replacing the current ones
+    pub fn load(&mut self, path: &Path) -> Result<(), AIProcessingError> {
+        let data = std::fs::read_to_string(path).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to read domain rules {}: {}", path.display(), e),
+        })?;
+        let rules: BTreeMap<String, CContentType> = serde_json::from_str(&data).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Invalid domain rules {}: {}", path.display(), e),
+        })?;
+        self.user_rules = rules.into_iter().map(|(pattern, t)| (normalize_pattern(&pattern), t)).collect();
+        self.revision += 1;
+        Ok(())
+    }
+
+    /// Save user rules to a JSON file
+    pub fn save(&self, path: &Path) -> Result<(), AIProcessingError> {
+        let data = serde_json::to_string_pretty(&self.user_rules).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to serialize domain rules: {}", e),
+        })?;
+        std::fs::write(path, data).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to write domain rules {}: {}", path.display(), e),
+        })
+    }
+}
+
+fn normalize_pattern(pattern: &str) -> String {
+    let pattern = pattern.trim().to_lowercase();
+    let pattern = pattern.strip_prefix("www.").unwrap_or(&pattern);
+    pattern.trim_matches('.').to_string()
+}
+
+/// Lowercase host of a URL without credentials, port or trailing dot
+fn host_of(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?.trim_end_matches('.').to_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_rules_match_subdomains_and_wildcards() {
+        let domains = DomainCategories::new();
+        assert_eq!(domains.lookup("https://m.youtube.com/watch?v=1").unwrap().0, CContentType::Video);
+        assert_eq!(domains.lookup("https://www.amazon.co.uk/dp/123").unwrap(), (CContentType::Shopping, "amazon.*".to_string()));
+        assert_eq!(domains.lookup("https://serde.readthedocs.io/en/latest").unwrap().0, CContentType::Documentation);
+        assert!(domains.lookup("https://example.com/").is_none());
+        assert!(domains.lookup("").is_none());
+    }
+
+    #[test]
+    fn test_user_rules_override_bundled() {
+        let mut domains = DomainCategories::new();
+        assert!(domains.set_rule("www.YouTube.com", CContentType::Reference));
+        assert!(domains.set_rule("blog.example.com", CContentType::Article));
+        assert!(!domains.set_rule("*", CContentType::Other));
+
+        assert_eq!(domains.lookup("https://youtube.com/x").unwrap().0, CContentType::Reference);
+        assert_eq!(domains.lookup("http://user@blog.example.com:8080/post").unwrap().0, CContentType::Article);
+        assert!(domains.lookup("https://example.com").is_none());
+
+        assert!(domains.remove_rule("youtube.com"));
+        assert_eq!(domains.lookup("https://youtube.com/x").unwrap().0, CContentType::Video);
+    }
+
+    #[test]
+    fn test_save_load_roundtrip() {
+        let mut domains = DomainCategories::new();
+        domains.set_rule("intranet.corp", CContentType::Documentation);
+        let path = std::env::temp_dir().join(format!("domain_rules_{}.json", std::process::id()));
+        domains.save(&path).unwrap();
+
+        let mut loaded = DomainCategories::new();
+        loaded.load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.lookup("https://wiki.intranet.corp/page").unwrap().0, CContentType::Documentation);
+    }
+}
@@ -0,0 +1,324 @@
+//! Text embeddings for semantic similarity
+//!
+//! An `EmbeddingModel` maps text to a fixed-size, L2-normalized vector so that
+//! similarity becomes a dot product. The default `HashingEmbedder` needs no
+//! model files and hashes tokens into a signed feature vector. With the
+//! `onnx-embeddings` feature a sentence-embedding ONNX model (for example
+//! all-MiniLM-L6-v2) can be loaded from a configurable path instead.
+
+use std::path::PathBuf;
+
+use web_page_manager_core::AIProcessingError;
+
+use crate::tokenize;
+
+/// Dimensions of the built-in hashing embedder
+pub const DEFAULT_HASHING_DIMENSIONS: usize = 512;
+
+/// Model producing dense text embeddings
+pub trait EmbeddingModel: Send + Sync {
+    /// Embed `text` into an L2-normalized vector of `dimensions()` floats
+    fn embed(&self, text: &str) -> Result<Vec<f32>, AIProcessingError>;
+
+    /// Length of the vectors returned by `embed`
+    fn dimensions(&self) -> usize;
+
+    /// Identifier of the model, stored alongside persisted embeddings
+    fn name(&self) -> &str;
+}
+
+/// Feature-hashing embedder over the processor's token stream
+///
+/// Deterministic across runs and platforms (FNV-1a), so its vectors can be
+/// persisted and compared later.
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    dimensions: usize,
+    name: String,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        let dimensions = dimensions.max(1);
+        Self {
+            dimensions,
+            name: format!("hashing-{}", dimensions),
+        }
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASHING_DIMENSIONS)
+    }
+}
+
+impl EmbeddingModel for HashingEmbedder {
+    fn embed(&self, text: &str) -> Result<Vec<f32>, AIProcessingError> {
+        let mut vector = vec![0.0f32; self.dimensions];
+
+        for token in tokenize(text) {
+            let hash = fnv1a(token.as_bytes());
+            let index = (hash % self.dimensions as u64) as usize;
+            // Use an independent bit for the sign so collisions cancel out on average
+            let sign = if (hash >> 63) & 1 == 0 { 1.0 } else { -1.0 };
+            vector[index] += sign;
+        }
+
+        l2_normalize(&mut vector);
+        Ok(vector)
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Configuration for an ONNX sentence-embedding model
+#[derive(Debug, Clone)]
+pub struct OnnxEmbeddingConfig {
+    /// Path to the exported `model.onnx`
+    pub model_path: PathBuf,
+    /// Path to the `tokenizer.json` matching the model
+    pub tokenizer_path: PathBuf,
+    /// Inputs longer than this many tokens are truncated
+    pub max_sequence_length: usize,
+}
+
+impl OnnxEmbeddingConfig {
+    pub fn new(model_path: impl Into<PathBuf>, tokenizer_path: impl Into<PathBuf>) -> Self {
+        Self {
+            model_path: model_path.into(),
+            tokenizer_path: tokenizer_path.into(),
+            max_sequence_length: 256,
+        }
+    }
+}
+
+/// Load an ONNX sentence-embedding model
+pub fn load_onnx_embedder(config: &OnnxEmbeddingConfig) -> Result<Box<dyn EmbeddingModel>, AIProcessingError> {
+    #[cfg(feature = "onnx-embeddings")]
+    {
+        Ok(Box::new(onnx_backend::OnnxEmbedder::load(config)?))
+    }
+
+    #[cfg(not(feature = "onnx-embeddings"))]
+    {
+        Err(AIProcessingError::ModelLoadFailed {
+            model: format!(
+                "{} (built without the onnx-embeddings feature)",
+                config.model_path.display()
+            ),
+        })
+    }
+}
+
+/// Cosine similarity of two vectors, 0.0 if either is empty or zero
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+
+    let mut dot = 0.0f64;
+    let mut norm_a = 0.0f64;
+    let mut norm_b = 0.0f64;
+    for (x, y) in a.iter().zip(b) {
+        dot += (*x as f64) * (*y as f64);
+        norm_a += (*x as f64) * (*x as f64);
+        norm_b += (*y as f64) * (*y as f64);
+    }
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// Scale a vector to unit length in place (zero vectors are left untouched)
+pub(crate) fn l2_normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for v in vector.iter_mut() {
+            *v /= norm;
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(feature = "onnx-embeddings")]
+mod onnx_backend {
+    use tokenizers::{Tokenizer, TruncationParams};
+    use tract_onnx::prelude::*;
+
+    use super::*;
+
+    type OnnxPlan = std::sync::Arc<TypedRunnableModel>;
+
+    /// Transformer encoder with mean pooling over the attention mask
+    pub struct OnnxEmbedder {
+        plan: OnnxPlan,
+        tokenizer: Tokenizer,
+        input_count: usize,
+        dimensions: usize,
+        name: String,
+    }
+
+    impl OnnxEmbedder {
+        pub fn load(config: &OnnxEmbeddingConfig) -> Result<Self, AIProcessingError> {
+            let load_failed = |e: &dyn std::fmt::Display| AIProcessingError::ModelLoadFailed {
+                model: format!("{}: {}", config.model_path.display(), e),
+            };
+
+            let mut tokenizer = Tokenizer::from_file(&config.tokenizer_path).map_err(|e| load_failed(&e))?;
+            tokenizer
+                .with_truncation(Some(TruncationParams {
+                    max_length: config.max_sequence_length,
+                    ..Default::default()
+                }))
+                .map_err(|e| load_failed(&e))?;
+
+            let plan = tract_onnx::onnx()
+                .model_for_path(&config.model_path)
+                .and_then(|model| model.into_optimized())
+                .and_then(|model| model.into_runnable())
+                .map_err(|e| load_failed(&e))?;
+            let input_count = plan.model().inputs.len();
+
+            let name = config
+                .model_path
+                .file_stem()
+                .map(|s| format!("onnx-{}", s.to_string_lossy()))
+                .unwrap_or_else(|| "onnx".to_string());
+
+            let mut embedder = Self {
+                plan,
+                tokenizer,
+                input_count,
+                dimensions: 0,
+                name,
+            };
+            // Probe once to learn the output width
+            embedder.dimensions = embedder.run("dimension probe").map_err(|e| load_failed(&e))?.len();
+            Ok(embedder)
+        }
+
+        fn run(&self, text: &str) -> Result<Vec<f32>, Box<dyn std::error::Error + Send + Sync>> {
+            let encoding = self.tokenizer.encode(text, true)?;
+            let len = encoding.get_ids().len();
+            let to_tensor = |values: &[u32]| -> TractResult<Tensor> {
+                let values: Vec<i64> = values.iter().map(|v| *v as i64).collect();
+                Ok(tract_ndarray::Array2::from_shape_vec((1, len), values)?.into())
+            };
+
+            let inputs: TVec<TValue> = [
+                to_tensor(encoding.get_ids())?,
+                to_tensor(encoding.get_attention_mask())?,
+                to_tensor(encoding.get_type_ids())?,
+            ]
+            .into_iter()
+            .take(self.input_count)
+            .map(TValue::from)
+            .collect();
+
+            let outputs = self.plan.run(inputs)?;
+            // Last hidden state: [1, sequence, hidden]
+            let hidden = outputs[0].to_plain_array_view::<f32>()?;
+            let hidden_size = *hidden.shape().last().ok_or("model output has no dimensions")?;
+
+            let mut pooled = vec![0.0f32; hidden_size];
+            let mut weight = 0.0f32;
+            for (position, mask) in encoding.get_attention_mask().iter().enumerate() {
+                if *mask == 0 {
+                    continue;
+                }
+                for (i, value) in pooled.iter_mut().enumerate() {
+                    *value += hidden[[0, position, i]];
+                }
+                weight += 1.0;
+            }
+            if weight > 0.0 {
+                pooled.iter_mut().for_each(|v| *v /= weight);
+            }
+
+            l2_normalize(&mut pooled);
+            Ok(pooled)
+        }
+    }
+
+    impl EmbeddingModel for OnnxEmbedder {
+        fn embed(&self, text: &str) -> Result<Vec<f32>, AIProcessingError> {
+            self.run(text).map_err(|e| AIProcessingError::ProcessingFailed {
+                reason: format!("Embedding inference failed: {}", e),
+            })
+        }
+
+        fn dimensions(&self) -> usize {
+            self.dimensions
+        }
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashing_embedder_is_normalized() {
+        let embedder = HashingEmbedder::default();
+        let vector = embedder.embed("Rust programming language systems safety").unwrap();
+        assert_eq!(vector.len(), DEFAULT_HASHING_DIMENSIONS);
+
+        let norm: f32 = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_hashing_embedder_empty_text() {
+        let embedder = HashingEmbedder::new(64);
+        let vector = embedder.embed("").unwrap();
+        assert_eq!(vector.len(), 64);
+        assert!(vector.iter().all(|v| *v == 0.0));
+    }
+
+    #[test]
+    fn test_hashing_embedder_similarity_ordering() {
+        let embedder = HashingEmbedder::default();
+        let a = embedder.embed("machine learning neural networks training data").unwrap();
+        let b = embedder.embed("neural networks machine learning models").unwrap();
+        let c = embedder.embed("chocolate cake recipe with butter and sugar").unwrap();
+
+        assert!((cosine_similarity(&a, &a) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&a, &b) > cosine_similarity(&a, &c));
+    }
+
+    #[test]
+    fn test_cosine_similarity_mismatched_lengths() {
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[], &[]), 0.0);
+    }
+
+    #[cfg(not(feature = "onnx-embeddings"))]
+    #[test]
+    fn test_load_onnx_without_feature_fails() {
+        let result = load_onnx_embedder(&OnnxEmbeddingConfig::new("model.onnx", "tokenizer.json"));
+        assert!(matches!(result, Err(AIProcessingError::ModelLoadFailed { .. })));
+    }
+}
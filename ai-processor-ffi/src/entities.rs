@@ -0,0 +1,303 @@
+//! Typed named-entity extraction
+//!
+//! Rule-based recognizers for websites, monetary amounts, dates, product and
+//! version strings, organizations, locations and person names. Recognizers
+//! run from most to least specific and a span claimed by one cannot be
+//! claimed by another, so "March 2024" is a date rather than a person and
+//! "Acme Corp" an organization. Repeated mentions are merged into one entity
+//! with every byte offset recorded and a higher confidence.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// Kind of a recognized entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntityType {
+    Person,
+    Organization,
+    Location,
+    Date,
+    Money,
+    Product,
+    Website,
+}
+
+/// Entity found in a text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity {
+    pub name: String,
+    pub entity_type: EntityType,
+    pub confidence: f32,
+    /// Byte offsets of every mention, ascending
+    pub positions: Vec<usize>,
+}
+
+const MONTHS: &str = "January|February|March|April|May|June|July|August|September|October|November|December\
+                      |Jan|Feb|Mar|Apr|Jun|Jul|Aug|Sep|Sept|Oct|Nov|Dec";
+
+/// Countries and major cities recognized without further context
+const KNOWN_LOCATIONS: &[&str] = &[
+    "Afghanistan", "Argentina", "Australia", "Austria", "Belgium", "Brazil",
+    "Canada", "Chile", "China", "Colombia", "Denmark", "Egypt", "England",
+    "Finland", "France", "Germany", "Greece", "India", "Indonesia", "Iran",
+    "Iraq", "Ireland", "Israel", "Italy", "Japan", "Kenya", "Mexico",
+    "Netherlands", "New Zealand", "Nigeria", "Norway", "Pakistan", "Peru",
+    "Philippines", "Poland", "Portugal", "Russia", "Saudi Arabia", "Scotland",
+    "Singapore", "South Africa", "South Korea", "Spain", "Sweden",
+    "Switzerland", "Taiwan", "Thailand", "Turkey", "Ukraine",
+    "United Kingdom", "United States", "Vietnam", "Europe", "Asia", "Africa",
+    "North America", "South America", "Amsterdam", "Athens", "Bangkok",
+    "Barcelona", "Beijing", "Berlin", "Boston", "Brussels", "Buenos Aires",
+    "Cairo", "Chicago", "Delhi", "Dubai", "Dublin", "Hong Kong", "Istanbul",
+    "Jakarta", "Lisbon", "London", "Los Angeles", "Madrid", "Melbourne",
+    "Mexico City", "Milan", "Moscow", "Mumbai", "Munich", "Nairobi",
+    "New York", "Oslo", "Paris", "Prague", "Rome", "San Francisco", "Seattle",
+    "Seoul", "Shanghai", "Stockholm", "Sydney", "Tokyo", "Toronto",
+    "Vancouver", "Vienna", "Warsaw", "Washington", "Zurich",
+];
+
+/// Words that start a capitalized sequence without being part of a name
+const NON_NAME_WORDS: &[&str] = &[
+    "The", "A", "An", "This", "That", "These", "Those", "In", "On", "At",
+    "For", "From", "With", "By", "Of", "And", "But", "Or", "If", "When",
+    "While", "After", "Before", "Today", "Yesterday", "Tomorrow", "Monday",
+    "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday",
+];
+
+struct Recognizers {
+    website: Regex,
+    money: [Regex; 2],
+    date: [Regex; 4],
+    product: Regex,
+    organization: Regex,
+    location_suffix: Regex,
+    known_location: Regex,
+    person: Regex,
+}
+
+fn recognizers() -> &'static Recognizers {
+    static RECOGNIZERS: OnceLock<Recognizers> = OnceLock::new();
+    RECOGNIZERS.get_or_init(|| {
+        let regex = |pattern: &str| Regex::new(pattern).expect("entity pattern is valid");
+        let mut locations: Vec<&str> = KNOWN_LOCATIONS.to_vec();
+        // Longest first so "New York" wins over "York"-like prefixes
+        locations.sort_by_key(|l| std::cmp::Reverse(l.len()));
+
+        Recognizers {
+            website: regex(r"https?://([a-zA-Z0-9.-]+)"),
+            money: [
+                regex(r"[$€£¥]\s?\d[\d,]*(?:\.\d+)?(?:\s?(?:million|billion|trillion|thousand|[kKmMbB]\b))?"),
+                regex(r"\b\d[\d,]*(?:\.\d+)?\s?(?:million\s|billion\s)?(?:USD|EUR|GBP|JPY|CNY|dollars|euros|pounds|yen)\b"),
+            ],
+            date: [
+                regex(r"\b\d{4}-\d{2}-\d{2}\b"),
+                regex(r"\b\d{1,2}/\d{1,2}/\d{2,4}\b"),
+                regex(&format!(r"\b(?:{})\.?\s+\d{{1,2}}(?:st|nd|rd|th)?(?:,?\s+\d{{4}})?\b", MONTHS)),
+                regex(&format!(r"\b(?:\d{{1,2}}\s+)?(?:{})\.?,?\s+\d{{4}}\b", MONTHS)),
+            ],
+            product: regex(
+                r"\b((?:[A-Z]|[a-z]+[A-Z])[A-Za-z0-9+#]*(?:\s[A-Z][A-Za-z0-9+#]*)?)[\s-](v?\d+(?:\.\d+)+|v\d+|\d+)(?:\s(?:Pro|Max|Plus|Ultra|Mini|LTS|Beta|RC\d*))?\b",
+            ),
+            organization: regex(
+                r"\b[A-Z][A-Za-z&]*(?:\s+[A-Z][A-Za-z&]*)*\s+(?:Inc|Corp|Ltd|LLC|GmbH|Company|Corporation|Foundation|Institute|University|Association|Group)\b\.?",
+            ),
+            location_suffix: regex(
+                r"\b[A-Z][a-z]+(?:\s+[A-Z][a-z]+)*\s+(?:City|County|Province|State|River|Lake|Island|Mountains|Valley|Bay|Street|Avenue)\b",
+            ),
+            known_location: regex(&format!(r"\b(?:{})\b", locations.join("|"))),
+            person: regex(r"\b[A-Z][a-z]+(?:\s+[A-Z]\.)?(?:\s+[A-Z][a-z]+)+\b"),
+        }
+    })
+}
+
+/// Candidate mention before merging
+struct Mention {
+    start: usize,
+    end: usize,
+    name: String,
+    entity_type: EntityType,
+    confidence: f32,
+}
+
+/// Extract typed entities from `text`, most confident first
+pub fn extract_entities(text: &str) -> Vec<Entity> {
+    let r = recognizers();
+    let mut mentions: Vec<Mention> = Vec::new();
+
+    let mut claim = |start: usize, end: usize, name: &str, entity_type: EntityType, confidence: f32| {
+        if mentions.iter().any(|m| start < m.end && m.start < end) {
+            return;
+        }
+        mentions.push(Mention { start, end, name: name.trim_end_matches('.').to_string(), entity_type, confidence });
+    };
+
+    for cap in r.website.captures_iter(text) {
+        let whole = cap.get(0).expect("match has a whole group");
+        if let Some(domain) = cap.get(1) {
+            claim(whole.start(), whole.end(), domain.as_str(), EntityType::Website, 0.9);
+        }
+    }
+
+    for re in &r.money {
+        for m in re.find_iter(text) {
+            claim(m.start(), m.end(), m.as_str(), EntityType::Money, 0.85);
+        }
+    }
+
+    for re in &r.date {
+        for m in re.find_iter(text) {
+            claim(m.start(), m.end(), m.as_str(), EntityType::Date, 0.85);
+        }
+    }
+
+    for cap in r.product.captures_iter(text) {
+        let (Some(name), Some(version)) = (cap.get(1), cap.get(2)) else { continue };
+        if is_product_name(name.as_str(), version.as_str()) {
+            let whole = cap.get(0).expect("match has a whole group");
+            claim(whole.start(), whole.end(), whole.as_str(), EntityType::Product, 0.7);
+        }
+    }
+
+    for m in r.organization.find_iter(text) {
+        let (start, name) = strip_leading_non_name(m.start(), m.as_str());
+        claim(start, m.end(), name, EntityType::Organization, 0.75);
+    }
+
+    for m in r.known_location.find_iter(text) {
+        claim(m.start(), m.end(), m.as_str(), EntityType::Location, 0.8);
+    }
+    for m in r.location_suffix.find_iter(text) {
+        let (start, name) = strip_leading_non_name(m.start(), m.as_str());
+        claim(start, m.end(), name, EntityType::Location, 0.7);
+    }
+
+    for m in r.person.find_iter(text) {
+        let (start, name) = strip_leading_non_name(m.start(), m.as_str());
+        if name.contains(' ') {
+            claim(start, m.end(), name, EntityType::Person, 0.6);
+        }
+    }
+
+    merge_mentions(mentions)
+}
+
+/// Whether a capitalized word followed by a number looks like a product
+///
+/// Dotted or "v" versions always qualify; a plain number only follows names
+/// with inner capitals ("iPhone 15", "PlayStation 5") or acronyms ("GPT-4").
+fn is_product_name(name: &str, version: &str) -> bool {
+    let first_word = name.split_whitespace().next().unwrap_or(name);
+    if MONTHS.split('|').any(|month| month == first_word) || NON_NAME_WORDS.contains(&first_word) {
+        return false;
+    }
+    if version.contains('.') || version.starts_with('v') {
+        return name.chars().any(|c| c.is_uppercase());
+    }
+
+    let inner_capital = name.chars().skip(1).any(|c| c.is_uppercase());
+    let acronym = name.len() >= 2 && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    inner_capital || acronym
+}
+
+/// Drop a leading word such as "The" from a capitalized sequence
+fn strip_leading_non_name(start: usize, text: &str) -> (usize, &str) {
+    match text.split_once(' ') {
+        Some((first, rest)) if NON_NAME_WORDS.contains(&first) => (start + first.len() + 1, rest),
+        _ => (start, text),
+    }
+}
+
+/// Merge mentions of the same entity, raising confidence per repeat
+fn merge_mentions(mentions: Vec<Mention>) -> Vec<Entity> {
+    let mut merged: HashMap<(String, EntityType), Entity> = HashMap::new();
+    for mention in mentions {
+        merged
+            .entry((mention.name.clone(), mention.entity_type))
+            .and_modify(|entity| {
+                entity.confidence = (entity.confidence + 0.1).min(0.95);
+                entity.positions.push(mention.start);
+            })
+            .or_insert(Entity {
+                name: mention.name,
+                entity_type: mention.entity_type,
+                confidence: mention.confidence,
+                positions: vec![mention.start],
+            });
+    }
+
+    let mut entities: Vec<Entity> = merged.into_values().collect();
+    for entity in &mut entities {
+        entity.positions.sort_unstable();
+    }
+    entities.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.positions[0].cmp(&b.positions[0]))
+    });
+    entities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(entities: &'a [Entity], name: &str) -> &'a Entity {
+        entities
+            .iter()
+            .find(|e| e.name == name)
+            .unwrap_or_else(|| panic!("{} not found in {:?}", name, entities))
+    }
+
+    #[test]
+    fn test_dates_and_money() {
+        let text = "On March 5, 2024 the startup raised $12.5 million. The deal closed on 2024-04-01 for 300 EUR.";
+        let entities = extract_entities(text);
+
+        assert_eq!(find(&entities, "March 5, 2024").entity_type, EntityType::Date);
+        assert_eq!(find(&entities, "2024-04-01").entity_type, EntityType::Date);
+        assert_eq!(find(&entities, "$12.5 million").entity_type, EntityType::Money);
+        assert_eq!(find(&entities, "300 EUR").entity_type, EntityType::Money);
+    }
+
+    #[test]
+    fn test_locations_and_organizations() {
+        let text = "Acme Corp opened an office in New York near the Hudson River. Jane Smith leads it.";
+        let entities = extract_entities(text);
+
+        assert_eq!(find(&entities, "Acme Corp").entity_type, EntityType::Organization);
+        assert_eq!(find(&entities, "New York").entity_type, EntityType::Location);
+        assert_eq!(find(&entities, "Hudson River").entity_type, EntityType::Location);
+        assert_eq!(find(&entities, "Jane Smith").entity_type, EntityType::Person);
+    }
+
+    #[test]
+    fn test_products_and_versions() {
+        let text = "We tested Rust 1.75 on Windows 11 and an iPhone 15 Pro. Chapter 3 covers GPT-4.";
+        let entities = extract_entities(text);
+
+        assert_eq!(find(&entities, "Rust 1.75").entity_type, EntityType::Product);
+        assert_eq!(find(&entities, "iPhone 15 Pro").entity_type, EntityType::Product);
+        assert_eq!(find(&entities, "GPT-4").entity_type, EntityType::Product);
+        assert!(entities.iter().all(|e| e.name != "Chapter 3"));
+    }
+
+    #[test]
+    fn test_repeated_mentions_merge_positions() {
+        let text = "Ada Lovelace wrote notes. Later, Ada Lovelace published them.";
+        let entities = extract_entities(text);
+        let ada = find(&entities, "Ada Lovelace");
+
+        assert_eq!(ada.positions, vec![0, 33]);
+        assert!(ada.confidence > 0.6);
+    }
+
+    #[test]
+    fn test_month_is_not_a_person() {
+        let entities = extract_entities("The report from January 2024 was late.");
+        assert_eq!(find(&entities, "January 2024").entity_type, EntityType::Date);
+        assert!(entities.iter().all(|e| e.entity_type != EntityType::Person));
+    }
+}
@@ -0,0 +1,338 @@
+//! Linking entities and keywords to Wikidata items
+//!
+//! Names are resolved to candidate items through a `CandidateSource` (the
+//! Wikidata search API when built with the `wikidata` feature) and the
+//! candidate whose label and description share the most words with the
+//! surrounding page is chosen, so "Rust" on a programming page links to the
+//! language and on a chemistry page to the oxide. Candidate lists are cached
+//! per name and language and can be saved to disk, so pages already seen can
+//! be linked offline and repeated names cost a single lookup.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use web_page_manager_core::AIProcessingError;
+
+/// A Wikidata item that a name may refer to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WikidataCandidate {
+    /// Item id such as "Q575650"
+    pub id: String,
+    pub label: String,
+    pub description: String,
+}
+
+/// Source of candidate items for a name
+pub trait CandidateSource: Send + Sync {
+    /// Candidate items for `name`, most likely first
+    fn candidates(&self, name: &str, language: &str) -> Result<Vec<WikidataCandidate>, AIProcessingError>;
+}
+
+/// Serialized form of the candidate cache
+#[derive(Default, Serialize, Deserialize)]
+struct CacheFile {
+    candidates: HashMap<String, Vec<WikidataCandidate>>,
+}
+
+/// Resolves names to Wikidata items, caching candidate lists
+pub struct EntityLinker {
+    source: Option<Box<dyn CandidateSource>>,
+    cache: Mutex<HashMap<String, Vec<WikidataCandidate>>>,
+}
+
+impl Default for EntityLinker {
+    fn default() -> Self {
+        Self::offline()
+    }
+}
+
+impl EntityLinker {
+    /// Linker that only uses cached candidates
+    pub fn offline() -> Self {
+        Self {
+            source: None,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Linker that looks up uncached names with `source`
+    pub fn with_source(source: Box<dyn CandidateSource>) -> Self {
+        Self {
+            source: Some(source),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether uncached names are looked up online
+    pub fn is_online(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// Replace the source while keeping the cached candidates
+    pub fn set_source(&mut self, source: Option<Box<dyn CandidateSource>>) {
+        self.source = source;
+    }
+
+    /// Link `name` to the candidate that best fits `context` (page tokens)
+    ///
+    /// Returns `None` if the name has no candidates or, offline, has not been
+    /// looked up before. Lookup failures are logged and not cached.
+    pub fn link(&self, name: &str, context: &[String], language: &str) -> Option<WikidataCandidate> {
+        let candidates = self.candidates(name, language);
+        choose_candidate(&candidates, context)
+    }
+
+    fn candidates(&self, name: &str, language: &str) -> Vec<WikidataCandidate> {
+        let key = format!("{}|{}", language, name.to_lowercase());
+        if let Some(cached) = self.lock().get(&key) {
+            return cached.clone();
+        }
+
+        let Some(source) = &self.source else {
+            return Vec::new();
+        };
+        match source.candidates(name, language) {
+            Ok(candidates) => {
+                self.lock().insert(key, candidates.clone());
+                candidates
+            }
+            Err(e) => {
+                tracing::warn!("Wikidata lookup for {} failed: {}", name, e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Vec<WikidataCandidate>>> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Number of cached names
+    pub fn cached_count(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Load cached candidates from a JSON file, merging with the current ones
+    pub fn load_cache(&self, path: &Path) -> Result<(), AIProcessingError> {
+        let data = std::fs::read_to_string(path).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to read entity link cache {}: {}", path.display(), e),
+        })?;
+        let file: CacheFile = serde_json::from_str(&data).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Invalid entity link cache {}: {}", path.display(), e),
+        })?;
+        self.lock().extend(file.candidates);
+        Ok(())
+    }
+
+    /// Save cached candidates to a JSON file
+    pub fn save_cache(&self, path: &Path) -> Result<(), AIProcessingError> {
+        let file = CacheFile {
+            candidates: self.lock().clone(),
+        };
+        let data = serde_json::to_string(&file).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to serialize entity link cache: {}", e),
+        })?;
+        std::fs::write(path, data).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to write entity link cache {}: {}", path.display(), e),
+        })
+    }
+}
+
+/// Candidate source backed by the Wikidata search API
+pub fn wikidata_source() -> Result<Box<dyn CandidateSource>, AIProcessingError> {
+    #[cfg(feature = "wikidata")]
+    {
+        Ok(Box::new(wikidata_api::WikidataApi::new()?))
+    }
+
+    #[cfg(not(feature = "wikidata"))]
+    {
+        Err(AIProcessingError::ProcessingFailed {
+            reason: "Online entity linking is not available (built without the wikidata feature)".to_string(),
+        })
+    }
+}
+
+/// Pick the candidate sharing the most words with the context
+///
+/// Ties keep the source's ranking, so without useful context the most
+/// likely item wins.
+fn choose_candidate(candidates: &[WikidataCandidate], context: &[String]) -> Option<WikidataCandidate> {
+    let context: HashSet<String> = context.iter().map(|t| t.to_lowercase()).collect();
+    let overlap = |candidate: &WikidataCandidate| {
+        let words: HashSet<String> = format!("{} {}", candidate.label, candidate.description)
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| w.chars().count() > 2)
+            .map(|w| w.to_lowercase())
+            .collect();
+        words.iter().filter(|w| context.contains(*w)).count()
+    };
+
+    candidates
+        .iter()
+        .enumerate()
+        .max_by(|(ia, a), (ib, b)| overlap(a).cmp(&overlap(b)).then_with(|| ib.cmp(ia)))
+        .map(|(_, candidate)| candidate.clone())
+}
+
+#[cfg(feature = "wikidata")]
+mod wikidata_api {
+    use std::time::Duration;
+
+    use super::*;
+
+    const SEARCH_URL: &str = "https://www.wikidata.org/w/api.php";
+    /// Candidates requested per name
+    const SEARCH_LIMIT: &str = "7";
+
+    #[derive(Deserialize)]
+    struct SearchResponse {
+        #[serde(default)]
+        search: Vec<SearchResult>,
+    }
+
+    #[derive(Deserialize)]
+    struct SearchResult {
+        id: String,
+        #[serde(default)]
+        label: String,
+        #[serde(default)]
+        description: String,
+    }
+
+    /// Candidate source querying `wbsearchentities`
+    pub struct WikidataApi {
+        client: reqwest::blocking::Client,
+    }
+
+    impl WikidataApi {
+        pub fn new() -> Result<Self, AIProcessingError> {
+            let client = reqwest::blocking::Client::builder()
+                .timeout(Duration::from_secs(5))
+                .user_agent(concat!("web-page-manager/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .map_err(|e| AIProcessingError::ProcessingFailed {
+                    reason: format!("Failed to create HTTP client: {}", e),
+                })?;
+            Ok(Self { client })
+        }
+    }
+
+    impl CandidateSource for WikidataApi {
+        fn candidates(&self, name: &str, language: &str) -> Result<Vec<WikidataCandidate>, AIProcessingError> {
+            let response: SearchResponse = self
+                .client
+                .get(SEARCH_URL)
+                .query(&[
+                    ("action", "wbsearchentities"),
+                    ("format", "json"),
+                    ("search", name),
+                    ("language", language),
+                    ("uselang", language),
+                    ("limit", SEARCH_LIMIT),
+                ])
+                .send()
+                .and_then(|r| r.error_for_status())
+                .and_then(|r| r.json())
+                .map_err(|_| AIProcessingError::ContentFetchFailed {
+                    url: SEARCH_URL.to_string(),
+                })?;
+
+            Ok(response
+                .search
+                .into_iter()
+                .map(|r| WikidataCandidate {
+                    id: r.id,
+                    label: r.label,
+                    description: r.description,
+                })
+                .collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+
+    struct StaticSource {
+        lookups: Arc<AtomicUsize>,
+    }
+
+    impl CandidateSource for StaticSource {
+        fn candidates(&self, name: &str, _language: &str) -> Result<Vec<WikidataCandidate>, AIProcessingError> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            if name.eq_ignore_ascii_case("rust") {
+                Ok(vec![
+                    candidate("Q137056", "rust", "iron oxide formed by corrosion"),
+                    candidate("Q575650", "Rust", "general-purpose programming language"),
+                ])
+            } else {
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    fn candidate(id: &str, label: &str, description: &str) -> WikidataCandidate {
+        WikidataCandidate {
+            id: id.to_string(),
+            label: label.to_string(),
+            description: description.to_string(),
+        }
+    }
+
+    fn context(text: &str) -> Vec<String> {
+        text.split_whitespace().map(|w| w.to_string()).collect()
+    }
+
+    fn linker() -> (EntityLinker, Arc<AtomicUsize>) {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let source = StaticSource { lookups: Arc::clone(&lookups) };
+        (EntityLinker::with_source(Box::new(source)), lookups)
+    }
+
+    #[test]
+    fn test_context_disambiguates() {
+        let (linker, _) = linker();
+        let language = linker.link("Rust", &context("compiler programming language ownership"), "en");
+        let oxide = linker.link("rust", &context("iron corrosion on steel"), "en");
+
+        assert_eq!(language.unwrap().id, "Q575650");
+        assert_eq!(oxide.unwrap().id, "Q137056");
+    }
+
+    #[test]
+    fn test_lookups_are_cached_case_insensitively() {
+        let (linker, lookups) = linker();
+        linker.link("Rust", &[], "en");
+        linker.link("rust", &[], "en");
+        linker.link("unknown", &[], "en");
+
+        assert_eq!(lookups.load(Ordering::SeqCst), 2);
+        assert_eq!(linker.cached_count(), 2);
+        // Without context the source's first candidate wins
+        assert_eq!(linker.link("RUST", &[], "en").unwrap().id, "Q137056");
+    }
+
+    #[test]
+    fn test_offline_linker_uses_saved_cache() {
+        let (linker, _) = linker();
+        linker.link("Rust", &[], "en");
+
+        let path = std::env::temp_dir().join(format!("entity_links_{}.json", std::process::id()));
+        linker.save_cache(&path).unwrap();
+
+        let offline = EntityLinker::offline();
+        assert!(offline.link("Rust", &[], "en").is_none());
+        offline.load_cache(&path).unwrap();
+        let linked = offline.link("Rust", &context("programming language"), "en");
+        assert_eq!(linked.unwrap().id, "Q575650");
+
+        std::fs::remove_file(&path).ok();
+    }
+}
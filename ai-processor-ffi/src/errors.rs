@@ -0,0 +1,150 @@
+//! Error reporting across the FFI boundary
+//!
+//! FFI functions signal failure through their return value (-1, a null
+//! pointer or an empty struct) and record the reason in a thread-local slot
+//! that the caller reads with `ai_processor_last_error`. The slot works like
+//! `errno`: a failure overwrites it, a success leaves it alone, so callers
+//! of functions whose failure value is also a valid result (a similarity of
+//! 0.0, an empty summary) clear it first with `ai_processor_clear_error`.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::fmt::Display;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use serde::de::DeserializeOwned;
+use web_page_manager_core::AIProcessingError;
+
+/// Error codes reported by `ai_processor_last_error`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CErrorCode {
+    Ok = 0,
+    /// A required pointer argument was null
+    NullArgument = 1,
+    /// A string argument was not valid UTF-8
+    InvalidUtf8 = 2,
+    /// A JSON argument could not be parsed into the expected shape
+    InvalidJson = 3,
+    /// An argument was well-formed but not acceptable, like an empty name
+    InvalidArgument = 4,
+    /// The referenced job, category or label does not exist
+    NotFound = 5,
+    /// A model could not be loaded
+    ModelLoadFailed = 6,
+    /// Analysis ran but failed, including file reads and writes
+    ProcessingFailed = 7,
+    /// The progress callback cancelled the operation
+    Cancelled = 8,
+}
+
+impl From<&AIProcessingError> for CErrorCode {
+    fn from(error: &AIProcessingError) -> Self {
+        match error {
+            AIProcessingError::ModelLoadFailed { .. } => CErrorCode::ModelLoadFailed,
+            AIProcessingError::UnsupportedContentType { .. } => CErrorCode::InvalidArgument,
+            _ => CErrorCode::ProcessingFailed,
+        }
+    }
+}
+
+/// Last error reported to C callers
+#[repr(C)]
+pub struct CLastError {
+    /// A `CErrorCode`
+    pub code: c_int,
+    /// Free with `ai_processor_free_string`; null when there is no error
+    pub message: *mut c_char,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<(CErrorCode, String)>> = const { RefCell::new(None) };
+}
+
+/// Record an error for the current thread and return `value`
+pub(crate) fn fail<T>(code: CErrorCode, message: impl Display, value: T) -> T {
+    let message = message.to_string();
+    tracing::debug!("FFI call failed ({:?}): {}", code, message);
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some((code, message)));
+    value
+}
+
+/// Record a null argument error naming the arguments that must be set
+pub(crate) fn null_argument<T>(arguments: &str, value: T) -> T {
+    fail(CErrorCode::NullArgument, format!("{} must not be null", arguments), value)
+}
+
+/// Record an error raised by the processor
+pub(crate) fn processing_failed<T>(context: &str, error: &AIProcessingError, value: T) -> T {
+    fail(CErrorCode::from(error), format!("{}: {}", context, error), value)
+}
+
+/// Parse a JSON string argument, recording why it is invalid
+///
+/// # Safety
+/// `json` must be a valid, non-null C string
+pub(crate) unsafe fn json_argument<T: DeserializeOwned>(json: *const c_char, name: &str) -> Option<T> {
+    let text = match CStr::from_ptr(json).to_str() {
+        Ok(text) => text,
+        Err(e) => return fail(CErrorCode::InvalidUtf8, format!("{}: {}", name, e), None),
+    };
+    match serde_json::from_str(text) {
+        Ok(value) => Some(value),
+        Err(e) => fail(CErrorCode::InvalidJson, format!("{}: {}", name, e), None),
+    }
+}
+
+/// Code and message of the last failed call on this thread
+#[no_mangle]
+pub extern "C" fn ai_processor_last_error() -> CLastError {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some((code, message)) => CLastError {
+            code: *code as c_int,
+            message: CString::new(message.replace('\0', " ")).map(CString::into_raw).unwrap_or(ptr::null_mut()),
+        },
+        None => CLastError {
+            code: CErrorCode::Ok as c_int,
+            message: ptr::null_mut(),
+        },
+    })
+}
+
+/// Forget the last error on this thread
+#[no_mangle]
+pub extern "C" fn ai_processor_clear_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_error_is_per_thread() {
+        ai_processor_clear_error();
+        assert_eq!(fail(CErrorCode::InvalidJson, "expected an object", -1), -1);
+
+        let error = ai_processor_last_error();
+        assert_eq!(error.code, CErrorCode::InvalidJson as c_int);
+        assert_eq!(unsafe { CStr::from_ptr(error.message) }.to_str().unwrap(), "expected an object");
+        crate::ai_processor_free_string(error.message);
+
+        let other = std::thread::spawn(|| ai_processor_last_error().code).join().unwrap();
+        assert_eq!(other, CErrorCode::Ok as c_int);
+
+        ai_processor_clear_error();
+        let error = ai_processor_last_error();
+        assert_eq!(error.code, CErrorCode::Ok as c_int);
+        assert!(error.message.is_null());
+    }
+
+    #[test]
+    fn test_processing_error_codes() {
+        let error = AIProcessingError::ModelLoadFailed { model: "model.gguf".to_string() };
+        processing_failed("Failed to load local model", &error, ());
+        let last = ai_processor_last_error();
+        assert_eq!(last.code, CErrorCode::ModelLoadFailed as c_int);
+        crate::ai_processor_free_string(last.message);
+    }
+}
@@ -0,0 +1,314 @@
+//! Background job queue for long-running analysis
+//!
+//! Jobs are closures executed on a small pool of worker threads. Callers get a
+//! job id back immediately and can poll the status, take the result once the
+//! job has finished, cancel jobs that have not started yet, or register a
+//! callback that is invoked on the worker thread whenever a job finishes.
+//! Workers are started lazily on the first submission and joined on drop;
+//! jobs still pending at that point are discarded.
+
+use std::collections::{HashMap, VecDeque};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+
+/// Identifier of a submitted job (never 0)
+pub type JobId = u64;
+
+/// Work executed by a job
+pub type Task<T> = Box<dyn FnOnce() -> Result<T, String> + Send>;
+
+/// Callback invoked on the worker thread when a job finishes
+pub type CompletionCallback = Arc<dyn Fn(JobId, JobStatus) + Send + Sync>;
+
+/// Lifecycle state of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Final result of a finished job
+#[derive(Debug, PartialEq)]
+pub enum JobOutcome<T> {
+    Completed(T),
+    Failed(String),
+    Cancelled,
+}
+
+enum JobState<T> {
+    Pending(Task<T>),
+    Running,
+    Finished(JobOutcome<T>),
+}
+
+impl<T> JobState<T> {
+    fn status(&self) -> JobStatus {
+        match self {
+            JobState::Pending(_) => JobStatus::Pending,
+            JobState::Running => JobStatus::Running,
+            JobState::Finished(JobOutcome::Completed(_)) => JobStatus::Completed,
+            JobState::Finished(JobOutcome::Failed(_)) => JobStatus::Failed,
+            JobState::Finished(JobOutcome::Cancelled) => JobStatus::Cancelled,
+        }
+    }
+}
+
+struct QueueState<T> {
+    next_id: JobId,
+    order: VecDeque<JobId>,
+    jobs: HashMap<JobId, JobState<T>>,
+    on_complete: Option<CompletionCallback>,
+    shutdown: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<QueueState<T>>,
+    available: Condvar,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, QueueState<T>> {
+        // A panicking job is caught before it can poison the lock
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Queue of jobs executed by background worker threads
+pub struct JobQueue<T: Send + 'static> {
+    shared: Arc<Shared<T>>,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+    worker_count: usize,
+}
+
+impl<T: Send + 'static> JobQueue<T> {
+    /// Create a queue executing at most `worker_count` jobs concurrently
+    pub fn new(worker_count: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(QueueState {
+                    next_id: 1,
+                    order: VecDeque::new(),
+                    jobs: HashMap::new(),
+                    on_complete: None,
+                    shutdown: false,
+                }),
+                available: Condvar::new(),
+            }),
+            workers: Mutex::new(Vec::new()),
+            worker_count: worker_count.max(1),
+        }
+    }
+
+    /// Queue a task and return its id
+    pub fn submit(&self, task: Task<T>) -> JobId {
+        self.ensure_workers();
+
+        let mut state = self.shared.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.jobs.insert(id, JobState::Pending(task));
+        state.order.push_back(id);
+        drop(state);
+
+        self.shared.available.notify_one();
+        id
+    }
+
+    /// Current status of a job, `None` if unknown or already taken
+    pub fn status(&self, id: JobId) -> Option<JobStatus> {
+        self.shared.lock().jobs.get(&id).map(JobState::status)
+    }
+
+    /// Remove a finished job and return its outcome
+    ///
+    /// Returns `None` while the job is pending or running, or if it is unknown.
+    pub fn take_result(&self, id: JobId) -> Option<JobOutcome<T>> {
+        let mut state = self.shared.lock();
+        if !matches!(state.jobs.get(&id), Some(JobState::Finished(_))) {
+            return None;
+        }
+        match state.jobs.remove(&id) {
+            Some(JobState::Finished(outcome)) => Some(outcome),
+            _ => None,
+        }
+    }
+
+    /// Cancel a job that has not started yet
+    ///
+    /// Returns false if the job is unknown, running or already finished.
+    pub fn cancel(&self, id: JobId) -> bool {
+        let mut state = self.shared.lock();
+        match state.jobs.get_mut(&id) {
+            Some(job @ JobState::Pending(_)) => {
+                *job = JobState::Finished(JobOutcome::Cancelled);
+                state.order.retain(|queued| *queued != id);
+                let callback = state.on_complete.clone();
+                drop(state);
+
+                if let Some(callback) = callback {
+                    callback(id, JobStatus::Cancelled);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Number of jobs waiting to start
+    pub fn pending_count(&self) -> usize {
+        self.shared.lock().order.len()
+    }
+
+    /// Set the callback invoked when a job finishes (or is cancelled)
+    pub fn set_completion_callback(&self, callback: Option<CompletionCallback>) {
+        self.shared.lock().on_complete = callback;
+    }
+
+    fn ensure_workers(&self) {
+        let mut workers = self.workers.lock().unwrap_or_else(|e| e.into_inner());
+        while workers.len() < self.worker_count {
+            let shared = Arc::clone(&self.shared);
+            let index = workers.len();
+            let spawned = std::thread::Builder::new()
+                .name(format!("ai-job-worker-{}", index))
+                .spawn(move || worker_loop(shared));
+            match spawned {
+                Ok(handle) => workers.push(handle),
+                Err(e) => {
+                    tracing::warn!("Failed to start job worker: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl<T: Send + 'static> Drop for JobQueue<T> {
+    fn drop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.available.notify_all();
+
+        let workers = std::mem::take(&mut *self.workers.lock().unwrap_or_else(|e| e.into_inner()));
+        for worker in workers {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn worker_loop<T: Send + 'static>(shared: Arc<Shared<T>>) {
+    loop {
+        let mut state = shared.lock();
+        let (id, task) = loop {
+            if state.shutdown {
+                return;
+            }
+            if let Some(id) = state.order.pop_front() {
+                if let Some(job) = state.jobs.get_mut(&id) {
+                    if let JobState::Pending(task) = std::mem::replace(job, JobState::Running) {
+                        break (id, task);
+                    }
+                }
+                continue;
+            }
+            state = shared.available.wait(state).unwrap_or_else(|e| e.into_inner());
+        };
+        drop(state);
+
+        let outcome = match catch_unwind(AssertUnwindSafe(task)) {
+            Ok(Ok(value)) => JobOutcome::Completed(value),
+            Ok(Err(reason)) => JobOutcome::Failed(reason),
+            Err(_) => JobOutcome::Failed("job panicked".to_string()),
+        };
+
+        let mut state = shared.lock();
+        let finished = JobState::Finished(outcome);
+        let status = finished.status();
+        state.jobs.insert(id, finished);
+        let callback = state.on_complete.clone();
+        drop(state);
+
+        if let Some(callback) = callback {
+            callback(id, status);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn wait_for(queue: &JobQueue<u32>, id: JobId) -> JobOutcome<u32> {
+        for _ in 0..500 {
+            if let Some(outcome) = queue.take_result(id) {
+                return outcome;
+            }
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        panic!("job {} did not finish", id);
+    }
+
+    #[test]
+    fn test_jobs_complete_and_fail() {
+        let queue = JobQueue::new(2);
+        let ok = queue.submit(Box::new(|| Ok(42)));
+        let failed = queue.submit(Box::new(|| Err("bad input".to_string())));
+        let panicked = queue.submit(Box::new(|| panic!("boom")));
+
+        assert_eq!(wait_for(&queue, ok), JobOutcome::Completed(42));
+        assert_eq!(wait_for(&queue, failed), JobOutcome::Failed("bad input".to_string()));
+        assert!(matches!(wait_for(&queue, panicked), JobOutcome::Failed(_)));
+        assert_eq!(queue.status(ok), None);
+    }
+
+    #[test]
+    fn test_cancel_pending_job() {
+        let queue = JobQueue::new(1);
+        let (release, gate) = mpsc::channel::<()>();
+        let blocker = queue.submit(Box::new(move || {
+            gate.recv().ok();
+            Ok(1)
+        }));
+        let pending = queue.submit(Box::new(|| Ok(2)));
+
+        assert_eq!(queue.status(pending), Some(JobStatus::Pending));
+        assert!(queue.cancel(pending));
+        assert!(!queue.cancel(pending));
+        assert_eq!(queue.status(pending), Some(JobStatus::Cancelled));
+
+        release.send(()).unwrap();
+        assert_eq!(wait_for(&queue, blocker), JobOutcome::Completed(1));
+        assert_eq!(queue.take_result(pending), Some(JobOutcome::Cancelled));
+    }
+
+    #[test]
+    fn test_completion_callback() {
+        let queue = JobQueue::new(1);
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        queue.set_completion_callback(Some(Arc::new(move |id, status| {
+            sender.lock().unwrap().send((id, status)).ok();
+        })));
+
+        let id = queue.submit(Box::new(|| Ok(7)));
+        let (done_id, status) = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!((done_id, status), (id, JobStatus::Completed));
+    }
+
+    #[test]
+    fn test_drop_discards_pending_jobs() {
+        let queue: JobQueue<u32> = JobQueue::new(1);
+        for _ in 0..10 {
+            queue.submit(Box::new(|| {
+                std::thread::sleep(Duration::from_millis(5));
+                Ok(0)
+            }));
+        }
+        drop(queue);
+    }
+}
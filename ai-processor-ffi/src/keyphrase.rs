@@ -0,0 +1,174 @@
+//! RAKE keyphrase extraction
+//!
+//! Rapid Automatic Keyword Extraction splits text into candidate phrases at
+//! stop words and punctuation, scores each word by degree / frequency over the
+//! candidates, and ranks phrases by the sum of their word scores. This yields
+//! multi-word keyphrases such as "memory safety" instead of single tokens.
+//! Words are scored by stem, so "memory safety" and "memory safeties" are
+//! one phrase reported in its most frequent form.
+
+use std::collections::HashMap;
+
+use crate::stemming;
+use crate::STOP_WORDS;
+
+/// Longest candidate phrase kept, in words
+const MAX_PHRASE_WORDS: usize = 4;
+
+/// Extract up to `max_phrases` keyphrases from `text` in `language`, best first
+pub fn extract_keyphrases(text: &str, max_phrases: usize, language: &str) -> Vec<String> {
+    let candidates = candidate_phrases(text);
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+
+    let stemmed: Vec<Vec<String>> = candidates
+        .iter()
+        .map(|phrase| phrase.iter().map(|w| stemming::stem(w, language)).collect())
+        .collect();
+
+    let mut frequency: HashMap<&str, usize> = HashMap::new();
+    let mut degree: HashMap<&str, usize> = HashMap::new();
+    for phrase in &stemmed {
+        for word in phrase {
+            *frequency.entry(word.as_str()).or_insert(0) += 1;
+            *degree.entry(word.as_str()).or_insert(0) += phrase.len();
+        }
+    }
+
+    let mut scores: HashMap<String, f64> = HashMap::new();
+    let mut surfaces: HashMap<String, HashMap<String, usize>> = HashMap::new();
+    for (phrase, stems) in candidates.iter().zip(&stemmed) {
+        let score: f64 = stems
+            .iter()
+            .map(|w| degree[w.as_str()] as f64 / frequency[w.as_str()] as f64)
+            .sum();
+        let key = stems.join(" ");
+        *surfaces.entry(key.clone()).or_default().entry(phrase.join(" ")).or_insert(0) += 1;
+        scores.insert(key, score);
+    }
+
+    let mut ranked: Vec<(String, f64)> = scores
+        .into_iter()
+        .map(|(key, score)| (most_frequent(&surfaces[&key]), score))
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    ranked
+        .into_iter()
+        .take(max_phrases)
+        .map(|(phrase, _)| phrase)
+        .collect()
+}
+
+/// Most frequent surface form of a phrase, ties broken alphabetically
+fn most_frequent(forms: &HashMap<String, usize>) -> String {
+    forms
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(form, _)| form.clone())
+        .unwrap_or_default()
+}
+
+/// Split text into runs of content words delimited by stop words and punctuation
+fn candidate_phrases(text: &str) -> Vec<Vec<String>> {
+    let mut phrases = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut word = String::new();
+
+    // Runs longer than a phrase are split into phrase-sized pieces
+    let mut flush_phrase = |current: &mut Vec<String>| {
+        phrases.extend(current.chunks(MAX_PHRASE_WORDS).map(<[String]>::to_vec));
+        current.clear();
+    };
+
+    for c in text.chars().chain(std::iter::once('.')) {
+        if c.is_alphanumeric() {
+            word.extend(c.to_lowercase());
+            continue;
+        }
+
+        if !word.is_empty() {
+            if word.chars().count() > 2 && !STOP_WORDS.contains(&word.as_str()) {
+                current.push(std::mem::take(&mut word));
+            } else {
+                word.clear();
+                flush_phrase(&mut current);
+            }
+        }
+
+        // Whitespace continues a phrase; any other character ends it
+        if !c.is_whitespace() {
+            flush_phrase(&mut current);
+        }
+    }
+
+    phrases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidate_phrases_split_on_stop_words() {
+        let candidates = candidate_phrases("The borrow checker, and the type system.");
+        assert_eq!(
+            candidates,
+            vec![
+                vec!["borrow".to_string(), "checker".to_string()],
+                vec!["type".to_string(), "system".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_candidate_phrases_split_long_runs() {
+        let candidates = candidate_phrases("Rust provides memory safety without garbage collection");
+        assert_eq!(
+            candidates,
+            vec![
+                vec!["rust".to_string(), "provides".to_string(), "memory".to_string(), "safety".to_string()],
+                vec!["without".to_string(), "garbage".to_string(), "collection".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_candidate_phrases_fold_non_ascii_words() {
+        // Three-letter words with multi-byte letters are kept, and lowercased
+        let candidates = candidate_phrases("Über Öle; ÉCOLE");
+        assert_eq!(
+            candidates,
+            vec![
+                vec!["über".to_string(), "öle".to_string()],
+                vec!["école".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_keyphrases_prefers_multi_word_phrases() {
+        let text = "Memory safety matters. Rust has memory safety. The borrow checker is strict.";
+        let phrases = extract_keyphrases(text, 3, "en");
+        assert_eq!(phrases, vec!["memory safety matters", "memory safety", "borrow checker"]);
+    }
+
+    #[test]
+    fn test_extract_keyphrases_merges_inflections() {
+        let text = "Borrow checkers, the borrow checker and a borrow checker. Type systems.";
+        let phrases = extract_keyphrases(text, 5, "en");
+        assert_eq!(phrases.iter().filter(|p| p.starts_with("borrow")).count(), 1);
+        assert!(phrases.contains(&"borrow checker".to_string()));
+    }
+
+    #[test]
+    fn test_extract_keyphrases_empty() {
+        assert!(extract_keyphrases("", 5, "en").is_empty());
+        assert!(extract_keyphrases("the and of", 5, "en").is_empty());
+    }
+}
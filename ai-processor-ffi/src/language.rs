@@ -0,0 +1,244 @@
+//! Language-aware tokenization
+//!
+//! Splits text into runs by script. Alphabetic scripts (Latin, Cyrillic,
+//! Hangul) are split on non-alphanumerics as before. Han runs are segmented
+//! into words with jieba, katakana runs are kept as loanwords, and hiragana
+//! runs, which are mostly particles and inflections, are dropped. Stop words
+//! are filtered using the English list, the lists of scripts that cannot
+//! collide with it (Chinese, Japanese, Russian), and the list for the
+//! detected Latin-script language.
+
+use std::sync::OnceLock;
+
+use jieba_rs::Jieba;
+
+/// English stop words
+pub const ENGLISH_STOP_WORDS: &[&str] = &[
+    "the", "a", "an", "and", "or", "but", "in", "on", "at", "to", "for",
+    "of", "with", "by", "from", "as", "is", "was", "are", "were", "been",
+    "be", "have", "has", "had", "do", "does", "did", "will", "would", "could",
+    "should", "may", "might", "must", "shall", "can", "need", "dare", "ought",
+    "used", "this", "that", "these", "those", "i", "you", "he", "she", "it",
+    "we", "they", "what", "which", "who", "whom", "whose", "where", "when",
+    "why", "how", "all", "each", "every", "both", "few", "more", "most",
+    "other", "some", "such", "no", "nor", "not", "only", "own", "same",
+    "so", "than", "too", "very", "just", "also", "now", "here", "there",
+];
+
+/// Spanish stop words
+pub const SPANISH_STOP_WORDS: &[&str] = &[
+    "que", "para", "como", "pero", "por", "con", "una", "uno", "los", "las",
+    "del", "unos", "unas", "este", "esta", "estos", "estas", "ese", "esa",
+    "eso", "sus", "más", "muy", "sin", "sobre", "también", "entre", "cuando",
+    "donde", "todo", "todos", "hay", "ser", "fue", "son", "está", "están",
+    "han", "era", "tiene", "tienen", "desde", "hasta", "porque", "cual",
+    "quien", "otro", "otra", "otros", "otras", "mismo", "ella", "ellos",
+    "nosotros", "usted", "aquí", "ahora",
+];
+
+/// French stop words
+pub const FRENCH_STOP_WORDS: &[&str] = &[
+    "les", "des", "une", "que", "qui", "pour", "avec", "dans", "sur", "par",
+    "pas", "plus", "est", "sont", "ont", "été", "être", "avoir", "fait",
+    "mais", "ces", "cette", "ses", "leur", "leurs", "nous", "vous", "ils",
+    "elles", "aux", "dont", "tout", "tous", "très", "sans", "entre", "comme",
+    "aussi", "bien", "encore", "même",
+];
+
+/// German stop words
+pub const GERMAN_STOP_WORDS: &[&str] = &[
+    "der", "die", "das", "und", "den", "dem", "des", "ein", "eine", "einer",
+    "eines", "einem", "einen", "ist", "sind", "war", "waren", "wird",
+    "werden", "hat", "haben", "mit", "für", "auf", "von", "aus", "bei",
+    "nach", "über", "unter", "auch", "nicht", "noch", "nur", "wie", "wenn",
+    "oder", "aber", "dass", "sich", "sie", "ihr", "ihre", "wir", "uns",
+    "ich", "mich", "dir", "zum", "zur", "durch", "gegen", "ohne", "sehr",
+    "schon", "kann", "können",
+];
+
+/// Russian stop words
+pub const RUSSIAN_STOP_WORDS: &[&str] = &[
+    "что", "как", "все", "она", "так", "его", "только", "мне", "было",
+    "вот", "от", "меня", "еще", "нет", "из", "ему", "теперь", "когда",
+    "даже", "если", "уже", "или", "быть", "был", "него", "вас", "там",
+    "потом", "себя", "ничего", "может", "они", "тут", "где", "есть", "надо",
+    "ней", "для", "тебя", "чем", "была", "сам", "без", "чего", "тоже",
+    "себе", "под", "будет", "тогда", "кто", "этот", "того", "потому",
+    "этого", "какой", "здесь", "этом", "один", "почти", "тем", "чтобы",
+    "сейчас", "были", "всех", "можно", "при", "после", "над", "больше",
+    "тот", "через", "эти", "нас", "про", "них", "много", "эту", "этой",
+    "перед", "более", "всегда", "между",
+];
+
+/// Chinese stop words (single characters are dropped by length already)
+pub const CHINESE_STOP_WORDS: &[&str] = &[
+    "一个", "没有", "自己", "这个", "那个", "我们", "你们", "他们", "她们",
+    "它们", "因为", "所以", "但是", "如果", "可以", "什么", "怎么", "这样",
+    "那样", "还是", "或者", "已经", "以及", "而且", "并且", "然后", "其中",
+    "之后", "之前", "通过", "进行", "以后", "由于", "对于", "关于", "这些",
+    "那些", "一些", "不是", "就是", "只是", "还有", "可能", "需要", "一样",
+];
+
+/// Japanese stop words (hiragana runs are dropped during segmentation)
+pub const JAPANESE_STOP_WORDS: &[&str] = &[
+    "場合", "今回", "以下", "以上", "自分", "一部", "全部", "方法", "部分",
+    "時点", "前回", "次回", "本日", "今日",
+];
+
+/// Stop words for a language code as returned by language detection
+pub fn stop_words(language: &str) -> &'static [&'static str] {
+    match language {
+        "en" => ENGLISH_STOP_WORDS,
+        "es" => SPANISH_STOP_WORDS,
+        "fr" => FRENCH_STOP_WORDS,
+        "de" => GERMAN_STOP_WORDS,
+        "ru" => RUSSIAN_STOP_WORDS,
+        "zh" => CHINESE_STOP_WORDS,
+        "ja" => JAPANESE_STOP_WORDS,
+        _ => &[],
+    }
+}
+
+/// Whether `word` (lowercase) is a stop word for text in `language`
+pub fn is_stop_word(word: &str, language: &str) -> bool {
+    ["en", "ru", "zh", "ja"]
+        .iter()
+        .chain(std::iter::once(&language))
+        .any(|lang| stop_words(lang).contains(&word))
+}
+
+/// Script class of a character
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    /// Letters and digits of space-delimited scripts
+    Alphabetic,
+    Han,
+    Hiragana,
+    Katakana,
+    Separator,
+}
+
+fn script_of(c: char) -> Script {
+    if is_han(c) {
+        Script::Han
+    } else if ('\u{3040}'..='\u{309F}').contains(&c) {
+        Script::Hiragana
+    } else if ('\u{30A0}'..='\u{30FF}').contains(&c) || ('\u{31F0}'..='\u{31FF}').contains(&c) {
+        Script::Katakana
+    } else if c.is_alphanumeric() {
+        Script::Alphabetic
+    } else {
+        Script::Separator
+    }
+}
+
+/// CJK unified ideograph (including extension A)
+pub fn is_han(c: char) -> bool {
+    ('\u{4E00}'..='\u{9FFF}').contains(&c) || ('\u{3400}'..='\u{4DBF}').contains(&c)
+}
+
+/// Japanese kana (hiragana or katakana)
+pub fn is_kana(c: char) -> bool {
+    ('\u{3040}'..='\u{30FF}').contains(&c) || ('\u{31F0}'..='\u{31FF}').contains(&c)
+}
+
+/// Hangul syllable or jamo
+pub fn is_hangul(c: char) -> bool {
+    ('\u{AC00}'..='\u{D7AF}').contains(&c) || ('\u{1100}'..='\u{11FF}').contains(&c)
+}
+
+fn jieba() -> &'static Jieba {
+    static JIEBA: OnceLock<Jieba> = OnceLock::new();
+    JIEBA.get_or_init(Jieba::new)
+}
+
+/// Tokenize text written in `language`, filtering stop words
+pub fn tokenize(text: &str, language: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut run = String::new();
+    let mut run_script = Script::Separator;
+
+    for c in text.chars().chain(std::iter::once(' ')) {
+        let script = script_of(c);
+        if script == run_script && script != Script::Separator {
+            run.push(c);
+            continue;
+        }
+
+        push_run(&run, run_script, language, &mut tokens);
+        run.clear();
+        run.push(c);
+        run_script = script;
+    }
+
+    tokens
+}
+
+/// Turn one same-script run into tokens
+fn push_run(run: &str, script: Script, language: &str, tokens: &mut Vec<String>) {
+    let mut push = |word: String, min_chars: usize| {
+        if word.chars().count() >= min_chars && !is_stop_word(&word, language) {
+            tokens.push(word);
+        }
+    };
+
+    match script {
+        Script::Alphabetic => {
+            let word = run.to_lowercase();
+            // Hangul syllables carry more information per character
+            let min_chars = if word.chars().any(is_hangul) { 2 } else { 3 };
+            push(word, min_chars);
+        }
+        Script::Han => {
+            for word in jieba().cut(run, true) {
+                push(word.to_string(), 2);
+            }
+        }
+        Script::Katakana => push(run.to_string(), 2),
+        Script::Hiragana | Script::Separator => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_english_unchanged() {
+        let tokens = tokenize("The Rust compiler is fast, and it is safe.", "en");
+        assert_eq!(tokens, vec!["rust", "compiler", "fast", "safe"]);
+    }
+
+    #[test]
+    fn test_tokenize_chinese_segments_words() {
+        let tokens = tokenize("我们正在学习机器学习和自然语言处理。", "zh");
+        assert!(tokens.contains(&"机器".to_string()) || tokens.contains(&"机器学习".to_string()));
+        assert!(tokens.iter().any(|t| t.contains("语言")));
+        assert!(!tokens.contains(&"我们".to_string()));
+        assert!(tokens.iter().all(|t| t.chars().count() >= 2));
+    }
+
+    #[test]
+    fn test_tokenize_japanese_keeps_kanji_and_katakana() {
+        let tokens = tokenize("私はコンピューターで日本語を勉強します。", "ja");
+        assert!(tokens.contains(&"コンピューター".to_string()));
+        assert!(tokens.iter().any(|t| t.contains("勉強")));
+        assert!(!tokens.iter().any(|t| t.contains('は') || t.contains('を')));
+    }
+
+    #[test]
+    fn test_tokenize_language_specific_stop_words() {
+        let tokens = tokenize("Der Hund und die Katze spielen", "de");
+        assert_eq!(tokens, vec!["hund", "katze", "spielen"]);
+
+        // German articles are not removed from English text
+        let tokens = tokenize("Let it die down", "en");
+        assert_eq!(tokens, vec!["let", "die", "down"]);
+    }
+
+    #[test]
+    fn test_tokenize_cyrillic_lowercases() {
+        let tokens = tokenize("Москва является столицей", "ru");
+        assert_eq!(tokens, vec!["москва", "является", "столицей"]);
+    }
+}
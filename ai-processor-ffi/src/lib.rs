@@ -1,11 +1,65 @@
 #![allow(unused_imports)]
 
 use std::ffi::{CStr, CString};
-use std::os::raw::{c_char, c_int, c_float, c_double};
+use std::os::raw::{c_char, c_int, c_float, c_double, c_void};
 use std::ptr;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
+pub mod llm;
+pub mod embedding;
+pub mod corpus;
+pub mod keyphrase;
+pub mod topics;
+pub mod language;
+pub mod jobs;
+pub mod result_cache;
+pub mod readability;
+pub mod entities;
+pub mod entity_linking;
+pub mod sentiment;
+pub mod categories;
+pub mod domains;
+pub mod zero_shot;
+pub mod stemming;
+pub mod recall;
+pub mod pipeline;
+pub mod errors;
+pub mod progress;
+pub mod access_wall;
+pub mod quality;
+pub mod media;
+pub mod reader;
+pub mod code;
+pub mod clustering;
+pub mod trends;
+
+use llm::{LocalLlm, LocalLlmConfig, SummaryLength};
+use embedding::{EmbeddingModel, HashingEmbedder, OnnxEmbeddingConfig};
+use corpus::CorpusStats;
+use jobs::{JobId, JobOutcome, JobQueue, JobStatus};
+use result_cache::{cache_key, AnalysisKind, CacheEntry, ResultCache};
+use readability::{Difficulty, Readability};
+use entities::EntityType;
+use entity_linking::EntityLinker;
+use sentiment::SentimentLabel;
+use categories::CategoryClassifier;
+use domains::DomainCategories;
+use zero_shot::ZeroShotClassifier;
+use stemming::StemmedTokens;
+use recall::{RecallFacts, SaveContext};
+use pipeline::PipelineConfig;
+use errors::{fail, json_argument, null_argument, processing_failed, CErrorCode};
+use progress::{Progress, Stage};
+use access_wall::AccessWall;
+use quality::Quality;
+use media::MediaSummary;
+use trends::{Granularity, TrendReport};
+
 /// C-compatible AI processor interface
+///
+/// A handle may be shared between threads; calls are synchronized internally.
 #[repr(C)]
 pub struct CAIProcessor {
     _private: [u8; 0],
@@ -21,6 +75,25 @@ pub struct PageContentInput {
     pub keywords: Vec<String>,
     pub images: Vec<String>,
     pub links: Vec<String>,
+    /// Page URL, used for domain-based classification
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// Page visited at a given time, for trend analysis
+#[derive(Debug, Clone, Deserialize)]
+struct PageVisitInput {
+    /// Local time of the visit, as RFC 3339 with the user's UTC offset
+    visited_at: chrono::DateTime<chrono::FixedOffset>,
+    #[serde(flatten)]
+    content: PageContentInput,
+}
+
+/// Topic and keyword trends returned by `ai_processor_analyze_trends`
+#[derive(Debug, Clone, Serialize)]
+struct TrendAnalysis {
+    topics: TrendReport,
+    keywords: TrendReport,
 }
 
 /// C-compatible content summary
@@ -33,6 +106,28 @@ pub struct CContentSummary {
     pub language: *mut c_char,
     pub reading_time_minutes: u32,
     pub confidence_score: c_float,
+    /// Reading ease, 0 (very hard) to 100 (very easy)
+    pub readability_score: c_float,
+    pub reading_difficulty: c_int,
+    /// A `CAccessWall`; walled pages have no summary text or key points
+    pub access_wall: c_int,
+    /// Worth keeping, 0 (low value) to 1 (high-quality reference)
+    pub quality_score: c_float,
+    /// Clickbait title, 0 (plain) to 1 (clickbait)
+    pub clickbait_score: c_float,
+    /// Absolute URL of the image to show in previews, null if none
+    pub image_url: *mut c_char,
+    pub image_count: u32,
+    /// Videos and embedded players
+    pub video_count: u32,
+}
+
+/// C-compatible analysis result for one page of a batch
+#[repr(C)]
+pub struct CPageAnalysis {
+    pub summary: CContentSummary,
+    pub keywords: *mut *mut c_char,
+    pub keywords_count: usize,
 }
 
 /// C-compatible category info
@@ -42,6 +137,8 @@ pub struct CCategoryInfo {
     pub secondary_categories: *mut *mut c_char,
     pub secondary_count: usize,
     pub confidence: c_float,
+    /// How the category was decided, a `CClassificationSource`
+    pub source: c_int,
 }
 
 /// C-compatible processing mode
@@ -51,11 +148,59 @@ pub enum CProcessingMode {
     Basic = 0,
     Enhanced = 1,
     Auto = 2,
+    /// Rewritten summaries from the local model in a chosen length
+    Abstractive = 3,
 }
 
-/// Content type enum matching C++ side
+/// Length preset for abstractive summaries
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CSummaryLength {
+    OneLiner = 0,
+    Paragraph = 1,
+    BulletList = 2,
+}
+
+impl From<CSummaryLength> for SummaryLength {
+    fn from(length: CSummaryLength) -> Self {
+        match length {
+            CSummaryLength::OneLiner => SummaryLength::OneLiner,
+            CSummaryLength::Paragraph => SummaryLength::Paragraph,
+            CSummaryLength::BulletList => SummaryLength::BulletList,
+        }
+    }
+}
+
+/// Keyword extraction algorithm
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CKeywordAlgorithm {
+    /// Single tokens ranked by frequency (BM25 once a corpus is loaded)
+    Frequency = 0,
+    /// Multi-word keyphrases ranked by RAKE
+    Rake = 1,
+}
+
+/// Period length for trend analysis
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CTrendGranularity {
+    Week = 0,
+    Month = 1,
+}
+
+impl From<CTrendGranularity> for Granularity {
+    fn from(granularity: CTrendGranularity) -> Self {
+        match granularity {
+            CTrendGranularity::Week => Granularity::Week,
+            CTrendGranularity::Month => Granularity::Month,
+        }
+    }
+}
+
+/// Content type enum matching C++ side
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CContentType {
     Article = 0,
     Video = 1,
@@ -67,18 +212,289 @@ pub enum CContentType {
     Other = 7,
 }
 
+/// Difficulty band of a page's reading ease
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CReadingDifficulty {
+    Easy = 0,
+    Moderate = 1,
+    Difficult = 2,
+    VeryDifficult = 3,
+}
+
+impl From<Difficulty> for CReadingDifficulty {
+    fn from(difficulty: Difficulty) -> Self {
+        match difficulty {
+            Difficulty::Easy => CReadingDifficulty::Easy,
+            Difficulty::Moderate => CReadingDifficulty::Moderate,
+            Difficulty::Difficult => CReadingDifficulty::Difficult,
+            Difficulty::VeryDifficult => CReadingDifficulty::VeryDifficult,
+        }
+    }
+}
+
+/// Login, cookie or paywall shown instead of a page's content
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CAccessWall {
+    None = 0,
+    Login = 1,
+    CookieWall = 2,
+    Paywall = 3,
+}
+
+impl From<AccessWall> for CAccessWall {
+    fn from(wall: AccessWall) -> Self {
+        match wall {
+            AccessWall::None => CAccessWall::None,
+            AccessWall::Login => CAccessWall::Login,
+            AccessWall::CookieWall => CAccessWall::CookieWall,
+            AccessWall::Paywall => CAccessWall::Paywall,
+        }
+    }
+}
+
+/// Signal that decided a classification, exposed for debugging
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CClassificationSource {
+    /// Title and text heuristics
+    Heuristic = 0,
+    /// Domain rule for the page URL
+    Domain = 1,
+    /// Local language model
+    LocalModel = 2,
+    /// User-defined category
+    UserCategory = 3,
+    /// Label description compared by embedding
+    ZeroShot = 4,
+}
+
+/// Callback receiving summary text chunks as they are generated
+pub type CSummaryChunkCallback = extern "C" fn(chunk: *const c_char, user_data: *mut c_void);
+
+/// Summary callback of one call together with the caller's context pointer
+#[derive(Clone, Copy)]
+struct SummaryCallback {
+    callback: CSummaryChunkCallback,
+    user_data: *mut c_void,
+}
+
+impl SummaryCallback {
+    fn emit(&self, chunk: &str) {
+        if let Ok(chunk) = CString::new(chunk) {
+            (self.callback)(chunk.as_ptr(), self.user_data);
+        }
+    }
+}
+
+/// Callback reporting the progress of a long operation
+///
+/// `stage` names the current step ("analyze", "embed", "group", "link" or
+/// "compare") and `percent` runs from 0 to 100 within it. Set `*cancel` to
+/// a non-zero value to stop the operation.
+pub type CProgressCallback =
+    extern "C" fn(stage: *const c_char, percent: c_float, cancel: *mut c_int, user_data: *mut c_void);
+
+/// Registered progress callback together with the caller's context pointer
+#[derive(Clone, Copy)]
+struct ProgressCallback {
+    callback: CProgressCallback,
+    user_data: *mut c_void,
+}
+
+// The caller owns `user_data` and guarantees it may be used from any thread,
+// since parallel stages report from worker threads
+unsafe impl Send for ProgressCallback {}
+unsafe impl Sync for ProgressCallback {}
+
+impl ProgressCallback {
+    /// Report progress, returning false if the caller asked to cancel
+    fn report(&self, stage: &str, percent: f32) -> bool {
+        let mut cancel: c_int = 0;
+        if let Ok(stage) = CString::new(stage) {
+            (self.callback)(stage.as_ptr(), percent, &mut cancel, self.user_data);
+        }
+        cancel == 0
+    }
+}
+
+/// Status of a background analysis job
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CJobStatus {
+    NotFound = 0,
+    Pending = 1,
+    Running = 2,
+    Completed = 3,
+    Failed = 4,
+    Cancelled = 5,
+}
+
+impl From<JobStatus> for CJobStatus {
+    fn from(status: JobStatus) -> Self {
+        match status {
+            JobStatus::Pending => CJobStatus::Pending,
+            JobStatus::Running => CJobStatus::Running,
+            JobStatus::Completed => CJobStatus::Completed,
+            JobStatus::Failed => CJobStatus::Failed,
+            JobStatus::Cancelled => CJobStatus::Cancelled,
+        }
+    }
+}
+
+/// Callback invoked on a worker thread when a job finishes or is cancelled
+pub type CJobCallback = extern "C" fn(job_id: u64, status: CJobStatus, user_data: *mut c_void);
+
+/// Caller context pointer passed back to job callbacks
+#[derive(Clone, Copy)]
+struct UserData(*mut c_void);
+
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+impl UserData {
+    fn get(self) -> *mut c_void {
+        self.0
+    }
+}
+
+/// Maximum number of background job workers
+const MAX_JOB_WORKERS: usize = 4;
+
+/// Analysis result stored in the result cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+enum CachedAnalysis {
+    Summary(PageSummary),
+    Keywords(Vec<String>),
+    Classification(Classification),
+}
+
+/// Content type of a page and the signal that decided it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Classification {
+    content_type: CContentType,
+    source: CClassificationSource,
+}
+
+/// Processor behind a `CAIProcessor` pointer
+///
+/// All FFI functions taking a processor may be called concurrently from any
+/// thread. Analyses share a read lock on the state and run in parallel;
+/// changing settings, models or learned data takes the write lock and waits
+/// for running analyses to finish. Callbacks are invoked while the lock is
+/// held and must not call back into the processor.
+struct ProcessorHandle {
+    /// Background analysis jobs; declared first so workers are joined
+    /// before the state is dropped
+    jobs: JobQueue<(PageSummary, Vec<String>)>,
+    /// Workers for batch analysis, separate from the host's rayon pool
+    pool: rayon::ThreadPool,
+    state: Arc<RwLock<AIProcessorState>>,
+}
+
+impl ProcessorHandle {
+    fn read(&self) -> RwLockReadGuard<'_, AIProcessorState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, AIProcessorState> {
+        self.state.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Borrow the handle behind a non-null processor pointer
+///
+/// # Safety
+/// `processor` must come from `ai_processor_create` and not be destroyed
+unsafe fn processor_handle<'a>(processor: *mut CAIProcessor) -> &'a ProcessorHandle {
+    &*(processor as *const ProcessorHandle)
+}
+
+// The handle is shared between host threads and workers
+const _: () = {
+    fn assert_send_sync<T: Send + Sync>() {}
+    let _ = assert_send_sync::<ProcessorHandle>;
+};
+
 /// Internal AI processor state
 struct AIProcessorState {
+    cache: ResultCache<CachedAnalysis>,
     mode: CProcessingMode,
+    local_llm: Option<LocalLlm>,
+    embedder: Box<dyn EmbeddingModel>,
+    corpus: CorpusStats,
+    keyword_algorithm: CKeywordAlgorithm,
+    progress_callback: Option<ProgressCallback>,
+    entity_linker: EntityLinker,
+    categories: CategoryClassifier,
+    domains: DomainCategories,
+    labels: ZeroShotClassifier,
+    pipeline: PipelineConfig,
+}
+
+impl AIProcessorState {
+    /// Settings that influence analysis results, part of every cache key
+    fn cache_fingerprint(&self) -> String {
+        format!(
+            "{}|{:?}|{}|{}|{:?}",
+            self.active_llm().is_some(),
+            self.keyword_algorithm,
+            self.corpus.document_count,
+            self.domains.revision(),
+            self.pipeline
+        )
+    }
+    
+    /// Report progress to the registered callback, false to cancel
+    fn report_progress(&self, stage: &str, percent: f32) -> bool {
+        self.progress_callback.is_none_or(|callback| callback.report(stage, percent))
+    }
+    
+    /// Local model to use for the current mode, if one is loaded
+    fn active_llm(&self) -> Option<&LocalLlm> {
+        match self.mode {
+            CProcessingMode::Basic => None,
+            CProcessingMode::Enhanced | CProcessingMode::Auto | CProcessingMode::Abstractive => self.local_llm.as_ref(),
+        }
+    }
 }
 
 /// Create AI processor instance
 #[no_mangle]
 pub extern "C" fn ai_processor_create() -> *mut CAIProcessor {
-    let state = Box::new(AIProcessorState {
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get().min(MAX_JOB_WORKERS))
+        .unwrap_or(1);
+    let pool = match rayon::ThreadPoolBuilder::new()
+        .num_threads(workers)
+        .thread_name(|i| format!("ai-processor-{}", i))
+        .build()
+    {
+        Ok(pool) => pool,
+        Err(e) => return fail(CErrorCode::ProcessingFailed, format!("Failed to start workers: {}", e), ptr::null_mut()),
+    };
+    let state = AIProcessorState {
+        cache: ResultCache::new(result_cache::DEFAULT_CACHE_CAPACITY),
         mode: CProcessingMode::Auto,
+        local_llm: None,
+        embedder: Box::new(HashingEmbedder::default()),
+        corpus: CorpusStats::new(),
+        keyword_algorithm: CKeywordAlgorithm::Frequency,
+        progress_callback: None,
+        entity_linker: EntityLinker::offline(),
+        categories: CategoryClassifier::new(),
+        domains: DomainCategories::new(),
+        labels: ZeroShotClassifier::new(),
+        pipeline: PipelineConfig::default(),
+    };
+    let handle = Box::new(ProcessorHandle {
+        jobs: JobQueue::new(workers),
+        pool,
+        state: Arc::new(RwLock::new(state)),
     });
-    Box::into_raw(state) as *mut CAIProcessor
+    Box::into_raw(handle) as *mut CAIProcessor
 }
 
 /// Destroy AI processor instance
@@ -86,7 +502,7 @@ pub extern "C" fn ai_processor_create() -> *mut CAIProcessor {
 pub extern "C" fn ai_processor_destroy(processor: *mut CAIProcessor) {
     if !processor.is_null() {
         unsafe {
-            let _ = Box::from_raw(processor as *mut AIProcessorState);
+            let _ = Box::from_raw(processor as *mut ProcessorHandle);
         }
     }
 }
@@ -97,6 +513,47 @@ pub extern "C" fn ai_processor_destroy(processor: *mut CAIProcessor) {
 pub extern "C" fn ai_processor_generate_summary(
     processor: *mut CAIProcessor,
     content_json: *const c_char,
+) -> CContentSummary {
+    generate_summary_c(processor, content_json, SummaryLength::default(), None)
+}
+
+/// Generate a content summary, reporting its text as it is generated
+///
+/// Chunks of the summary are passed to `callback` on the calling thread
+/// before the complete result is returned, together with `user_data`,
+/// which is passed through unchanged. Each call has its own callback, so
+/// concurrent summaries do not share chunks.
+#[no_mangle]
+pub extern "C" fn ai_processor_generate_summary_streaming(
+    processor: *mut CAIProcessor,
+    content_json: *const c_char,
+    callback: Option<CSummaryChunkCallback>,
+    user_data: *mut c_void,
+) -> CContentSummary {
+    let callback = callback.map(|callback| SummaryCallback { callback, user_data });
+    generate_summary_c(processor, content_json, SummaryLength::default(), callback)
+}
+
+/// Generate a content summary of the given length
+///
+/// In `CProcessingMode::Abstractive` the local model rewrites the page as a
+/// one-liner, a paragraph or a bullet list; without a model, the extractive
+/// summary is cut to one sentence or turned into key point bullets. Other
+/// modes ignore the length and behave like `ai_processor_generate_summary`.
+#[no_mangle]
+pub extern "C" fn ai_processor_generate_summary_with_length(
+    processor: *mut CAIProcessor,
+    content_json: *const c_char,
+    length: CSummaryLength,
+) -> CContentSummary {
+    generate_summary_c(processor, content_json, length.into(), None)
+}
+
+fn generate_summary_c(
+    processor: *mut CAIProcessor,
+    content_json: *const c_char,
+    length: SummaryLength,
+    callback: Option<SummaryCallback>,
 ) -> CContentSummary {
     let empty_summary = CContentSummary {
         summary_text: ptr::null_mut(),
@@ -106,72 +563,252 @@ pub extern "C" fn ai_processor_generate_summary(
         language: ptr::null_mut(),
         reading_time_minutes: 0,
         confidence_score: 0.0,
+        readability_score: 0.0,
+        reading_difficulty: CReadingDifficulty::VeryDifficult as c_int,
+        access_wall: CAccessWall::None as c_int,
+        quality_score: 0.0,
+        clickbait_score: 0.0,
+        image_url: ptr::null_mut(),
+        image_count: 0,
+        video_count: 0,
     };
     
     if processor.is_null() || content_json.is_null() {
-        return empty_summary;
+        return null_argument("processor and content_json", empty_summary);
     }
     
     unsafe {
         let content_str = match CStr::from_ptr(content_json).to_str() {
             Ok(s) => s,
-            Err(_) => return empty_summary,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("content_json: {}", e), empty_summary),
         };
         
         // Parse the JSON content
         let content: PageContentInput = match serde_json::from_str(content_str) {
             Ok(c) => c,
-            Err(_) => return empty_summary,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("content_json: {}", e), empty_summary),
         };
         
-        // Generate summary using extractive summarization
-        let summary_text = generate_extractive_summary(&content.text, 3);
-        let key_points = extract_key_points(&content.text, 5);
-        let content_type = classify_content_type(&content);
-        let language = detect_language(&content.text);
-        let reading_time = estimate_reading_time(&content.text);
+        let state = processor_handle(processor).read();
+        let mut on_chunk = |chunk: &str| {
+            if let Some(callback) = &callback {
+                callback.emit(chunk);
+            }
+        };
+        
+        summarize_page(&state, &content, length, &mut on_chunk).into_c()
+    }
+}
+
+/// Summarize, classify and extract keywords for many pages in one call
+///
+/// `contents_json` is a JSON array of pages. Pages are processed in parallel
+/// and results are returned in input order. Summaries of a batch are not
+/// streamed. Free the result with `ai_processor_free_batch_results`.
+#[no_mangle]
+pub extern "C" fn ai_processor_process_batch(
+    processor: *mut CAIProcessor,
+    contents_json: *const c_char,
+    results_out: *mut *mut CPageAnalysis,
+    count_out: *mut usize,
+) -> c_int {
+    if processor.is_null() || contents_json.is_null() || results_out.is_null() || count_out.is_null() {
+        return null_argument("processor, contents_json, results_out and count_out", -1);
+    }
+    
+    unsafe {
+        let contents_str = match CStr::from_ptr(contents_json).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("contents_json: {}", e), -1),
+        };
         
-        // Calculate confidence score
-        let mut confidence = 0.5f32;
-        if !summary_text.is_empty() { confidence += 0.15; }
-        if !key_points.is_empty() { confidence += 0.1; }
-        if !content.title.is_empty() { confidence += 0.1; }
-        if content.description.is_some() { confidence += 0.1; }
-        if content.text.len() > 500 { confidence += 0.05; }
-        confidence = confidence.min(0.95);
+        let contents: Vec<PageContentInput> = match serde_json::from_str(contents_str) {
+            Ok(c) => c,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("contents_json: {}", e), -1),
+        };
         
-        // Convert to C strings
-        let summary_c = CString::new(summary_text).unwrap_or_default();
-        let language_c = CString::new(language).unwrap_or_default();
+        let handle = processor_handle(processor);
+        let state = handle.read();
+        let state = &*state;
+        let report = |stage: &str, percent: f32| state.report_progress(stage, percent);
+        let progress = Progress::new(&report);
+        let analyze = progress.stage("analyze", contents.len());
+        let analyses: Option<Vec<(PageSummary, Vec<String>)>> = handle.pool.install(|| {
+            contents
+                .par_iter()
+                .map(|content| {
+                    if progress.is_cancelled() {
+                        return None;
+                    }
+                    let summary = summarize_page(state, content, SummaryLength::default(), &mut |_| {});
+                    let keywords = extract_page_keywords(state, content);
+                    analyze.step();
+                    Some((summary, keywords))
+                })
+                .collect()
+        });
+        let analyses = match analyses {
+            Some(analyses) if !progress.is_cancelled() => analyses,
+            _ => return fail(CErrorCode::Cancelled, "Batch analysis was cancelled", -1),
+        };
+        
+        if analyses.is_empty() {
+            *results_out = ptr::null_mut();
+            *count_out = 0;
+            return 0;
+        }
         
-        // Convert key points to C string array
-        let mut key_points_ptrs: Vec<*mut c_char> = key_points
+        let c_results: Vec<CPageAnalysis> = analyses
             .into_iter()
-            .filter_map(|kp| CString::new(kp).ok())
-            .map(|cs| cs.into_raw())
+            .map(|(summary, keywords)| {
+                let (keywords, keywords_count) = strings_to_c_array(keywords);
+                CPageAnalysis {
+                    summary: summary.into_c(),
+                    keywords,
+                    keywords_count,
+                }
+            })
             .collect();
         
-        let key_points_count = key_points_ptrs.len();
-        let key_points_ptr = if key_points_count > 0 {
-            let ptr = key_points_ptrs.as_mut_ptr();
-            std::mem::forget(key_points_ptrs);
-            ptr
-        } else {
-            ptr::null_mut()
+        let count = c_results.len();
+        *results_out = Box::into_raw(c_results.into_boxed_slice()) as *mut CPageAnalysis;
+        *count_out = count;
+        
+        0
+    }
+}
+
+/// Submit a page for background analysis
+///
+/// Returns the job id, or 0 if the input is invalid. Poll with
+/// `ai_processor_get_job_status` or register `ai_processor_set_job_callback`,
+/// then collect the result with `ai_processor_take_job_result`. Changing
+/// settings or models waits for running jobs; queued jobs use the new ones.
+#[no_mangle]
+pub extern "C" fn ai_processor_submit_job(
+    processor: *mut CAIProcessor,
+    content_json: *const c_char,
+) -> u64 {
+    if processor.is_null() || content_json.is_null() {
+        return null_argument("processor and content_json", 0);
+    }
+    
+    unsafe {
+        let content_str = match CStr::from_ptr(content_json).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("content_json: {}", e), 0),
         };
         
-        CContentSummary {
-            summary_text: summary_c.into_raw(),
-            key_points: key_points_ptr,
-            key_points_count,
-            content_type: content_type as c_int,
-            language: language_c.into_raw(),
-            reading_time_minutes: reading_time,
-            confidence_score: confidence,
+        let content: PageContentInput = match serde_json::from_str(content_str) {
+            Ok(c) => c,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("content_json: {}", e), 0),
+        };
+        
+        let handle = processor_handle(processor);
+        let shared = Arc::clone(&handle.state);
+        handle.jobs.submit(Box::new(move || {
+            let state = shared.read().unwrap_or_else(|e| e.into_inner());
+            let summary = summarize_page(&state, &content, SummaryLength::default(), &mut |_| {});
+            Ok((summary, extract_page_keywords(&state, &content)))
+        }))
+    }
+}
+
+/// Get the status of a background job
+#[no_mangle]
+pub extern "C" fn ai_processor_get_job_status(processor: *mut CAIProcessor, job_id: u64) -> CJobStatus {
+    if processor.is_null() {
+        return null_argument("processor", CJobStatus::NotFound);
+    }
+    
+    unsafe {
+        let handle = processor_handle(processor);
+        handle.jobs.status(job_id).map(CJobStatus::from).unwrap_or(CJobStatus::NotFound)
+    }
+}
+
+/// Take the result of a finished job
+///
+/// Returns 0 and fills `result_out` if the job completed. Returns -1 if the
+/// job is unknown or still pending/running. Failed and cancelled jobs are
+/// removed and return -1. Free the result with `ai_processor_free_page_analysis`.
+#[no_mangle]
+pub extern "C" fn ai_processor_take_job_result(
+    processor: *mut CAIProcessor,
+    job_id: u64,
+    result_out: *mut CPageAnalysis,
+) -> c_int {
+    if processor.is_null() || result_out.is_null() {
+        return null_argument("processor and result_out", -1);
+    }
+    
+    unsafe {
+        let handle = processor_handle(processor);
+        match handle.jobs.take_result(job_id as JobId) {
+            Some(JobOutcome::Completed((summary, keywords))) => {
+                let (keywords, keywords_count) = strings_to_c_array(keywords);
+                *result_out = CPageAnalysis {
+                    summary: summary.into_c(),
+                    keywords,
+                    keywords_count,
+                };
+                0
+            }
+            Some(JobOutcome::Failed(reason)) => {
+                tracing::warn!("Analysis job {} failed: {}", job_id, reason);
+                fail(CErrorCode::ProcessingFailed, format!("Analysis job {} failed: {}", job_id, reason), -1)
+            }
+            Some(JobOutcome::Cancelled) => fail(CErrorCode::NotFound, format!("Analysis job {} was cancelled", job_id), -1),
+            None => fail(CErrorCode::NotFound, format!("Analysis job {} is unknown or not finished", job_id), -1),
+        }
+    }
+}
+
+/// Cancel a job that has not started yet
+///
+/// Returns 0 if the job was cancelled, -1 if it is unknown, running or finished.
+#[no_mangle]
+pub extern "C" fn ai_processor_cancel_job(processor: *mut CAIProcessor, job_id: u64) -> c_int {
+    if processor.is_null() {
+        return null_argument("processor", -1);
+    }
+    
+    unsafe {
+        let handle = processor_handle(processor);
+        if handle.jobs.cancel(job_id) {
+            0
+        } else {
+            fail(CErrorCode::NotFound, format!("Analysis job {} is unknown or already started", job_id), -1)
         }
     }
 }
 
+/// Register a callback invoked when a job finishes or is cancelled
+///
+/// The callback runs on a worker thread. Pass a null callback to unregister.
+#[no_mangle]
+pub extern "C" fn ai_processor_set_job_callback(
+    processor: *mut CAIProcessor,
+    callback: Option<CJobCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    if processor.is_null() {
+        return null_argument("processor", -1);
+    }
+    
+    unsafe {
+        let handle = processor_handle(processor);
+        let user_data = UserData(user_data);
+        handle.jobs.set_completion_callback(callback.map(|callback| {
+            std::sync::Arc::new(move |job_id: JobId, status: JobStatus| {
+                callback(job_id, status.into(), user_data.get());
+            }) as jobs::CompletionCallback
+        }));
+    }
+    
+    0 // Success
+}
+
 /// Extract keywords from content
 #[no_mangle]
 pub extern "C" fn ai_processor_extract_keywords(
@@ -181,42 +818,23 @@ pub extern "C" fn ai_processor_extract_keywords(
     count_out: *mut usize,
 ) -> c_int {
     if processor.is_null() || content_json.is_null() || keywords_out.is_null() || count_out.is_null() {
-        return -1;
+        return null_argument("processor, content_json, keywords_out and count_out", -1);
     }
     
     unsafe {
         let content_str = match CStr::from_ptr(content_json).to_str() {
             Ok(s) => s,
-            Err(_) => return -1,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("content_json: {}", e), -1),
         };
         
         // Parse the JSON content
         let content: PageContentInput = match serde_json::from_str(content_str) {
             Ok(c) => c,
-            Err(_) => return -1,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("content_json: {}", e), -1),
         };
         
-        // Extract keywords
-        let mut keywords = content.keywords.clone();
-        let extracted = extract_keywords_from_text(&content.text, 15);
-        
-        // Merge keywords, avoiding duplicates
-        for kw in extracted {
-            if !keywords.contains(&kw) {
-                keywords.push(kw);
-            }
-        }
-        
-        // Also extract from title
-        let title_keywords = extract_keywords_from_text(&content.title, 5);
-        for kw in title_keywords {
-            if !keywords.contains(&kw) {
-                keywords.insert(0, kw);
-            }
-        }
-        
-        // Limit to 20 keywords
-        keywords.truncate(20);
+        let state = processor_handle(processor).read();
+        let keywords = extract_page_keywords(&state, &content);
         
         // Convert to C strings
         let c_keywords: Vec<*mut c_char> = keywords
@@ -251,106 +869,1165 @@ pub extern "C" fn ai_processor_classify_content(
         secondary_categories: ptr::null_mut(),
         secondary_count: 0,
         confidence: 0.0,
+        source: CClassificationSource::Heuristic as c_int,
     };
     
     if processor.is_null() || content_json.is_null() {
-        return empty_category;
+        return null_argument("processor and content_json", empty_category);
     }
     
     unsafe {
         let content_str = match CStr::from_ptr(content_json).to_str() {
             Ok(s) => s,
-            Err(_) => return empty_category,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("content_json: {}", e), empty_category),
         };
         
         let content: PageContentInput = match serde_json::from_str(content_str) {
             Ok(c) => c,
-            Err(_) => return empty_category,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("content_json: {}", e), empty_category),
         };
         
-        let content_type = classify_content_type(&content);
-        let (primary, secondary) = get_category_info(content_type);
-        
-        let primary_c = CString::new(primary).unwrap_or_default();
+        let state = processor_handle(processor).read();
+        if !state.pipeline.classification.enabled {
+            let (primary, secondary) = get_category_info(CContentType::Other);
+            return category_info_to_c(primary, secondary, 0.0, CClassificationSource::Heuristic);
+        }
         
-        let mut secondary_ptrs: Vec<*mut c_char> = secondary
-            .into_iter()
-            .filter_map(|s| CString::new(s).ok())
-            .map(|cs| cs.into_raw())
-            .collect();
+        // User-defined categories and labels take precedence over the built-in types
+        let use_embeddings = state.pipeline.classification.use_embeddings;
+        if use_embeddings && (!state.categories.is_empty() || !state.labels.is_empty()) {
+            let embedding = embed_page(&content, state.embedder.as_ref()).unwrap_or_default();
+            let learned = state.categories.classify(state.embedder.name(), &embedding);
+            let (matches, source) = if learned.is_empty() {
+                (state.labels.classify(state.embedder.as_ref(), &embedding), CClassificationSource::ZeroShot)
+            } else {
+                (learned, CClassificationSource::UserCategory)
+            };
+            let mut matches = matches.into_iter();
+            if let Some((primary, confidence)) = matches.next() {
+                let secondary = matches.take(3).map(|(name, _)| name).collect();
+                return category_info_to_c(primary, secondary, confidence, source);
+            }
+        }
         
-        let secondary_count = secondary_ptrs.len();
-        let secondary_ptr = if secondary_count > 0 {
-            let ptr = secondary_ptrs.as_mut_ptr();
-            std::mem::forget(secondary_ptrs);
-            ptr
-        } else {
-            ptr::null_mut()
+        let key = cache_key(AnalysisKind::Classification, &state.cache_fingerprint(), &content);
+        let classification = match state.cache.get(&key) {
+            Some(CachedAnalysis::Classification(classification)) => classification,
+            _ => {
+                let classification = classify_page(&state, &content);
+                state.cache.insert(key, CachedAnalysis::Classification(classification));
+                classification
+            }
         };
-        
-        CCategoryInfo {
-            primary_category: primary_c.into_raw(),
-            secondary_categories: secondary_ptr,
-            secondary_count,
-            confidence: 0.75,
-        }
+        let (primary, secondary) = get_category_info(classification.content_type);
+        let confidence = if classification.source == CClassificationSource::Domain { 0.9 } else { 0.75 };
+        category_info_to_c(primary, secondary, confidence, classification.source)
     }
 }
 
+/// Build a category info, freed with `ai_processor_free_category`
+fn category_info_to_c(
+    primary: String,
+    secondary: Vec<String>,
+    confidence: f32,
+    source: CClassificationSource,
+) -> CCategoryInfo {
+    let primary_c = CString::new(primary).unwrap_or_default();
+    
+    let mut secondary_ptrs: Vec<*mut c_char> = secondary
+        .into_iter()
+        .filter_map(|s| CString::new(s).ok())
+        .map(|cs| cs.into_raw())
+        .collect();
+    
+    let secondary_count = secondary_ptrs.len();
+    let secondary_ptr = if secondary_count > 0 {
+        let ptr = secondary_ptrs.as_mut_ptr();
+        std::mem::forget(secondary_ptrs);
+        ptr
+    } else {
+        ptr::null_mut()
+    };
+    
+    CCategoryInfo {
+        primary_category: primary_c.into_raw(),
+        secondary_categories: secondary_ptr,
+        secondary_count,
+        confidence,
+        source: source as c_int,
+    }
+}
 
-/// Calculate content similarity between two content JSONs
+/// Define a custom category, learning from example pages
+///
+/// `examples_json` is a JSON array of page contents and may be empty.
+/// Defining an existing category adds the examples to it. Once categories
+/// are defined, `ai_processor_classify_content` reports the best matching
+/// one and falls back to the built-in types when none matches.
 #[no_mangle]
-pub extern "C" fn ai_processor_calculate_similarity(
+pub extern "C" fn ai_processor_define_category(
     processor: *mut CAIProcessor,
-    content_a_json: *const c_char,
-    content_b_json: *const c_char,
-) -> c_double {
-    if processor.is_null() || content_a_json.is_null() || content_b_json.is_null() {
-        return 0.0;
+    name: *const c_char,
+    examples_json: *const c_char,
+) -> c_int {
+    if processor.is_null() || name.is_null() || examples_json.is_null() {
+        return null_argument("processor, name and examples_json", -1);
     }
     
     unsafe {
-        let content_a_str = match CStr::from_ptr(content_a_json).to_str() {
-            Ok(s) => s,
-            Err(_) => return 0.0,
+        let name = match CStr::from_ptr(name).to_str() {
+            Ok(s) if !s.trim().is_empty() => s.trim(),
+            Ok(_) => return fail(CErrorCode::InvalidArgument, "name must not be empty", -1),
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("name: {}", e), -1),
+        };
+        let examples: Vec<PageContentInput> = match json_argument(examples_json, "examples_json") {
+            Some(examples) => examples,
+            None => return -1,
+        };
+        
+        let mut guard = processor_handle(processor).write();
+        let state = &mut *guard;
+        let embeddings: Vec<Vec<f32>> = examples
+            .iter()
+            .map(|content| embed_page(content, state.embedder.as_ref()).unwrap_or_default())
+            .filter(|embedding| !embedding.is_empty())
+            .collect();
+        state.categories.define(name, state.embedder.name(), &embeddings);
+    }
+    
+    0
+}
+
+/// Remove a custom category
+///
+/// Returns -1 if the category does not exist.
+#[no_mangle]
+pub extern "C" fn ai_processor_remove_category(
+    processor: *mut CAIProcessor,
+    name: *const c_char,
+) -> c_int {
+    if processor.is_null() || name.is_null() {
+        return null_argument("processor and name", -1);
+    }
+    
+    unsafe {
+        let name = match CStr::from_ptr(name).to_str() {
+            Ok(s) => s.trim(),
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("name: {}", e), -1),
+        };
+        
+        let mut state = processor_handle(processor).write();
+        if state.categories.remove(name) {
+            0
+        } else {
+            fail(CErrorCode::NotFound, format!("Unknown category {}", name), -1)
+        }
+    }
+}
+
+/// Learn from the user accepting (non-zero) or rejecting (0) a category for a page
+///
+/// Accepting a category that does not exist yet defines it.
+#[no_mangle]
+pub extern "C" fn ai_processor_record_category_feedback(
+    processor: *mut CAIProcessor,
+    content_json: *const c_char,
+    category: *const c_char,
+    accepted: c_int,
+) -> c_int {
+    if processor.is_null() || content_json.is_null() || category.is_null() {
+        return null_argument("processor, content_json and category", -1);
+    }
+    
+    unsafe {
+        let category = match CStr::from_ptr(category).to_str() {
+            Ok(s) if !s.trim().is_empty() => s.trim(),
+            Ok(_) => return fail(CErrorCode::InvalidArgument, "category must not be empty", -1),
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("category: {}", e), -1),
+        };
+        let content: PageContentInput = match json_argument(content_json, "content_json") {
+            Some(content) => content,
+            None => return -1,
+        };
+        
+        let mut guard = processor_handle(processor).write();
+        let state = &mut *guard;
+        let embedding = embed_page(&content, state.embedder.as_ref()).unwrap_or_default();
+        if embedding.is_empty() {
+            return fail(CErrorCode::ProcessingFailed, "Failed to embed the page", -1);
+        }
+        state.categories.record_feedback(category, state.embedder.name(), &embedding, accepted != 0);
+    }
+    
+    0
+}
+
+/// Save the custom category model to a JSON file
+#[no_mangle]
+pub extern "C" fn ai_processor_save_categories(
+    processor: *mut CAIProcessor,
+    path: *const c_char,
+) -> c_int {
+    if processor.is_null() || path.is_null() {
+        return null_argument("processor and path", -1);
+    }
+
+    unsafe {
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("path: {}", e), -1),
+        };
+
+        let state = processor_handle(processor).read();
+        match state.categories.save(std::path::Path::new(path)) {
+            Ok(()) => 0,
+            Err(e) => {
+                tracing::warn!("Failed to save categories: {}", e);
+                processing_failed("Failed to save categories", &e, -1)
+            }
+        }
+    }
+}
+
+/// Load a custom category model from a JSON file, replacing the current one
+#[no_mangle]
+pub extern "C" fn ai_processor_load_categories(
+    processor: *mut CAIProcessor,
+    path: *const c_char,
+) -> c_int {
+    if processor.is_null() || path.is_null() {
+        return null_argument("processor and path", -1);
+    }
+
+    unsafe {
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("path: {}", e), -1),
+        };
+
+        match CategoryClassifier::load(std::path::Path::new(path)) {
+            Ok(categories) => {
+                let mut state = processor_handle(processor).write();
+                state.categories = categories;
+                0
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load categories: {}", e);
+                processing_failed("Failed to load categories", &e, -1)
+            }
+        }
+    }
+}
+
+/// Define or replace a zero-shot label described in plain words
+///
+/// Pages whose embedding is close to the description are classified under
+/// the label without any examples. Labels trained with examples through
+/// `ai_processor_define_category` are matched first.
+#[no_mangle]
+pub extern "C" fn ai_processor_define_label(
+    processor: *mut CAIProcessor,
+    name: *const c_char,
+    description: *const c_char,
+) -> c_int {
+    if processor.is_null() || name.is_null() || description.is_null() {
+        return null_argument("processor, name and description", -1);
+    }
+    
+    unsafe {
+        let name = match CStr::from_ptr(name).to_str() {
+            Ok(s) if !s.trim().is_empty() => s.trim(),
+            Ok(_) => return fail(CErrorCode::InvalidArgument, "name must not be empty", -1),
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("name: {}", e), -1),
+        };
+        let description = match CStr::from_ptr(description).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("description: {}", e), -1),
+        };
+        
+        let mut guard = processor_handle(processor).write();
+        let state = &mut *guard;
+        state.labels.define(name, description, state.embedder.as_ref());
+    }
+    
+    0
+}
+
+/// Remove a zero-shot label
+///
+/// Returns -1 if the label does not exist.
+#[no_mangle]
+pub extern "C" fn ai_processor_remove_label(
+    processor: *mut CAIProcessor,
+    name: *const c_char,
+) -> c_int {
+    if processor.is_null() || name.is_null() {
+        return null_argument("processor and name", -1);
+    }
+    
+    unsafe {
+        let name = match CStr::from_ptr(name).to_str() {
+            Ok(s) => s.trim(),
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("name: {}", e), -1),
+        };
+        
+        let mut state = processor_handle(processor).write();
+        if state.labels.remove(name) {
+            0
+        } else {
+            fail(CErrorCode::NotFound, format!("Unknown label {}", name), -1)
+        }
+    }
+}
+
+/// Save zero-shot labels to a JSON file
+#[no_mangle]
+pub extern "C" fn ai_processor_save_labels(
+    processor: *mut CAIProcessor,
+    path: *const c_char,
+) -> c_int {
+    if processor.is_null() || path.is_null() {
+        return null_argument("processor and path", -1);
+    }
+
+    unsafe {
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("path: {}", e), -1),
+        };
+
+        let state = processor_handle(processor).read();
+        match state.labels.save(std::path::Path::new(path)) {
+            Ok(()) => 0,
+            Err(e) => {
+                tracing::warn!("Failed to save labels: {}", e);
+                processing_failed("Failed to save labels", &e, -1)
+            }
+        }
+    }
+}
+
+/// Load zero-shot labels from a JSON file, replacing the current ones
+#[no_mangle]
+pub extern "C" fn ai_processor_load_labels(
+    processor: *mut CAIProcessor,
+    path: *const c_char,
+) -> c_int {
+    if processor.is_null() || path.is_null() {
+        return null_argument("processor and path", -1);
+    }
+
+    unsafe {
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("path: {}", e), -1),
+        };
+
+        match ZeroShotClassifier::load(std::path::Path::new(path)) {
+            Ok(mut labels) => {
+                let mut state = processor_handle(processor).write();
+                labels.refresh(state.embedder.as_ref());
+                state.labels = labels;
+                0
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load labels: {}", e);
+                processing_failed("Failed to load labels", &e, -1)
+            }
+        }
+    }
+}
+
+/// Classify pages on `domain` as `content_type`, overriding bundled rules
+///
+/// A domain also covers its subdomains; a pattern such as "amazon.*" matches
+/// any top-level domain.
+#[no_mangle]
+pub extern "C" fn ai_processor_set_domain_category(
+    processor: *mut CAIProcessor,
+    domain: *const c_char,
+    content_type: CContentType,
+) -> c_int {
+    if processor.is_null() || domain.is_null() {
+        return null_argument("processor and domain", -1);
+    }
+    
+    unsafe {
+        let domain = match CStr::from_ptr(domain).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("domain: {}", e), -1),
+        };
+        
+        let mut state = processor_handle(processor).write();
+        if state.domains.set_rule(domain, content_type) {
+            0
+        } else {
+            fail(CErrorCode::InvalidArgument, format!("Invalid domain pattern {:?}", domain), -1)
+        }
+    }
+}
+
+/// Remove a user domain rule; returns -1 if there was none
+#[no_mangle]
+pub extern "C" fn ai_processor_remove_domain_category(
+    processor: *mut CAIProcessor,
+    domain: *const c_char,
+) -> c_int {
+    if processor.is_null() || domain.is_null() {
+        return null_argument("processor and domain", -1);
+    }
+    
+    unsafe {
+        let domain = match CStr::from_ptr(domain).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("domain: {}", e), -1),
+        };
+        
+        let mut state = processor_handle(processor).write();
+        if state.domains.remove_rule(domain) {
+            0
+        } else {
+            fail(CErrorCode::NotFound, format!("No rule for domain {}", domain), -1)
+        }
+    }
+}
+
+/// Save user domain rules to a JSON file
+#[no_mangle]
+pub extern "C" fn ai_processor_save_domain_rules(
+    processor: *mut CAIProcessor,
+    path: *const c_char,
+) -> c_int {
+    if processor.is_null() || path.is_null() {
+        return null_argument("processor and path", -1);
+    }
+
+    unsafe {
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("path: {}", e), -1),
+        };
+
+        let state = processor_handle(processor).read();
+        match state.domains.save(std::path::Path::new(path)) {
+            Ok(()) => 0,
+            Err(e) => {
+                tracing::warn!("Failed to save domain rules: {}", e);
+                processing_failed("Failed to save domain rules", &e, -1)
+            }
+        }
+    }
+}
+
+/// Load user domain rules from a JSON file, replacing the current ones
+#[no_mangle]
+pub extern "C" fn ai_processor_load_domain_rules(
+    processor: *mut CAIProcessor,
+    path: *const c_char,
+) -> c_int {
+    if processor.is_null() || path.is_null() {
+        return null_argument("processor and path", -1);
+    }
+
+    unsafe {
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("path: {}", e), -1),
+        };
+
+        let mut state = processor_handle(processor).write();
+        match state.domains.load(std::path::Path::new(path)) {
+            Ok(()) => 0,
+            Err(e) => {
+                tracing::warn!("Failed to load domain rules: {}", e);
+                processing_failed("Failed to load domain rules", &e, -1)
+            }
+        }
+    }
+}
+
+
+/// Calculate content similarity between two content JSONs
+#[no_mangle]
+pub extern "C" fn ai_processor_calculate_similarity(
+    processor: *mut CAIProcessor,
+    content_a_json: *const c_char,
+    content_b_json: *const c_char,
+) -> c_double {
+    if processor.is_null() || content_a_json.is_null() || content_b_json.is_null() {
+        return null_argument("processor, content_a_json and content_b_json", 0.0);
+    }
+    
+    unsafe {
+        let content_a_str = match CStr::from_ptr(content_a_json).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("content_a_json: {}", e), 0.0),
         };
         
         let content_b_str = match CStr::from_ptr(content_b_json).to_str() {
             Ok(s) => s,
-            Err(_) => return 0.0,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("content_b_json: {}", e), 0.0),
+        };
+        
+        let content_a: PageContentInput = match serde_json::from_str(content_a_str) {
+            Ok(c) => c,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("content_a_json: {}", e), 0.0),
+        };
+        
+        let content_b: PageContentInput = match serde_json::from_str(content_b_str) {
+            Ok(c) => c,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("content_b_json: {}", e), 0.0),
+        };
+        
+        let state = processor_handle(processor).read();
+        
+        // Calculate embedding similarity
+        embedding_similarity(state.embedder.as_ref(), &content_a, &content_b)
+    }
+}
+
+/// Set processing mode
+#[no_mangle]
+pub extern "C" fn ai_processor_set_mode(
+    processor: *mut CAIProcessor,
+    mode: CProcessingMode,
+) -> c_int {
+    if processor.is_null() {
+        return null_argument("processor", -1);
+    }
+    
+    unsafe {
+        let mut state = processor_handle(processor).write();
+        state.mode = mode;
+    }
+    
+    0 // Success
+}
+
+/// Register a callback reporting progress of long operations
+///
+/// Batch analysis, group suggestion and cross-recommendation generation
+/// report each stage to `callback`, possibly from worker threads. A callback
+/// that sets `*cancel` stops the operation, which then returns -1 with
+/// `CErrorCode::Cancelled`. Pass a null callback to unregister.
+#[no_mangle]
+pub extern "C" fn ai_processor_set_progress_callback(
+    processor: *mut CAIProcessor,
+    callback: Option<CProgressCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    if processor.is_null() {
+        return null_argument("processor", -1);
+    }
+    
+    unsafe {
+        let mut state = processor_handle(processor).write();
+        state.progress_callback = callback.map(|callback| ProgressCallback { callback, user_data });
+    }
+    
+    0 // Success
+}
+
+/// Set the keyword extraction algorithm
+#[no_mangle]
+pub extern "C" fn ai_processor_set_keyword_algorithm(
+    processor: *mut CAIProcessor,
+    algorithm: CKeywordAlgorithm,
+) -> c_int {
+    if processor.is_null() {
+        return null_argument("processor", -1);
+    }
+    
+    unsafe {
+        let mut state = processor_handle(processor).write();
+        state.keyword_algorithm = algorithm;
+    }
+    
+    0 // Success
+}
+
+/// Configure which analysis stages run and their parameters
+///
+/// `config_json` is an object with optional `summary`, `keywords`,
+/// `entities`, `sentiment` and `classification` sections, for example
+/// `{"entities": {"enabled": false}, "keywords": {"max_keywords": 8}}`.
+/// Omitted fields keep their defaults, not their current values. Returns -1
+/// and keeps the current configuration if the JSON is invalid.
+#[no_mangle]
+pub extern "C" fn ai_processor_set_pipeline(
+    processor: *mut CAIProcessor,
+    config_json: *const c_char,
+) -> c_int {
+    if processor.is_null() || config_json.is_null() {
+        return null_argument("processor and config_json", -1);
+    }
+    
+    unsafe {
+        let config: PipelineConfig = match json_argument(config_json, "config_json") {
+            Some(config) => config,
+            None => return -1,
+        };
+        
+        let mut state = processor_handle(processor).write();
+        state.pipeline = config;
+    }
+    
+    0
+}
+
+/// Get the pipeline configuration as JSON, freed with `ai_processor_free_string`
+#[no_mangle]
+pub extern "C" fn ai_processor_get_pipeline(processor: *mut CAIProcessor) -> *mut c_char {
+    if processor.is_null() {
+        return null_argument("processor", ptr::null_mut());
+    }
+    
+    unsafe {
+        let state = processor_handle(processor).read();
+        serde_json::to_string(&state.pipeline)
+            .ok()
+            .and_then(|json| CString::new(json).ok())
+            .map_or(ptr::null_mut(), CString::into_raw)
+    }
+}
+
+/// Load a local GGUF model used for on-device summarization and classification
+///
+/// Returns -1 if the model cannot be loaded or the library was built without
+/// the `local-llm` feature; the processor then keeps its previous model, if any.
+#[no_mangle]
+pub extern "C" fn ai_processor_load_local_model(
+    processor: *mut CAIProcessor,
+    model_path: *const c_char,
+    tokenizer_path: *const c_char,
+) -> c_int {
+    if processor.is_null() || model_path.is_null() || tokenizer_path.is_null() {
+        return null_argument("processor, model_path and tokenizer_path", -1);
+    }
+    
+    unsafe {
+        let model_path = match CStr::from_ptr(model_path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("model_path: {}", e), -1),
+        };
+        let tokenizer_path = match CStr::from_ptr(tokenizer_path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("tokenizer_path: {}", e), -1),
+        };
+        
+        match LocalLlm::load(LocalLlmConfig::new(model_path, tokenizer_path)) {
+            Ok(llm) => {
+                let mut state = processor_handle(processor).write();
+                state.local_llm = Some(llm);
+                0
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load local model: {}", e);
+                processing_failed("Failed to load local model", &e, -1)
+            }
+        }
+    }
+}
+
+/// Unload the local model and return to the built-in algorithms
+#[no_mangle]
+pub extern "C" fn ai_processor_unload_local_model(processor: *mut CAIProcessor) -> c_int {
+    if processor.is_null() {
+        return null_argument("processor", -1);
+    }
+    
+    unsafe {
+        let mut state = processor_handle(processor).write();
+        state.local_llm = None;
+    }
+    
+    0
+}
+
+/// Load an ONNX sentence-embedding model used for similarity and grouping
+///
+/// Returns -1 if the model cannot be loaded or the library was built without
+/// the `onnx-embeddings` feature; the current embedder stays in place.
+#[no_mangle]
+pub extern "C" fn ai_processor_load_embedding_model(
+    processor: *mut CAIProcessor,
+    model_path: *const c_char,
+    tokenizer_path: *const c_char,
+) -> c_int {
+    if processor.is_null() || model_path.is_null() || tokenizer_path.is_null() {
+        return null_argument("processor, model_path and tokenizer_path", -1);
+    }
+    
+    unsafe {
+        let model_path = match CStr::from_ptr(model_path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("model_path: {}", e), -1),
+        };
+        let tokenizer_path = match CStr::from_ptr(tokenizer_path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("tokenizer_path: {}", e), -1),
+        };
+        
+        match embedding::load_onnx_embedder(&OnnxEmbeddingConfig::new(model_path, tokenizer_path)) {
+            Ok(embedder) => {
+                let mut guard = processor_handle(processor).write();
+                let state = &mut *guard;
+                state.embedder = embedder;
+                state.labels.refresh(state.embedder.as_ref());
+                0
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load embedding model: {}", e);
+                processing_failed("Failed to load embedding model", &e, -1)
+            }
+        }
+    }
+}
+
+/// Generate an embedding vector for page content
+///
+/// The vector is L2-normalized, so the dot product of two embeddings from the
+/// same model is their cosine similarity. Free it with `ai_processor_free_embedding`.
+#[no_mangle]
+pub extern "C" fn ai_processor_generate_embedding(
+    processor: *mut CAIProcessor,
+    content_json: *const c_char,
+    embedding_out: *mut *mut c_float,
+    dimensions_out: *mut usize,
+) -> c_int {
+    if processor.is_null() || content_json.is_null() || embedding_out.is_null() || dimensions_out.is_null() {
+        return null_argument("processor, content_json, embedding_out and dimensions_out", -1);
+    }
+    
+    unsafe {
+        *embedding_out = ptr::null_mut();
+        *dimensions_out = 0;
+        
+        let content_str = match CStr::from_ptr(content_json).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("content_json: {}", e), -1),
+        };
+        
+        let content: PageContentInput = match serde_json::from_str(content_str) {
+            Ok(c) => c,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("content_json: {}", e), -1),
+        };
+        
+        let state = processor_handle(processor).read();
+        let embedding = match embed_page(&content, state.embedder.as_ref()) {
+            Ok(e) => e,
+            Err(e) => {
+                tracing::warn!("Embedding generation failed: {}", e);
+                return processing_failed("Embedding generation failed", &e, -1);
+            }
+        };
+        
+        let dimensions = embedding.len();
+        if dimensions > 0 {
+            *embedding_out = Box::into_raw(embedding.into_boxed_slice()) as *mut c_float;
+            *dimensions_out = dimensions;
+        }
+        
+        0
+    }
+}
+
+/// Get the identifier of the active embedding model
+///
+/// Stored next to persisted embeddings so vectors from different models are
+/// never compared. Free the result with `ai_processor_free_string`.
+#[no_mangle]
+pub extern "C" fn ai_processor_get_embedding_model(processor: *mut CAIProcessor) -> *mut c_char {
+    if processor.is_null() {
+        return null_argument("processor", ptr::null_mut());
+    }
+    
+    unsafe {
+        let state = processor_handle(processor).read();
+        match CString::new(state.embedder.name()) {
+            Ok(s) => s.into_raw(),
+            Err(_) => ptr::null_mut(),
+        }
+    }
+}
+
+/// Calculate cosine similarity between two previously generated embeddings
+#[no_mangle]
+pub extern "C" fn ai_processor_embedding_similarity(
+    embedding_a: *const c_float,
+    embedding_b: *const c_float,
+    dimensions: usize,
+) -> c_double {
+    if embedding_a.is_null() || embedding_b.is_null() || dimensions == 0 {
+        return null_argument("embedding_a and embedding_b", 0.0);
+    }
+    
+    unsafe {
+        let a = std::slice::from_raw_parts(embedding_a, dimensions);
+        let b = std::slice::from_raw_parts(embedding_b, dimensions);
+        embedding::cosine_similarity(a, b)
+    }
+}
+
+/// Free embedding vector
+#[no_mangle]
+pub extern "C" fn ai_processor_free_embedding(embedding: *mut c_float, dimensions: usize) {
+    if !embedding.is_null() && dimensions > 0 {
+        unsafe {
+            let _ = Box::from_raw(ptr::slice_from_raw_parts_mut(embedding, dimensions));
+        }
+    }
+}
+
+/// Set the maximum number of cached analysis results (0 disables caching)
+#[no_mangle]
+pub extern "C" fn ai_processor_set_cache_capacity(processor: *mut CAIProcessor, capacity: usize) -> c_int {
+    if processor.is_null() {
+        return null_argument("processor", -1);
+    }
+
+    unsafe {
+        let state = processor_handle(processor).read();
+        state.cache.set_capacity(capacity);
+    }
+
+    0
+}
+
+/// Remove all cached analysis results
+#[no_mangle]
+pub extern "C" fn ai_processor_clear_cache(processor: *mut CAIProcessor) -> c_int {
+    if processor.is_null() {
+        return null_argument("processor", -1);
+    }
+
+    unsafe {
+        let state = processor_handle(processor).read();
+        state.cache.clear();
+    }
+
+    0
+}
+
+/// Get result cache hit/miss counters and the number of cached entries
+#[no_mangle]
+pub extern "C" fn ai_processor_get_cache_stats(
+    processor: *mut CAIProcessor,
+    hits_out: *mut u64,
+    misses_out: *mut u64,
+    entries_out: *mut usize,
+) -> c_int {
+    if processor.is_null() || hits_out.is_null() || misses_out.is_null() || entries_out.is_null() {
+        return null_argument("processor, hits_out, misses_out and entries_out", -1);
+    }
+
+    unsafe {
+        let state = processor_handle(processor).read();
+        let stats = state.cache.stats();
+        *hits_out = stats.hits;
+        *misses_out = stats.misses;
+        *entries_out = stats.entries;
+    }
+
+    0
+}
+
+/// Export cached analysis results as a JSON array for persistence
+///
+/// Each element has a `content_hash` key and an `analysis` value; store them
+/// with the data-access analysis cache repository and restore them with
+/// `ai_processor_import_cache`. Free the result with `ai_processor_free_string`.
+#[no_mangle]
+pub extern "C" fn ai_processor_export_cache(processor: *mut CAIProcessor) -> *mut c_char {
+    if processor.is_null() {
+        return null_argument("processor", ptr::null_mut());
+    }
+
+    unsafe {
+        let state = processor_handle(processor).read();
+        match serde_json::to_string(&state.cache.export()) {
+            Ok(json) => CString::new(json).map(|s| s.into_raw()).unwrap_or(ptr::null_mut()),
+            Err(e) => fail(CErrorCode::ProcessingFailed, format!("Failed to export cache: {}", e), ptr::null_mut()),
+        }
+    }
+}
+
+/// Import cached analysis results previously exported with `ai_processor_export_cache`
+#[no_mangle]
+pub extern "C" fn ai_processor_import_cache(processor: *mut CAIProcessor, entries_json: *const c_char) -> c_int {
+    if processor.is_null() || entries_json.is_null() {
+        return null_argument("processor and entries_json", -1);
+    }
+
+    unsafe {
+        let entries_str = match CStr::from_ptr(entries_json).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("entries_json: {}", e), -1),
+        };
+
+        let entries: Vec<CacheEntry<CachedAnalysis>> = match serde_json::from_str(entries_str) {
+            Ok(e) => e,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("entries_json: {}", e), -1),
+        };
+
+        let state = processor_handle(processor).read();
+        state.cache.import(entries);
+        0
+    }
+}
+
+/// Add a page to the corpus statistics used for keyword weighting
+///
+/// Once the corpus has documents, keyword and key point extraction switch
+/// from raw term frequency to BM25 weighting.
+#[no_mangle]
+pub extern "C" fn ai_processor_add_to_corpus(
+    processor: *mut CAIProcessor,
+    content_json: *const c_char,
+) -> c_int {
+    if processor.is_null() || content_json.is_null() {
+        return null_argument("processor and content_json", -1);
+    }
+
+    unsafe {
+        let content_str = match CStr::from_ptr(content_json).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("content_json: {}", e), -1),
+        };
+
+        let content: PageContentInput = match serde_json::from_str(content_str) {
+            Ok(c) => c,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("content_json: {}", e), -1),
+        };
+
+        let mut state = processor_handle(processor).write();
+        state.corpus.add_document(&stemmed_tokens(&format!("{}\n{}", content.title, content.text)).stems);
+        0
+    }
+}
+
+/// Get the number of documents in the corpus statistics
+#[no_mangle]
+pub extern "C" fn ai_processor_get_corpus_size(processor: *mut CAIProcessor) -> u64 {
+    if processor.is_null() {
+        return null_argument("processor", 0);
+    }
+
+    unsafe {
+        let state = processor_handle(processor).read();
+        state.corpus.document_count
+    }
+}
+
+/// Save the corpus statistics to a JSON file
+#[no_mangle]
+pub extern "C" fn ai_processor_save_corpus(
+    processor: *mut CAIProcessor,
+    path: *const c_char,
+) -> c_int {
+    if processor.is_null() || path.is_null() {
+        return null_argument("processor and path", -1);
+    }
+
+    unsafe {
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("path: {}", e), -1),
         };
-        
-        let content_a: PageContentInput = match serde_json::from_str(content_a_str) {
-            Ok(c) => c,
-            Err(_) => return 0.0,
+
+        let state = processor_handle(processor).read();
+        match state.corpus.save(std::path::Path::new(path)) {
+            Ok(()) => 0,
+            Err(e) => {
+                tracing::warn!("Failed to save corpus statistics: {}", e);
+                processing_failed("Failed to save corpus statistics", &e, -1)
+            }
+        }
+    }
+}
+
+/// Load corpus statistics from a JSON file, replacing the current ones
+#[no_mangle]
+pub extern "C" fn ai_processor_load_corpus(
+    processor: *mut CAIProcessor,
+    path: *const c_char,
+) -> c_int {
+    if processor.is_null() || path.is_null() {
+        return null_argument("processor and path", -1);
+    }
+
+    unsafe {
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("path: {}", e), -1),
+        };
+
+        match CorpusStats::load(std::path::Path::new(path)) {
+            Ok(corpus) => {
+                let mut state = processor_handle(processor).write();
+                state.corpus = corpus;
+                0
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load corpus statistics: {}", e);
+                processing_failed("Failed to load corpus statistics", &e, -1)
+            }
+        }
+    }
+}
+
+/// Enable or disable online Wikidata lookups for entity linking
+///
+/// Linking always uses cached lookups; enabling adds lookups of uncached
+/// names. Returns -1 if the library was built without the `wikidata` feature.
+#[no_mangle]
+pub extern "C" fn ai_processor_set_entity_linking(
+    processor: *mut CAIProcessor,
+    online: c_int,
+) -> c_int {
+    if processor.is_null() {
+        return null_argument("processor", -1);
+    }
+
+    unsafe {
+        let mut state = processor_handle(processor).write();
+        if online == 0 {
+            state.entity_linker.set_source(None);
+            return 0;
+        }
+
+        match entity_linking::wikidata_source() {
+            Ok(source) => {
+                state.entity_linker.set_source(Some(source));
+                0
+            }
+            Err(e) => {
+                tracing::warn!("Failed to enable entity linking: {}", e);
+                processing_failed("Failed to enable entity linking", &e, -1)
+            }
+        }
+    }
+}
+
+/// Save cached Wikidata lookups to a JSON file
+#[no_mangle]
+pub extern "C" fn ai_processor_save_entity_links(
+    processor: *mut CAIProcessor,
+    path: *const c_char,
+) -> c_int {
+    if processor.is_null() || path.is_null() {
+        return null_argument("processor and path", -1);
+    }
+
+    unsafe {
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("path: {}", e), -1),
+        };
+
+        let state = processor_handle(processor).read();
+        match state.entity_linker.save_cache(std::path::Path::new(path)) {
+            Ok(()) => 0,
+            Err(e) => {
+                tracing::warn!("Failed to save entity links: {}", e);
+                processing_failed("Failed to save entity links", &e, -1)
+            }
+        }
+    }
+}
+
+/// Load cached Wikidata lookups from a JSON file, merging with the current ones
+#[no_mangle]
+pub extern "C" fn ai_processor_load_entity_links(
+    processor: *mut CAIProcessor,
+    path: *const c_char,
+) -> c_int {
+    if processor.is_null() || path.is_null() {
+        return null_argument("processor and path", -1);
+    }
+
+    unsafe {
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("path: {}", e), -1),
+        };
+
+        let state = processor_handle(processor).read();
+        match state.entity_linker.load_cache(std::path::Path::new(path)) {
+            Ok(()) => 0,
+            Err(e) => {
+                tracing::warn!("Failed to load entity links: {}", e);
+                processing_failed("Failed to load entity links", &e, -1)
+            }
+        }
+    }
+}
+
+/// Explain why a page was saved, as a recall hint to store with its history entry
+///
+/// `context_json` is an object with `saved_at` (RFC 3339 local time with
+/// offset), an optional `tab_group` name and `concurrent_tabs`, the titles of
+/// the other open tabs. The hint names the time, group, page topics and
+/// related tabs; with a local model active it is rephrased. Returns null on
+/// invalid input; free the result with `ai_processor_free_string`.
+#[no_mangle]
+pub extern "C" fn ai_processor_explain_save(
+    processor: *mut CAIProcessor,
+    content_json: *const c_char,
+    context_json: *const c_char,
+) -> *mut c_char {
+    if processor.is_null() || content_json.is_null() || context_json.is_null() {
+        return null_argument("processor, content_json and context_json", ptr::null_mut());
+    }
+    
+    unsafe {
+        let content: PageContentInput = match json_argument(content_json, "content_json") {
+            Some(content) => content,
+            None => return ptr::null_mut(),
+        };
+        let context: SaveContext = match json_argument(context_json, "context_json") {
+            Some(context) => context,
+            None => return ptr::null_mut(),
         };
         
-        let content_b: PageContentInput = match serde_json::from_str(content_b_str) {
-            Ok(c) => c,
-            Err(_) => return 0.0,
+        let state = processor_handle(processor).read();
+        let keywords = extract_page_keywords(&state, &content);
+        let language = detect_language(&content.text);
+        let hint = RecallFacts::gather(&keywords, &context, &language).to_hint();
+        let hint = match state.active_llm().map(|llm| llm.explain_save(&content, &hint)) {
+            Some(Ok(rephrased)) if !rephrased.is_empty() => rephrased,
+            Some(Err(e)) => {
+                tracing::warn!("Local LLM recall hint failed: {}", e);
+                hint
+            }
+            _ => hint,
         };
         
-        // Calculate cosine similarity
-        calculate_cosine_similarity(&content_a.text, &content_b.text)
+        CString::new(hint).map_or(ptr::null_mut(), CString::into_raw)
     }
 }
 
-/// Set processing mode
+/// Detect whether a fetched page is a login, cookie or paywall screen
+///
+/// Returns a `CAccessWall`, or -1 on invalid input. Summaries report the
+/// same value in `CContentSummary::access_wall`.
 #[no_mangle]
-pub extern "C" fn ai_processor_set_mode(
+pub extern "C" fn ai_processor_detect_access_wall(
     processor: *mut CAIProcessor,
-    mode: CProcessingMode,
+    content_json: *const c_char,
 ) -> c_int {
-    if processor.is_null() {
-        return -1;
+    if processor.is_null() || content_json.is_null() {
+        return null_argument("processor and content_json", -1);
     }
     
     unsafe {
-        let state = &mut *(processor as *mut AIProcessorState);
-        state.mode = mode;
+        let content: PageContentInput = match json_argument(content_json, "content_json") {
+            Some(content) => content,
+            None => return -1,
+        };
+        
+        CAccessWall::from(access_wall::detect(&content.title, &content.text, &content.html)) as c_int
     }
-    
-    0 // Success
 }
 
 /// Free C string
@@ -388,6 +2065,9 @@ pub extern "C" fn ai_processor_free_summary(summary: CContentSummary) {
     if !summary.language.is_null() {
         unsafe { let _ = CString::from_raw(summary.language); }
     }
+    if !summary.image_url.is_null() {
+        unsafe { let _ = CString::from_raw(summary.image_url); }
+    }
     if !summary.key_points.is_null() && summary.key_points_count > 0 {
         unsafe {
             let key_points_slice = std::slice::from_raw_parts_mut(summary.key_points, summary.key_points_count);
@@ -401,6 +2081,28 @@ pub extern "C" fn ai_processor_free_summary(summary: CContentSummary) {
     }
 }
 
+/// Free a single page analysis
+#[no_mangle]
+pub extern "C" fn ai_processor_free_page_analysis(analysis: CPageAnalysis) {
+    ai_processor_free_summary(analysis.summary);
+    unsafe {
+        free_c_string_array(analysis.keywords, analysis.keywords_count);
+    }
+}
+
+/// Free batch analysis results
+#[no_mangle]
+pub extern "C" fn ai_processor_free_batch_results(results: *mut CPageAnalysis, count: usize) {
+    if !results.is_null() && count > 0 {
+        unsafe {
+            let results = Box::from_raw(ptr::slice_from_raw_parts_mut(results, count));
+            for result in results.into_vec() {
+                ai_processor_free_page_analysis(result);
+            }
+        }
+    }
+}
+
 /// Free category info
 #[no_mangle]
 pub extern "C" fn ai_processor_free_category(category: CCategoryInfo) {
@@ -439,14 +2141,72 @@ pub struct CPageStructure {
     pub content_density: c_float,
 }
 
+/// Kind of an extracted entity
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CEntityType {
+    Person = 0,
+    Organization = 1,
+    Location = 2,
+    Date = 3,
+    Money = 4,
+    Product = 5,
+    Website = 6,
+}
+
+impl From<EntityType> for CEntityType {
+    fn from(entity_type: EntityType) -> Self {
+        match entity_type {
+            EntityType::Person => CEntityType::Person,
+            EntityType::Organization => CEntityType::Organization,
+            EntityType::Location => CEntityType::Location,
+            EntityType::Date => CEntityType::Date,
+            EntityType::Money => CEntityType::Money,
+            EntityType::Product => CEntityType::Product,
+            EntityType::Website => CEntityType::Website,
+        }
+    }
+}
+
 /// C-compatible entity info
 #[repr(C)]
 pub struct CEntityInfo {
     pub name: *mut c_char,
-    pub entity_type: *mut c_char,
+    pub entity_type: c_int,
     pub confidence: c_float,
     pub positions: *mut usize,
     pub positions_count: usize,
+    /// Linked Wikidata item id, null when the entity is not linked
+    pub wikidata_id: *mut c_char,
+}
+
+/// Polarity of a sentence
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CSentimentLabel {
+    Positive = 0,
+    Neutral = 1,
+    Negative = 2,
+}
+
+impl From<SentimentLabel> for CSentimentLabel {
+    fn from(label: SentimentLabel) -> Self {
+        match label {
+            SentimentLabel::Positive => CSentimentLabel::Positive,
+            SentimentLabel::Neutral => CSentimentLabel::Neutral,
+            SentimentLabel::Negative => CSentimentLabel::Negative,
+        }
+    }
+}
+
+/// C-compatible sentiment of one sentence
+#[repr(C)]
+pub struct CSentenceSentiment {
+    pub text: *mut c_char,
+    /// Byte offset of the sentence in the analyzed text
+    pub offset: usize,
+    pub score: c_float,
+    pub label: CSentimentLabel,
 }
 
 /// C-compatible cross recommendation
@@ -468,6 +2228,19 @@ pub struct CGroupSuggestion {
     pub page_ids: *mut *mut c_char,
     pub page_ids_count: usize,
     pub similarity_score: c_float,
+    /// Id derived from the group's pages, stable across runs
+    pub group_id: *mut c_char,
+}
+
+/// C-compatible topic discovered across a page library
+#[repr(C)]
+pub struct CTopic {
+    pub label: *mut c_char,
+    pub keywords: *mut *mut c_char,
+    pub keywords_count: usize,
+    pub page_ids: *mut *mut c_char,
+    pub page_ids_count: usize,
+    pub coherence: c_float,
 }
 
 /// Analyze page structure from HTML content
@@ -494,13 +2267,13 @@ pub extern "C" fn ai_processor_analyze_page_structure(
     };
     
     if processor.is_null() || html.is_null() {
-        return empty_structure;
+        return null_argument("processor and html", empty_structure);
     }
     
     unsafe {
         let html_str = match CStr::from_ptr(html).to_str() {
             Ok(s) => s,
-            Err(_) => return empty_structure,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("html: {}", e), empty_structure),
         };
         
         let structure = analyze_page_structure_internal(html_str);
@@ -517,16 +2290,23 @@ pub extern "C" fn ai_processor_extract_entities(
     count_out: *mut usize,
 ) -> c_int {
     if processor.is_null() || text.is_null() || entities_out.is_null() || count_out.is_null() {
-        return -1;
+        return null_argument("processor, text, entities_out and count_out", -1);
     }
     
     unsafe {
         let text_str = match CStr::from_ptr(text).to_str() {
             Ok(s) => s,
-            Err(_) => return -1,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("text: {}", e), -1),
         };
         
-        let entities = extract_entities_internal(text_str);
+        let state = processor_handle(processor).read();
+        let stage = &state.pipeline.entities;
+        let entities = if stage.enabled { entities::extract_entities(text_str) } else { Vec::new() };
+        let wikidata_ids = if stage.link {
+            link_entities(&state.entity_linker, &entities, text_str)
+        } else {
+            vec![None; entities.len()]
+        };
         
         if entities.is_empty() {
             *entities_out = ptr::null_mut();
@@ -536,25 +2316,26 @@ pub extern "C" fn ai_processor_extract_entities(
         
         let c_entities: Vec<CEntityInfo> = entities
             .into_iter()
-            .map(|(name, entity_type, confidence, positions)| {
-                let name_c = CString::new(name).unwrap_or_default();
-                let type_c = CString::new(entity_type).unwrap_or_default();
+            .zip(wikidata_ids)
+            .map(|(entity, wikidata_id)| {
+                let name_c = CString::new(entity.name).unwrap_or_default();
+                let positions_count = entity.positions.len();
                 
-                let positions_ptr = if !positions.is_empty() {
-                    let mut pos_vec = positions.into_boxed_slice();
-                    let ptr = pos_vec.as_mut_ptr();
-                    std::mem::forget(pos_vec);
-                    ptr
+                let positions_ptr = if positions_count > 0 {
+                    Box::into_raw(entity.positions.into_boxed_slice()) as *mut usize
                 } else {
                     ptr::null_mut()
                 };
                 
                 CEntityInfo {
                     name: name_c.into_raw(),
-                    entity_type: type_c.into_raw(),
-                    confidence,
+                    entity_type: CEntityType::from(entity.entity_type) as c_int,
+                    confidence: entity.confidence,
                     positions: positions_ptr,
-                    positions_count: 0, // Will be set properly
+                    positions_count,
+                    wikidata_id: wikidata_id
+                        .and_then(|id| CString::new(id).ok())
+                        .map_or(ptr::null_mut(), CString::into_raw),
                 }
             })
             .collect();
@@ -577,91 +2358,321 @@ pub extern "C" fn ai_processor_analyze_sentiment(
     score_out: *mut c_float,
 ) -> c_int {
     if processor.is_null() || text.is_null() || label_out.is_null() || score_out.is_null() {
-        return -1;
+        return null_argument("processor, text, label_out and score_out", -1);
+    }
+    
+    unsafe {
+        let text_str = match CStr::from_ptr(text).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("text: {}", e), -1),
+        };
+        
+        let state = processor_handle(processor).read();
+        let sentiment = if state.pipeline.sentiment.enabled {
+            sentiment::analyze(text_str, &detect_language(text_str))
+        } else {
+            sentiment::Sentiment::default()
+        };
+        
+        let label_c = CString::new(sentiment.label.as_str()).unwrap_or_default();
+        *label_out = label_c.into_raw();
+        *score_out = sentiment.score;
+        
+        0
+    }
+}
+
+/// Analyze sentiment of each sentence of a text
+///
+/// Free the result with `ai_processor_free_sentence_sentiments`.
+#[no_mangle]
+pub extern "C" fn ai_processor_analyze_sentence_sentiment(
+    processor: *mut CAIProcessor,
+    text: *const c_char,
+    sentences_out: *mut *mut CSentenceSentiment,
+    count_out: *mut usize,
+) -> c_int {
+    if processor.is_null() || text.is_null() || sentences_out.is_null() || count_out.is_null() {
+        return null_argument("processor, text, sentences_out and count_out", -1);
+    }
+    
+    unsafe {
+        let text_str = match CStr::from_ptr(text).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("text: {}", e), -1),
+        };
+        
+        let state = processor_handle(processor).read();
+        let sentiment = if state.pipeline.sentiment.enabled {
+            sentiment::analyze(text_str, &detect_language(text_str))
+        } else {
+            sentiment::Sentiment::default()
+        };
+        if sentiment.sentences.is_empty() {
+            *sentences_out = ptr::null_mut();
+            *count_out = 0;
+            return 0;
+        }
+        
+        let c_sentences: Vec<CSentenceSentiment> = sentiment
+            .sentences
+            .into_iter()
+            .map(|sentence| CSentenceSentiment {
+                text: CString::new(sentence.text).unwrap_or_default().into_raw(),
+                offset: sentence.offset,
+                score: sentence.score,
+                label: CSentimentLabel::from(sentence.label),
+            })
+            .collect();
+        
+        *count_out = c_sentences.len();
+        *sentences_out = Box::into_raw(c_sentences.into_boxed_slice()) as *mut CSentenceSentiment;
+        
+        0
+    }
+}
+
+/// Free sentence sentiment array
+#[no_mangle]
+pub extern "C" fn ai_processor_free_sentence_sentiments(sentences: *mut CSentenceSentiment, count: usize) {
+    if !sentences.is_null() && count > 0 {
+        unsafe {
+            let sentences = Box::from_raw(ptr::slice_from_raw_parts_mut(sentences, count));
+            for sentence in sentences.iter() {
+                if !sentence.text.is_null() {
+                    let _ = CString::from_raw(sentence.text);
+                }
+            }
+        }
+    }
+}
+
+/// Suggest groups from multiple page contents
+///
+/// Pages are clustered hierarchically and the hierarchy is cut at
+/// `similarity_threshold`: higher thresholds give smaller, tighter groups.
+/// Each suggestion carries a `group_id` that stays the same across runs.
+#[no_mangle]
+pub extern "C" fn ai_processor_suggest_groups(
+    processor: *mut CAIProcessor,
+    contents_json: *const c_char,
+    similarity_threshold: c_double,
+    suggestions_out: *mut *mut CGroupSuggestion,
+    count_out: *mut usize,
+) -> c_int {
+    if processor.is_null() || contents_json.is_null() || suggestions_out.is_null() || count_out.is_null() {
+        return null_argument("processor, contents_json, suggestions_out and count_out", -1);
+    }
+    
+    unsafe {
+        let contents_str = match CStr::from_ptr(contents_json).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("contents_json: {}", e), -1),
+        };
+        
+        let contents: Vec<PageContentInput> = match serde_json::from_str(contents_str) {
+            Ok(c) => c,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("contents_json: {}", e), -1),
+        };
+        
+        let state = processor_handle(processor).read();
+        let report = |stage: &str, percent: f32| state.report_progress(stage, percent);
+        let progress = Progress::new(&report);
+        let groups = suggest_groups_internal(&contents, similarity_threshold, state.embedder.as_ref(), &progress);
+        if progress.is_cancelled() {
+            return fail(CErrorCode::Cancelled, "Group suggestion was cancelled", -1);
+        }
+        let suggestions = groups
+            .into_iter()
+            .map(|group| GroupSuggestion {
+                description: format!("A collection of {} related pages", group.page_ids.len()),
+                id: group.id,
+                name: group.name,
+                page_ids: group.page_ids,
+                similarity: group.similarity,
+            })
+            .collect();
+        write_group_suggestions(suggestions, suggestions_out, count_out);
+        
+        0
+    }
+}
+
+/// Suggest groups with their sub-groups as JSON
+///
+/// Returns an array of `{id, name, page_ids, similarity, children}` groups cut
+/// from the same clustering as `ai_processor_suggest_groups`; `children` are
+/// the tighter groups merged into each one. Ids depend only on the pages, so
+/// a group keeps its id when the threshold or other pages change. Free the
+/// result with `ai_processor_free_string`.
+#[no_mangle]
+pub extern "C" fn ai_processor_suggest_group_hierarchy(
+    processor: *mut CAIProcessor,
+    contents_json: *const c_char,
+    similarity_threshold: c_double,
+) -> *mut c_char {
+    if processor.is_null() || contents_json.is_null() {
+        return null_argument("processor and contents_json", ptr::null_mut());
+    }
+    
+    unsafe {
+        let Some(contents) = json_argument::<Vec<PageContentInput>>(contents_json, "contents_json") else {
+            return ptr::null_mut();
+        };
+        
+        let state = processor_handle(processor).read();
+        let report = |stage: &str, percent: f32| state.report_progress(stage, percent);
+        let progress = Progress::new(&report);
+        let groups = suggest_groups_internal(&contents, similarity_threshold, state.embedder.as_ref(), &progress);
+        if progress.is_cancelled() {
+            return fail(CErrorCode::Cancelled, "Group suggestion was cancelled", ptr::null_mut());
+        }
+        serde_json::to_string(&groups)
+            .ok()
+            .and_then(|json| CString::new(json).ok())
+            .map_or(ptr::null_mut(), CString::into_raw)
+    }
+}
+
+/// Suggest groups from topics discovered across the whole library
+///
+/// Unlike `ai_processor_suggest_groups`, which clusters pages by embedding
+/// similarity, this factorizes the library into `num_topics` topics
+/// (0 chooses automatically) and suggests one group per topic with at least
+/// two pages. Free the result with `ai_processor_free_group_suggestions`.
+#[no_mangle]
+pub extern "C" fn ai_processor_suggest_topic_groups(
+    processor: *mut CAIProcessor,
+    contents_json: *const c_char,
+    num_topics: usize,
+    suggestions_out: *mut *mut CGroupSuggestion,
+    count_out: *mut usize,
+) -> c_int {
+    if processor.is_null() || contents_json.is_null() || suggestions_out.is_null() || count_out.is_null() {
+        return null_argument("processor, contents_json, suggestions_out and count_out", -1);
+    }
+    
+    unsafe {
+        let contents_str = match CStr::from_ptr(contents_json).to_str() {
+            Ok(s) => s,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("contents_json: {}", e), -1),
+        };
+        
+        let contents: Vec<PageContentInput> = match serde_json::from_str(contents_str) {
+            Ok(c) => c,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("contents_json: {}", e), -1),
+        };
+        
+        let suggestions = discover_topics_internal(&contents, num_topics)
+            .into_iter()
+            .filter(|topic| topic.members.len() > 1)
+            .map(|topic| {
+                let description = format!("{} pages about {}", topic.members.len(), topic.terms.join(", "));
+                let keys: Vec<&str> = topic.members.iter().map(|&idx| contents[idx].title.as_str()).collect();
+                GroupSuggestion {
+                    id: clustering::group_id(&keys),
+                    name: topic.label,
+                    description,
+                    page_ids: topic.members.iter().map(|idx| idx.to_string()).collect(),
+                    similarity: topic.coherence,
+                }
+            })
+            .collect();
+        write_group_suggestions(suggestions, suggestions_out, count_out);
+        
+        0
+    }
+}
+
+/// Report emerging and fading topics and keywords across visited pages
+///
+/// `visits_json` is a JSON array of pages, each with a `visited_at` RFC 3339
+/// local time. Pages are bucketed by week or month; the last
+/// `recent_periods` periods are compared with the earlier ones. Topics are
+/// discovered across all the pages. Returns a JSON object with `topics` and
+/// `keywords` reports, each listing `periods`, `emerging` and `fading`
+/// terms. Free the result with `ai_processor_free_string`.
+#[no_mangle]
+pub extern "C" fn ai_processor_analyze_trends(
+    processor: *mut CAIProcessor,
+    visits_json: *const c_char,
+    granularity: CTrendGranularity,
+    recent_periods: usize,
+) -> *mut c_char {
+    if processor.is_null() || visits_json.is_null() {
+        return null_argument("processor and visits_json", ptr::null_mut());
     }
     
     unsafe {
-        let text_str = match CStr::from_ptr(text).to_str() {
-            Ok(s) => s,
-            Err(_) => return -1,
+        let Some(visits) = json_argument::<Vec<PageVisitInput>>(visits_json, "visits_json") else {
+            return ptr::null_mut();
         };
         
-        let (label, score) = analyze_sentiment_internal(text_str);
-        
-        let label_c = CString::new(label).unwrap_or_default();
-        *label_out = label_c.into_raw();
-        *score_out = score;
-        
-        0
+        let state = processor_handle(processor).read();
+        let analysis = analyze_trends_internal(&state, &visits, granularity.into(), recent_periods);
+        serde_json::to_string(&analysis)
+            .ok()
+            .and_then(|json| CString::new(json).ok())
+            .map_or(ptr::null_mut(), CString::into_raw)
     }
 }
 
-/// Suggest groups from multiple page contents
+/// Discover topics across a page library
+///
+/// `contents_json` is a JSON array of pages; page ids in the result are their
+/// indices in that array. `num_topics` of 0 chooses the count automatically.
+/// Free the result with `ai_processor_free_topics`.
 #[no_mangle]
-pub extern "C" fn ai_processor_suggest_groups(
+pub extern "C" fn ai_processor_discover_topics(
     processor: *mut CAIProcessor,
     contents_json: *const c_char,
-    similarity_threshold: c_double,
-    suggestions_out: *mut *mut CGroupSuggestion,
+    num_topics: usize,
+    topics_out: *mut *mut CTopic,
     count_out: *mut usize,
 ) -> c_int {
-    if processor.is_null() || contents_json.is_null() || suggestions_out.is_null() || count_out.is_null() {
-        return -1;
+    if processor.is_null() || contents_json.is_null() || topics_out.is_null() || count_out.is_null() {
+        return null_argument("processor, contents_json, topics_out and count_out", -1);
     }
     
     unsafe {
         let contents_str = match CStr::from_ptr(contents_json).to_str() {
             Ok(s) => s,
-            Err(_) => return -1,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("contents_json: {}", e), -1),
         };
         
         let contents: Vec<PageContentInput> = match serde_json::from_str(contents_str) {
             Ok(c) => c,
-            Err(_) => return -1,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("contents_json: {}", e), -1),
         };
         
-        let suggestions = suggest_groups_internal(&contents, similarity_threshold);
+        let topics = discover_topics_internal(&contents, num_topics);
         
-        if suggestions.is_empty() {
-            *suggestions_out = ptr::null_mut();
+        if topics.is_empty() {
+            *topics_out = ptr::null_mut();
             *count_out = 0;
             return 0;
         }
         
-        let c_suggestions: Vec<CGroupSuggestion> = suggestions
+        let c_topics: Vec<CTopic> = topics
             .into_iter()
-            .map(|(name, description, page_ids, score)| {
-                let name_c = CString::new(name).unwrap_or_default();
-                let desc_c = CString::new(description).unwrap_or_default();
-                
-                let mut page_ids_ptrs: Vec<*mut c_char> = page_ids
-                    .into_iter()
-                    .filter_map(|id| CString::new(id).ok())
-                    .map(|cs| cs.into_raw())
-                    .collect();
-                
-                let page_ids_count = page_ids_ptrs.len();
-                let page_ids_ptr = if page_ids_count > 0 {
-                    let ptr = page_ids_ptrs.as_mut_ptr();
-                    std::mem::forget(page_ids_ptrs);
-                    ptr
-                } else {
-                    ptr::null_mut()
-                };
+            .map(|topic| {
+                let (keywords, keywords_count) = strings_to_c_array(topic.terms);
+                let (page_ids, page_ids_count) =
+                    strings_to_c_array(topic.members.iter().map(|idx| idx.to_string()).collect());
                 
-                CGroupSuggestion {
-                    group_name: name_c.into_raw(),
-                    description: desc_c.into_raw(),
-                    page_ids: page_ids_ptr,
+                CTopic {
+                    label: CString::new(topic.label).unwrap_or_default().into_raw(),
+                    keywords,
+                    keywords_count,
+                    page_ids,
                     page_ids_count,
-                    similarity_score: score,
+                    coherence: topic.coherence,
                 }
             })
             .collect();
         
-        let count = c_suggestions.len();
-        let suggestions_box = c_suggestions.into_boxed_slice();
-        *suggestions_out = Box::into_raw(suggestions_box) as *mut CGroupSuggestion;
+        let count = c_topics.len();
+        *topics_out = Box::into_raw(c_topics.into_boxed_slice()) as *mut CTopic;
         *count_out = count;
         
         0
@@ -678,21 +2689,33 @@ pub extern "C" fn ai_processor_generate_cross_recommendations(
     count_out: *mut usize,
 ) -> c_int {
     if processor.is_null() || contents_json.is_null() || recommendations_out.is_null() || count_out.is_null() {
-        return -1;
+        return null_argument("processor, contents_json, recommendations_out and count_out", -1);
     }
     
     unsafe {
         let contents_str = match CStr::from_ptr(contents_json).to_str() {
             Ok(s) => s,
-            Err(_) => return -1,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("contents_json: {}", e), -1),
         };
         
         let contents: Vec<PageContentInput> = match serde_json::from_str(contents_str) {
             Ok(c) => c,
-            Err(_) => return -1,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("contents_json: {}", e), -1),
         };
         
-        let recommendations = generate_cross_recommendations_internal(&contents, min_relevance);
+        let state = processor_handle(processor).read();
+        let report = |stage: &str, percent: f32| state.report_progress(stage, percent);
+        let progress = Progress::new(&report);
+        let recommendations = generate_cross_recommendations_internal(
+            &contents,
+            min_relevance,
+            state.embedder.as_ref(),
+            &state.entity_linker,
+            &progress,
+        );
+        if progress.is_cancelled() {
+            return fail(CErrorCode::Cancelled, "Cross-recommendation generation was cancelled", -1);
+        }
         
         if recommendations.is_empty() {
             *recommendations_out = ptr::null_mut();
@@ -779,12 +2802,12 @@ pub extern "C" fn ai_processor_free_entities(entities: *mut CEntityInfo, count:
                 if !entity.name.is_null() {
                     let _ = CString::from_raw(entity.name);
                 }
-                if !entity.entity_type.is_null() {
-                    let _ = CString::from_raw(entity.entity_type);
-                }
                 if !entity.positions.is_null() && entity.positions_count > 0 {
                     let _ = Box::from_raw(std::slice::from_raw_parts_mut(entity.positions, entity.positions_count) as *mut [usize]);
                 }
+                if !entity.wikidata_id.is_null() {
+                    let _ = CString::from_raw(entity.wikidata_id);
+                }
             }
             let _ = Box::from_raw(std::slice::from_raw_parts_mut(entities, count) as *mut [CEntityInfo]);
         }
@@ -804,6 +2827,9 @@ pub extern "C" fn ai_processor_free_group_suggestions(suggestions: *mut CGroupSu
                 if !suggestion.description.is_null() {
                     let _ = CString::from_raw(suggestion.description);
                 }
+                if !suggestion.group_id.is_null() {
+                    let _ = CString::from_raw(suggestion.group_id);
+                }
                 if !suggestion.page_ids.is_null() && suggestion.page_ids_count > 0 {
                     let page_ids_slice = std::slice::from_raw_parts_mut(suggestion.page_ids, suggestion.page_ids_count);
                     for id in page_ids_slice {
@@ -819,6 +2845,23 @@ pub extern "C" fn ai_processor_free_group_suggestions(suggestions: *mut CGroupSu
     }
 }
 
+/// Free topic array
+#[no_mangle]
+pub extern "C" fn ai_processor_free_topics(topics: *mut CTopic, count: usize) {
+    if !topics.is_null() && count > 0 {
+        unsafe {
+            let topics = Box::from_raw(ptr::slice_from_raw_parts_mut(topics, count));
+            for topic in topics.iter() {
+                if !topic.label.is_null() {
+                    let _ = CString::from_raw(topic.label);
+                }
+                free_c_string_array(topic.keywords, topic.keywords_count);
+                free_c_string_array(topic.page_ids, topic.page_ids_count);
+            }
+        }
+    }
+}
+
 /// Free cross recommendation array
 #[no_mangle]
 pub extern "C" fn ai_processor_free_cross_recommendations(recommendations: *mut CCrossRecommendation, count: usize) {
@@ -860,7 +2903,7 @@ pub extern "C" fn ai_processor_recommend_related(
     count: *mut usize,
 ) -> i32 {
     if processor.is_null() || target_content_json.is_null() || candidates_json.is_null() || recommendations.is_null() || count.is_null() {
-        return -1;
+        return null_argument("processor, target_content_json, candidates_json, recommendations and count", -1);
     }
 
     unsafe {
@@ -869,24 +2912,24 @@ pub extern "C" fn ai_processor_recommend_related(
 
         let target_json_str = match CStr::from_ptr(target_content_json).to_str() {
             Ok(s) => s,
-            Err(_) => return -1,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("target_content_json: {}", e), -1),
         };
 
         let candidates_json_str = match CStr::from_ptr(candidates_json).to_str() {
             Ok(s) => s,
-            Err(_) => return -1,
+            Err(e) => return fail(CErrorCode::InvalidUtf8, format!("candidates_json: {}", e), -1),
         };
 
         // Parse target content
         let target: PageContentInput = match serde_json::from_str(target_json_str) {
             Ok(c) => c,
-            Err(_) => return -1,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("target_content_json: {}", e), -1),
         };
 
         // Parse candidates
         let candidates: Vec<PageContentInput> = match serde_json::from_str(candidates_json_str) {
             Ok(c) => c,
-            Err(_) => return -1,
+            Err(e) => return fail(CErrorCode::InvalidJson, format!("candidates_json: {}", e), -1),
         };
 
         if candidates.is_empty() {
@@ -1002,39 +3045,31 @@ pub extern "C" fn ai_processor_free_groups(groups: *mut CGroupSuggestion, count:
 // ============================================================================
 
 /// Common stop words to filter out
-const STOP_WORDS: &[&str] = &[
-    "the", "a", "an", "and", "or", "but", "in", "on", "at", "to", "for",
-    "of", "with", "by", "from", "as", "is", "was", "are", "were", "been",
-    "be", "have", "has", "had", "do", "does", "did", "will", "would", "could",
-    "should", "may", "might", "must", "shall", "can", "need", "dare", "ought",
-    "used", "this", "that", "these", "those", "i", "you", "he", "she", "it",
-    "we", "they", "what", "which", "who", "whom", "whose", "where", "when",
-    "why", "how", "all", "each", "every", "both", "few", "more", "most",
-    "other", "some", "such", "no", "nor", "not", "only", "own", "same",
-    "so", "than", "too", "very", "just", "also", "now", "here", "there",
-];
+const STOP_WORDS: &[&str] = language::ENGLISH_STOP_WORDS;
 
 /// Tokenize text into words, filtering stop words
+///
+/// CJK text is segmented into words and stop words are filtered for the
+/// detected language as well as English.
 fn tokenize(text: &str) -> Vec<String> {
-    let mut tokens = Vec::new();
-    let mut word = String::new();
-    
-    for c in text.chars() {
-        if c.is_alphanumeric() {
-            word.push(c.to_ascii_lowercase());
-        } else if !word.is_empty() {
-            if word.len() > 2 && !STOP_WORDS.contains(&word.as_str()) {
-                tokens.push(word.clone());
-            }
-            word.clear();
-        }
-    }
-    
-    if !word.is_empty() && word.len() > 2 && !STOP_WORDS.contains(&word.as_str()) {
-        tokens.push(word);
+    language::tokenize(text, &detect_language(text))
+}
+
+/// Tokenize text and fold inflected forms onto their stems
+///
+/// Corpus statistics are kept per stem, so text ranked against the corpus
+/// must be stemmed the same way.
+fn stemmed_tokens(text: &str) -> StemmedTokens {
+    let language = detect_language(text);
+    StemmedTokens::new(&language::tokenize(text, &language), &language)
+}
+
+/// Truncate text to at most `max_chars` characters on a char boundary
+fn truncate_chars(text: &str, max_chars: usize) -> &str {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => &text[..idx],
+        None => text,
     }
-    
-    tokens
 }
 
 /// Split text into sentences
@@ -1045,7 +3080,7 @@ fn split_into_sentences(text: &str) -> Vec<String> {
     for c in text.chars() {
         current.push(c);
         
-        if c == '.' || c == '!' || c == '?' {
+        if matches!(c, '.' | '!' | '?' | '。' | '！' | '？') {
             let trimmed = current.trim().to_string();
             if trimmed.len() > 10 {
                 sentences.push(trimmed);
@@ -1099,6 +3134,228 @@ fn score_sentence(sentence: &str, word_freq: &std::collections::HashMap<String,
     (score / tokens.len() as f64) * length_factor
 }
 
+/// Summary fields computed for one page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PageSummary {
+    summary_text: String,
+    key_points: Vec<String>,
+    content_type: CContentType,
+    language: String,
+    reading_time_minutes: u32,
+    confidence_score: f32,
+    #[serde(default)]
+    readability: Readability,
+    #[serde(default)]
+    access_wall: AccessWall,
+    #[serde(default)]
+    quality: Quality,
+    #[serde(default)]
+    media: MediaSummary,
+}
+
+impl PageSummary {
+    /// Convert into the C representation, freed with `ai_processor_free_summary`
+    fn into_c(self) -> CContentSummary {
+        let (key_points, key_points_count) = strings_to_c_array(self.key_points);
+        CContentSummary {
+            summary_text: CString::new(self.summary_text).unwrap_or_default().into_raw(),
+            key_points,
+            key_points_count,
+            content_type: self.content_type as c_int,
+            language: CString::new(self.language).unwrap_or_default().into_raw(),
+            reading_time_minutes: self.reading_time_minutes,
+            confidence_score: self.confidence_score,
+            readability_score: self.readability.score,
+            reading_difficulty: CReadingDifficulty::from(self.readability.difficulty) as c_int,
+            access_wall: CAccessWall::from(self.access_wall) as c_int,
+            quality_score: self.quality.score,
+            clickbait_score: self.quality.clickbait,
+            image_url: self
+                .media
+                .image
+                .and_then(|url| CString::new(url).ok())
+                .map_or(ptr::null_mut(), CString::into_raw),
+            image_count: self.media.image_count as u32,
+            video_count: self.media.video_count as u32,
+        }
+    }
+}
+
+/// Analyze one page, reporting summary text chunks to `on_chunk`
+///
+/// Cached results are reported as a single chunk.
+fn summarize_page(
+    state: &AIProcessorState,
+    content: &PageContentInput,
+    length: SummaryLength,
+    on_chunk: &mut dyn FnMut(&str),
+) -> PageSummary {
+    let fingerprint = format!("{}|{:?}|{:?}", state.cache_fingerprint(), state.mode, length);
+    let key = cache_key(AnalysisKind::Summary, &fingerprint, content);
+    if let Some(CachedAnalysis::Summary(summary)) = state.cache.get(&key) {
+        if !summary.summary_text.is_empty() {
+            on_chunk(&summary.summary_text);
+        }
+        return summary;
+    }
+    
+    let summary = analyze_summary(state, content, length, on_chunk);
+    state.cache.insert(key, CachedAnalysis::Summary(summary.clone()));
+    summary
+}
+
+/// Compute the summary fields for one page
+fn analyze_summary(
+    state: &AIProcessorState,
+    content: &PageContentInput,
+    length: SummaryLength,
+    on_chunk: &mut dyn FnMut(&str),
+) -> PageSummary {
+    let stage = &state.pipeline.summary;
+    // Text of a login or paywall screen is not worth summarizing
+    let access_wall = access_wall::detect(&content.title, &content.text, &content.html);
+    let (summary_text, key_points) = if stage.enabled && access_wall == AccessWall::None {
+        let code_points = code::key_points(&technical_snippets(state, content));
+        let mut key_points = extract_key_points(&content.text, stage.max_key_points, &state.corpus);
+        key_points.truncate(stage.max_key_points.saturating_sub(code_points.len()));
+        key_points.extend(code_points.into_iter().take(stage.max_key_points));
+        (generate_summary_text(state, content, length, on_chunk), key_points)
+    } else {
+        (String::new(), Vec::new())
+    };
+    
+    // Calculate confidence score
+    let mut confidence = 0.5f32;
+    if !summary_text.is_empty() { confidence += 0.15; }
+    if !key_points.is_empty() { confidence += 0.1; }
+    if !content.title.is_empty() { confidence += 0.1; }
+    if content.description.is_some() { confidence += 0.1; }
+    if content.text.len() > 500 { confidence += 0.05; }
+    
+    let language = detect_language(&content.text);
+    PageSummary {
+        summary_text,
+        key_points,
+        content_type: if state.pipeline.classification.enabled {
+            classify_content_type(content, &state.domains).content_type
+        } else {
+            CContentType::Other
+        },
+        readability: readability::score(&content.text, &language),
+        language,
+        reading_time_minutes: estimate_reading_time(&content.text),
+        confidence_score: confidence.min(0.95),
+        access_wall,
+        quality: quality::assess(&content.title, &content.text, &content.html),
+        media: media::summarize(&content.html, &content.images, content.url.as_deref()),
+    }
+}
+
+/// Generate the summary text, reporting chunks to `on_chunk`
+///
+/// Prefers the local model when available and falls back to extractive
+/// summarization, which is reported as a single chunk. If the model fails
+/// after streaming part of its output, the fallback is reported after it.
+/// `length` only applies in abstractive mode.
+fn generate_summary_text(
+    state: &AIProcessorState,
+    content: &PageContentInput,
+    length: SummaryLength,
+    on_chunk: &mut dyn FnMut(&str),
+) -> String {
+    let abstractive = matches!(state.mode, CProcessingMode::Abstractive);
+    if let Some(llm) = state.active_llm() {
+        let result = if abstractive {
+            llm.rewrite_stream(content, length, on_chunk)
+        } else {
+            llm.summarize_stream(content, on_chunk)
+        };
+        match result {
+            Ok(summary) => return summary,
+            Err(e) => tracing::warn!("Local LLM summary failed: {}", e),
+        }
+    }
+    
+    let summary = match (abstractive, length) {
+        (true, SummaryLength::OneLiner) => generate_extractive_summary(&content.text, 1),
+        (true, SummaryLength::BulletList) => extract_key_points(&content.text, state.pipeline.summary.max_key_points.max(1), &state.corpus)
+            .iter()
+            .map(|point| format!("- {}", point))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => generate_extractive_summary(&content.text, state.pipeline.summary.max_sentences.max(1)),
+    };
+    if !summary.is_empty() {
+        on_chunk(&summary);
+    }
+    summary
+}
+
+/// Merge provided, extracted and title keywords for one page
+fn extract_page_keywords(state: &AIProcessorState, content: &PageContentInput) -> Vec<String> {
+    let key = cache_key(AnalysisKind::Keywords, &state.cache_fingerprint(), content);
+    if let Some(CachedAnalysis::Keywords(keywords)) = state.cache.get(&key) {
+        return keywords;
+    }
+    
+    let keywords = merge_page_keywords(state, content);
+    state.cache.insert(key, CachedAnalysis::Keywords(keywords.clone()));
+    keywords
+}
+
+/// Compute the keywords for one page
+fn merge_page_keywords(state: &AIProcessorState, content: &PageContentInput) -> Vec<String> {
+    let stage = &state.pipeline.keywords;
+    if !stage.enabled {
+        return content.keywords.clone();
+    }
+    
+    let language = detect_language(&content.text);
+    let extracted = match state.keyword_algorithm {
+        CKeywordAlgorithm::Frequency => extract_keywords_from_text(&content.text, stage.max_keywords, &state.corpus),
+        CKeywordAlgorithm::Rake => keyphrase::extract_keyphrases(&content.text, stage.max_keywords, &language),
+    };
+    
+    // Merge keywords, treating case and inflection variants as duplicates
+    let mut seen = std::collections::HashSet::new();
+    let mut keywords: Vec<String> = content.keywords
+        .iter()
+        .chain(&extracted)
+        .filter(|kw| seen.insert(stemming::phrase_key(kw, &language)))
+        .cloned()
+        .collect();
+    
+    // Also extract from title
+    let title_keywords = extract_keywords_from_text(&content.title, 5, &state.corpus);
+    for kw in title_keywords {
+        if seen.insert(stemming::phrase_key(&kw, &language)) {
+            keywords.insert(0, kw);
+        }
+    }
+    
+    // Limit to the text keywords plus up to 5 from the title
+    keywords.truncate(stage.max_keywords + 5);
+    
+    // Code examples are what technical pages are remembered by
+    for kw in code::keywords(&technical_snippets(state, content)) {
+        if seen.insert(stemming::phrase_key(&kw, &language)) {
+            keywords.push(kw);
+        }
+    }
+    keywords
+}
+
+/// Code blocks of documentation and article pages
+fn technical_snippets(state: &AIProcessorState, content: &PageContentInput) -> Vec<code::CodeSnippet> {
+    if content.html.is_empty() {
+        return Vec::new();
+    }
+    match classify_content_type(content, &state.domains).content_type {
+        CContentType::Documentation | CContentType::Article => code::extract_snippets(&content.html),
+        _ => Vec::new(),
+    }
+}
+
 /// Generate extractive summary
 fn generate_extractive_summary(text: &str, max_sentences: usize) -> String {
     if text.is_empty() {
@@ -1107,10 +3364,10 @@ fn generate_extractive_summary(text: &str, max_sentences: usize) -> String {
     
     let sentences = split_into_sentences(text);
     if sentences.is_empty() {
-        return if text.len() <= 200 {
+        return if text.chars().count() <= 200 {
             text.to_string()
         } else {
-            format!("{}...", &text[..200])
+            format!("{}...", truncate_chars(text, 200))
         };
     }
     
@@ -1143,7 +3400,10 @@ fn generate_extractive_summary(text: &str, max_sentences: usize) -> String {
 }
 
 /// Extract key points from text
-fn extract_key_points(text: &str, max_points: usize) -> Vec<String> {
+///
+/// Sentences are scored by term frequency, or by BM25 weight once the corpus
+/// has documents, so boilerplate terms stop dominating the selection.
+fn extract_key_points(text: &str, max_points: usize, corpus: &CorpusStats) -> Vec<String> {
     if text.is_empty() {
         return Vec::new();
     }
@@ -1154,13 +3414,20 @@ fn extract_key_points(text: &str, max_points: usize) -> Vec<String> {
     }
     
     let all_tokens = tokenize(text);
-    let word_freq = calculate_word_frequency(&all_tokens);
-    let max_freq = word_freq.values().max().copied().unwrap_or(1);
-    
-    let mut scored: Vec<(f64, String)> = sentences
-        .iter()
-        .map(|s| (score_sentence(s, &word_freq, max_freq), s.clone()))
-        .collect();
+    let mut scored: Vec<(f64, String)> = if corpus.is_empty() {
+        let word_freq = calculate_word_frequency(&all_tokens);
+        let max_freq = word_freq.values().max().copied().unwrap_or(1);
+        sentences
+            .iter()
+            .map(|s| (score_sentence(s, &word_freq, max_freq), s.clone()))
+            .collect()
+    } else {
+        let weights = corpus.bm25_weights(&stemmed_tokens(text).stems);
+        sentences
+            .iter()
+            .map(|s| (score_sentence_weighted(s, &weights), s.clone()))
+            .collect()
+    };
     
     scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
     
@@ -1168,8 +3435,8 @@ fn extract_key_points(text: &str, max_points: usize) -> Vec<String> {
         .into_iter()
         .take(max_points)
         .map(|(_, s)| {
-            if s.len() > 150 {
-                format!("{}...", &s[..147])
+            if s.chars().count() > 150 {
+                format!("{}...", truncate_chars(&s, 147))
             } else {
                 s
             }
@@ -1177,14 +3444,46 @@ fn extract_key_points(text: &str, max_points: usize) -> Vec<String> {
         .collect()
 }
 
+/// Score a sentence by the average BM25 weight of its terms
+fn score_sentence_weighted(sentence: &str, weights: &std::collections::HashMap<String, f64>) -> f64 {
+    let tokens = stemmed_tokens(sentence).stems;
+    if tokens.is_empty() {
+        return 0.0;
+    }
+    
+    let score: f64 = tokens.iter().filter_map(|t| weights.get(t)).sum();
+    let length_factor = if tokens.len() < 5 {
+        0.5
+    } else if tokens.len() > 30 {
+        0.7
+    } else {
+        1.0
+    };
+    
+    (score / tokens.len() as f64) * length_factor
+}
+
 /// Extract keywords from text
-fn extract_keywords_from_text(text: &str, max_keywords: usize) -> Vec<String> {
+///
+/// Variants of a word are counted together under their stem and reported as
+/// the most frequent variant. Uses raw term frequency until the corpus has
+/// documents, then BM25.
+fn extract_keywords_from_text(text: &str, max_keywords: usize, corpus: &CorpusStats) -> Vec<String> {
     if text.is_empty() {
         return Vec::new();
     }
     
-    let tokens = tokenize(text);
-    let word_freq = calculate_word_frequency(&tokens);
+    let tokens = stemmed_tokens(text);
+    
+    if !corpus.is_empty() {
+        return corpus::rank_terms(&tokens.stems, corpus)
+            .into_iter()
+            .take(max_keywords)
+            .map(|(stem, _)| tokens.surface(&stem))
+            .collect();
+    }
+    
+    let word_freq = calculate_word_frequency(&tokens.stems);
     
     let mut sorted: Vec<(String, usize)> = word_freq.into_iter().collect();
     sorted.sort_by(|a, b| b.1.cmp(&a.1));
@@ -1193,19 +3492,45 @@ fn extract_keywords_from_text(text: &str, max_keywords: usize) -> Vec<String> {
         .into_iter()
         .take(max_keywords)
         .filter(|(_, count)| *count > 1 || max_keywords > 10)
-        .map(|(word, _)| word)
+        .map(|(stem, _)| tokens.surface(&stem))
         .collect()
 }
 
-/// Classify content type
-fn classify_content_type(content: &PageContentInput) -> CContentType {
+/// Classify a page with the local model if active, else by domain and heuristics
+///
+/// Domain rules are consulted before the model since they are more reliable.
+fn classify_page(state: &AIProcessorState, content: &PageContentInput) -> Classification {
+    let by_rules = classify_content_type(content, &state.domains);
+    if by_rules.source == CClassificationSource::Domain {
+        return by_rules;
+    }
+    
+    state.active_llm()
+        .and_then(|llm| llm.classify(content)
+            .map_err(|e| tracing::warn!("Local LLM classification failed: {}", e))
+            .ok())
+        .map(|content_type| Classification { content_type, source: CClassificationSource::LocalModel })
+        .unwrap_or(by_rules)
+}
+
+/// Classify content type from the page domain, then title and text heuristics
+fn classify_content_type(content: &PageContentInput, domains: &DomainCategories) -> Classification {
+    if let Some((content_type, rule)) = content.url.as_deref().and_then(|url| domains.lookup(url)) {
+        tracing::debug!("Classified {:?} as {:?} by domain rule {}", content.url, content_type, rule);
+        return Classification { content_type, source: CClassificationSource::Domain };
+    }
+    
+    Classification {
+        content_type: classify_by_text(content),
+        source: CClassificationSource::Heuristic,
+    }
+}
+
+/// Classify content type from title and text keywords
+fn classify_by_text(content: &PageContentInput) -> CContentType {
     let lower_title = content.title.to_lowercase();
     let lower_text = content.text.to_lowercase();
-    let sample_text = if lower_text.len() > 1000 {
-        &lower_text[..1000]
-    } else {
-        &lower_text
-    };
+    let sample_text = truncate_chars(&lower_text, 1000);
     
     // Check for video content
     if lower_title.contains("video") || lower_title.contains("watch") ||
@@ -1266,14 +3591,20 @@ fn get_category_info(content_type: CContentType) -> (String, Vec<String>) {
 /// Detect language from text
 fn detect_language(text: &str) -> String {
     let mut chinese_chars = 0;
+    let mut kana_chars = 0;
+    let mut hangul_chars = 0;
     let mut latin_chars = 0;
     let mut cyrillic_chars = 0;
     
     for c in text.chars() {
         if c.is_ascii_alphabetic() {
             latin_chars += 1;
-        } else if c >= '\u{4E00}' && c <= '\u{9FFF}' {
+        } else if language::is_han(c) {
             chinese_chars += 1;
+        } else if language::is_kana(c) {
+            kana_chars += 1;
+        } else if language::is_hangul(c) {
+            hangul_chars += 1;
         } else if c >= '\u{0400}' && c <= '\u{04FF}' {
             cyrillic_chars += 1;
         }
@@ -1297,6 +3628,15 @@ fn detect_language(text: &str) -> String {
         .filter(|w| lower_text.contains(*w))
         .count();
     
+    // Kana only occur in Japanese, which also uses Han characters
+    if kana_chars > 0 && kana_chars + chinese_chars > latin_chars {
+        return "ja".to_string();
+    }
+    
+    if hangul_chars > latin_chars {
+        return "ko".to_string();
+    }
+    
     if chinese_chars > latin_chars {
         return "zh".to_string();
     }
@@ -1377,6 +3717,38 @@ fn calculate_cosine_similarity(text_a: &str, text_b: &str) -> f64 {
     dot_product / (magnitude_a * magnitude_b)
 }
 
+/// Calculate semantic similarity between two pages using an embedding model
+///
+/// Falls back to bag-of-words cosine similarity if the model fails.
+fn embedding_similarity(embedder: &dyn EmbeddingModel, a: &PageContentInput, b: &PageContentInput) -> f64 {
+    match (embed_page(a, embedder), embed_page(b, embedder)) {
+        (Ok(a), Ok(b)) => embedding::cosine_similarity(&a, &b).clamp(0.0, 1.0),
+        _ => calculate_cosine_similarity(&a.text, &b.text),
+    }
+}
+
+/// Embed the title and text of a page
+///
+/// Every page embedding goes through here, so vectors persisted from
+/// `ai_processor_generate_embedding` match the ones grouping compares.
+fn embed_page(content: &PageContentInput, embedder: &dyn EmbeddingModel) -> Result<Vec<f32>, web_page_manager_core::AIProcessingError> {
+    embedder.embed(&format!("{}\n{}", content.title, content.text))
+}
+
+/// Embed every page, using an empty vector for failures
+///
+/// Stops early, returning fewer embeddings, if `stage` is cancelled.
+fn embed_contents(contents: &[PageContentInput], embedder: &dyn EmbeddingModel, stage: &Stage) -> Vec<Vec<f32>> {
+    let mut embeddings = Vec::with_capacity(contents.len());
+    for content in contents {
+        embeddings.push(embed_page(content, embedder).unwrap_or_default());
+        if !stage.step() {
+            break;
+        }
+    }
+    embeddings
+}
+
 /// Analyze page structure from HTML
 fn analyze_page_structure_internal(html: &str) -> CPageStructure {
     use regex::Regex;
@@ -1529,173 +3901,182 @@ fn strip_html_tags(html: &str) -> String {
     result.trim().to_string()
 }
 
-/// Extract entities from text
-fn extract_entities_internal(text: &str) -> Vec<(String, String, f32, Vec<usize>)> {
-    use regex::Regex;
-    use std::collections::HashMap;
+/// Suggested group of pages before conversion to `CGroupSuggestion`
+struct GroupSuggestion {
+    id: String,
+    name: String,
+    description: String,
+    page_ids: Vec<String>,
+    similarity: f32,
+}
+
+/// Group of the hierarchy returned by `ai_processor_suggest_group_hierarchy`
+#[derive(Debug, Clone, Serialize)]
+struct GroupNode {
+    id: String,
+    name: String,
+    page_ids: Vec<String>,
+    similarity: f32,
+    children: Vec<GroupNode>,
+}
+
+/// Suggest groups from page contents
+///
+/// Groups are the clusters of the average-linkage dendrogram cut at
+/// `similarity_threshold`, each with its sub-groups.
+fn suggest_groups_internal(
+    contents: &[PageContentInput],
+    similarity_threshold: f64,
+    embedder: &dyn EmbeddingModel,
+    progress: &Progress,
+) -> Vec<GroupNode> {
+    if contents.is_empty() {
+        return Vec::new();
+    }
     
-    let mut entities: HashMap<String, (String, f32, Vec<usize>)> = HashMap::new();
+    let embeddings = embed_contents(contents, embedder, &progress.stage("embed", contents.len()));
+    if progress.is_cancelled() {
+        return Vec::new();
+    }
     
-    // Extract potential person names (capitalized word sequences)
-    if let Ok(re) = Regex::new(r"\b([A-Z][a-z]+(?:\s+[A-Z][a-z]+)+)\b") {
-        for cap in re.captures_iter(text) {
-            if let Some(m) = cap.get(1) {
-                let name = m.as_str().to_string();
-                let pos = m.start();
-                
-                entities.entry(name.clone())
-                    .and_modify(|(_, conf, positions)| {
-                        *conf = (*conf + 0.1).min(0.95);
-                        positions.push(pos);
-                    })
-                    .or_insert(("person".to_string(), 0.6, vec![pos]));
-            }
-        }
+    let grouping = progress.stage("group", contents.len().saturating_sub(1));
+    let dendrogram = clustering::Dendrogram::build(&embeddings, &grouping);
+    if progress.is_cancelled() {
+        return Vec::new();
     }
     
-    // Extract potential organizations
-    if let Ok(re) = Regex::new(r"\b([A-Z][A-Za-z]*(?:\s+[A-Z][A-Za-z]*)*\s+(?:Inc|Corp|Ltd|LLC|Company|Corporation|Foundation|Institute|University))\b") {
-        for cap in re.captures_iter(text) {
-            if let Some(m) = cap.get(1) {
-                let org = m.as_str().to_string();
-                let pos = m.start();
-                
-                entities.entry(org.clone())
-                    .and_modify(|(entity_type, conf, positions)| {
-                        *entity_type = "organization".to_string();
-                        *conf = (*conf + 0.1).min(0.95);
-                        positions.push(pos);
-                    })
-                    .or_insert(("organization".to_string(), 0.75, vec![pos]));
-            }
+    dendrogram
+        .cut(similarity_threshold)
+        .iter()
+        .enumerate()
+        .map(|(i, cluster)| group_node(contents, cluster, &format!("Group {}", i + 1)))
+        .collect()
+}
+
+/// Name and identify a cluster and its sub-groups
+fn group_node(contents: &[PageContentInput], cluster: &clustering::Cluster, fallback_name: &str) -> GroupNode {
+    let texts: Vec<&str> = cluster.members.iter().map(|&idx| contents[idx].text.as_str()).collect();
+    let common_words = find_common_words(&texts, 3);
+    let name = if common_words.is_empty() { fallback_name.to_string() } else { common_words.join(" & ") };
+    
+    // Pages are keyed by URL, then title, so ids survive reordering
+    let keys: Vec<String> = cluster
+        .members
+        .iter()
+        .map(|&idx| {
+            let content = &contents[idx];
+            content.url.clone().filter(|url| !url.is_empty()).unwrap_or_else(|| content.title.clone())
+        })
+        .collect();
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+    
+    GroupNode {
+        id: clustering::group_id(&keys),
+        page_ids: cluster.members.iter().map(|idx| idx.to_string()).collect(),
+        similarity: cluster.similarity as f32,
+        children: cluster
+            .children
+            .iter()
+            .enumerate()
+            .map(|(i, child)| group_node(contents, child, &format!("{} {}", name, i + 1)))
+            .collect(),
+        name,
+    }
+}
+
+/// Trends of the dominant topic and the keywords of visited pages
+fn analyze_trends_internal(
+    state: &AIProcessorState,
+    visits: &[PageVisitInput],
+    granularity: Granularity,
+    recent_periods: usize,
+) -> TrendAnalysis {
+    let contents: Vec<PageContentInput> = visits.iter().map(|visit| visit.content.clone()).collect();
+    let mut page_topics = vec![Vec::new(); visits.len()];
+    for topic in discover_topics_internal(&contents, 0) {
+        for &member in &topic.members {
+            page_topics[member] = vec![topic.label.clone()];
         }
     }
     
-    // Extract URLs as website entities
-    if let Ok(re) = Regex::new(r"https?://([a-zA-Z0-9.-]+)") {
-        for cap in re.captures_iter(text) {
-            if let Some(m) = cap.get(1) {
-                let domain = m.as_str().to_string();
-                let pos = m.start();
-                
-                entities.entry(domain.clone())
-                    .or_insert(("website".to_string(), 0.9, vec![pos]));
-            }
-        }
+    let topic_pages: Vec<_> = visits.iter().map(|visit| visit.visited_at).zip(page_topics).collect();
+    let keyword_pages: Vec<_> = visits
+        .iter()
+        .map(|visit| (visit.visited_at, extract_page_keywords(state, &visit.content)))
+        .collect();
+    TrendAnalysis {
+        topics: trends::analyze(&topic_pages, granularity, recent_periods),
+        keywords: trends::analyze(&keyword_pages, granularity, recent_periods),
+    }
+}
+
+/// Run topic discovery over page title and text
+fn discover_topics_internal(contents: &[PageContentInput], num_topics: usize) -> Vec<topics::Topic> {
+    let documents: Vec<Vec<String>> = contents
+        .iter()
+        .map(|c| tokenize(&format!("{}\n{}", c.title, c.text)))
+        .collect();
+    topics::discover_topics(&documents, num_topics, 8)
+}
+
+/// Write group suggestions into a C array owned by the caller
+unsafe fn write_group_suggestions(
+    suggestions: Vec<GroupSuggestion>,
+    suggestions_out: *mut *mut CGroupSuggestion,
+    count_out: *mut usize,
+) {
+    if suggestions.is_empty() {
+        *suggestions_out = ptr::null_mut();
+        *count_out = 0;
+        return;
     }
     
-    // Convert to vector and sort by confidence
-    let mut result: Vec<(String, String, f32, Vec<usize>)> = entities
+    let c_suggestions: Vec<CGroupSuggestion> = suggestions
         .into_iter()
-        .map(|(name, (entity_type, conf, positions))| (name, entity_type, conf, positions))
+        .map(|suggestion| {
+            let (page_ids, page_ids_count) = strings_to_c_array(suggestion.page_ids);
+            CGroupSuggestion {
+                group_name: CString::new(suggestion.name).unwrap_or_default().into_raw(),
+                description: CString::new(suggestion.description).unwrap_or_default().into_raw(),
+                page_ids,
+                page_ids_count,
+                similarity_score: suggestion.similarity,
+                group_id: CString::new(suggestion.id).unwrap_or_default().into_raw(),
+            }
+        })
         .collect();
     
-    result.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
-    
-    result
+    let count = c_suggestions.len();
+    *suggestions_out = Box::into_raw(c_suggestions.into_boxed_slice()) as *mut CGroupSuggestion;
+    *count_out = count;
 }
 
-/// Analyze sentiment of text
-fn analyze_sentiment_internal(text: &str) -> (String, f32) {
-    const POSITIVE_WORDS: &[&str] = &[
-        "good", "great", "excellent", "amazing", "wonderful", "fantastic",
-        "awesome", "best", "love", "happy", "beautiful", "perfect",
-        "brilliant", "outstanding", "superb", "incredible", "positive",
-        "success", "successful", "win", "winner", "benefit", "helpful",
-        "easy", "simple", "fast", "efficient", "effective", "recommend",
-        "like", "enjoy", "pleased", "satisfied", "impressive", "innovative"
-    ];
-    
-    const NEGATIVE_WORDS: &[&str] = &[
-        "bad", "terrible", "awful", "horrible", "worst", "hate", "poor",
-        "disappointing", "disappointed", "fail", "failure", "problem",
-        "issue", "bug", "error", "wrong", "broken", "slow", "difficult",
-        "hard", "complicated", "confusing", "frustrating", "annoying",
-        "useless", "waste", "expensive", "overpriced", "scam", "fake",
-        "never", "cannot", "impossible", "unfortunately", "sadly"
-    ];
-    
-    let tokens = tokenize(text);
-    
-    let positive_count = tokens.iter()
-        .filter(|t| POSITIVE_WORDS.contains(&t.as_str()))
-        .count();
-    
-    let negative_count = tokens.iter()
-        .filter(|t| NEGATIVE_WORDS.contains(&t.as_str()))
-        .count();
-    
-    let total = positive_count + negative_count;
-    let score = if total > 0 {
-        (positive_count as f32 - negative_count as f32) / total as f32
-    } else {
-        0.0
-    };
-    
-    let label = if score > 0.3 {
-        "positive"
-    } else if score < -0.3 {
-        "negative"
-    } else {
-        "neutral"
-    };
+/// Convert strings into a boxed C string array (null when empty)
+fn strings_to_c_array(values: Vec<String>) -> (*mut *mut c_char, usize) {
+    let ptrs: Vec<*mut c_char> = values
+        .into_iter()
+        .filter_map(|v| CString::new(v).ok())
+        .map(|cs| cs.into_raw())
+        .collect();
     
-    (label.to_string(), score)
+    let count = ptrs.len();
+    if count == 0 {
+        return (ptr::null_mut(), 0);
+    }
+    (Box::into_raw(ptrs.into_boxed_slice()) as *mut *mut c_char, count)
 }
 
-/// Suggest groups from page contents
-fn suggest_groups_internal(contents: &[PageContentInput], similarity_threshold: f64) -> Vec<(String, String, Vec<String>, f32)> {
-    if contents.is_empty() {
-        return Vec::new();
+/// Free an array created by `strings_to_c_array`
+unsafe fn free_c_string_array(values: *mut *mut c_char, count: usize) {
+    if values.is_null() || count == 0 {
+        return;
     }
-    
-    let mut suggestions = Vec::new();
-    let mut assigned = vec![false; contents.len()];
-    
-    for i in 0..contents.len() {
-        if assigned[i] {
-            continue;
-        }
-        
-        let mut group_indices = vec![i];
-        assigned[i] = true;
-        
-        for j in (i + 1)..contents.len() {
-            if assigned[j] {
-                continue;
-            }
-            
-            let similarity = calculate_cosine_similarity(&contents[i].text, &contents[j].text);
-            
-            if similarity >= similarity_threshold {
-                group_indices.push(j);
-                assigned[j] = true;
-            }
-        }
-        
-        // Only create group if more than one page
-        if group_indices.len() > 1 {
-            // Find common words for group name
-            let texts: Vec<&str> = group_indices.iter()
-                .map(|&idx| contents[idx].text.as_str())
-                .collect();
-            
-            let common_words = find_common_words(&texts, 3);
-            
-            let group_name = if !common_words.is_empty() {
-                common_words.join(" & ")
-            } else {
-                format!("Group {}", suggestions.len() + 1)
-            };
-            
-            let description = format!("A collection of {} related pages", group_indices.len());
-            let page_ids: Vec<String> = group_indices.iter().map(|&idx| idx.to_string()).collect();
-            
-            suggestions.push((group_name, description, page_ids, similarity_threshold as f32));
+    let values = Box::from_raw(ptr::slice_from_raw_parts_mut(values, count));
+    for value in values.iter() {
+        if !value.is_null() {
+            let _ = CString::from_raw(*value);
         }
     }
-    
-    suggestions
 }
 
 /// Find common words across multiple texts
@@ -1725,21 +4106,89 @@ fn find_common_words(texts: &[&str], max_words: usize) -> Vec<String> {
         .collect()
 }
 
+/// Wikidata ids for linkable entities, `None` for the rest
+///
+/// Dates, amounts and websites are never linked.
+fn link_entities(linker: &EntityLinker, entities: &[entities::Entity], text: &str) -> Vec<Option<String>> {
+    if !linker.is_online() && linker.cached_count() == 0 {
+        return vec![None; entities.len()];
+    }
+    
+    let language = detect_language(text);
+    let context = tokenize(text);
+    entities
+        .iter()
+        .map(|entity| match entity.entity_type {
+            EntityType::Person | EntityType::Organization | EntityType::Location | EntityType::Product => linker
+                .link(&entity.name, &context, &language)
+                .map(|candidate| candidate.id),
+            EntityType::Date | EntityType::Money | EntityType::Website => None,
+        })
+        .collect()
+}
+
+/// Identity of each page keyword: its Wikidata id when linked, else the keyword
+///
+/// Pages sharing a word that refers to different things ("Rust" the language
+/// and "rust" the oxide) then no longer count as sharing a topic.
+fn link_keywords(linker: &EntityLinker, content: &PageContentInput) -> Vec<String> {
+    if !linker.is_online() && linker.cached_count() == 0 {
+        return content.keywords.clone();
+    }
+    
+    let text = format!("{} {}", content.title, content.text);
+    let language = detect_language(&text);
+    let context = tokenize(&text);
+    content
+        .keywords
+        .iter()
+        .map(|keyword| {
+            linker
+                .link(keyword, &context, &language)
+                .map_or_else(|| keyword.clone(), |candidate| candidate.id)
+        })
+        .collect()
+}
+
 /// Generate cross-content recommendations
-fn generate_cross_recommendations_internal(contents: &[PageContentInput], min_relevance: f32) -> Vec<(String, String, f32, String, Vec<String>)> {
+fn generate_cross_recommendations_internal(
+    contents: &[PageContentInput],
+    min_relevance: f32,
+    embedder: &dyn EmbeddingModel,
+    linker: &EntityLinker,
+    progress: &Progress,
+) -> Vec<(String, String, f32, String, Vec<String>)> {
     if contents.len() < 2 {
         return Vec::new();
     }
     
+    let embeddings = embed_contents(contents, embedder, &progress.stage("embed", contents.len()));
+    let linking = progress.stage("link", contents.len());
+    let keyword_ids: Vec<Vec<String>> = contents
+        .iter()
+        .map(|content| {
+            let ids = link_keywords(linker, content);
+            linking.step();
+            ids
+        })
+        .collect();
+    if progress.is_cancelled() {
+        return Vec::new();
+    }
+    
     let mut recommendations = Vec::new();
+    let comparing = progress.stage("compare", contents.len());
     
     for i in 0..contents.len() {
+        if !comparing.step() {
+            return Vec::new();
+        }
         for j in (i + 1)..contents.len() {
             // Calculate content similarity
-            let text_sim = calculate_cosine_similarity(&contents[i].text, &contents[j].text);
+            let text_sim = embedding::cosine_similarity(&embeddings[i], &embeddings[j]).max(0.0);
             
             // Calculate keyword overlap
-            let keyword_sim = calculate_jaccard_similarity(&contents[i].keywords, &contents[j].keywords);
+            let keyword_sim = calculate_jaccard_similarity(&keyword_ids[i], &keyword_ids[j]);
             
             // Combined relevance score
             let relevance = (0.6 * text_sim + 0.4 * keyword_sim) as f32;
@@ -1747,8 +4196,9 @@ fn generate_cross_recommendations_internal(contents: &[PageContentInput], min_re
             if relevance >= min_relevance {
                 // Find common keywords
                 let common_topics: Vec<String> = contents[i].keywords.iter()
-                    .filter(|kw| contents[j].keywords.contains(kw))
-                    .cloned()
+                    .zip(&keyword_ids[i])
+                    .filter(|(_, id)| keyword_ids[j].contains(id))
+                    .map(|(kw, _)| kw.clone())
                     .collect();
                 
                 // Generate reason
@@ -1874,144 +4324,1220 @@ mod tests {
         let text = "Machine learning is a subset of artificial intelligence. \
                     Deep learning uses neural networks with many layers. \
                     Natural language processing helps computers understand text.";
-        let key_points = extract_key_points(text, 2);
+        let key_points = extract_key_points(text, 2, &CorpusStats::new());
         assert!(key_points.len() <= 2);
     }
-
+
+    #[test]
+    fn test_extract_keywords_from_text() {
+        let text = "Rust programming language is fast and safe. \
+                    Rust provides memory safety without garbage collection. \
+                    Programming in Rust is enjoyable.";
+        let keywords = extract_keywords_from_text(text, 5, &CorpusStats::new());
+        assert!(keywords.contains(&"rust".to_string()));
+        assert!(keywords.contains(&"programming".to_string()));
+    }
+    
+    #[test]
+    fn test_keyword_variants_are_merged() {
+        let text = "Programs are written by programming. A program runs. \
+                    Programming languages help programming. Compilers compile code.";
+        let keywords = extract_keywords_from_text(text, 15, &CorpusStats::new());
+        assert_eq!(keywords[0], "programming");
+        assert!(!keywords.iter().any(|k| k == "program" || k == "programs"));
+        
+        let processor = ai_processor_create();
+        let mut content = page("Program guide", text, &["Programs", "Compiler"]);
+        content.keywords.push("compilers".to_string());
+        let merged = merge_page_keywords(&unsafe { processor_handle(processor) }.read(), &content);
+        let stems: Vec<String> = merged.iter().map(|k| stemming::phrase_key(k, "en")).collect();
+        let unique: std::collections::HashSet<&String> = stems.iter().collect();
+        assert_eq!(unique.len(), stems.len(), "duplicate variants in {:?}", merged);
+        ai_processor_destroy(processor);
+    }
+    
+    #[test]
+    fn test_extract_keywords_with_corpus_weighting() {
+        let mut corpus = CorpusStats::new();
+        for text in [
+            "Home page click here for cooking recipes",
+            "Home page click here for travel guides",
+            "Home page click here for gardening tips",
+        ] {
+            corpus.add_document(&stemmed_tokens(text).stems);
+        }
+        
+        let text = "Home page home page click click. Rust compiler.";
+        let keywords = extract_keywords_from_text(text, 2, &corpus);
+        assert_eq!(keywords, vec!["compiler".to_string(), "rust".to_string()]);
+    }
+    
+    #[test]
+    fn test_corpus_ffi_roundtrip() {
+        let processor = ai_processor_create();
+        let content = PageContentInput {
+            html: String::new(),
+            text: "Rust compiler internals".to_string(),
+            title: "Rust".to_string(),
+            description: None,
+            keywords: vec![],
+            images: vec![],
+            links: vec![],
+            url: None,
+        };
+        let content = CString::new(serde_json::to_string(&content).unwrap()).unwrap();
+        assert_eq!(ai_processor_add_to_corpus(processor, content.as_ptr()), 0);
+        assert_eq!(ai_processor_get_corpus_size(processor), 1);
+        
+        let path = std::env::temp_dir().join(format!("ffi_corpus_{}.json", std::process::id()));
+        let path_c = CString::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(ai_processor_save_corpus(processor, path_c.as_ptr()), 0);
+        
+        let other = ai_processor_create();
+        assert_eq!(ai_processor_load_corpus(other, path_c.as_ptr()), 0);
+        assert_eq!(ai_processor_get_corpus_size(other), 1);
+        std::fs::remove_file(&path).ok();
+        
+        ai_processor_destroy(processor);
+        ai_processor_destroy(other);
+    }
+
+    #[test]
+    fn test_classify_content_type_video() {
+        let content = PageContentInput {
+            html: String::new(),
+            text: "Watch this amazing video".to_string(),
+            title: "YouTube - Amazing Video".to_string(),
+            description: None,
+            keywords: vec![],
+            images: vec![],
+            links: vec![],
+            url: None,
+        };
+        assert_eq!(classify_content_type(&content, &DomainCategories::new()).content_type, CContentType::Video);
+    }
+
+    #[test]
+    fn test_classify_content_type_documentation() {
+        let content = PageContentInput {
+            html: String::new(),
+            text: "This function takes a parameter and returns a value".to_string(),
+            title: "API Documentation".to_string(),
+            description: None,
+            keywords: vec![],
+            images: vec![],
+            links: vec![],
+            url: None,
+        };
+        assert_eq!(classify_content_type(&content, &DomainCategories::new()).content_type, CContentType::Documentation);
+    }
+
+    #[test]
+    fn test_classify_content_type_shopping() {
+        let content = PageContentInput {
+            html: String::new(),
+            text: "Add to cart and checkout now".to_string(),
+            title: "Buy Now - Great Deals".to_string(),
+            description: None,
+            keywords: vec![],
+            images: vec![],
+            links: vec![],
+            url: None,
+        };
+        assert_eq!(classify_content_type(&content, &DomainCategories::new()).content_type, CContentType::Shopping);
+    }
+
+    #[test]
+    fn test_classify_content_type_news() {
+        let content = PageContentInput {
+            html: String::new(),
+            text: "The reporter covered the story".to_string(),
+            title: "Breaking News Today".to_string(),
+            description: None,
+            keywords: vec![],
+            images: vec![],
+            links: vec![],
+            url: None,
+        };
+        assert_eq!(classify_content_type(&content, &DomainCategories::new()).content_type, CContentType::News);
+    }
+
+    #[test]
+    fn test_detect_language_english() {
+        let text = "This is a sample English text for testing language detection.";
+        assert_eq!(detect_language(text), "en");
+    }
+
+    #[test]
+    fn test_detect_language_chinese() {
+        let text = "这是一段中文文本用于测试语言检测功能";
+        assert_eq!(detect_language(text), "zh");
+    }
+    
+    #[test]
+    fn test_detect_language_japanese_and_korean() {
+        assert_eq!(detect_language("私は日本語を勉強しています"), "ja");
+        assert_eq!(detect_language("한국어 텍스트입니다"), "ko");
+    }
+    
+    #[test]
+    fn test_extract_key_points_long_cjk_sentence() {
+        let sentence = "机器学习".repeat(60);
+        let text = format!("{}。自然语言处理是人工智能的重要方向。", sentence);
+        let key_points = extract_key_points(&text, 2, &CorpusStats::new());
+        assert_eq!(key_points.len(), 2);
+        assert!(key_points.iter().any(|kp| kp.ends_with("...") && kp.chars().count() == 150));
+    }
+
+    #[test]
+    fn test_estimate_reading_time_short() {
+        let text = "Short text.";
+        assert_eq!(estimate_reading_time(text), 1); // Minimum 1 minute
+    }
+
+    #[test]
+    fn test_estimate_reading_time_long() {
+        // 400 words should be about 2 minutes
+        let words: Vec<&str> = (0..400).map(|_| "word").collect();
+        let text = words.join(" ");
+        assert_eq!(estimate_reading_time(&text), 2);
+    }
+
+    #[test]
+    fn test_calculate_cosine_similarity_identical() {
+        let text = "The quick brown fox jumps over the lazy dog";
+        let similarity = calculate_cosine_similarity(text, text);
+        assert!((similarity - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_calculate_cosine_similarity_different() {
+        let text_a = "Machine learning artificial intelligence";
+        let text_b = "Cooking recipes food preparation";
+        let similarity = calculate_cosine_similarity(text_a, text_b);
+        assert!(similarity < 0.5);
+    }
+
+    #[test]
+    fn test_calculate_cosine_similarity_similar() {
+        let text_a = "Machine learning is a subset of artificial intelligence";
+        let text_b = "Artificial intelligence includes machine learning techniques";
+        let similarity = calculate_cosine_similarity(text_a, text_b);
+        assert!(similarity > 0.3);
+    }
+
+    #[test]
+    fn test_embedding_similarity_range() {
+        let embedder = HashingEmbedder::default();
+        let rust = page("Rust", "rust ownership borrowing", &[]);
+        let same = embedding_similarity(&embedder, &rust, &rust);
+        let different = embedding_similarity(&embedder, &rust, &page("Garden", "gardening tomatoes soil", &[]));
+        assert!((same - 1.0).abs() < 1e-6);
+        assert!((0.0..same).contains(&different));
+    }
+
+    #[test]
+    fn test_get_category_info() {
+        let (primary, secondary) = get_category_info(CContentType::Article);
+        assert_eq!(primary, "Articles");
+        assert!(secondary.contains(&"Reading".to_string()));
+    }
+
+    #[test]
+    fn test_ai_processor_create_destroy() {
+        let processor = ai_processor_create();
+        assert!(!processor.is_null());
+        ai_processor_destroy(processor);
+    }
+
+    #[test]
+    fn test_ai_processor_set_mode() {
+        let processor = ai_processor_create();
+        let result = ai_processor_set_mode(processor, CProcessingMode::Enhanced);
+        assert_eq!(result, 0);
+        ai_processor_destroy(processor);
+    }
+
+    extern "C" fn collect_chunk(chunk: *const c_char, user_data: *mut c_void) {
+        let chunks = unsafe { &mut *(user_data as *mut Vec<String>) };
+        chunks.push(unsafe { CStr::from_ptr(chunk) }.to_string_lossy().into_owned());
+    }
+    
+    #[test]
+    fn test_streaming_summaries_keep_their_own_chunks() {
+        let processor = ai_processor_create() as usize;
+        let texts = [
+            "Rust is a systems programming language. It guarantees memory safety.",
+            "Sourdough bread rises slowly. The starter needs daily feeding with flour.",
+        ];
+        
+        let threads: Vec<_> = texts
+            .iter()
+            .map(|text| {
+                let content = CString::new(serde_json::to_string(&page("Page", text, &[])).unwrap()).unwrap();
+                std::thread::spawn(move || {
+                    let processor = processor as *mut CAIProcessor;
+                    for _ in 0..10 {
+                        let mut chunks: Vec<String> = Vec::new();
+                        let summary = ai_processor_generate_summary_streaming(
+                            processor,
+                            content.as_ptr(),
+                            Some(collect_chunk),
+                            &mut chunks as *mut Vec<String> as *mut c_void,
+                        );
+                        let summary_text = unsafe { CStr::from_ptr(summary.summary_text) }.to_string_lossy().into_owned();
+                        assert!(!chunks.is_empty());
+                        assert_eq!(chunks.concat(), summary_text);
+                        ai_processor_free_summary(summary);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        
+        // Without a callback it is a plain summary
+        let processor = processor as *mut CAIProcessor;
+        let content = CString::new(serde_json::to_string(&page("Page", texts[0], &[])).unwrap()).unwrap();
+        let summary = ai_processor_generate_summary_streaming(processor, content.as_ptr(), None, ptr::null_mut());
+        assert!(!summary.summary_text.is_null());
+        ai_processor_free_summary(summary);
+        ai_processor_destroy(processor);
+    }
+    
+    /// Records reports and cancels once `cancel_at` percent is reached
+    struct ProgressLog {
+        reports: std::sync::Mutex<Vec<(String, f32)>>,
+        cancel_at: f32,
+    }
+    
+    #[test]
+    fn test_group_hierarchy_has_stable_ids() {
+        let processor = ai_processor_create();
+        let mut pages = vec![
+            page("Ownership", "Rust ownership and borrowing rules for memory safety.", &["rust"]),
+            page("Borrowing", "Rust borrowing rules and ownership for memory safety.", &["rust"]),
+            page("Sourdough", "Bake sourdough bread with a starter, flour and water.", &["baking"]),
+            page("Starter", "Feed a sourdough starter with flour and water before you bake bread.", &["baking"]),
+        ];
+        for (i, page) in pages.iter_mut().enumerate() {
+            page.url = Some(format!("https://example.com/{}", i));
+        }
+        let hierarchy = |pages: &[PageContentInput], threshold: f64| -> Vec<serde_json::Value> {
+            let contents = CString::new(serde_json::to_string(pages).unwrap()).unwrap();
+            let json = ai_processor_suggest_group_hierarchy(processor, contents.as_ptr(), threshold);
+            assert!(!json.is_null());
+            let groups = serde_json::from_str(unsafe { CStr::from_ptr(json) }.to_str().unwrap()).unwrap();
+            ai_processor_free_string(json);
+            groups
+        };
+        
+        let groups = hierarchy(&pages, 0.5);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0]["page_ids"], serde_json::json!(["0", "1"]));
+        assert_eq!(groups[1]["page_ids"], serde_json::json!(["2", "3"]));
+        
+        // Reordering pages keeps the ids of the groups
+        pages.reverse();
+        let reordered = hierarchy(&pages, 0.5);
+        let ids = |groups: &[serde_json::Value]| {
+            let mut ids: Vec<String> = groups.iter().map(|g| g["id"].as_str().unwrap().to_string()).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(&groups), ids(&reordered));
+        
+        // A loose cut nests the tight groups as children
+        let loose = hierarchy(&pages, -1.0);
+        assert_eq!(loose.len(), 1);
+        assert_eq!(loose[0]["page_ids"].as_array().unwrap().len(), 4);
+        assert_eq!(ids(loose[0]["children"].as_array().unwrap()), ids(&groups));
+        
+        let contents = CString::new(serde_json::to_string(&pages).unwrap()).unwrap();
+        let mut suggestions: *mut CGroupSuggestion = ptr::null_mut();
+        let mut count = 0;
+        assert_eq!(ai_processor_suggest_groups(processor, contents.as_ptr(), 0.5, &mut suggestions, &mut count), 0);
+        let mut suggestion_ids: Vec<String> = unsafe { std::slice::from_raw_parts(suggestions, count) }
+            .iter()
+            .map(|s| unsafe { CStr::from_ptr(s.group_id) }.to_str().unwrap().to_string())
+            .collect();
+        suggestion_ids.sort();
+        assert_eq!(ids(&reordered), suggestion_ids);
+        ai_processor_free_group_suggestions(suggestions, count);
+        ai_processor_destroy(processor);
+    }
+    
+    #[test]
+    fn test_trends_report_emerging_keywords() {
+        let processor = ai_processor_create();
+        let visit = |day: u32, title: &str, text: &str| {
+            let mut visit = serde_json::to_value(page(title, text, &[])).unwrap();
+            visit["visited_at"] = format!("2026-03-{:02}T10:00:00+01:00", day).into();
+            visit
+        };
+        let visits = serde_json::json!([
+            visit(2, "Sourdough", "Sourdough bread needs a lively starter and flour."),
+            visit(3, "Starter", "Feed the sourdough starter with flour every day."),
+            visit(10, "Kubernetes", "Kubernetes schedules containers across cluster nodes."),
+            visit(11, "Pods", "Kubernetes pods group containers that share a network."),
+            visit(12, "Deployments", "Kubernetes deployments roll out containers gradually."),
+        ]);
+        let visits = CString::new(visits.to_string()).unwrap();
+        
+        let json = ai_processor_analyze_trends(processor, visits.as_ptr(), CTrendGranularity::Week, 1);
+        assert!(!json.is_null());
+        let analysis: serde_json::Value = serde_json::from_str(unsafe { CStr::from_ptr(json) }.to_str().unwrap()).unwrap();
+        ai_processor_free_string(json);
+        
+        let keywords = &analysis["keywords"];
+        assert_eq!(keywords["periods"].as_array().unwrap().len(), 2);
+        assert_eq!(keywords["periods"][0]["start"], "2026-03-02");
+        let terms = |trends: &serde_json::Value| -> Vec<String> {
+            trends.as_array().unwrap().iter().map(|t| t["term"].as_str().unwrap().to_string()).collect()
+        };
+        assert!(terms(&keywords["emerging"]).contains(&"kubernetes".to_string()), "{}", keywords);
+        assert!(terms(&keywords["fading"]).contains(&"sourdough".to_string()), "{}", keywords);
+        assert!(analysis["topics"]["periods"].is_array());
+        
+        let invalid = CString::new("[{\"title\": \"no time\"}]").unwrap();
+        assert!(ai_processor_analyze_trends(processor, invalid.as_ptr(), CTrendGranularity::Month, 1).is_null());
+        assert_eq!(errors::ai_processor_last_error().code, CErrorCode::InvalidJson as c_int);
+        ai_processor_destroy(processor);
+    }
+    
+    extern "C" fn log_progress(stage: *const c_char, percent: c_float, cancel: *mut c_int, user_data: *mut c_void) {
+        let log = unsafe { &*(user_data as *const ProgressLog) };
+        let stage = unsafe { CStr::from_ptr(stage) }.to_string_lossy().into_owned();
+        log.reports.lock().unwrap().push((stage, percent));
+        if percent >= log.cancel_at {
+            unsafe { *cancel = 1 };
+        }
+    }
+    
+    #[test]
+    fn test_progress_callback_reports_and_cancels() {
+        let processor = ai_processor_create();
+        let pages: Vec<PageContentInput> = (0..10)
+            .map(|i| page(&format!("Page {}", i), "Rust programming with the borrow checker.", &["rust"]))
+            .collect();
+        let contents = CString::new(serde_json::to_string(&pages).unwrap()).unwrap();
+        let mut suggestions: *mut CGroupSuggestion = ptr::null_mut();
+        let mut count = 0;
+        
+        let log = ProgressLog { reports: std::sync::Mutex::new(Vec::new()), cancel_at: 101.0 };
+        let user_data = &log as *const ProgressLog as *mut c_void;
+        assert_eq!(ai_processor_set_progress_callback(processor, Some(log_progress), user_data), 0);
+        assert_eq!(ai_processor_suggest_groups(processor, contents.as_ptr(), 0.5, &mut suggestions, &mut count), 0);
+        ai_processor_free_group_suggestions(suggestions, count);
+        {
+            let reports = log.reports.lock().unwrap();
+            assert_eq!(reports.first(), Some(&("embed".to_string(), 0.0)));
+            assert_eq!(reports.last(), Some(&("group".to_string(), 100.0)));
+            assert!(reports.windows(2).all(|w| w[0].0 != w[1].0 || w[0].1 <= w[1].1));
+        }
+        
+        let log = ProgressLog { reports: std::sync::Mutex::new(Vec::new()), cancel_at: 50.0 };
+        let user_data = &log as *const ProgressLog as *mut c_void;
+        assert_eq!(ai_processor_set_progress_callback(processor, Some(log_progress), user_data), 0);
+        let mut recommendations: *mut CCrossRecommendation = ptr::null_mut();
+        assert_eq!(
+            ai_processor_generate_cross_recommendations(processor, contents.as_ptr(), 0.0, &mut recommendations, &mut count),
+            -1
+        );
+        assert_eq!(errors::ai_processor_last_error().code, CErrorCode::Cancelled as c_int);
+        assert!(log.reports.lock().unwrap().iter().all(|(stage, _)| stage == "embed"));
+        
+        assert_eq!(ai_processor_set_progress_callback(processor, None, ptr::null_mut()), 0);
+        ai_processor_destroy(processor);
+    }
+    
+    #[test]
+    fn test_walled_pages_are_not_summarized() {
+        let processor = ai_processor_create();
+        let mut login = page("Sign in", "Sign in to continue. Forgot password? Create account.", &[]);
+        login.html = "<form><input type=\"password\"></form>".to_string();
+        let content = CString::new(serde_json::to_string(&login).unwrap()).unwrap();
+        
+        assert_eq!(ai_processor_detect_access_wall(processor, content.as_ptr()), CAccessWall::Login as c_int);
+        let summary = ai_processor_generate_summary(processor, content.as_ptr());
+        assert_eq!(summary.access_wall, CAccessWall::Login as c_int);
+        assert_eq!(unsafe { CStr::from_ptr(summary.summary_text) }.to_str().unwrap(), "");
+        assert_eq!(summary.key_points_count, 0);
+        ai_processor_free_summary(summary);
+        
+        let article = page("Rust", "Rust is a systems programming language. It guarantees memory safety.", &[]);
+        let content = CString::new(serde_json::to_string(&article).unwrap()).unwrap();
+        let summary = ai_processor_generate_summary(processor, content.as_ptr());
+        assert_eq!(summary.access_wall, CAccessWall::None as c_int);
+        ai_processor_free_summary(summary);
+        
+        ai_processor_destroy(processor);
+    }
+    
+    #[test]
+    fn test_summary_reports_quality() {
+        let processor = ai_processor_create();
+        let text = "Ownership is a set of rules that govern how a Rust program manages memory. ".repeat(30);
+        let reference = CString::new(serde_json::to_string(&page("Understanding Ownership", &text, &[])).unwrap()).unwrap();
+        let listicle = CString::new(
+            serde_json::to_string(&page("You Won't Believe These 7 SHOCKING Rust TRICKS!!", "Number one will surprise you.", &[]))
+                .unwrap(),
+        )
+        .unwrap();
+        
+        let reference = ai_processor_generate_summary(processor, reference.as_ptr());
+        let listicle = ai_processor_generate_summary(processor, listicle.as_ptr());
+        assert!(reference.quality_score > listicle.quality_score);
+        assert_eq!(reference.clickbait_score, 0.0);
+        assert!(listicle.clickbait_score > 0.5);
+        ai_processor_free_summary(reference);
+        ai_processor_free_summary(listicle);
+        
+        ai_processor_destroy(processor);
+    }
+    
+    #[test]
+    fn test_summary_includes_media() {
+        let processor = ai_processor_create();
+        let mut content = page("Trip report", "We hiked to the lake and filmed the sunrise.", &[]);
+        content.url = Some("https://example.com/trips/lake.html".to_string());
+        content.html = r#"<article><img src="lake.jpg" width="800" height="600">
+            <iframe src="https://player.vimeo.com/video/1"></iframe></article>"#.to_string();
+        let content = CString::new(serde_json::to_string(&content).unwrap()).unwrap();
+        
+        let summary = ai_processor_generate_summary(processor, content.as_ptr());
+        assert_eq!(unsafe { CStr::from_ptr(summary.image_url) }.to_str().unwrap(), "https://example.com/trips/lake.jpg");
+        assert_eq!(summary.image_count, 1);
+        assert_eq!(summary.video_count, 1);
+        ai_processor_free_summary(summary);
+        
+        let plain = CString::new(serde_json::to_string(&page("Notes", "Plain text only.", &[])).unwrap()).unwrap();
+        let summary = ai_processor_generate_summary(processor, plain.as_ptr());
+        assert!(summary.image_url.is_null());
+        ai_processor_free_summary(summary);
+        
+        ai_processor_destroy(processor);
+    }
+    
+    #[test]
+    fn test_code_examples_become_keywords_and_key_points() {
+        let processor = ai_processor_create();
+        let mut content = page(
+            "tokio::select! - Rust documentation",
+            "Waits on multiple concurrent branches, returning when the first branch completes. \
+             The API reference lists every parameter of this macro.",
+            &[],
+        );
+        content.html = r#"<pre><code class="language-rust">tokio::select! {
+    v = rx1.recv() =&gt; {}
+    _ = tokio::time::sleep(timeout) =&gt; {}
+}</code></pre>"#.to_string();
+        let content = CString::new(serde_json::to_string(&content).unwrap()).unwrap();
+        
+        let mut keywords: *mut *mut c_char = ptr::null_mut();
+        let mut count = 0;
+        assert_eq!(ai_processor_extract_keywords(processor, content.as_ptr(), &mut keywords, &mut count), 0);
+        let extracted: Vec<String> = unsafe { std::slice::from_raw_parts(keywords, count) }
+            .iter()
+            .map(|k| unsafe { CStr::from_ptr(*k) }.to_string_lossy().into_owned())
+            .collect();
+        ai_processor_free_keywords(keywords, count);
+        assert!(extracted.contains(&"tokio::select!".to_string()), "{:?}", extracted);
+        assert!(extracted.contains(&"Rust".to_string()) || extracted.contains(&"rust".to_string()));
+        
+        let summary = ai_processor_generate_summary(processor, content.as_ptr());
+        let points: Vec<String> = unsafe { std::slice::from_raw_parts(summary.key_points, summary.key_points_count) }
+            .iter()
+            .map(|p| unsafe { CStr::from_ptr(*p) }.to_string_lossy().into_owned())
+            .collect();
+        ai_processor_free_summary(summary);
+        assert!(points.iter().any(|p| p.starts_with("Rust code example using tokio::select!")), "{:?}", points);
+        
+        ai_processor_destroy(processor);
+    }
+    
     #[test]
-    fn test_extract_keywords_from_text() {
-        let text = "Rust programming language is fast and safe. \
-                    Rust provides memory safety without garbage collection. \
-                    Programming in Rust is enjoyable.";
-        let keywords = extract_keywords_from_text(text, 5);
-        assert!(keywords.contains(&"rust".to_string()));
-        assert!(keywords.contains(&"programming".to_string()));
+    fn test_process_batch_preserves_order() {
+        let processor = ai_processor_create();
+        
+        let contents: Vec<PageContentInput> = ["Rust", "Python", "Go"]
+            .iter()
+            .map(|name| PageContentInput {
+                html: String::new(),
+                text: format!("{} is a programming language. {} programs are popular. Developers enjoy {}.", name, name, name),
+                title: format!("{} Guide", name),
+                description: None,
+                keywords: vec![],
+                images: vec![],
+                links: vec![],
+                url: None,
+            })
+            .collect();
+        let contents_c = CString::new(serde_json::to_string(&contents).unwrap()).unwrap();
+        
+        let mut results_out: *mut CPageAnalysis = ptr::null_mut();
+        let mut count: usize = 0;
+        let result = ai_processor_process_batch(processor, contents_c.as_ptr(), &mut results_out, &mut count);
+        assert_eq!(result, 0);
+        assert_eq!(count, 3);
+        
+        let results = unsafe { std::slice::from_raw_parts(results_out, count) };
+        for (analysis, name) in results.iter().zip(["rust", "python", "go"]) {
+            let summary = unsafe { CStr::from_ptr(analysis.summary.summary_text) }.to_string_lossy().to_lowercase();
+            assert!(summary.contains(name));
+            assert!(analysis.keywords_count > 0);
+        }
+        
+        ai_processor_free_batch_results(results_out, count);
+        ai_processor_destroy(processor);
     }
-
+    
     #[test]
-    fn test_classify_content_type_video() {
+    fn test_background_job_lifecycle() {
+        let processor = ai_processor_create();
+        
         let content = PageContentInput {
             html: String::new(),
-            text: "Watch this amazing video".to_string(),
-            title: "YouTube - Amazing Video".to_string(),
+            text: "Rust is a systems programming language. It guarantees memory safety.".to_string(),
+            title: "Rust".to_string(),
             description: None,
             keywords: vec![],
             images: vec![],
             links: vec![],
+            url: None,
         };
-        assert_eq!(classify_content_type(&content), CContentType::Video);
+        let content_c = CString::new(serde_json::to_string(&content).unwrap()).unwrap();
+        
+        let job_id = ai_processor_submit_job(processor, content_c.as_ptr());
+        assert_ne!(job_id, 0);
+        
+        let mut status = ai_processor_get_job_status(processor, job_id);
+        for _ in 0..500 {
+            if status == CJobStatus::Completed {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            status = ai_processor_get_job_status(processor, job_id);
+        }
+        assert_eq!(status, CJobStatus::Completed);
+        
+        let mut analysis = std::mem::MaybeUninit::<CPageAnalysis>::uninit();
+        assert_eq!(ai_processor_take_job_result(processor, job_id, analysis.as_mut_ptr()), 0);
+        let analysis = unsafe { analysis.assume_init() };
+        assert!(!analysis.summary.summary_text.is_null());
+        ai_processor_free_page_analysis(analysis);
+        
+        assert_eq!(ai_processor_get_job_status(processor, job_id), CJobStatus::NotFound);
+        assert_eq!(ai_processor_cancel_job(processor, job_id), -1);
+        let invalid = CString::new("not json").unwrap();
+        assert_eq!(ai_processor_submit_job(processor, invalid.as_ptr()), 0);
+        
+        ai_processor_destroy(processor);
     }
-
+    
     #[test]
-    fn test_classify_content_type_documentation() {
+    fn test_result_cache_hits_and_roundtrip() {
+        let processor = ai_processor_create();
+        
         let content = PageContentInput {
             html: String::new(),
-            text: "This function takes a parameter and returns a value".to_string(),
-            title: "API Documentation".to_string(),
+            text: "Rust is a systems programming language. It guarantees memory safety.".to_string(),
+            title: "Rust".to_string(),
             description: None,
             keywords: vec![],
             images: vec![],
             links: vec![],
+            url: None,
+        };
+        let content_c = CString::new(serde_json::to_string(&content).unwrap()).unwrap();
+        
+        let first = ai_processor_generate_summary(processor, content_c.as_ptr());
+        let second = ai_processor_generate_summary(processor, content_c.as_ptr());
+        let (first_text, second_text) = unsafe {
+            (CStr::from_ptr(first.summary_text).to_owned(), CStr::from_ptr(second.summary_text).to_owned())
         };
-        assert_eq!(classify_content_type(&content), CContentType::Documentation);
+        assert_eq!(first_text, second_text);
+        ai_processor_free_summary(first);
+        ai_processor_free_summary(second);
+        
+        let (mut hits, mut misses, mut entries) = (0u64, 0u64, 0usize);
+        assert_eq!(ai_processor_get_cache_stats(processor, &mut hits, &mut misses, &mut entries), 0);
+        assert_eq!((hits, misses, entries), (1, 1, 1));
+        
+        let exported = ai_processor_export_cache(processor);
+        assert!(!exported.is_null());
+        
+        let other = ai_processor_create();
+        assert_eq!(ai_processor_import_cache(other, exported), 0);
+        let restored = ai_processor_generate_summary(other, content_c.as_ptr());
+        assert_eq!(ai_processor_get_cache_stats(other, &mut hits, &mut misses, &mut entries), 0);
+        assert_eq!((hits, misses), (1, 0));
+        
+        ai_processor_free_summary(restored);
+        ai_processor_free_string(exported);
+        ai_processor_destroy(other);
+        ai_processor_destroy(processor);
     }
-
+    
     #[test]
-    fn test_classify_content_type_shopping() {
+    fn test_extract_keywords_with_rake() {
+        let processor = ai_processor_create();
+        assert_eq!(ai_processor_set_keyword_algorithm(processor, CKeywordAlgorithm::Rake), 0);
+        
         let content = PageContentInput {
             html: String::new(),
-            text: "Add to cart and checkout now".to_string(),
-            title: "Buy Now - Great Deals".to_string(),
+            text: "Memory safety matters. Rust has memory safety. The borrow checker is strict.".to_string(),
+            title: String::new(),
             description: None,
             keywords: vec![],
             images: vec![],
             links: vec![],
+            url: None,
+        };
+        let content_c = CString::new(serde_json::to_string(&content).unwrap()).unwrap();
+        
+        let mut keywords_out: *mut *mut c_char = ptr::null_mut();
+        let mut count: usize = 0;
+        let result = ai_processor_extract_keywords(processor, content_c.as_ptr(), &mut keywords_out, &mut count);
+        assert_eq!(result, 0);
+        
+        let keywords: Vec<String> = unsafe {
+            std::slice::from_raw_parts(keywords_out, count)
+                .iter()
+                .map(|k| CStr::from_ptr(*k).to_string_lossy().into_owned())
+                .collect()
         };
-        assert_eq!(classify_content_type(&content), CContentType::Shopping);
+        assert!(keywords.contains(&"memory safety".to_string()));
+        assert!(keywords.contains(&"borrow checker".to_string()));
+        
+        ai_processor_free_keywords(keywords_out, count);
+        ai_processor_destroy(processor);
     }
 
     #[test]
-    fn test_classify_content_type_news() {
-        let content = PageContentInput {
+    fn test_ai_processor_load_local_model_missing_file() {
+        let processor = ai_processor_create();
+        let model = CString::new("/nonexistent/model.gguf").unwrap();
+        let tokenizer = CString::new("/nonexistent/tokenizer.json").unwrap();
+        
+        let result = ai_processor_load_local_model(processor, model.as_ptr(), tokenizer.as_ptr());
+        assert_eq!(result, -1);
+        assert_eq!(ai_processor_unload_local_model(processor), 0);
+        
+        ai_processor_destroy(processor);
+    }
+
+    #[test]
+    fn test_ai_processor_load_embedding_model_missing_file() {
+        let processor = ai_processor_create();
+        let model = CString::new("/nonexistent/model.onnx").unwrap();
+        let tokenizer = CString::new("/nonexistent/tokenizer.json").unwrap();
+        
+        let result = ai_processor_load_embedding_model(processor, model.as_ptr(), tokenizer.as_ptr());
+        assert_eq!(result, -1);
+        
+        ai_processor_destroy(processor);
+    }
+
+    fn page(title: &str, text: &str, keywords: &[&str]) -> PageContentInput {
+        PageContentInput {
             html: String::new(),
-            text: "The reporter covered the story".to_string(),
-            title: "Breaking News Today".to_string(),
+            text: text.to_string(),
+            title: title.to_string(),
             description: None,
-            keywords: vec![],
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
             images: vec![],
             links: vec![],
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_linked_keywords_separate_homonyms() {
+        let path = std::env::temp_dir().join(format!("entity_links_ffi_{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"candidates":{"en|rust":[
+                {"id":"Q137056","label":"rust","description":"iron oxide formed by corrosion"},
+                {"id":"Q575650","label":"Rust","description":"general-purpose programming language"}
+            ]}}"#,
+        )
+        .unwrap();
+        
+        let processor = ai_processor_create();
+        let path_c = CString::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(ai_processor_load_entity_links(processor, path_c.as_ptr()), 0);
+        std::fs::remove_file(&path).ok();
+        
+        let pages = vec![
+            page("Learning Rust", "The Rust programming language has a strict compiler.", &["Rust", "compiler"]),
+            page("Rust on cars", "Rust is iron oxide caused by corrosion of steel.", &["Rust", "steel"]),
+            page("Rust ownership", "Ownership makes the programming language safe.", &["Rust", "ownership"]),
+        ];
+        let state = unsafe { processor_handle(processor) }.read();
+        let recommendations = generate_cross_recommendations_internal(
+            &pages,
+            0.0,
+            state.embedder.as_ref(),
+            &state.entity_linker,
+            &Progress::silent(),
+        );
+        drop(state);
+        
+        let common = |a: &str, b: &str| {
+            recommendations
+                .iter()
+                .find(|r| r.0 == a && r.1 == b)
+                .map(|r| r.4.clone())
+                .unwrap()
         };
-        assert_eq!(classify_content_type(&content), CContentType::News);
+        assert!(common("0", "1").is_empty());
+        assert_eq!(common("0", "2"), vec!["Rust".to_string()]);
+        
+        ai_processor_destroy(processor);
     }
 
+    #[cfg(not(feature = "wikidata"))]
     #[test]
-    fn test_detect_language_english() {
-        let text = "This is a sample English text for testing language detection.";
-        assert_eq!(detect_language(text), "en");
+    fn test_online_entity_linking_requires_feature() {
+        let processor = ai_processor_create();
+        assert_eq!(ai_processor_set_entity_linking(processor, 1), -1);
+        assert_eq!(ai_processor_set_entity_linking(processor, 0), 0);
+        ai_processor_destroy(processor);
     }
 
     #[test]
-    fn test_detect_language_chinese() {
-        let text = "这是一段中文文本用于测试语言检测功能";
-        assert_eq!(detect_language(text), "zh");
+    fn test_ai_processor_sentence_sentiment() {
+        let processor = ai_processor_create();
+        let text = CString::new("The camera is not good. The battery is excellent!").unwrap();
+        
+        let mut sentences: *mut CSentenceSentiment = ptr::null_mut();
+        let mut count: usize = 0;
+        let result = ai_processor_analyze_sentence_sentiment(processor, text.as_ptr(), &mut sentences, &mut count);
+        assert_eq!(result, 0);
+        assert_eq!(count, 2);
+        
+        let slice = unsafe { std::slice::from_raw_parts(sentences, count) };
+        assert_eq!(slice[0].label, CSentimentLabel::Negative);
+        assert_eq!(slice[1].label, CSentimentLabel::Positive);
+        assert_eq!(slice[1].offset, 24);
+        assert_eq!(unsafe { CStr::from_ptr(slice[1].text) }.to_str().unwrap(), "The battery is excellent!");
+        ai_processor_free_sentence_sentiments(sentences, count);
+        
+        let mut label: *mut c_char = ptr::null_mut();
+        let mut score: c_float = 0.0;
+        let negated = CString::new("This is not good at all.").unwrap();
+        assert_eq!(ai_processor_analyze_sentiment(processor, negated.as_ptr(), &mut label, &mut score), 0);
+        assert_eq!(unsafe { CStr::from_ptr(label) }.to_str().unwrap(), "negative");
+        assert!(score < 0.0);
+        ai_processor_free_string(label);
+        
+        ai_processor_destroy(processor);
     }
 
     #[test]
-    fn test_estimate_reading_time_short() {
-        let text = "Short text.";
-        assert_eq!(estimate_reading_time(text), 1); // Minimum 1 minute
+    fn test_custom_categories_learn_from_feedback() {
+        let processor = ai_processor_create();
+        let recipes = serde_json::to_string(&vec![
+            page("Tomato pasta", "Boil the pasta, simmer tomato sauce with garlic and basil.", &[]),
+            page("Garlic bread", "Bake bread with garlic butter and basil until golden.", &[]),
+        ])
+        .unwrap();
+        let name = CString::new("Recipes").unwrap();
+        let examples = CString::new(recipes).unwrap();
+        assert_eq!(ai_processor_define_category(processor, name.as_ptr(), examples.as_ptr()), 0);
+        
+        let classify = |content: &PageContentInput| {
+            let json = CString::new(serde_json::to_string(content).unwrap()).unwrap();
+            let info = ai_processor_classify_content(processor, json.as_ptr());
+            let primary = unsafe { CStr::from_ptr(info.primary_category) }.to_str().unwrap().to_string();
+            ai_processor_free_category(info);
+            primary
+        };
+        
+        let soup = page("Tomato soup", "Simmer tomato with garlic and basil, then blend the sauce.", &[]);
+        assert_eq!(classify(&soup), "Recipes");
+        
+        // Unrelated pages fall back to the built-in types
+        let docs = page("API documentation", "The function takes a parameter and returns a value.", &[]);
+        assert_eq!(classify(&docs), "Documentation");
+        
+        // Accepting a new category for a page defines it
+        let travel = page("Trip to Lisbon", "Flights, hotels and trams around Lisbon old town.", &[]);
+        let travel_json = CString::new(serde_json::to_string(&travel).unwrap()).unwrap();
+        let travel_name = CString::new("Travel").unwrap();
+        assert_eq!(ai_processor_record_category_feedback(processor, travel_json.as_ptr(), travel_name.as_ptr(), 1), 0);
+        assert_eq!(classify(&page("Lisbon hotels", "Hotels near the trams of Lisbon old town.", &[])), "Travel");
+        
+        let path = std::env::temp_dir().join(format!("categories_ffi_{}.json", std::process::id()));
+        let path_c = CString::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(ai_processor_save_categories(processor, path_c.as_ptr()), 0);
+        assert_eq!(ai_processor_remove_category(processor, name.as_ptr()), 0);
+        assert_eq!(ai_processor_remove_category(processor, name.as_ptr()), -1);
+        assert_eq!(ai_processor_load_categories(processor, path_c.as_ptr()), 0);
+        assert_eq!(classify(&soup), "Recipes");
+        std::fs::remove_file(&path).ok();
+        
+        ai_processor_destroy(processor);
     }
 
     #[test]
-    fn test_estimate_reading_time_long() {
-        // 400 words should be about 2 minutes
-        let words: Vec<&str> = (0..400).map(|_| "word").collect();
-        let text = words.join(" ");
-        assert_eq!(estimate_reading_time(&text), 2);
+    fn test_concurrent_calls_from_many_threads() {
+        let processor = ai_processor_create() as usize;
+        let content = serde_json::to_string(&page(
+            "Rust threads",
+            "Rust makes concurrent programming safe. Threads share data through locks.",
+            &["rust"],
+        ))
+        .unwrap();
+        
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let content = CString::new(content.clone()).unwrap();
+                std::thread::spawn(move || {
+                    let processor = processor as *mut CAIProcessor;
+                    for _ in 0..10 {
+                        if i % 2 == 0 {
+                            let mode = if i % 4 == 0 { CProcessingMode::Basic } else { CProcessingMode::Auto };
+                            assert_eq!(ai_processor_set_mode(processor, mode), 0);
+                            let pipeline = CString::new(r#"{"keywords": {"max_keywords": 5}}"#).unwrap();
+                            assert_eq!(ai_processor_set_pipeline(processor, pipeline.as_ptr()), 0);
+                        } else {
+                            let summary = ai_processor_generate_summary(processor, content.as_ptr());
+                            assert!(!summary.summary_text.is_null());
+                            ai_processor_free_summary(summary);
+                            assert!(ai_processor_submit_job(processor, content.as_ptr()) > 0);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        
+        ai_processor_destroy(processor as *mut CAIProcessor);
+    }
+
+    fn last_error() -> (c_int, String) {
+        let error = errors::ai_processor_last_error();
+        let message = unsafe { CStr::from_ptr(error.message) }.to_str().unwrap().to_string();
+        ai_processor_free_string(error.message);
+        (error.code, message)
     }
 
     #[test]
-    fn test_calculate_cosine_similarity_identical() {
-        let text = "The quick brown fox jumps over the lazy dog";
-        let similarity = calculate_cosine_similarity(text, text);
-        assert!((similarity - 1.0).abs() < 0.01);
+    fn test_failures_report_last_error() {
+        let processor = ai_processor_create();
+        errors::ai_processor_clear_error();
+        
+        let summary = ai_processor_generate_summary(processor, ptr::null());
+        assert!(summary.summary_text.is_null());
+        assert_eq!(last_error(), (CErrorCode::NullArgument as c_int, "processor and content_json must not be null".to_string()));
+        
+        let invalid = CString::new("{\"title\": 1}").unwrap();
+        let mut keywords: *mut *mut c_char = ptr::null_mut();
+        let mut count = 0;
+        assert_eq!(ai_processor_extract_keywords(processor, invalid.as_ptr(), &mut keywords, &mut count), -1);
+        let (code, message) = last_error();
+        assert_eq!(code, CErrorCode::InvalidJson as c_int);
+        assert!(message.starts_with("content_json: "));
+        
+        let missing = CString::new("/nonexistent/categories.json").unwrap();
+        assert_eq!(ai_processor_load_categories(processor, missing.as_ptr()), -1);
+        let (code, message) = last_error();
+        assert_eq!(code, CErrorCode::ProcessingFailed as c_int);
+        assert!(message.contains("/nonexistent/categories.json"));
+        
+        let name = CString::new("Unknown").unwrap();
+        assert_eq!(ai_processor_remove_label(processor, name.as_ptr()), -1);
+        assert_eq!(last_error().0, CErrorCode::NotFound as c_int);
+        
+        ai_processor_destroy(processor);
     }
 
     #[test]
-    fn test_calculate_cosine_similarity_different() {
-        let text_a = "Machine learning artificial intelligence";
-        let text_b = "Cooking recipes food preparation";
-        let similarity = calculate_cosine_similarity(text_a, text_b);
-        assert!(similarity < 0.5);
+    fn test_pipeline_config_disables_stages() {
+        let processor = ai_processor_create();
+        let config = CString::new(r#"{
+            "summary": {"max_key_points": 1},
+            "keywords": {"enabled": false},
+            "entities": {"enabled": false},
+            "sentiment": {"enabled": false},
+            "classification": {"enabled": false}
+        }"#).unwrap();
+        assert_eq!(ai_processor_set_pipeline(processor, config.as_ptr()), 0);
+        
+        let text = "Acme Corp released a great new compiler. The compiler is excellent. \
+                    Developers love the compiler. Acme Corp is based in London.";
+        let content = CString::new(serde_json::to_string(&page("News: compiler release", text, &["given"])).unwrap()).unwrap();
+        
+        let mut keywords: *mut *mut c_char = ptr::null_mut();
+        let mut count = 0;
+        assert_eq!(ai_processor_extract_keywords(processor, content.as_ptr(), &mut keywords, &mut count), 0);
+        assert_eq!(count, 1);
+        ai_processor_free_keywords(keywords, count);
+        
+        let text_c = CString::new(text).unwrap();
+        let mut entities: *mut CEntityInfo = ptr::null_mut();
+        assert_eq!(ai_processor_extract_entities(processor, text_c.as_ptr(), &mut entities, &mut count), 0);
+        assert_eq!(count, 0);
+        
+        let mut label: *mut c_char = ptr::null_mut();
+        let mut score: c_float = 1.0;
+        assert_eq!(ai_processor_analyze_sentiment(processor, text_c.as_ptr(), &mut label, &mut score), 0);
+        assert_eq!(unsafe { CStr::from_ptr(label) }.to_str().unwrap(), "neutral");
+        assert_eq!(score, 0.0);
+        ai_processor_free_string(label);
+        
+        let info = ai_processor_classify_content(processor, content.as_ptr());
+        assert_eq!(unsafe { CStr::from_ptr(info.primary_category) }.to_str().unwrap(), "Other");
+        ai_processor_free_category(info);
+        
+        let summary = ai_processor_generate_summary(processor, content.as_ptr());
+        assert_eq!(summary.key_points_count, 1);
+        assert_eq!(summary.content_type, CContentType::Other as c_int);
+        ai_processor_free_summary(summary);
+        
+        // The effective configuration is reported with defaults filled in
+        let json = ai_processor_get_pipeline(processor);
+        let config: PipelineConfig = serde_json::from_str(unsafe { CStr::from_ptr(json) }.to_str().unwrap()).unwrap();
+        ai_processor_free_string(json);
+        assert_eq!(config.summary.max_sentences, 3);
+        assert!(!config.entities.enabled);
+        
+        let invalid = CString::new(r#"{"keywords": {"enabled": "no"}}"#).unwrap();
+        assert_eq!(ai_processor_set_pipeline(processor, invalid.as_ptr()), -1);
+        
+        ai_processor_destroy(processor);
     }
 
     #[test]
-    fn test_calculate_cosine_similarity_similar() {
-        let text_a = "Machine learning is a subset of artificial intelligence";
-        let text_b = "Artificial intelligence includes machine learning techniques";
-        let similarity = calculate_cosine_similarity(text_a, text_b);
-        assert!(similarity > 0.3);
+    fn test_explain_save_builds_recall_hint() {
+        let processor = ai_processor_create();
+        let content = page("Lisbon trams", "Tram 28 crosses Lisbon. Lisbon trams are old and Lisbon hills are steep.", &[]);
+        let content = CString::new(serde_json::to_string(&content).unwrap()).unwrap();
+        let context = CString::new(r#"{
+            "saved_at": "2026-03-03T08:15:00+00:00",
+            "tab_group": "Trip planning",
+            "concurrent_tabs": ["Flights to Lisbon", "Inbox"]
+        }"#).unwrap();
+        
+        let hint = ai_processor_explain_save(processor, content.as_ptr(), context.as_ptr());
+        assert!(!hint.is_null());
+        let text = unsafe { CStr::from_ptr(hint) }.to_str().unwrap().to_string();
+        ai_processor_free_string(hint);
+        assert!(text.starts_with("Saved on Tuesday morning, 3 March 2026 from the \"Trip planning\" tab group"), "{}", text);
+        assert!(text.contains("\"Flights to Lisbon\""), "{}", text);
+        
+        let invalid = CString::new(r#"{"tab_group": "No time"}"#).unwrap();
+        assert!(ai_processor_explain_save(processor, content.as_ptr(), invalid.as_ptr()).is_null());
+        
+        ai_processor_destroy(processor);
     }
 
     #[test]
-    fn test_get_category_info() {
-        let (primary, secondary) = get_category_info(CContentType::Article);
-        assert_eq!(primary, "Articles");
-        assert!(secondary.contains(&"Reading".to_string()));
+    fn test_abstractive_mode_length_presets() {
+        let processor = ai_processor_create();
+        assert_eq!(ai_processor_set_mode(processor, CProcessingMode::Abstractive), 0);
+        let text = "Rust is a systems programming language. The borrow checker enforces memory safety. \
+                    Cargo builds projects and manages dependencies. Traits describe shared behavior. \
+                    Async functions run on executors such as Tokio.";
+        let content = CString::new(serde_json::to_string(&page("Rust", text, &[])).unwrap()).unwrap();
+        let summary_text = |length: CSummaryLength| {
+            let summary = ai_processor_generate_summary_with_length(processor, content.as_ptr(), length);
+            let text = unsafe { CStr::from_ptr(summary.summary_text) }.to_str().unwrap().to_string();
+            ai_processor_free_summary(summary);
+            text
+        };
+        
+        // Without a local model the extractive summary is shaped instead
+        let one_liner = summary_text(CSummaryLength::OneLiner);
+        assert_eq!(split_into_sentences(&one_liner).len(), 1);
+        let bullets = summary_text(CSummaryLength::BulletList);
+        assert!(bullets.lines().count() > 1);
+        assert!(bullets.lines().all(|line| line.starts_with("- ")));
+        assert_eq!(split_into_sentences(&summary_text(CSummaryLength::Paragraph)).len(), 3);
+        
+        // Rewritten summaries come from the model
+        struct Rewriter;
+        impl llm::LlmBackend for Rewriter {
+            fn generate(&self, _prompt: &str, _max_new_tokens: usize) -> Result<String, web_page_manager_core::AIProcessingError> {
+                Ok("Rust makes memory safety practical.".to_string())
+            }
+        }
+        unsafe { processor_handle(processor) }.write().local_llm = Some(LocalLlm::with_backend(Box::new(Rewriter), LocalLlmConfig::new("m.gguf", "t.json")));
+        assert_eq!(summary_text(CSummaryLength::OneLiner), "Rust makes memory safety practical.");
+        
+        ai_processor_destroy(processor);
     }
 
     #[test]
-    fn test_ai_processor_create_destroy() {
+    fn test_zero_shot_labels_classify_without_examples() {
         let processor = ai_processor_create();
-        assert!(!processor.is_null());
+        let classify = |content: &PageContentInput| {
+            let json = CString::new(serde_json::to_string(content).unwrap()).unwrap();
+            let info = ai_processor_classify_content(processor, json.as_ptr());
+            let primary = unsafe { CStr::from_ptr(info.primary_category) }.to_str().unwrap().to_string();
+            let source = info.source;
+            ai_processor_free_category(info);
+            (primary, source)
+        };
+        
+        let name = CString::new("Gardening").unwrap();
+        let description = CString::new("gardening plants soil seeds tomatoes watering compost").unwrap();
+        assert_eq!(ai_processor_define_label(processor, name.as_ptr(), description.as_ptr()), 0);
+        
+        let garden = page("Growing tomatoes", "Plant the seeds in rich soil, add compost and keep watering.", &[]);
+        assert_eq!(classify(&garden), ("Gardening".to_string(), CClassificationSource::ZeroShot as c_int));
+        let docs = page("API documentation", "The function takes a parameter and returns a value.", &[]);
+        assert_eq!(classify(&docs).0, "Documentation");
+        
+        let path = std::env::temp_dir().join(format!("labels_ffi_{}.json", std::process::id()));
+        let path_c = CString::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(ai_processor_save_labels(processor, path_c.as_ptr()), 0);
+        assert_eq!(ai_processor_remove_label(processor, name.as_ptr()), 0);
+        assert_eq!(ai_processor_remove_label(processor, name.as_ptr()), -1);
+        assert_ne!(classify(&garden).1, CClassificationSource::ZeroShot as c_int);
+        assert_eq!(ai_processor_load_labels(processor, path_c.as_ptr()), 0);
+        assert_eq!(classify(&garden).0, "Gardening");
+        std::fs::remove_file(&path).ok();
+        
         ai_processor_destroy(processor);
     }
 
     #[test]
-    fn test_ai_processor_set_mode() {
+    fn test_domain_rules_decide_classification() {
         let processor = ai_processor_create();
-        let result = ai_processor_set_mode(processor, CProcessingMode::Enhanced);
+        let classify = |content: &PageContentInput| {
+            let json = CString::new(serde_json::to_string(content).unwrap()).unwrap();
+            let info = ai_processor_classify_content(processor, json.as_ptr());
+            let primary = unsafe { CStr::from_ptr(info.primary_category) }.to_str().unwrap().to_string();
+            let source = info.source;
+            ai_processor_free_category(info);
+            (primary, source)
+        };
+        
+        // The domain wins over a documentation-looking title
+        let mut video = page("API documentation walkthrough", "The function takes a parameter.", &[]);
+        video.url = Some("https://www.youtube.com/watch?v=abc".to_string());
+        assert_eq!(classify(&video), ("Media".to_string(), CClassificationSource::Domain as c_int));
+        
+        let mut internal = page("Team wiki", "Notes about the quarterly plan.", &[]);
+        internal.url = Some("https://wiki.intranet.corp/plan".to_string());
+        assert_eq!(classify(&internal).1, CClassificationSource::Heuristic as c_int);
+        
+        let domain = CString::new("intranet.corp").unwrap();
+        assert_eq!(ai_processor_set_domain_category(processor, domain.as_ptr(), CContentType::Documentation), 0);
+        assert_eq!(classify(&internal), ("Documentation".to_string(), CClassificationSource::Domain as c_int));
+        
+        let path = std::env::temp_dir().join(format!("domain_rules_ffi_{}.json", std::process::id()));
+        let path_c = CString::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(ai_processor_save_domain_rules(processor, path_c.as_ptr()), 0);
+        assert_eq!(ai_processor_remove_domain_category(processor, domain.as_ptr()), 0);
+        assert_eq!(ai_processor_remove_domain_category(processor, domain.as_ptr()), -1);
+        assert_eq!(classify(&internal).1, CClassificationSource::Heuristic as c_int);
+        assert_eq!(ai_processor_load_domain_rules(processor, path_c.as_ptr()), 0);
+        assert_eq!(classify(&internal).1, CClassificationSource::Domain as c_int);
+        std::fs::remove_file(&path).ok();
+        
+        ai_processor_destroy(processor);
+    }
+
+    #[test]
+    fn test_ai_processor_extract_entities_typed() {
+        let processor = ai_processor_create();
+        let text = CString::new("Acme Corp paid $5 million on 2024-01-15. Acme Corp is based in London.").unwrap();
+        
+        let mut entities: *mut CEntityInfo = ptr::null_mut();
+        let mut count: usize = 0;
+        let result = ai_processor_extract_entities(processor, text.as_ptr(), &mut entities, &mut count);
         assert_eq!(result, 0);
+        
+        let slice = unsafe { std::slice::from_raw_parts(entities, count) };
+        let find = |name: &str| {
+            slice
+                .iter()
+                .find(|e| unsafe { CStr::from_ptr(e.name) }.to_str().unwrap() == name)
+                .unwrap()
+        };
+        
+        let acme = find("Acme Corp");
+        assert_eq!(acme.entity_type, CEntityType::Organization as c_int);
+        assert_eq!(acme.positions_count, 2);
+        assert_eq!(unsafe { *acme.positions.add(1) }, 41);
+        assert_eq!(find("$5 million").entity_type, CEntityType::Money as c_int);
+        assert_eq!(find("2024-01-15").entity_type, CEntityType::Date as c_int);
+        assert_eq!(find("London").entity_type, CEntityType::Location as c_int);
+        
+        ai_processor_free_entities(entities, count);
+        ai_processor_destroy(processor);
+    }
+
+    #[test]
+    fn test_ai_processor_generate_embedding() {
+        let processor = ai_processor_create();
+        
+        let make_json = |text: &str| {
+            let content = PageContentInput {
+                html: String::new(),
+                text: text.to_string(),
+                title: "Embedding Test".to_string(),
+                description: None,
+                keywords: vec![],
+                images: vec![],
+                links: vec![],
+                url: None,
+            };
+            CString::new(serde_json::to_string(&content).unwrap()).unwrap()
+        };
+        let json_a = make_json("Rust ownership and borrowing rules explained");
+        let json_b = make_json("Rust ownership and borrowing rules explained");
+        
+        let mut embedding_a: *mut c_float = ptr::null_mut();
+        let mut embedding_b: *mut c_float = ptr::null_mut();
+        let mut dims_a = 0usize;
+        let mut dims_b = 0usize;
+        
+        assert_eq!(ai_processor_generate_embedding(processor, json_a.as_ptr(), &mut embedding_a, &mut dims_a), 0);
+        assert_eq!(ai_processor_generate_embedding(processor, json_b.as_ptr(), &mut embedding_b, &mut dims_b), 0);
+        assert_eq!(dims_a, embedding::DEFAULT_HASHING_DIMENSIONS);
+        assert_eq!(dims_a, dims_b);
+        
+        let similarity = ai_processor_embedding_similarity(embedding_a, embedding_b, dims_a);
+        assert!((similarity - 1.0).abs() < 1e-6);
+        
+        let model = ai_processor_get_embedding_model(processor);
+        assert!(!model.is_null());
+        let model_name = unsafe { CStr::from_ptr(model) }.to_str().unwrap().to_string();
+        assert_eq!(model_name, "hashing-512");
+        
+        ai_processor_free_string(model);
+        ai_processor_free_embedding(embedding_a, dims_a);
+        ai_processor_free_embedding(embedding_b, dims_b);
+        ai_processor_destroy(processor);
+    }
+
+    #[test]
+    fn test_generated_embedding_matches_grouping() {
+        let processor = ai_processor_create();
+        let pages = [
+            page("Rust ownership", "Ownership and borrowing rules keep Rust memory safe.", &[]),
+            page("Sourdough", "Feed the starter daily before baking bread.", &[]),
+        ];
+        let state = unsafe { processor_handle(processor) }.read();
+        let grouped = embed_contents(&pages, state.embedder.as_ref(), &Progress::silent().stage("embed", pages.len()));
+        drop(state);
+        
+        for (content, expected) in pages.iter().zip(&grouped) {
+            let json = CString::new(serde_json::to_string(content).unwrap()).unwrap();
+            let mut embedding: *mut c_float = ptr::null_mut();
+            let mut dims = 0usize;
+            assert_eq!(ai_processor_generate_embedding(processor, json.as_ptr(), &mut embedding, &mut dims), 0);
+            assert_eq!(unsafe { std::slice::from_raw_parts(embedding, dims) }, expected.as_slice());
+            ai_processor_free_embedding(embedding, dims);
+        }
         ai_processor_destroy(processor);
     }
 
@@ -2029,6 +5555,7 @@ mod tests {
             keywords: vec!["programming".to_string()],
             images: vec![],
             links: vec![],
+            url: None,
         };
         
         let content_json = serde_json::to_string(&content).unwrap();
@@ -2039,6 +5566,8 @@ mod tests {
         assert!(!summary.summary_text.is_null());
         assert!(summary.confidence_score > 0.0);
         assert!(summary.reading_time_minutes >= 1);
+        assert!(summary.readability_score > 0.0 && summary.readability_score <= 100.0);
+        assert!(summary.reading_difficulty <= CReadingDifficulty::VeryDifficult as c_int);
         
         ai_processor_free_summary(summary);
         ai_processor_destroy(processor);
@@ -2056,6 +5585,7 @@ mod tests {
             keywords: vec![],
             images: vec![],
             links: vec![],
+            url: None,
         };
         
         let content_b = PageContentInput {
@@ -2066,6 +5596,7 @@ mod tests {
             keywords: vec![],
             images: vec![],
             links: vec![],
+            url: None,
         };
         
         let json_a = CString::new(serde_json::to_string(&content_a).unwrap()).unwrap();
@@ -2078,4 +5609,49 @@ mod tests {
         
         ai_processor_destroy(processor);
     }
+
+    #[test]
+    fn test_ai_processor_discover_topics() {
+        let processor = ai_processor_create();
+        
+        let page = |title: &str, text: &str| PageContentInput {
+            html: String::new(),
+            text: text.to_string(),
+            title: title.to_string(),
+            description: None,
+            keywords: vec![],
+            images: vec![],
+            links: vec![],
+            url: None,
+        };
+        let contents = vec![
+            page("Rust ownership", "Rust ownership and the borrow checker prevent data races"),
+            page("Rust lifetimes", "Lifetimes let the Rust borrow checker validate references"),
+            page("Pasta recipe", "Cook pasta with tomato sauce, garlic and basil"),
+            page("Tomato sauce", "Simmer tomato with garlic and basil for pasta"),
+        ];
+        let contents_c = CString::new(serde_json::to_string(&contents).unwrap()).unwrap();
+        
+        let mut topics_out: *mut CTopic = ptr::null_mut();
+        let mut count: usize = 0;
+        let result = ai_processor_discover_topics(processor, contents_c.as_ptr(), 2, &mut topics_out, &mut count);
+        assert_eq!(result, 0);
+        assert_eq!(count, 2);
+        
+        let topics = unsafe { std::slice::from_raw_parts(topics_out, count) };
+        for topic in topics {
+            assert!(!topic.label.is_null());
+            assert!(topic.keywords_count > 0);
+            assert_eq!(topic.page_ids_count, 2);
+        }
+        ai_processor_free_topics(topics_out, count);
+        
+        let mut suggestions_out: *mut CGroupSuggestion = ptr::null_mut();
+        let result = ai_processor_suggest_topic_groups(processor, contents_c.as_ptr(), 2, &mut suggestions_out, &mut count);
+        assert_eq!(result, 0);
+        assert_eq!(count, 2);
+        ai_processor_free_group_suggestions(suggestions_out, count);
+        
+        ai_processor_destroy(processor);
+    }
 }
@@ -0,0 +1,478 @@
+//! Local LLM inference backend
+//!
+//! Runs a small quantized language model on-device so that summarization and
+//! classification never send page content off the machine. The model runtime
+//! (GGUF weights executed with candle) is only compiled in with the
+//! `local-llm` feature; without it `LocalLlm::load` fails with
+//! `ModelLoadFailed` and the processor keeps using the extractive pipeline.
+
+use std::path::PathBuf;
+
+use web_page_manager_core::AIProcessingError;
+
+use crate::{truncate_chars, CContentType, PageContentInput};
+
+/// Configuration for the on-device model
+#[derive(Debug, Clone)]
+pub struct LocalLlmConfig {
+    /// Path to the quantized GGUF model file
+    pub model_path: PathBuf,
+    /// Path to the `tokenizer.json` matching the model
+    pub tokenizer_path: PathBuf,
+    /// Maximum number of tokens generated for a summary
+    pub max_summary_tokens: usize,
+    /// Maximum number of characters of page text placed into a prompt
+    pub max_input_chars: usize,
+    /// Sampling temperature (0.0 means greedy decoding)
+    pub temperature: f64,
+    /// Seed for the sampler, so results are reproducible
+    pub seed: u64,
+}
+
+impl LocalLlmConfig {
+    pub fn new(model_path: impl Into<PathBuf>, tokenizer_path: impl Into<PathBuf>) -> Self {
+        Self {
+            model_path: model_path.into(),
+            tokenizer_path: tokenizer_path.into(),
+            max_summary_tokens: 160,
+            max_input_chars: 6000,
+            temperature: 0.0,
+            seed: 42,
+        }
+    }
+}
+
+/// Length preset for abstractive summaries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SummaryLength {
+    /// A single sentence
+    OneLiner,
+    /// A short paragraph of up to three sentences
+    #[default]
+    Paragraph,
+    /// Three to five bullet points, one per line starting with "- "
+    BulletList,
+}
+
+impl SummaryLength {
+    fn instruction(self) -> &'static str {
+        match self {
+            SummaryLength::OneLiner => "a single short sentence",
+            SummaryLength::Paragraph => "one short paragraph of at most three sentences",
+            SummaryLength::BulletList => "three to five short bullet points, each on its own line starting with \"- \"",
+        }
+    }
+
+    fn max_tokens(self, config: &LocalLlmConfig) -> usize {
+        match self {
+            SummaryLength::OneLiner => config.max_summary_tokens / 3,
+            SummaryLength::Paragraph => config.max_summary_tokens,
+            SummaryLength::BulletList => config.max_summary_tokens * 3 / 2,
+        }
+    }
+}
+
+/// Text generation backend used for LLM-assisted analysis
+pub trait LlmBackend: Send + Sync {
+    /// Generate a completion for `prompt`, producing at most `max_new_tokens` tokens
+    fn generate(&self, prompt: &str, max_new_tokens: usize) -> Result<String, AIProcessingError>;
+
+    /// Like `generate`, but reports decoded text to `on_chunk` as it is produced
+    ///
+    /// The default implementation reports the whole completion at once.
+    fn generate_stream(
+        &self,
+        prompt: &str,
+        max_new_tokens: usize,
+        on_chunk: &mut dyn FnMut(&str),
+    ) -> Result<String, AIProcessingError> {
+        let output = self.generate(prompt, max_new_tokens)?;
+        on_chunk(&output);
+        Ok(output)
+    }
+}
+
+/// A loaded local model together with its prompt settings
+pub struct LocalLlm {
+    backend: Box<dyn LlmBackend>,
+    config: LocalLlmConfig,
+}
+
+impl LocalLlm {
+    /// Load the model described by `config`
+    pub fn load(config: LocalLlmConfig) -> Result<Self, AIProcessingError> {
+        #[cfg(feature = "local-llm")]
+        {
+            let backend = candle_backend::CandleLlm::load(&config)?;
+            Ok(Self::with_backend(Box::new(backend), config))
+        }
+
+        #[cfg(not(feature = "local-llm"))]
+        {
+            Err(AIProcessingError::ModelLoadFailed {
+                model: format!(
+                    "{} (built without the local-llm feature)",
+                    config.model_path.display()
+                ),
+            })
+        }
+    }
+
+    /// Wrap an already constructed backend
+    pub fn with_backend(backend: Box<dyn LlmBackend>, config: LocalLlmConfig) -> Self {
+        Self { backend, config }
+    }
+
+    /// Produce a short abstract of the page
+    pub fn summarize(&self, content: &PageContentInput) -> Result<String, AIProcessingError> {
+        self.summarize_stream(content, &mut |_| {})
+    }
+
+    /// Produce a short abstract of the page, reporting text chunks as they are generated
+    ///
+    /// Leading whitespace is not reported; the returned summary is trimmed.
+    pub fn summarize_stream(
+        &self,
+        content: &PageContentInput,
+        on_chunk: &mut dyn FnMut(&str),
+    ) -> Result<String, AIProcessingError> {
+        let prompt = format!(
+            "Summarize the following web page in at most three sentences.\n\n\
+             Title: {}\n\nContent:\n{}\n\nSummary:",
+            content.title,
+            truncate_chars(&content.text, self.config.max_input_chars),
+        );
+        self.generate_summary(&prompt, self.config.max_summary_tokens, on_chunk)
+    }
+
+    /// Rewrite the page as an abstract of the given length, reporting chunks
+    ///
+    /// Unlike `summarize_stream` the model is asked not to copy sentences, and
+    /// bullet lists are normalized to lines starting with "- ".
+    pub fn rewrite_stream(
+        &self,
+        content: &PageContentInput,
+        length: SummaryLength,
+        on_chunk: &mut dyn FnMut(&str),
+    ) -> Result<String, AIProcessingError> {
+        let prompt = format!(
+            "Rewrite the key information of the following web page in your own words \
+             as {}. Do not copy sentences from the page.\n\n\
+             Title: {}\n\nContent:\n{}\n\nSummary:",
+            length.instruction(),
+            content.title,
+            truncate_chars(&content.text, self.config.max_input_chars),
+        );
+        let summary = self.generate_summary(&prompt, length.max_tokens(&self.config), on_chunk)?;
+
+        Ok(match length {
+            SummaryLength::OneLiner => summary.lines().next().unwrap_or_default().trim().to_string(),
+            SummaryLength::Paragraph => summary,
+            SummaryLength::BulletList => normalize_bullets(&summary),
+        })
+    }
+
+    /// Run a summary prompt, trimming the output and rejecting empty results
+    fn generate_summary(
+        &self,
+        prompt: &str,
+        max_new_tokens: usize,
+        on_chunk: &mut dyn FnMut(&str),
+    ) -> Result<String, AIProcessingError> {
+        let mut started = false;
+        let output = self.backend.generate_stream(prompt, max_new_tokens, &mut |chunk| {
+            let chunk = if started { chunk } else { chunk.trim_start() };
+            if !chunk.is_empty() {
+                started = true;
+                on_chunk(chunk);
+            }
+        })?;
+        let summary = output.trim();
+        if summary.is_empty() {
+            return Err(AIProcessingError::ProcessingFailed {
+                reason: "Local LLM returned an empty summary".to_string(),
+            });
+        }
+
+        Ok(summary.to_string())
+    }
+
+    /// Rephrase a template recall hint into a natural reminder of why the page was saved
+    pub fn explain_save(&self, content: &PageContentInput, template_hint: &str) -> Result<String, AIProcessingError> {
+        let prompt = format!(
+            "Rewrite the following note as one short sentence reminding the user why they \
+             saved this web page. Keep every date, group and tab name.\n\n\
+             Note: {}\n\nTitle: {}\n\nContent:\n{}\n\nReminder:",
+            template_hint,
+            content.title,
+            truncate_chars(&content.text, self.config.max_input_chars / 4),
+        );
+        let hint = self.generate_summary(&prompt, self.config.max_summary_tokens / 2, &mut |_| {})?;
+        Ok(hint.lines().next().unwrap_or_default().trim().to_string())
+    }
+
+    /// Classify the page into one of the known content types
+    pub fn classify(&self, content: &PageContentInput) -> Result<CContentType, AIProcessingError> {
+        let prompt = format!(
+            "Classify the following web page as exactly one of: \
+             article, video, documentation, social, shopping, news, reference, other.\n\n\
+             Title: {}\n\nContent:\n{}\n\nCategory:",
+            content.title,
+            truncate_chars(&content.text, self.config.max_input_chars / 4),
+        );
+
+        let output = self.backend.generate(&prompt, 8)?;
+        parse_content_type(&output).ok_or_else(|| AIProcessingError::ProcessingFailed {
+            reason: format!("Unrecognized category from local LLM: {}", output.trim()),
+        })
+    }
+}
+
+/// Put every non-empty line on a "- " bullet, replacing other list markers
+fn normalize_bullets(text: &str) -> String {
+    text.lines()
+        .map(strip_list_marker)
+        .filter(|line| !line.is_empty())
+        .map(|line| format!("- {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Remove a leading "-", "*", "•", "1." or "1)" marker
+fn strip_list_marker(line: &str) -> &str {
+    let line = line.trim();
+    if let Some(rest) = line.strip_prefix(['-', '*', '•']) {
+        return rest.trim_start();
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    match line[digits..].strip_prefix(['.', ')']) {
+        Some(rest) if digits > 0 => rest.trim_start(),
+        _ => line,
+    }
+}
+
+/// Map the first recognizable label in model output to a content type
+fn parse_content_type(output: &str) -> Option<CContentType> {
+    let lower = output.to_lowercase();
+    lower
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .find_map(|word| match word {
+            "article" | "blog" => Some(CContentType::Article),
+            "video" => Some(CContentType::Video),
+            "documentation" | "docs" => Some(CContentType::Documentation),
+            "social" => Some(CContentType::SocialMedia),
+            "shopping" => Some(CContentType::Shopping),
+            "news" => Some(CContentType::News),
+            "reference" => Some(CContentType::Reference),
+            "other" => Some(CContentType::Other),
+            _ => None,
+        })
+}
+
+#[cfg(feature = "local-llm")]
+mod candle_backend {
+    use std::sync::Mutex;
+
+    use candle_core::quantized::gguf_file;
+    use candle_core::{Device, Tensor};
+    use candle_transformers::generation::LogitsProcessor;
+    use candle_transformers::models::quantized_llama::ModelWeights;
+    use tokenizers::Tokenizer;
+
+    use super::*;
+
+    /// End-of-sequence markers used by common small chat models
+    const EOS_TOKENS: &[&str] = &["</s>", "<|endoftext|>", "<|eot_id|>", "<|im_end|>"];
+
+    /// Quantized llama-family model executed on the CPU
+    pub struct CandleLlm {
+        model: Mutex<ModelWeights>,
+        tokenizer: Tokenizer,
+        device: Device,
+        eos_token: Option<u32>,
+        temperature: f64,
+        seed: u64,
+    }
+
+    impl CandleLlm {
+        pub fn load(config: &LocalLlmConfig) -> Result<Self, AIProcessingError> {
+            let load_failed = |e: &dyn std::fmt::Display| AIProcessingError::ModelLoadFailed {
+                model: format!("{}: {}", config.model_path.display(), e),
+            };
+
+            let device = Device::Cpu;
+            let mut file = std::fs::File::open(&config.model_path).map_err(|e| load_failed(&e))?;
+            let content = gguf_file::Content::read(&mut file).map_err(|e| load_failed(&e))?;
+            let model = ModelWeights::from_gguf(content, &mut file, &device).map_err(|e| load_failed(&e))?;
+            let tokenizer = Tokenizer::from_file(&config.tokenizer_path).map_err(|e| load_failed(&e))?;
+            let eos_token = EOS_TOKENS.iter().find_map(|t| tokenizer.token_to_id(t));
+
+            Ok(Self {
+                model: Mutex::new(model),
+                tokenizer,
+                device,
+                eos_token,
+                temperature: config.temperature,
+                seed: config.seed,
+            })
+        }
+
+        fn run(
+            &self,
+            prompt: &str,
+            max_new_tokens: usize,
+            on_chunk: &mut dyn FnMut(&str),
+        ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+            let prompt_tokens = self.tokenizer.encode(prompt, true)?.get_ids().to_vec();
+            let mut model = self.model.lock().map_err(|_| "model lock poisoned")?;
+            model.clear_kv_cache();
+
+            let mut sampler = LogitsProcessor::new(self.seed, Some(self.temperature), None);
+            let input = Tensor::new(prompt_tokens.as_slice(), &self.device)?.unsqueeze(0)?;
+            let logits = model.forward(&input, 0)?.squeeze(0)?;
+            let mut next_token = sampler.sample(&logits)?;
+
+            let mut generated = Vec::with_capacity(max_new_tokens);
+            let mut emitted = 0;
+            for index in 0..max_new_tokens {
+                if Some(next_token) == self.eos_token {
+                    break;
+                }
+                generated.push(next_token);
+
+                // Re-decode so multi-token characters are reported whole
+                let text = self.tokenizer.decode(&generated, true)?;
+                if text.len() > emitted && !text.ends_with('\u{FFFD}') && text.is_char_boundary(emitted) {
+                    on_chunk(&text[emitted..]);
+                    emitted = text.len();
+                }
+
+                let input = Tensor::new(&[next_token], &self.device)?.unsqueeze(0)?;
+                let logits = model.forward(&input, prompt_tokens.len() + index)?.squeeze(0)?;
+                next_token = sampler.sample(&logits)?;
+            }
+
+            let text = self.tokenizer.decode(&generated, true)?;
+            if text.len() > emitted && text.is_char_boundary(emitted) {
+                on_chunk(&text[emitted..]);
+            }
+            Ok(text)
+        }
+    }
+
+    impl LlmBackend for CandleLlm {
+        fn generate(&self, prompt: &str, max_new_tokens: usize) -> Result<String, AIProcessingError> {
+            self.generate_stream(prompt, max_new_tokens, &mut |_| {})
+        }
+
+        fn generate_stream(
+            &self,
+            prompt: &str,
+            max_new_tokens: usize,
+            on_chunk: &mut dyn FnMut(&str),
+        ) -> Result<String, AIProcessingError> {
+            self.run(prompt, max_new_tokens, on_chunk)
+                .map_err(|e| AIProcessingError::ProcessingFailed {
+                    reason: format!("Local LLM inference failed: {}", e),
+                })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedBackend(&'static str);
+
+    impl LlmBackend for FixedBackend {
+        fn generate(&self, _prompt: &str, _max_new_tokens: usize) -> Result<String, AIProcessingError> {
+            Ok(self.0.to_string())
+        }
+    }
+
+    fn sample_content() -> PageContentInput {
+        PageContentInput {
+            html: String::new(),
+            text: "Rust is a systems programming language focused on safety.".to_string(),
+            title: "Rust".to_string(),
+            description: None,
+            keywords: vec![],
+            images: vec![],
+            links: vec![],
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_content_type() {
+        assert_eq!(parse_content_type(" Documentation."), Some(CContentType::Documentation));
+        assert_eq!(parse_content_type("Category: news"), Some(CContentType::News));
+        assert_eq!(parse_content_type("???"), None);
+    }
+
+    #[test]
+    fn test_truncate_chars_respects_boundaries() {
+        assert_eq!(truncate_chars("你好世界", 2), "你好");
+        assert_eq!(truncate_chars("abc", 10), "abc");
+    }
+
+    #[test]
+    fn test_summarize_and_classify_with_backend() {
+        let config = LocalLlmConfig::new("model.gguf", "tokenizer.json");
+        let llm = LocalLlm::with_backend(Box::new(FixedBackend("  A short summary.  ")), config.clone());
+        assert_eq!(llm.summarize(&sample_content()).unwrap(), "A short summary.");
+
+        let llm = LocalLlm::with_backend(Box::new(FixedBackend("video")), config);
+        assert_eq!(llm.classify(&sample_content()).unwrap(), CContentType::Video);
+    }
+
+    struct ChunkedBackend(&'static [&'static str]);
+
+    impl LlmBackend for ChunkedBackend {
+        fn generate(&self, _prompt: &str, _max_new_tokens: usize) -> Result<String, AIProcessingError> {
+            Ok(self.0.concat())
+        }
+
+        fn generate_stream(
+            &self,
+            _prompt: &str,
+            _max_new_tokens: usize,
+            on_chunk: &mut dyn FnMut(&str),
+        ) -> Result<String, AIProcessingError> {
+            self.0.iter().for_each(|chunk| on_chunk(chunk));
+            Ok(self.0.concat())
+        }
+    }
+
+    #[test]
+    fn test_summarize_stream_reports_chunks() {
+        let config = LocalLlmConfig::new("model.gguf", "tokenizer.json");
+        let llm = LocalLlm::with_backend(Box::new(ChunkedBackend(&["  ", " Rust is", " fast.", " "])), config);
+
+        let mut chunks = Vec::new();
+        let summary = llm.summarize_stream(&sample_content(), &mut |c| chunks.push(c.to_string())).unwrap();
+        assert_eq!(summary, "Rust is fast.");
+        assert_eq!(chunks, vec!["Rust is", " fast.", " "]);
+    }
+
+    #[test]
+    fn test_rewrite_stream_shapes_output() {
+        let config = LocalLlmConfig::new("model.gguf", "tokenizer.json");
+        let llm = LocalLlm::with_backend(Box::new(FixedBackend("* Safe\n2. Fast\n\n• Fearless\n2024 release")), config.clone());
+        let bullets = llm.rewrite_stream(&sample_content(), SummaryLength::BulletList, &mut |_| {}).unwrap();
+        assert_eq!(bullets, "- Safe\n- Fast\n- Fearless\n- 2024 release");
+
+        let llm = LocalLlm::with_backend(Box::new(FixedBackend("Rust is safe.\nMore text.")), config);
+        let line = llm.rewrite_stream(&sample_content(), SummaryLength::OneLiner, &mut |_| {}).unwrap();
+        assert_eq!(line, "Rust is safe.");
+    }
+
+    #[cfg(not(feature = "local-llm"))]
+    #[test]
+    fn test_load_without_feature_fails() {
+        let result = LocalLlm::load(LocalLlmConfig::new("model.gguf", "tokenizer.json"));
+        assert!(matches!(result, Err(AIProcessingError::ModelLoadFailed { .. })));
+    }
+}
@@ -0,0 +1,187 @@
+//! Representative image and media summary
+//!
+//! Picks the image a preview should show for a page and counts its embedded
+//! media. The page's declared preview (`og:image`, then `twitter:image`) wins;
+//! otherwise the largest image in the main content by its width and height
+//! attributes, skipping icons, tracking pixels and images outside
+//! `<article>`/`<main>` when the page has such an element. Relative URLs are
+//! resolved against the page URL.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Images with a known side below this many pixels are icons or pixels
+const MIN_IMAGE_SIDE: u32 = 50;
+
+/// Image and video content of a page
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MediaSummary {
+    /// Absolute URL of the representative image
+    pub image: Option<String>,
+    /// Images in the main content
+    pub image_count: usize,
+    /// `<video>` elements and embedded players
+    pub video_count: usize,
+}
+
+struct Patterns {
+    meta: Regex,
+    content_region: Regex,
+    img: Regex,
+    attribute: Regex,
+    video: Regex,
+    player: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let regex = |pattern: &str| Regex::new(pattern).expect("media pattern is valid");
+        Patterns {
+            meta: regex(r"(?is)<meta\s[^>]*>"),
+            content_region: regex(r"(?is)<(article|main)\b.*?</(article|main)>"),
+            img: regex(r"(?is)<img\s[^>]*>"),
+            attribute: regex(r#"(?is)([a-z:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#),
+            video: regex(r"(?is)<video\b"),
+            player: regex(
+                r#"(?is)<(?:iframe|embed)\s[^>]*src\s*=\s*["'][^"']*(?:youtube\.com|youtube-nocookie\.com|youtu\.be|vimeo\.com|dailymotion\.com|twitch\.tv)"#,
+            ),
+        }
+    })
+}
+
+/// Summarize the media of a page
+///
+/// `images` are image URLs extracted by the browser, used when the HTML has
+/// no usable image.
+pub fn summarize(html: &str, images: &[String], page_url: Option<&str>) -> MediaSummary {
+    let patterns = patterns();
+    let content = patterns.content_region.find(html).map_or(html, |m| m.as_str());
+
+    let content_images: Vec<(String, Option<u64>)> = patterns
+        .img
+        .find_iter(content)
+        .filter_map(|tag| {
+            let attributes = attributes(tag.as_str());
+            let src = attribute(&attributes, "src").or_else(|| attribute(&attributes, "data-src"))?;
+            let width = attribute(&attributes, "width").and_then(|w| w.trim_end_matches("px").parse::<u32>().ok());
+            let height = attribute(&attributes, "height").and_then(|h| h.trim_end_matches("px").parse::<u32>().ok());
+            if width.into_iter().chain(height).any(|side| side < MIN_IMAGE_SIDE) || src.starts_with("data:") {
+                return None;
+            }
+            Some((src, width.zip(height).map(|(w, h)| w as u64 * h as u64)))
+        })
+        .collect();
+
+    let declared = patterns.meta.find_iter(html).find_map(|tag| {
+        let attributes = attributes(tag.as_str());
+        let name = attribute(&attributes, "property").or_else(|| attribute(&attributes, "name"))?;
+        matches!(name.to_lowercase().as_str(), "og:image" | "og:image:url" | "twitter:image")
+            .then(|| attribute(&attributes, "content"))
+            .flatten()
+    });
+    // Largest known area first, otherwise the first image in the content
+    let largest = content_images
+        .iter()
+        .enumerate()
+        .max_by_key(|(index, (_, area))| (area.unwrap_or(0), std::cmp::Reverse(*index)))
+        .map(|(_, (src, _))| src.clone());
+
+    let image = declared
+        .or(largest)
+        .or_else(|| images.first().cloned())
+        .filter(|src| !src.trim().is_empty())
+        .map(|src| resolve_url(src.trim(), page_url));
+
+    MediaSummary {
+        image,
+        image_count: content_images.len(),
+        video_count: patterns.video.find_iter(html).count() + patterns.player.find_iter(html).count(),
+    }
+}
+
+pub(crate) fn attributes(tag: &str) -> Vec<(String, String)> {
+    patterns()
+        .attribute
+        .captures_iter(tag)
+        .map(|c| {
+            let value = c.get(2).or_else(|| c.get(3)).map_or("", |m| m.as_str());
+            (c[1].to_lowercase(), value.replace("&amp;", "&"))
+        })
+        .collect()
+}
+
+pub(crate) fn attribute(attributes: &[(String, String)], name: &str) -> Option<String> {
+    attributes
+        .iter()
+        .find(|(key, value)| key == name && !value.is_empty())
+        .map(|(_, value)| value.clone())
+}
+
+/// Resolve an image URL against the URL of the page it appears on
+pub(crate) fn resolve_url(src: &str, page_url: Option<&str>) -> String {
+    if src.contains("://") {
+        return src.to_string();
+    }
+    let Some(page_url) = page_url else {
+        return src.to_string();
+    };
+    let Some((scheme, rest)) = page_url.split_once("://") else {
+        return src.to_string();
+    };
+
+    if let Some(host_relative) = src.strip_prefix("//") {
+        return format!("{}://{}", scheme, host_relative);
+    }
+    let origin_end = rest.find('/').unwrap_or(rest.len());
+    let origin = format!("{}://{}", scheme, &rest[..origin_end]);
+    if src.starts_with('/') {
+        return format!("{}{}", origin, src);
+    }
+
+    let path = &rest[origin_end..];
+    let path = path.split(['?', '#']).next().unwrap_or("");
+    let directory = path.rfind('/').map_or("/", |i| &path[..=i]);
+    format!("{}{}{}", origin, directory, src)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: Option<&str> = Some("https://example.com/blog/post.html?ref=home");
+
+    #[test]
+    fn test_declared_image_wins() {
+        let html = r#"<head><meta property="og:image" content="/images/cover.jpg"></head>
+            <body><img src="big.jpg" width="1200" height="800"></body>"#;
+        let media = summarize(html, &[], PAGE);
+        assert_eq!(media.image.as_deref(), Some("https://example.com/images/cover.jpg"));
+        assert_eq!(media.image_count, 1);
+    }
+
+    #[test]
+    fn test_largest_content_image_and_videos() {
+        let html = r#"<header><img src="logo.png" width="1600" height="900"></header>
+            <article>
+              <img src="pixel.gif" width="1" height="1">
+              <img src="small.jpg" width="300" height="200">
+              <img src="//cdn.example.com/large.jpg" width="900" height="600">
+              <video src="clip.mp4"></video>
+              <iframe src="https://www.youtube.com/embed/abc"></iframe>
+            </article>"#;
+        let media = summarize(html, &[], PAGE);
+        assert_eq!(media.image.as_deref(), Some("https://cdn.example.com/large.jpg"));
+        assert_eq!(media.image_count, 2);
+        assert_eq!(media.video_count, 2);
+    }
+
+    #[test]
+    fn test_falls_back_to_browser_images() {
+        let media = summarize("<p>No images</p>", &["photo.png".to_string()], PAGE);
+        assert_eq!(media.image.as_deref(), Some("https://example.com/blog/photo.png"));
+        assert_eq!(summarize("", &[], None), MediaSummary::default());
+    }
+}
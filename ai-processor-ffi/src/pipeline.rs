@@ -0,0 +1,214 @@
+//! Configurable processing pipeline
+//!
+//! Each analysis stage can be switched off or tuned, so that low-power
+//! devices can skip the expensive ones (embedding-based classification,
+//! entity linking) while keeping cheap ones. The configuration is plain
+//! serde data: C callers pass it as JSON, where every field is optional and
+//! falls back to its default, and Rust callers use `PipelineBuilder`.
+//! Disabled stages return empty results rather than errors.
+
+use serde::{Deserialize, Serialize};
+
+/// Summary text and key points
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SummaryStage {
+    pub enabled: bool,
+    /// Sentences in extractive summaries
+    pub max_sentences: usize,
+    pub max_key_points: usize,
+}
+
+impl Default for SummaryStage {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_sentences: 3,
+            max_key_points: 5,
+        }
+    }
+}
+
+/// Keyword extraction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeywordStage {
+    /// When disabled only keywords supplied with the page are returned
+    pub enabled: bool,
+    /// Keywords extracted from the page text
+    pub max_keywords: usize,
+}
+
+impl Default for KeywordStage {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_keywords: 15,
+        }
+    }
+}
+
+/// Entity extraction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EntityStage {
+    pub enabled: bool,
+    /// Link entities to Wikidata items
+    pub link: bool,
+}
+
+impl Default for EntityStage {
+    fn default() -> Self {
+        Self { enabled: true, link: true }
+    }
+}
+
+/// Sentiment analysis
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SentimentStage {
+    /// When disabled all text is reported as neutral
+    pub enabled: bool,
+}
+
+impl Default for SentimentStage {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Content classification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassificationStage {
+    /// When disabled all pages are classified as `Other`
+    pub enabled: bool,
+    /// Match user categories and labels, which embeds every page
+    pub use_embeddings: bool,
+}
+
+impl Default for ClassificationStage {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            use_embeddings: true,
+        }
+    }
+}
+
+/// Stages run by the processor and their parameters
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PipelineConfig {
+    pub summary: SummaryStage,
+    pub keywords: KeywordStage,
+    pub entities: EntityStage,
+    pub sentiment: SentimentStage,
+    pub classification: ClassificationStage,
+}
+
+impl PipelineConfig {
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder::default()
+    }
+
+    /// Cheap stages only: no entity linking, no embedding-based
+    /// classification and shorter outputs
+    pub fn low_power() -> Self {
+        Self::builder()
+            .max_key_points(3)
+            .max_keywords(8)
+            .link_entities(false)
+            .classify_with_embeddings(false)
+            .build()
+    }
+}
+
+/// Builder for `PipelineConfig`, starting from the defaults
+#[derive(Debug, Clone, Default)]
+pub struct PipelineBuilder {
+    config: PipelineConfig,
+}
+
+impl PipelineBuilder {
+    pub fn summary(mut self, enabled: bool) -> Self {
+        self.config.summary.enabled = enabled;
+        self
+    }
+
+    pub fn max_summary_sentences(mut self, sentences: usize) -> Self {
+        self.config.summary.max_sentences = sentences.max(1);
+        self
+    }
+
+    pub fn max_key_points(mut self, points: usize) -> Self {
+        self.config.summary.max_key_points = points;
+        self
+    }
+
+    pub fn keywords(mut self, enabled: bool) -> Self {
+        self.config.keywords.enabled = enabled;
+        self
+    }
+
+    pub fn max_keywords(mut self, keywords: usize) -> Self {
+        self.config.keywords.max_keywords = keywords;
+        self
+    }
+
+    pub fn entities(mut self, enabled: bool) -> Self {
+        self.config.entities.enabled = enabled;
+        self
+    }
+
+    pub fn link_entities(mut self, link: bool) -> Self {
+        self.config.entities.link = link;
+        self
+    }
+
+    pub fn sentiment(mut self, enabled: bool) -> Self {
+        self.config.sentiment.enabled = enabled;
+        self
+    }
+
+    pub fn classification(mut self, enabled: bool) -> Self {
+        self.config.classification.enabled = enabled;
+        self
+    }
+
+    pub fn classify_with_embeddings(mut self, use_embeddings: bool) -> Self {
+        self.config.classification.use_embeddings = use_embeddings;
+        self
+    }
+
+    pub fn build(self) -> PipelineConfig {
+        self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_json_keeps_defaults() {
+        let config: PipelineConfig =
+            serde_json::from_str(r#"{"entities": {"enabled": false}, "keywords": {"max_keywords": 5}}"#).unwrap();
+        assert!(!config.entities.enabled);
+        assert!(config.entities.link);
+        assert_eq!(config.keywords.max_keywords, 5);
+        assert!(config.keywords.enabled);
+        assert_eq!(config.summary, SummaryStage::default());
+    }
+
+    #[test]
+    fn test_builder_matches_json() {
+        let built = PipelineConfig::builder().sentiment(false).max_summary_sentences(0).build();
+        assert_eq!(built.summary.max_sentences, 1);
+
+        let json = serde_json::to_string(&built).unwrap();
+        let parsed: PipelineConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, built);
+        assert!(!PipelineConfig::low_power().classification.use_embeddings);
+    }
+}
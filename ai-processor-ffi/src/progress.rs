@@ -0,0 +1,118 @@
+//! Progress reporting for long operations
+//!
+//! Batch analysis, group suggestion and cross-recommendation generation can
+//! run for minutes over a large library. They are split into named stages
+//! ("embed", "compare", ...) that report how far they got. The report
+//! function may ask to cancel; the operation then stops at its next step and
+//! the caller checks `Progress::is_cancelled` to discard the partial result.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Receives a stage name and percent done, returns false to cancel
+///
+/// Parallel stages report from several threads.
+pub type ReportFn<'a> = &'a (dyn Fn(&str, f32) -> bool + Sync);
+
+/// Progress of one operation
+pub struct Progress<'a> {
+    report: Option<ReportFn<'a>>,
+    cancelled: AtomicBool,
+}
+
+impl<'a> Progress<'a> {
+    pub fn new(report: ReportFn<'a>) -> Self {
+        Self {
+            report: Some(report),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    /// Progress that is not reported and never cancelled
+    pub fn silent() -> Self {
+        Self {
+            report: None,
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    /// Start a stage of `total` steps, reporting 0%
+    pub fn stage(&self, name: &'static str, total: usize) -> Stage<'_, 'a> {
+        self.report(name, 0.0);
+        Stage {
+            progress: self,
+            name,
+            total: total.max(1),
+            done: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn report(&self, stage: &str, percent: f32) {
+        if let Some(report) = self.report {
+            if !self.is_cancelled() && !report(stage, percent) {
+                self.cancelled.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// A stage of an operation, advanced one step at a time
+pub struct Stage<'p, 'a> {
+    progress: &'p Progress<'a>,
+    name: &'static str,
+    total: usize,
+    done: AtomicUsize,
+}
+
+impl Stage<'_, '_> {
+    /// Record a finished step, returning false if the operation was cancelled
+    ///
+    /// Progress is reported whenever the whole percentage changes.
+    pub fn step(&self) -> bool {
+        let done = (self.done.fetch_add(1, Ordering::Relaxed) + 1).min(self.total);
+        let percent = done * 100 / self.total;
+        if percent != (done - 1) * 100 / self.total {
+            self.progress.report(self.name, percent as f32);
+        }
+        !self.progress.is_cancelled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_reports_each_whole_percent() {
+        let reports = Mutex::new(Vec::new());
+        let report = |stage: &str, percent: f32| {
+            reports.lock().unwrap().push((stage.to_string(), percent));
+            true
+        };
+        let progress = Progress::new(&report);
+        let stage = progress.stage("embed", 4);
+        assert!((0..4).all(|_| stage.step()));
+
+        let percents: Vec<f32> = reports.lock().unwrap().iter().map(|(_, p)| *p).collect();
+        assert_eq!(percents, vec![0.0, 25.0, 50.0, 75.0, 100.0]);
+        assert!(reports.lock().unwrap().iter().all(|(s, _)| s == "embed"));
+    }
+
+    #[test]
+    fn test_report_can_cancel() {
+        let report = |_: &str, percent: f32| percent < 50.0;
+        let progress = Progress::new(&report);
+        let stage = progress.stage("compare", 10);
+        let steps = (0..10).take_while(|_| stage.step()).count();
+        assert_eq!(steps, 4);
+        assert!(progress.is_cancelled());
+
+        let silent = Progress::silent();
+        let stage = silent.stage("compare", 1);
+        assert!(stage.step());
+    }
+}
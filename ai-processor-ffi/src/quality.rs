@@ -0,0 +1,149 @@
+//! Content quality and clickbait scoring
+//!
+//! Rates how worthwhile a page is to keep, so cleanup suggestions can drop
+//! low-value pages first. The score combines ad density in the HTML,
+//! clickbait and listicle title patterns, the share of the HTML that is the
+//! extracted text rather than navigation and other boilerplate, and the
+//! amount of text. All signals are cheap heuristics; scores compare pages
+//! rather than judge them in absolute terms.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Ad markers per 1000 words at which the ad signal is saturated
+const SATURATED_AD_DENSITY: f32 = 20.0;
+/// Text to HTML ratio of a typical article page
+const TYPICAL_TEXT_RATIO: f32 = 0.25;
+/// Pages with fewer words are penalized as thin content
+const MIN_SUBSTANTIAL_WORDS: usize = 300;
+
+/// Signal weights, summing to 1
+const AD_WEIGHT: f32 = 0.3;
+const CLICKBAIT_WEIGHT: f32 = 0.3;
+const BOILERPLATE_WEIGHT: f32 = 0.25;
+const THIN_CONTENT_WEIGHT: f32 = 0.15;
+
+/// Class, id and source fragments of ad containers
+const AD_MARKERS: &[&str] = &[
+    "advert", "adsbygoogle", "ad-slot", "ad-container", "ad-banner", "ad_unit", "sponsored", "doubleclick",
+    "googlesyndication", "taboola", "outbrain", "promoted",
+];
+
+fn clickbait_patterns() -> &'static [Regex] {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            // Listicles: "10 things", "Top 7 reasons"
+            r"^(top )?\d+ (things|reasons|ways|tricks|tips|facts|signs|times|photos|celebrities|secrets)\b",
+            r"you won'?t believe",
+            r"what happen(s|ed) next",
+            r"this one (weird )?(trick|thing)",
+            r"(doctors|experts) hate",
+            r"(will|is going to) (shock|blow your mind|change your life)",
+            r"\b(shocking|unbelievable|jaw-dropping|mind-blowing|insane)\b",
+            r"^(here'?s|this is) (why|what|how)\b",
+            r"\bnumber \d+ will\b",
+            r"[!?]{2,}",
+        ]
+        .iter()
+        .map(|pattern| Regex::new(pattern).expect("clickbait pattern is valid"))
+        .collect()
+    })
+}
+
+/// Quality of a page
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Quality {
+    /// 0 (low value) to 1 (high-quality reference)
+    pub score: f32,
+    /// 0 (plain title) to 1 (clickbait)
+    pub clickbait: f32,
+}
+
+/// Score a page from its title, extracted text and HTML
+///
+/// Without HTML the ad and boilerplate signals are neutral.
+pub fn assess(title: &str, text: &str, html: &str) -> Quality {
+    let words = text.split_whitespace().count();
+    let clickbait = clickbait_score(title);
+
+    let (ads, boilerplate) = if html.is_empty() {
+        (0.0, 0.0)
+    } else {
+        let html_lower = html.to_lowercase();
+        let markers: usize = AD_MARKERS.iter().map(|marker| html_lower.matches(marker).count()).sum();
+        let density = markers as f32 * 1000.0 / words.max(100) as f32;
+        let text_ratio = text.len() as f32 / html.len() as f32;
+        (
+            (density / SATURATED_AD_DENSITY).min(1.0),
+            1.0 - (text_ratio / TYPICAL_TEXT_RATIO).min(1.0),
+        )
+    };
+    let thin = 1.0 - (words as f32 / MIN_SUBSTANTIAL_WORDS as f32).min(1.0);
+
+    let penalty = AD_WEIGHT * ads
+        + CLICKBAIT_WEIGHT * clickbait
+        + BOILERPLATE_WEIGHT * boilerplate
+        + THIN_CONTENT_WEIGHT * thin;
+    Quality {
+        score: (1.0 - penalty).clamp(0.0, 1.0),
+        clickbait,
+    }
+}
+
+/// Share of clickbait signals in a title, saturating at three
+pub fn clickbait_score(title: &str) -> f32 {
+    let title = title.trim();
+    if title.is_empty() {
+        return 0.0;
+    }
+
+    let lower = title.to_lowercase();
+    let mut hits = clickbait_patterns().iter().filter(|pattern| pattern.is_match(&lower)).count();
+
+    // Shouting: several words in capitals
+    let shouted = title
+        .split_whitespace()
+        .filter(|word| word.chars().filter(|c| c.is_alphabetic()).count() > 2)
+        .filter(|word| word.chars().all(|c| !c.is_lowercase()))
+        .count();
+    if shouted >= 2 {
+        hits += 1;
+    }
+
+    (hits as f32 / 3.0).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clickbait_titles() {
+        assert!(clickbait_score("10 Things You Won't Believe Celebrities Do!!") >= 0.66);
+        assert!(clickbait_score("This One Weird Trick Doctors Hate") >= 0.66);
+        assert!(clickbait_score("SHOCKING NEWS about the budget") > 0.3);
+        assert_eq!(clickbait_score("The Rust Programming Language"), 0.0);
+        assert_eq!(clickbait_score("Rust 1.75 release notes"), 0.0);
+    }
+
+    #[test]
+    fn test_reference_outscores_ad_heavy_listicle() {
+        let text = "Ownership is a set of rules that govern how a Rust program manages memory. ".repeat(30);
+        let reference = assess("Understanding Ownership", &text, &format!("<main><p>{}</p></main>", text));
+
+        let listicle_text = "Number one will surprise you. ".repeat(20);
+        let ads = "<div class=\"ad-slot\"></div><iframe src=\"https://doubleclick.net\"></iframe>".repeat(10);
+        let listicle = assess(
+            "15 Tricks You Won't Believe",
+            &listicle_text,
+            &format!("<nav>{}</nav>{}<p>{}</p>", "menu ".repeat(400), ads, listicle_text),
+        );
+
+        assert!(reference.score > 0.9, "{:?}", reference);
+        assert!(listicle.score < 0.5, "{:?}", listicle);
+        assert!(listicle.clickbait > reference.clickbait);
+    }
+}
@@ -0,0 +1,207 @@
+//! Readability scoring
+//!
+//! Computes the Flesch reading ease of a text using the variant calibrated for
+//! its language: Flesch-Kincaid for English, Fernández Huerta for Spanish,
+//! Kandel-Moles for French, Amstad for German and Oborneva for Russian.
+//! Syllables are approximated by counting vowel groups. Chinese, Japanese and
+//! Korean have no comparable formula; their score is derived from the average
+//! sentence length in characters. All scores are on the same 0-100 scale where
+//! higher means easier to read.
+
+use serde::{Deserialize, Serialize};
+
+use crate::language;
+
+/// Coarse difficulty band of a readability score
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    /// Score 80 and above
+    Easy,
+    /// Score 60-80
+    Moderate,
+    /// Score 30-60
+    Difficult,
+    /// Score below 30
+    VeryDifficult,
+}
+
+impl Difficulty {
+    /// Difficulty band of a 0-100 reading ease score
+    pub fn from_score(score: f32) -> Self {
+        if score >= 80.0 {
+            Difficulty::Easy
+        } else if score >= 60.0 {
+            Difficulty::Moderate
+        } else if score >= 30.0 {
+            Difficulty::Difficult
+        } else {
+            Difficulty::VeryDifficult
+        }
+    }
+}
+
+/// Readability of a text
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Readability {
+    /// Reading ease, 0 (very hard) to 100 (very easy)
+    pub score: f32,
+    pub difficulty: Difficulty,
+}
+
+impl Default for Readability {
+    fn default() -> Self {
+        Self::from_score(0.0)
+    }
+}
+
+impl Readability {
+    fn from_score(score: f64) -> Self {
+        let score = score.clamp(0.0, 100.0) as f32;
+        Self {
+            score,
+            difficulty: Difficulty::from_score(score),
+        }
+    }
+}
+
+/// Score the readability of `text` written in `language`
+///
+/// Texts without any words score 0.
+pub fn score(text: &str, language: &str) -> Readability {
+    match language {
+        "zh" | "ja" | "ko" => score_by_characters(text),
+        _ => score_by_syllables(text, language),
+    }
+}
+
+/// Flesch-style formulas over words per sentence and syllables per word
+fn score_by_syllables(text: &str, language: &str) -> Readability {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().any(char::is_alphabetic))
+        .collect();
+    if words.is_empty() {
+        return Readability::default();
+    }
+
+    let syllables: usize = words.iter().map(|w| count_syllables(w, language)).sum();
+    let words_per_sentence = words.len() as f64 / count_sentences(text) as f64;
+    let syllables_per_word = syllables as f64 / words.len() as f64;
+
+    let ease = match language {
+        "es" => 206.84 - 0.60 * syllables_per_word * 100.0 - 102.0 / words_per_sentence,
+        "fr" => 207.0 - 1.015 * words_per_sentence - 73.6 * syllables_per_word,
+        "de" => 180.0 - words_per_sentence - 58.5 * syllables_per_word,
+        "ru" => 206.835 - 1.3 * words_per_sentence - 60.1 * syllables_per_word,
+        _ => 206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word,
+    };
+    Readability::from_score(ease)
+}
+
+/// Sentence length based score for scripts without syllable-delimited words
+///
+/// Ten characters per sentence or fewer scores 100, each further character
+/// costs two points.
+fn score_by_characters(text: &str) -> Readability {
+    let characters = text
+        .chars()
+        .filter(|c| language::is_han(*c) || language::is_kana(*c) || language::is_hangul(*c) || c.is_alphanumeric())
+        .count();
+    if characters == 0 {
+        return Readability::default();
+    }
+
+    let characters_per_sentence = characters as f64 / count_sentences(text) as f64;
+    Readability::from_score(100.0 - 2.0 * (characters_per_sentence - 10.0).max(0.0))
+}
+
+/// Number of sentences, at least one
+fn count_sentences(text: &str) -> usize {
+    let mut count = 0;
+    let mut in_sentence = false;
+    for c in text.chars() {
+        if matches!(c, '.' | '!' | '?' | '。' | '！' | '？') {
+            if in_sentence {
+                count += 1;
+            }
+            in_sentence = false;
+        } else if c.is_alphanumeric() {
+            in_sentence = true;
+        }
+    }
+    if in_sentence {
+        count += 1;
+    }
+    count.max(1)
+}
+
+/// Approximate syllable count of one word as its number of vowel groups
+fn count_syllables(word: &str, language: &str) -> usize {
+    let word = word.to_lowercase();
+    let vowels = if language == "ru" { "аеёиоуыэюя" } else { "aeiouyàáâäèéêëìíîïòóôöùúûü" };
+
+    let mut groups = 0;
+    let mut previous_vowel = false;
+    for c in word.chars() {
+        let vowel = vowels.contains(c);
+        if vowel && !previous_vowel {
+            groups += 1;
+        }
+        previous_vowel = vowel;
+    }
+
+    // English drops the final "e" in words like "make", but not in "the" or "table"
+    if language == "en" && groups > 1 && word.ends_with('e') && !word.ends_with("le") {
+        groups -= 1;
+    }
+    groups.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_syllables() {
+        assert_eq!(count_syllables("cat", "en"), 1);
+        assert_eq!(count_syllables("make", "en"), 1);
+        assert_eq!(count_syllables("table", "en"), 2);
+        assert_eq!(count_syllables("readability", "en"), 5);
+        assert_eq!(count_syllables("молоко", "ru"), 3);
+    }
+
+    #[test]
+    fn test_simple_text_easier_than_complex_text() {
+        let simple = score("The cat sat on the mat. It was warm. The dog ran.", "en");
+        let complex = score(
+            "Notwithstanding considerable methodological heterogeneity, comprehensive \
+             epidemiological investigations consistently demonstrate statistically \
+             significant associations between socioeconomic deprivation and cardiovascular morbidity.",
+            "en",
+        );
+        assert_eq!(simple.difficulty, Difficulty::Easy);
+        assert_eq!(complex.difficulty, Difficulty::VeryDifficult);
+        assert!(simple.score > complex.score);
+    }
+
+    #[test]
+    fn test_language_specific_formulas_stay_in_range() {
+        for (text, lang) in [
+            ("Der Hund spielt im Garten. Die Sonne scheint.", "de"),
+            ("El perro juega en el jardín. Hace sol.", "es"),
+            ("Le chien joue dans le jardin. Il fait beau.", "fr"),
+            ("Собака играет в саду. Светит солнце.", "ru"),
+            ("我们正在学习机器学习。天气很好。", "zh"),
+        ] {
+            let readability = score(text, lang);
+            assert!(readability.score > 0.0 && readability.score <= 100.0, "{}: {:?}", lang, readability);
+        }
+    }
+
+    #[test]
+    fn test_empty_text_scores_zero() {
+        assert_eq!(score("", "en").score, 0.0);
+        assert_eq!(score("123 456", "en").score, 0.0);
+        assert_eq!(score("", "zh").score, 0.0);
+    }
+}
@@ -0,0 +1,288 @@
+//! Reader-mode extraction
+//!
+//! Extracts a clean, readable version of a page to render offline: its
+//! title, byline and main content as simple HTML of headings, paragraphs,
+//! lists, quotes, code blocks and images. The main content is the page's
+//! `<article>` or `<main>` element when it has one, otherwise the body;
+//! scripts, styles, forms, navigation, headers, footers and asides are
+//! dropped, as are blocks made mostly of links. Image URLs are resolved
+//! against the page URL.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::media::{attribute, attributes, resolve_url};
+
+/// Pages with fewer words in their main content have no reader version
+const MIN_WORDS: usize = 20;
+
+/// Blocks with a larger share of their text in links are navigation
+const MAX_LINK_DENSITY: f32 = 0.5;
+
+/// Images with a known side below this many pixels are icons or pixels
+const MIN_IMAGE_SIDE: u32 = 50;
+
+/// Reader version of a page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReaderDocument {
+    pub title: String,
+    pub byline: Option<String>,
+    /// Main content as simple, self-contained HTML
+    pub content_html: String,
+    /// Main content as plain text, one block per paragraph
+    pub text: String,
+    /// Absolute URLs of the images in the main content
+    pub images: Vec<String>,
+    pub word_count: usize,
+}
+
+struct Patterns {
+    comment: Regex,
+    unreadable: Regex,
+    boilerplate: Regex,
+    article: Regex,
+    main: Regex,
+    body: Regex,
+    block: Regex,
+    link: Regex,
+    tag: Regex,
+    meta: Regex,
+    title: Regex,
+    h1: Regex,
+    byline: Regex,
+    whitespace: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let regex = |pattern: &str| Regex::new(pattern).expect("reader pattern is valid");
+        Patterns {
+            comment: regex(r"(?s)<!--.*?-->"),
+            unreadable: regex(
+                r"(?is)<script\b.*?</script>|<style\b.*?</style>|<noscript\b.*?</noscript>|<template\b.*?</template>|<svg\b.*?</svg>|<form\b.*?</form>|<button\b.*?</button>",
+            ),
+            boilerplate: regex(r"(?is)<nav\b.*?</nav>|<header\b.*?</header>|<footer\b.*?</footer>|<aside\b.*?</aside>"),
+            article: regex(r"(?is)<article\b[^>]*>(.*)</article>"),
+            main: regex(r"(?is)<main\b[^>]*>(.*)</main>"),
+            body: regex(r"(?is)<body\b[^>]*>(.*)</body>"),
+            block: regex(
+                r"(?is)<pre\b[^>]*>(.*?)</pre>|<(h[1-6]|p|li|blockquote)\b[^>]*>(.*?)</(?:h[1-6]|p|li|blockquote)>|<img\s[^>]*>",
+            ),
+            link: regex(r"(?is)<a\b[^>]*>(.*?)</a>"),
+            tag: regex(r"(?s)<[^>]*>"),
+            meta: regex(r"(?is)<meta\s[^>]*>"),
+            title: regex(r"(?is)<title\b[^>]*>(.*?)</title>"),
+            h1: regex(r"(?is)<h1\b[^>]*>(.*?)</h1>"),
+            byline: regex(
+                r#"(?is)<(?:[a-z]+)\b[^>]*(?:class|rel|itemprop)\s*=\s*["'][^"']*\b(?:byline|author)\b[^"']*["'][^>]*>(.*?)</"#,
+            ),
+            whitespace: regex(r"\s+"),
+        }
+    })
+}
+
+/// Extract the reader version of a page, or None if it has too little
+/// readable content
+pub fn extract(html: &str, page_url: Option<&str>) -> Option<ReaderDocument> {
+    let patterns = patterns();
+    let cleaned = patterns.comment.replace_all(html, "");
+    let cleaned = patterns.unreadable.replace_all(&cleaned, "");
+
+    let region = patterns
+        .article
+        .captures(&cleaned)
+        .or_else(|| patterns.main.captures(&cleaned))
+        .or_else(|| patterns.body.captures(&cleaned))
+        .map_or(cleaned.to_string(), |c| c[1].to_string());
+    let region = patterns.boilerplate.replace_all(&region, "");
+
+    let title = meta_content(html, &["og:title", "twitter:title"])
+        .or_else(|| patterns.h1.captures(&region).map(|c| plain_text(&c[1])))
+        .or_else(|| patterns.title.captures(html).map(|c| plain_text(&c[1])))
+        .filter(|t| !t.is_empty())
+        .unwrap_or_default();
+    let byline = meta_content(html, &["author", "article:author"])
+        .filter(|a| !a.contains("://"))
+        .or_else(|| {
+            patterns
+                .byline
+                .captures(&region)
+                .map(|c| plain_text(&c[1]))
+                .filter(|b| !b.is_empty() && b.chars().count() <= 100)
+        });
+
+    let mut content_html = String::new();
+    let mut blocks = Vec::new();
+    let mut images = Vec::new();
+    let mut in_list = false;
+    for block in patterns.block.captures_iter(&region) {
+        let (element, inner) = match (block.get(1), block.get(2)) {
+            (Some(code), _) => ("pre", code.as_str()),
+            (None, Some(name)) => (name.as_str(), block.get(3).map_or("", |m| m.as_str())),
+            (None, None) => ("img", block.get(0).map_or("", |m| m.as_str())),
+        };
+        let element = element.to_lowercase();
+
+        let rendered = if element == "img" {
+            let attributes = attributes(inner);
+            let Some(src) = attribute(&attributes, "src").or_else(|| attribute(&attributes, "data-src")) else {
+                continue;
+            };
+            let small = ["width", "height"].iter().any(|side| {
+                attribute(&attributes, side)
+                    .and_then(|v| v.trim_end_matches("px").parse::<u32>().ok())
+                    .is_some_and(|v| v < MIN_IMAGE_SIDE)
+            });
+            if small || src.starts_with("data:") {
+                continue;
+            }
+            let src = resolve_url(src.trim(), page_url);
+            let alt = attribute(&attributes, "alt").map(|a| plain_text(&a)).unwrap_or_default();
+            images.push(src.clone());
+            format!("<img src=\"{}\" alt=\"{}\">", escape(&src), escape(&alt))
+        } else if element == "pre" {
+            let code = decode_entities(&patterns.tag.replace_all(inner, ""));
+            if code.trim().is_empty() {
+                continue;
+            }
+            blocks.push(code.trim().to_string());
+            format!("<pre>{}</pre>", escape(code.trim_matches('\n')))
+        } else {
+            let text = plain_text(inner);
+            if text.is_empty() || link_density(inner) > MAX_LINK_DENSITY || (element == "h1" && text == title) {
+                continue;
+            }
+            blocks.push(text.clone());
+            format!("<{0}>{1}</{0}>", element, escape(&text))
+        };
+
+        match (element == "li", in_list) {
+            (true, false) => content_html.push_str("<ul>"),
+            (false, true) => content_html.push_str("</ul>"),
+            _ => {}
+        }
+        in_list = element == "li";
+        content_html.push_str(&rendered);
+    }
+    if in_list {
+        content_html.push_str("</ul>");
+    }
+
+    let text = blocks.join("\n\n");
+    let word_count = text.split_whitespace().count();
+    if word_count < MIN_WORDS {
+        return None;
+    }
+    Some(ReaderDocument {
+        title,
+        byline,
+        content_html,
+        text,
+        images,
+        word_count,
+    })
+}
+
+/// Content of the first `<meta>` with one of `names` as name or property
+fn meta_content(html: &str, names: &[&str]) -> Option<String> {
+    patterns().meta.find_iter(html).find_map(|tag| {
+        let attributes = attributes(tag.as_str());
+        let name = attribute(&attributes, "property").or_else(|| attribute(&attributes, "name"))?;
+        names
+            .contains(&name.to_lowercase().as_str())
+            .then(|| attribute(&attributes, "content").map(|c| plain_text(&c)))
+            .flatten()
+            .filter(|c| !c.is_empty())
+    })
+}
+
+/// Share of a block's text inside links
+fn link_density(html: &str) -> f32 {
+    let total = plain_text(html).chars().count();
+    if total == 0 {
+        return 0.0;
+    }
+    let linked: usize = patterns().link.captures_iter(html).map(|c| plain_text(&c[1]).chars().count()).sum();
+    linked as f32 / total as f32
+}
+
+fn plain_text(html: &str) -> String {
+    let patterns = patterns();
+    let text = decode_entities(&patterns.tag.replace_all(html, " "));
+    patterns.whitespace.replace_all(&text, " ").trim().to_string()
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html><head>
+        <title>Sourdough basics | Bakery Blog</title>
+        <meta property="og:title" content="Sourdough basics">
+        <meta name="author" content="Ana Baker">
+        <script>track();</script>
+        </head><body>
+        <nav><ul><li><a href="/">Home</a></li><li><a href="/recipes">Recipes</a></li></ul></nav>
+        <article>
+          <h1>Sourdough basics</h1>
+          <p>A sourdough starter is a culture of wild yeast and bacteria that leavens bread &amp; gives it flavor.</p>
+          <img src="/img/starter.jpg" alt="A bubbly starter" width="800" height="600">
+          <img src="/pixel.gif" width="1" height="1">
+          <h2>Feeding</h2>
+          <ul><li>Discard half of the starter</li><li>Add equal weights of flour and water</li></ul>
+          <pre><code>ratio = 1:1:1</code></pre>
+          <p>See: <a href="/a">one</a> <a href="/b">two</a> <a href="/c">three</a></p>
+          <aside><p>Subscribe to our newsletter for more recipes every week.</p></aside>
+        </article>
+        <footer><p>Copyright</p></footer>
+        </body></html>"#;
+
+    #[test]
+    fn test_extracts_main_content() {
+        let reader = extract(PAGE, Some("https://bakery.example.com/posts/sourdough")).unwrap();
+        assert_eq!(reader.title, "Sourdough basics");
+        assert_eq!(reader.byline.as_deref(), Some("Ana Baker"));
+        assert_eq!(reader.images, vec!["https://bakery.example.com/img/starter.jpg"]);
+        assert_eq!(
+            reader.content_html,
+            "<p>A sourdough starter is a culture of wild yeast and bacteria that leavens bread &amp; gives it flavor.</p>\
+             <img src=\"https://bakery.example.com/img/starter.jpg\" alt=\"A bubbly starter\">\
+             <h2>Feeding</h2>\
+             <ul><li>Discard half of the starter</li><li>Add equal weights of flour and water</li></ul>\
+             <pre>ratio = 1:1:1</pre>"
+        );
+        assert!(reader.text.starts_with("A sourdough starter"));
+        assert!(!reader.text.contains("Subscribe"));
+        assert_eq!(reader.word_count, reader.text.split_whitespace().count());
+    }
+
+    #[test]
+    fn test_byline_from_markup_and_thin_pages() {
+        let html = format!(
+            r#"<body><div class="post-byline">By <a href="/lee">Lee</a></div><p>{}</p></body>"#,
+            "Word ".repeat(30)
+        );
+        let reader = extract(&html, None).unwrap();
+        assert_eq!(reader.byline.as_deref(), Some("By Lee"));
+        assert_eq!(reader.title, "");
+
+        assert!(extract("<body><p>Too short to read.</p></body>", None).is_none());
+    }
+}
@@ -0,0 +1,162 @@
+//! "Why you saved this" recall hints
+//!
+//! A saved tab is easier to rediscover weeks later if it carries a reminder
+//! of the moment it was saved: when, from which tab group, what the page was
+//! about and which related tabs were open next to it. The hint is built from
+//! a template; a loaded local model may rephrase the same facts.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, FixedOffset, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::stemming;
+
+/// Related tabs named in a hint
+const MAX_RELATED_TABS: usize = 2;
+/// Keywords named in a hint
+const MAX_TOPICS: usize = 2;
+
+/// Circumstances in which a page was saved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveContext {
+    /// Name of the tab group the page was saved from
+    #[serde(default)]
+    pub tab_group: Option<String>,
+    /// Local time of saving, as RFC 3339 with the user's UTC offset
+    pub saved_at: DateTime<FixedOffset>,
+    /// Titles of the other tabs open at the time
+    #[serde(default)]
+    pub concurrent_tabs: Vec<String>,
+}
+
+/// Facts a recall hint is made of
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecallFacts {
+    pub when: String,
+    pub tab_group: Option<String>,
+    pub topics: Vec<String>,
+    /// Open tabs sharing keywords with the page
+    pub related_tabs: Vec<String>,
+    pub other_tab_count: usize,
+}
+
+impl RecallFacts {
+    /// Collect the facts for a page with the given keywords
+    pub fn gather(keywords: &[String], context: &SaveContext, language: &str) -> Self {
+        let keyword_stems: HashSet<String> = keywords
+            .iter()
+            .flat_map(|k| stemming::phrase_key(k, language).split(' ').map(str::to_string).collect::<Vec<_>>())
+            .collect();
+
+        let related_tabs: Vec<String> = context
+            .concurrent_tabs
+            .iter()
+            .filter(|title| {
+                crate::tokenize(title)
+                    .iter()
+                    .any(|token| keyword_stems.contains(&stemming::stem(token, language)))
+            })
+            .cloned()
+            .collect();
+
+        Self {
+            when: describe_time(&context.saved_at),
+            tab_group: context.tab_group.clone().filter(|g| !g.trim().is_empty()),
+            topics: keywords.iter().take(MAX_TOPICS).cloned().collect(),
+            other_tab_count: context.concurrent_tabs.len() - related_tabs.len(),
+            related_tabs,
+        }
+    }
+
+    /// One-sentence hint built from the facts
+    pub fn to_hint(&self) -> String {
+        let mut hint = format!("Saved {}", self.when);
+        if let Some(group) = &self.tab_group {
+            hint.push_str(&format!(" from the \"{}\" tab group", group));
+        }
+        if !self.topics.is_empty() {
+            hint.push_str(&format!(" while reading about {}", self.topics.join(" and ")));
+        }
+
+        match self.related_tabs.len() {
+            0 if self.other_tab_count > 0 => {
+                hint.push_str(&format!(", with {} other {} open", self.other_tab_count, plural(self.other_tab_count, "tab")));
+            }
+            0 => {}
+            count => {
+                let named: Vec<String> = self
+                    .related_tabs
+                    .iter()
+                    .take(MAX_RELATED_TABS)
+                    .map(|title| format!("\"{}\"", title))
+                    .collect();
+                let lead = if count > named.len() { "such as " } else { "" };
+                hint.push_str(&format!(", next to {} related {} {}{}", count, plural(count, "tab"), lead, named.join(" and ")));
+            }
+        }
+        hint.push('.');
+        hint
+    }
+}
+
+/// "on Tuesday evening, 3 March 2026"
+fn describe_time(time: &DateTime<FixedOffset>) -> String {
+    let period = match time.hour() {
+        5..=11 => "morning",
+        12..=16 => "afternoon",
+        17..=21 => "evening",
+        _ => "night",
+    };
+    format!("on {} {}, {}", time.format("%A"), period, time.format("%-d %B %Y"))
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        word.to_string()
+    } else {
+        format!("{}s", word)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(tabs: &[&str]) -> SaveContext {
+        SaveContext {
+            tab_group: Some("Trip planning".to_string()),
+            saved_at: DateTime::parse_from_rfc3339("2026-03-03T19:30:00+01:00").unwrap(),
+            concurrent_tabs: tabs.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    fn keywords(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_hint_names_time_group_topics_and_related_tabs() {
+        let context = context(&["Cheap flights to Lisbon", "Inbox", "Lisbon hotel deals"]);
+        let facts = RecallFacts::gather(&keywords(&["lisbon", "hotels", "trams"]), &context, "en");
+        assert_eq!(facts.related_tabs, vec!["Cheap flights to Lisbon", "Lisbon hotel deals"]);
+        assert_eq!(
+            facts.to_hint(),
+            "Saved on Tuesday evening, 3 March 2026 from the \"Trip planning\" tab group \
+             while reading about lisbon and hotels, next to 2 related tabs \
+             \"Cheap flights to Lisbon\" and \"Lisbon hotel deals\"."
+        );
+    }
+
+    #[test]
+    fn test_hint_without_related_tabs() {
+        let mut context = context(&["Inbox"]);
+        context.tab_group = None;
+        let facts = RecallFacts::gather(&keywords(&["rust"]), &context, "en");
+        assert_eq!(facts.to_hint(), "Saved on Tuesday evening, 3 March 2026 while reading about rust, with 1 other tab open.");
+
+        context.concurrent_tabs.clear();
+        let facts = RecallFacts::gather(&[], &context, "en");
+        assert_eq!(facts.to_hint(), "Saved on Tuesday evening, 3 March 2026.");
+    }
+}
@@ -0,0 +1,304 @@
+//! Content-hash keyed cache of analysis results
+//!
+//! Summaries, keywords and classifications are cached under a hash of the
+//! analyzed content and the processor settings that influence the result, so
+//! re-analyzing an unchanged page is a lookup. Entries can be exported and
+//! imported as JSON so the host can persist them (for example with the
+//! data-access analysis cache repository) across sessions.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::embedding::fnv1a;
+use crate::PageContentInput;
+
+/// Default maximum number of cached results
+pub const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+
+/// Kind of analysis a cached result belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisKind {
+    Summary,
+    Keywords,
+    Classification,
+}
+
+impl AnalysisKind {
+    fn tag(self) -> &'static str {
+        match self {
+            AnalysisKind::Summary => "summary",
+            AnalysisKind::Keywords => "keywords",
+            AnalysisKind::Classification => "classification",
+        }
+    }
+}
+
+/// Exported cache entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry<V> {
+    pub content_hash: String,
+    pub analysis: V,
+}
+
+/// Cache statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+struct Slot<V> {
+    value: V,
+    last_used: u64,
+}
+
+struct Inner<V> {
+    entries: HashMap<String, Slot<V>>,
+    capacity: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+/// Bounded least-recently-used cache of analysis results
+pub struct ResultCache<V> {
+    inner: Mutex<Inner<V>>,
+}
+
+impl<V: Clone> ResultCache<V> {
+    /// Create a cache holding at most `capacity` results (0 disables caching)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                entries: HashMap::new(),
+                capacity,
+                clock: 0,
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner<V>> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Look up a cached result
+    pub fn get(&self, key: &str) -> Option<V> {
+        let mut inner = self.lock();
+        inner.clock += 1;
+        let clock = inner.clock;
+        match inner.entries.get_mut(key) {
+            Some(slot) => {
+                slot.last_used = clock;
+                let value = slot.value.clone();
+                inner.hits += 1;
+                Some(value)
+            }
+            None => {
+                inner.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store a result, evicting the least recently used tenth when full
+    pub fn insert(&self, key: String, value: V) {
+        let mut inner = self.lock();
+        if inner.capacity == 0 {
+            return;
+        }
+
+        inner.clock += 1;
+        let last_used = inner.clock;
+        inner.entries.insert(key, Slot { value, last_used });
+
+        if inner.entries.len() > inner.capacity {
+            let mut ages: Vec<u64> = inner.entries.values().map(|s| s.last_used).collect();
+            ages.sort_unstable();
+            let evict = (inner.capacity / 10).max(1).min(ages.len() - 1);
+            let cutoff = ages[evict];
+            inner.entries.retain(|_, slot| slot.last_used >= cutoff);
+        }
+    }
+
+    /// Return the cached result for `key` or compute and store it
+    pub fn get_or_insert_with(&self, key: String, compute: impl FnOnce() -> V) -> V {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        let value = compute();
+        self.insert(key, value.clone());
+        value
+    }
+
+    /// Change the capacity, dropping all entries if it shrinks below the current size
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut inner = self.lock();
+        inner.capacity = capacity;
+        if inner.entries.len() > capacity {
+            inner.entries.clear();
+        }
+    }
+
+    /// Remove all entries and reset statistics
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.entries.clear();
+        inner.hits = 0;
+        inner.misses = 0;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.lock();
+        CacheStats {
+            hits: inner.hits,
+            misses: inner.misses,
+            entries: inner.entries.len(),
+        }
+    }
+
+    /// All entries, most recently used first
+    pub fn export(&self) -> Vec<CacheEntry<V>> {
+        let inner = self.lock();
+        let mut entries: Vec<(&String, &Slot<V>)> = inner.entries.iter().collect();
+        entries.sort_by_key(|(_, slot)| std::cmp::Reverse(slot.last_used));
+        entries
+            .into_iter()
+            .map(|(key, slot)| CacheEntry {
+                content_hash: key.clone(),
+                analysis: slot.value.clone(),
+            })
+            .collect()
+    }
+
+    /// Add previously exported entries, most recently used first as
+    /// `export` returns them, keeping the most recent up to the capacity
+    pub fn import(&self, entries: Vec<CacheEntry<V>>) {
+        // Oldest first, so the most recently used entry is used last
+        for entry in entries.into_iter().rev() {
+            self.insert(entry.content_hash, entry.analysis);
+        }
+    }
+}
+
+/// Cache key for an analysis of `content` under the given settings fingerprint
+pub fn cache_key(kind: AnalysisKind, fingerprint: &str, content: &PageContentInput) -> String {
+    let mut bytes = Vec::with_capacity(content.text.len() + content.title.len() + 64);
+    for part in [kind.tag(), fingerprint, &content.title, &content.text] {
+        bytes.extend_from_slice(part.as_bytes());
+        bytes.push(0);
+    }
+    if let Some(description) = &content.description {
+        bytes.extend_from_slice(description.as_bytes());
+    }
+    bytes.push(0);
+    if let Some(url) = &content.url {
+        bytes.extend_from_slice(url.as_bytes());
+    }
+    bytes.push(0);
+    for keyword in &content.keywords {
+        bytes.extend_from_slice(keyword.as_bytes());
+        bytes.push(0);
+    }
+    format!("{:016x}", fnv1a(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content(text: &str) -> PageContentInput {
+        PageContentInput {
+            html: String::new(),
+            text: text.to_string(),
+            title: "Title".to_string(),
+            description: None,
+            keywords: vec![],
+            images: vec![],
+            links: vec![],
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_cache_key_depends_on_content_kind_and_settings() {
+        let a = cache_key(AnalysisKind::Summary, "basic", &content("one"));
+        assert_eq!(a, cache_key(AnalysisKind::Summary, "basic", &content("one")));
+        assert_ne!(a, cache_key(AnalysisKind::Summary, "basic", &content("two")));
+        assert_ne!(a, cache_key(AnalysisKind::Keywords, "basic", &content("one")));
+        assert_ne!(a, cache_key(AnalysisKind::Summary, "llm", &content("one")));
+        assert_eq!(a.len(), 16);
+    }
+
+    #[test]
+    fn test_get_or_insert_counts_hits() {
+        let cache = ResultCache::new(10);
+        let mut computed = 0;
+        for _ in 0..3 {
+            let value = cache.get_or_insert_with("k".to_string(), || {
+                computed += 1;
+                42
+            });
+            assert_eq!(value, 42);
+        }
+        assert_eq!(computed, 1);
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 1, entries: 1 });
+    }
+
+    #[test]
+    fn test_eviction_keeps_recent_entries() {
+        let cache = ResultCache::new(10);
+        for i in 0..10 {
+            cache.insert(format!("k{}", i), i);
+        }
+        // Touch the oldest entry so it survives eviction
+        assert_eq!(cache.get("k0"), Some(0));
+        cache.insert("k10".to_string(), 10);
+
+        assert!(cache.stats().entries <= 10);
+        assert_eq!(cache.get("k0"), Some(0));
+        assert_eq!(cache.get("k10"), Some(10));
+        assert_eq!(cache.get("k1"), None);
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let cache = ResultCache::new(10);
+        cache.insert("a".to_string(), "alpha".to_string());
+        cache.insert("b".to_string(), "beta".to_string());
+
+        let exported = cache.export();
+        assert_eq!(exported[0].content_hash, "b");
+
+        let restored = ResultCache::new(10);
+        restored.import(exported);
+        assert_eq!(restored.get("a"), Some("alpha".to_string()));
+    }
+
+    #[test]
+    fn test_import_into_smaller_cache_keeps_recent_entries() {
+        let cache = ResultCache::new(10);
+        for i in 0..5 {
+            cache.insert(format!("k{}", i), i);
+        }
+        assert_eq!(cache.get("k1"), Some(1));
+
+        let restored = ResultCache::new(3);
+        restored.import(cache.export());
+        assert!(restored.stats().entries <= 3);
+        assert_eq!(restored.get("k1"), Some(1));
+        assert_eq!(restored.get("k4"), Some(4));
+        assert_eq!(restored.get("k0"), None);
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_cache() {
+        let cache = ResultCache::new(0);
+        cache.insert("a".to_string(), 1);
+        assert_eq!(cache.get("a"), None);
+    }
+}
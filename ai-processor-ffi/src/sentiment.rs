@@ -0,0 +1,456 @@
+//! Lexicon-based sentiment analysis
+//!
+//! Each sentence is scored from the polarity words it contains, using the
+//! English lexicon together with the lexicon of the detected language.
+//! Intensifiers ("very") and diminishers ("slightly") directly before a word
+//! scale it, and a negator shortly before it ("not good", "no es bueno")
+//! flips and dampens it. Chinese and Japanese are matched by substring since
+//! they are not space-delimited; Japanese negation follows the word
+//! ("良くない"). The document score weighs sentences by how much sentiment
+//! they carry.
+
+use crate::language;
+
+/// Score magnitude beyond which text is labeled positive or negative
+const LABEL_THRESHOLD: f32 = 0.3;
+/// Factor applied to a negated word
+const NEGATION_FACTOR: f32 = -0.75;
+/// Words before a polarity word in which a negator applies
+const NEGATION_WINDOW: usize = 3;
+
+/// Overall polarity of a text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SentimentLabel {
+    Positive,
+    #[default]
+    Neutral,
+    Negative,
+}
+
+impl SentimentLabel {
+    fn from_score(score: f32) -> Self {
+        if score > LABEL_THRESHOLD {
+            SentimentLabel::Positive
+        } else if score < -LABEL_THRESHOLD {
+            SentimentLabel::Negative
+        } else {
+            SentimentLabel::Neutral
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SentimentLabel::Positive => "positive",
+            SentimentLabel::Neutral => "neutral",
+            SentimentLabel::Negative => "negative",
+        }
+    }
+}
+
+/// Sentiment of one sentence
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentenceSentiment {
+    pub text: String,
+    /// Byte offset of the sentence in the analyzed text
+    pub offset: usize,
+    /// -1.0 (negative) to 1.0 (positive)
+    pub score: f32,
+    pub label: SentimentLabel,
+}
+
+/// Sentiment of a document and its sentences, neutral by default
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Sentiment {
+    pub label: SentimentLabel,
+    /// -1.0 (negative) to 1.0 (positive)
+    pub score: f32,
+    pub sentences: Vec<SentenceSentiment>,
+}
+
+/// Polarity words and modifiers of one language
+struct Lexicon {
+    positive: &'static [&'static str],
+    negative: &'static [&'static str],
+    negators: &'static [&'static str],
+    intensifiers: &'static [&'static str],
+    diminishers: &'static [&'static str],
+}
+
+const ENGLISH: Lexicon = Lexicon {
+    positive: &[
+        "good", "great", "excellent", "amazing", "wonderful", "fantastic",
+        "awesome", "best", "love", "happy", "beautiful", "perfect",
+        "brilliant", "outstanding", "superb", "incredible", "positive",
+        "success", "successful", "win", "winner", "benefit", "helpful",
+        "easy", "simple", "fast", "efficient", "effective", "recommend",
+        "like", "enjoy", "pleased", "satisfied", "impressive", "innovative",
+        "nice", "glad", "reliable", "useful",
+    ],
+    negative: &[
+        "bad", "terrible", "awful", "horrible", "worst", "hate", "poor",
+        "disappointing", "disappointed", "fail", "failure", "problem",
+        "issue", "bug", "error", "wrong", "broken", "slow", "difficult",
+        "hard", "complicated", "confusing", "frustrating", "annoying",
+        "useless", "waste", "expensive", "overpriced", "scam", "fake",
+        "impossible", "unfortunately", "sadly", "ugly", "unreliable",
+    ],
+    negators: &[
+        "not", "no", "never", "neither", "nor", "none", "nothing", "cannot",
+        "without", "hardly", "isn't", "aren't", "wasn't", "weren't", "don't",
+        "doesn't", "didn't", "won't", "wouldn't", "can't", "couldn't",
+        "shouldn't", "isnt", "dont", "doesnt", "didnt", "cant", "wont",
+    ],
+    intensifiers: &[
+        "very", "really", "extremely", "so", "too", "incredibly", "highly",
+        "totally", "absolutely", "super", "most",
+    ],
+    diminishers: &["slightly", "somewhat", "fairly", "rather", "barely", "kinda", "bit"],
+};
+
+const SPANISH: Lexicon = Lexicon {
+    positive: &[
+        "bueno", "buena", "buenos", "buenas", "excelente", "genial",
+        "maravilloso", "maravillosa", "fantástico", "fantástica", "mejor",
+        "perfecto", "perfecta", "feliz", "encanta", "encantó", "útil",
+        "fácil", "rápido", "rápida", "recomiendo", "increíble", "bonito",
+        "bonita", "eficaz",
+    ],
+    negative: &[
+        "malo", "mala", "malos", "malas", "terrible", "horrible", "peor",
+        "odio", "pésimo", "pésima", "problema", "error", "roto", "rota",
+        "lento", "lenta", "difícil", "caro", "cara", "inútil", "fallo",
+        "decepcionante", "decepcionado", "desafortunadamente",
+    ],
+    negators: &["no", "nunca", "jamás", "ni", "nada", "ningún", "ninguna", "tampoco", "sin"],
+    intensifiers: &["muy", "muchísimo", "realmente", "totalmente", "súper", "tan", "extremadamente"],
+    diminishers: &["poco", "algo", "ligeramente", "apenas"],
+};
+
+const FRENCH: Lexicon = Lexicon {
+    positive: &[
+        "bon", "bonne", "bons", "bonnes", "excellent", "excellente", "génial",
+        "géniale", "merveilleux", "parfait", "parfaite", "meilleur",
+        "meilleure", "heureux", "heureuse", "aime", "adore", "utile",
+        "facile", "rapide", "recommande", "incroyable", "beau", "belle",
+        "efficace", "super",
+    ],
+    negative: &[
+        "mauvais", "mauvaise", "terrible", "horrible", "pire", "déteste",
+        "nul", "nulle", "problème", "erreur", "cassé", "cassée", "lent",
+        "lente", "difficile", "cher", "chère", "inutile", "décevant",
+        "décevante", "déçu", "déçue", "malheureusement", "échec",
+    ],
+    negators: &["ne", "pas", "jamais", "rien", "aucun", "aucune", "ni", "sans", "guère"],
+    intensifiers: &["très", "vraiment", "extrêmement", "trop", "tellement", "totalement", "absolument"],
+    diminishers: &["peu", "assez", "plutôt", "légèrement"],
+};
+
+const GERMAN: Lexicon = Lexicon {
+    positive: &[
+        "gut", "gute", "guter", "gutes", "toll", "super", "ausgezeichnet",
+        "hervorragend", "wunderbar", "perfekt", "beste", "besten", "glücklich",
+        "liebe", "nützlich", "einfach", "schnell", "empfehlen", "empfehle",
+        "schön", "schöne", "großartig", "zufrieden", "effizient",
+    ],
+    negative: &[
+        "schlecht", "schlechte", "schlechter", "schrecklich", "furchtbar",
+        "schlimm", "schlimmste", "hasse", "problem", "fehler", "kaputt",
+        "langsam", "schwierig", "teuer", "nutzlos", "enttäuschend",
+        "enttäuscht", "leider", "mangelhaft",
+    ],
+    negators: &["nicht", "kein", "keine", "keinen", "keiner", "nie", "niemals", "nichts", "ohne", "weder"],
+    intensifiers: &["sehr", "wirklich", "extrem", "total", "absolut", "besonders", "echt", "zu"],
+    diminishers: &["etwas", "ziemlich", "leicht", "kaum", "bisschen"],
+};
+
+const RUSSIAN: Lexicon = Lexicon {
+    positive: &[
+        "хороший", "хорошая", "хорошее", "хорошо", "отличный", "отличная",
+        "отлично", "прекрасный", "прекрасно", "замечательный", "замечательно",
+        "лучший", "лучшая", "идеальный", "люблю", "нравится", "полезный",
+        "полезно", "удобный", "удобно", "быстрый", "быстро", "рекомендую",
+        "счастлив", "красивый",
+    ],
+    negative: &[
+        "плохой", "плохая", "плохое", "плохо", "ужасный", "ужасная", "ужасно",
+        "худший", "ненавижу", "проблема", "ошибка", "сломан", "сломано",
+        "медленный", "медленно", "сложный", "сложно", "дорогой", "дорого",
+        "бесполезный", "разочарован", "разочарование", "неудобно", "сожалению",
+    ],
+    negators: &["не", "нет", "ни", "никогда", "ничего", "без", "нельзя"],
+    intensifiers: &["очень", "крайне", "действительно", "совсем", "абсолютно", "слишком", "весьма"],
+    diminishers: &["немного", "слегка", "чуть", "довольно"],
+};
+
+const CHINESE: Lexicon = Lexicon {
+    positive: &[
+        "好", "很好", "优秀", "出色", "喜欢", "满意", "完美", "精彩", "推荐",
+        "方便", "快", "有用", "开心", "高兴", "棒", "漂亮", "成功", "不错",
+    ],
+    negative: &[
+        "差", "糟糕", "讨厌", "失望", "问题", "错误", "坏", "慢", "难", "贵",
+        "没用", "失败", "垃圾", "难用", "遗憾", "可惜",
+    ],
+    negators: &["不", "没", "没有", "别", "未", "无"],
+    intensifiers: &["很", "非常", "特别", "太", "真", "极", "十分", "超"],
+    diminishers: &["有点", "稍微", "有些", "略"],
+};
+
+const JAPANESE: Lexicon = Lexicon {
+    positive: &[
+        "良い", "良く", "いい", "よい", "よく", "素晴らしい", "最高", "好き",
+        "満足", "便利", "完璧", "おすすめ", "嬉しい", "楽しい", "簡単", "速い",
+        "綺麗", "成功",
+    ],
+    negative: &[
+        "悪い", "悪く", "最悪", "嫌い", "不満", "問題", "エラー", "遅い",
+        "難しい", "高い", "残念", "失敗", "不便", "壊れ",
+    ],
+    // Japanese negation follows the word, see `negated_after`
+    negators: &["ない", "なかった", "ません", "ず"],
+    intensifiers: &["とても", "すごく", "非常に", "本当に", "かなり", "超"],
+    diminishers: &["少し", "ちょっと", "やや"],
+};
+
+fn lexicon(language: &str) -> Option<&'static Lexicon> {
+    match language {
+        "es" => Some(&SPANISH),
+        "fr" => Some(&FRENCH),
+        "de" => Some(&GERMAN),
+        "ru" => Some(&RUSSIAN),
+        "zh" => Some(&CHINESE),
+        "ja" => Some(&JAPANESE),
+        _ => None,
+    }
+}
+
+/// Analyze the sentiment of `text` written in `language`
+pub fn analyze(text: &str, language: &str) -> Sentiment {
+    let lexicons: Vec<&Lexicon> = std::iter::once(&ENGLISH).chain(lexicon(language)).collect();
+
+    let mut positive_total = 0.0f32;
+    let mut negative_total = 0.0f32;
+    let mut sentences = Vec::new();
+
+    for (offset, sentence) in split_sentences(text) {
+        let (positive, negative) = if matches!(language, "zh" | "ja") {
+            score_unsegmented(sentence, &lexicons, language == "ja")
+        } else {
+            score_words(sentence, &lexicons)
+        };
+        positive_total += positive;
+        negative_total += negative;
+
+        let score = polarity(positive, negative);
+        sentences.push(SentenceSentiment {
+            text: sentence.to_string(),
+            offset,
+            score,
+            label: SentimentLabel::from_score(score),
+        });
+    }
+
+    let score = polarity(positive_total, negative_total);
+    Sentiment {
+        label: SentimentLabel::from_score(score),
+        score,
+        sentences,
+    }
+}
+
+/// Normalized difference of positive and negative weight
+fn polarity(positive: f32, negative: f32) -> f32 {
+    let total = positive + negative;
+    if total > 0.0 {
+        (positive - negative) / total
+    } else {
+        0.0
+    }
+}
+
+/// Split into trimmed sentences with their byte offsets
+fn split_sentences(text: &str) -> Vec<(usize, &str)> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        if matches!(c, '.' | '!' | '?' | '。' | '！' | '？' | '\n') {
+            push_sentence(text, start, i + c.len_utf8(), &mut sentences);
+            start = i + c.len_utf8();
+        }
+    }
+    push_sentence(text, start, text.len(), &mut sentences);
+    sentences
+}
+
+fn push_sentence<'a>(text: &'a str, start: usize, end: usize, sentences: &mut Vec<(usize, &'a str)>) {
+    let raw = &text[start..end];
+    let trimmed = raw.trim_start();
+    let offset = start + (raw.len() - trimmed.len());
+    let trimmed = trimmed.trim_end();
+    if trimmed.chars().any(char::is_alphanumeric) {
+        sentences.push((offset, trimmed));
+    }
+}
+
+/// Positive and negative weight of a space-delimited sentence
+fn score_words(sentence: &str, lexicons: &[&Lexicon]) -> (f32, f32) {
+    let words: Vec<String> = sentence
+        .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase().replace('\u{2019}', "'"))
+        .collect();
+    let any = |select: fn(&Lexicon) -> &'static [&'static str], word: &str| {
+        lexicons.iter().any(|lexicon| select(lexicon).contains(&word))
+    };
+
+    let mut positive = 0.0;
+    let mut negative = 0.0;
+    for (i, word) in words.iter().enumerate() {
+        let polarity = if any(|l| l.positive, word) {
+            1.0
+        } else if any(|l| l.negative, word) {
+            -1.0
+        } else {
+            continue;
+        };
+
+        let mut weight = polarity;
+        if i > 0 {
+            let previous = words[i - 1].as_str();
+            if any(|l| l.intensifiers, previous) {
+                weight *= 1.5;
+            } else if any(|l| l.diminishers, previous) {
+                weight *= 0.5;
+            }
+        }
+        let window = &words[i.saturating_sub(NEGATION_WINDOW)..i];
+        if window.iter().any(|w| any(|l| l.negators, w)) {
+            weight *= NEGATION_FACTOR;
+        }
+
+        if weight > 0.0 {
+            positive += weight;
+        } else {
+            negative -= weight;
+        }
+    }
+    (positive, negative)
+}
+
+/// Positive and negative weight of a Chinese or Japanese sentence
+///
+/// Polarity words are matched longest first so "不错" (not bad, i.e. good)
+/// is not read as a negated "错".
+fn score_unsegmented(sentence: &str, lexicons: &[&Lexicon], negation_follows: bool) -> (f32, f32) {
+    let mut terms: Vec<(&str, f32)> = lexicons
+        .iter()
+        .flat_map(|l| {
+            l.positive
+                .iter()
+                .map(|w| (*w, 1.0))
+                .chain(l.negative.iter().map(|w| (*w, -1.0)))
+        })
+        .filter(|(w, _)| w.chars().any(|c| language::is_han(c) || language::is_kana(c)))
+        .collect();
+    terms.sort_by_key(|(w, _)| std::cmp::Reverse(w.len()));
+    let modifiers = |select: fn(&Lexicon) -> &'static [&'static str]| -> Vec<&'static str> {
+        lexicons.iter().flat_map(|l| select(l).iter().copied()).collect()
+    };
+    let (negators, intensifiers, diminishers) = (modifiers(|l| l.negators), modifiers(|l| l.intensifiers), modifiers(|l| l.diminishers));
+
+    let mut positive = 0.0;
+    let mut negative = 0.0;
+    let mut i = 0;
+    while i < sentence.len() {
+        let rest = &sentence[i..];
+        let Some((term, polarity)) = terms.iter().find(|(w, _)| rest.starts_with(*w)) else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+
+        let before = &sentence[..i];
+        let after = &rest[term.len()..];
+        let mut weight = *polarity;
+        if intensifiers.iter().any(|m| before.ends_with(m)) {
+            weight *= 1.5;
+        } else if diminishers.iter().any(|m| before.ends_with(m)) {
+            weight *= 0.5;
+        }
+
+        let negated = if negation_follows {
+            let following: String = after.chars().take(4).collect();
+            negators.iter().any(|n| following.contains(n))
+        } else {
+            let preceding: String = before.chars().rev().take(3).collect::<Vec<_>>().into_iter().rev().collect();
+            negators.iter().any(|n| preceding.contains(n))
+        };
+        if negated {
+            weight *= NEGATION_FACTOR;
+        }
+
+        if weight > 0.0 {
+            positive += weight;
+        } else {
+            negative -= weight;
+        }
+        i += term.len();
+    }
+    (positive, negative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negation_flips_polarity() {
+        assert_eq!(analyze("This product is good.", "en").label, SentimentLabel::Positive);
+        assert_eq!(analyze("This product is not good.", "en").label, SentimentLabel::Negative);
+        assert_eq!(analyze("It isn't bad at all.", "en").label, SentimentLabel::Positive);
+    }
+
+    #[test]
+    fn test_intensifiers_scale_weight() {
+        let mixed = analyze("The screen is very good. The battery is bad.", "en");
+        assert!(mixed.score > 0.0);
+        let mixed = analyze("The screen is slightly good. The battery is bad.", "en");
+        assert!(mixed.score < 0.0);
+    }
+
+    #[test]
+    fn test_sentence_scores() {
+        let text = "I love the design. The setup was frustrating. It ships tomorrow.";
+        let sentiment = analyze(text, "en");
+
+        let labels: Vec<SentimentLabel> = sentiment.sentences.iter().map(|s| s.label).collect();
+        assert_eq!(labels, vec![SentimentLabel::Positive, SentimentLabel::Negative, SentimentLabel::Neutral]);
+        let second = &sentiment.sentences[1];
+        assert_eq!(&text[second.offset..second.offset + second.text.len()], second.text);
+    }
+
+    #[test]
+    fn test_multilingual_lexicons() {
+        assert_eq!(analyze("El servicio es muy bueno.", "es").label, SentimentLabel::Positive);
+        assert_eq!(analyze("El servicio no es bueno.", "es").label, SentimentLabel::Negative);
+        assert_eq!(analyze("Das Essen ist nicht gut.", "de").label, SentimentLabel::Negative);
+        assert_eq!(analyze("Le film est vraiment excellent.", "fr").label, SentimentLabel::Positive);
+        assert_eq!(analyze("Это очень плохо.", "ru").label, SentimentLabel::Negative);
+    }
+
+    #[test]
+    fn test_chinese_and_japanese() {
+        assert_eq!(analyze("这个产品非常好。", "zh").label, SentimentLabel::Positive);
+        assert_eq!(analyze("这个产品不好。", "zh").label, SentimentLabel::Negative);
+        assert_eq!(analyze("味道不错。", "zh").label, SentimentLabel::Positive);
+        assert_eq!(analyze("この店は良くない。", "ja").label, SentimentLabel::Negative);
+        assert_eq!(analyze("この店は最高です。", "ja").label, SentimentLabel::Positive);
+    }
+
+    #[test]
+    fn test_no_sentiment_words_is_neutral() {
+        let sentiment = analyze("The meeting starts at noon.", "en");
+        assert_eq!(sentiment.label, SentimentLabel::Neutral);
+        assert_eq!(sentiment.score, 0.0);
+        assert!(analyze("", "en").sentences.is_empty());
+    }
+}
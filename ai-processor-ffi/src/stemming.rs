@@ -0,0 +1,220 @@
+//! Light stemming for keyword normalization
+//!
+//! Folds inflected forms ("program", "programs", "programming") onto one stem
+//! so their counts are merged before ranking. Stemming is rule-based suffix
+//! stripping per language, in the spirit of Porter's algorithm but much
+//! smaller: stems only need to be consistent, never readable, because
+//! keywords are displayed as the most frequent surface form of their stem.
+//! Chinese, Japanese and Korean tokens are left unchanged.
+
+use std::collections::HashMap;
+
+/// Shortest stem left after stripping a suffix, in characters
+const MIN_STEM_CHARS: usize = 3;
+
+const SPANISH_SUFFIXES: &[&str] = &[
+    "amientos", "imientos", "amiento", "imiento", "aciones", "uciones", "idades", "ación", "ución",
+    "mente", "idad", "ando", "iendo", "ados", "idos", "ado", "ido", "es", "os", "as", "s", "a", "o", "e",
+];
+
+const FRENCH_SUFFIXES: &[&str] = &[
+    "issements", "issement", "ements", "ations", "ement", "ation", "euses", "ités", "euse", "ité",
+    "eux", "ées", "ée", "es", "er", "é", "s", "x", "e",
+];
+
+const GERMAN_SUFFIXES: &[&str] = &[
+    "heiten", "keiten", "ungen", "heit", "keit", "ung", "ern", "em", "en", "er", "es", "e", "n", "s",
+];
+
+const RUSSIAN_SUFFIXES: &[&str] = &[
+    "ностями", "ностей", "ости", "ость", "иями", "ями", "ами", "ией", "ого", "его", "ому", "ему",
+    "ыми", "ими", "ах", "ях", "ов", "ев", "ий", "ый", "ой", "ая", "яя", "ое", "ее", "ые", "ие",
+    "ам", "ям", "ом", "ем", "ую", "юю", "ию", "ия", "а", "я", "о", "е", "ы", "и", "у", "ю", "ь", "й",
+];
+
+/// Stem a lowercase token written in `language`
+pub fn stem(word: &str, language: &str) -> String {
+    match language {
+        "en" => stem_english(word),
+        "es" => strip_suffix(word, SPANISH_SUFFIXES),
+        "fr" => strip_suffix(word, FRENCH_SUFFIXES),
+        "de" => strip_suffix(&fold_umlauts(word), GERMAN_SUFFIXES),
+        "ru" => strip_suffix(&word.replace('ё', "е"), RUSSIAN_SUFFIXES),
+        _ => word.to_string(),
+    }
+}
+
+/// Stem every word of a phrase, for comparing multi-word keywords
+pub fn phrase_key(phrase: &str, language: &str) -> String {
+    phrase
+        .split_whitespace()
+        .map(|word| stem(&word.to_lowercase(), language))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Tokens mapped to their stems, remembering how each stem was written
+#[derive(Debug, Clone, Default)]
+pub struct StemmedTokens {
+    pub stems: Vec<String>,
+    /// Surface forms of every stem with their counts
+    variants: HashMap<String, HashMap<String, usize>>,
+}
+
+impl StemmedTokens {
+    pub fn new(tokens: &[String], language: &str) -> Self {
+        let mut stemmed = Self::default();
+        for token in tokens {
+            let stem = stem(token, language);
+            *stemmed
+                .variants
+                .entry(stem.clone())
+                .or_default()
+                .entry(token.clone())
+                .or_insert(0) += 1;
+            stemmed.stems.push(stem);
+        }
+        stemmed
+    }
+
+    /// Display form of a stem: its most frequent variant, then the shortest
+    pub fn surface(&self, stem: &str) -> String {
+        self.variants
+            .get(stem)
+            .and_then(|forms| {
+                forms
+                    .iter()
+                    .max_by(|a, b| {
+                        a.1.cmp(b.1)
+                            .then_with(|| b.0.chars().count().cmp(&a.0.chars().count()))
+                            .then_with(|| b.0.cmp(a.0))
+                    })
+                    .map(|(form, _)| form.clone())
+            })
+            .unwrap_or_else(|| stem.to_string())
+    }
+}
+
+fn stem_english(word: &str) -> String {
+    if word.chars().count() <= MIN_STEM_CHARS || !word.is_ascii() {
+        return word.to_string();
+    }
+
+    let mut stem = word.to_string();
+
+    // Plurals and third person
+    if let Some(base) = stem.strip_suffix("ies").filter(|b| b.len() >= 2) {
+        stem = format!("{}y", base);
+    } else if stem.ends_with("sses") || stem.ends_with("xes") || stem.ends_with("ches") || stem.ends_with("shes") {
+        stem.truncate(stem.len() - 2);
+    } else if stem.ends_with('s') && !stem.ends_with("ss") && !stem.ends_with("us") && !stem.ends_with("is") {
+        stem.pop();
+    }
+
+    // Progressive and past forms, only if a vowel remains
+    for suffix in ["ing", "ed"] {
+        if let Some(base) = stem.strip_suffix(suffix) {
+            if base.len() >= MIN_STEM_CHARS && base.chars().any(is_english_vowel) {
+                stem = base.to_string();
+                if let Some(base) = undouble(&stem) {
+                    stem = base;
+                }
+                break;
+            }
+        }
+    }
+
+    // "make" and "making" share a stem without the final e
+    if stem.len() > MIN_STEM_CHARS && stem.ends_with('e') {
+        stem.pop();
+    }
+    if stem.ends_with('y') && stem.len() > MIN_STEM_CHARS {
+        stem.pop();
+        stem.push('i');
+    }
+    stem
+}
+
+fn is_english_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+/// "programm" -> "program", but keep "ll", "ss" and "zz" ("install", "pass")
+fn undouble(stem: &str) -> Option<String> {
+    let mut chars = stem.chars().rev();
+    let (last, previous) = (chars.next()?, chars.next()?);
+    if last == previous && !is_english_vowel(last) && !matches!(last, 'l' | 's' | 'z') {
+        Some(stem[..stem.len() - last.len_utf8()].to_string())
+    } else {
+        None
+    }
+}
+
+/// Strip the longest matching suffix that leaves a long enough stem
+fn strip_suffix(word: &str, suffixes: &[&str]) -> String {
+    let length = word.chars().count();
+    suffixes
+        .iter()
+        .filter(|suffix| word.ends_with(*suffix))
+        .filter(|suffix| length - suffix.chars().count() >= MIN_STEM_CHARS)
+        .max_by_key(|suffix| suffix.len())
+        .map(|suffix| word[..word.len() - suffix.len()].to_string())
+        .unwrap_or_else(|| word.to_string())
+}
+
+fn fold_umlauts(word: &str) -> String {
+    word.chars()
+        .map(|c| match c {
+            'ä' => 'a',
+            'ö' => 'o',
+            'ü' => 'u',
+            other => other,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same_stem(words: &[&str], language: &str) -> bool {
+        words.windows(2).all(|pair| stem(pair[0], language) == stem(pair[1], language))
+    }
+
+    #[test]
+    fn test_english_variants_share_a_stem() {
+        assert!(same_stem(&["program", "programs", "programming", "programmed"], "en"));
+        assert!(same_stem(&["library", "libraries"], "en"));
+        assert!(same_stem(&["make", "making", "makes"], "en"));
+        assert!(same_stem(&["class", "classes"], "en"));
+        assert!(!same_stem(&["program", "progress"], "en"));
+        assert_eq!(stem("bus", "en"), "bus");
+        assert_eq!(stem("install", "en"), "install");
+    }
+
+    #[test]
+    fn test_other_languages() {
+        assert!(same_stem(&["programa", "programas", "programación"], "es"));
+        assert!(same_stem(&["développement", "développements"], "fr"));
+        assert!(same_stem(&["entwicklung", "entwicklungen"], "de"));
+        assert!(same_stem(&["программа", "программы", "программой"], "ru"));
+        assert_eq!(stem("编程", "zh"), "编程");
+    }
+
+    #[test]
+    fn test_surface_prefers_most_frequent_variant() {
+        let tokens: Vec<String> = ["programming", "programs", "programming", "program", "rust"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        let stemmed = StemmedTokens::new(&tokens, "en");
+        assert_eq!(stemmed.stems.iter().filter(|s| **s == stemmed.stems[0]).count(), 4);
+        assert_eq!(stemmed.surface(&stemmed.stems[0]), "programming");
+        assert_eq!(stemmed.surface(&stemmed.stems[4]), "rust");
+    }
+
+    #[test]
+    fn test_phrase_key_folds_each_word() {
+        assert_eq!(phrase_key("Memory Safety", "en"), phrase_key("memory safeties", "en"));
+    }
+}
@@ -0,0 +1,279 @@
+//! Topic modeling over a page library
+//!
+//! Factorizes the TF-IDF document-term matrix with non-negative matrix
+//! factorization (NMF, multiplicative updates) into document-topic and
+//! topic-term weights. Each topic is labeled by its strongest terms and every
+//! document is assigned to its dominant topic. The matrix is kept sparse so
+//! the cost grows with the number of non-zero entries, not documents x terms.
+
+use std::collections::{HashMap, HashSet};
+
+/// Largest vocabulary used for factorization
+const MAX_VOCABULARY: usize = 2000;
+/// Number of multiplicative update iterations
+const ITERATIONS: usize = 150;
+/// Guards divisions in the update rules
+const EPSILON: f64 = 1e-9;
+/// Upper bound for the automatically chosen number of topics
+const MAX_AUTO_TOPICS: usize = 20;
+
+/// A discovered topic
+#[derive(Debug, Clone, PartialEq)]
+pub struct Topic {
+    /// Short label built from the top terms
+    pub label: String,
+    /// Strongest terms, best first
+    pub terms: Vec<String>,
+    /// Indices of the documents whose dominant topic this is
+    pub members: Vec<usize>,
+    /// Mean share of this topic in its member documents (0.0-1.0)
+    pub coherence: f32,
+}
+
+/// Sparse TF-IDF row: (term index, weight)
+type SparseRow = Vec<(usize, f64)>;
+
+/// Discover up to `num_topics` topics in tokenized documents
+///
+/// With `num_topics == 0` the count is derived from the library size.
+/// Topics without members are dropped; the rest are ordered by size.
+pub fn discover_topics(documents: &[Vec<String>], num_topics: usize, terms_per_topic: usize) -> Vec<Topic> {
+    let non_empty = documents.iter().filter(|d| !d.is_empty()).count();
+    if non_empty == 0 {
+        return Vec::new();
+    }
+
+    let k = if num_topics == 0 {
+        ((non_empty as f64 / 2.0).sqrt().round() as usize).clamp(1, MAX_AUTO_TOPICS)
+    } else {
+        num_topics.min(non_empty)
+    };
+
+    let (vocabulary, rows) = build_tfidf(documents);
+    if vocabulary.is_empty() {
+        return Vec::new();
+    }
+
+    let (w, h) = factorize(&rows, vocabulary.len(), k);
+
+    let mut topics: Vec<Topic> = (0..k)
+        .map(|topic| {
+            let mut ranked: Vec<(usize, f64)> = h[topic].iter().copied().enumerate().collect();
+            ranked.sort_by(|a, b| {
+                b.1.partial_cmp(&a.1)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| vocabulary[a.0].cmp(&vocabulary[b.0]))
+            });
+            let terms: Vec<String> = ranked
+                .into_iter()
+                .take_while(|(_, weight)| *weight > EPSILON)
+                .take(terms_per_topic.max(1))
+                .map(|(term, _)| vocabulary[term].clone())
+                .collect();
+
+            Topic {
+                label: terms.iter().take(3).cloned().collect::<Vec<_>>().join(" & "),
+                terms,
+                members: Vec::new(),
+                coherence: 0.0,
+            }
+        })
+        .collect();
+
+    let mut shares = vec![0.0f64; k];
+    for (doc, weights) in w.iter().enumerate() {
+        let total: f64 = weights.iter().sum();
+        if total <= EPSILON || rows[doc].is_empty() {
+            continue;
+        }
+        let (best, best_weight) = weights
+            .iter()
+            .copied()
+            .enumerate()
+            .fold((0, f64::MIN), |acc, (i, v)| if v > acc.1 { (i, v) } else { acc });
+        topics[best].members.push(doc);
+        shares[best] += best_weight / total;
+    }
+
+    for (topic, share) in topics.iter_mut().zip(shares) {
+        if !topic.members.is_empty() {
+            topic.coherence = (share / topic.members.len() as f64) as f32;
+        }
+    }
+
+    topics.retain(|t| !t.members.is_empty() && !t.terms.is_empty());
+    topics.sort_by(|a, b| b.members.len().cmp(&a.members.len()).then_with(|| a.label.cmp(&b.label)));
+    topics
+}
+
+/// Build the vocabulary and L2-normalized sparse TF-IDF rows
+fn build_tfidf(documents: &[Vec<String>]) -> (Vec<String>, Vec<SparseRow>) {
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for doc in documents {
+        let unique: HashSet<&str> = doc.iter().map(|t| t.as_str()).collect();
+        for term in unique {
+            *document_frequency.entry(term).or_insert(0) += 1;
+        }
+    }
+
+    // Terms found in only one document cannot link documents; keep them only
+    // for tiny libraries where nothing else would remain
+    let min_df = if documents.len() > 2 { 2 } else { 1 };
+    let mut candidates: Vec<(&str, usize)> = document_frequency
+        .into_iter()
+        .filter(|(_, df)| *df >= min_df)
+        .collect();
+    candidates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    candidates.truncate(MAX_VOCABULARY);
+
+    let vocabulary: Vec<String> = candidates.iter().map(|(t, _)| t.to_string()).collect();
+    let index: HashMap<&str, (usize, usize)> = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, (t, df))| (*t, (i, *df)))
+        .collect();
+
+    let n = documents.len() as f64;
+    let rows = documents
+        .iter()
+        .map(|doc| {
+            let mut counts: HashMap<usize, (usize, usize)> = HashMap::new();
+            for token in doc {
+                if let Some(&(term, df)) = index.get(token.as_str()) {
+                    counts.entry(term).or_insert((0, df)).0 += 1;
+                }
+            }
+
+            let mut row: SparseRow = counts
+                .into_iter()
+                .map(|(term, (tf, df))| {
+                    let idf = ((1.0 + n) / (1.0 + df as f64)).ln() + 1.0;
+                    (term, (1.0 + (tf as f64).ln()) * idf)
+                })
+                .collect();
+            row.sort_by_key(|(term, _)| *term);
+
+            let norm = row.iter().map(|(_, v)| v * v).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                row.iter_mut().for_each(|(_, v)| *v /= norm);
+            }
+            row
+        })
+        .collect();
+
+    (vocabulary, rows)
+}
+
+/// Factorize V (documents x terms) into W (documents x k) and H (k x terms)
+fn factorize(rows: &[SparseRow], terms: usize, k: usize) -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+    let n = rows.len();
+
+    // Deterministic initialization so results are reproducible
+    let mut seed: u64 = 0x9e3779b97f4a7c15;
+    let mut next = move || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((seed >> 33) as f64 / (1u64 << 31) as f64) * 0.9 + 0.1
+    };
+    let nnz: usize = rows.iter().map(|r| r.len()).sum();
+    let mean = rows.iter().flatten().map(|(_, v)| v).sum::<f64>() / nnz.max(1) as f64;
+    let scale = (mean / k as f64).sqrt();
+
+    let mut w: Vec<Vec<f64>> = (0..n).map(|_| (0..k).map(|_| next() * scale).collect()).collect();
+    let mut h: Vec<Vec<f64>> = (0..k).map(|_| (0..terms).map(|_| next() * scale).collect()).collect();
+
+    for _ in 0..ITERATIONS {
+        // H <- H * (W^T V) / (W^T W H)
+        let mut wt_v = vec![vec![0.0; terms]; k];
+        for (doc, row) in rows.iter().enumerate() {
+            for &(term, value) in row {
+                for topic in 0..k {
+                    wt_v[topic][term] += w[doc][topic] * value;
+                }
+            }
+        }
+        let wt_w = gram(&w, k);
+        for topic in 0..k {
+            for term in 0..terms {
+                let denominator: f64 = (0..k).map(|j| wt_w[topic][j] * h[j][term]).sum();
+                h[topic][term] *= wt_v[topic][term] / (denominator + EPSILON);
+            }
+        }
+
+        // W <- W * (V H^T) / (W H H^T)
+        let h_ht: Vec<Vec<f64>> = (0..k)
+            .map(|a| (0..k).map(|b| h[a].iter().zip(&h[b]).map(|(x, y)| x * y).sum()).collect())
+            .collect();
+        for (doc, row) in rows.iter().enumerate() {
+            let v_ht: Vec<f64> = (0..k)
+                .map(|topic| row.iter().map(|&(term, value)| value * h[topic][term]).sum())
+                .collect();
+            let current = w[doc].clone();
+            for topic in 0..k {
+                let denominator: f64 = (0..k).map(|j| current[j] * h_ht[j][topic]).sum();
+                w[doc][topic] *= v_ht[topic] / (denominator + EPSILON);
+            }
+        }
+    }
+
+    (w, h)
+}
+
+/// W^T W for a row-major matrix with `k` columns
+fn gram(matrix: &[Vec<f64>], k: usize) -> Vec<Vec<f64>> {
+    let mut result = vec![vec![0.0; k]; k];
+    for row in matrix {
+        for a in 0..k {
+            for b in 0..k {
+                result[a][b] += row[a] * row[b];
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(text: &str) -> Vec<String> {
+        text.split_whitespace().map(|w| w.to_string()).collect()
+    }
+
+    fn library() -> Vec<Vec<String>> {
+        vec![
+            doc("rust compiler borrow checker ownership"),
+            doc("rust ownership lifetimes compiler"),
+            doc("borrow checker lifetimes rust"),
+            doc("pasta recipe tomato garlic"),
+            doc("tomato garlic basil recipe"),
+            doc("pasta basil recipe dinner"),
+        ]
+    }
+
+    #[test]
+    fn test_discover_topics_separates_themes() {
+        let topics = discover_topics(&library(), 2, 5);
+        assert_eq!(topics.len(), 2);
+
+        let mut members: Vec<Vec<usize>> = topics.iter().map(|t| t.members.clone()).collect();
+        members.sort();
+        assert_eq!(members, vec![vec![0, 1, 2], vec![3, 4, 5]]);
+
+        for topic in &topics {
+            assert!(!topic.label.is_empty());
+            assert!(topic.coherence > 0.0 && topic.coherence <= 1.0);
+        }
+    }
+
+    #[test]
+    fn test_discover_topics_is_deterministic() {
+        assert_eq!(discover_topics(&library(), 2, 5), discover_topics(&library(), 2, 5));
+    }
+
+    #[test]
+    fn test_discover_topics_auto_count_and_empty_input() {
+        assert!(discover_topics(&[], 3, 5).is_empty());
+        assert!(discover_topics(&[Vec::new()], 0, 5).is_empty());
+        assert!(!discover_topics(&library(), 0, 5).is_empty());
+    }
+}
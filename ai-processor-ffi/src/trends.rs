@@ -0,0 +1,220 @@
+//! Topic and keyword trends over time
+//!
+//! Pages are bucketed by the week (starting Monday) or month of their visit,
+//! in the user's local time. A term is emerging when the share of pages
+//! mentioning it in the most recent periods clearly exceeds its share before,
+//! and fading in the opposite case. Shares rather than counts are compared
+//! so a busy week does not make every term look like it is emerging.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Minimum share difference for a term to emerge or fade
+const MIN_SHARE_CHANGE: f64 = 0.1;
+/// Pages a term needs in the window where it is strong
+const MIN_TERM_PAGES: usize = 2;
+/// Terms listed per period
+const TERMS_PER_PERIOD: usize = 10;
+/// Emerging and fading terms reported
+const MAX_TRENDS: usize = 10;
+
+/// Length of a trend period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    #[default]
+    Week,
+    Month,
+}
+
+impl Granularity {
+    /// First day of the period containing `time`
+    pub fn period_start(self, time: &DateTime<FixedOffset>) -> NaiveDate {
+        let date = time.date_naive();
+        match self {
+            Granularity::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Granularity::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+}
+
+/// Page counts of one period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Period {
+    pub start: NaiveDate,
+    pub page_count: usize,
+    /// Most frequent terms with their page counts
+    pub terms: Vec<(String, usize)>,
+}
+
+/// Change in a term's share of pages between the earlier and recent periods
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TermTrend {
+    pub term: String,
+    pub recent_pages: usize,
+    pub earlier_pages: usize,
+    pub recent_share: f64,
+    pub earlier_share: f64,
+}
+
+impl TermTrend {
+    pub fn change(&self) -> f64 {
+        self.recent_share - self.earlier_share
+    }
+}
+
+/// Trends of a set of terms across periods
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TrendReport {
+    /// Periods with at least one page, oldest first
+    pub periods: Vec<Period>,
+    /// Strongest rise first
+    pub emerging: Vec<TermTrend>,
+    /// Strongest decline first
+    pub fading: Vec<TermTrend>,
+}
+
+/// Analyze the terms of visited pages
+///
+/// `pages` pairs each visit time with the page's terms. The last
+/// `recent_periods` periods (at least one) are compared with all earlier
+/// ones; with a single period there is nothing to compare and no trends.
+pub fn analyze(pages: &[(DateTime<FixedOffset>, Vec<String>)], granularity: Granularity, recent_periods: usize) -> TrendReport {
+    // Terms are counted once per page
+    let mut buckets: Vec<(NaiveDate, Vec<HashSet<&str>>)> = Vec::new();
+    for (time, terms) in pages {
+        let start = granularity.period_start(time);
+        let terms: HashSet<&str> = terms.iter().map(String::as_str).filter(|t| !t.is_empty()).collect();
+        match buckets.iter_mut().find(|(s, _)| *s == start) {
+            Some((_, bucket)) => bucket.push(terms),
+            None => buckets.push((start, vec![terms])),
+        }
+    }
+    buckets.sort_by_key(|(start, _)| *start);
+
+    let periods = buckets
+        .iter()
+        .map(|(start, bucket)| Period {
+            start: *start,
+            page_count: bucket.len(),
+            terms: top_terms(count_terms(bucket.iter()), TERMS_PER_PERIOD),
+        })
+        .collect();
+
+    let recent_periods = recent_periods.max(1);
+    if buckets.len() <= recent_periods {
+        return TrendReport { periods, ..Default::default() };
+    }
+    let (earlier, recent) = buckets.split_at(buckets.len() - recent_periods);
+    let earlier_pages: Vec<&HashSet<&str>> = earlier.iter().flat_map(|(_, bucket)| bucket).collect();
+    let recent_pages: Vec<&HashSet<&str>> = recent.iter().flat_map(|(_, bucket)| bucket).collect();
+    let earlier_counts = count_terms(earlier_pages.iter().copied());
+    let recent_counts = count_terms(recent_pages.iter().copied());
+
+    let mut terms: Vec<&str> = earlier_counts.keys().chain(recent_counts.keys()).copied().collect();
+    terms.sort_unstable();
+    terms.dedup();
+    let trends: Vec<TermTrend> = terms
+        .into_iter()
+        .map(|term| {
+            let recent = recent_counts.get(term).copied().unwrap_or(0);
+            let earlier = earlier_counts.get(term).copied().unwrap_or(0);
+            TermTrend {
+                term: term.to_string(),
+                recent_pages: recent,
+                earlier_pages: earlier,
+                recent_share: share(recent, recent_pages.len()),
+                earlier_share: share(earlier, earlier_pages.len()),
+            }
+        })
+        .collect();
+
+    let mut emerging: Vec<TermTrend> = trends
+        .iter()
+        .filter(|t| t.change() >= MIN_SHARE_CHANGE && t.recent_pages >= MIN_TERM_PAGES)
+        .cloned()
+        .collect();
+    emerging.sort_by(|a, b| b.change().total_cmp(&a.change()).then_with(|| a.term.cmp(&b.term)));
+    emerging.truncate(MAX_TRENDS);
+
+    let mut fading: Vec<TermTrend> = trends
+        .into_iter()
+        .filter(|t| t.change() <= -MIN_SHARE_CHANGE && t.earlier_pages >= MIN_TERM_PAGES)
+        .collect();
+    fading.sort_by(|a, b| a.change().total_cmp(&b.change()).then_with(|| a.term.cmp(&b.term)));
+    fading.truncate(MAX_TRENDS);
+
+    TrendReport { periods, emerging, fading }
+}
+
+fn count_terms<'a>(pages: impl Iterator<Item = &'a HashSet<&'a str>>) -> HashMap<&'a str, usize> {
+    let mut counts = HashMap::new();
+    for term in pages.flatten() {
+        *counts.entry(*term).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn top_terms(counts: HashMap<&str, usize>, limit: usize) -> Vec<(String, usize)> {
+    let mut terms: Vec<(String, usize)> = counts.into_iter().map(|(term, count)| (term.to_string(), count)).collect();
+    terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    terms.truncate(limit);
+    terms
+}
+
+fn share(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visit(time: &str, terms: &[&str]) -> (DateTime<FixedOffset>, Vec<String>) {
+        (
+            DateTime::parse_from_rfc3339(time).unwrap(),
+            terms.iter().map(|t| t.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_period_start() {
+        // 2026-03-05 is a Thursday; local time decides the day
+        let time = DateTime::parse_from_rfc3339("2026-03-05T23:30:00-05:00").unwrap();
+        assert_eq!(Granularity::Week.period_start(&time), NaiveDate::from_ymd_opt(2026, 3, 2).unwrap());
+        assert_eq!(Granularity::Month.period_start(&time), NaiveDate::from_ymd_opt(2026, 3, 1).unwrap());
+    }
+
+    #[test]
+    fn test_emerging_and_fading_terms() {
+        let pages = vec![
+            visit("2026-03-02T10:00:00+00:00", &["python", "web"]),
+            visit("2026-03-03T10:00:00+00:00", &["python"]),
+            visit("2026-03-04T10:00:00+00:00", &["python", "web"]),
+            visit("2026-03-10T10:00:00+00:00", &["rust", "web"]),
+            visit("2026-03-11T10:00:00+00:00", &["rust", "python"]),
+            visit("2026-03-12T10:00:00+00:00", &["rust"]),
+        ];
+        let report = analyze(&pages, Granularity::Week, 1);
+
+        assert_eq!(report.periods.len(), 2);
+        assert_eq!(report.periods[0].page_count, 3);
+        assert_eq!(report.periods[0].terms[0], ("python".to_string(), 3));
+
+        let emerging: Vec<&str> = report.emerging.iter().map(|t| t.term.as_str()).collect();
+        let fading: Vec<&str> = report.fading.iter().map(|t| t.term.as_str()).collect();
+        assert_eq!(emerging, vec!["rust"]);
+        assert_eq!(fading, vec!["python", "web"]);
+        assert!((report.fading[0].change() + 2.0 / 3.0).abs() < 1e-9);
+
+        let single = analyze(&pages, Granularity::Month, 1);
+        assert_eq!(single.periods.len(), 1);
+        assert!(single.emerging.is_empty() && single.fading.is_empty());
+    }
+}
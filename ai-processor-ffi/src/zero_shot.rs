@@ -0,0 +1,186 @@
+//! Zero-shot classification against label descriptions
+//!
+//! A label is a name plus a short description of what belongs in it ("Recipes:
+//! cooking instructions, ingredients and dishes"). Pages are assigned to the
+//! label whose description embedding is most similar to the page embedding, so
+//! any category can be added without examples or training. Results are only as
+//! good as the embedder: the hashing embedder matches shared words, a sentence
+//! model matches meaning. Only names and descriptions are persisted; the
+//! description embeddings are recomputed for the current embedder.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use web_page_manager_core::AIProcessingError;
+
+use crate::embedding::{cosine_similarity, EmbeddingModel};
+
+/// Minimum similarity for a page to be assigned to a label
+pub const MIN_ZERO_SHOT_SCORE: f64 = 0.15;
+
+/// A label and its description embedding
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Label {
+    description: String,
+    #[serde(skip)]
+    embedding: Vec<f32>,
+}
+
+/// Classifier over user-described labels
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ZeroShotClassifier {
+    labels: BTreeMap<String, Label>,
+    /// Embedding model the description embeddings were computed with
+    #[serde(skip)]
+    model: String,
+}
+
+impl ZeroShotClassifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Names of the defined labels, sorted
+    pub fn label_names(&self) -> Vec<String> {
+        self.labels.keys().cloned().collect()
+    }
+
+    /// Add or replace a label
+    pub fn define(&mut self, name: &str, description: &str, embedder: &dyn EmbeddingModel) {
+        self.refresh(embedder);
+        let description = description.trim().to_string();
+        let embedding = embed_label(name, &description, embedder);
+        self.labels.insert(name.to_string(), Label { description, embedding });
+    }
+
+    /// Remove a label, returning whether it existed
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.labels.remove(name).is_some()
+    }
+
+    /// Recompute description embeddings if `embedder` is a different model
+    pub fn refresh(&mut self, embedder: &dyn EmbeddingModel) {
+        if self.model == embedder.name() {
+            return;
+        }
+        for (name, label) in self.labels.iter_mut() {
+            label.embedding = embed_label(name, &label.description, embedder);
+        }
+        self.model = embedder.name().to_string();
+    }
+
+    /// Labels matching a page embedding, best first, with their similarities
+    ///
+    /// If the labels were embedded with another model they are embedded on
+    /// the fly; call `refresh` after switching models to avoid that.
+    pub fn classify(&self, embedder: &dyn EmbeddingModel, embedding: &[f32]) -> Vec<(String, f32)> {
+        if embedding.is_empty() {
+            return Vec::new();
+        }
+
+        let stale = self.model != embedder.name();
+        let mut matches: Vec<(String, f32)> = self
+            .labels
+            .iter()
+            .map(|(name, label)| {
+                let score = if stale {
+                    cosine_similarity(&embed_label(name, &label.description, embedder), embedding)
+                } else {
+                    cosine_similarity(&label.embedding, embedding)
+                };
+                (name.clone(), score)
+            })
+            .filter(|(_, score)| *score >= MIN_ZERO_SHOT_SCORE)
+            .map(|(name, score)| (name, score as f32))
+            .collect();
+        matches.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        matches
+    }
+
+    /// Load labels from a JSON file
+    ///
+    /// Descriptions are embedded on first use; call `refresh` to do it now.
+    pub fn load(path: &Path) -> Result<Self, AIProcessingError> {
+        let data = std::fs::read_to_string(path).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to read labels {}: {}", path.display(), e),
+        })?;
+        serde_json::from_str(&data).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Invalid labels {}: {}", path.display(), e),
+        })
+    }
+
+    /// Save labels to a JSON file
+    pub fn save(&self, path: &Path) -> Result<(), AIProcessingError> {
+        let data = serde_json::to_string_pretty(self).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to serialize labels: {}", e),
+        })?;
+        std::fs::write(path, data).map_err(|e| AIProcessingError::ProcessingFailed {
+            reason: format!("Failed to write labels {}: {}", path.display(), e),
+        })
+    }
+}
+
+/// Embed a label as its name followed by its description
+fn embed_label(name: &str, description: &str, embedder: &dyn EmbeddingModel) -> Vec<f32> {
+    embedder
+        .embed(&format!("{}: {}", name, description))
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to embed label {}: {}", name, e);
+            Vec::new()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::HashingEmbedder;
+
+    fn classifier(embedder: &dyn EmbeddingModel) -> ZeroShotClassifier {
+        let mut classifier = ZeroShotClassifier::new();
+        classifier.define("Recipes", "cooking recipe ingredients garlic pasta sauce bake", embedder);
+        classifier.define("Travel", "travel flights hotels trip city tourism", embedder);
+        classifier
+    }
+
+    #[test]
+    fn test_classify_matches_description() {
+        let embedder = HashingEmbedder::default();
+        let classifier = classifier(&embedder);
+
+        let page = embedder.embed("Bake the pasta with garlic sauce and fresh ingredients").unwrap();
+        let matches = classifier.classify(&embedder, &page);
+        assert_eq!(matches[0].0, "Recipes");
+        assert!(matches.iter().all(|(name, _)| name != "Travel"));
+
+        let unrelated = embedder.embed("quarterly revenue and stock buybacks").unwrap();
+        assert!(classifier.classify(&embedder, &unrelated).is_empty());
+    }
+
+    #[test]
+    fn test_other_model_embeds_on_the_fly() {
+        let classifier = classifier(&HashingEmbedder::default());
+        let other = HashingEmbedder::new(64);
+        let page = other.embed("cheap flights and hotels for a city trip").unwrap();
+        assert_eq!(classifier.classify(&other, &page)[0].0, "Travel");
+    }
+
+    #[test]
+    fn test_save_load_roundtrip() {
+        let embedder = HashingEmbedder::default();
+        let classifier = classifier(&embedder);
+        let path = std::env::temp_dir().join(format!("zero_shot_{}.json", std::process::id()));
+        classifier.save(&path).unwrap();
+        let mut loaded = ZeroShotClassifier::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        loaded.refresh(&embedder);
+
+        assert_eq!(loaded.label_names(), vec!["Recipes", "Travel"]);
+        let page = embedder.embed("hotels near the city for your trip").unwrap();
+        assert_eq!(loaded.classify(&embedder, &page), classifier.classify(&embedder, &page));
+    }
+}
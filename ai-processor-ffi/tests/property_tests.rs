@@ -51,6 +51,7 @@ fn arb_article_content() -> impl Strategy<Value = PageContentInput> {
                 keywords,
                 images: vec![],
                 links: vec![],
+                url: None,
             }
         })
 }
@@ -72,6 +73,7 @@ fn arb_video_content() -> impl Strategy<Value = PageContentInput> {
                 keywords,
                 images: vec!["https://example.com/thumbnail.jpg".to_string()],
                 links: vec![],
+                url: None,
             }
         })
 }
@@ -98,6 +100,7 @@ fn arb_documentation_content() -> impl Strategy<Value = PageContentInput> {
                 keywords,
                 images: vec![],
                 links: vec!["https://docs.example.com/api".to_string()],
+                url: None,
             }
         })
 }
@@ -124,6 +127,7 @@ fn arb_shopping_content() -> impl Strategy<Value = PageContentInput> {
                 keywords,
                 images: vec!["https://shop.example.com/product.jpg".to_string()],
                 links: vec![],
+                url: None,
             }
         })
 }
@@ -150,6 +154,7 @@ fn arb_news_content() -> impl Strategy<Value = PageContentInput> {
                 keywords,
                 images: vec![],
                 links: vec![],
+                url: None,
             }
         })
 }
@@ -176,6 +181,7 @@ fn arb_minimal_content() -> impl Strategy<Value = PageContentInput> {
             keywords: vec![],
             images: vec![],
             links: vec![],
+            url: None,
         }
     })
 }
@@ -197,6 +203,7 @@ fn arb_substantial_content() -> impl Strategy<Value = PageContentInput> {
                 keywords: vec!["article".to_string(), "content".to_string()],
                 images: vec![],
                 links: vec![],
+                url: None,
             }
         })
 }
@@ -740,6 +747,7 @@ mod unit_tests {
                 keywords: vec![],
                 images: vec![],
                 links: vec![],
+                url: None,
             };
             
             let json = content_to_json(&content);
@@ -765,6 +773,7 @@ mod unit_tests {
                 keywords: vec![],
                 images: vec![],
                 links: vec![],
+                url: None,
             };
             
             let json = content_to_json(&content);
@@ -830,6 +839,7 @@ proptest! {
                     keywords: vec!["similar".to_string(), "content".to_string()],
                     images: vec![],
                     links: vec![],
+                    url: None,
                 }
             }).collect();
 
@@ -956,6 +966,7 @@ proptest! {
                     keywords: vec![],
                     images: vec![],
                     links: vec![format!("https://{}.com/page{}", domain, i)],
+                    url: None,
                 }
             }).collect();
 
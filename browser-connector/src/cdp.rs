@@ -2,16 +2,25 @@
 //!
 //! This module implements browser detection and connection functionality for
 //! Chromium-based browsers (Chrome and Edge) using the Chrome DevTools Protocol.
+//!
+//! CDP has no bookmarks domain, so bookmarks are read-only here: the
+//! bookmark writes of `BrowserConnector` are refused with permission denied.
 
 use crate::traits::BrowserConnector;
 use web_page_manager_core::*;
 use async_trait::async_trait;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
-// WebSocket imports reserved for future CDP WebSocket implementation
-// use futures_util::{SinkExt, StreamExt};
-// use tokio_tungstenite::{connect_async, tungstenite::Message};
+use futures_util::{SinkExt, StreamExt};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+/// Longest wait for a page snapshot
+const CAPTURE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Longest wait for any other CDP command
+const COMMAND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 /// CDP target information returned by the browser
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,18 +56,16 @@ pub struct CdpVersion {
     pub web_socket_debugger_url: Option<String>,
 }
 
-/// CDP command message (reserved for WebSocket-based CDP communication)
+/// CDP command message sent over a target's WebSocket
 #[derive(Debug, Serialize)]
-#[allow(dead_code)]
 struct CdpCommand {
     id: u64,
     method: String,
     params: serde_json::Value,
 }
 
-/// CDP response message (reserved for WebSocket-based CDP communication)
+/// CDP response message; events have no `id`
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 struct CdpResponse {
     id: Option<u64>,
     result: Option<serde_json::Value>,
@@ -67,18 +74,28 @@ struct CdpResponse {
 
 /// CDP error information
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 struct CdpError {
     code: i64,
     message: String,
 }
 
+/// Window opened by a connector
+#[derive(Debug, Clone)]
+struct CdpWindow {
+    /// Latest target opened in the window, activated to focus it
+    last_target: String,
+    /// Blank target the window was opened with, closed once a tab is added
+    blank_target: Option<String>,
+}
+
 /// Internal connection state for CDP
 struct CdpConnectionState {
     connected: bool,
     targets: Vec<CdpTarget>,
     version: Option<CdpVersion>,
     ws_url: Option<String>,
+    /// Windows opened through `create_window`, by window ID
+    windows: HashMap<String, CdpWindow>,
 }
 
 impl Default for CdpConnectionState {
@@ -88,6 +105,7 @@ impl Default for CdpConnectionState {
             targets: Vec::new(),
             version: None,
             ws_url: None,
+            windows: HashMap::new(),
         }
     }
 }
@@ -293,6 +311,7 @@ impl BrowserConnector for ChromeConnector {
         state.targets.clear();
         state.version = None;
         state.ws_url = None;
+        state.windows.clear();
         
         Ok(())
     }
@@ -548,6 +567,49 @@ impl BrowserConnector for ChromeConnector {
         
         Ok(TabId(target.id))
     }
+
+    async fn capture_mhtml(&self, tab_id: &TabId) -> Result<Option<Vec<u8>>> {
+        tracing::info!("Capturing Chrome tab as MHTML: {:?}", tab_id);
+
+        let state = self.state.read().await;
+        if !state.connected {
+            return Err(WebPageManagerError::BrowserConnection {
+                source: BrowserConnectionError::BrowserNotRunning {
+                    browser: BrowserType::Chrome,
+                },
+            });
+        }
+        drop(state);
+
+        let ws_url = self
+            .fetch_targets()
+            .await?
+            .into_iter()
+            .find(|t| t.id == tab_id.0)
+            .and_then(|t| t.web_socket_debugger_url)
+            .ok_or(WebPageManagerError::BrowserConnection {
+                source: BrowserConnectionError::InvalidResponse {
+                    browser: BrowserType::Chrome,
+                },
+            })?;
+        capture_target_mhtml(BrowserType::Chrome, &ws_url).await.map(Some)
+    }
+
+    async fn create_window(&self) -> Result<Option<String>> {
+        tracing::info!("Opening Chrome window");
+        open_window(BrowserType::Chrome, &self.state).await.map(Some)
+    }
+
+    /// CDP cannot pin tabs, so `pinned` is ignored
+    async fn create_tab_with(&self, url: &str, window_id: Option<&str>, pinned: bool) -> Result<TabId> {
+        let _ = pinned;
+        if let Some(window_id) = window_id {
+            if let Some(tab_id) = open_tab_in_window(BrowserType::Chrome, &self.state, url, window_id).await? {
+                return Ok(tab_id);
+            }
+        }
+        self.create_tab(url).await
+    }
 }
 
 /// Edge browser connector using CDP (Edge is Chromium-based)
@@ -749,6 +811,7 @@ impl BrowserConnector for EdgeConnector {
         state.targets.clear();
         state.version = None;
         state.ws_url = None;
+        state.windows.clear();
         
         Ok(())
     }
@@ -983,6 +1046,203 @@ impl BrowserConnector for EdgeConnector {
         
         Ok(TabId(target.id))
     }
+
+    async fn capture_mhtml(&self, tab_id: &TabId) -> Result<Option<Vec<u8>>> {
+        tracing::info!("Capturing Edge tab as MHTML: {:?}", tab_id);
+
+        let state = self.state.read().await;
+        if !state.connected {
+            return Err(WebPageManagerError::BrowserConnection {
+                source: BrowserConnectionError::BrowserNotRunning {
+                    browser: BrowserType::Edge,
+                },
+            });
+        }
+        drop(state);
+
+        let ws_url = self
+            .fetch_targets()
+            .await?
+            .into_iter()
+            .find(|t| t.id == tab_id.0)
+            .and_then(|t| t.web_socket_debugger_url)
+            .ok_or(WebPageManagerError::BrowserConnection {
+                source: BrowserConnectionError::InvalidResponse {
+                    browser: BrowserType::Edge,
+                },
+            })?;
+        capture_target_mhtml(BrowserType::Edge, &ws_url).await.map(Some)
+    }
+
+    async fn create_window(&self) -> Result<Option<String>> {
+        tracing::info!("Opening Edge window");
+        open_window(BrowserType::Edge, &self.state).await.map(Some)
+    }
+
+    /// CDP cannot pin tabs, so `pinned` is ignored
+    async fn create_tab_with(&self, url: &str, window_id: Option<&str>, pinned: bool) -> Result<TabId> {
+        let _ = pinned;
+        if let Some(window_id) = window_id {
+            if let Some(tab_id) = open_tab_in_window(BrowserType::Edge, &self.state, url, window_id).await? {
+                return Ok(tab_id);
+            }
+        }
+        self.create_tab(url).await
+    }
+}
+
+/// Send one CDP command over a WebSocket, returning its result
+async fn send_command(
+    browser: BrowserType,
+    ws_url: &str,
+    method: &str,
+    params: serde_json::Value,
+    limit: std::time::Duration,
+) -> Result<serde_json::Value> {
+    let invalid = || WebPageManagerError::BrowserConnection {
+        source: BrowserConnectionError::InvalidResponse { browser },
+    };
+    let timeout = || WebPageManagerError::BrowserConnection {
+        source: BrowserConnectionError::ConnectionTimeout { browser },
+    };
+
+    let exchange = async {
+        let (mut socket, _) = connect_async(ws_url).await.map_err(|_| timeout())?;
+        let command = CdpCommand {
+            id: 1,
+            method: method.to_string(),
+            params,
+        };
+        let text = serde_json::to_string(&command).map_err(|_| invalid())?;
+        socket.send(Message::Text(text)).await.map_err(|_| invalid())?;
+
+        while let Some(message) = socket.next().await {
+            let Message::Text(text) = message.map_err(|_| invalid())? else {
+                continue;
+            };
+            let Ok(response) = serde_json::from_str::<CdpResponse>(&text) else {
+                continue;
+            };
+            if response.id != Some(command.id) {
+                continue;
+            }
+            let _ = socket.close(None).await;
+            if let Some(error) = response.error {
+                tracing::warn!("{:?} refused {}: {} ({})", browser, method, error.message, error.code);
+                return Err(invalid());
+            }
+            return response.result.ok_or_else(invalid);
+        }
+        Err(invalid())
+    };
+    tokio::time::timeout(limit, exchange).await.map_err(|_| timeout())?
+}
+
+/// Capture the page of a target as MHTML with `Page.captureSnapshot`,
+/// over the target's WebSocket
+async fn capture_target_mhtml(browser: BrowserType, ws_url: &str) -> Result<Vec<u8>> {
+    let params = serde_json::json!({ "format": "mhtml" });
+    let result = send_command(browser, ws_url, "Page.captureSnapshot", params, CAPTURE_TIMEOUT).await?;
+    result
+        .get("data")
+        .and_then(|d| d.as_str())
+        .map(|d| d.as_bytes().to_vec())
+        .ok_or(WebPageManagerError::BrowserConnection {
+            source: BrowserConnectionError::InvalidResponse { browser },
+        })
+}
+
+/// Browser-level WebSocket of a connected browser
+async fn browser_ws_url(browser: BrowserType, state: &RwLock<CdpConnectionState>) -> Result<String> {
+    let state = state.read().await;
+    if !state.connected {
+        return Err(WebPageManagerError::BrowserConnection {
+            source: BrowserConnectionError::BrowserNotRunning { browser },
+        });
+    }
+    state.ws_url.clone().ok_or(WebPageManagerError::BrowserConnection {
+        source: BrowserConnectionError::InvalidResponse { browser },
+    })
+}
+
+/// ID of the target a `Target.createTarget` result names
+fn created_target_id(browser: BrowserType, result: &serde_json::Value) -> Result<String> {
+    result
+        .get("targetId")
+        .and_then(|id| id.as_str())
+        .map(str::to_string)
+        .ok_or(WebPageManagerError::BrowserConnection {
+            source: BrowserConnectionError::InvalidResponse { browser },
+        })
+}
+
+/// Open a window with `Target.createTarget`, returning its window ID
+///
+/// CDP opens a window with a tab in it; the blank tab is closed when the
+/// first tab is added through `open_tab_in_window`.
+async fn open_window(browser: BrowserType, state: &RwLock<CdpConnectionState>) -> Result<String> {
+    let ws_url = browser_ws_url(browser, state).await?;
+    let params = serde_json::json!({ "url": "about:blank", "newWindow": true });
+    let result = send_command(browser, &ws_url, "Target.createTarget", params, COMMAND_TIMEOUT).await?;
+    let target_id = created_target_id(browser, &result)?;
+
+    let params = serde_json::json!({ "targetId": target_id });
+    let result = send_command(browser, &ws_url, "Browser.getWindowForTarget", params, COMMAND_TIMEOUT).await?;
+    let window_id = result
+        .get("windowId")
+        .and_then(|id| id.as_i64())
+        .ok_or(WebPageManagerError::BrowserConnection {
+            source: BrowserConnectionError::InvalidResponse { browser },
+        })?
+        .to_string();
+
+    let window = CdpWindow {
+        last_target: target_id.clone(),
+        blank_target: Some(target_id),
+    };
+    state.write().await.windows.insert(window_id.clone(), window);
+    Ok(window_id)
+}
+
+/// Open a tab in a window from `open_window`, or None if the window was not
+/// opened by this connector
+///
+/// `Target.createTarget` has no window parameter and opens tabs in the
+/// focused window, so the window's latest tab is activated first.
+async fn open_tab_in_window(
+    browser: BrowserType,
+    state: &RwLock<CdpConnectionState>,
+    url: &str,
+    window_id: &str,
+) -> Result<Option<TabId>> {
+    let ws_url = browser_ws_url(browser, state).await?;
+    let Some(window) = state.read().await.windows.get(window_id).cloned() else {
+        return Ok(None);
+    };
+
+    let params = serde_json::json!({ "targetId": window.last_target });
+    send_command(browser, &ws_url, "Target.activateTarget", params, COMMAND_TIMEOUT).await?;
+    let params = serde_json::json!({ "url": url });
+    let result = send_command(browser, &ws_url, "Target.createTarget", params, COMMAND_TIMEOUT).await?;
+    let target_id = created_target_id(browser, &result)?;
+    let tab_id = Uuid::parse_str(&target_id).map(TabId).map_err(|_| WebPageManagerError::BrowserConnection {
+        source: BrowserConnectionError::InvalidResponse { browser },
+    })?;
+
+    if let Some(blank) = &window.blank_target {
+        let params = serde_json::json!({ "targetId": blank });
+        if let Err(e) = send_command(browser, &ws_url, "Target.closeTarget", params, COMMAND_TIMEOUT).await {
+            tracing::warn!("Failed to close the blank tab of {:?} window {}: {}", browser, window_id, e);
+        }
+    }
+    state.write().await.windows.insert(
+        window_id.to_string(),
+        CdpWindow {
+            last_target: target_id,
+            blank_target: None,
+        },
+    );
+    Ok(Some(tab_id))
 }
 
 // Helper functions for basic HTML content extraction
@@ -1088,6 +1348,28 @@ mod tests {
         assert_eq!(extract_meta_description(html), Some("This is a test description".to_string()));
     }
 
+    #[test]
+    fn test_created_target_id() {
+        let result = serde_json::json!({ "targetId": "8C4D6A1B2E3F40516273849A0B1C2D3E" });
+        assert_eq!(created_target_id(BrowserType::Chrome, &result).unwrap(), "8C4D6A1B2E3F40516273849A0B1C2D3E");
+        assert!(Uuid::parse_str("8C4D6A1B2E3F40516273849A0B1C2D3E").is_ok());
+        assert!(created_target_id(BrowserType::Edge, &serde_json::json!({})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tab_in_unknown_window_falls_back() {
+        let state = RwLock::new(CdpConnectionState {
+            connected: true,
+            ws_url: Some("ws://localhost:9/devtools/browser/unused".to_string()),
+            ..Default::default()
+        });
+        let tab = open_tab_in_window(BrowserType::Chrome, &state, "https://example.com", "7").await.unwrap();
+        assert!(tab.is_none());
+
+        let disconnected = RwLock::new(CdpConnectionState::default());
+        assert!(open_window(BrowserType::Chrome, &disconnected).await.is_err());
+    }
+
     #[test]
     fn test_extract_text_content() {
         let html = "<html><body><p>Hello World</p><script>var x = 1;</script></body></html>";
@@ -34,8 +34,27 @@ pub enum ExtensionMessage {
     CloseTab { tab_id: i64 },
     #[serde(rename = "activateTab")]
     ActivateTab { tab_id: i64 },
+    /// `tabs.create`, in the given window or the current one
     #[serde(rename = "createTab")]
-    CreateTab { url: String },
+    CreateTab {
+        url: String,
+        #[serde(default)]
+        window_id: Option<i64>,
+        #[serde(default)]
+        pinned: bool,
+    },
+    /// `windows.create`, opening an empty window
+    #[serde(rename = "createWindow")]
+    CreateWindow,
+    /// `bookmarks.create` in the folder at `folder_path`, created as needed
+    #[serde(rename = "createBookmark")]
+    CreateBookmark { url: String, title: String, folder_path: Vec<String> },
+    /// `bookmarks.update` of the title, then `bookmarks.move` to the folder
+    #[serde(rename = "updateBookmark")]
+    UpdateBookmark { bookmark_id: Uuid, title: String, folder_path: Vec<String> },
+    /// `bookmarks.remove`
+    #[serde(rename = "removeBookmark")]
+    RemoveBookmark { bookmark_id: Uuid },
     #[serde(rename = "getPageContent")]
     GetPageContent { tab_id: i64 },
 }
@@ -53,7 +72,15 @@ pub enum ExtensionResponse {
     #[serde(rename = "tabActivated")]
     TabActivated { success: bool },
     #[serde(rename = "tabCreated")]
-    TabCreated { tab_id: i64 },
+    TabCreated { tab_id: Uuid },
+    #[serde(rename = "windowCreated")]
+    WindowCreated { window_id: i64 },
+    #[serde(rename = "bookmarkCreated")]
+    BookmarkCreated { bookmark_id: Uuid },
+    #[serde(rename = "bookmarkUpdated")]
+    BookmarkUpdated { success: bool },
+    #[serde(rename = "bookmarkRemoved")]
+    BookmarkRemoved { success: bool },
     #[serde(rename = "pageContent")]
     PageContent { content: String, title: String },
     #[serde(rename = "error")]
@@ -248,6 +275,36 @@ impl FirefoxConnector {
         }
     }
 
+    /// Send a message to the extension, returning its reply
+    ///
+    /// Fails unless connected with the extension installed. The native
+    /// messaging relay does not return replies yet, so this is None.
+    async fn send_message(&self, message: ExtensionMessage) -> Result<Option<ExtensionResponse>> {
+        let state = self.state.read().await;
+        if !state.connected {
+            return Err(WebPageManagerError::BrowserConnection {
+                source: BrowserConnectionError::BrowserNotRunning {
+                    browser: BrowserType::Firefox,
+                },
+            });
+        }
+        
+        if !state.extension_installed {
+            return Err(WebPageManagerError::BrowserConnection {
+                source: BrowserConnectionError::PermissionDenied {
+                    browser: BrowserType::Firefox,
+                },
+            });
+        }
+        drop(state);
+        
+        // In a full implementation, this would write the message to the
+        // native messaging host and wait for the extension's reply
+        tracing::debug!("Firefox extension message: {:?}", message);
+        
+        Ok(None)
+    }
+
     /// Convert Firefox tab to TabInfo
     #[allow(dead_code)]
     fn firefox_tab_to_tab_info(&self, tab: &FirefoxTab) -> TabInfo {
@@ -512,29 +569,99 @@ impl BrowserConnector for FirefoxConnector {
     }
 
     async fn create_tab(&self, url: &str) -> Result<TabId> {
+        self.create_tab_with(url, None, false).await
+    }
+
+    async fn create_window(&self) -> Result<Option<String>> {
+        tracing::info!("Opening Firefox window");
+        
+        match self.send_message(ExtensionMessage::CreateWindow).await? {
+            Some(ExtensionResponse::WindowCreated { window_id }) => Ok(Some(window_id.to_string())),
+            Some(response) => Err(unexpected_response(response)),
+            None => Err(no_reply()),
+        }
+    }
+
+    async fn create_tab_with(&self, url: &str, window_id: Option<&str>, pinned: bool) -> Result<TabId> {
         tracing::info!("Creating Firefox tab: {}", url);
         
-        let state = self.state.read().await;
-        if !state.connected {
-            return Err(WebPageManagerError::BrowserConnection {
-                source: BrowserConnectionError::BrowserNotRunning {
-                    browser: BrowserType::Firefox,
-                },
-            });
+        // Window IDs from other browsers are not Firefox windows
+        let window_id = window_id.and_then(|id| id.parse().ok());
+        let message = ExtensionMessage::CreateTab {
+            url: url.to_string(),
+            window_id,
+            pinned,
+        };
+        match self.send_message(message).await? {
+            Some(ExtensionResponse::TabCreated { tab_id }) => Ok(TabId(tab_id)),
+            Some(response) => Err(unexpected_response(response)),
+            None => Err(no_reply()),
         }
+    }
+
+    async fn create_bookmark(&self, bookmark: &BookmarkInfo) -> Result<BookmarkId> {
+        tracing::info!("Creating Firefox bookmark: {}", bookmark.url);
         
-        if !state.extension_installed {
-            return Err(WebPageManagerError::BrowserConnection {
-                source: BrowserConnectionError::PermissionDenied {
-                    browser: BrowserType::Firefox,
-                },
-            });
+        let message = ExtensionMessage::CreateBookmark {
+            url: bookmark.url.clone(),
+            title: bookmark.title.clone(),
+            folder_path: bookmark.folder_path.clone(),
+        };
+        match self.send_message(message).await? {
+            Some(ExtensionResponse::BookmarkCreated { bookmark_id }) => Ok(BookmarkId(bookmark_id)),
+            Some(response) => Err(unexpected_response(response)),
+            None => Err(no_reply()),
         }
-        drop(state);
+    }
+
+    async fn update_bookmark(&self, bookmark_id: &BookmarkId, title: &str, folder_path: &[String]) -> Result<()> {
+        tracing::info!("Updating Firefox bookmark: {:?}", bookmark_id);
         
-        // In a full implementation, this would send a message to the Firefox extension
+        let message = ExtensionMessage::UpdateBookmark {
+            bookmark_id: bookmark_id.0,
+            title: title.to_string(),
+            folder_path: folder_path.to_vec(),
+        };
+        match self.send_message(message).await? {
+            Some(ExtensionResponse::BookmarkUpdated { success: true }) => Ok(()),
+            Some(response) => Err(unexpected_response(response)),
+            None => Err(no_reply()),
+        }
+    }
+
+    async fn delete_bookmark(&self, bookmark_id: &BookmarkId) -> Result<()> {
+        tracing::info!("Deleting Firefox bookmark: {:?}", bookmark_id);
         
-        Ok(TabId::new())
+        match self.send_message(ExtensionMessage::RemoveBookmark { bookmark_id: bookmark_id.0 }).await? {
+            Some(ExtensionResponse::BookmarkRemoved { success: true }) => Ok(()),
+            Some(response) => Err(unexpected_response(response)),
+            None => Err(no_reply()),
+        }
+    }
+}
+
+/// Error for an extension reply other than the one a message expects
+fn unexpected_response(response: ExtensionResponse) -> WebPageManagerError {
+    match response {
+        ExtensionResponse::Error { message } => tracing::warn!("Firefox extension refused: {}", message),
+        response => tracing::warn!("Unexpected Firefox extension reply: {:?}", response),
+    }
+    WebPageManagerError::BrowserConnection {
+        source: BrowserConnectionError::InvalidResponse {
+            browser: BrowserType::Firefox,
+        },
+    }
+}
+
+/// Error for a message sent without a reply, so its effect is unknown
+///
+/// The native messaging relay does not return replies yet; until it does,
+/// writes are refused rather than reported as done.
+fn no_reply() -> WebPageManagerError {
+    WebPageManagerError::BrowserConnection {
+        source: BrowserConnectionError::PermissionDenied {
+            browser: BrowserType::Firefox,
+        },
     }
 }
 
@@ -630,6 +757,90 @@ mod tests {
         assert!(!connector.is_connected());
     }
 
+    #[test]
+    fn test_window_and_tab_messages() {
+        let message = ExtensionMessage::CreateTab {
+            url: "https://example.com".to_string(),
+            window_id: Some(3),
+            pinned: true,
+        };
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({ "type": "createTab", "url": "https://example.com", "window_id": 3, "pinned": true })
+        );
+        assert_eq!(serde_json::to_value(ExtensionMessage::CreateWindow).unwrap(), serde_json::json!({ "type": "createWindow" }));
+
+        let reply: ExtensionResponse = serde_json::from_str(r#"{"type":"windowCreated","window_id":7}"#).unwrap();
+        assert!(matches!(reply, ExtensionResponse::WindowCreated { window_id: 7 }));
+    }
+
+    #[test]
+    fn test_bookmark_messages() {
+        let bookmark_id = Uuid::new_v4();
+        let message = ExtensionMessage::UpdateBookmark {
+            bookmark_id,
+            title: "Docs".to_string(),
+            folder_path: vec!["Toolbar".to_string(), "Rust".to_string()],
+        };
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({
+                "type": "updateBookmark",
+                "bookmark_id": bookmark_id,
+                "title": "Docs",
+                "folder_path": ["Toolbar", "Rust"],
+            })
+        );
+
+        let reply: ExtensionResponse =
+            serde_json::from_value(serde_json::json!({ "type": "bookmarkCreated", "bookmark_id": bookmark_id })).unwrap();
+        assert!(matches!(reply, ExtensionResponse::BookmarkCreated { bookmark_id: id } if id == bookmark_id));
+    }
+
+    #[tokio::test]
+    async fn test_bookmark_writes_need_connection() {
+        let connector = FirefoxConnector::new();
+        let bookmark_id = BookmarkId::new();
+        assert!(connector.update_bookmark(&bookmark_id, "Docs", &[]).await.is_err());
+        assert!(connector.delete_bookmark(&bookmark_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_window_needs_connection() {
+        let connector = FirefoxConnector::new();
+        assert!(connector.create_window().await.is_err());
+        assert!(connector.create_tab_with("https://example.com", Some("3"), true).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_writes_without_reply_fail() {
+        let connector = FirefoxConnector::new();
+        {
+            let mut state = connector.state.write().await;
+            state.connected = true;
+            state.extension_installed = true;
+        }
+        let bookmark = BookmarkInfo {
+            id: BookmarkId::new(),
+            url: "https://example.com".to_string(),
+            title: "Example".to_string(),
+            favicon_url: None,
+            browser_type: BrowserType::Firefox,
+            folder_path: vec!["Toolbar".to_string()],
+            created_at: Utc::now(),
+            last_accessed: None,
+        };
+        assert!(connector.create_bookmark(&bookmark).await.is_err());
+        assert!(connector.update_bookmark(&bookmark.id, "Docs", &[]).await.is_err());
+        assert!(connector.delete_bookmark(&bookmark.id).await.is_err());
+        assert!(connector.create_window().await.is_err());
+        assert!(connector.create_tab_with("https://example.com", Some("3"), true).await.is_err());
+
+        let reply: ExtensionResponse =
+            serde_json::from_value(serde_json::json!({ "type": "tabCreated", "tab_id": bookmark.id.0 })).unwrap();
+        assert!(matches!(reply, ExtensionResponse::TabCreated { tab_id } if tab_id == bookmark.id.0));
+    }
+
     #[test]
     fn test_extract_title() {
         let html = "<html><head><title>Firefox Test</title></head></html>";
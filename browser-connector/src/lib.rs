@@ -6,13 +6,14 @@
 //!
 //! # Features
 //! - Automatic browser detection for Chrome, Edge, and Firefox
-//! - CDP (Chrome DevTools Protocol) support for Chromium-based browsers
+//! - CDP (Chrome DevTools Protocol) support for Chromium-based browsers, with MHTML page capture
 //! - WebExtensions Native Messaging support for Firefox
 //! - Privacy mode filtering to exclude incognito/private tabs
 //! - Browser instance lifecycle management
 //! - Tab state monitoring and change detection
 //! - Enhanced tab information extraction and categorization
 //! - Bookmark import from multiple browsers with validation
+//! - Bookmark create, update and delete through connectors that can write bookmarks
 
 pub mod traits;
 pub mod cdp;
@@ -273,8 +274,22 @@ impl BrowserConnectorManager {
         }
     }
 
+    /// Use an already connected connector for its browser, replacing any
+    /// earlier connection to that browser
+    pub async fn add_connector(&self, connector: Box<dyn BrowserConnector>) {
+        let browser_type = connector.browser_type();
+        self.connections.write().await.insert(browser_type, connector);
+
+        let mut instances = self.instances.write().await;
+        if let Some(instance) = instances.get_mut(&browser_type) {
+            instance.status = ConnectionStatus::Connected;
+            instance.last_error = None;
+            instance.connected_at = Some(chrono::Utc::now());
+        }
+    }
+
     /// Connect to all detected browsers
-    /// 
+    ///
     /// Returns a list of browser types that were successfully connected
     pub async fn connect_all(&self) -> Vec<BrowserType> {
         let mut connected = Vec::new();
@@ -399,6 +414,20 @@ impl BrowserConnectorManager {
         connector.close_tab(tab_id).await
     }
 
+    /// Capture a tab in a specific browser as MHTML; None if the browser
+    /// cannot
+    pub async fn capture_mhtml(&self, browser_type: BrowserType, tab_id: &TabId) -> Result<Option<Vec<u8>>> {
+        let connections = self.connections.read().await;
+
+        let connector = connections.get(&browser_type).ok_or(WebPageManagerError::BrowserConnection {
+            source: BrowserConnectionError::BrowserNotRunning {
+                browser: browser_type,
+            },
+        })?;
+
+        connector.capture_mhtml(tab_id).await
+    }
+
     /// Activate a tab in a specific browser
     pub async fn activate_tab(&self, browser_type: BrowserType, tab_id: &TabId) -> Result<()> {
         let connections = self.connections.read().await;
@@ -429,6 +458,84 @@ impl BrowserConnectorManager {
         connector.create_tab(url).await
     }
 
+    /// Open a new window in a specific browser, returning its ID; None if
+    /// the browser cannot, so tabs open in the current window
+    pub async fn create_window(&self, browser_type: BrowserType) -> Result<Option<String>> {
+        let connections = self.connections.read().await;
+
+        let connector = connections.get(&browser_type).ok_or(WebPageManagerError::BrowserConnection {
+            source: BrowserConnectionError::BrowserNotRunning {
+                browser: browser_type,
+            },
+        })?;
+
+        connector.create_window().await
+    }
+
+    /// Create a new tab in a window of a specific browser, pinned or not
+    pub async fn create_tab_with(
+        &self,
+        browser_type: BrowserType,
+        url: &str,
+        window_id: Option<&str>,
+        pinned: bool,
+    ) -> Result<TabId> {
+        let connections = self.connections.read().await;
+
+        let connector = connections.get(&browser_type).ok_or(WebPageManagerError::BrowserConnection {
+            source: BrowserConnectionError::BrowserNotRunning {
+                browser: browser_type,
+            },
+        })?;
+
+        connector.create_tab_with(url, window_id, pinned).await
+    }
+
+    /// Create a bookmark in a specific browser, returning its ID
+    pub async fn create_bookmark(&self, browser_type: BrowserType, bookmark: &BookmarkInfo) -> Result<BookmarkId> {
+        let connections = self.connections.read().await;
+
+        let connector = connections.get(&browser_type).ok_or(WebPageManagerError::BrowserConnection {
+            source: BrowserConnectionError::BrowserNotRunning {
+                browser: browser_type,
+            },
+        })?;
+
+        connector.create_bookmark(bookmark).await
+    }
+
+    /// Change the title and folder path of a bookmark in a specific browser
+    pub async fn update_bookmark(
+        &self,
+        browser_type: BrowserType,
+        bookmark_id: &BookmarkId,
+        title: &str,
+        folder_path: &[String],
+    ) -> Result<()> {
+        let connections = self.connections.read().await;
+
+        let connector = connections.get(&browser_type).ok_or(WebPageManagerError::BrowserConnection {
+            source: BrowserConnectionError::BrowserNotRunning {
+                browser: browser_type,
+            },
+        })?;
+
+        connector.update_bookmark(bookmark_id, title, folder_path).await
+    }
+
+    /// Delete a bookmark in a specific browser
+    pub async fn delete_bookmark(&self, browser_type: BrowserType, bookmark_id: &BookmarkId) -> Result<()> {
+        let connections = self.connections.read().await;
+
+        let connector = connections.get(&browser_type).ok_or(WebPageManagerError::BrowserConnection {
+            source: BrowserConnectionError::BrowserNotRunning {
+                browser: browser_type,
+            },
+        })?;
+
+        connector.delete_bookmark(bookmark_id).await
+    }
+
     /// Disconnect from a specific browser
     pub async fn disconnect(&self, browser_type: BrowserType) -> Result<()> {
         let mut connections = self.connections.write().await;
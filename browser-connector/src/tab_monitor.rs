@@ -2,6 +2,8 @@
 //!
 //! This module provides functionality to monitor tab state changes across
 //! multiple browsers, including tab creation, closure, navigation, and updates.
+//! It also tracks when each tab was last active: the later of the time its
+//! browser reports, its last navigation and its last recorded activation.
 
 use web_page_manager_core::{BrowserType, TabId, TabInfo, Utc};
 use std::collections::HashMap;
@@ -61,6 +63,8 @@ pub enum TabEvent {
 pub struct TabSnapshot {
     pub tab: TabInfo,
     pub captured_at: DateTime<chrono::Utc>,
+    /// When the tab was last used
+    pub last_active: DateTime<chrono::Utc>,
 }
 
 /// Configuration for the tab monitor
@@ -160,8 +164,11 @@ impl TabMonitor {
                 
                 let key = (browser_type, tab.id.clone());
                 seen_tabs.insert(key.clone(), true);
+                let mut last_active = tab.last_accessed;
                 
                 if let Some(previous) = current_states.get(&key) {
+                    last_active = last_active.max(previous.last_active);
+
                     // Tab exists - check for changes
                     
                     // Check for navigation
@@ -174,6 +181,7 @@ impl TabMonitor {
                             timestamp: now,
                         };
                         events.push(event);
+                        last_active = now;
                     }
                     
                     // Check for title change
@@ -200,6 +208,7 @@ impl TabMonitor {
                 current_states.insert(key, TabSnapshot {
                     tab,
                     captured_at: now,
+                    last_active,
                 });
             }
         }
@@ -288,6 +297,40 @@ impl TabMonitor {
         states.get(&(browser_type, tab_id.clone())).map(|s| s.tab.clone())
     }
 
+    /// Record that a tab was activated, returning false if it is not
+    /// monitored
+    pub async fn record_activation(&self, browser_type: BrowserType, tab_id: &TabId) -> bool {
+        let now = Utc::now();
+        {
+            let mut states = self.tab_states.write().await;
+            let Some(snapshot) = states.get_mut(&(browser_type, tab_id.clone())) else {
+                return false;
+            };
+            snapshot.last_active = now;
+        }
+
+        let events = [TabEvent::Activated {
+            tab_id: tab_id.clone(),
+            browser_type,
+            timestamp: now,
+        }];
+        self.store_events(&events).await;
+        self.broadcast_events(&events).await;
+        true
+    }
+
+    /// When a tab was last used
+    pub async fn get_last_active(&self, browser_type: BrowserType, tab_id: &TabId) -> Option<DateTime<chrono::Utc>> {
+        let states = self.tab_states.read().await;
+        states.get(&(browser_type, tab_id.clone())).map(|s| s.last_active)
+    }
+
+    /// All monitored tabs with when each was last used
+    pub async fn get_tab_activity(&self) -> Vec<(TabInfo, DateTime<chrono::Utc>)> {
+        let states = self.tab_states.read().await;
+        states.values().map(|s| (s.tab.clone(), s.last_active)).collect()
+    }
+
     /// Get recent events
     pub async fn get_recent_events(&self, count: usize) -> Vec<TabEvent> {
         let history = self.event_history.read().await;
@@ -405,9 +448,10 @@ mod tests {
     use super::*;
     use web_page_manager_core::{TabId, Uuid};
 
+    /// Tab whose ID is derived from `id`, so the same name is the same tab
     fn create_test_tab(id: &str, url: &str, title: &str, browser_type: BrowserType) -> TabInfo {
         TabInfo {
-            id: TabId(Uuid::try_parse(id).unwrap()),
+            id: TabId(Uuid::new_v5(&Uuid::NAMESPACE_OID, id.as_bytes())),
             url: url.to_string(),
             title: title.to_string(),
             favicon_url: None,
@@ -418,6 +462,26 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_last_active_tracking() {
+        let monitor = TabMonitor::new();
+        let mut tab = create_test_tab("tab1", "https://example.com", "Example", BrowserType::Chrome);
+        tab.last_accessed = Utc::now() - Duration::days(3);
+        let mut tabs = HashMap::new();
+        tabs.insert(BrowserType::Chrome, vec![tab.clone()]);
+        monitor.update_tabs(tabs.clone()).await;
+        assert_eq!(monitor.get_last_active(BrowserType::Chrome, &tab.id).await, Some(tab.last_accessed));
+
+        // An activation is kept even if the browser reports an older time
+        assert!(monitor.record_activation(BrowserType::Chrome, &tab.id).await);
+        assert!(!monitor.record_activation(BrowserType::Firefox, &tab.id).await);
+        monitor.update_tabs(tabs).await;
+        let activity = monitor.get_tab_activity().await;
+        assert_eq!(activity.len(), 1);
+        assert!(activity[0].1 > Utc::now() - Duration::minutes(1));
+        assert!(matches!(monitor.get_recent_events(1).await[0], TabEvent::Activated { .. }));
+    }
+
     #[tokio::test]
     async fn test_monitor_creation() {
         let monitor = TabMonitor::new();
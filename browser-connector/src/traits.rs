@@ -35,4 +35,55 @@ pub trait BrowserConnector: Send + Sync {
     
     /// Create a new tab
     async fn create_tab(&self, url: &str) -> Result<TabId>;
+
+    /// Open a new window, returning its ID; None if the browser cannot,
+    /// so tabs open in the current window
+    async fn create_window(&self) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Create a new tab in a window, pinned or not
+    ///
+    /// Connectors that cannot place or pin tabs create a plain tab.
+    async fn create_tab_with(&self, url: &str, window_id: Option<&str>, pinned: bool) -> Result<TabId> {
+        let _ = (window_id, pinned);
+        self.create_tab(url).await
+    }
+
+    /// Create a bookmark at the URL, title and folder path of `bookmark`,
+    /// returning the ID of the new bookmark
+    ///
+    /// Connectors that cannot write bookmarks refuse with permission denied,
+    /// as do the other bookmark writes. Firefox sends them to its extension
+    /// and fails until the extension's replies are relayed; Chrome and Edge
+    /// cannot, as CDP has no bookmarks domain.
+    async fn create_bookmark(&self, bookmark: &BookmarkInfo) -> Result<BookmarkId> {
+        let _ = bookmark;
+        Err(bookmark_write_denied(self.browser_type()))
+    }
+
+    /// Change the title and folder path of a bookmark
+    async fn update_bookmark(&self, bookmark_id: &BookmarkId, title: &str, folder_path: &[String]) -> Result<()> {
+        let _ = (bookmark_id, title, folder_path);
+        Err(bookmark_write_denied(self.browser_type()))
+    }
+
+    /// Delete a bookmark
+    async fn delete_bookmark(&self, bookmark_id: &BookmarkId) -> Result<()> {
+        let _ = bookmark_id;
+        Err(bookmark_write_denied(self.browser_type()))
+    }
+
+    /// Capture the rendered page of a tab as MHTML, the page with its
+    /// resources in one file; None if the browser cannot
+    async fn capture_mhtml(&self, tab_id: &TabId) -> Result<Option<Vec<u8>>> {
+        let _ = tab_id;
+        Ok(None)
+    }
+}
+
+fn bookmark_write_denied(browser: BrowserType) -> WebPageManagerError {
+    WebPageManagerError::BrowserConnection {
+        source: BrowserConnectionError::PermissionDenied { browser },
+    }
 }
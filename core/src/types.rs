@@ -280,6 +280,9 @@ pub struct HistoryEntry {
     pub tab_id: Option<TabId>,
     pub closed_at: DateTime<Utc>,
     pub session_info: Option<SessionInfo>,
+    /// Short explanation of why the page was saved, to help rediscover it
+    #[serde(default)]
+    pub recall_hint: Option<String>,
 }
 
 /// Session information for history entries
@@ -289,6 +292,9 @@ pub struct SessionInfo {
     pub window_id: Option<String>,
     pub tab_index: Option<u32>,
     pub scroll_position: Option<u32>,
+    /// Whether the tab was pinned in its window
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 /// Filter for querying history entries
@@ -304,12 +310,24 @@ pub struct HistoryFilter {
 }
 
 /// Retention policy for automatic history cleanup
+///
+/// Pinned entries are always kept.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RetentionPolicy {
     pub max_age_days: u32,
     pub max_entries: usize,
     pub preserve_important: bool,
     pub importance_threshold: f32,
+    /// Maximum age in days for pages of a category, overriding
+    /// `max_age_days`; categories are compared ignoring case
+    #[serde(default)]
+    pub category_max_age_days: HashMap<String, u32>,
+    /// Keep entries of bookmarked pages regardless of age, count and size
+    #[serde(default)]
+    pub keep_bookmarked: bool,
+    /// Maximum total size of the entries in bytes, as serialized
+    #[serde(default)]
+    pub max_total_bytes: Option<u64>,
 }
 
 impl Default for RetentionPolicy {
@@ -319,10 +337,26 @@ impl Default for RetentionPolicy {
             max_entries: 10000,
             preserve_important: true,
             importance_threshold: 0.7,
+            category_max_age_days: HashMap::new(),
+            keep_bookmarked: false,
+            max_total_bytes: None,
         }
     }
 }
 
+impl RetentionPolicy {
+    /// Maximum age in days of pages in `category`
+    pub fn max_age_days_for(&self, category: Option<&str>) -> u32 {
+        category
+            .and_then(|category| {
+                self.category_max_age_days
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(category))
+            })
+            .map_or(self.max_age_days, |(_, days)| *days)
+    }
+}
+
 /// Match information for tab-bookmark associations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MatchInfo {
@@ -333,15 +367,48 @@ pub struct MatchInfo {
     pub matched_at: DateTime<Utc>,
 }
 
+impl MatchInfo {
+    /// How certain the association is
+    pub fn tier(&self) -> MatchTier {
+        self.match_type.tier()
+    }
+}
+
 /// Type of match between tab and bookmark
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MatchType {
     ExactUrl,
+    /// Different URLs serving the same content hash
+    SameContent,
+    /// Similar titles on the same domain
+    SimilarTitle,
     SameDomain,
     SimilarContent,
     UserDefined,
 }
 
+impl MatchType {
+    /// Certainty tier of matches of this type
+    pub fn tier(&self) -> MatchTier {
+        match self {
+            MatchType::ExactUrl | MatchType::UserDefined => MatchTier::Exact,
+            MatchType::SameContent => MatchTier::Strong,
+            MatchType::SimilarTitle | MatchType::SameDomain | MatchType::SimilarContent => MatchTier::Weak,
+        }
+    }
+}
+
+/// Certainty of a tab-bookmark association, from certain to guessed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum MatchTier {
+    /// Same page after URL normalization, or linked by the user
+    Exact,
+    /// Same content under a different URL
+    Strong,
+    /// Likely related, e.g. a similar title on the same domain
+    Weak,
+}
+
 /// Result of bookmark analysis
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BookmarkAnalysisResult {
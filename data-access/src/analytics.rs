@@ -0,0 +1,386 @@
+//! Usage analytics for the statistics dashboard
+//!
+//! Page accesses, searches and tab lifetimes are recorded as events. Daily
+//! totals and per-domain access counts are kept up to date by triggers
+//! (see `schema::ANALYTICS_SQL`), so the dashboard's aggregations read the
+//! rollups rather than raw events or history.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use tokio_rusqlite::Connection;
+use web_page_manager_core::*;
+
+/// Accesses of one domain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainCount {
+    pub domain: String,
+    pub accesses: u64,
+}
+
+/// How often a search was run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchCount {
+    /// The query as first typed; repeats are matched ignoring case
+    pub query: String,
+    pub count: u64,
+    /// Results the latest run returned
+    pub last_result_count: u64,
+}
+
+/// Activity of one day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyUsage {
+    pub day: NaiveDate,
+    pub page_accesses: u64,
+    pub searches: u64,
+    /// Tab churn: tabs opened and closed that day
+    pub tabs_opened: u64,
+    pub tabs_closed: u64,
+}
+
+impl DailyUsage {
+    fn empty(day: NaiveDate) -> Self {
+        Self {
+            day,
+            page_accesses: 0,
+            searches: 0,
+            tabs_opened: 0,
+            tabs_closed: 0,
+        }
+    }
+}
+
+/// How long tabs stay open
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TabLifetimeStats {
+    /// Tabs closed in the period
+    pub closed: u64,
+    /// Tabs still open
+    pub open: u64,
+    pub average_secs: f64,
+    pub longest_secs: u64,
+}
+
+/// Repository trait for usage analytics
+#[async_trait]
+pub trait AnalyticsRepository: Send + Sync {
+    async fn record_access(&self, page_id: Option<&Uuid>, url: &str, at: DateTime<Utc>) -> Result<()>;
+    async fn record_search(&self, query: &str, result_count: usize, at: DateTime<Utc>) -> Result<()>;
+    /// Record a tab opening; a tab already recorded is left as it is
+    async fn record_tab_opened(&self, tab_id: &TabId, browser: BrowserType, url: &str, at: DateTime<Utc>) -> Result<()>;
+    /// Record a tab closing, returning false if it was not open
+    async fn record_tab_closed(&self, tab_id: &TabId, at: DateTime<Utc>) -> Result<bool>;
+    /// Most accessed domains from `since` on, most accessed first
+    async fn top_domains(&self, since: NaiveDate, limit: usize) -> Result<Vec<DomainCount>>;
+    /// Most repeated searches after `since`, most repeated first
+    async fn top_searches(&self, since: DateTime<Utc>, limit: usize) -> Result<Vec<SearchCount>>;
+    /// Activity of each day from `from` to `to`, including days without any
+    async fn daily_usage(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<DailyUsage>>;
+    /// Lifetimes of the tabs closed after `since`
+    async fn tab_lifetimes(&self, since: DateTime<Utc>) -> Result<TabLifetimeStats>;
+    /// Drop raw events before `before`, keeping the daily rollups and open
+    /// tabs; returns how many were removed
+    async fn prune_events(&self, before: DateTime<Utc>) -> Result<usize>;
+}
+
+/// Host of a URL, lowercased, or "unknown" if it has none
+pub(crate) fn domain_of(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = if host.starts_with('[') {
+        // IPv6 literal, kept with its brackets
+        host.split_inclusive(']').next().unwrap_or(host)
+    } else {
+        host.split(':').next().unwrap_or(host)
+    };
+    if host.is_empty() || !url.contains("://") {
+        "unknown".to_string()
+    } else {
+        host.to_ascii_lowercase()
+    }
+}
+
+/// SQLite implementation of AnalyticsRepository
+#[derive(Clone)]
+pub struct SqliteAnalyticsRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteAnalyticsRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+}
+
+fn analytics_error(action: &str, e: tokio_rusqlite::Error) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Failed to {}: {}", action, e),
+        },
+    }
+}
+
+#[async_trait]
+impl AnalyticsRepository for SqliteAnalyticsRepository {
+    async fn record_access(&self, page_id: Option<&Uuid>, url: &str, at: DateTime<Utc>) -> Result<()> {
+        let page_id = page_id.map(Uuid::to_string);
+        let url = url.to_string();
+        let domain = domain_of(&url);
+
+        self.connection
+            .call(move |conn| {
+                conn.prepare_cached(
+                    "INSERT INTO page_access_events (page_id, url, domain, accessed_at) VALUES (?1, ?2, ?3, ?4)",
+                )?
+                .execute(rusqlite::params![page_id, url, domain, at.timestamp()])?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| analytics_error("record page access", e))
+    }
+
+    async fn record_search(&self, query: &str, result_count: usize, at: DateTime<Utc>) -> Result<()> {
+        let query = query.trim().to_string();
+        if query.is_empty() {
+            return Ok(());
+        }
+
+        self.connection
+            .call(move |conn| {
+                conn.prepare_cached(
+                    "INSERT INTO search_queries (query, result_count, searched_at) VALUES (?1, ?2, ?3)",
+                )?
+                .execute(rusqlite::params![query, result_count as i64, at.timestamp()])?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| analytics_error("record search", e))
+    }
+
+    async fn record_tab_opened(&self, tab_id: &TabId, browser: BrowserType, url: &str, at: DateTime<Utc>) -> Result<()> {
+        let tab_id = tab_id.0.to_string();
+        let browser = serde_json::to_string(&browser).unwrap_or_default();
+        let domain = domain_of(url);
+
+        self.connection
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT OR IGNORE INTO tab_lifetimes (tab_id, browser_type, domain, opened_at) \
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![tab_id, browser, domain, at.timestamp()],
+                )?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| analytics_error("record tab opening", e))
+    }
+
+    async fn record_tab_closed(&self, tab_id: &TabId, at: DateTime<Utc>) -> Result<bool> {
+        let tab_id = tab_id.0.to_string();
+
+        self.connection
+            .call(move |conn| {
+                let updated = conn.execute(
+                    "UPDATE tab_lifetimes SET closed_at = MAX(?2, opened_at) WHERE tab_id = ?1 AND closed_at IS NULL",
+                    rusqlite::params![tab_id, at.timestamp()],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+            .map_err(|e| analytics_error("record tab closing", e))
+    }
+
+    async fn top_domains(&self, since: NaiveDate, limit: usize) -> Result<Vec<DomainCount>> {
+        let since = since.to_string();
+
+        self.connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT domain, SUM(accesses) AS total FROM domain_usage_daily WHERE day >= ?1 \
+                     GROUP BY domain ORDER BY total DESC, domain LIMIT ?2",
+                )?;
+                let rows = stmt.query_map(rusqlite::params![since, limit as i64], |row| {
+                    Ok(DomainCount {
+                        domain: row.get(0)?,
+                        accesses: row.get::<_, i64>(1)? as u64,
+                    })
+                })?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
+            .await
+            .map_err(|e| analytics_error("aggregate domains", e))
+    }
+
+    async fn top_searches(&self, since: DateTime<Utc>, limit: usize) -> Result<Vec<SearchCount>> {
+        let ts = since.timestamp();
+
+        self.connection
+            .call(move |conn| {
+                // Bare columns next to MAX() come from the row holding the maximum
+                let mut stmt = conn.prepare(
+                    "SELECT MIN(query), COUNT(*) AS runs, result_count, MAX(searched_at) FROM search_queries \
+                     WHERE searched_at > ?1 GROUP BY lower(query) ORDER BY runs DESC, MAX(searched_at) DESC LIMIT ?2",
+                )?;
+                let rows = stmt.query_map(rusqlite::params![ts, limit as i64], |row| {
+                    Ok(SearchCount {
+                        query: row.get(0)?,
+                        count: row.get::<_, i64>(1)? as u64,
+                        last_result_count: row.get::<_, i64>(2)? as u64,
+                    })
+                })?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
+            .await
+            .map_err(|e| analytics_error("aggregate searches", e))
+    }
+
+    async fn daily_usage(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<DailyUsage>> {
+        let (first, last) = (from.to_string(), to.to_string());
+
+        let recorded = self
+            .connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT day, page_accesses, searches, tabs_opened, tabs_closed FROM usage_daily \
+                     WHERE day BETWEEN ?1 AND ?2 ORDER BY day",
+                )?;
+                let rows = stmt.query_map([first, last], |row| {
+                    let day: String = row.get(0)?;
+                    Ok((day, [row.get::<_, i64>(1)?, row.get(2)?, row.get(3)?, row.get(4)?]))
+                })?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
+            .await
+            .map_err(|e| analytics_error("aggregate daily usage", e))?;
+
+        let mut days: Vec<DailyUsage> = from.iter_days().take_while(|day| *day <= to).map(DailyUsage::empty).collect();
+        for (day, [accesses, searches, opened, closed]) in recorded {
+            let Ok(day) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") else { continue };
+            let index = (day - from).num_days() as usize;
+            if let Some(usage) = days.get_mut(index) {
+                usage.page_accesses = accesses as u64;
+                usage.searches = searches as u64;
+                usage.tabs_opened = opened as u64;
+                usage.tabs_closed = closed as u64;
+            }
+        }
+        Ok(days)
+    }
+
+    async fn tab_lifetimes(&self, since: DateTime<Utc>) -> Result<TabLifetimeStats> {
+        let ts = since.timestamp();
+
+        self.connection
+            .call(move |conn| {
+                let (closed, average, longest): (i64, Option<f64>, Option<i64>) = conn.query_row(
+                    "SELECT COUNT(*), AVG(closed_at - opened_at), MAX(closed_at - opened_at) \
+                     FROM tab_lifetimes WHERE closed_at > ?1",
+                    [ts],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )?;
+                let open: i64 =
+                    conn.query_row("SELECT COUNT(*) FROM tab_lifetimes WHERE closed_at IS NULL", [], |row| row.get(0))?;
+                Ok(TabLifetimeStats {
+                    closed: closed as u64,
+                    open: open as u64,
+                    average_secs: average.unwrap_or(0.0),
+                    longest_secs: longest.unwrap_or(0) as u64,
+                })
+            })
+            .await
+            .map_err(|e| analytics_error("aggregate tab lifetimes", e))
+    }
+
+    async fn prune_events(&self, before: DateTime<Utc>) -> Result<usize> {
+        let ts = before.timestamp();
+
+        self.connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                let mut pruned = tx.execute("DELETE FROM page_access_events WHERE accessed_at < ?1", [ts])?;
+                pruned += tx.execute("DELETE FROM search_queries WHERE searched_at < ?1", [ts])?;
+                pruned += tx.execute("DELETE FROM tab_lifetimes WHERE closed_at < ?1", [ts])?;
+                tx.commit()?;
+                Ok(pruned)
+            })
+            .await
+            .map_err(|e| analytics_error("prune usage events", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DatabaseManager;
+
+    #[test]
+    fn test_domain_of() {
+        assert_eq!(domain_of("https://Docs.Rust-Lang.org/std/?q=1"), "docs.rust-lang.org");
+        assert_eq!(domain_of("http://user:pw@example.com:8080/path"), "example.com");
+        assert_eq!(domain_of("http://[::1]:3000/"), "[::1]");
+        assert_eq!(domain_of("about:blank"), "unknown");
+        assert_eq!(domain_of("file:///home/me/page.html"), "unknown");
+    }
+
+    #[tokio::test]
+    async fn test_usage_rollups() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let analytics = db.analytics_repository();
+        let day = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        let at = |hour: u32| day.and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        let next = |hour: u32| at(hour) + chrono::Duration::days(1);
+
+        for url in ["https://a.example/1", "https://a.example/2", "https://b.example/"] {
+            analytics.record_access(None, url, at(9)).await.unwrap();
+        }
+        analytics.record_access(Some(&Uuid::new_v4()), "https://b.example/x", next(9)).await.unwrap();
+        analytics.record_access(None, "https://b.example/y", next(10)).await.unwrap();
+
+        analytics.record_search("Rust async", 4, at(10)).await.unwrap();
+        analytics.record_search("rust async", 2, next(10)).await.unwrap();
+        analytics.record_search("trams", 0, at(11)).await.unwrap();
+        analytics.record_search("  ", 0, at(11)).await.unwrap();
+
+        let (first, second) = (TabId::new(), TabId::new());
+        analytics.record_tab_opened(&first, BrowserType::Chrome, "https://a.example/", at(8)).await.unwrap();
+        analytics.record_tab_opened(&first, BrowserType::Chrome, "https://a.example/", at(9)).await.unwrap();
+        analytics.record_tab_opened(&second, BrowserType::Firefox, "https://b.example/", at(12)).await.unwrap();
+        assert!(analytics.record_tab_closed(&first, next(8)).await.unwrap());
+        assert!(!analytics.record_tab_closed(&first, next(9)).await.unwrap());
+
+        // b.example leads over both days, a.example on the first alone
+        let domains = analytics.top_domains(day, 10).await.unwrap();
+        assert_eq!(
+            domains,
+            vec![
+                DomainCount { domain: "b.example".to_string(), accesses: 3 },
+                DomainCount { domain: "a.example".to_string(), accesses: 2 },
+            ]
+        );
+        assert_eq!(analytics.top_domains(day.succ_opt().unwrap(), 1).await.unwrap()[0].accesses, 2);
+
+        let searches = analytics.top_searches(at(0), 10).await.unwrap();
+        assert_eq!(searches[0].count, 2);
+        assert_eq!(searches[0].last_result_count, 2);
+        assert_eq!(searches.len(), 2);
+
+        let usage = analytics.daily_usage(day, day + chrono::Duration::days(2)).await.unwrap();
+        assert_eq!(usage.len(), 3);
+        assert_eq!((usage[0].page_accesses, usage[0].searches, usage[0].tabs_opened, usage[0].tabs_closed), (3, 2, 2, 0));
+        assert_eq!((usage[1].page_accesses, usage[1].searches, usage[1].tabs_opened, usage[1].tabs_closed), (2, 1, 0, 1));
+        assert_eq!(usage[2], DailyUsage::empty(day + chrono::Duration::days(2)));
+
+        let lifetimes = analytics.tab_lifetimes(at(0)).await.unwrap();
+        assert_eq!(lifetimes.closed, 1);
+        assert_eq!(lifetimes.open, 1);
+        assert_eq!(lifetimes.longest_secs, 24 * 60 * 60);
+
+        // Pruning keeps the rollups and the open tab
+        assert_eq!(analytics.prune_events(next(23)).await.unwrap(), 9);
+        assert_eq!(analytics.top_domains(day, 10).await.unwrap(), domains);
+        assert_eq!(analytics.tab_lifetimes(at(0)).await.unwrap().open, 1);
+    }
+}
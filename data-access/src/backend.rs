@@ -0,0 +1,67 @@
+//! Storage backends
+//!
+//! A backend provides the core repositories behind trait objects, so code
+//! written against it runs on the local SQLite database or, with the
+//! `postgres` feature, on a PostgreSQL library shared by a team.
+
+use std::sync::Arc;
+
+use crate::repository::{GroupRepository, HistoryRepository, PageRepository};
+use crate::DatabaseManager;
+
+/// Store of pages, groups and history
+pub trait StorageBackend: Send + Sync {
+    fn pages(&self) -> Arc<dyn PageRepository>;
+    fn groups(&self) -> Arc<dyn GroupRepository>;
+    fn history(&self) -> Arc<dyn HistoryRepository>;
+}
+
+impl StorageBackend for DatabaseManager {
+    fn pages(&self) -> Arc<dyn PageRepository> {
+        Arc::new(self.page_repository())
+    }
+
+    fn groups(&self) -> Arc<dyn GroupRepository> {
+        Arc::new(self.group_repository())
+    }
+
+    fn history(&self) -> Arc<dyn HistoryRepository> {
+        Arc::new(self.history_repository())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use web_page_manager_core::*;
+
+    #[tokio::test]
+    async fn test_database_manager_as_backend() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let backend: &dyn StorageBackend = &db;
+
+        let page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: "https://example.com/".to_string(),
+            title: "Example".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Chrome,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        backend.pages().save(&page).await.unwrap();
+        assert_eq!(db.page_repository().get_by_id(&page.id).await.unwrap().unwrap().title, "Example");
+        assert_eq!(backend.groups().get_all().await.unwrap().len(), 0);
+        assert_eq!(backend.history().count().await.unwrap(), 0);
+    }
+}
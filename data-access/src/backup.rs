@@ -0,0 +1,324 @@
+//! Online backups with retention
+//!
+//! Backups use SQLite's online backup API, which copies the database page by
+//! page while it stays usable, so a consistent snapshot is taken without
+//! closing the application. Scheduled backups are written to one directory
+//! as timestamped files and only the newest few are kept.
+//!
+//! Restoring validates the backup first and copies it into the open
+//! database in a single transaction, so a bad file leaves the data as it was.
+//!
+//! Encrypted databases cannot be copied to plaintext files this way; use
+//! `DatabaseManager::encrypt_to` for them.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use tracing::{info, warn};
+use web_page_manager_core::*;
+
+use crate::{schema, DatabaseManager};
+
+/// Prefix and extension of scheduled backup files
+const BACKUP_PREFIX: &str = "backup-";
+const BACKUP_EXTENSION: &str = "db";
+/// Timestamp in backup file names, sortable as text
+const BACKUP_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Schedule and retention of automatic backups
+#[derive(Debug, Clone)]
+pub struct BackupPolicy {
+    /// Directory the backup files are written to
+    pub directory: PathBuf,
+    /// Time between backups
+    pub interval: Duration,
+    /// Number of backups to keep, oldest deleted first (at least one is kept)
+    pub keep: usize,
+}
+
+impl BackupPolicy {
+    /// Daily backups into `directory`, keeping the last week
+    pub fn daily<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            directory: directory.into(),
+            interval: Duration::from_secs(24 * 60 * 60),
+            keep: 7,
+        }
+    }
+}
+
+impl DatabaseManager {
+    /// Copy the database to `path` while it stays in use
+    ///
+    /// The copy is written next to `path` and renamed into place when
+    /// complete, so an interrupted backup never leaves a partial file.
+    pub async fn backup_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| WebPageManagerError::System {
+                source: SystemError::IO { source: e },
+            })?;
+        }
+        let partial = path.with_extension("partial");
+        let target = partial.clone();
+
+        self.connection
+            .call(move |conn| {
+                conn.backup(rusqlite::DatabaseName::Main, &target, None)?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to back up database to {:?}: {}", path, e),
+                },
+            })?;
+        std::fs::rename(&partial, &path).map_err(|e| WebPageManagerError::System {
+            source: SystemError::IO { source: e },
+        })?;
+
+        info!("Database backed up to {:?}", path);
+        Ok(())
+    }
+
+    /// Replace the database contents with a backup while the database stays open
+    ///
+    /// The backup must pass `PRAGMA integrity_check` and must not come from a
+    /// newer schema version; older backups are migrated after restoring.
+    /// Caches are cleared so no data from before the restore is served.
+    pub async fn restore_from<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+        verify_backup(&path).await?;
+
+        let source = path.clone();
+        self.connection
+            .call(move |conn| {
+                conn.restore(rusqlite::DatabaseName::Main, &source, None::<fn(rusqlite::backup::Progress)>)?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to restore database from {:?}: {}", path, e),
+                },
+            })?;
+
+        self.run_migrations().await?;
+        self.cache.clear_all().await;
+        self.embeddings.invalidate().await;
+
+        info!("Database restored from {:?}", path);
+        Ok(())
+    }
+
+    /// Take a backup if the newest one in the policy directory is older than
+    /// the interval, then delete backups beyond the retention count
+    ///
+    /// Returns the path of the new backup, or `None` if none was due.
+    pub async fn run_scheduled_backup(&self, policy: &BackupPolicy) -> Result<Option<PathBuf>> {
+        let now = Utc::now().naive_utc();
+        let latest = list_backups(&policy.directory)?.pop();
+        // A backup dated in the future means the clock moved back; take a new one
+        let due = latest.is_none_or(|(taken_at, _)| {
+            (now - taken_at).to_std().ok().is_none_or(|age| age >= policy.interval)
+        });
+        if !due {
+            return Ok(None);
+        }
+
+        let path = policy.directory.join(format!(
+            "{}{}.{}",
+            BACKUP_PREFIX,
+            now.format(BACKUP_TIME_FORMAT),
+            BACKUP_EXTENSION
+        ));
+        self.backup_to(&path).await?;
+        prune_backups(&policy.directory, policy.keep.max(1))?;
+        Ok(Some(path))
+    }
+
+    /// Run `run_scheduled_backup` in the background, checking once per
+    /// interval (at most hourly) until the returned task is aborted
+    pub fn spawn_scheduled_backups(self: Arc<Self>, policy: BackupPolicy) -> tokio::task::JoinHandle<()> {
+        let period = policy.interval.min(Duration::from_secs(60 * 60)).max(Duration::from_secs(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_scheduled_backup(&policy).await {
+                    warn!("Scheduled backup failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Check that a backup file is intact and from a compatible schema version
+pub async fn verify_backup(path: &Path) -> Result<()> {
+    let path = path.to_path_buf();
+    let invalid = |details: String| WebPageManagerError::DataConsistency {
+        source: DataConsistencyError::DatabaseIntegrityViolation { details },
+    };
+
+    let checked = tokio::task::spawn_blocking(move || {
+        // Not read-only: FTS5 index checks need write access
+        let flags = rusqlite::OpenFlags::default().difference(rusqlite::OpenFlags::SQLITE_OPEN_CREATE);
+        let conn = rusqlite::Connection::open_with_flags(&path, flags)?;
+        let integrity: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+        let version: Option<u32> = conn
+            .query_row("SELECT MAX(version) FROM schema_migrations", [], |row| row.get(0))
+            .ok()
+            .flatten();
+        Ok::<_, rusqlite::Error>((integrity, version))
+    })
+    .await
+    .map_err(|e| invalid(format!("Backup verification did not complete: {}", e)))?;
+
+    match checked {
+        Err(e) => Err(invalid(format!("Backup cannot be read: {}", e))),
+        Ok((integrity, _)) if integrity != "ok" => Err(invalid(format!("Backup is corrupted: {}", integrity))),
+        Ok((_, None)) => Err(invalid("Backup is not a Web Page Manager database".to_string())),
+        Ok((_, Some(version))) if version > schema::SCHEMA_VERSION => Err(invalid(format!(
+            "Backup has schema version {}, newer than the supported {}",
+            version,
+            schema::SCHEMA_VERSION
+        ))),
+        Ok(_) => Ok(()),
+    }
+}
+
+/// Scheduled backups in `directory`, oldest first
+pub fn list_backups(directory: &Path) -> Result<Vec<(NaiveDateTime, PathBuf)>> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(WebPageManagerError::System {
+                source: SystemError::IO { source: e },
+            })
+        }
+    };
+
+    let mut backups: Vec<(NaiveDateTime, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = path.file_name()?.to_str()?;
+            let stamp = name.strip_prefix(BACKUP_PREFIX)?.strip_suffix(BACKUP_EXTENSION)?.strip_suffix('.')?;
+            let taken_at = NaiveDateTime::parse_from_str(stamp, BACKUP_TIME_FORMAT).ok()?;
+            Some((taken_at, path))
+        })
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+/// Delete all but the `keep` newest backups
+fn prune_backups(directory: &Path, keep: usize) -> Result<()> {
+    let backups = list_backups(directory)?;
+    let excess = backups.len().saturating_sub(keep);
+    for (_, path) in backups.into_iter().take(excess) {
+        std::fs::remove_file(&path).map_err(|e| WebPageManagerError::System {
+            source: SystemError::IO { source: e },
+        })?;
+        info!("Deleted old backup {:?}", path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CachedPageRepository, PageRepository};
+    use crate::test_support::{temp_dir, test_page};
+
+    #[tokio::test]
+    async fn test_backup_to_copies_live_database() {
+        let dir = temp_dir("backup_copy");
+        let db = DatabaseManager::in_memory().await.unwrap();
+        db.page_repository().save(&test_page("https://example.com/1", "Backed up")).await.unwrap();
+
+        let path = dir.join("nested").join("copy.db");
+        db.backup_to(&path).await.unwrap();
+        assert!(!path.with_extension("partial").exists());
+
+        let restored = DatabaseManager::new(&path).await.unwrap();
+        assert_eq!(restored.page_repository().count().await.unwrap(), 1);
+        assert_eq!(restored.page_repository().search("backed").await.unwrap().len(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_restore_replaces_contents_after_verification() {
+        let dir = temp_dir("backup_restore");
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let repo = db.page_repository();
+        let kept = test_page("https://example.com/kept", "Backed up");
+        repo.save(&kept).await.unwrap();
+        let backup = dir.join("good.db");
+        db.backup_to(&backup).await.unwrap();
+
+        let cached = CachedPageRepository::new(db.connection(), db.cache());
+        let later = test_page("https://example.com/later", "Backed up");
+        cached.save(&later).await.unwrap();
+        assert!(cached.get_by_id(&later.id).await.unwrap().is_some());
+
+        db.restore_from(&backup).await.unwrap();
+        assert_eq!(repo.count().await.unwrap(), 1);
+        // The cache no longer serves the page saved after the backup
+        assert!(cached.get_by_id(&later.id).await.unwrap().is_none());
+        assert!(cached.get_by_id(&kept.id).await.unwrap().is_some());
+
+        // Corrupted, foreign and newer backups are rejected without touching the data
+        let corrupted = dir.join("corrupted.db");
+        let mut bytes = std::fs::read(&backup).unwrap();
+        bytes.truncate(bytes.len() / 2);
+        bytes[100..200].fill(0xAB);
+        std::fs::write(&corrupted, bytes).unwrap();
+        assert!(db.restore_from(&corrupted).await.is_err());
+
+        let foreign = dir.join("foreign.db");
+        rusqlite::Connection::open(&foreign).unwrap().execute_batch("CREATE TABLE t (a);").unwrap();
+        assert!(db.restore_from(&foreign).await.is_err());
+
+        let newer = dir.join("newer.db");
+        std::fs::copy(&backup, &newer).unwrap();
+        rusqlite::Connection::open(&newer)
+            .unwrap()
+            .execute("INSERT INTO schema_migrations VALUES (?1, 0, 'future')", [schema::SCHEMA_VERSION + 1])
+            .unwrap();
+        assert!(db.restore_from(&newer).await.is_err());
+        assert!(db.restore_from(dir.join("missing.db")).await.is_err());
+        assert_eq!(repo.count().await.unwrap(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_backups_respect_interval_and_retention() {
+        let dir = temp_dir("backup_schedule");
+        std::fs::create_dir_all(&dir).unwrap();
+        // Older backups from earlier days, plus an unrelated file
+        for day in 1..=3 {
+            std::fs::write(dir.join(format!("backup-2026010{}-120000.db", day)), b"old").unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), b"keep me").unwrap();
+
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let policy = BackupPolicy { keep: 2, ..BackupPolicy::daily(&dir) };
+        let taken = db.run_scheduled_backup(&policy).await.unwrap().unwrap();
+        assert!(taken.exists());
+
+        let remaining: Vec<PathBuf> = list_backups(&dir).unwrap().into_iter().map(|(_, path)| path).collect();
+        assert_eq!(remaining, vec![dir.join("backup-20260103-120000.db"), taken]);
+        assert!(dir.join("notes.txt").exists());
+
+        // The new backup is recent, so nothing is due
+        assert_eq!(db.run_scheduled_backup(&policy).await.unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
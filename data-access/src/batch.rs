@@ -7,6 +7,8 @@ use web_page_manager_core::*;
 use tokio_rusqlite::Connection;
 use std::sync::Arc;
 
+use crate::repository::insert_page;
+
 /// Batch size for insert operations
 const DEFAULT_BATCH_SIZE: usize = 100;
 
@@ -55,49 +57,8 @@ impl BatchPageOperations {
             .call(move |conn| {
                 let tx = conn.transaction()?;
 
-                {
-                    let mut stmt = tx.prepare_cached(
-                        r#"
-                        INSERT OR REPLACE INTO unified_pages
-                        (id, url, title, favicon_url, content_summary, keywords, category,
-                         source_type, browser_info, tab_info, bookmark_info, created_at, last_accessed, access_count)
-                        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
-                        "#,
-                    )?;
-
-                    for page in &pages_vec {
-                        let content_summary_json = page.content_summary
-                            .as_ref()
-                            .map(|s| serde_json::to_string(s).unwrap_or_default());
-                        let keywords_json = serde_json::to_string(&page.keywords).unwrap_or_default();
-                        let source_type_json = serde_json::to_string(&page.source_type).unwrap_or_default();
-                        let browser_info_json = page.browser_info
-                            .as_ref()
-                            .map(|b| serde_json::to_string(b).unwrap_or_default());
-                        let tab_info_json = page.tab_info
-                            .as_ref()
-                            .map(|t| serde_json::to_string(t).unwrap_or_default());
-                        let bookmark_info_json = page.bookmark_info
-                            .as_ref()
-                            .map(|b| serde_json::to_string(b).unwrap_or_default());
-
-                        stmt.execute(rusqlite::params![
-                            page.id.to_string(),
-                            page.url,
-                            page.title,
-                            page.favicon_url,
-                            content_summary_json,
-                            keywords_json,
-                            page.category,
-                            source_type_json,
-                            browser_info_json,
-                            tab_info_json,
-                            bookmark_info_json,
-                            page.created_at.timestamp(),
-                            page.last_accessed.timestamp(),
-                            page.access_count,
-                        ])?;
-                    }
+                for page in &pages_vec {
+                    insert_page(&tx, page)?;
                 }
 
                 tx.commit()?;
@@ -113,25 +74,32 @@ impl BatchPageOperations {
         Ok(())
     }
 
-    /// Delete multiple pages in a single transaction
+    /// Move multiple pages to the trash in a single transaction
     pub async fn batch_delete(&self, ids: &[Uuid]) -> Result<usize> {
         if ids.is_empty() {
             return Ok(0);
         }
 
         let id_strings: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        let now = Utc::now().timestamp();
+        let batch_size = self.batch_size.max(1);
 
         let deleted = self.connection
             .call(move |conn| {
                 let tx = conn.transaction()?;
                 let mut total_deleted = 0;
 
-                {
-                    let mut stmt = tx.prepare_cached("DELETE FROM unified_pages WHERE id = ?1")?;
-
-                    for id_str in &id_strings {
-                        total_deleted += stmt.execute([id_str])?;
-                    }
+                // One statement per chunk rather than per page; the change
+                // log triggers still run for each page
+                for chunk in id_strings.chunks(batch_size) {
+                    let placeholders = vec!["?"; chunk.len()].join(", ");
+                    let mut stmt = tx.prepare(&format!(
+                        "UPDATE unified_pages SET deleted_at = ? WHERE id IN ({}) AND deleted_at IS NULL",
+                        placeholders
+                    ))?;
+                    let params = std::iter::once(&now as &dyn rusqlite::ToSql)
+                        .chain(chunk.iter().map(|id| id as &dyn rusqlite::ToSql));
+                    total_deleted += stmt.execute(rusqlite::params_from_iter(params))?;
                 }
 
                 tx.commit()?;
@@ -233,7 +201,7 @@ mod tests {
     #[tokio::test]
     async fn test_batch_delete() {
         let db = DatabaseManager::in_memory().await.unwrap();
-        let batch_ops = BatchPageOperations::new(db.connection());
+        let batch_ops = BatchPageOperations::with_batch_size(db.connection(), 4);
         let page_repo = db.page_repository();
 
         // Create and save pages
@@ -267,13 +235,14 @@ mod tests {
         let count_before = page_repo.count().await.unwrap();
         assert_eq!(count_before, 10);
 
-        // Batch delete
+        // Batch delete, in chunks smaller than the batch
         let deleted = batch_ops.batch_delete(&ids).await.unwrap();
         assert_eq!(deleted, 10);
 
         // Verify pages are deleted
         let count_after = page_repo.count().await.unwrap();
         assert_eq!(count_after, 0);
+        assert_eq!(batch_ops.batch_delete(&ids[..3]).await.unwrap(), 0);
     }
 
     #[tokio::test]
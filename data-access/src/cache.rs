@@ -1,27 +1,165 @@
 //! Data caching layer for Web Page Manager
 //!
 //! Implements LRU caching for frequently accessed data with TTL support.
+//!
+//! Besides the entry count, every cache tracks the estimated memory its keys
+//! and values hold and evicts least recently used entries to stay within a
+//! byte limit. `DataCache` splits one memory budget between its caches.
+//!
+//! URLs found missing are remembered for a short time so repeated lookups do
+//! not reach the database, and `SingleFlight` lets concurrent misses for the
+//! same key share one query.
 
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::mem::size_of;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, OwnedMutexGuard, RwLock};
 use web_page_manager_core::*;
 
+use crate::canonical::canonical_url;
+
+/// Estimated memory held by a cached key or value
+pub trait CacheWeight {
+    /// Approximate size in bytes, including the heap data it owns
+    fn weight(&self) -> usize;
+}
+
+macro_rules! fixed_weight {
+    ($($ty:ty),*) => {
+        $(impl CacheWeight for $ty {
+            fn weight(&self) -> usize {
+                size_of::<Self>()
+            }
+        })*
+    };
+}
+
+fixed_weight!((), i32, i64, u32, u64, usize, Uuid, HistoryId);
+
+fn string_weight(value: &str) -> usize {
+    value.len()
+}
+
+fn strings_weight(values: &[String]) -> usize {
+    values.iter().map(|v| size_of::<String>() + v.len()).sum()
+}
+
+impl CacheWeight for String {
+    fn weight(&self) -> usize {
+        size_of::<Self>() + self.capacity()
+    }
+}
+
+impl<T: CacheWeight> CacheWeight for Vec<T> {
+    fn weight(&self) -> usize {
+        size_of::<Self>() + self.iter().map(CacheWeight::weight).sum::<usize>()
+    }
+}
+
+impl CacheWeight for HistoryEntry {
+    fn weight(&self) -> usize {
+        let session = self.session_info.as_ref().map_or(0, |session| {
+            string_weight(&session.session_id) + session.window_id.as_deref().map_or(0, string_weight)
+        });
+        size_of::<Self>() - size_of::<UnifiedPageInfo>()
+            + self.page_info.weight()
+            + session
+            + self.recall_hint.as_deref().map_or(0, string_weight)
+    }
+}
+
+impl CacheWeight for ContentSummary {
+    fn weight(&self) -> usize {
+        size_of::<Self>()
+            + string_weight(&self.summary_text)
+            + strings_weight(&self.key_points)
+            + string_weight(&self.language)
+    }
+}
+
+impl CacheWeight for UnifiedPageInfo {
+    fn weight(&self) -> usize {
+        let optional = |value: &Option<String>| value.as_deref().map_or(0, string_weight);
+        let tab = self.tab_info.as_ref().map_or(0, |tab| {
+            size_of::<TabInfo>() + string_weight(&tab.url) + string_weight(&tab.title) + optional(&tab.favicon_url)
+        });
+        let bookmark = self.bookmark_info.as_ref().map_or(0, |bookmark| {
+            size_of::<BookmarkInfo>()
+                + string_weight(&bookmark.url)
+                + string_weight(&bookmark.title)
+                + optional(&bookmark.favicon_url)
+                + strings_weight(&bookmark.folder_path)
+        });
+        let browser = self.browser_info.as_ref().map_or(0, |browser| {
+            size_of::<BrowserInstance>() + string_weight(&browser.version) + optional(&browser.profile_path)
+        });
+
+        size_of::<Self>()
+            + string_weight(&self.url)
+            + string_weight(&self.title)
+            + optional(&self.favicon_url)
+            + self.content_summary.as_ref().map_or(0, CacheWeight::weight)
+            + strings_weight(&self.keywords)
+            + optional(&self.category)
+            + tab
+            + bookmark
+            + browser
+    }
+}
+
+impl CacheWeight for SmartGroup {
+    fn weight(&self) -> usize {
+        let group_type = match &self.group_type {
+            GroupType::Domain(value) | GroupType::Topic(value) => string_weight(value),
+            _ => 0,
+        };
+        size_of::<Self>()
+            + string_weight(&self.name)
+            + string_weight(&self.description)
+            + group_type
+            + self.pages.len() * size_of::<Uuid>()
+    }
+}
+
+/// Entries removed by one cache, by reason
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvictionMetrics {
+    /// Evicted to stay within the entry limit
+    pub evictions: u64,
+    /// Evicted, or not cached at all, to stay within the memory limit
+    pub memory_evictions: u64,
+    /// Dropped after their TTL passed
+    pub expirations: u64,
+}
+
+impl EvictionMetrics {
+    fn add(self, other: EvictionMetrics) -> Self {
+        Self {
+            evictions: self.evictions + other.evictions,
+            memory_evictions: self.memory_evictions + other.memory_evictions,
+            expirations: self.expirations + other.expirations,
+        }
+    }
+}
+
 /// Cache entry with value and metadata
 struct CacheEntry<V> {
     value: V,
+    /// Estimated bytes of the key and value
+    weight: usize,
     inserted_at: Instant,
     last_accessed: Instant,
     access_count: u64,
 }
 
 impl<V: Clone> CacheEntry<V> {
-    fn new(value: V) -> Self {
+    fn new(value: V, weight: usize) -> Self {
         let now = Instant::now();
         Self {
             value,
+            weight,
             inserted_at: now,
             last_accessed: now,
             access_count: 1,
@@ -38,28 +176,44 @@ impl<V: Clone> CacheEntry<V> {
     }
 }
 
-/// LRU cache with TTL support
+/// LRU cache with TTL support and a memory limit
 pub struct LruCache<K, V> {
     entries: HashMap<K, CacheEntry<V>>,
     max_size: usize,
+    /// Most bytes the entries may hold
+    max_bytes: usize,
+    /// Estimated bytes the entries hold
+    bytes: usize,
     ttl: Duration,
     order: Vec<K>,
+    metrics: EvictionMetrics,
 }
 
-impl<K: Eq + Hash + Clone, V: Clone> LruCache<K, V> {
+impl<K: Eq + Hash + Clone + CacheWeight, V: Clone + CacheWeight> LruCache<K, V> {
+    /// Create a cache limited by entry count only
     pub fn new(max_size: usize, ttl: Duration) -> Self {
         Self {
             entries: HashMap::with_capacity(max_size),
             max_size,
+            max_bytes: usize::MAX,
+            bytes: 0,
             ttl,
             order: Vec::with_capacity(max_size),
+            metrics: EvictionMetrics::default(),
         }
     }
 
+    /// Also limit the estimated memory of the entries to `max_bytes`
+    pub fn with_memory_limit(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
     pub fn get(&mut self, key: &K) -> Option<V> {
         if let Some(entry) = self.entries.get_mut(key) {
             if entry.is_expired(self.ttl) {
                 self.remove(key);
+                self.metrics.expirations += 1;
                 return None;
             }
             entry.touch();
@@ -78,13 +232,30 @@ impl<K: Eq + Hash + Clone, V: Clone> LruCache<K, V> {
         // Remove if already exists
         self.remove(&key);
 
-        // Evict oldest if at capacity
-        while self.entries.len() >= self.max_size && !self.order.is_empty() {
+        // A value larger than the whole limit is not cached
+        let weight = key.weight() + value.weight();
+        if weight > self.max_bytes {
+            self.metrics.memory_evictions += 1;
+            return;
+        }
+
+        // Evict oldest if at capacity or over the memory limit
+        while !self.order.is_empty() {
+            if self.entries.len() >= self.max_size {
+                self.metrics.evictions += 1;
+            } else if self.bytes + weight > self.max_bytes {
+                self.metrics.memory_evictions += 1;
+            } else {
+                break;
+            }
             let oldest_key = self.order.remove(0);
-            self.entries.remove(&oldest_key);
+            if let Some(entry) = self.entries.remove(&oldest_key) {
+                self.bytes -= entry.weight;
+            }
         }
 
-        self.entries.insert(key.clone(), CacheEntry::new(value));
+        self.bytes += weight;
+        self.entries.insert(key.clone(), CacheEntry::new(value, weight));
         self.order.push(key);
     }
 
@@ -92,12 +263,25 @@ impl<K: Eq + Hash + Clone, V: Clone> LruCache<K, V> {
         if let Some(pos) = self.order.iter().position(|k| k == key) {
             self.order.remove(pos);
         }
-        self.entries.remove(key).map(|e| e.value)
+        let entry = self.entries.remove(key)?;
+        self.bytes -= entry.weight;
+        Some(entry.value)
     }
 
     pub fn clear(&mut self) {
         self.entries.clear();
         self.order.clear();
+        self.bytes = 0;
+    }
+
+    /// Estimated bytes held by the entries
+    pub fn memory_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Entries removed since the cache was created
+    pub fn metrics(&self) -> EvictionMetrics {
+        self.metrics
     }
 
     pub fn len(&self) -> usize {
@@ -117,6 +301,7 @@ impl<K: Eq + Hash + Clone, V: Clone> LruCache<K, V> {
             .map(|(k, _)| k.clone())
             .collect();
 
+        self.metrics.expirations += expired_keys.len() as u64;
         for key in expired_keys {
             self.remove(&key);
         }
@@ -138,6 +323,35 @@ pub struct CacheConfig {
     pub summary_ttl: Duration,
     /// TTL for group cache entries
     pub group_ttl: Duration,
+    /// Maximum number of history entries in cache
+    pub max_history: usize,
+    /// Maximum number of cached history query results
+    pub max_history_queries: usize,
+    /// TTL for history entries and query results
+    pub history_ttl: Duration,
+    /// Maximum number of URLs remembered as missing
+    pub max_missing: usize,
+    /// How long a URL is remembered as missing; pages written without going
+    /// through the cache stay hidden from cached lookups this long
+    pub missing_ttl: Duration,
+    /// Estimated memory all caches together may hold, in bytes
+    pub memory_budget_bytes: usize,
+}
+
+/// Shares of the memory budget, in percent: pages, URL index, summaries,
+/// groups, missing URLs, group memberships, history entries, history queries
+const BUDGET_SHARES: [usize; 8] = [60, 4, 15, 4, 2, 5, 5, 5];
+
+impl CacheConfig {
+    /// Limit the memory of all caches together to `mb` megabytes
+    pub fn with_memory_budget_mb(mut self, mb: usize) -> Self {
+        self.memory_budget_bytes = mb.saturating_mul(1024 * 1024);
+        self
+    }
+
+    fn budget_share(&self, cache: usize) -> usize {
+        self.memory_budget_bytes / 100 * BUDGET_SHARES[cache]
+    }
 }
 
 impl Default for CacheConfig {
@@ -149,6 +363,12 @@ impl Default for CacheConfig {
             page_ttl: Duration::from_secs(3600),      // 1 hour
             summary_ttl: Duration::from_secs(1800),   // 30 minutes
             group_ttl: Duration::from_secs(1800),     // 30 minutes
+            max_history: 500,
+            max_history_queries: 50,
+            history_ttl: Duration::from_secs(300),    // 5 minutes
+            max_missing: 1000,
+            missing_ttl: Duration::from_secs(30),
+            memory_budget_bytes: 100 * 1024 * 1024,  // 100 MB
         }
     }
 }
@@ -159,16 +379,50 @@ pub struct DataCache {
     pages_by_url: Arc<RwLock<LruCache<String, Uuid>>>,
     summaries: Arc<RwLock<LruCache<Uuid, ContentSummary>>>,
     groups: Arc<RwLock<LruCache<Uuid, SmartGroup>>>,
+    missing_urls: Arc<RwLock<LruCache<String, ()>>>,
+    /// Pages in each group, best match first
+    group_pages: Arc<RwLock<LruCache<Uuid, Vec<Uuid>>>>,
+    /// Groups of each page
+    page_groups: Arc<RwLock<LruCache<Uuid, Vec<Uuid>>>>,
+    history: Arc<RwLock<LruCache<HistoryId, HistoryEntry>>>,
+    /// Results of history queries, keyed by the serialized filter
+    history_queries: Arc<RwLock<LruCache<String, Vec<HistoryEntry>>>>,
+    url_lookups: SingleFlight<String>,
     config: CacheConfig,
 }
 
 impl DataCache {
     pub fn new(config: CacheConfig) -> Self {
         Self {
-            pages: Arc::new(RwLock::new(LruCache::new(config.max_pages, config.page_ttl))),
-            pages_by_url: Arc::new(RwLock::new(LruCache::new(config.max_pages, config.page_ttl))),
-            summaries: Arc::new(RwLock::new(LruCache::new(config.max_summaries, config.summary_ttl))),
-            groups: Arc::new(RwLock::new(LruCache::new(config.max_groups, config.group_ttl))),
+            pages: Arc::new(RwLock::new(
+                LruCache::new(config.max_pages, config.page_ttl).with_memory_limit(config.budget_share(0)),
+            )),
+            pages_by_url: Arc::new(RwLock::new(
+                LruCache::new(config.max_pages, config.page_ttl).with_memory_limit(config.budget_share(1)),
+            )),
+            summaries: Arc::new(RwLock::new(
+                LruCache::new(config.max_summaries, config.summary_ttl).with_memory_limit(config.budget_share(2)),
+            )),
+            groups: Arc::new(RwLock::new(
+                LruCache::new(config.max_groups, config.group_ttl).with_memory_limit(config.budget_share(3)),
+            )),
+            missing_urls: Arc::new(RwLock::new(
+                LruCache::new(config.max_missing, config.missing_ttl).with_memory_limit(config.budget_share(4)),
+            )),
+            group_pages: Arc::new(RwLock::new(
+                LruCache::new(config.max_groups, config.group_ttl).with_memory_limit(config.budget_share(5) / 2),
+            )),
+            page_groups: Arc::new(RwLock::new(
+                LruCache::new(config.max_pages, config.group_ttl).with_memory_limit(config.budget_share(5) / 2),
+            )),
+            history: Arc::new(RwLock::new(
+                LruCache::new(config.max_history, config.history_ttl).with_memory_limit(config.budget_share(6)),
+            )),
+            history_queries: Arc::new(RwLock::new(
+                LruCache::new(config.max_history_queries, config.history_ttl)
+                    .with_memory_limit(config.budget_share(7)),
+            )),
+            url_lookups: SingleFlight::new(),
             config,
         }
     }
@@ -179,10 +433,10 @@ impl DataCache {
         cache.get(id)
     }
 
-    /// Get a page ID by URL from cache
+    /// Get a page ID by URL from cache; URLs are matched by canonical form
     pub async fn get_page_id_by_url(&self, url: &str) -> Option<Uuid> {
         let mut cache = self.pages_by_url.write().await;
-        cache.get(&url.to_string())
+        cache.get(&canonical_url(url))
     }
 
     /// Cache a page
@@ -190,8 +444,29 @@ impl DataCache {
         let mut pages_cache = self.pages.write().await;
         let mut url_cache = self.pages_by_url.write().await;
         
+        let mut missing_cache = self.missing_urls.write().await;
+
+        let url = canonical_url(&page.url);
         pages_cache.insert(page.id, page.clone());
-        url_cache.insert(page.url.clone(), page.id);
+        missing_cache.remove(&url);
+        url_cache.insert(url, page.id);
+    }
+
+    /// Whether `url` was recently looked up and not found
+    pub async fn is_url_missing(&self, url: &str) -> bool {
+        let mut cache = self.missing_urls.write().await;
+        cache.get(&canonical_url(url)).is_some()
+    }
+
+    /// Remember that no page has `url`
+    pub async fn cache_missing_url(&self, url: &str) {
+        let mut cache = self.missing_urls.write().await;
+        cache.insert(canonical_url(url), ());
+    }
+
+    /// Wait until no other caller is looking up `url` in the database
+    pub async fn begin_url_lookup(&self, url: &str) -> Flight<'_, String> {
+        self.url_lookups.begin(canonical_url(url)).await
     }
 
     /// Invalidate a page from cache
@@ -199,7 +474,7 @@ impl DataCache {
         let mut pages_cache = self.pages.write().await;
         if let Some(page) = pages_cache.remove(id) {
             let mut url_cache = self.pages_by_url.write().await;
-            url_cache.remove(&page.url);
+            url_cache.remove(&canonical_url(&page.url));
         }
     }
 
@@ -239,17 +514,110 @@ impl DataCache {
         cache.remove(id);
     }
 
+    /// Get the pages of a group from cache
+    pub async fn get_group_pages(&self, group_id: &Uuid) -> Option<Vec<Uuid>> {
+        let mut cache = self.group_pages.write().await;
+        cache.get(group_id)
+    }
+
+    /// Cache the pages of a group
+    pub async fn cache_group_pages(&self, group_id: Uuid, page_ids: &[Uuid]) {
+        let mut cache = self.group_pages.write().await;
+        cache.insert(group_id, page_ids.to_vec());
+    }
+
+    /// Get the groups of a page from cache
+    pub async fn get_page_groups(&self, page_id: &Uuid) -> Option<Vec<Uuid>> {
+        let mut cache = self.page_groups.write().await;
+        cache.get(page_id)
+    }
+
+    /// Cache the groups of a page
+    pub async fn cache_page_groups(&self, page_id: Uuid, group_ids: &[Uuid]) {
+        let mut cache = self.page_groups.write().await;
+        cache.insert(page_id, group_ids.to_vec());
+    }
+
+    /// Invalidate the cached membership of one page in one group
+    pub async fn invalidate_membership(&self, page_id: &Uuid, group_id: &Uuid) {
+        let mut group_pages = self.group_pages.write().await;
+        let mut page_groups = self.page_groups.write().await;
+        group_pages.remove(group_id);
+        page_groups.remove(page_id);
+    }
+
+    /// Invalidate all cached group memberships
+    pub async fn invalidate_memberships(&self) {
+        let mut group_pages = self.group_pages.write().await;
+        let mut page_groups = self.page_groups.write().await;
+        group_pages.clear();
+        page_groups.clear();
+    }
+
+    /// Get a history entry from cache
+    pub async fn get_history(&self, id: &HistoryId) -> Option<HistoryEntry> {
+        let mut cache = self.history.write().await;
+        cache.get(id)
+    }
+
+    /// Cache a history entry; cached query results may no longer match, so
+    /// they are dropped
+    pub async fn cache_history(&self, entry: &HistoryEntry) {
+        let mut cache = self.history.write().await;
+        let mut queries = self.history_queries.write().await;
+        cache.insert(entry.id.clone(), entry.clone());
+        queries.clear();
+    }
+
+    /// Get the cached result of a history query
+    pub async fn get_history_query(&self, key: &str) -> Option<Vec<HistoryEntry>> {
+        let mut cache = self.history_queries.write().await;
+        cache.get(&key.to_string())
+    }
+
+    /// Cache the result of a history query
+    pub async fn cache_history_query(&self, key: String, entries: &[HistoryEntry]) {
+        let mut cache = self.history_queries.write().await;
+        cache.insert(key, entries.to_vec());
+    }
+
+    /// Invalidate a history entry and every cached history query
+    pub async fn invalidate_history(&self, id: &HistoryId) {
+        let mut cache = self.history.write().await;
+        let mut queries = self.history_queries.write().await;
+        cache.remove(id);
+        queries.clear();
+    }
+
+    /// Invalidate all cached history
+    pub async fn invalidate_all_history(&self) {
+        let mut cache = self.history.write().await;
+        let mut queries = self.history_queries.write().await;
+        cache.clear();
+        queries.clear();
+    }
+
     /// Clear all caches
     pub async fn clear_all(&self) {
         let mut pages = self.pages.write().await;
         let mut urls = self.pages_by_url.write().await;
         let mut summaries = self.summaries.write().await;
         let mut groups = self.groups.write().await;
+        let mut missing = self.missing_urls.write().await;
+        let mut group_pages = self.group_pages.write().await;
+        let mut page_groups = self.page_groups.write().await;
+        let mut history = self.history.write().await;
+        let mut history_queries = self.history_queries.write().await;
         
         pages.clear();
         urls.clear();
         summaries.clear();
         groups.clear();
+        missing.clear();
+        group_pages.clear();
+        page_groups.clear();
+        history.clear();
+        history_queries.clear();
     }
 
     /// Cleanup expired entries from all caches
@@ -258,11 +626,21 @@ impl DataCache {
         let mut urls = self.pages_by_url.write().await;
         let mut summaries = self.summaries.write().await;
         let mut groups = self.groups.write().await;
+        let mut missing = self.missing_urls.write().await;
+        let mut group_pages = self.group_pages.write().await;
+        let mut page_groups = self.page_groups.write().await;
+        let mut history = self.history.write().await;
+        let mut history_queries = self.history_queries.write().await;
         
         pages.cleanup_expired();
         urls.cleanup_expired();
         summaries.cleanup_expired();
         groups.cleanup_expired();
+        missing.cleanup_expired();
+        group_pages.cleanup_expired();
+        page_groups.cleanup_expired();
+        history.cleanup_expired();
+        history_queries.cleanup_expired();
     }
 
     /// Get cache statistics
@@ -271,7 +649,23 @@ impl DataCache {
         let urls = self.pages_by_url.read().await;
         let summaries = self.summaries.read().await;
         let groups = self.groups.read().await;
+        let missing = self.missing_urls.read().await;
+        let group_pages = self.group_pages.read().await;
+        let page_groups = self.page_groups.read().await;
+        let history = self.history.read().await;
+        let history_queries = self.history_queries.read().await;
         
+        let metrics = pages
+            .metrics()
+            .add(urls.metrics())
+            .add(summaries.metrics())
+            .add(groups.metrics())
+            .add(missing.metrics())
+            .add(group_pages.metrics())
+            .add(page_groups.metrics())
+            .add(history.metrics())
+            .add(history_queries.metrics());
+
         CacheStats {
             pages_count: pages.len(),
             pages_max: self.config.max_pages,
@@ -280,6 +674,24 @@ impl DataCache {
             summaries_max: self.config.max_summaries,
             groups_count: groups.len(),
             groups_max: self.config.max_groups,
+            missing_urls_count: missing.len(),
+            memberships_count: group_pages.len() + page_groups.len(),
+            history_count: history.len(),
+            history_max: self.config.max_history,
+            history_queries_count: history_queries.len(),
+            memory_bytes: pages.memory_bytes()
+                + urls.memory_bytes()
+                + summaries.memory_bytes()
+                + groups.memory_bytes()
+                + missing.memory_bytes()
+                + group_pages.memory_bytes()
+                + page_groups.memory_bytes()
+                + history.memory_bytes()
+                + history_queries.memory_bytes(),
+            memory_budget_bytes: self.config.memory_budget_bytes,
+            evictions: metrics.evictions,
+            memory_evictions: metrics.memory_evictions,
+            expirations: metrics.expirations,
         }
     }
 }
@@ -290,6 +702,66 @@ impl Default for DataCache {
     }
 }
 
+/// Coalesces concurrent loads of the same key
+///
+/// The first caller to `begin` a key proceeds; later callers wait until its
+/// `Flight` is dropped, by which time the result is normally cached, so
+/// they check the cache again instead of querying too.
+pub struct SingleFlight<K> {
+    flights: std::sync::Mutex<HashMap<K, Arc<Mutex<()>>>>,
+}
+
+impl<K: Eq + Hash + Clone> SingleFlight<K> {
+    pub fn new() -> Self {
+        Self {
+            flights: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait for any load of `key` in progress, then start one
+    pub async fn begin(&self, key: K) -> Flight<'_, K> {
+        let lock = {
+            let mut flights = self.flights.lock().unwrap_or_else(|e| e.into_inner());
+            flights.entry(key.clone()).or_default().clone()
+        };
+        Flight {
+            owner: self,
+            key,
+            guard: lock.lock_owned().await,
+        }
+    }
+
+    /// Number of keys being loaded or waited on
+    pub fn in_flight(&self) -> usize {
+        self.flights.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+impl<K: Eq + Hash + Clone> Default for SingleFlight<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A load in progress; waiting callers continue when it is dropped
+pub struct Flight<'a, K: Eq + Hash> {
+    owner: &'a SingleFlight<K>,
+    key: K,
+    guard: OwnedMutexGuard<()>,
+}
+
+impl<K: Eq + Hash> Drop for Flight<'_, K> {
+    fn drop(&mut self) {
+        let mut flights = self.owner.flights.lock().unwrap_or_else(|e| e.into_inner());
+        // Only the map and this flight hold the lock when nobody is waiting
+        let lock = OwnedMutexGuard::mutex(&self.guard);
+        let idle = flights.get(&self.key).is_some_and(|l| Arc::ptr_eq(l, lock)) && Arc::strong_count(lock) == 2;
+        if idle {
+            flights.remove(&self.key);
+        }
+    }
+}
+
 /// Cache statistics
 #[derive(Debug, Clone)]
 pub struct CacheStats {
@@ -300,6 +772,22 @@ pub struct CacheStats {
     pub summaries_max: usize,
     pub groups_count: usize,
     pub groups_max: usize,
+    /// URLs remembered as missing
+    pub missing_urls_count: usize,
+    /// Cached page lists of groups and group lists of pages
+    pub memberships_count: usize,
+    pub history_count: usize,
+    pub history_max: usize,
+    pub history_queries_count: usize,
+    /// Estimated bytes held by all caches
+    pub memory_bytes: usize,
+    pub memory_budget_bytes: usize,
+    /// Entries evicted because a cache was full
+    pub evictions: u64,
+    /// Entries evicted or refused to stay within the memory budget
+    pub memory_evictions: u64,
+    /// Entries dropped after their TTL
+    pub expirations: u64,
 }
 
 #[cfg(test)]
@@ -351,6 +839,72 @@ mod tests {
         assert_eq!(cache.get(&"c".to_string()), Some(3));
     }
 
+    #[test]
+    fn test_lru_cache_memory_limit() {
+        let entry = "a".to_string().weight() + "value".to_string().weight();
+        let mut cache: LruCache<String, String> =
+            LruCache::new(10, Duration::from_secs(60)).with_memory_limit(entry * 2);
+
+        cache.insert("a".to_string(), "value".to_string());
+        cache.insert("b".to_string(), "value".to_string());
+        assert_eq!(cache.memory_bytes(), entry * 2);
+
+        // A third entry pushes out the least recently used one
+        cache.insert("c".to_string(), "value".to_string());
+        assert_eq!(cache.get(&"a".to_string()), None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.memory_bytes(), entry * 2);
+
+        // A value larger than the whole limit is refused
+        cache.insert("d".to_string(), "x".repeat(entry * 2));
+        assert_eq!(cache.get(&"d".to_string()), None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.metrics(), EvictionMetrics { evictions: 0, memory_evictions: 2, expirations: 0 });
+
+        cache.remove(&"b".to_string());
+        cache.clear();
+        assert_eq!(cache.memory_bytes(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_data_cache_memory_budget() {
+        let config = CacheConfig::default().with_memory_budget_mb(1);
+        let cache = DataCache::new(config);
+
+        let mut page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: "https://example.com/large".to_string(),
+            title: "Large".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec!["k".repeat(200 * 1024)],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Chrome,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        for _ in 0..10 {
+            page.id = Uuid::new_v4();
+            cache.cache_page(&page).await;
+        }
+
+        // Only three 200 KB pages fit in the pages' 60% of 1 MB
+        let stats = cache.stats().await;
+        assert_eq!(stats.pages_count, 3);
+        assert_eq!(stats.memory_evictions, 7);
+        assert_eq!(stats.evictions, 0);
+        assert_eq!(stats.memory_budget_bytes, 1024 * 1024);
+        assert!(stats.memory_bytes <= stats.memory_budget_bytes);
+        assert!(cache.get_page(&page.id).await.is_some());
+    }
+
     #[tokio::test]
     async fn test_data_cache_pages() {
         let cache = DataCache::new(CacheConfig::default());
@@ -385,4 +939,31 @@ mod tests {
         assert!(cached_by_url.is_some());
         assert_eq!(cached_by_url.unwrap(), page.id);
     }
+
+    #[tokio::test]
+    async fn test_single_flight_coalesces_loads() {
+        let flights = Arc::new(SingleFlight::<String>::new());
+        let loads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let loaded = Arc::new(RwLock::new(false));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let (flights, loads, loaded) = (flights.clone(), loads.clone(), loaded.clone());
+                tokio::spawn(async move {
+                    let _flight = flights.begin("key".to_string()).await;
+                    if !*loaded.read().await {
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        loads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                        *loaded.write().await = true;
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(flights.in_flight(), 0);
+    }
 }
@@ -0,0 +1,175 @@
+//! Canonical page URLs
+//!
+//! Pages are unique by canonical URL: the URL with its scheme and host
+//! lowercased, default ports, fragments and tracking parameters removed and
+//! an empty path written as `/`. Lookups by URL compare canonical forms, so
+//! `https://Example.com/?utm_source=mail` finds the page saved as
+//! `https://example.com/`. URLs without an authority (`about:`, `data:`) are
+//! only trimmed.
+
+use rusqlite::functions::FunctionFlags;
+
+/// Query parameters that only track where a visit came from
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_ga", "_gl",
+];
+
+/// Whether a `name=value` query pair only tracks where a visit came from
+pub fn is_tracking_param(pair: &str) -> bool {
+    let name = pair.split('=').next().unwrap_or_default().to_ascii_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name.as_str())
+}
+
+/// Canonical form of `url`, used to match and deduplicate pages
+pub fn canonical_url(url: &str) -> String {
+    let url = url.trim();
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let scheme = scheme.to_ascii_lowercase();
+
+    // Fragments are dropped, except client-side routes such as `#/inbox`
+    let (rest, fragment) = match rest.split_once('#') {
+        Some((rest, fragment)) if fragment.starts_with('/') || fragment.starts_with('!') => (rest, Some(fragment)),
+        Some((rest, _)) => (rest, None),
+        None => (rest, None),
+    };
+    let (rest, query) = rest.split_once('?').map_or((rest, None), |(rest, query)| (rest, Some(query)));
+    let (authority, path) = rest.find('/').map_or((rest, "/"), |i| (&rest[..i], &rest[i..]));
+
+    let mut authority = authority.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "http" | "ws" => Some(":80"),
+        "https" | "wss" => Some(":443"),
+        _ => None,
+    };
+    if let Some(port) = default_port {
+        if let Some(stripped) = authority.strip_suffix(port) {
+            authority.truncate(stripped.len());
+        }
+    }
+
+    let mut canonical = format!("{}://{}{}", scheme, authority, path);
+    let params: Vec<&str> = query
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty() && !is_tracking_param(pair))
+        .collect();
+    if !params.is_empty() {
+        canonical.push('?');
+        canonical.push_str(&params.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        canonical.push('#');
+        canonical.push_str(fragment);
+    }
+    canonical
+}
+
+/// Register `canonical_url` as an SQL function, used when migrating
+/// existing pages
+pub(crate) fn register_functions(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "canonical_url",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let url: String = ctx.get(0)?;
+            Ok(canonical_url(&url))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CachedPageRepository, DatabaseManager, PageRepository, TagRepository};
+    use web_page_manager_core::*;
+    use crate::test_support::test_page;
+
+    #[test]
+    fn test_canonical_url() {
+        let cases = [
+            ("https://example.com", "https://example.com/"),
+            ("HTTPS://Example.COM:443/?utm_source=x&utm_medium=mail", "https://example.com/"),
+            ("http://example.com:8080/Docs/?id=7&fbclid=abc#intro", "http://example.com:8080/Docs/?id=7"),
+            ("https://app.example/?gclid=1#/inbox", "https://app.example/#/inbox"),
+            ("  https://example.com/a?&b=2&  ", "https://example.com/a?b=2"),
+            ("about:blank", "about:blank"),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(canonical_url(url), expected, "{}", url);
+            assert_eq!(canonical_url(expected), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pages_are_unique_by_canonical_url() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let repo = db.page_repository();
+
+        let page = UnifiedPageInfo { access_count: 3, ..test_page("https://example.com/", "Example") };
+        repo.save(&page).await.unwrap();
+        let found = repo.get_by_url("https://Example.com/?utm_source=x#top").await.unwrap().unwrap();
+        assert_eq!(found.id, page.id);
+
+        // Saving the same page under a new id merges into the saved one
+        let again = UnifiedPageInfo { access_count: 1, ..test_page("https://example.com?utm_campaign=spring", "Example") };
+        let ids = repo.save_resolving(std::slice::from_ref(&again)).await.unwrap();
+        assert_eq!(ids, vec![page.id]);
+        assert_eq!(repo.count().await.unwrap(), 1);
+        let merged = repo.get_by_id(&page.id).await.unwrap().unwrap();
+        assert_eq!(merged.url, again.url);
+        assert_eq!(merged.access_count, 3);
+        assert!(repo.get_by_id(&again.id).await.unwrap().is_none());
+
+        // The cache follows the merge instead of caching the unsaved id
+        let cached = CachedPageRepository::new(db.connection(), db.cache());
+        cached.save(&test_page("https://EXAMPLE.com:443/", "Example")).await.unwrap();
+        assert_eq!(cached.get_by_url("https://example.com").await.unwrap().unwrap().id, page.id);
+        assert_eq!(repo.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_migration_merges_duplicates() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        db.migrate_to(12, false).await.unwrap();
+
+        let (first, second) = (
+            UnifiedPageInfo { access_count: 2, ..test_page("https://example.com/a", "Duplicate") },
+            UnifiedPageInfo { access_count: 5, ..test_page("https://example.com/a?fbclid=1", "Duplicate") },
+        );
+        for page in [&first, &second] {
+            let source_type = serde_json::to_string(&page.source_type).unwrap();
+            let (id, url, accessed) = (page.id.to_string(), page.url.clone(), page.access_count);
+            db.connection()
+                .call(move |conn| {
+                    Ok(conn.execute(
+                        "INSERT INTO unified_pages (id, url, title, keywords, source_type, created_at, last_accessed, access_count) \
+                         VALUES (?1, ?2, 'Duplicate', '[]', ?3, 0, ?4, ?4)",
+                        rusqlite::params![id, url, source_type, accessed],
+                    )?)
+                })
+                .await
+                .unwrap();
+        }
+        // Tags predate the tag hierarchy, so the tag is written directly
+        let tag_id = Uuid::new_v4();
+        let tag_str = tag_id.to_string();
+        db.connection()
+            .call(move |conn| Ok(conn.execute("INSERT INTO tags (id, name, created_at) VALUES (?1, 'kept', 0)", [tag_str])?))
+            .await
+            .unwrap();
+        let tags = db.tag_repository();
+        tags.tag_page(&first.id, &tag_id).await.unwrap();
+
+        db.migrate_to(crate::schema::SCHEMA_VERSION, false).await.unwrap();
+        let repo = db.page_repository();
+        assert_eq!(repo.count().await.unwrap(), 1);
+        // The most recently accessed survives, with the other's tags and accesses
+        let merged = repo.get_by_url("https://example.com/a").await.unwrap().unwrap();
+        assert_eq!(merged.id, second.id);
+        assert_eq!(merged.access_count, 7);
+        assert_eq!(tags.get_pages_with_tag(&tag_id).await.unwrap(), vec![second.id]);
+    }
+}
@@ -0,0 +1,343 @@
+//! Change log for sync and undo
+//!
+//! Every insert, update and delete of pages, groups, history, archives,
+//! tags, notes and sessions is recorded in `change_log` by triggers, so no
+//! write path can forget to log. Entries are numbered in the order the
+//! changes were made: a sync peer asks for the changes after the last
+//! sequence number it has seen, and undo walks an entity's changes
+//! backwards. The payload hash identifies the row contents after the change,
+//! letting two devices tell whether they hold the same version of a record.
+//!
+//! The triggers use plain SQL, so any connection can write the logged
+//! tables. They store the new row as JSON; it is hashed in Rust and dropped
+//! when the log is read or maintained.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{OptionalExtension, Row};
+use tokio_rusqlite::Connection;
+use web_page_manager_core::*;
+
+use crate::compression::bytes_hash;
+
+/// Kind of record a change applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeEntity {
+    Page,
+    Group,
+    /// Membership of a page in a group, identified as `page_id:group_id`
+    PageGroup,
+    History,
+    Archive,
+    Tag,
+    /// Tag on a page, identified as `page_id:tag_id`
+    PageTag,
+    Note,
+    Session,
+}
+
+impl ChangeEntity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeEntity::Page => "page",
+            ChangeEntity::Group => "group",
+            ChangeEntity::PageGroup => "page_group",
+            ChangeEntity::History => "history",
+            ChangeEntity::Archive => "archive",
+            ChangeEntity::Tag => "tag",
+            ChangeEntity::PageTag => "page_tag",
+            ChangeEntity::Note => "note",
+            ChangeEntity::Session => "session",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "page" => ChangeEntity::Page,
+            "group" => ChangeEntity::Group,
+            "page_group" => ChangeEntity::PageGroup,
+            "history" => ChangeEntity::History,
+            "archive" => ChangeEntity::Archive,
+            "tag" => ChangeEntity::Tag,
+            "page_tag" => ChangeEntity::PageTag,
+            "note" => ChangeEntity::Note,
+            "session" => ChangeEntity::Session,
+            _ => return None,
+        })
+    }
+}
+
+/// Kind of write a change was
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOp {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl ChangeOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeOp::Insert => "insert",
+            ChangeOp::Update => "update",
+            ChangeOp::Delete => "delete",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "insert" => ChangeOp::Insert,
+            "update" => ChangeOp::Update,
+            "delete" => ChangeOp::Delete,
+            _ => return None,
+        })
+    }
+}
+
+/// One recorded write
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeRecord {
+    /// Position in the log; later changes have larger numbers
+    pub seq: i64,
+    pub entity: ChangeEntity,
+    pub entity_id: String,
+    pub op: ChangeOp,
+    pub changed_at: DateTime<Utc>,
+    /// Hex SHA-1 of the row after the change, `None` for deletes
+    pub payload_hash: Option<String>,
+}
+
+/// Repository trait for the change log
+#[async_trait]
+pub trait ChangeLogRepository: Send + Sync {
+    /// Changes after sequence number `seq`, oldest first
+    async fn changes_since(&self, seq: i64, limit: usize) -> Result<Vec<ChangeRecord>>;
+    /// Changes of one record, oldest first
+    async fn changes_for(&self, entity: ChangeEntity, entity_id: &str) -> Result<Vec<ChangeRecord>>;
+    /// Sequence number of the latest change, 0 if nothing was logged; not
+    /// lowered by pruning
+    async fn latest_seq(&self) -> Result<i64>;
+    /// Drop changes made before `before`, returning how many were removed
+    async fn prune(&self, before: DateTime<Utc>) -> Result<usize>;
+}
+
+/// Register the SQL functions hashing change log payloads
+///
+/// Needed by `hash_pending_payloads` and by reverting the migration that
+/// moved hashing out of the triggers.
+pub(crate) fn register_functions(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "change_hash",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            // Hash the text in place rather than copying it out first
+            let payload = ctx.get_raw(0).as_bytes().map_err(|e| rusqlite::Error::UserFunctionError(e.into()))?;
+            Ok(bytes_hash(payload))
+        },
+    )
+}
+
+/// Replace the payloads the triggers stored with their hashes, returning
+/// how many were hashed
+pub(crate) fn hash_pending_payloads(conn: &rusqlite::Connection) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE change_log SET payload_hash = change_hash(payload), payload = NULL WHERE payload IS NOT NULL",
+        [],
+    )
+}
+
+/// Helper function to map a row to ChangeRecord
+fn row_to_change(row: &Row) -> rusqlite::Result<ChangeRecord> {
+    let entity: String = row.get(1)?;
+    let op: String = row.get(3)?;
+    let changed_at_ts: i64 = row.get(4)?;
+    let invalid = |column: usize, value: String| {
+        rusqlite::Error::FromSqlConversionFailure(
+            column,
+            rusqlite::types::Type::Text,
+            format!("Unknown change log value '{}'", value).into(),
+        )
+    };
+
+    Ok(ChangeRecord {
+        seq: row.get(0)?,
+        entity: ChangeEntity::parse(&entity).ok_or_else(|| invalid(1, entity.clone()))?,
+        entity_id: row.get(2)?,
+        op: ChangeOp::parse(&op).ok_or_else(|| invalid(3, op.clone()))?,
+        changed_at: DateTime::from_timestamp(changed_at_ts, 0).unwrap_or_else(Utc::now),
+        payload_hash: row.get(5)?,
+    })
+}
+
+/// SQLite implementation of ChangeLogRepository
+pub struct SqliteChangeLogRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteChangeLogRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+}
+
+#[async_trait]
+impl ChangeLogRepository for SqliteChangeLogRepository {
+    async fn changes_since(&self, seq: i64, limit: usize) -> Result<Vec<ChangeRecord>> {
+        self.connection
+            .call(move |conn| {
+                hash_pending_payloads(conn)?;
+                let mut stmt = conn.prepare(
+                    "SELECT seq, entity, entity_id, op, changed_at, payload_hash \
+                     FROM change_log WHERE seq > ?1 ORDER BY seq LIMIT ?2"
+                )?;
+                let rows = stmt.query_map(rusqlite::params![seq, limit as i64], row_to_change)?;
+                Ok(rows.flatten().collect())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to read change log: {}", e),
+                },
+            })
+    }
+
+    async fn changes_for(&self, entity: ChangeEntity, entity_id: &str) -> Result<Vec<ChangeRecord>> {
+        let entity_id = entity_id.to_string();
+
+        self.connection
+            .call(move |conn| {
+                hash_pending_payloads(conn)?;
+                let mut stmt = conn.prepare(
+                    "SELECT seq, entity, entity_id, op, changed_at, payload_hash \
+                     FROM change_log WHERE entity = ?1 AND entity_id = ?2 ORDER BY seq"
+                )?;
+                let rows = stmt.query_map(rusqlite::params![entity.as_str(), entity_id], row_to_change)?;
+                Ok(rows.flatten().collect())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to read change log: {}", e),
+                },
+            })
+    }
+
+    async fn latest_seq(&self) -> Result<i64> {
+        self.connection
+            .call(|conn| {
+                let seq: Option<i64> = conn
+                    .query_row("SELECT seq FROM sqlite_sequence WHERE name = 'change_log'", [], |row| row.get(0))
+                    .optional()?;
+                Ok(seq.unwrap_or(0))
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to read change log: {}", e),
+                },
+            })
+    }
+
+    async fn prune(&self, before: DateTime<Utc>) -> Result<usize> {
+        let ts = before.timestamp();
+
+        self.connection
+            .call(move |conn| Ok(conn.execute("DELETE FROM change_log WHERE changed_at < ?1", [ts])?))
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to prune change log: {}", e),
+                },
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DatabaseManager, PageRepository, TagRepository};
+    use crate::test_support::{temp_dir, test_page};
+
+    #[tokio::test]
+    async fn test_writes_are_logged_in_order() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let log = db.change_log_repository();
+        let pages = db.page_repository();
+        let tags = db.tag_repository();
+        assert_eq!(log.latest_seq().await.unwrap(), 0);
+
+        let mut page = test_page("https://example.com/changes", "Logged page");
+        pages.save(&page).await.unwrap();
+        let tag = tags.get_or_create("logged").await.unwrap();
+        tags.tag_page(&page.id, &tag.id).await.unwrap();
+        let saved_hash = log.changes_for(ChangeEntity::Page, &page.id.to_string()).await.unwrap()[0]
+            .payload_hash
+            .clone();
+
+        // Saving the same row again gives the same hash, a change a new one
+        pages.save(&page).await.unwrap();
+        page.title = "Renamed".to_string();
+        pages.save(&page).await.unwrap();
+        pages.delete(&page.id).await.unwrap();
+        pages.purge_trash(Utc::now() + chrono::Duration::seconds(1)).await.unwrap();
+
+        let changes = log.changes_for(ChangeEntity::Page, &page.id.to_string()).await.unwrap();
+        let ops: Vec<ChangeOp> = changes.iter().map(|c| c.op).collect();
+        assert_eq!(
+            ops,
+            vec![ChangeOp::Insert, ChangeOp::Update, ChangeOp::Update, ChangeOp::Update, ChangeOp::Delete]
+        );
+        assert!(saved_hash.is_some());
+        assert_eq!(changes[1].payload_hash, saved_hash);
+        assert_ne!(changes[2].payload_hash, saved_hash);
+        assert_eq!(changes[4].payload_hash, None);
+        assert!(changes.windows(2).all(|w| w[0].seq < w[1].seq));
+
+        // The purge cascaded to the page's tag
+        let tag_changes = log
+            .changes_for(ChangeEntity::PageTag, &format!("{}:{}", page.id, tag.id))
+            .await
+            .unwrap();
+        assert_eq!(tag_changes.iter().map(|c| c.op).collect::<Vec<_>>(), vec![ChangeOp::Insert, ChangeOp::Delete]);
+
+        // Reading from a sequence number returns what came after it
+        let all = log.changes_since(0, 100).await.unwrap();
+        assert_eq!(all.last().unwrap().seq, log.latest_seq().await.unwrap());
+        let tail = log.changes_since(all[1].seq, 2).await.unwrap();
+        assert_eq!(tail, all[2..4].to_vec());
+
+        assert_eq!(log.prune(Utc::now() + chrono::Duration::seconds(1)).await.unwrap(), all.len());
+        assert!(log.changes_since(0, 100).await.unwrap().is_empty());
+        assert_eq!(log.latest_seq().await.unwrap(), all.last().unwrap().seq);
+    }
+
+    #[tokio::test]
+    async fn test_plain_connections_can_write_logged_tables() {
+        let dir = temp_dir("changelog_plain");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("pages.db");
+        drop(DatabaseManager::new(&path).await.unwrap());
+
+        // A connection without the manager's SQL functions
+        let id = Uuid::new_v4().to_string();
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute(
+            "INSERT INTO tags (id, name, created_at) VALUES (?1, 'plain', 0)",
+            rusqlite::params![id],
+        )
+        .unwrap();
+        drop(conn);
+
+        let db = DatabaseManager::new(&path).await.unwrap();
+        let changes = db.change_log_repository().changes_for(ChangeEntity::Tag, &id).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].payload_hash.is_some());
+        drop(db);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
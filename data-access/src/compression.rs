@@ -0,0 +1,63 @@
+//! Compressed storage of archived page content
+//!
+//! Archived HTML is stored once per distinct content in `archive_blobs`,
+//! compressed with zstd and keyed by the SHA-1 of the uncompressed text.
+//! Archives refer to their blob by hash, so archiving the same page many
+//! times costs one copy. The archive text stays uncompressed in
+//! `content_archives` because the full-text index reads it from there.
+
+use std::io;
+
+use sha1::{Digest, Sha1};
+
+/// zstd level for archived content; favors speed, as archiving happens
+/// while browsing
+pub const COMPRESSION_LEVEL: i32 = 3;
+
+/// Hex SHA-1 of `content`, the key of its blob
+pub(crate) fn content_hash(content: &str) -> String {
+    bytes_hash(content.as_bytes())
+}
+
+/// Hex SHA-1 of binary data
+pub(crate) fn bytes_hash(data: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut hash = String::with_capacity(40);
+    for byte in Sha1::digest(data) {
+        hash.push(HEX[usize::from(byte >> 4)] as char);
+        hash.push(HEX[usize::from(byte & 0x0f)] as char);
+    }
+    hash
+}
+
+pub(crate) fn compress(content: &str) -> io::Result<Vec<u8>> {
+    zstd::encode_all(content.as_bytes(), COMPRESSION_LEVEL)
+}
+
+pub(crate) fn decompress(data: &[u8]) -> io::Result<String> {
+    let bytes = zstd::decode_all(data)?;
+    String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Decompress a blob column, reporting corrupt data as a conversion error
+/// of that column
+pub(crate) fn decompress_column(data: &[u8], column: usize) -> rusqlite::Result<String> {
+    decompress(data).map_err(|e| rusqlite::Error::FromSqlConversionFailure(column, rusqlite::types::Type::Blob, Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_and_hash() {
+        let html = "<p>archived 归档</p>".repeat(200);
+        let data = compress(&html).unwrap();
+        assert!(data.len() < html.len() / 10);
+        assert_eq!(decompress(&data).unwrap(), html);
+
+        assert_eq!(content_hash(""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(content_hash(&html), content_hash(&html.clone()));
+        assert!(decompress(b"not zstd").is_err());
+    }
+}
@@ -0,0 +1,332 @@
+//! Page embeddings with approximate nearest-neighbor search
+//!
+//! Embeddings are stored in `page_embeddings` together with the links of the
+//! HNSW graph built over them, so the index survives restarts, backups and
+//! exports like any other data. The graph is loaded into memory on first use
+//! and shared by every repository of the database; each write updates the
+//! in-memory graph and the rows of all nodes whose links changed in one
+//! transaction.
+//!
+//! Embeddings of deleted pages are skipped in results until they are
+//! replaced or removed.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rusqlite::OptionalExtension;
+use tokio::sync::RwLock;
+use tokio_rusqlite::Connection;
+use web_page_manager_core::*;
+
+use crate::hnsw::{Hnsw, EF_SEARCH};
+
+/// Page found by a similarity search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimilarPage {
+    pub page_id: Uuid,
+    /// Cosine similarity to the query, 1.0 for the same direction
+    pub similarity: f32,
+}
+
+/// Repository trait for page embeddings
+#[async_trait]
+pub trait EmbeddingRepository: Send + Sync {
+    /// Store or replace the embedding of a page
+    async fn save(&self, page_id: &Uuid, embedding: &[f32]) -> Result<()>;
+    async fn get(&self, page_id: &Uuid) -> Result<Option<Vec<f32>>>;
+    async fn delete(&self, page_id: &Uuid) -> Result<()>;
+    /// Pages most similar to `page_id`, best first, excluding the page itself
+    async fn find_similar(&self, page_id: &Uuid, k: usize) -> Result<Vec<SimilarPage>>;
+    /// Pages closest to an embedding, best first
+    async fn find_nearest(&self, embedding: &[f32], k: usize) -> Result<Vec<SimilarPage>>;
+    async fn count(&self) -> Result<usize>;
+}
+
+/// In-memory graph shared by the embedding repositories of one database
+#[derive(Default)]
+pub struct EmbeddingIndex {
+    graph: RwLock<Option<Hnsw>>,
+}
+
+impl EmbeddingIndex {
+    /// Drop the in-memory graph so it is reloaded from the table, after the
+    /// table changed underneath it
+    pub async fn invalidate(&self) {
+        *self.graph.write().await = None;
+    }
+}
+
+/// SQLite implementation of EmbeddingRepository
+pub struct SqliteEmbeddingRepository {
+    connection: Arc<Connection>,
+    index: Arc<EmbeddingIndex>,
+}
+
+impl SqliteEmbeddingRepository {
+    pub fn new(connection: Arc<Connection>, index: Arc<EmbeddingIndex>) -> Self {
+        Self { connection, index }
+    }
+
+    async fn load_graph(&self) -> Result<Hnsw> {
+        let stored = self
+            .connection
+            .call(|conn| {
+                let mut stmt = conn.prepare("SELECT page_id, vector, neighbors FROM page_embeddings ORDER BY rowid")?;
+                let rows = stmt.query_map([], |row| {
+                    let id: String = row.get(0)?;
+                    let vector: Vec<u8> = row.get(1)?;
+                    let neighbors: String = row.get(2)?;
+                    Ok((id, vector, neighbors))
+                })?;
+                Ok(rows
+                    .flatten()
+                    .filter_map(|(id, vector, neighbors)| {
+                        Some((
+                            Uuid::parse_str(&id).ok()?,
+                            decode_vector(&vector),
+                            serde_json::from_str(&neighbors).unwrap_or_default(),
+                        ))
+                    })
+                    .collect::<Vec<_>>())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to load embeddings: {}", e),
+                },
+            })?;
+        Ok(Hnsw::from_stored(stored))
+    }
+
+    /// Search the graph, loading it first if needed
+    async fn search(&self, query: &[f32], k: usize, exclude: Option<Uuid>) -> Result<Vec<SimilarPage>> {
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+        // Room for results that are filtered out below
+        let wanted = 2 * k + 1;
+        let candidates = {
+            let guard = self.index.graph.read().await;
+            match guard.as_ref() {
+                Some(graph) => graph.search(query, wanted, EF_SEARCH.max(wanted)),
+                None => {
+                    drop(guard);
+                    let mut guard = self.index.graph.write().await;
+                    if guard.is_none() {
+                        *guard = Some(self.load_graph().await?);
+                    }
+                    guard.as_ref().map(|g| g.search(query, wanted, EF_SEARCH.max(wanted))).unwrap_or_default()
+                }
+            }
+        };
+
+        // Drop the query page and pages deleted or trashed since they were embedded
+        let ids: Vec<String> = candidates.iter().map(|(id, _)| id.to_string()).collect();
+        let existing: Vec<String> = self
+            .connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare("SELECT 1 FROM unified_pages WHERE id = ?1 AND deleted_at IS NULL")?;
+                let mut existing = Vec::new();
+                for id in ids {
+                    if stmt.exists([&id])? {
+                        existing.push(id);
+                    }
+                }
+                Ok(existing)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to find similar pages: {}", e),
+                },
+            })?;
+
+        Ok(candidates
+            .into_iter()
+            .filter(|(id, _)| Some(*id) != exclude && existing.contains(&id.to_string()))
+            .take(k)
+            .map(|(page_id, similarity)| SimilarPage { page_id, similarity })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl EmbeddingRepository for SqliteEmbeddingRepository {
+    async fn save(&self, page_id: &Uuid, embedding: &[f32]) -> Result<()> {
+        if embedding.is_empty() {
+            return Err(WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: "Embedding is empty".to_string(),
+                },
+            });
+        }
+
+        let mut guard = self.index.graph.write().await;
+        if guard.is_none() {
+            *guard = Some(self.load_graph().await?);
+        }
+        let Some(graph) = guard.as_mut() else {
+            return Ok(());
+        };
+        if let Some(dimensions) = graph.dimensions().filter(|d| *d != embedding.len()) {
+            return Err(WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Embedding has {} dimensions, the index uses {}", embedding.len(), dimensions),
+                },
+            });
+        }
+
+        let changed = graph.insert(*page_id, embedding);
+        let new_row = (page_id.to_string(), encode_vector(embedding), embedding.len() as i64);
+        let links: Vec<(String, String)> = changed
+            .iter()
+            .map(|&node| {
+                let neighbors = serde_json::to_string(&graph.neighbor_ids(node)).unwrap_or_default();
+                (graph.node(node).id.to_string(), neighbors)
+            })
+            .collect();
+
+        let written = self
+            .connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                let (id, vector, dimensions) = new_row;
+                tx.execute(
+                    r#"
+                    INSERT OR REPLACE INTO page_embeddings (page_id, dimensions, vector, neighbors, updated_at)
+                    VALUES (?1, ?2, ?3, '[]', ?4)
+                    "#,
+                    rusqlite::params![id, dimensions, vector, Utc::now().timestamp()],
+                )?;
+                for (id, neighbors) in links {
+                    tx.execute("UPDATE page_embeddings SET neighbors = ?1 WHERE page_id = ?2", [neighbors, id])?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await;
+
+        if let Err(e) = written {
+            // The graph is ahead of the table; reload it on next use
+            *guard = None;
+            return Err(WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to save embedding: {}", e),
+                },
+            });
+        }
+        Ok(())
+    }
+
+    async fn get(&self, page_id: &Uuid) -> Result<Option<Vec<f32>>> {
+        let id_str = page_id.to_string();
+        self.connection
+            .call(move |conn| {
+                let vector: Option<Vec<u8>> = conn
+                    .query_row("SELECT vector FROM page_embeddings WHERE page_id = ?1", [id_str], |row| row.get(0))
+                    .optional()?;
+                Ok(vector.map(|v| decode_vector(&v)))
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get embedding: {}", e),
+                },
+            })
+    }
+
+    async fn delete(&self, page_id: &Uuid) -> Result<()> {
+        let mut guard = self.index.graph.write().await;
+        let id_str = page_id.to_string();
+        self.connection
+            .call(move |conn| {
+                conn.execute("DELETE FROM page_embeddings WHERE page_id = ?1", [id_str])?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to delete embedding: {}", e),
+                },
+            })?;
+        // Links to the removed node are dropped when the graph is reloaded
+        if let Some(graph) = guard.as_mut() {
+            graph.remove(page_id);
+        }
+        Ok(())
+    }
+
+    async fn find_similar(&self, page_id: &Uuid, k: usize) -> Result<Vec<SimilarPage>> {
+        match self.get(page_id).await? {
+            Some(embedding) => self.search(&embedding, k, Some(*page_id)).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn find_nearest(&self, embedding: &[f32], k: usize) -> Result<Vec<SimilarPage>> {
+        self.search(embedding, k, None).await
+    }
+
+    async fn count(&self) -> Result<usize> {
+        self.connection
+            .call(|conn| {
+                let count: i64 = conn.query_row("SELECT COUNT(*) FROM page_embeddings", [], |row| row.get(0))?;
+                Ok(count as usize)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to count embeddings: {}", e),
+                },
+            })
+    }
+}
+
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DatabaseManager, PageRepository};
+    use crate::test_support::test_page;
+
+    #[tokio::test]
+    async fn test_find_similar_pages() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let embeddings = db.embedding_repository();
+
+        let vectors = [[1.0, 0.0, 0.0], [0.9, 0.1, 0.0], [0.0, 1.0, 0.0], [0.1, 0.9, 0.1], [0.0, 0.0, 1.0]];
+        let mut pages = Vec::new();
+        for (i, vector) in vectors.iter().enumerate() {
+            let page = test_page(&format!("https://example.com/page-{}", i), &format!("page-{}", i));
+            db.page_repository().save(&page).await.unwrap();
+            embeddings.save(&page.id, vector).await.unwrap();
+            pages.push(page.id);
+        }
+        assert_eq!(embeddings.count().await.unwrap(), 5);
+        assert_eq!(embeddings.get(&pages[1]).await.unwrap(), Some(vec![0.9, 0.1, 0.0]));
+        assert!(embeddings.save(&pages[0], &[1.0, 0.0]).await.is_err());
+
+        let similar = embeddings.find_similar(&pages[0], 1).await.unwrap();
+        assert_eq!(similar[0].page_id, pages[1]);
+        assert!(similar[0].similarity > 0.9);
+
+        // Deleted pages drop out; the graph reloads from the table
+        db.page_repository().delete(&pages[1]).await.unwrap();
+        embeddings.delete(&pages[3]).await.unwrap();
+        let fresh = db.embedding_repository();
+        fresh.index.invalidate().await;
+        let nearest: Vec<Uuid> =
+            fresh.find_nearest(&[0.0, 1.0, 0.1], 3).await.unwrap().into_iter().map(|s| s.page_id).collect();
+        assert_eq!(nearest, vec![pages[2], pages[4], pages[0]]);
+    }
+}
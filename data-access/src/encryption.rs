@@ -0,0 +1,227 @@
+//! Encryption at rest with SQLCipher
+//!
+//! Available with the `encryption` feature, which builds SQLite as SQLCipher.
+//! Every database page is encrypted with AES-256. A passphrase is stretched
+//! by SQLCipher itself (PBKDF2-HMAC-SHA512 with a random per-database salt);
+//! a raw key, typically generated once and kept in the OS keychain, is used
+//! as is. The key is needed on every open, so losing it loses the database.
+
+use std::fmt;
+
+use web_page_manager_core::*;
+
+/// Length of a raw key in bytes (AES-256)
+pub const KEY_LEN: usize = 32;
+
+/// Key for an encrypted database
+#[derive(Clone)]
+pub enum DatabaseKey {
+    /// User passphrase, stretched by SQLCipher
+    Passphrase(String),
+    /// Random 256-bit key
+    Raw([u8; KEY_LEN]),
+}
+
+/// Secret storage provided by the platform, such as the macOS Keychain,
+/// Windows Credential Manager or the Secret Service on Linux
+pub trait KeyStore: Send + Sync {
+    /// Read the secret stored for `account`, if any
+    fn load(&self, account: &str) -> Result<Option<Vec<u8>>>;
+    /// Store or replace the secret for `account`
+    fn save(&self, account: &str, secret: &[u8]) -> Result<()>;
+}
+
+/// Key store backed by the OS keychain, keeping each account's secret
+/// under `service`
+///
+/// Calls block on the platform service (over D-Bus on Linux), so async
+/// callers should run them with `spawn_blocking`.
+#[derive(Debug, Clone)]
+pub struct OsKeyStore {
+    service: String,
+}
+
+impl OsKeyStore {
+    pub fn new(service: impl Into<String>) -> Self {
+        Self { service: service.into() }
+    }
+
+    fn entry(&self, account: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(&self.service, account).map_err(|e| keychain_error(account, e))
+    }
+}
+
+impl KeyStore for OsKeyStore {
+    fn load(&self, account: &str) -> Result<Option<Vec<u8>>> {
+        match self.entry(account)?.get_secret() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(keychain_error(account, e)),
+        }
+    }
+
+    fn save(&self, account: &str, secret: &[u8]) -> Result<()> {
+        self.entry(account)?.set_secret(secret).map_err(|e| keychain_error(account, e))
+    }
+}
+
+fn keychain_error(account: &str, error: keyring::Error) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Keychain access for {} failed: {}", account, error),
+        },
+    }
+}
+
+impl DatabaseKey {
+    /// Generate a random raw key
+    pub fn generate() -> Result<Self> {
+        let mut key = [0u8; KEY_LEN];
+        getrandom::fill(&mut key).map_err(|e| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Failed to generate database key: {}", e),
+            },
+        })?;
+        Ok(DatabaseKey::Raw(key))
+    }
+
+    /// Load the raw key kept in `store` for `account`, generating and saving
+    /// one on first use
+    pub fn from_key_store(store: &dyn KeyStore, account: &str) -> Result<Self> {
+        if let Some(secret) = store.load(account)? {
+            let key: [u8; KEY_LEN] = secret.as_slice().try_into().map_err(|_| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!(
+                        "Database key for {} has {} bytes, expected {}",
+                        account,
+                        secret.len(),
+                        KEY_LEN
+                    ),
+                },
+            })?;
+            return Ok(DatabaseKey::Raw(key));
+        }
+
+        let key = Self::generate()?;
+        if let DatabaseKey::Raw(raw) = &key {
+            store.save(account, raw)?;
+        }
+        Ok(key)
+    }
+
+    /// Set this key on a connection with `PRAGMA key` or `PRAGMA rekey`
+    pub(crate) fn apply(&self, conn: &rusqlite::Connection, pragma: &str) -> rusqlite::Result<()> {
+        match self {
+            DatabaseKey::Passphrase(passphrase) => conn.pragma_update(None, pragma, passphrase),
+            // A blob literal inside the string tells SQLCipher to skip key derivation
+            DatabaseKey::Raw(key) => conn.execute_batch(&format!("PRAGMA {} = \"x'{}'\";", pragma, hex(key))),
+        }
+    }
+
+    /// Key clause for `ATTACH DATABASE ... KEY`
+    pub(crate) fn attach_clause(&self) -> String {
+        match self {
+            DatabaseKey::Passphrase(passphrase) => format!("'{}'", passphrase.replace('\'', "''")),
+            DatabaseKey::Raw(key) => format!("\"x'{}'\"", hex(key)),
+        }
+    }
+}
+
+impl fmt::Debug for DatabaseKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatabaseKey::Passphrase(_) => f.write_str("DatabaseKey::Passphrase(..)"),
+            DatabaseKey::Raw(_) => f.write_str("DatabaseKey::Raw(..)"),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// Check that the key opened the database
+///
+/// SQLCipher only notices a wrong key when the first page is read.
+pub(crate) fn verify_key(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, test_page};
+    use crate::{DatabaseManager, PageRepository};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryKeyStore(Mutex<HashMap<String, Vec<u8>>>);
+
+    impl KeyStore for MemoryKeyStore {
+        fn load(&self, account: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.0.lock().unwrap().get(account).cloned())
+        }
+
+        fn save(&self, account: &str, secret: &[u8]) -> Result<()> {
+            self.0.lock().unwrap().insert(account.to_string(), secret.to_vec());
+            Ok(())
+        }
+    }
+
+    fn temp_db(name: &str) -> PathBuf {
+        temp_dir(&format!("encryption_{}", name)).join("pages.db")
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_database_requires_key() {
+        let path = temp_db("passphrase");
+        let key = DatabaseKey::Passphrase("correct horse battery staple".to_string());
+        {
+            let db = DatabaseManager::encrypted(&path, key.clone()).await.unwrap();
+            db.page_repository().save(&test_page("https://example.com/private", "Private page")).await.unwrap();
+        }
+
+        let plain = std::fs::read(&path).unwrap();
+        assert!(!plain.starts_with(b"SQLite format 3"));
+        assert!(DatabaseManager::new(&path).await.is_err());
+        assert!(DatabaseManager::encrypted(&path, DatabaseKey::Passphrase("wrong".to_string())).await.is_err());
+
+        let db = DatabaseManager::encrypted(&path, key).await.unwrap();
+        assert_eq!(db.page_repository().count().await.unwrap(), 1);
+
+        // Changing the key re-encrypts the file
+        let new_key = DatabaseKey::generate().unwrap();
+        db.rekey(&new_key).await.unwrap();
+        drop(db);
+        let db = DatabaseManager::encrypted(&path, new_key).await.unwrap();
+        assert_eq!(db.page_repository().count().await.unwrap(), 1);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_plaintext_database_is_exported_encrypted() {
+        let plain_path = temp_db("export");
+        let encrypted_path = plain_path.with_file_name("encrypted.db");
+        let store = MemoryKeyStore::default();
+        let key = DatabaseKey::from_key_store(&store, "pages").unwrap();
+        let stored = store.load("pages").unwrap().unwrap();
+        assert_eq!(stored.len(), KEY_LEN);
+        assert!(matches!(DatabaseKey::from_key_store(&store, "pages").unwrap(), DatabaseKey::Raw(raw) if raw[..] == stored[..]));
+
+        let db = DatabaseManager::new(&plain_path).await.unwrap();
+        db.page_repository().save(&test_page("https://example.com/private", "Private page")).await.unwrap();
+        db.encrypt_to(&encrypted_path, &key).await.unwrap();
+        drop(db);
+
+        let db = DatabaseManager::encrypted(&encrypted_path, key).await.unwrap();
+        let pages = db.page_repository().get_all().await.unwrap();
+        assert_eq!(pages[0].title, "Private page");
+        assert_eq!(db.page_repository().search("private").await.unwrap().len(), 1);
+
+        let _ = std::fs::remove_dir_all(plain_path.parent().unwrap());
+    }
+}
@@ -0,0 +1,357 @@
+//! Full database export to newline-delimited JSON
+//!
+//! Every table is written to its own file with one JSON object per line, so
+//! exports can be streamed into other tools or diffed line by line. A
+//! `manifest.json` beside them records the schema version and the record
+//! count of each file. All tables are read in one transaction, so the files
+//! form a consistent snapshot even while the database is in use. Pages and
+//! history in the trash are left out, along with the records of those pages.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use rusqlite::Row;
+use tracing::info;
+use web_page_manager_core::*;
+
+use crate::repository::{
+    load_session, row_to_archive, row_to_group, row_to_history_entry, row_to_note, row_to_page, row_to_tag,
+};
+use crate::{schema, DatabaseManager};
+
+/// Format name recorded in the manifest
+pub const EXPORT_FORMAT: &str = "web-page-manager-ndjson";
+/// Version of the export layout, bumped when files or fields change meaning
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+/// Name of the manifest file in the export directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Description of an export, written as `manifest.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub format: String,
+    pub format_version: u32,
+    /// Database schema version the data was read from
+    pub schema_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub tables: Vec<ExportedTable>,
+}
+
+/// One exported table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedTable {
+    pub name: String,
+    /// File name relative to the export directory
+    pub file: String,
+    pub records: usize,
+}
+
+/// Tag on a page, as stored in `page_tags`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageTagRecord {
+    pub page_id: String,
+    pub tag_id: String,
+    pub added_at: DateTime<Utc>,
+}
+
+/// Membership of a page in a group, as stored in `page_group_relations`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageGroupRecord {
+    pub page_id: String,
+    pub group_id: String,
+    pub added_at: DateTime<Utc>,
+    pub confidence_score: Option<f32>,
+}
+
+impl ExportManifest {
+    /// Read the manifest of an export directory
+    pub fn load<P: AsRef<Path>>(directory: P) -> Result<Self> {
+        let file = File::open(directory.as_ref().join(MANIFEST_FILE)).map_err(|e| WebPageManagerError::System {
+            source: SystemError::IO { source: e },
+        })?;
+        serde_json::from_reader(file).map_err(|e| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Invalid export manifest: {}", e),
+            },
+        })
+    }
+}
+
+impl DatabaseManager {
+    /// Write every table to `<table>.ndjson` in `directory`, followed by the
+    /// manifest
+    ///
+    /// The manifest is written last, so a directory without one holds an
+    /// incomplete export.
+    pub async fn export_ndjson<P: AsRef<Path>>(&self, directory: P) -> Result<ExportManifest> {
+        let directory = directory.as_ref().to_path_buf();
+        std::fs::create_dir_all(&directory).map_err(|e| WebPageManagerError::System {
+            source: SystemError::IO { source: e },
+        })?;
+
+        let target = directory.clone();
+        let tables = self
+            .connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                let tables = vec![
+                    export_table(
+                        &tx,
+                        &target,
+                        "pages",
+                        "SELECT id, url, title, favicon_url, content_summary, keywords, category, \
+                         source_type, browser_info, tab_info, bookmark_info, created_at, last_accessed, access_count \
+                         FROM unified_pages WHERE deleted_at IS NULL ORDER BY created_at, id",
+                        row_to_page,
+                    )?,
+                    export_table(
+                        &tx,
+                        &target,
+                        "groups",
+                        "SELECT id, name, description, group_type, created_at, auto_generated, similarity_threshold \
+                         FROM smart_groups ORDER BY created_at, id",
+                        row_to_group,
+                    )?,
+                    export_table(
+                        &tx,
+                        &target,
+                        "page_groups",
+                        "SELECT page_id, group_id, added_at, confidence_score \
+                         FROM page_group_relations WHERE page_id IN (SELECT id FROM unified_pages WHERE deleted_at IS NULL) \
+                         ORDER BY group_id, added_at, page_id",
+                        row_to_page_group,
+                    )?,
+                    export_table(
+                        &tx,
+                        &target,
+                        "history",
+                        "SELECT id, page_id, url, title, favicon_url, browser_type, tab_id, closed_at, session_info, content_summary, recall_hint \
+                         FROM tab_history WHERE deleted_at IS NULL ORDER BY closed_at, id",
+                        row_to_history_entry,
+                    )?,
+                    export_table(
+                        &tx,
+                        &target,
+                        "archives",
+                        "SELECT a.id, a.page_id, a.url, a.title, a.content_html, a.content_text, a.media_files, \
+                         a.archived_at, a.file_size, a.checksum, b.data \
+                         FROM content_archives a LEFT JOIN archive_blobs b ON b.hash = a.html_hash \
+                         WHERE a.page_id IN (SELECT id FROM unified_pages WHERE deleted_at IS NULL) \
+                         ORDER BY a.archived_at, a.id",
+                        row_to_archive,
+                    )?,
+                    export_table(
+                        &tx,
+                        &target,
+                        "tags",
+                        "SELECT id, name, created_at, parent_id FROM tags ORDER BY created_at, id",
+                        row_to_tag,
+                    )?,
+                    export_table(
+                        &tx,
+                        &target,
+                        "page_tags",
+                        "SELECT page_id, tag_id, added_at FROM page_tags \
+                         WHERE page_id IN (SELECT id FROM unified_pages WHERE deleted_at IS NULL) \
+                         ORDER BY tag_id, added_at, page_id",
+                        row_to_page_tag,
+                    )?,
+                    export_table(
+                        &tx,
+                        &target,
+                        "notes",
+                        "SELECT id, page_id, content, quote, created_at, updated_at \
+                         FROM page_notes WHERE page_id IN (SELECT id FROM unified_pages WHERE deleted_at IS NULL) \
+                         ORDER BY created_at, id",
+                        row_to_note,
+                    )?,
+                    export_table(
+                        &tx,
+                        &target,
+                        "sessions",
+                        "SELECT id FROM sessions ORDER BY created_at, id",
+                        |row| {
+                            let id: String = row.get(0)?;
+                            load_session(&tx, &id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+                        },
+                    )?,
+                ];
+                tx.finish()?;
+                Ok(tables)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to export database to {:?}: {}", directory, e),
+                },
+            })?;
+
+        let manifest = ExportManifest {
+            format: EXPORT_FORMAT.to_string(),
+            format_version: EXPORT_FORMAT_VERSION,
+            schema_version: schema::SCHEMA_VERSION,
+            exported_at: Utc::now(),
+            tables,
+        };
+        let json = serde_json::to_vec_pretty(&manifest).map_err(|e| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Failed to serialize export manifest: {}", e),
+            },
+        })?;
+        std::fs::write(directory.join(MANIFEST_FILE), json).map_err(|e| WebPageManagerError::System {
+            source: SystemError::IO { source: e },
+        })?;
+
+        info!(
+            "Exported {} records to {:?}",
+            manifest.tables.iter().map(|t| t.records).sum::<usize>(),
+            directory
+        );
+        Ok(manifest)
+    }
+}
+
+/// Stream the rows of `sql` into `<name>.ndjson`
+fn export_table<T, F>(
+    conn: &rusqlite::Connection,
+    directory: &Path,
+    name: &str,
+    sql: &str,
+    map: F,
+) -> std::result::Result<ExportedTable, tokio_rusqlite::Error>
+where
+    T: Serialize,
+    F: FnMut(&Row) -> rusqlite::Result<T>,
+{
+    let file = format!("{}.ndjson", name);
+    let path: PathBuf = directory.join(&file);
+    let io_error = |e: std::io::Error| tokio_rusqlite::Error::Other(Box::new(e));
+
+    let mut writer = BufWriter::new(File::create(&path).map_err(io_error)?);
+    let mut stmt = conn.prepare(sql)?;
+    let mut records = 0;
+    for record in stmt.query_map([], map)? {
+        serde_json::to_writer(&mut writer, &record?).map_err(|e| tokio_rusqlite::Error::Other(Box::new(e)))?;
+        writer.write_all(b"\n").map_err(io_error)?;
+        records += 1;
+    }
+    writer.flush().map_err(io_error)?;
+
+    Ok(ExportedTable {
+        name: name.to_string(),
+        file,
+        records,
+    })
+}
+
+fn row_to_page_group(row: &Row) -> rusqlite::Result<PageGroupRecord> {
+    let added_at_ts: i64 = row.get(2)?;
+    Ok(PageGroupRecord {
+        page_id: row.get(0)?,
+        group_id: row.get(1)?,
+        added_at: DateTime::from_timestamp(added_at_ts, 0).unwrap_or_else(Utc::now),
+        confidence_score: row.get(3)?,
+    })
+}
+
+fn row_to_page_tag(row: &Row) -> rusqlite::Result<PageTagRecord> {
+    let added_at_ts: i64 = row.get(2)?;
+    Ok(PageTagRecord {
+        page_id: row.get(0)?,
+        tag_id: row.get(1)?,
+        added_at: DateTime::from_timestamp(added_at_ts, 0).unwrap_or_else(Utc::now),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        GroupRepository, NoteRepository, PageNote, PageRepository, SavedSession, SessionRepository, SessionTab,
+        SessionWindow, TagRepository,
+    };
+    use crate::test_support::{temp_dir, test_page};
+
+    #[tokio::test]
+    async fn test_export_writes_every_table_and_manifest() {
+        let dir = temp_dir("export");
+        let db = DatabaseManager::in_memory().await.unwrap();
+
+        let pages = ["https://example.com/a", "https://example.com/b"]
+            .map(|url| UnifiedPageInfo { keywords: vec!["export".to_string()], ..test_page(url, "Exported page") });
+        for page in &pages {
+            db.page_repository().save(page).await.unwrap();
+        }
+        let group = SmartGroup {
+            id: Uuid::new_v4(),
+            name: "Reading".to_string(),
+            description: String::new(),
+            group_type: GroupType::UserDefined,
+            pages: vec![],
+            created_at: Utc::now(),
+            auto_generated: false,
+            similarity_threshold: 0.5,
+        };
+        let groups = db.group_repository();
+        groups.save(&group).await.unwrap();
+        groups.add_page_to_group(&pages[0].id, &group.id, 0.75).await.unwrap();
+        let tag = db.tag_repository().get_or_create("reading").await.unwrap();
+        db.tag_repository().tag_page(&pages[1].id, &tag.id).await.unwrap();
+        db.note_repository().save(&PageNote::note(pages[0].id, "Read later")).await.unwrap();
+        let session = SavedSession::new(
+            "Morning",
+            vec![SessionWindow {
+                browser: BrowserType::Edge,
+                tabs: vec![SessionTab {
+                    url: pages[0].url.clone(),
+                    title: pages[0].title.clone(),
+                    favicon_url: None,
+                    pinned: true,
+                }],
+            }],
+        );
+        db.session_repository().save(&session).await.unwrap();
+
+        let manifest = db.export_ndjson(&dir).await.unwrap();
+        assert_eq!(manifest.schema_version, schema::SCHEMA_VERSION);
+        assert_eq!(ExportManifest::load(&dir).unwrap(), manifest);
+
+        let counts: Vec<(&str, usize)> = manifest.tables.iter().map(|t| (t.name.as_str(), t.records)).collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("pages", 2),
+                ("groups", 1),
+                ("page_groups", 1),
+                ("history", 0),
+                ("archives", 0),
+                ("tags", 1),
+                ("page_tags", 1),
+                ("notes", 1),
+                ("sessions", 1)
+            ]
+        );
+
+        let lines: Vec<UnifiedPageInfo> = std::fs::read_to_string(dir.join("pages.ndjson"))
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|p| p.keywords == vec!["export".to_string()]));
+
+        let relation: PageGroupRecord =
+            serde_json::from_str(std::fs::read_to_string(dir.join("page_groups.ndjson")).unwrap().trim()).unwrap();
+        assert_eq!(relation.page_id, pages[0].id.to_string());
+        assert_eq!(relation.confidence_score, Some(0.75));
+
+        // Sessions are written whole, with their windows and tabs
+        let exported: SavedSession =
+            serde_json::from_str(std::fs::read_to_string(dir.join("sessions.ndjson")).unwrap().trim()).unwrap();
+        assert_eq!(exported.id, session.id);
+        assert_eq!(exported.windows, session.windows);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
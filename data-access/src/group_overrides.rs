@@ -0,0 +1,230 @@
+//! Smart group overrides
+//!
+//! Users correct generated groups by pinning a page to a group, so it stays
+//! there whatever the clustering finds, or excluding it, so it is never put
+//! back. The group generator applies the overrides on every regeneration.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rusqlite::Row;
+use tokio_rusqlite::Connection;
+use web_page_manager_core::*;
+
+/// How an override changes a group's membership
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GroupOverrideKind {
+    /// The page always belongs to the group
+    Pin,
+    /// The page never belongs to the group
+    Exclude,
+}
+
+impl GroupOverrideKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            GroupOverrideKind::Pin => "pin",
+            GroupOverrideKind::Exclude => "exclude",
+        }
+    }
+}
+
+/// A user's pin or exclusion of a page in a group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupOverride {
+    pub group_id: Uuid,
+    pub page_id: Uuid,
+    pub kind: GroupOverrideKind,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Repository trait for group overrides
+#[async_trait]
+pub trait GroupOverrideRepository: Send + Sync {
+    /// Pin or exclude a page, replacing its earlier override in the group
+    async fn set(&self, group_id: &Uuid, page_id: &Uuid, kind: GroupOverrideKind) -> Result<GroupOverride>;
+    /// Remove the override of a page in a group, returning whether it was there
+    async fn remove(&self, group_id: &Uuid, page_id: &Uuid) -> Result<bool>;
+    /// Overrides of one group, oldest first
+    async fn get_for_group(&self, group_id: &Uuid) -> Result<Vec<GroupOverride>>;
+    /// Every override, oldest first
+    async fn get_all(&self) -> Result<Vec<GroupOverride>>;
+    /// Remove every override of a group, returning how many there were
+    async fn clear_group(&self, group_id: &Uuid) -> Result<usize>;
+}
+
+/// SQLite implementation of GroupOverrideRepository
+#[derive(Clone)]
+pub struct SqliteGroupOverrideRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteGroupOverrideRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+}
+
+fn override_error(action: &str, e: tokio_rusqlite::Error) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Failed to {}: {}", action, e),
+        },
+    }
+}
+
+/// Helper function to map a row to GroupOverride
+fn row_to_override(row: &Row) -> rusqlite::Result<GroupOverride> {
+    let group_id: String = row.get(0)?;
+    let page_id: String = row.get(1)?;
+    let kind: String = row.get(2)?;
+    Ok(GroupOverride {
+        group_id: Uuid::parse_str(&group_id).unwrap_or_else(|_| Uuid::new_v4()),
+        page_id: Uuid::parse_str(&page_id).unwrap_or_else(|_| Uuid::new_v4()),
+        kind: if kind == "pin" { GroupOverrideKind::Pin } else { GroupOverrideKind::Exclude },
+        created_at: DateTime::from_timestamp(row.get(3)?, 0).unwrap_or_else(Utc::now),
+    })
+}
+
+#[async_trait]
+impl GroupOverrideRepository for SqliteGroupOverrideRepository {
+    async fn set(&self, group_id: &Uuid, page_id: &Uuid, kind: GroupOverrideKind) -> Result<GroupOverride> {
+        let item = GroupOverride {
+            group_id: *group_id,
+            page_id: *page_id,
+            kind,
+            created_at: Utc::now(),
+        };
+        let row = item.clone();
+
+        self.connection
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT OR REPLACE INTO group_overrides (group_id, page_id, kind, created_at) \
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![
+                        row.group_id.to_string(),
+                        row.page_id.to_string(),
+                        row.kind.as_str(),
+                        row.created_at.timestamp(),
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| override_error("save group override", e))?;
+        Ok(item)
+    }
+
+    async fn remove(&self, group_id: &Uuid, page_id: &Uuid) -> Result<bool> {
+        let (group_id, page_id) = (group_id.to_string(), page_id.to_string());
+
+        self.connection
+            .call(move |conn| {
+                Ok(conn.execute(
+                    "DELETE FROM group_overrides WHERE group_id = ?1 AND page_id = ?2",
+                    [group_id, page_id],
+                )? > 0)
+            })
+            .await
+            .map_err(|e| override_error("remove group override", e))
+    }
+
+    async fn get_for_group(&self, group_id: &Uuid) -> Result<Vec<GroupOverride>> {
+        let group_id = group_id.to_string();
+
+        self.connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT group_id, page_id, kind, created_at FROM group_overrides \
+                     WHERE group_id = ?1 ORDER BY created_at, rowid",
+                )?;
+                let rows = stmt.query_map([group_id], row_to_override)?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
+            .await
+            .map_err(|e| override_error("get group overrides", e))
+    }
+
+    async fn get_all(&self) -> Result<Vec<GroupOverride>> {
+        self.connection
+            .call(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT group_id, page_id, kind, created_at FROM group_overrides ORDER BY created_at, rowid",
+                )?;
+                let rows = stmt.query_map([], row_to_override)?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
+            .await
+            .map_err(|e| override_error("list group overrides", e))
+    }
+
+    async fn clear_group(&self, group_id: &Uuid) -> Result<usize> {
+        let group_id = group_id.to_string();
+
+        self.connection
+            .call(move |conn| Ok(conn.execute("DELETE FROM group_overrides WHERE group_id = ?1", [group_id])?))
+            .await
+            .map_err(|e| override_error("clear group overrides", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DatabaseManager, GroupRepository, PageRepository};
+
+    #[tokio::test]
+    async fn test_group_overrides() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let group = SmartGroup {
+            id: Uuid::new_v4(),
+            name: "Reading".to_string(),
+            description: String::new(),
+            group_type: GroupType::UserDefined,
+            pages: vec![],
+            created_at: Utc::now(),
+            auto_generated: true,
+            similarity_threshold: 0.5,
+        };
+        db.group_repository().save(&group).await.unwrap();
+        let page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: "https://example.com".to_string(),
+            title: "Example".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Chrome,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        db.page_repository().save(&page).await.unwrap();
+
+        let overrides = db.group_override_repository();
+        overrides.set(&group.id, &page.id, GroupOverrideKind::Pin).await.unwrap();
+        // A later override of the same page replaces the earlier one
+        overrides.set(&group.id, &page.id, GroupOverrideKind::Exclude).await.unwrap();
+        let stored = overrides.get_for_group(&group.id).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].kind, GroupOverrideKind::Exclude);
+
+        assert!(overrides.remove(&group.id, &page.id).await.unwrap());
+        assert!(!overrides.remove(&group.id, &page.id).await.unwrap());
+
+        // Saving the group again keeps its overrides
+        overrides.set(&group.id, &page.id, GroupOverrideKind::Pin).await.unwrap();
+        db.group_repository().save(&group).await.unwrap();
+        assert_eq!(overrides.get_all().await.unwrap().len(), 1);
+        assert_eq!(overrides.clear_group(&group.id).await.unwrap(), 1);
+        assert!(overrides.get_all().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+//! Smart group archiving and page order
+//!
+//! An archived group is one the user is done with: it is hidden from the
+//! group list but kept, and still found by search. Pages within a group can
+//! be put in a manual order, which is kept apart from the group's
+//! memberships so the order survives a membership being re-added.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio_rusqlite::Connection;
+use web_page_manager_core::*;
+
+/// When a group was archived
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedGroup {
+    pub group_id: Uuid,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// Repository trait for group archiving and page order
+#[async_trait]
+pub trait GroupStateRepository: Send + Sync {
+    /// Archive a group, keeping the original time if it already is
+    async fn archive(&self, group_id: &Uuid) -> Result<ArchivedGroup>;
+    /// Bring a group back from the archive; false if it was not archived
+    async fn unarchive(&self, group_id: &Uuid) -> Result<bool>;
+    async fn is_archived(&self, group_id: &Uuid) -> Result<bool>;
+    /// Archived groups, most recently archived first
+    async fn archived(&self) -> Result<Vec<ArchivedGroup>>;
+    /// Store the manual order of a group's pages, replacing the previous one
+    async fn set_page_order(&self, group_id: &Uuid, page_ids: &[Uuid]) -> Result<()>;
+    /// Manually ordered pages of a group, empty if it has no manual order
+    async fn page_order(&self, group_id: &Uuid) -> Result<Vec<Uuid>>;
+    /// Drop the archive state and page order of a deleted group
+    async fn clear(&self, group_id: &Uuid) -> Result<()>;
+}
+
+/// SQLite implementation of GroupStateRepository
+#[derive(Clone)]
+pub struct SqliteGroupStateRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteGroupStateRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+}
+
+fn group_state_error(action: &str, e: tokio_rusqlite::Error) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Failed to {}: {}", action, e),
+        },
+    }
+}
+
+fn parse_id(id: String) -> Uuid {
+    Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4())
+}
+
+#[async_trait]
+impl GroupStateRepository for SqliteGroupStateRepository {
+    async fn archive(&self, group_id: &Uuid) -> Result<ArchivedGroup> {
+        let id = group_id.to_string();
+
+        let archived_at: i64 = self
+            .connection
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT OR IGNORE INTO archived_groups (group_id, archived_at) VALUES (?1, ?2)",
+                    rusqlite::params![id, Utc::now().timestamp()],
+                )?;
+                Ok(conn.query_row("SELECT archived_at FROM archived_groups WHERE group_id = ?1", [id], |row| {
+                    row.get(0)
+                })?)
+            })
+            .await
+            .map_err(|e| group_state_error("archive group", e))?;
+
+        Ok(ArchivedGroup {
+            group_id: *group_id,
+            archived_at: DateTime::from_timestamp(archived_at, 0).unwrap_or_else(Utc::now),
+        })
+    }
+
+    async fn unarchive(&self, group_id: &Uuid) -> Result<bool> {
+        let id = group_id.to_string();
+
+        self.connection
+            .call(move |conn| Ok(conn.execute("DELETE FROM archived_groups WHERE group_id = ?1", [id])? > 0))
+            .await
+            .map_err(|e| group_state_error("unarchive group", e))
+    }
+
+    async fn is_archived(&self, group_id: &Uuid) -> Result<bool> {
+        let id = group_id.to_string();
+
+        self.connection
+            .call(move |conn| {
+                Ok(conn.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM archived_groups WHERE group_id = ?1)",
+                    [id],
+                    |row| row.get(0),
+                )?)
+            })
+            .await
+            .map_err(|e| group_state_error("check archived group", e))
+    }
+
+    async fn archived(&self) -> Result<Vec<ArchivedGroup>> {
+        self.connection
+            .call(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT group_id, archived_at FROM archived_groups ORDER BY archived_at DESC, rowid DESC",
+                )?;
+                let rows = stmt.query_map([], |row| {
+                    Ok(ArchivedGroup {
+                        group_id: parse_id(row.get(0)?),
+                        archived_at: DateTime::from_timestamp(row.get(1)?, 0).unwrap_or_else(Utc::now),
+                    })
+                })?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
+            .await
+            .map_err(|e| group_state_error("list archived groups", e))
+    }
+
+    async fn set_page_order(&self, group_id: &Uuid, page_ids: &[Uuid]) -> Result<()> {
+        let id = group_id.to_string();
+        let page_ids: Vec<String> = page_ids.iter().map(Uuid::to_string).collect();
+
+        self.connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute("DELETE FROM group_page_order WHERE group_id = ?1", [&id])?;
+                {
+                    let mut insert = tx.prepare(
+                        "INSERT OR IGNORE INTO group_page_order (group_id, page_id, position) VALUES (?1, ?2, ?3)",
+                    )?;
+                    for (position, page_id) in page_ids.iter().enumerate() {
+                        insert.execute(rusqlite::params![id, page_id, position as i64])?;
+                    }
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| group_state_error("order group pages", e))
+    }
+
+    async fn page_order(&self, group_id: &Uuid) -> Result<Vec<Uuid>> {
+        let id = group_id.to_string();
+
+        self.connection
+            .call(move |conn| {
+                let mut stmt =
+                    conn.prepare("SELECT page_id FROM group_page_order WHERE group_id = ?1 ORDER BY position")?;
+                let rows = stmt.query_map([id], |row| row.get::<_, String>(0))?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?.into_iter().map(parse_id).collect())
+            })
+            .await
+            .map_err(|e| group_state_error("get group page order", e))
+    }
+
+    async fn clear(&self, group_id: &Uuid) -> Result<()> {
+        let id = group_id.to_string();
+
+        self.connection
+            .call(move |conn| {
+                conn.execute("DELETE FROM archived_groups WHERE group_id = ?1", [&id])?;
+                conn.execute("DELETE FROM group_page_order WHERE group_id = ?1", [&id])?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| group_state_error("clear group state", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DatabaseManager, PageRepository};
+    use crate::test_support::test_page;
+
+    #[tokio::test]
+    async fn test_archive_and_page_order() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let state = db.group_state_repository();
+        let (group, other) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let archived = state.archive(&group).await.unwrap();
+        assert_eq!(state.archive(&group).await.unwrap(), archived);
+        assert!(state.is_archived(&group).await.unwrap());
+        assert!(!state.is_archived(&other).await.unwrap());
+        assert_eq!(state.archived().await.unwrap(), vec![archived]);
+        assert!(state.unarchive(&group).await.unwrap());
+        assert!(!state.unarchive(&group).await.unwrap());
+
+        let mut pages = Vec::new();
+        for url in ["https://a.example.com", "https://b.example.com", "https://c.example.com"] {
+            let page = test_page(url, url);
+            db.page_repository().save(&page).await.unwrap();
+            pages.push(page.id);
+        }
+        state.set_page_order(&group, &[pages[2], pages[0], pages[1]]).await.unwrap();
+        state.set_page_order(&group, &[pages[1], pages[2]]).await.unwrap();
+        assert_eq!(state.page_order(&group).await.unwrap(), vec![pages[1], pages[2]]);
+        assert!(state.page_order(&other).await.unwrap().is_empty());
+
+        state.archive(&group).await.unwrap();
+        state.clear(&group).await.unwrap();
+        assert!(state.page_order(&group).await.unwrap().is_empty());
+        assert!(state.archived().await.unwrap().is_empty());
+    }
+}
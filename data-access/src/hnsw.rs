@@ -0,0 +1,391 @@
+//! Hierarchical navigable small world graph for nearest-neighbor search
+//!
+//! Every vector is a node on layer 0 and, with exponentially falling
+//! probability, on the layers above. A search walks greedily from the single
+//! entry node on the top layer down to layer 0, where a best-first search
+//! keeps the `ef` closest candidates. Results are approximate: larger `ef`
+//! trades speed for recall.
+//!
+//! Vectors are normalized on insert and compared by cosine distance. A
+//! node's level is derived from its id rather than drawn at random, so
+//! rebuilding from the same data gives the same graph. Removed nodes stay in
+//! the graph as tombstones that searches pass through but never return.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use uuid::Uuid;
+
+/// Links per node on the upper layers
+pub const MAX_NEIGHBORS: usize = 16;
+/// Links per node on layer 0, which holds every node
+pub const MAX_NEIGHBORS_BASE: usize = 2 * MAX_NEIGHBORS;
+/// Candidates kept while linking a new node
+pub const EF_CONSTRUCTION: usize = 100;
+/// Default candidates kept while searching
+pub const EF_SEARCH: usize = 64;
+/// Highest layer a node can reach
+const MAX_LEVEL: usize = 16;
+
+/// A vector in the graph
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub id: Uuid,
+    /// Unit-length vector
+    pub vector: Vec<f32>,
+    pub level: usize,
+    /// Node indices linked on each layer up to `level`
+    pub neighbors: Vec<Vec<usize>>,
+    pub deleted: bool,
+}
+
+/// Approximate nearest-neighbor index over unit vectors
+#[derive(Debug, Clone, Default)]
+pub struct Hnsw {
+    nodes: Vec<Node>,
+    /// Live node of each id
+    index: HashMap<Uuid, usize>,
+    entry: Option<usize>,
+}
+
+/// Node index ordered by distance
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    node: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance.total_cmp(&other.distance).then(self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hnsw {
+    /// Rebuild a graph from stored nodes, whose links name other nodes by id
+    ///
+    /// Links to ids that are not among the nodes are dropped.
+    pub fn from_stored(stored: Vec<(Uuid, Vec<f32>, Vec<Vec<Uuid>>)>) -> Self {
+        let index: HashMap<Uuid, usize> = stored.iter().enumerate().map(|(i, (id, _, _))| (*id, i)).collect();
+        let nodes: Vec<Node> = stored
+            .into_iter()
+            .map(|(id, vector, links)| {
+                let level = level_for(&id);
+                let mut neighbors: Vec<Vec<usize>> = links
+                    .iter()
+                    .take(level + 1)
+                    .map(|layer| layer.iter().filter_map(|n| index.get(n).copied()).collect())
+                    .collect();
+                neighbors.resize(level + 1, Vec::new());
+                Node {
+                    id,
+                    vector: normalized(&vector),
+                    level,
+                    neighbors,
+                    deleted: false,
+                }
+            })
+            .collect();
+        // The first node on the highest layer is the entry point
+        let entry = (0..nodes.len()).rev().max_by_key(|&i| nodes[i].level);
+        Self { nodes, index, entry }
+    }
+
+    /// Number of live vectors
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Dimensions of the indexed vectors, if any were added
+    pub fn dimensions(&self) -> Option<usize> {
+        self.nodes.first().map(|n| n.vector.len())
+    }
+
+    pub fn node(&self, index: usize) -> &Node {
+        &self.nodes[index]
+    }
+
+    pub fn get(&self, id: &Uuid) -> Option<&Node> {
+        self.index.get(id).map(|&i| &self.nodes[i])
+    }
+
+    /// Ids linked from `node` on each layer, skipping removed nodes
+    pub fn neighbor_ids(&self, node: usize) -> Vec<Vec<Uuid>> {
+        self.nodes[node]
+            .neighbors
+            .iter()
+            .map(|layer| layer.iter().filter(|&&n| !self.nodes[n].deleted).map(|&n| self.nodes[n].id).collect())
+            .collect()
+    }
+
+    /// Add or replace the vector of `id`
+    ///
+    /// Returns the indices of all nodes whose links changed, starting with
+    /// the new node. The vector must have the dimensions of the index.
+    pub fn insert(&mut self, id: Uuid, vector: &[f32]) -> Vec<usize> {
+        self.remove(&id);
+
+        let level = level_for(&id);
+        let new = self.nodes.len();
+        self.nodes.push(Node {
+            id,
+            vector: normalized(vector),
+            level,
+            neighbors: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.index.insert(id, new);
+        let mut changed = vec![new];
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(new);
+            return changed;
+        };
+        let top = self.nodes[entry].level;
+        let query = self.nodes[new].vector.clone();
+
+        let mut entry_points = vec![entry];
+        for layer in (level + 1..=top).rev() {
+            entry_points = self.search_layer(&query, &entry_points, 1, layer).iter().map(|c| c.node).collect();
+        }
+        for layer in (0..=level.min(top)).rev() {
+            let candidates = self.search_layer(&query, &entry_points, EF_CONSTRUCTION, layer);
+            let limit = max_links(layer);
+            let links: Vec<usize> = candidates
+                .iter()
+                .filter(|c| c.node != new && !self.nodes[c.node].deleted)
+                .take(limit)
+                .map(|c| c.node)
+                .collect();
+
+            for &neighbor in &links {
+                self.nodes[neighbor].neighbors[layer].push(new);
+                if self.nodes[neighbor].neighbors[layer].len() > limit {
+                    self.prune(neighbor, layer, limit);
+                }
+                if !changed.contains(&neighbor) {
+                    changed.push(neighbor);
+                }
+            }
+            self.nodes[new].neighbors[layer] = links;
+            entry_points = candidates.iter().map(|c| c.node).collect();
+        }
+
+        if level > top {
+            self.entry = Some(new);
+        }
+        changed
+    }
+
+    /// Remove the vector of `id`, returning whether it was present
+    pub fn remove(&mut self, id: &Uuid) -> bool {
+        match self.index.remove(id) {
+            Some(node) => {
+                self.nodes[node].deleted = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The `k` live nodes closest to `query`, closest first, with their
+    /// cosine similarity
+    pub fn search(&self, query: &[f32], k: usize, ef: usize) -> Vec<(Uuid, f32)> {
+        let Some(entry) = self.entry else {
+            return Vec::new();
+        };
+        if k == 0 || self.dimensions() != Some(query.len()) {
+            return Vec::new();
+        }
+        let query = normalized(query);
+
+        let mut entry_points = vec![entry];
+        for layer in (1..=self.nodes[entry].level).rev() {
+            entry_points = self.search_layer(&query, &entry_points, 1, layer).iter().map(|c| c.node).collect();
+        }
+        self.search_layer(&query, &entry_points, ef.max(k), 0)
+            .into_iter()
+            .filter(|c| !self.nodes[c.node].deleted)
+            .take(k)
+            .map(|c| (self.nodes[c.node].id, 1.0 - c.distance))
+            .collect()
+    }
+
+    /// Best-first search on one layer, returning up to `ef` nodes closest first
+    fn search_layer(&self, query: &[f32], entry_points: &[usize], ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entry_points.iter().copied().collect();
+        // Closest unexpanded candidate on top
+        let mut frontier: BinaryHeap<std::cmp::Reverse<Candidate>> = BinaryHeap::new();
+        // Farthest kept result on top
+        let mut results: BinaryHeap<Candidate> = BinaryHeap::new();
+        for &node in entry_points {
+            let candidate = Candidate {
+                distance: distance(query, &self.nodes[node].vector),
+                node,
+            };
+            frontier.push(std::cmp::Reverse(candidate));
+            results.push(candidate);
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(std::cmp::Reverse(current)) = frontier.pop() {
+            if results.len() >= ef && results.peek().is_some_and(|far| current.distance > far.distance) {
+                break;
+            }
+            let Some(links) = self.nodes[current.node].neighbors.get(layer) else {
+                continue;
+            };
+            for &next in links {
+                if !visited.insert(next) {
+                    continue;
+                }
+                let candidate = Candidate {
+                    distance: distance(query, &self.nodes[next].vector),
+                    node: next,
+                };
+                if results.len() < ef || results.peek().is_some_and(|far| candidate.distance < far.distance) {
+                    frontier.push(std::cmp::Reverse(candidate));
+                    results.push(candidate);
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    /// Keep the `limit` links of `node` on `layer` closest to it
+    fn prune(&mut self, node: usize, layer: usize, limit: usize) {
+        let vector = &self.nodes[node].vector;
+        let mut links: Vec<Candidate> = self.nodes[node].neighbors[layer]
+            .iter()
+            .map(|&n| Candidate {
+                distance: distance(vector, &self.nodes[n].vector),
+                node: n,
+            })
+            .collect();
+        links.sort();
+        links.truncate(limit);
+        self.nodes[node].neighbors[layer] = links.into_iter().map(|c| c.node).collect();
+    }
+}
+
+fn max_links(layer: usize) -> usize {
+    if layer == 0 {
+        MAX_NEIGHBORS_BASE
+    } else {
+        MAX_NEIGHBORS
+    }
+}
+
+/// Layer of a node, geometrically distributed with ratio 1/`MAX_NEIGHBORS`
+fn level_for(id: &Uuid) -> usize {
+    // FNV-1a spreads the id bits; uuids from one generator share structure
+    let hash = id.as_bytes().iter().fold(0xcbf29ce484222325u64, |h, b| (h ^ *b as u64).wrapping_mul(0x100000001b3));
+    let uniform = ((hash >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+    let level = -uniform.ln() / (MAX_NEIGHBORS as f64).ln();
+    (level as usize).min(MAX_LEVEL)
+}
+
+fn normalized(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        vector.to_vec()
+    } else {
+        vector.iter().map(|v| v / norm).collect()
+    }
+}
+
+/// Cosine distance of unit vectors
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    1.0 - a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random vectors
+    fn vectors(count: usize, dimensions: usize) -> Vec<Vec<f32>> {
+        let mut state = 0x2545f4914f6cdd1du64;
+        (0..count)
+            .map(|_| {
+                (0..dimensions)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        (state % 2000) as f32 / 1000.0 - 1.0
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn exact(data: &[(Uuid, Vec<f32>)], query: &[f32], k: usize) -> Vec<Uuid> {
+        let query = normalized(query);
+        let mut scored: Vec<(f32, Uuid)> = data.iter().map(|(id, v)| (distance(&query, &normalized(v)), *id)).collect();
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+        scored.into_iter().take(k).map(|(_, id)| id).collect()
+    }
+
+    #[test]
+    fn test_search_recall_against_exact() {
+        let data: Vec<(Uuid, Vec<f32>)> = vectors(500, 16).into_iter().map(|v| (Uuid::new_v4(), v)).collect();
+        let mut graph = Hnsw::default();
+        for (id, vector) in &data {
+            graph.insert(*id, vector);
+        }
+        assert_eq!(graph.len(), 500);
+
+        let mut found = 0;
+        for query in vectors(20, 16) {
+            let expected = exact(&data, &query, 10);
+            let results = graph.search(&query, 10, EF_SEARCH);
+            found += results.iter().filter(|(id, _)| expected.contains(id)).count();
+        }
+        assert!(found >= 180, "recall {} of 200", found);
+    }
+
+    #[test]
+    fn test_remove_and_rebuild_from_stored() {
+        let data: Vec<(Uuid, Vec<f32>)> = vectors(50, 8).into_iter().map(|v| (Uuid::new_v4(), v)).collect();
+        let mut graph = Hnsw::default();
+        for (id, vector) in &data {
+            graph.insert(*id, vector);
+        }
+
+        let (removed, vector) = &data[0];
+        assert_eq!(graph.search(vector, 1, EF_SEARCH)[0].0, *removed);
+        assert!(graph.remove(removed));
+        assert!(graph.search(vector, 5, EF_SEARCH).iter().all(|(id, _)| id != removed));
+
+        let stored = data[1..]
+            .iter()
+            .map(|(id, vector)| {
+                let node = graph.index[id];
+                (*id, vector.clone(), graph.neighbor_ids(node))
+            })
+            .collect();
+        let rebuilt = Hnsw::from_stored(stored);
+        assert_eq!(rebuilt.len(), 49);
+        let (id, vector) = &data[7];
+        assert_eq!(rebuilt.search(vector, 1, EF_SEARCH)[0].0, *id);
+        assert!((rebuilt.search(vector, 1, EF_SEARCH)[0].1 - 1.0).abs() < 1e-5);
+    }
+}
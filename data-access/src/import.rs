@@ -0,0 +1,541 @@
+//! Import of NDJSON exports into an existing database
+//!
+//! The counterpart of [`crate::export`]: records from an export directory
+//! are merged into the open database in one transaction, so a failed import
+//! changes nothing. A record conflicts with an existing one when they share
+//! an id, for pages a URL, or for tags a name. Pages matched by URL and tags
+//! matched by name keep the local id, and the records imported with them
+//! (group memberships, tags, notes, history and archives) are attached to it.
+//! Saved sessions refer to pages only by URL and are matched by id.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use rusqlite::OptionalExtension;
+use serde::de::DeserializeOwned;
+use tracing::info;
+use web_page_manager_core::*;
+
+use crate::canonical::canonical_url;
+use crate::export::{ExportManifest, PageGroupRecord, PageTagRecord, EXPORT_FORMAT, EXPORT_FORMAT_VERSION};
+use crate::repository::{insert_archive, insert_group, insert_history_entry, insert_note, insert_page, insert_session};
+use crate::{schema, ContentArchive, DatabaseManager, PageNote, SavedSession, Tag};
+
+/// What to do with an imported record that already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Keep the existing record
+    #[default]
+    Skip,
+    /// Replace the existing record
+    Overwrite,
+    /// Keep whichever record was modified last; ties keep the existing one
+    NewerWins,
+}
+
+/// Outcome of importing one table
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportedTable {
+    pub name: String,
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+/// Outcome of an import, in the order the tables were imported
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub tables: Vec<ImportedTable>,
+}
+
+impl ImportReport {
+    pub fn table(&self, name: &str) -> Option<&ImportedTable> {
+        self.tables.iter().find(|t| t.name == name)
+    }
+}
+
+/// Decision for one imported record
+enum Resolution {
+    Insert,
+    Update,
+    Skip,
+}
+
+impl ConflictStrategy {
+    /// Resolve a record against the modification time of an existing one
+    fn resolve(self, existing: Option<i64>, incoming: DateTime<Utc>) -> Resolution {
+        match (existing, self) {
+            (None, _) => Resolution::Insert,
+            (Some(_), ConflictStrategy::Skip) => Resolution::Skip,
+            (Some(_), ConflictStrategy::Overwrite) => Resolution::Update,
+            (Some(existing), ConflictStrategy::NewerWins) if incoming.timestamp() > existing => Resolution::Update,
+            (Some(_), ConflictStrategy::NewerWins) => Resolution::Skip,
+        }
+    }
+}
+
+impl ImportedTable {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn record(&mut self, resolution: &Resolution) {
+        match resolution {
+            Resolution::Insert => self.inserted += 1,
+            Resolution::Update => self.updated += 1,
+            Resolution::Skip => self.skipped += 1,
+        }
+    }
+}
+
+/// Records of an export directory
+struct ExportData {
+    pages: Vec<UnifiedPageInfo>,
+    groups: Vec<SmartGroup>,
+    page_groups: Vec<PageGroupRecord>,
+    history: Vec<HistoryEntry>,
+    archives: Vec<ContentArchive>,
+    tags: Vec<Tag>,
+    page_tags: Vec<PageTagRecord>,
+    notes: Vec<PageNote>,
+    sessions: Vec<SavedSession>,
+}
+
+impl DatabaseManager {
+    /// Merge the export in `directory` into this database
+    ///
+    /// The export must have a manifest from a compatible format and a schema
+    /// version no newer than this database's. Group memberships whose page or
+    /// group is missing are skipped. Caches are cleared afterwards.
+    pub async fn import_ndjson<P: AsRef<Path>>(&self, directory: P, strategy: ConflictStrategy) -> Result<ImportReport> {
+        let directory = directory.as_ref().to_path_buf();
+        let data = read_export(&directory)?;
+
+        let report = self
+            .connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                let report = import_data(&tx, data, strategy)?;
+                tx.commit()?;
+                Ok(report)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to import {:?}: {}", directory, e),
+                },
+            })?;
+        self.cache.clear_all().await;
+        self.embeddings.invalidate().await;
+
+        info!(
+            "Imported {:?} with {:?}: {}",
+            directory,
+            strategy,
+            report
+                .tables
+                .iter()
+                .map(|t| format!("{} +{} ~{} ={}", t.name, t.inserted, t.updated, t.skipped))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        Ok(report)
+    }
+}
+
+fn read_export(directory: &Path) -> Result<ExportData> {
+    let invalid = |details: String| WebPageManagerError::DataConsistency {
+        source: DataConsistencyError::DatabaseIntegrityViolation { details },
+    };
+
+    let manifest = ExportManifest::load(directory)?;
+    if manifest.format != EXPORT_FORMAT {
+        return Err(invalid(format!("Unknown export format {:?}", manifest.format)));
+    }
+    if manifest.format_version > EXPORT_FORMAT_VERSION {
+        return Err(invalid(format!(
+            "Export format version {} is newer than the supported {}",
+            manifest.format_version, EXPORT_FORMAT_VERSION
+        )));
+    }
+    if manifest.schema_version > schema::SCHEMA_VERSION {
+        return Err(invalid(format!(
+            "Export has schema version {}, newer than the supported {}",
+            manifest.schema_version,
+            schema::SCHEMA_VERSION
+        )));
+    }
+
+    let file_of = |name: &str| manifest.tables.iter().find(|t| t.name == name).map(|t| t.file.as_str());
+    Ok(ExportData {
+        pages: read_records(directory, file_of("pages"))?,
+        groups: read_records(directory, file_of("groups"))?,
+        page_groups: read_records(directory, file_of("page_groups"))?,
+        history: read_records(directory, file_of("history"))?,
+        archives: read_records(directory, file_of("archives"))?,
+        tags: read_records(directory, file_of("tags"))?,
+        page_tags: read_records(directory, file_of("page_tags"))?,
+        notes: read_records(directory, file_of("notes"))?,
+        sessions: read_records(directory, file_of("sessions"))?,
+    })
+}
+
+/// Parse one NDJSON file; a table missing from the manifest has no records
+fn read_records<T: DeserializeOwned>(directory: &Path, file: Option<&str>) -> Result<Vec<T>> {
+    let Some(file) = file else {
+        return Ok(Vec::new());
+    };
+    let io_error = |e: std::io::Error| WebPageManagerError::System {
+        source: SystemError::IO { source: e },
+    };
+
+    let reader = BufReader::new(File::open(directory.join(file)).map_err(io_error)?);
+    let mut records = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(io_error)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line).map_err(|e| WebPageManagerError::DataConsistency {
+            source: DataConsistencyError::DatabaseIntegrityViolation {
+                details: format!("Invalid record on line {} of {}: {}", index + 1, file, e),
+            },
+        })?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn import_data(
+    conn: &rusqlite::Connection,
+    data: ExportData,
+    strategy: ConflictStrategy,
+) -> rusqlite::Result<ImportReport> {
+    // Imported page id -> local page id, for pages matched by URL
+    let mut page_ids: HashMap<Uuid, Uuid> = HashMap::new();
+
+    let mut pages = ImportedTable::new("pages");
+    for mut page in data.pages {
+        let existing: Option<(String, i64)> = conn
+            .query_row(
+                "SELECT id, last_accessed FROM unified_pages WHERE id = ?1 OR canonical_url = ?2 \
+                 ORDER BY id = ?1 DESC LIMIT 1",
+                rusqlite::params![page.id.to_string(), canonical_url(&page.url)],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        if let Some(local_id) = existing.as_ref().and_then(|(id, _)| Uuid::parse_str(id).ok()) {
+            if local_id != page.id {
+                page_ids.insert(page.id, local_id);
+                page.id = local_id;
+            }
+        }
+
+        let resolution = strategy.resolve(existing.map(|(_, ts)| ts), page.last_accessed);
+        if !matches!(resolution, Resolution::Skip) {
+            insert_page(conn, &page)?;
+        }
+        pages.record(&resolution);
+    }
+    let local_page = |id: Uuid| page_ids.get(&id).copied().unwrap_or(id);
+
+    let mut groups = ImportedTable::new("groups");
+    for group in data.groups {
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT created_at FROM smart_groups WHERE id = ?1",
+                [group.id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let resolution = strategy.resolve(existing, group.created_at);
+        if !matches!(resolution, Resolution::Skip) {
+            insert_group(conn, &group)?;
+        }
+        groups.record(&resolution);
+    }
+
+    let mut page_groups = ImportedTable::new("page_groups");
+    for mut relation in data.page_groups {
+        if let Ok(id) = Uuid::parse_str(&relation.page_id) {
+            relation.page_id = local_page(id).to_string();
+        }
+        let related: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM unified_pages WHERE id = ?1) \
+             AND EXISTS(SELECT 1 FROM smart_groups WHERE id = ?2)",
+            [&relation.page_id, &relation.group_id],
+            |row| row.get(0),
+        )?;
+        if !related {
+            page_groups.skipped += 1;
+            continue;
+        }
+
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT added_at FROM page_group_relations WHERE page_id = ?1 AND group_id = ?2",
+                [&relation.page_id, &relation.group_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let resolution = strategy.resolve(existing, relation.added_at);
+        if !matches!(resolution, Resolution::Skip) {
+            conn.execute(
+                "INSERT OR REPLACE INTO page_group_relations (page_id, group_id, added_at, confidence_score) \
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![
+                    relation.page_id,
+                    relation.group_id,
+                    relation.added_at.timestamp(),
+                    relation.confidence_score,
+                ],
+            )?;
+        }
+        page_groups.record(&resolution);
+    }
+
+    let mut history = ImportedTable::new("history");
+    for mut entry in data.history {
+        entry.page_info.id = local_page(entry.page_info.id);
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT closed_at FROM tab_history WHERE id = ?1",
+                [entry.id.0.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let resolution = strategy.resolve(existing, entry.closed_at);
+        if !matches!(resolution, Resolution::Skip) {
+            insert_history_entry(conn, &entry)?;
+        }
+        history.record(&resolution);
+    }
+
+    let mut archives = ImportedTable::new("archives");
+    for mut archive in data.archives {
+        archive.page_id = local_page(archive.page_id);
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT archived_at FROM content_archives WHERE id = ?1",
+                [archive.id.0.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let resolution = strategy.resolve(existing, archive.archived_at);
+        if !matches!(resolution, Resolution::Skip) {
+            insert_archive(conn, &archive)?;
+        }
+        archives.record(&resolution);
+    }
+
+    // Imported tag id -> local tag id, for tags matched by name
+    let mut tag_ids: HashMap<Uuid, Uuid> = HashMap::new();
+    // Local tag id -> imported parent id, set once every tag is in
+    let mut tag_parents: Vec<(Uuid, Uuid)> = Vec::new();
+
+    let mut tags = ImportedTable::new("tags");
+    for tag in data.tags {
+        let existing: Option<(String, i64)> = conn
+            .query_row(
+                "SELECT id, created_at FROM tags WHERE id = ?1 OR name = ?2 \
+                 ORDER BY id = ?1 DESC LIMIT 1",
+                rusqlite::params![tag.id.to_string(), tag.name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let local_id = existing
+            .as_ref()
+            .and_then(|(id, _)| Uuid::parse_str(id).ok())
+            .unwrap_or(tag.id);
+        if local_id != tag.id {
+            tag_ids.insert(tag.id, local_id);
+        }
+
+        let resolution = strategy.resolve(existing.map(|(_, ts)| ts), tag.created_at);
+        match resolution {
+            Resolution::Insert => {
+                conn.execute(
+                    "INSERT OR IGNORE INTO tags (id, name, created_at) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![tag.id.to_string(), tag.name, tag.created_at.timestamp()],
+                )?;
+            }
+            // A name taken by another local tag is left alone
+            Resolution::Update => {
+                conn.execute(
+                    "UPDATE OR IGNORE tags SET name = ?1 WHERE id = ?2",
+                    [&tag.name, &local_id.to_string()],
+                )?;
+            }
+            Resolution::Skip => {}
+        }
+        if let (false, Some(parent)) = (matches!(resolution, Resolution::Skip), tag.parent_id) {
+            tag_parents.push((local_id, parent));
+        }
+        tags.record(&resolution);
+    }
+    let local_tag = |id: Uuid| tag_ids.get(&id).copied().unwrap_or(id);
+    for (id, parent) in tag_parents {
+        conn.execute(
+            "UPDATE tags SET parent_id = ?1 WHERE id = ?2 AND ?1 != ?2 AND EXISTS(SELECT 1 FROM tags WHERE id = ?1)",
+            [local_tag(parent).to_string(), id.to_string()],
+        )?;
+    }
+
+    let mut page_tags = ImportedTable::new("page_tags");
+    for mut record in data.page_tags {
+        if let Ok(id) = Uuid::parse_str(&record.page_id) {
+            record.page_id = local_page(id).to_string();
+        }
+        if let Ok(id) = Uuid::parse_str(&record.tag_id) {
+            record.tag_id = local_tag(id).to_string();
+        }
+        let related: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM unified_pages WHERE id = ?1) \
+             AND EXISTS(SELECT 1 FROM tags WHERE id = ?2)",
+            [&record.page_id, &record.tag_id],
+            |row| row.get(0),
+        )?;
+        if !related {
+            page_tags.skipped += 1;
+            continue;
+        }
+
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT added_at FROM page_tags WHERE page_id = ?1 AND tag_id = ?2",
+                [&record.page_id, &record.tag_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let resolution = strategy.resolve(existing, record.added_at);
+        if !matches!(resolution, Resolution::Skip) {
+            conn.execute(
+                "INSERT OR REPLACE INTO page_tags (page_id, tag_id, added_at) VALUES (?1, ?2, ?3)",
+                rusqlite::params![record.page_id, record.tag_id, record.added_at.timestamp()],
+            )?;
+        }
+        page_tags.record(&resolution);
+    }
+
+    let mut notes = ImportedTable::new("notes");
+    for mut note in data.notes {
+        note.page_id = local_page(note.page_id);
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT updated_at FROM page_notes WHERE id = ?1",
+                [note.id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let resolution = strategy.resolve(existing, note.updated_at);
+        if !matches!(resolution, Resolution::Skip) {
+            insert_note(conn, &note)?;
+        }
+        notes.record(&resolution);
+    }
+
+    let mut sessions = ImportedTable::new("sessions");
+    for session in data.sessions {
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT updated_at FROM sessions WHERE id = ?1",
+                [session.id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let resolution = strategy.resolve(existing, session.updated_at);
+        if !matches!(resolution, Resolution::Skip) {
+            insert_session(conn, &session)?;
+        }
+        sessions.record(&resolution);
+    }
+
+    Ok(ImportReport {
+        tables: vec![pages, groups, page_groups, history, archives, tags, page_tags, notes, sessions],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GroupRepository, PageRepository, TagRepository};
+    use chrono::Duration;
+    use crate::test_support::{temp_dir, test_page};
+
+    async fn title_of(db: &DatabaseManager, url: &str) -> String {
+        db.page_repository().get_by_url(url).await.unwrap().unwrap().title
+    }
+
+    #[tokio::test]
+    async fn test_import_merges_with_conflict_strategies() {
+        let dir = temp_dir("import");
+        let now = Utc::now();
+
+        // Export: a newer copy of a shared URL, a new page, and a group of both
+        let source = DatabaseManager::in_memory().await.unwrap();
+        let shared = UnifiedPageInfo { created_at: now, last_accessed: now, ..test_page("https://example.com/shared", "Exported") };
+        let fresh = UnifiedPageInfo { created_at: now, last_accessed: now, ..test_page("https://example.com/fresh", "Fresh") };
+        source.page_repository().save(&shared).await.unwrap();
+        source.page_repository().save(&fresh).await.unwrap();
+        let group = SmartGroup {
+            id: Uuid::new_v4(),
+            name: "Reading".to_string(),
+            description: String::new(),
+            group_type: GroupType::UserDefined,
+            pages: vec![],
+            created_at: now,
+            auto_generated: false,
+            similarity_threshold: 0.5,
+        };
+        source.group_repository().save(&group).await.unwrap();
+        source.group_repository().add_page_to_group(&shared.id, &group.id, 1.0).await.unwrap();
+        let tag = source.tag_repository().get_or_create("Reading").await.unwrap();
+        source.tag_repository().tag_page(&shared.id, &tag.id).await.unwrap();
+        source.export_ndjson(&dir).await.unwrap();
+
+        // Target: an older page with the same URL under another id
+        let target = DatabaseManager::in_memory().await.unwrap();
+        let local = UnifiedPageInfo {
+            created_at: now - Duration::days(1),
+            last_accessed: now - Duration::days(1),
+            ..test_page("https://example.com/shared", "Local")
+        };
+        target.page_repository().save(&local).await.unwrap();
+        let local_tag = target.tag_repository().get_or_create("reading").await.unwrap();
+
+        let report = target.import_ndjson(&dir, ConflictStrategy::Skip).await.unwrap();
+        let pages = report.table("pages").unwrap();
+        assert_eq!((pages.inserted, pages.updated, pages.skipped), (1, 0, 1));
+        assert_eq!(title_of(&target, "https://example.com/shared").await, "Local");
+        // The membership follows the page onto its local id
+        let members = target.group_repository().get_pages_in_group(&group.id).await.unwrap();
+        assert_eq!(members, vec![local.id]);
+        // Tags are matched by name the same way
+        assert_eq!(report.table("tags").unwrap().skipped, 1);
+        assert_eq!(target.tag_repository().get_pages_with_tag(&local_tag.id).await.unwrap(), vec![local.id]);
+
+        let report = target.import_ndjson(&dir, ConflictStrategy::NewerWins).await.unwrap();
+        assert_eq!(report.table("pages").unwrap().updated, 1);
+        assert_eq!(report.table("groups").unwrap().skipped, 1);
+        assert_eq!(title_of(&target, "https://example.com/shared").await, "Exported");
+        assert_eq!(target.page_repository().count().await.unwrap(), 2);
+
+        // A newer local edit survives newer-wins but not overwrite
+        let mut edited = target.page_repository().get_by_url("https://example.com/fresh").await.unwrap().unwrap();
+        edited.title = "Edited".to_string();
+        edited.last_accessed = now + Duration::days(1);
+        target.page_repository().save(&edited).await.unwrap();
+        target.import_ndjson(&dir, ConflictStrategy::NewerWins).await.unwrap();
+        assert_eq!(title_of(&target, "https://example.com/fresh").await, "Edited");
+        target.import_ndjson(&dir, ConflictStrategy::Overwrite).await.unwrap();
+        assert_eq!(title_of(&target, "https://example.com/fresh").await, "Fresh");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,449 @@
+//! Integrity checking and corruption recovery
+//!
+//! A check runs `PRAGMA quick_check`. When it finds problems, recovery goes
+//! from cheapest to most drastic: first the full-text indexes are rebuilt
+//! and all indexes reindexed, which fixes damage confined to derived data.
+//! If the check still fails, every readable row is salvaged into a new
+//! database file, which then replaces the contents of the damaged one.
+//!
+//! Salvaging reads each table in rowid ranges, so a damaged page costs only
+//! the rows stored on it. Rows whose parent record was lost are dropped by
+//! the foreign keys. The change log is not carried over; the salvaged rows
+//! are logged as inserts instead.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rusqlite::ErrorCode;
+use tracing::{error, info, warn};
+use web_page_manager_core::*;
+
+use crate::DatabaseManager;
+
+/// Tables copied by a salvage, parents before children
+const SALVAGE_TABLES: &[&str] = &[
+    "unified_pages",
+    "smart_groups",
+    "page_group_relations",
+    "tab_history",
+    "archive_blobs",
+    "content_archives",
+    "analysis_cache",
+    "page_embeddings",
+    "tags",
+    "page_tags",
+    "page_notes",
+    "sessions",
+    "session_windows",
+    "session_tabs",
+];
+
+/// Full-text indexes rebuilt by a repair
+const FTS_TABLES: &[&str] = &["pages_fts", "pages_word_fts", "archives_fts", "history_fts", "notes_fts"];
+
+/// Rows read per statement while salvaging
+const SALVAGE_RANGE: i64 = 256;
+
+/// Schedule and recovery options of integrity checks
+#[derive(Debug, Clone)]
+pub struct IntegrityPolicy {
+    /// Time between checks; the first runs at once
+    pub interval: Duration,
+    /// Directory salvaged databases are written to; without one, recovery
+    /// stops after rebuilding indexes
+    pub salvage_directory: Option<PathBuf>,
+}
+
+impl IntegrityPolicy {
+    /// Daily checks, salvaging into `directory` when needed
+    pub fn daily<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            interval: Duration::from_secs(24 * 60 * 60),
+            salvage_directory: Some(directory.into()),
+        }
+    }
+}
+
+/// Result of an integrity check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityOutcome {
+    /// No problems found
+    Healthy,
+    /// Problems were fixed by rebuilding indexes
+    Repaired,
+    /// The database was replaced by the rows that could be salvaged
+    Recovered,
+    /// Problems remain
+    Unrecovered,
+}
+
+/// Rows of one table handled by a salvage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableSalvage {
+    pub table: String,
+    /// Rows copied to the new database
+    pub copied: usize,
+    /// Row ids that could not be read
+    pub unreadable: usize,
+    /// Rows read but refused by a constraint, such as a lost parent row
+    pub rejected: usize,
+    /// Why the table could not be read at all, if it could not
+    pub error: Option<String>,
+}
+
+/// Rows carried over by a salvage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SalvageReport {
+    /// The salvaged database file
+    pub path: PathBuf,
+    pub tables: Vec<TableSalvage>,
+}
+
+impl SalvageReport {
+    pub fn copied(&self) -> usize {
+        self.tables.iter().map(|t| t.copied).sum()
+    }
+
+    /// Rows that were lost: unreadable, rejected, or in unreadable tables
+    pub fn lost(&self) -> usize {
+        self.tables.iter().map(|t| t.unreadable + t.rejected).sum()
+    }
+}
+
+/// What an integrity check found and did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub checked_at: DateTime<Utc>,
+    pub outcome: IntegrityOutcome,
+    /// Problems reported by the first check
+    pub problems: Vec<String>,
+    /// Problems still reported after recovery
+    pub remaining: Vec<String>,
+    /// Indexes that were rebuilt
+    pub rebuilt: bool,
+    pub salvage: Option<SalvageReport>,
+}
+
+impl IntegrityReport {
+    /// One-line description for logs and error reports
+    pub fn summary(&self) -> String {
+        let found = format!("{} problem(s) found", self.problems.len());
+        match self.outcome {
+            IntegrityOutcome::Healthy => "Database is healthy".to_string(),
+            IntegrityOutcome::Repaired => format!("{}, fixed by rebuilding indexes", found),
+            IntegrityOutcome::Recovered => {
+                let salvage = self.salvage.as_ref();
+                format!(
+                    "{}, recovered {} row(s) and lost {} by salvaging into {}",
+                    found,
+                    salvage.map_or(0, SalvageReport::copied),
+                    salvage.map_or(0, SalvageReport::lost),
+                    salvage.map_or_else(String::new, |s| s.path.display().to_string()),
+                )
+            }
+            IntegrityOutcome::Unrecovered => {
+                format!("{}, {} remaining: {}", found, self.remaining.len(), self.remaining.join("; "))
+            }
+        }
+    }
+
+    /// The report as an error, or `None` if the database was healthy
+    pub fn to_error(&self) -> Option<WebPageManagerError> {
+        (self.outcome != IntegrityOutcome::Healthy).then(|| WebPageManagerError::DataConsistency {
+            source: DataConsistencyError::DatabaseIntegrityViolation { details: self.summary() },
+        })
+    }
+}
+
+fn integrity_error(action: &str, e: impl std::fmt::Display) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Failed to {}: {}", action, e),
+        },
+    }
+}
+
+/// Copy the readable rows of `table` from the attached `damaged` database
+fn salvage_table(conn: &rusqlite::Connection, table: &str) -> TableSalvage {
+    let mut report = TableSalvage {
+        table: table.to_string(),
+        copied: 0,
+        unreadable: 0,
+        rejected: 0,
+        error: None,
+    };
+    let bounds: rusqlite::Result<(Option<i64>, Option<i64>)> = conn.query_row(
+        &format!("SELECT MIN(rowid), MAX(rowid) FROM damaged.{}", table),
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    );
+    let (low, high) = match bounds {
+        Ok((Some(low), Some(high))) => (low, high),
+        Ok(_) => return report,
+        Err(e) => {
+            report.error = Some(e.to_string());
+            return report;
+        }
+    };
+
+    let copy = format!("INSERT OR IGNORE INTO main.{0} SELECT * FROM damaged.{0} WHERE rowid BETWEEN ?1 AND ?2", table);
+    let mut start = low;
+    while start <= high {
+        let end = start.saturating_add(SALVAGE_RANGE - 1).min(high);
+        match conn.execute(&copy, [start, end]) {
+            Ok(copied) => report.copied += copied,
+            // Retry the range row by row to keep what is still readable
+            Err(_) => {
+                for rowid in start..=end {
+                    match conn.execute(&copy, [rowid, rowid]) {
+                        Ok(copied) => report.copied += copied,
+                        Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == ErrorCode::ConstraintViolation => {
+                            report.rejected += 1
+                        }
+                        Err(_) => report.unreadable += 1,
+                    }
+                }
+            }
+        }
+        if end == high {
+            break;
+        }
+        start = end + 1;
+    }
+    report
+}
+
+impl DatabaseManager {
+    /// Run `PRAGMA quick_check`, returning the problems it reports
+    pub async fn quick_check(&self) -> Result<Vec<String>> {
+        self.connection
+            .call(|conn| {
+                let messages: rusqlite::Result<Vec<String>> = conn
+                    .prepare("PRAGMA quick_check")
+                    .and_then(|mut stmt| stmt.query_map([], |row| row.get::<_, String>(0))?.collect());
+                match messages {
+                    Ok(messages) => Ok(messages.into_iter().filter(|m| m != "ok").collect()),
+                    // Damage the check cannot read past is itself the finding
+                    Err(e) if e.sqlite_error_code() == Some(rusqlite::ErrorCode::DatabaseCorrupt) => {
+                        Ok(vec![e.to_string()])
+                    }
+                    Err(e) => Err(e.into()),
+                }
+            })
+            .await
+            .map_err(|e| integrity_error("check database integrity", e))
+    }
+
+    /// Rebuild the full-text indexes and reindex all tables
+    pub async fn rebuild_indexes(&self) -> Result<()> {
+        self.connection
+            .call(|conn| {
+                for table in FTS_TABLES {
+                    conn.execute(&format!("INSERT INTO {0}({0}) VALUES('rebuild')", table), [])?;
+                }
+                conn.execute_batch("REINDEX;")?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| integrity_error("rebuild indexes", e))?;
+
+        info!("Database indexes rebuilt");
+        Ok(())
+    }
+
+    /// Copy every readable row into a new database at `path`
+    ///
+    /// The new file gets the current schema; `path` must not exist yet.
+    /// In-memory databases cannot be salvaged.
+    pub async fn salvage_to<P: AsRef<Path>>(&self, path: P) -> Result<SalvageReport> {
+        let path = path.as_ref().to_path_buf();
+        if path.exists() {
+            return Err(integrity_error("salvage database", format!("{:?} already exists", path)));
+        }
+        let source = self
+            .connection
+            .call(|conn| Ok(conn.path().filter(|p| !p.is_empty()).map(str::to_string)))
+            .await
+            .map_err(|e| integrity_error("salvage database", e))?
+            .ok_or_else(|| integrity_error("salvage database", "in-memory databases cannot be salvaged"))?;
+
+        let target = DatabaseManager::new(&path).await?;
+        let tables = target
+            .connection
+            .call(move |conn| {
+                conn.execute("ATTACH DATABASE ?1 AS damaged", [source])?;
+                let tables: Vec<TableSalvage> = SALVAGE_TABLES.iter().map(|table| salvage_table(conn, table)).collect();
+                conn.execute_batch("DETACH DATABASE damaged; PRAGMA wal_checkpoint(TRUNCATE);")?;
+                Ok(tables)
+            })
+            .await
+            .map_err(|e| integrity_error("salvage database", e))?;
+
+        let report = SalvageReport { path, tables };
+        info!(
+            "Salvaged {} rows into {:?}, {} lost",
+            report.copied(),
+            report.path,
+            report.lost()
+        );
+        Ok(report)
+    }
+
+    /// Check the database and recover from any corruption found
+    pub async fn check_integrity(&self, policy: &IntegrityPolicy) -> Result<IntegrityReport> {
+        let problems = self.quick_check().await?;
+        let mut report = IntegrityReport {
+            checked_at: Utc::now(),
+            outcome: IntegrityOutcome::Healthy,
+            problems,
+            remaining: vec![],
+            rebuilt: false,
+            salvage: None,
+        };
+        if report.problems.is_empty() {
+            return Ok(report);
+        }
+        warn!("Integrity check found {} problem(s)", report.problems.len());
+
+        // Rebuilding may itself fail on a damaged table; salvaging still can help
+        match self.rebuild_indexes().await {
+            Ok(()) => report.rebuilt = true,
+            Err(e) => warn!("Index rebuild failed: {}", e),
+        }
+        report.remaining = self.quick_check().await.unwrap_or_else(|e| vec![e.to_string()]);
+        if report.remaining.is_empty() {
+            report.outcome = IntegrityOutcome::Repaired;
+            return Ok(report);
+        }
+
+        report.outcome = IntegrityOutcome::Unrecovered;
+        let Some(directory) = &policy.salvage_directory else {
+            return Ok(report);
+        };
+        let path = directory.join(format!("salvage-{}.db", Utc::now().format("%Y%m%d-%H%M%S")));
+        match self.salvage_to(&path).await {
+            Ok(salvage) => {
+                report.salvage = Some(salvage);
+                match self.restore_from(&path).await {
+                    Ok(()) => {
+                        report.remaining = self.quick_check().await?;
+                        if report.remaining.is_empty() {
+                            report.outcome = IntegrityOutcome::Recovered;
+                        }
+                    }
+                    Err(e) => report.remaining.push(format!("Restoring the salvaged database failed: {}", e)),
+                }
+            }
+            Err(e) => report.remaining.push(e.to_string()),
+        }
+        Ok(report)
+    }
+
+    /// Run `check_integrity` now and then once per interval until the
+    /// returned task is aborted, passing every report that is not healthy
+    /// to `on_problem`
+    pub fn spawn_integrity_checks<F>(self: Arc<Self>, policy: IntegrityPolicy, on_problem: F) -> tokio::task::JoinHandle<()>
+    where
+        F: Fn(IntegrityReport) + Send + 'static,
+    {
+        let period = policy.interval.max(Duration::from_secs(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                match self.check_integrity(&policy).await {
+                    Ok(report) if report.outcome == IntegrityOutcome::Healthy => {}
+                    Ok(report) => {
+                        error!("Database integrity: {}", report.summary());
+                        on_problem(report);
+                    }
+                    Err(e) => warn!("Integrity check failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PageRepository;
+    use crate::test_support::{temp_dir, test_page};
+
+    /// Page padded so a few hundred of them span many database pages
+    fn checked_page(n: usize) -> UnifiedPageInfo {
+        test_page(&format!("https://example.com/{}", n), &format!("Checked page {} {}", n, "padding ".repeat(40)))
+    }
+
+    #[tokio::test]
+    async fn test_damaged_fts_index_is_repaired() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        db.page_repository().save(&checked_page(1)).await.unwrap();
+        let policy = IntegrityPolicy { interval: Duration::from_secs(60), salvage_directory: None };
+        assert_eq!(db.check_integrity(&policy).await.unwrap().outcome, IntegrityOutcome::Healthy);
+
+        db.connection()
+            .call(|conn| Ok(conn.execute_batch("DELETE FROM pages_fts_idx; DELETE FROM pages_fts_data WHERE id > 10;")?))
+            .await
+            .unwrap();
+        let report = db.check_integrity(&policy).await.unwrap();
+        assert_eq!(report.outcome, IntegrityOutcome::Repaired);
+        assert!(!report.problems.is_empty());
+        assert!(report.rebuilt);
+        assert!(report.to_error().is_some());
+        assert_eq!(db.page_repository().search("checked").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_salvage_copies_readable_rows() {
+        let dir = temp_dir("integrity_salvage");
+        let db = DatabaseManager::new(dir.join("pages.db")).await.unwrap();
+        let pages: Vec<UnifiedPageInfo> = (0..50).map(checked_page).collect();
+        db.page_repository().save_batch(&pages).await.unwrap();
+
+        assert!(DatabaseManager::in_memory().await.unwrap().salvage_to(dir.join("x.db")).await.is_err());
+        let report = db.salvage_to(dir.join("salvaged.db")).await.unwrap();
+        assert!(db.salvage_to(dir.join("salvaged.db")).await.is_err());
+        assert_eq!(report.copied(), 50);
+        assert_eq!(report.lost(), 0);
+
+        let salvaged = DatabaseManager::new(&report.path).await.unwrap();
+        assert_eq!(salvaged.page_repository().count().await.unwrap(), 50);
+        assert_eq!(salvaged.page_repository().search("checked").await.unwrap().len(), 50);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_file_is_recovered() {
+        let dir = temp_dir("integrity_recover");
+        let path = dir.join("pages.db");
+        {
+            let db = DatabaseManager::new(&path).await.unwrap();
+            let pages: Vec<UnifiedPageInfo> = (0..400).map(checked_page).collect();
+            db.page_repository().save_batch(&pages).await.unwrap();
+            db.connection()
+                .call(|conn| Ok(conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?))
+                .await
+                .unwrap();
+        }
+
+        // Overwrite one page in the middle of the file with garbage
+        let mut bytes = std::fs::read(&path).unwrap();
+        let page = bytes.len() / 4096 / 2;
+        bytes[page * 4096..(page + 1) * 4096].fill(0xA5);
+        std::fs::write(&path, bytes).unwrap();
+
+        let db = DatabaseManager::new(&path).await.unwrap();
+        let report = db.check_integrity(&IntegrityPolicy::daily(dir.join("salvage"))).await.unwrap();
+        assert_ne!(report.outcome, IntegrityOutcome::Healthy);
+        assert_ne!(report.outcome, IntegrityOutcome::Unrecovered, "{}", report.summary());
+        assert!(db.quick_check().await.unwrap().is_empty());
+        let count = db.page_repository().count().await.unwrap();
+        assert!(count > 0 && count <= 400);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
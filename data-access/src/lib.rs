@@ -5,22 +5,114 @@
 //!
 //! # Features
 //! - SQLite database with FTS5 full-text search
-//! - Schema migrations support
-//! - LRU caching with TTL
+//! - Schema migrations, reversible with dry runs
+//! - LRU caching with TTL and a memory budget, written through for pages, groups and history
 //! - Repository pattern for data access
-//! - Unified search across pages, history, and archives
+//! - Pages unique by canonical URL, ignoring tracking parameters
+//! - Keyset-paginated listings and batched result streams
+//! - Unified search across pages, history, archives, and notes
+//! - Saved tab sessions with ordered windows and tabs
+//! - Search history for autocompletion and saved searches with filters
+//! - Snoozed tabs waiting to reopen
+//! - User pins and exclusions of pages in smart groups
+//! - Archived smart groups and manual page order within groups
+//! - Page embeddings with approximate nearest-neighbor search
+//! - WAL mode with a pool of read-only connections for searches
+//! - zstd-compressed, deduplicated storage of archived page content
+//! - Deduplicated favicon and thumbnail storage for pages and history
+//! - Content-addressed files for MHTML, screenshot and PDF snapshots of archives
+//! - Reader versions of archived pages for offline reading
+//! - Log of remote tab operations and migrations for undo across restarts
+//! - Trash for deleted pages and history with scheduled purging
+//! - Incremental vacuuming and planner statistics refreshed off-peak
+//! - Retention rules for history, archives, trash and the change log, with previews
+//! - Change log of every write for sync and undo
+//! - Usage analytics with daily rollups for the statistics dashboard
+//! - Online backups with scheduled retention
+//! - Scheduled integrity checks with index rebuilds and row salvage
+//! - Full export to newline-delimited JSON and merging imports
+//! - Diagnostics bundles for bug reports, with anonymized URLs
+//! - Isolated database profiles with hot switching
+//! - Optional SQLCipher encryption at rest (`encryption` feature)
+//! - Storage backend trait with an optional shared PostgreSQL library (`postgres` feature)
 
 pub mod schema;
 pub mod repository;
 pub mod cache;
 pub mod batch;
+pub mod compression;
+pub mod media;
+pub mod snapshots;
+pub mod canonical;
+pub mod hnsw;
+pub mod embeddings;
+pub mod pool;
+pub mod backup;
+pub mod integrity;
+pub mod trash;
+pub mod maintenance;
+pub mod retention;
+pub mod changelog;
+pub mod analytics;
+pub mod searches;
+pub mod snoozes;
+pub mod group_overrides;
+pub mod group_state;
+pub mod reader_views;
+pub mod operation_log;
+pub mod export;
+pub mod diagnostics;
+pub mod import;
+pub mod profiles;
+pub mod backend;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(test)]
+mod test_support;
 
 pub use repository::*;
 pub use cache::*;
 pub use batch::*;
+pub use canonical::{canonical_url, is_tracking_param};
+pub use embeddings::{EmbeddingIndex, EmbeddingRepository, SimilarPage, SqliteEmbeddingRepository};
+pub use media::{MediaAsset, MediaKind, MediaRepository, MediaUsage, SqliteMediaRepository};
+pub use snapshots::{
+    Snapshot, SnapshotCollection, SnapshotKind, SnapshotRepository, SnapshotStore, SnapshotUsage, SqliteSnapshotRepository,
+};
+pub use pool::ReadPool;
+pub use backup::BackupPolicy;
+pub use integrity::{IntegrityOutcome, IntegrityPolicy, IntegrityReport, SalvageReport, TableSalvage};
+pub use trash::{TrashPolicy, TrashPurge};
+pub use maintenance::{MaintenancePolicy, MaintenanceReport};
+pub use retention::{RetentionReport, RetentionRules};
+pub use changelog::{ChangeEntity, ChangeLogRepository, ChangeOp, ChangeRecord, SqliteChangeLogRepository};
+pub use analytics::{
+    AnalyticsRepository, DailyUsage, DomainCount, SearchCount, SqliteAnalyticsRepository, TabLifetimeStats,
+};
+pub use searches::{RecentSearch, SavedSearch, SearchRecordRepository, SqliteSearchRecordRepository};
+pub use snoozes::{SnoozeRepository, SnoozedTab, SqliteSnoozeRepository};
+pub use group_overrides::{
+    GroupOverride, GroupOverrideKind, GroupOverrideRepository, SqliteGroupOverrideRepository,
+};
+pub use group_state::{ArchivedGroup, GroupStateRepository, SqliteGroupStateRepository};
+pub use reader_views::{ReaderView, ReaderViewRepository, SqliteReaderViewRepository};
+pub use operation_log::{
+    OperationLogEntry, OperationLogKind, OperationLogRepository, SqliteOperationLogRepository,
+};
+pub use export::ExportManifest;
+pub use diagnostics::{anonymize_url, DiagnosticsBundle};
+pub use import::{ConflictStrategy, ImportReport};
+pub use profiles::{ActiveProfile, ProfileInfo, ProfileManager};
+pub use backend::StorageBackend;
+#[cfg(feature = "encryption")]
+pub use encryption::{DatabaseKey, KeyStore, OsKeyStore};
+#[cfg(feature = "postgres")]
+pub use postgres::{PostgresBackend, PostgresGroupRepository, PostgresHistoryRepository, PostgresPageRepository};
 
 use web_page_manager_core::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tokio_rusqlite::Connection;
 use std::sync::Arc;
 use tracing::{info, warn, debug};
@@ -28,7 +120,10 @@ use tracing::{info, warn, debug};
 /// Database manager for handling SQLite connections and migrations
 pub struct DatabaseManager {
     connection: Arc<Connection>,
+    /// Read-only connections for list and search queries
+    readers: Arc<ReadPool>,
     cache: Arc<DataCache>,
+    embeddings: Arc<EmbeddingIndex>,
 }
 
 impl DatabaseManager {
@@ -39,8 +134,59 @@ impl DatabaseManager {
 
     /// Create a new database manager with custom cache configuration
     pub async fn with_cache_config<P: AsRef<Path>>(db_path: P, cache_config: CacheConfig) -> Result<Self> {
-        let path = db_path.as_ref().to_path_buf();
-        
+        let path = db_path.as_ref();
+        let connection = Self::open_file(path).await?;
+        Self::initialize(connection, cache_config, path, Arc::new(|_| Ok(()))).await
+    }
+
+    /// Open or create a database encrypted with `key`
+    ///
+    /// A new file is encrypted from the start; an existing one must have been
+    /// created with the same key. Plaintext databases are converted with
+    /// `encrypt_to`.
+    #[cfg(feature = "encryption")]
+    pub async fn encrypted<P: AsRef<Path>>(db_path: P, key: DatabaseKey) -> Result<Self> {
+        Self::encrypted_with_cache_config(db_path, CacheConfig::default(), key).await
+    }
+
+    /// Open or create an encrypted database with custom cache configuration
+    #[cfg(feature = "encryption")]
+    pub async fn encrypted_with_cache_config<P: AsRef<Path>>(
+        db_path: P,
+        cache_config: CacheConfig,
+        key: DatabaseKey,
+    ) -> Result<Self> {
+        let path = db_path.as_ref();
+        let connection = Self::open_file(path).await?;
+        let reader_setup = Self::key_setup(&key);
+        // The key must be set before anything reads the file
+        connection
+            .call(move |conn| {
+                key.apply(conn, "key")?;
+                encryption::verify_key(conn)?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to unlock database at {:?} (wrong key?): {}", path, e),
+                },
+            })?;
+        Self::initialize(connection, cache_config, path, reader_setup).await
+    }
+
+    /// Read connection setup that unlocks the database with `key`
+    #[cfg(feature = "encryption")]
+    fn key_setup(key: &DatabaseKey) -> pool::ConnectionSetup {
+        let key = key.clone();
+        Arc::new(move |conn| {
+            key.apply(conn, "key")?;
+            encryption::verify_key(conn)
+        })
+    }
+
+    /// Open a database file, creating its directory if needed
+    async fn open_file(path: &Path) -> Result<Connection> {
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             if !parent.exists() {
@@ -50,17 +196,28 @@ impl DatabaseManager {
             }
         }
         
-        let connection = Connection::open(&path)
+        Connection::open(path)
             .await
             .map_err(|e| WebPageManagerError::System {
                 source: SystemError::Configuration {
                     details: format!("Failed to open database at {:?}: {}", path, e),
                 },
-            })?;
+            })
+    }
 
-        let manager = Self {
+    /// Optimize and migrate a newly opened file database, then open its
+    /// read connections
+    async fn initialize(
+        connection: Connection,
+        cache_config: CacheConfig,
+        path: &Path,
+        reader_setup: pool::ConnectionSetup,
+    ) -> Result<Self> {
+        let mut manager = Self {
             connection: Arc::new(connection),
+            readers: Arc::new(ReadPool::empty()),
             cache: Arc::new(DataCache::new(cache_config)),
+            embeddings: Arc::new(EmbeddingIndex::default()),
         };
 
         // Apply performance optimizations
@@ -69,6 +226,9 @@ impl DatabaseManager {
         // Run migrations
         manager.run_migrations().await?;
 
+        // Readers are opened after migrations so they see the final schema
+        manager.readers = Arc::new(ReadPool::open(path, pool::DEFAULT_READ_CONNECTIONS, reader_setup).await?);
+
         info!("Database initialized at {:?}", path);
 
         Ok(manager)
@@ -91,7 +251,9 @@ impl DatabaseManager {
         
         let manager = Self {
             connection: Arc::new(connection),
+            readers: Arc::new(ReadPool::empty()),
             cache: Arc::new(DataCache::new(cache_config)),
+            embeddings: Arc::new(EmbeddingIndex::default()),
         };
 
         // Apply performance optimizations
@@ -108,9 +270,16 @@ impl DatabaseManager {
     async fn optimize_connection(&self) -> Result<()> {
         self.connection
             .call(|conn| {
+                // Keep freed pages for incremental vacuuming; only takes
+                // effect before the first table is created
+                conn.execute_batch("PRAGMA auto_vacuum = INCREMENTAL;")?;
+
                 // Use WAL mode for better concurrent read/write performance
                 conn.execute_batch("PRAGMA journal_mode = WAL;")?;
 
+                // Wait for locks held by other processes instead of failing at once
+                conn.busy_timeout(pool::BUSY_TIMEOUT)?;
+
                 // Increase cache size to 64MB for better performance
                 conn.execute_batch("PRAGMA cache_size = -64000;")?;
 
@@ -126,6 +295,10 @@ impl DatabaseManager {
                 // Optimize page size
                 conn.execute_batch("PRAGMA page_size = 4096;")?;
 
+                // Functions called by the change log triggers and migrations
+                changelog::register_functions(conn)?;
+                canonical::register_functions(conn)?;
+
                 Ok(())
             })
             .await
@@ -226,11 +399,84 @@ impl DatabaseManager {
         Ok(())
     }
 
+    /// Migrate the database up or down to schema `version`
+    ///
+    /// Returns the steps in the order they run. With `dry_run` nothing is
+    /// changed; the steps are only reported. The whole plan is checked before
+    /// the first step runs, so a migration that cannot be reverted fails the
+    /// call without touching the database. Each reverted migration runs in its
+    /// own transaction. Downgrading is meant for handing the file to an older
+    /// build; this manager expects the current schema and should be dropped
+    /// after migrating down.
+    pub async fn migrate_to(&self, version: u32, dry_run: bool) -> Result<Vec<schema::MigrationStep>> {
+        let current_version = self.get_schema_version().await?;
+        let steps = schema::migration_plan(current_version, version).map_err(|details| WebPageManagerError::System {
+            source: SystemError::Configuration { details },
+        })?;
+        if dry_run {
+            return Ok(steps);
+        }
+
+        for step in &steps {
+            let Some(migration) = schema::get_migration(step.version) else {
+                continue;
+            };
+            match step.direction {
+                schema::MigrationDirection::Up => self.apply_migration(migration).await?,
+                schema::MigrationDirection::Down => self.revert_migration(migration).await?,
+            }
+            info!("{:?} migration {}: {}", step.direction, step.version, step.description);
+        }
+
+        if !steps.is_empty() {
+            self.cache.clear_all().await;
+            self.embeddings.invalidate().await;
+        }
+        Ok(steps)
+    }
+
+    /// Revert a single migration
+    async fn revert_migration(&self, migration: &'static schema::Migration) -> Result<()> {
+        let version = migration.version;
+        let down = migration.down.ok_or_else(|| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Migration {} cannot be reverted", version),
+            },
+        })?;
+
+        self.connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute_batch(down)?;
+                tx.execute("DELETE FROM schema_migrations WHERE version = ?1", [version])?;
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to revert migration {}: {}", version, e),
+                },
+            })
+    }
+
     /// Get the connection for repository operations
     pub fn connection(&self) -> Arc<Connection> {
         Arc::clone(&self.connection)
     }
 
+    /// Get a read-only connection for list and search queries
+    ///
+    /// Falls back to the main connection for in-memory databases.
+    pub fn read_connection(&self) -> Arc<Connection> {
+        self.readers.get().unwrap_or_else(|| self.connection())
+    }
+
+    /// Get the pool of read-only connections
+    pub fn read_pool(&self) -> Arc<ReadPool> {
+        Arc::clone(&self.readers)
+    }
+
     /// Get the cache instance
     pub fn cache(&self) -> Arc<DataCache> {
         Arc::clone(&self.cache)
@@ -238,7 +484,7 @@ impl DatabaseManager {
 
     /// Create a page repository
     pub fn page_repository(&self) -> SqlitePageRepository {
-        SqlitePageRepository::new(self.connection())
+        SqlitePageRepository::with_reader(self.connection(), self.read_connection())
     }
 
     /// Create a group repository
@@ -248,27 +494,102 @@ impl DatabaseManager {
 
     /// Create a history repository
     pub fn history_repository(&self) -> SqliteHistoryRepository {
-        SqliteHistoryRepository::new(self.connection())
+        SqliteHistoryRepository::with_reader(self.connection(), self.read_connection())
     }
 
     /// Create an archive repository
     pub fn archive_repository(&self) -> SqliteArchiveRepository {
-        SqliteArchiveRepository::new(self.connection())
+        SqliteArchiveRepository::with_reader(self.connection(), self.read_connection())
+    }
+
+    /// Create an AI analysis cache repository
+    pub fn analysis_cache_repository(&self) -> SqliteAnalysisCacheRepository {
+        SqliteAnalysisCacheRepository::new(self.connection())
+    }
+
+    /// Create a tag repository
+    pub fn tag_repository(&self) -> SqliteTagRepository {
+        SqliteTagRepository::new(self.connection())
+    }
+
+    /// Create a page note repository
+    pub fn note_repository(&self) -> SqliteNoteRepository {
+        SqliteNoteRepository::new(self.connection())
+    }
+
+    /// Create a saved session repository
+    pub fn session_repository(&self) -> SqliteSessionRepository {
+        SqliteSessionRepository::new(self.connection())
+    }
+
+    /// Create a page embedding repository
+    pub fn embedding_repository(&self) -> SqliteEmbeddingRepository {
+        SqliteEmbeddingRepository::new(self.connection(), Arc::clone(&self.embeddings))
+    }
+
+    /// Create a change log repository
+    pub fn change_log_repository(&self) -> SqliteChangeLogRepository {
+        SqliteChangeLogRepository::new(self.connection())
+    }
+
+    /// Create a search history and saved search repository
+    pub fn search_record_repository(&self) -> SqliteSearchRecordRepository {
+        SqliteSearchRecordRepository::new(self.connection())
+    }
+
+    /// Create a snoozed tab repository
+    pub fn snooze_repository(&self) -> SqliteSnoozeRepository {
+        SqliteSnoozeRepository::new(self.connection())
+    }
+
+    /// Create a smart group override repository
+    pub fn group_override_repository(&self) -> SqliteGroupOverrideRepository {
+        SqliteGroupOverrideRepository::new(self.connection())
+    }
+
+    /// Create a smart group archive and page order repository
+    pub fn group_state_repository(&self) -> SqliteGroupStateRepository {
+        SqliteGroupStateRepository::new(self.connection())
+    }
+
+    /// Create a reader view repository
+    pub fn reader_view_repository(&self) -> SqliteReaderViewRepository {
+        SqliteReaderViewRepository::new(self.connection())
+    }
+
+    /// Create a repository for the log of remote tab operations
+    pub fn operation_log_repository(&self) -> SqliteOperationLogRepository {
+        SqliteOperationLogRepository::new(self.connection())
+    }
+
+    /// Create a favicon and thumbnail repository
+    pub fn media_repository(&self) -> SqliteMediaRepository {
+        SqliteMediaRepository::new(self.connection())
+    }
+
+    /// Create a snapshot repository keeping its files under `root`
+    pub fn snapshot_repository<P: Into<PathBuf>>(&self, root: P) -> SqliteSnapshotRepository {
+        SqliteSnapshotRepository::new(self.connection(), SnapshotStore::new(root))
+    }
+
+    /// Create a usage analytics repository
+    pub fn analytics_repository(&self) -> SqliteAnalyticsRepository {
+        SqliteAnalyticsRepository::new(self.connection())
     }
 
     /// Create a unified search repository
     pub fn unified_search_repository(&self) -> UnifiedSearchRepository {
-        UnifiedSearchRepository::new(self.connection())
+        UnifiedSearchRepository::new(self.read_connection())
     }
 
     /// Get database statistics
     pub async fn stats(&self) -> Result<DatabaseStats> {
         let connection = self.connection();
         
-        let (page_count, group_count, history_count, archive_count, db_size) = connection
+        let (page_count, group_count, history_count, archive_count, db_size, page_total, free_pages) = connection
             .call(|conn| {
                 let page_count: i64 = conn.query_row(
-                    "SELECT COUNT(*) FROM unified_pages",
+                    "SELECT COUNT(*) FROM unified_pages WHERE deleted_at IS NULL",
                     [],
                     |row| row.get(0),
                 )?;
@@ -280,7 +601,7 @@ impl DatabaseManager {
                 )?;
                 
                 let history_count: i64 = conn.query_row(
-                    "SELECT COUNT(*) FROM tab_history",
+                    "SELECT COUNT(*) FROM tab_history WHERE deleted_at IS NULL",
                     [],
                     |row| row.get(0),
                 )?;
@@ -291,13 +612,14 @@ impl DatabaseManager {
                     |row| row.get(0),
                 )?;
                 
-                let db_size: i64 = conn.query_row(
-                    "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+                let (db_size, page_total, free_pages): (i64, i64, i64) = conn.query_row(
+                    "SELECT page_count * page_size, page_count, freelist_count \
+                     FROM pragma_page_count(), pragma_page_size(), pragma_freelist_count()",
                     [],
-                    |row| row.get(0),
-                ).unwrap_or(0);
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                ).unwrap_or((0, 0, 0));
                 
-                Ok((page_count, group_count, history_count, archive_count, db_size))
+                Ok((page_count, group_count, history_count, archive_count, db_size, page_total, free_pages))
             })
             .await
             .map_err(|e| WebPageManagerError::System {
@@ -314,15 +636,20 @@ impl DatabaseManager {
             history_count: history_count as usize,
             archive_count: archive_count as usize,
             database_size_bytes: db_size as u64,
+            free_pages: free_pages as u64,
+            fragmentation: if page_total > 0 { free_pages as f64 / page_total as f64 } else { 0.0 },
             cache_stats,
         })
     }
 
-    /// Optimize database (vacuum and analyze)
+    /// Optimize database (full vacuum and analyze)
+    ///
+    /// Rewrites the whole file; `spawn_maintenance` reclaims space
+    /// incrementally instead.
     pub async fn optimize(&self) -> Result<()> {
         self.connection
             .call(|conn| {
-                conn.execute_batch("VACUUM; ANALYZE;")?;
+                conn.execute_batch("PRAGMA auto_vacuum = INCREMENTAL; VACUUM; ANALYZE;")?;
                 Ok(())
             })
             .await
@@ -342,6 +669,55 @@ impl DatabaseManager {
         debug!("Cache cleared");
     }
 
+    /// Re-encrypt an encrypted database with a new key
+    #[cfg(feature = "encryption")]
+    pub async fn rekey(&self, key: &DatabaseKey) -> Result<()> {
+        let new_key = key.clone();
+        self.connection
+            .call(move |conn| {
+                new_key.apply(conn, "rekey")?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to change database key: {}", e),
+                },
+            })?;
+        // Readers still hold the old key
+        self.readers.reopen(Self::key_setup(key)).await?;
+        
+        info!("Database key changed");
+        Ok(())
+    }
+
+    /// Write an encrypted copy of this plaintext database to `dest`
+    ///
+    /// Open the copy with `DatabaseManager::encrypted`, then delete the
+    /// plaintext file.
+    #[cfg(feature = "encryption")]
+    pub async fn encrypt_to<P: AsRef<Path>>(&self, dest: P, key: &DatabaseKey) -> Result<()> {
+        let dest = dest.as_ref().to_string_lossy().into_owned();
+        let key_clause = key.attach_clause();
+        self.connection
+            .call(move |conn| {
+                conn.execute(&format!("ATTACH DATABASE ?1 AS encrypted KEY {}", key_clause), [&dest])?;
+                let exported = conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()));
+                conn.execute_batch("DETACH DATABASE encrypted;")?;
+                exported?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to export encrypted database: {}", e),
+                },
+            })?;
+        
+        info!("Encrypted copy of database written");
+        Ok(())
+    }
+
     /// Create batch operations handler
     pub fn batch_operations(&self) -> BatchPageOperations {
         BatchPageOperations::new(self.connection())
@@ -356,6 +732,10 @@ pub struct DatabaseStats {
     pub history_count: usize,
     pub archive_count: usize,
     pub database_size_bytes: u64,
+    /// Unused pages in the file, returned to the filesystem by maintenance
+    pub free_pages: u64,
+    /// Share of the file's pages that are unused
+    pub fragmentation: f64,
     pub cache_stats: CacheStats,
 }
 
@@ -375,8 +755,25 @@ impl CachedPageRepository {
 
     /// Save a page and update cache
     pub async fn save(&self, page: &UnifiedPageInfo) -> Result<()> {
-        self.inner.save(page).await?;
-        self.cache.cache_page(page).await;
+        self.save_batch(std::slice::from_ref(page)).await
+    }
+
+    /// Save many pages in one transaction and cache them
+    ///
+    /// Pages merged into a saved page with the same canonical URL are not
+    /// cached; the merged page is reloaded on its next lookup.
+    pub async fn save_batch(&self, pages: &[UnifiedPageInfo]) -> Result<()> {
+        if pages.is_empty() {
+            return Ok(());
+        }
+        let ids = self.inner.save_resolving(pages).await?;
+        for (page, id) in pages.iter().zip(ids) {
+            if id == page.id {
+                self.cache.cache_page(page).await;
+            } else {
+                self.cache.invalidate_page(&id).await;
+            }
+        }
         Ok(())
     }
 
@@ -399,28 +796,52 @@ impl CachedPageRepository {
     }
 
     /// Get a page by URL, checking cache first
+    ///
+    /// URLs not found are remembered for `CacheConfig::missing_ttl`, and
+    /// concurrent misses for one URL share a single query.
     pub async fn get_by_url(&self, url: &str) -> Result<Option<UnifiedPageInfo>> {
-        // Check cache for URL -> ID mapping
-        if let Some(id) = self.cache.get_page_id_by_url(url).await {
-            if let Some(page) = self.cache.get_page(&id).await {
-                return Ok(Some(page));
-            }
+        if let Some(cached) = self.cached_by_url(url).await {
+            return Ok(cached);
+        }
+
+        let _flight = self.cache.begin_url_lookup(url).await;
+        // Another caller may have looked the URL up while this one waited
+        if let Some(cached) = self.cached_by_url(url).await {
+            return Ok(cached);
         }
         
         // Fetch from database
         let page = self.inner.get_by_url(url).await?;
         
-        // Cache the result
-        if let Some(ref p) = page {
-            self.cache.cache_page(p).await;
+        // Cache the result, including its absence
+        match page {
+            Some(ref p) => self.cache.cache_page(p).await,
+            None => self.cache.cache_missing_url(url).await,
         }
         
         Ok(page)
     }
 
+    /// Cached lookup of a URL: `Some(None)` if it is known to be missing,
+    /// `None` if the database has to be asked
+    async fn cached_by_url(&self, url: &str) -> Option<Option<UnifiedPageInfo>> {
+        // Check cache for URL -> ID mapping
+        if let Some(id) = self.cache.get_page_id_by_url(url).await {
+            if let Some(page) = self.cache.get_page(&id).await {
+                return Some(Some(page));
+            }
+        }
+        if self.cache.is_url_missing(url).await {
+            return Some(None);
+        }
+        None
+    }
+
     /// Delete a page and invalidate cache
     pub async fn delete(&self, id: &Uuid) -> Result<()> {
         self.cache.invalidate_page(id).await;
+        // Trashed pages drop out of their groups' page lists
+        self.cache.invalidate_memberships().await;
         self.inner.delete(id).await
     }
 
@@ -429,10 +850,243 @@ impl CachedPageRepository {
         self.inner.search(query).await
     }
 
+    /// Search pages with relevance scores and highlights (not cached)
+    pub async fn search_ranked(&self, query: &str, limit: usize) -> Result<Vec<PageSearchHit>> {
+        self.inner.search_ranked(query, limit).await
+    }
+
     /// Get all pages (not cached)
     pub async fn get_all(&self) -> Result<Vec<UnifiedPageInfo>> {
         self.inner.get_all().await
     }
+
+    /// List one page of pages (not cached)
+    pub async fn list(&self, query: &PageQuery) -> Result<Paged<UnifiedPageInfo>> {
+        self.inner.list(query).await
+    }
+
+    /// Stream every page in batches (not cached)
+    pub fn stream_all(&self) -> futures::stream::BoxStream<'static, Result<UnifiedPageInfo>> {
+        self.inner.stream_all()
+    }
+}
+
+/// Group repository with write-through caching of groups and memberships
+pub struct CachedGroupRepository {
+    inner: SqliteGroupRepository,
+    cache: Arc<DataCache>,
+}
+
+impl CachedGroupRepository {
+    pub fn new(connection: Arc<Connection>, cache: Arc<DataCache>) -> Self {
+        Self {
+            inner: SqliteGroupRepository::new(connection),
+            cache,
+        }
+    }
+
+    /// Cache a group as the database returns it, without its pages
+    async fn cache_saved(&self, group: &SmartGroup) {
+        let stored = SmartGroup {
+            pages: vec![],
+            ..group.clone()
+        };
+        self.cache.cache_group(&stored).await;
+    }
+
+    /// Save a group and update cache
+    ///
+    /// Saving replaces the group row, which drops its memberships, so the
+    /// cached memberships are invalidated as well.
+    pub async fn save(&self, group: &SmartGroup) -> Result<()> {
+        self.inner.save(group).await?;
+        self.cache_saved(group).await;
+        self.cache.invalidate_memberships().await;
+        Ok(())
+    }
+
+    /// Save many groups in one transaction and cache them
+    pub async fn save_batch(&self, groups: &[SmartGroup]) -> Result<()> {
+        self.inner.save_batch(groups).await?;
+        for group in groups {
+            self.cache_saved(group).await;
+        }
+        self.cache.invalidate_memberships().await;
+        Ok(())
+    }
+
+    /// Get a group by ID, checking cache first
+    pub async fn get_by_id(&self, id: &Uuid) -> Result<Option<SmartGroup>> {
+        if let Some(group) = self.cache.get_group(id).await {
+            return Ok(Some(group));
+        }
+
+        let group = self.inner.get_by_id(id).await?;
+        if let Some(ref g) = group {
+            self.cache.cache_group(g).await;
+        }
+        Ok(group)
+    }
+
+    /// Get all groups (not cached)
+    pub async fn get_all(&self) -> Result<Vec<SmartGroup>> {
+        self.inner.get_all().await
+    }
+
+    /// List one page of groups (not cached)
+    pub async fn list(&self, query: &ListQuery) -> Result<Paged<SmartGroup>> {
+        self.inner.list(query).await
+    }
+
+    /// Delete a group and invalidate cache
+    pub async fn delete(&self, id: &Uuid) -> Result<()> {
+        self.cache.invalidate_group(id).await;
+        self.cache.invalidate_memberships().await;
+        self.inner.delete(id).await
+    }
+
+    /// Add a page to a group and invalidate both sides of the membership
+    pub async fn add_page_to_group(&self, page_id: &Uuid, group_id: &Uuid, confidence: f32) -> Result<()> {
+        self.inner.add_page_to_group(page_id, group_id, confidence).await?;
+        self.cache.invalidate_membership(page_id, group_id).await;
+        Ok(())
+    }
+
+    /// Remove a page from a group and invalidate both sides of the membership
+    pub async fn remove_page_from_group(&self, page_id: &Uuid, group_id: &Uuid) -> Result<()> {
+        self.inner.remove_page_from_group(page_id, group_id).await?;
+        self.cache.invalidate_membership(page_id, group_id).await;
+        Ok(())
+    }
+
+    /// Get the pages of a group, checking cache first
+    pub async fn get_pages_in_group(&self, group_id: &Uuid) -> Result<Vec<Uuid>> {
+        if let Some(page_ids) = self.cache.get_group_pages(group_id).await {
+            return Ok(page_ids);
+        }
+
+        let page_ids = self.inner.get_pages_in_group(group_id).await?;
+        self.cache.cache_group_pages(*group_id, &page_ids).await;
+        Ok(page_ids)
+    }
+
+    /// Get the groups of a page, checking cache first
+    pub async fn get_groups_for_page(&self, page_id: &Uuid) -> Result<Vec<Uuid>> {
+        if let Some(group_ids) = self.cache.get_page_groups(page_id).await {
+            return Ok(group_ids);
+        }
+
+        let group_ids = self.inner.get_groups_for_page(page_id).await?;
+        self.cache.cache_page_groups(*page_id, &group_ids).await;
+        Ok(group_ids)
+    }
+}
+
+/// History repository with write-through caching of entries and filtered
+/// queries
+///
+/// Any write to history drops every cached query result, since an entry can
+/// fall into or out of any filter.
+pub struct CachedHistoryRepository {
+    inner: SqliteHistoryRepository,
+    cache: Arc<DataCache>,
+}
+
+impl CachedHistoryRepository {
+    pub fn new(connection: Arc<Connection>, cache: Arc<DataCache>) -> Self {
+        Self {
+            inner: SqliteHistoryRepository::new(connection),
+            cache,
+        }
+    }
+
+    /// Save an entry and update cache
+    pub async fn save(&self, entry: &HistoryEntry) -> Result<()> {
+        self.inner.save(entry).await?;
+        self.cache.cache_history(entry).await;
+        Ok(())
+    }
+
+    /// Save many entries in one transaction and cache them
+    pub async fn save_batch(&self, entries: &[HistoryEntry]) -> Result<()> {
+        self.inner.save_batch(entries).await?;
+        for entry in entries {
+            self.cache.cache_history(entry).await;
+        }
+        Ok(())
+    }
+
+    /// Get an entry by ID, checking cache first
+    pub async fn get_by_id(&self, id: &HistoryId) -> Result<Option<HistoryEntry>> {
+        if let Some(entry) = self.cache.get_history(id).await {
+            return Ok(Some(entry));
+        }
+
+        let entry = self.inner.get_by_id(id).await?;
+        if let Some(ref e) = entry {
+            self.cache.cache_history(e).await;
+        }
+        Ok(entry)
+    }
+
+    /// Get the entries matching a filter, checking cache first
+    pub async fn get_filtered(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>> {
+        let key = serde_json::to_string(filter).unwrap_or_default();
+        if let Some(entries) = self.cache.get_history_query(&key).await {
+            return Ok(entries);
+        }
+
+        let entries = self.inner.get_filtered(filter).await?;
+        self.cache.cache_history_query(key, &entries).await;
+        Ok(entries)
+    }
+
+    /// List one page of entries (not cached)
+    pub async fn list(&self, query: &ListQuery) -> Result<Paged<HistoryEntry>> {
+        self.inner.list(query).await
+    }
+
+    /// Search entries (not cached)
+    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<HistoryEntry>> {
+        self.inner.search(query, limit).await
+    }
+
+    /// Count entries (not cached)
+    pub async fn count(&self) -> Result<usize> {
+        self.inner.count().await
+    }
+
+    /// Move an entry to the trash and invalidate cache
+    pub async fn delete(&self, id: &HistoryId) -> Result<()> {
+        self.cache.invalidate_history(id).await;
+        self.inner.delete(id).await
+    }
+
+    /// Delete old entries and invalidate all cached history
+    pub async fn delete_older_than(&self, timestamp: DateTime<Utc>) -> Result<usize> {
+        let deleted = self.inner.delete_older_than(timestamp).await?;
+        self.cache.invalidate_all_history().await;
+        Ok(deleted)
+    }
+
+    /// Set the recall hint of an entry and invalidate it in cache
+    pub async fn set_recall_hint(&self, id: &HistoryId, hint: Option<&str>) -> Result<bool> {
+        let found = self.inner.set_recall_hint(id, hint).await?;
+        self.cache.invalidate_history(id).await;
+        Ok(found)
+    }
+
+    /// Take an entry out of the trash and invalidate cached queries
+    pub async fn restore(&self, id: &HistoryId) -> Result<bool> {
+        let restored = self.inner.restore(id).await?;
+        self.cache.invalidate_history(id).await;
+        Ok(restored)
+    }
+
+    /// Purge old trash (cached entries are already hidden)
+    pub async fn purge_trash(&self, before: DateTime<Utc>) -> Result<usize> {
+        self.inner.purge_trash(before).await
+    }
 }
 
 #[cfg(test)]
@@ -550,22 +1204,23 @@ mod tests {
         // Search for "programming" - should find both
         let results = repo.search("programming").await.unwrap();
         assert_eq!(results.len(), 2);
+        
+        // Latin-script terms match word prefixes
+        assert_eq!(repo.search("prog lang").await.unwrap().len(), 2);
     }
 
     #[tokio::test]
-    async fn test_group_repository() {
+    async fn test_ranked_search_scores_and_highlights() {
         let db = DatabaseManager::in_memory().await.unwrap();
-        let group_repo = db.group_repository();
-        let page_repo = db.page_repository();
+        let repo = db.page_repository();
         
-        // Create a page first
-        let page = UnifiedPageInfo {
+        let page = |url: &str, title: &str, keywords: &[&str]| UnifiedPageInfo {
             id: Uuid::new_v4(),
-            url: "https://example.com".to_string(),
-            title: "Example".to_string(),
+            url: url.to_string(),
+            title: title.to_string(),
             favicon_url: None,
             content_summary: None,
-            keywords: vec![],
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
             category: None,
             source_type: PageSourceType::Bookmark {
                 browser: BrowserType::Chrome,
@@ -578,21 +1233,107 @@ mod tests {
             last_accessed: Utc::now(),
             access_count: 0,
         };
-        page_repo.save(&page).await.unwrap();
+        repo.save(&page("https://example.com/async", "Async programming", &["tokio"])).await.unwrap();
+        repo.save(&page("https://tokio.rs", "Tokio tutorial", &["tokio", "async"])).await.unwrap();
         
-        // Create a group
-        let group = SmartGroup {
-            id: Uuid::new_v4(),
-            name: "Test Group".to_string(),
-            description: "A test group".to_string(),
-            group_type: GroupType::UserDefined,
-            pages: vec![],
-            created_at: Utc::now(),
-            auto_generated: false,
-            similarity_threshold: 0.8,
-        };
+        let hits = repo.search_ranked("tokio", 10).await.unwrap();
+        assert_eq!(hits.len(), 2);
+        // A title match outweighs a keyword-only match
+        assert_eq!(hits[0].page.title, "Tokio tutorial");
+        assert!(hits[0].score > hits[1].score && hits[1].score > 0.0);
+        assert_eq!(hits[0].highlighted_title, "<mark>Tokio</mark> tutorial");
+        assert_eq!(hits[1].highlighted_title, "Async programming");
+        assert!(hits[1].snippet.contains("<mark>tokio</mark>"), "{}", hits[1].snippet);
         
-        group_repo.save(&group).await.unwrap();
+        let pages = repo.search_with_limit("tokio", 1).await.unwrap();
+        assert_eq!(pages[0].title, "Tokio tutorial");
+    }
+
+    #[tokio::test]
+    async fn test_cjk_search() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let repo = db.page_repository();
+        
+        let page = |url: &str, title: &str| UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: url.to_string(),
+            title: title.to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Chrome,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        repo.save(&page("https://example.cn/pku", "北京大学的历史与校园")).await.unwrap();
+        repo.save(&page("https://example.jp/tower", "東京タワーの観光ガイド")).await.unwrap();
+        repo.save(&page("https://example.com/go", "Go concurrency patterns")).await.unwrap();
+        
+        // Words inside unsegmented text are found through the trigram index
+        let hits = repo.search_ranked("北京大学", 10).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].highlighted_title, "<mark>北京大学</mark>的历史与校园");
+        assert_eq!(repo.search("タワー").await.unwrap()[0].url, "https://example.jp/tower");
+        
+        // Two-character words are too short for trigrams and fall back to LIKE
+        let hits = repo.search_ranked("大学", 10).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].highlighted_title, "北京<mark>大学</mark>的历史与校园");
+        assert_eq!(repo.search("go").await.unwrap().len(), 1);
+        assert_eq!(repo.search("大学 历史").await.unwrap().len(), 1);
+        assert!(repo.search("大学 tokio").await.unwrap().is_empty());
+        assert!(repo.search("  ").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_group_repository() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let group_repo = db.group_repository();
+        let page_repo = db.page_repository();
+        
+        // Create a page first
+        let page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: "https://example.com".to_string(),
+            title: "Example".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Chrome,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        page_repo.save(&page).await.unwrap();
+        
+        // Create a group
+        let group = SmartGroup {
+            id: Uuid::new_v4(),
+            name: "Test Group".to_string(),
+            description: "A test group".to_string(),
+            group_type: GroupType::UserDefined,
+            pages: vec![],
+            created_at: Utc::now(),
+            auto_generated: false,
+            similarity_threshold: 0.8,
+        };
+        
+        group_repo.save(&group).await.unwrap();
         
         // Add page to group
         group_repo.add_page_to_group(&page.id, &group.id, 0.9).await.unwrap();
@@ -608,6 +1349,815 @@ mod tests {
         assert_eq!(groups[0], group.id);
     }
 
+    #[tokio::test]
+    async fn test_tag_repository() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let tags = db.tag_repository();
+        let page_repo = db.page_repository();
+        
+        let mut pages = Vec::new();
+        for path in ["a", "b"] {
+            let page = UnifiedPageInfo {
+                id: Uuid::new_v4(),
+                url: format!("https://example.com/{}", path),
+                title: "Example".to_string(),
+                favicon_url: None,
+                content_summary: None,
+                keywords: vec![],
+                category: None,
+                source_type: PageSourceType::Bookmark {
+                    browser: BrowserType::Chrome,
+                    bookmark_id: BookmarkId::new(),
+                },
+                browser_info: None,
+                tab_info: None,
+                bookmark_info: None,
+                created_at: Utc::now(),
+                last_accessed: Utc::now(),
+                access_count: 0,
+            };
+            page_repo.save(&page).await.unwrap();
+            pages.push(page.id);
+        }
+        
+        // Names are matched ignoring case
+        let rust = tags.get_or_create("Rust").await.unwrap();
+        assert_eq!(tags.get_or_create(" rust ").await.unwrap(), rust);
+        assert!(tags.get_or_create("  ").await.is_err());
+        let lang = tags.get_or_create("rustlang").await.unwrap();
+        
+        tags.tag_page(&pages[0], &rust.id).await.unwrap();
+        tags.tag_page(&pages[0], &lang.id).await.unwrap();
+        tags.tag_page(&pages[1], &lang.id).await.unwrap();
+        assert_eq!(tags.get_tags_for_page(&pages[0]).await.unwrap().len(), 2);
+        
+        let counts: Vec<(String, usize)> = tags
+            .tag_counts()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.tag.name, c.page_count))
+            .collect();
+        assert_eq!(counts, vec![("rustlang".to_string(), 2), ("Rust".to_string(), 1)]);
+        
+        // Renaming onto another tag's name is refused
+        assert!(tags.rename(&rust.id, "RUSTLANG").await.is_err());
+        assert!(tags.rename(&rust.id, "Rust language").await.unwrap());
+        assert_eq!(tags.get_by_name("rust LANGUAGE").await.unwrap().unwrap().id, rust.id);
+        
+        // Merging keeps one membership per page
+        assert_eq!(tags.merge(&lang.id, &rust.id).await.unwrap(), 1);
+        assert!(tags.get_by_id(&lang.id).await.unwrap().is_none());
+        let mut tagged = tags.get_pages_with_tag(&rust.id).await.unwrap();
+        tagged.sort();
+        let mut expected = pages.clone();
+        expected.sort();
+        assert_eq!(tagged, expected);
+        
+        tags.untag_page(&pages[1], &rust.id).await.unwrap();
+        tags.delete(&rust.id).await.unwrap();
+        assert!(tags.get_all().await.unwrap().is_empty());
+        assert!(tags.get_tags_for_page(&pages[0]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tag_hierarchy() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let tags = db.tag_repository();
+        
+        let programming = tags.get_or_create("Programming").await.unwrap();
+        let rust = tags.get_or_create("Rust").await.unwrap();
+        let async_rust = tags.get_or_create("Async").await.unwrap();
+        assert!(tags.set_parent(&rust.id, Some(&programming.id)).await.unwrap());
+        assert!(tags.set_parent(&async_rust.id, Some(&rust.id)).await.unwrap());
+        assert_eq!(tags.get_by_id(&async_rust.id).await.unwrap().unwrap().parent_id, Some(rust.id));
+        
+        // Cycles and missing parents are refused
+        assert!(tags.set_parent(&programming.id, Some(&async_rust.id)).await.is_err());
+        assert!(tags.set_parent(&rust.id, Some(&rust.id)).await.is_err());
+        assert!(tags.set_parent(&rust.id, Some(&Uuid::new_v4())).await.is_err());
+        assert!(!tags.set_parent(&Uuid::new_v4(), None).await.unwrap());
+        
+        // Deleting a tag moves its children up
+        tags.delete(&rust.id).await.unwrap();
+        assert_eq!(tags.get_by_id(&async_rust.id).await.unwrap().unwrap().parent_id, Some(programming.id));
+        
+        // Merging a parent into its child lifts the child into its place
+        let tokio = tags.get_or_create("Tokio").await.unwrap();
+        tags.set_parent(&tokio.id, Some(&async_rust.id)).await.unwrap();
+        tags.merge(&programming.id, &tokio.id).await.unwrap();
+        let all = tags.get_all().await.unwrap();
+        let parent_of = |name: &str| all.iter().find(|t| t.name == name).unwrap().parent_id;
+        assert_eq!(parent_of("Tokio"), None);
+        assert_eq!(parent_of("Async"), Some(tokio.id));
+    }
+
+    #[tokio::test]
+    async fn test_note_repository() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let notes = db.note_repository();
+        let page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: "https://example.com/borrowing".to_string(),
+            title: "Borrowing".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Chrome,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        db.page_repository().save(&page).await.unwrap();
+        let page_id = page.id;
+        
+        let mut note = PageNote::note(page_id, "Compare with the async book");
+        notes.save(&note).await.unwrap();
+        let highlight = PageNote::highlight(page_id, "借用检查器会拒绝这段代码", "");
+        notes.save(&highlight).await.unwrap();
+        assert!(highlight.is_highlight());
+        
+        let for_page = notes.get_for_page(&page_id).await.unwrap();
+        assert_eq!(for_page.len(), 2);
+        assert_eq!(for_page[0].content, "Compare with the async book");
+        
+        // Notes and quotes are searchable, also through unified search
+        assert_eq!(notes.search("async", 10).await.unwrap()[0].id, note.id);
+        assert_eq!(notes.search("借用", 10).await.unwrap()[0].id, highlight.id);
+        let unified = db.unified_search_repository().search("检查器", 10).await.unwrap();
+        assert!(matches!(&unified[..], [UnifiedSearchResult::Note(n)] if n.id == highlight.id));
+        
+        // Editing replaces the indexed text
+        note.content = "Compare with the tokio tutorial".to_string();
+        notes.save(&note).await.unwrap();
+        assert!(notes.search("async", 10).await.unwrap().is_empty());
+        assert_eq!(notes.search("tokio", 10).await.unwrap().len(), 1);
+        
+        // Saving the page again keeps its notes
+        db.page_repository().save(&page).await.unwrap();
+        assert_eq!(notes.get_for_page(&page_id).await.unwrap().len(), 2);
+        
+        notes.delete(&note.id).await.unwrap();
+        assert_eq!(notes.count().await.unwrap(), 1);
+        assert!(notes.get_by_id(&note.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_repository() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let sessions = db.session_repository();
+        let tab = |url: &str, pinned: bool| SessionTab {
+            url: url.to_string(),
+            title: url.to_string(),
+            favicon_url: None,
+            pinned,
+        };
+        
+        let mut session = SavedSession::new(
+            "Research",
+            vec![
+                SessionWindow {
+                    browser: BrowserType::Firefox,
+                    tabs: vec![tab("https://a.example", true), tab("https://b.example", false)],
+                },
+                SessionWindow {
+                    browser: BrowserType::Chrome,
+                    tabs: vec![tab("https://c.example", false)],
+                },
+            ],
+        );
+        sessions.save(&session).await.unwrap();
+        let loaded = sessions.get_by_id(&session.id).await.unwrap().unwrap();
+        assert_eq!((loaded.name.as_str(), &loaded.windows), ("Research", &session.windows));
+        
+        let summaries = sessions.list().await.unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!((summaries[0].window_count, summaries[0].tab_count), (2, 3));
+        
+        // Saving again replaces the windows and their order
+        session.windows.remove(0);
+        session.windows[0].tabs.insert(0, tab("https://d.example", true));
+        sessions.save(&session).await.unwrap();
+        let loaded = sessions.get_by_id(&session.id).await.unwrap().unwrap();
+        assert_eq!(loaded.windows.len(), 1);
+        assert_eq!(loaded.windows[0].browser, BrowserType::Chrome);
+        assert_eq!(loaded.windows[0].tabs[0].url, "https://d.example");
+        assert!(loaded.windows[0].tabs[0].pinned);
+        assert_eq!(loaded.tab_count(), 2);
+        
+        assert!(sessions.rename(&session.id, "Archived research").await.unwrap());
+        assert!(!sessions.rename(&Uuid::new_v4(), "Missing").await.unwrap());
+        assert_eq!(sessions.list().await.unwrap()[0].name, "Archived research");
+        
+        sessions.delete(&session.id).await.unwrap();
+        assert_eq!(sessions.count().await.unwrap(), 0);
+        assert!(sessions.get_by_id(&session.id).await.unwrap().is_none());
+        let orphaned: i64 = db
+            .connection()
+            .call(|conn| Ok(conn.query_row("SELECT COUNT(*) FROM session_tabs", [], |row| row.get(0))?))
+            .await
+            .unwrap();
+        assert_eq!(orphaned, 0);
+    }
+
+    #[tokio::test]
+    async fn test_archive_compression_and_dedup() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let archives = db.archive_repository();
+        let page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: "https://example.com/report".to_string(),
+            title: "Quarterly report".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Chrome,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        db.page_repository().save(&page).await.unwrap();
+        let html = "<table><tr><td>revenue</td></tr></table>".repeat(500);
+        let archive = |html: &str| ContentArchive {
+            id: ArchiveId::new(),
+            page_id: page.id,
+            url: page.url.clone(),
+            title: page.title.clone(),
+            content_html: html.to_string(),
+            content_text: "revenue grew".to_string(),
+            media_files: vec![],
+            archived_at: Utc::now(),
+            file_size: html.len() as u64,
+            checksum: None,
+        };
+        
+        // The same content archived twice is stored once, compressed
+        let first = archive(&html);
+        let second = archive(&html);
+        archives.save(&first).await.unwrap();
+        archives.save(&second).await.unwrap();
+        let stats = archives.storage_stats().await.unwrap();
+        assert_eq!((stats.archives, stats.blobs), (2, 1));
+        assert_eq!(stats.content_bytes, 2 * html.len() as u64);
+        assert!(stats.stored_bytes < html.len() as u64 / 10);
+        assert_eq!(archives.get_by_id(&first.id).await.unwrap().unwrap().content_html, html);
+        
+        // Search results leave the HTML compressed until it is asked for
+        let hits = archives.search("revenue", 10).await.unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|a| a.content_html.is_empty()));
+        assert_eq!(archives.get_html(&second.id).await.unwrap().unwrap(), html);
+        
+        // A blob outlives archives until the last one using it is gone
+        archives.delete(&first.id).await.unwrap();
+        assert_eq!(archives.storage_stats().await.unwrap().blobs, 1);
+        let mut edited = second.clone();
+        edited.content_html = "<p>revised</p>".to_string();
+        archives.save(&edited).await.unwrap();
+        assert_eq!(archives.storage_stats().await.unwrap().blobs, 1);
+        assert_eq!(archives.get_html(&second.id).await.unwrap().unwrap(), "<p>revised</p>");
+        
+        // Archives stored inline before compression are compacted in place
+        let legacy = archive(&html);
+        let legacy_id = legacy.id.0.to_string();
+        db.connection()
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO content_archives (id, page_id, url, title, content_html, content_text, media_files, archived_at, file_size) \
+                     VALUES (?1, ?2, ?3, 'Legacy', ?4, 'legacy text', '[]', 0, 0)",
+                    rusqlite::params![legacy_id, legacy.page_id.to_string(), legacy.url, legacy.content_html],
+                )?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(archives.get_html(&legacy.id).await.unwrap().unwrap(), html);
+        assert_eq!(archives.compact().await.unwrap(), 1);
+        assert_eq!(archives.compact().await.unwrap(), 0);
+        assert_eq!(archives.storage_stats().await.unwrap().blobs, 2);
+        assert_eq!(archives.get_by_id(&legacy.id).await.unwrap().unwrap().content_html, html);
+    }
+
+    #[tokio::test]
+    async fn test_page_and_history_trash() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let pages = db.page_repository();
+        let history = db.history_repository();
+        let page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: "https://example.com/recipes".to_string(),
+            title: "Bread recipes".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Chrome,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        pages.save(&page).await.unwrap();
+        let tag = db.tag_repository().get_or_create("baking").await.unwrap();
+        db.tag_repository().tag_page(&page.id, &tag.id).await.unwrap();
+        db.note_repository().save(&PageNote::note(page.id, "Try the rye")).await.unwrap();
+        
+        // A deleted page disappears from reads but keeps its records
+        pages.delete(&page.id).await.unwrap();
+        assert!(pages.get_by_id(&page.id).await.unwrap().is_none());
+        assert!(pages.get_by_url(&page.url).await.unwrap().is_none());
+        assert!(pages.search("bread").await.unwrap().is_empty());
+        assert_eq!(pages.count().await.unwrap(), 0);
+        assert!(db.tag_repository().get_pages_with_tag(&tag.id).await.unwrap().is_empty());
+        let trash = pages.get_trash().await.unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].item.id, page.id);
+        
+        assert!(pages.restore(&page.id).await.unwrap());
+        assert!(!pages.restore(&page.id).await.unwrap());
+        assert_eq!(pages.search("bread").await.unwrap().len(), 1);
+        assert_eq!(db.tag_repository().get_pages_with_tag(&tag.id).await.unwrap(), vec![page.id]);
+        assert_eq!(db.note_repository().get_for_page(&page.id).await.unwrap().len(), 1);
+        
+        // Purging deletes for good, cascading to the page's records
+        pages.delete(&page.id).await.unwrap();
+        assert_eq!(pages.purge_trash(Utc::now() + chrono::Duration::seconds(1)).await.unwrap(), 1);
+        assert!(pages.get_trash().await.unwrap().is_empty());
+        assert!(!pages.restore(&page.id).await.unwrap());
+        assert_eq!(db.note_repository().count().await.unwrap(), 0);
+        
+        // Saving a page again brings it back
+        pages.save(&page).await.unwrap();
+        pages.delete(&page.id).await.unwrap();
+        pages.save(&page).await.unwrap();
+        assert!(pages.get_by_id(&page.id).await.unwrap().is_some());
+        
+        let entry = HistoryEntry {
+            id: HistoryId::new(),
+            page_info: page.clone(),
+            browser_type: BrowserType::Chrome,
+            tab_id: None,
+            closed_at: Utc::now(),
+            session_info: None,
+            recall_hint: None,
+        };
+        history.save(&entry).await.unwrap();
+        history.delete(&entry.id).await.unwrap();
+        assert!(history.get_by_id(&entry.id).await.unwrap().is_none());
+        assert!(history.get_filtered(&HistoryFilter::default()).await.unwrap().is_empty());
+        assert_eq!(history.get_trash().await.unwrap()[0].item.id, entry.id);
+        assert!(history.restore(&entry.id).await.unwrap());
+        assert_eq!(history.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_to() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: "https://example.com/migrate".to_string(),
+            title: "Migrated page".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Chrome,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        db.page_repository().save(&page).await.unwrap();
+        let table_exists = |name: &'static str| {
+            let connection = db.connection();
+            async move {
+                connection
+                    .call(move |conn| {
+                        Ok(conn.query_row(
+                            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                            [name],
+                            |row| row.get::<_, bool>(0),
+                        )?)
+                    })
+                    .await
+                    .unwrap()
+            }
+        };
+        
+        // A dry run reports the steps without running them
+        let steps = db.migrate_to(9, true).await.unwrap();
+        assert_eq!(steps.iter().map(|s| s.version).collect::<Vec<_>>(), vec![25, 24, 23, 22, 21, 20, 19, 18, 17, 16, 15, 14, 13, 12, 11, 10]);
+        assert!(steps.iter().all(|s| s.direction == schema::MigrationDirection::Down));
+        assert!(table_exists("change_log").await);
+        
+        // An irreversible step fails the plan before anything runs
+        assert!(db.migrate_to(5, false).await.is_err());
+        assert!(db.migrate_to(schema::SCHEMA_VERSION + 1, true).await.is_err());
+        assert_eq!(db.get_schema_version().await.unwrap(), schema::SCHEMA_VERSION);
+        
+        assert_eq!(db.migrate_to(9, false).await.unwrap(), steps);
+        assert_eq!(db.get_schema_version().await.unwrap(), 9);
+        assert!(!table_exists("change_log").await);
+        
+        let steps = db.migrate_to(schema::SCHEMA_VERSION, false).await.unwrap();
+        assert_eq!(steps.iter().map(|s| s.version).collect::<Vec<_>>(), vec![10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25]);
+        assert!(db.migrate_to(schema::SCHEMA_VERSION, false).await.unwrap().is_empty());
+        let pages = db.page_repository();
+        assert_eq!(pages.search("migrated").await.unwrap().len(), 1);
+        pages.delete(&page.id).await.unwrap();
+        assert!(pages.restore(&page.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_keyset_pagination() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let pages = db.page_repository();
+        let start = Utc::now() - chrono::Duration::days(1);
+        let mut saved = Vec::new();
+        for i in 0..7 {
+            let page = UnifiedPageInfo {
+                id: Uuid::new_v4(),
+                url: format!("https://example.com/{}", i),
+                title: format!("Page {}", i),
+                favicon_url: None,
+                content_summary: None,
+                keywords: vec![],
+                category: None,
+                source_type: PageSourceType::Bookmark {
+                    browser: BrowserType::Chrome,
+                    bookmark_id: BookmarkId::new(),
+                },
+                browser_info: None,
+                tab_info: None,
+                bookmark_info: None,
+                // Pairs share a timestamp so ties are broken by id
+                created_at: start + chrono::Duration::minutes(i / 2),
+                last_accessed: start + chrono::Duration::minutes(i / 2),
+                access_count: 0,
+            };
+            pages.save(&page).await.unwrap();
+            saved.push(page);
+        }
+        
+        let mut query = PageQuery { limit: 3, ..Default::default() };
+        let mut listed = Vec::new();
+        loop {
+            let page = pages.list(&query).await.unwrap();
+            listed.extend(page.items.iter().map(|p| p.id));
+            match page.next {
+                Some(next) => query.after = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(listed.len(), 7);
+        let mut unique = listed.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 7);
+        // Newest first
+        let keys: Vec<i64> = listed
+            .iter()
+            .map(|id| saved.iter().find(|p| p.id == *id).unwrap().last_accessed.timestamp())
+            .collect();
+        assert!(keys.windows(2).all(|w| w[0] >= w[1]));
+        
+        // A page inserted behind the cursor does not shift later pages
+        let first = pages.list(&PageQuery { limit: 2, sort: PageSort::CreatedAt, order: SortOrder::Ascending, ..Default::default() }).await.unwrap();
+        assert_eq!(first.items[0].created_at.timestamp(), start.timestamp());
+        let mut early = saved[0].clone();
+        early.id = Uuid::new_v4();
+        early.url = "https://example.com/early".to_string();
+        early.created_at = start - chrono::Duration::minutes(5);
+        pages.save(&early).await.unwrap();
+        let second = pages
+            .list(&PageQuery { after: first.next.clone(), limit: 2, sort: PageSort::CreatedAt, order: SortOrder::Ascending, ..Default::default() })
+            .await
+            .unwrap();
+        assert!(second.items.iter().all(|p| !first.items.iter().any(|f| f.id == p.id)));
+        assert_eq!(second.items[0].created_at.timestamp(), (start + chrono::Duration::minutes(1)).timestamp());
+        
+        // Offsets still work, and an empty listing has no next page
+        let offset = pages.list(&PageQuery { offset: 6, limit: 10, ..Default::default() }).await.unwrap();
+        assert_eq!(offset.items.len(), 2);
+        assert!(offset.next.is_none());
+        assert!(pages.list(&PageQuery { limit: 0, ..Default::default() }).await.unwrap().items.is_empty());
+        
+        let history = db.history_repository();
+        for (i, page) in saved.iter().enumerate() {
+            history
+                .save(&HistoryEntry {
+                    id: HistoryId::new(),
+                    page_info: page.clone(),
+                    browser_type: BrowserType::Chrome,
+                    tab_id: None,
+                    closed_at: start + chrono::Duration::minutes(i as i64),
+                    session_info: None,
+                    recall_hint: None,
+                })
+                .await
+                .unwrap();
+        }
+        let oldest = history.list(&ListQuery { limit: 4, sort: SortOrder::Ascending, ..Default::default() }).await.unwrap();
+        assert_eq!(oldest.items[0].page_info.id, saved[0].id);
+        let rest = history.list(&ListQuery { after: oldest.next, limit: 4, sort: SortOrder::Ascending, ..Default::default() }).await.unwrap();
+        assert_eq!(rest.items.iter().map(|e| e.page_info.id).collect::<Vec<_>>(), saved[4..].iter().map(|p| p.id).collect::<Vec<_>>());
+        assert!(rest.next.is_none());
+        
+        assert!(db.group_repository().list(&ListQuery::default()).await.unwrap().items.is_empty());
+        assert!(db.archive_repository().list(&ListQuery::default()).await.unwrap().items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_page_query_filters() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let pages = db.page_repository();
+        let now = Utc::now();
+        let page = |n: u32, source_type: PageSourceType, category: Option<&str>| UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: format!("https://example.com/{}", n),
+            title: format!("Page {}", n),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: category.map(String::from),
+            source_type,
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: now - chrono::Duration::days(n as i64),
+            last_accessed: now - chrono::Duration::hours(n as i64),
+            access_count: n * 10 % 7,
+        };
+        let chrome_tab = page(1, PageSourceType::ActiveTab { browser: BrowserType::Chrome, tab_id: TabId::new() }, Some("news"));
+        let firefox_bookmark = page(2, PageSourceType::Bookmark { browser: BrowserType::Firefox, bookmark_id: BookmarkId::new() }, Some("news"));
+        let mut summarized = page(3, PageSourceType::Bookmark { browser: BrowserType::Chrome, bookmark_id: BookmarkId::new() }, Some("docs"));
+        summarized.content_summary = Some(ContentSummary {
+            summary_text: "A summary".to_string(),
+            key_points: vec![],
+            content_type: ContentType::Article,
+            language: "en".to_string(),
+            reading_time_minutes: 2,
+            confidence_score: 0.9,
+            generated_at: now,
+        });
+        let closed = page(10, PageSourceType::ClosedTab { history_id: HistoryId::new() }, None);
+        for p in [&chrome_tab, &firefox_bookmark, &summarized, &closed] {
+            pages.save(p).await.unwrap();
+        }
+        let tag = db.tag_repository().get_or_create("read later").await.unwrap();
+        db.tag_repository().tag_page(&firefox_bookmark.id, &tag.id).await.unwrap();
+        db.tag_repository().tag_page(&closed.id, &tag.id).await.unwrap();
+        
+        let ids = |query: PageQuery| {
+            let pages = db.page_repository();
+            async move { pages.list(&query).await.unwrap().items.into_iter().map(|p| p.id).collect::<Vec<_>>() }
+        };
+        
+        assert_eq!(ids(PageQuery::new().category("news")).await, vec![chrome_tab.id, firefox_bookmark.id]);
+        assert_eq!(ids(PageQuery::new().browser(BrowserType::Chrome)).await, vec![chrome_tab.id, summarized.id]);
+        assert_eq!(ids(PageQuery::new().source(PageRawSourceType::Bookmark)).await, vec![firefox_bookmark.id, summarized.id]);
+        assert_eq!(ids(PageQuery::new().has_summary(true)).await, vec![summarized.id]);
+        assert_eq!(ids(PageQuery::new().has_summary(false).tag(tag.id)).await, vec![firefox_bookmark.id, closed.id]);
+        assert_eq!(
+            ids(PageQuery::new().created_between(now - chrono::Duration::days(5), now - chrono::Duration::hours(36))).await,
+            vec![firefox_bookmark.id, summarized.id]
+        );
+        assert_eq!(
+            ids(PageQuery::new().accessed_between(now - chrono::Duration::minutes(90), now)).await,
+            vec![chrome_tab.id]
+        );
+        assert!(ids(PageQuery::new().category("news").browser(BrowserType::Safari)).await.is_empty());
+        
+        // Access counts are 3, 6, 2 and 2
+        let by_access = PageQuery::new().sort(PageSort::AccessCount, SortOrder::Descending).limit(2);
+        let first = pages.list(&by_access).await.unwrap();
+        assert_eq!(first.items.iter().map(|p| p.id).collect::<Vec<_>>(), vec![firefox_bookmark.id, chrome_tab.id]);
+        let rest = ids(by_access.after(first.next.unwrap())).await;
+        assert_eq!(rest.len(), 2);
+        assert!(rest.contains(&summarized.id) && rest.contains(&closed.id));
+        
+        // Filters combine with the cursor
+        let news = PageQuery::new().category("news").limit(1);
+        let first = pages.list(&news).await.unwrap();
+        assert_eq!(ids(news.after(first.next.unwrap())).await, vec![firefox_bookmark.id]);
+    }
+
+    #[tokio::test]
+    async fn test_save_batch() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let mut pages: Vec<UnifiedPageInfo> = (0..50)
+            .map(|i| UnifiedPageInfo {
+                id: Uuid::new_v4(),
+                url: format!("https://example.com/{}", i),
+                title: format!("Imported {}", i),
+                favicon_url: None,
+                content_summary: None,
+                keywords: vec![],
+                category: None,
+                source_type: PageSourceType::Bookmark {
+                    browser: BrowserType::Edge,
+                    bookmark_id: BookmarkId::new(),
+                },
+                browser_info: None,
+                tab_info: None,
+                bookmark_info: None,
+                created_at: Utc::now(),
+                last_accessed: Utc::now(),
+                access_count: 0,
+            })
+            .collect();
+        let page_repo = db.page_repository();
+        page_repo.save_batch(&pages).await.unwrap();
+        assert_eq!(page_repo.count().await.unwrap(), 50);
+        
+        // Saving again updates in place
+        pages[0].title = "Renamed".to_string();
+        page_repo.save_batch(&pages[..1]).await.unwrap();
+        assert_eq!(page_repo.count().await.unwrap(), 50);
+        assert_eq!(page_repo.get_by_id(&pages[0].id).await.unwrap().unwrap().title, "Renamed");
+        page_repo.save_batch(&[]).await.unwrap();
+        
+        let groups: Vec<SmartGroup> = (0..3)
+            .map(|i| SmartGroup {
+                id: Uuid::new_v4(),
+                name: format!("Group {}", i),
+                description: String::new(),
+                group_type: GroupType::UserDefined,
+                pages: vec![],
+                created_at: Utc::now(),
+                auto_generated: false,
+                similarity_threshold: 0.5,
+            })
+            .collect();
+        db.group_repository().save_batch(&groups).await.unwrap();
+        assert_eq!(db.group_repository().get_all().await.unwrap().len(), 3);
+        
+        let entry = |page: &UnifiedPageInfo| HistoryEntry {
+            id: HistoryId::new(),
+            page_info: page.clone(),
+            browser_type: BrowserType::Edge,
+            tab_id: None,
+            closed_at: Utc::now(),
+            session_info: None,
+            recall_hint: None,
+        };
+        let history = db.history_repository();
+        history.save_batch(&pages.iter().take(10).map(entry).collect::<Vec<_>>()).await.unwrap();
+        assert_eq!(history.count().await.unwrap(), 10);
+        
+        // One failing entry rolls back the whole batch
+        let mut unsaved_page = pages[0].clone();
+        unsaved_page.id = Uuid::new_v4();
+        let batch = vec![entry(&pages[20]), entry(&unsaved_page)];
+        assert!(history.save_batch(&batch).await.is_err());
+        assert_eq!(history.count().await.unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_stream_all_reads_in_batches() {
+        use futures::{StreamExt, TryStreamExt};
+        
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let start = Utc::now() - chrono::Duration::days(30);
+        let total = STREAM_BATCH_SIZE * 2 + 10;
+        let pages: Vec<UnifiedPageInfo> = (0..total)
+            .map(|i| UnifiedPageInfo {
+                id: Uuid::new_v4(),
+                url: format!("https://example.com/{}", i),
+                title: format!("Page {}", i),
+                favicon_url: None,
+                content_summary: None,
+                keywords: vec![],
+                category: None,
+                source_type: PageSourceType::Bookmark {
+                    browser: BrowserType::Chrome,
+                    bookmark_id: BookmarkId::new(),
+                },
+                browser_info: None,
+                tab_info: None,
+                bookmark_info: None,
+                created_at: start + chrono::Duration::seconds(i as i64),
+                last_accessed: Utc::now(),
+                access_count: 0,
+            })
+            .collect();
+        db.batch_operations().batch_save(&pages).await.unwrap();
+        
+        let streamed: Vec<UnifiedPageInfo> = db.page_repository().stream_all().try_collect().await.unwrap();
+        assert_eq!(
+            streamed.iter().map(|p| p.id).collect::<Vec<_>>(),
+            pages.iter().map(|p| p.id).collect::<Vec<_>>()
+        );
+        
+        // Streams are lazy; taking a few items fetches only the first batch
+        let first: Vec<_> = db.page_repository().stream_all().take(3).try_collect().await.unwrap();
+        assert_eq!(first.len(), 3);
+        
+        let empty: Vec<HistoryEntry> = db.history_repository().stream_all().try_collect().await.unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_history_recall_hints() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let repo = db.history_repository();
+        
+        let history_id = HistoryId::new();
+        let entry = HistoryEntry {
+            id: history_id.clone(),
+            page_info: UnifiedPageInfo {
+                id: Uuid::new_v4(),
+                url: "https://example.com/trams".to_string(),
+                title: "Tram 28".to_string(),
+                favicon_url: None,
+                content_summary: None,
+                keywords: vec![],
+                category: None,
+                source_type: PageSourceType::ClosedTab { history_id: history_id.clone() },
+                browser_info: None,
+                tab_info: None,
+                bookmark_info: None,
+                created_at: Utc::now(),
+                last_accessed: Utc::now(),
+                access_count: 0,
+            },
+            browser_type: BrowserType::Firefox,
+            tab_id: None,
+            closed_at: Utc::now(),
+            session_info: None,
+            recall_hint: Some("Saved while planning the Lisbon trip".to_string()),
+        };
+        db.page_repository().save(&entry.page_info).await.unwrap();
+        repo.save(&entry).await.unwrap();
+        
+        let fetched = repo.get_by_id(&history_id).await.unwrap().unwrap();
+        assert_eq!(fetched.recall_hint.as_deref(), Some("Saved while planning the Lisbon trip"));
+        assert_eq!(repo.search("lisbon", 10).await.unwrap().len(), 1);
+        
+        assert!(repo.set_recall_hint(&history_id, Some("Saved from the Porto group")).await.unwrap());
+        assert!(repo.search("lisbon", 10).await.unwrap().is_empty());
+        assert_eq!(repo.search("porto", 10).await.unwrap().len(), 1);
+        assert!(!repo.set_recall_hint(&HistoryId::new(), None).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_analysis_cache_repository() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let repo = db.analysis_cache_repository();
+        
+        let old_time = Utc::now() - chrono::Duration::days(30);
+        let entries = vec![
+            AnalysisCacheEntry {
+                content_hash: "00000000000000aa".to_string(),
+                analysis: r#"{"kind":"keywords","value":["rust"]}"#.to_string(),
+                created_at: old_time,
+                last_used: old_time,
+            },
+            AnalysisCacheEntry {
+                content_hash: "00000000000000bb".to_string(),
+                analysis: r#"{"kind":"classification","value":"Article"}"#.to_string(),
+                created_at: Utc::now(),
+                last_used: Utc::now(),
+            },
+        ];
+        repo.save_batch(&entries).await.unwrap();
+        assert_eq!(repo.count().await.unwrap(), 2);
+        
+        let recent = repo.get_recent(10).await.unwrap();
+        assert_eq!(recent[0].content_hash, "00000000000000bb");
+        
+        let pruned = repo.delete_unused_since(Utc::now() - chrono::Duration::days(7)).await.unwrap();
+        assert_eq!(pruned, 1);
+        
+        let entry = repo.get("00000000000000bb").await.unwrap().unwrap();
+        assert!(entry.analysis.contains("Article"));
+        assert!(repo.get("00000000000000aa").await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_cached_page_repository() {
         let db = DatabaseManager::in_memory().await.unwrap();
@@ -648,4 +2198,141 @@ mod tests {
         let stats = db.cache().stats().await;
         assert!(stats.pages_count > 0);
     }
+
+    #[tokio::test]
+    async fn test_cached_page_repository_missing_urls() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let cached_repo = Arc::new(CachedPageRepository::new(db.connection(), db.cache()));
+        let url = "https://missing.example.com";
+
+        // Concurrent misses share one lookup and remember the result
+        let lookups: Vec<_> = (0..8)
+            .map(|_| {
+                let repo = cached_repo.clone();
+                tokio::spawn(async move { repo.get_by_url(url).await })
+            })
+            .collect();
+        for lookup in lookups {
+            assert!(lookup.await.unwrap().unwrap().is_none());
+        }
+        let stats = db.cache().stats().await;
+        assert_eq!(stats.missing_urls_count, 1);
+
+        // A page written behind the cache's back stays hidden until the
+        // negative entry expires; one saved through it shows at once
+        let mut page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: url.to_string(),
+            title: "Found".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Chrome,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        db.page_repository().save(&page).await.unwrap();
+        assert!(cached_repo.get_by_url(url).await.unwrap().is_none());
+
+        page.title = "Saved through the cache".to_string();
+        cached_repo.save(&page).await.unwrap();
+        assert_eq!(cached_repo.get_by_url(url).await.unwrap().unwrap().title, page.title);
+        assert_eq!(db.cache().stats().await.missing_urls_count, 0);
+    }
+
+
+    #[tokio::test]
+    async fn test_cached_group_and_history_repositories() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let pages = CachedPageRepository::new(db.connection(), db.cache());
+        let groups = CachedGroupRepository::new(db.connection(), db.cache());
+        let history = CachedHistoryRepository::new(db.connection(), db.cache());
+
+        let page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: "https://example.com/grouped".to_string(),
+            title: "Grouped".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Chrome,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        pages.save(&page).await.unwrap();
+
+        let group = SmartGroup {
+            id: Uuid::new_v4(),
+            name: "Cached Group".to_string(),
+            description: String::new(),
+            group_type: GroupType::UserDefined,
+            pages: vec![page.id],
+            created_at: Utc::now(),
+            auto_generated: false,
+            similarity_threshold: 0.8,
+        };
+        groups.save(&group).await.unwrap();
+        // The cached group matches what the database returns
+        assert!(groups.get_by_id(&group.id).await.unwrap().unwrap().pages.is_empty());
+
+        assert!(groups.get_pages_in_group(&group.id).await.unwrap().is_empty());
+        assert!(groups.get_groups_for_page(&page.id).await.unwrap().is_empty());
+        groups.add_page_to_group(&page.id, &group.id, 0.9).await.unwrap();
+        assert_eq!(groups.get_pages_in_group(&group.id).await.unwrap(), vec![page.id]);
+        assert_eq!(groups.get_groups_for_page(&page.id).await.unwrap(), vec![group.id]);
+        assert_eq!(db.cache().stats().await.memberships_count, 2);
+
+        // Saving the group again replaces its row and drops the membership
+        groups.save(&group).await.unwrap();
+        assert!(groups.get_pages_in_group(&group.id).await.unwrap().is_empty());
+        groups.add_page_to_group(&page.id, &group.id, 0.9).await.unwrap();
+        assert_eq!(groups.get_pages_in_group(&group.id).await.unwrap(), vec![page.id]);
+
+        // Trashing the page removes it from the group's page list
+        pages.delete(&page.id).await.unwrap();
+        assert!(groups.get_pages_in_group(&group.id).await.unwrap().is_empty());
+        db.page_repository().restore(&page.id).await.unwrap();
+        pages.save(&page).await.unwrap();
+
+        let history_id = HistoryId::new();
+        let mut entry = HistoryEntry {
+            id: history_id.clone(),
+            page_info: page.clone(),
+            browser_type: BrowserType::Chrome,
+            tab_id: None,
+            closed_at: Utc::now(),
+            session_info: None,
+            recall_hint: None,
+        };
+        let filter = HistoryFilter::default();
+        assert!(history.get_filtered(&filter).await.unwrap().is_empty());
+        history.save(&entry).await.unwrap();
+        assert_eq!(history.get_filtered(&filter).await.unwrap().len(), 1);
+        assert_eq!(db.cache().stats().await.history_queries_count, 1);
+
+        history.set_recall_hint(&history_id, Some("cached hint")).await.unwrap();
+        entry = history.get_by_id(&history_id).await.unwrap().unwrap();
+        assert_eq!(entry.recall_hint.as_deref(), Some("cached hint"));
+        assert_eq!(history.get_filtered(&filter).await.unwrap()[0].recall_hint.as_deref(), Some("cached hint"));
+
+        history.delete(&history_id).await.unwrap();
+        assert!(history.get_by_id(&history_id).await.unwrap().is_none());
+        assert!(history.get_filtered(&filter).await.unwrap().is_empty());
+    }
 }
@@ -0,0 +1,223 @@
+//! Scheduled storage maintenance
+//!
+//! Databases use `auto_vacuum = INCREMENTAL`: pages freed by deletions stay
+//! in the file on a free list until an incremental vacuum returns them to
+//! the filesystem, a little at a time and without rewriting the database
+//! like a full `VACUUM`. Maintenance runs that vacuum followed by
+//! `PRAGMA optimize`, which refreshes query planner statistics where they
+//! have gone stale. It also hashes change log payloads not yet read.
+//! Scheduled maintenance waits for the off-peak hours of the local day and
+//! runs once in each of them.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate, Timelike};
+use tracing::{info, warn};
+use web_page_manager_core::*;
+
+use crate::changelog::hash_pending_payloads;
+use crate::DatabaseManager;
+
+/// When and how much maintenance runs
+#[derive(Debug, Clone)]
+pub struct MaintenancePolicy {
+    /// Local hour the off-peak window starts at
+    pub off_peak_start: u32,
+    /// Local hour the off-peak window ends at, exclusive; before the start
+    /// for a window past midnight
+    pub off_peak_end: u32,
+    /// Free pages returned to the filesystem per run, all of them if 0
+    pub max_vacuum_pages: u32,
+    /// Time between checks for the off-peak window
+    pub check_interval: Duration,
+}
+
+impl Default for MaintenancePolicy {
+    /// Run between 2 and 5 in the morning, vacuuming up to 40MB at a time
+    fn default() -> Self {
+        Self {
+            off_peak_start: 2,
+            off_peak_end: 5,
+            max_vacuum_pages: 10_000,
+            check_interval: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+impl MaintenancePolicy {
+    /// Whether maintenance may run at `hour` of the local day
+    pub fn is_off_peak(&self, hour: u32) -> bool {
+        if self.off_peak_start <= self.off_peak_end {
+            (self.off_peak_start..self.off_peak_end).contains(&hour)
+        } else {
+            hour >= self.off_peak_start || hour < self.off_peak_end
+        }
+    }
+}
+
+/// Outcome of a maintenance run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MaintenanceReport {
+    /// Pages returned to the filesystem
+    pub pages_freed: u64,
+    /// Free pages left for later runs
+    pub pages_remaining: u64,
+    /// The database predated incremental vacuuming and was converted with
+    /// a full `VACUUM`
+    pub converted: bool,
+}
+
+impl DatabaseManager {
+    /// Return free pages to the filesystem, up to the policy's limit, and
+    /// refresh planner statistics
+    pub async fn run_maintenance(&self, policy: &MaintenancePolicy) -> Result<MaintenanceReport> {
+        let max_pages = policy.max_vacuum_pages;
+
+        let report = self
+            .connection
+            .call(move |conn| {
+                // Before the vacuum, so the space of the payloads is freed
+                hash_pending_payloads(conn)?;
+                let before: u64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+                // 2 is INCREMENTAL; switching from NONE takes a full VACUUM
+                let mode: i64 = conn.query_row("PRAGMA auto_vacuum", [], |row| row.get(0))?;
+                let converted = mode != 2;
+                if converted {
+                    conn.execute_batch("PRAGMA auto_vacuum = INCREMENTAL; VACUUM;")?;
+                } else {
+                    // Frees one page per step, so every row is read
+                    let mut stmt = conn.prepare(&format!("PRAGMA incremental_vacuum({})", max_pages))?;
+                    let mut rows = stmt.query([])?;
+                    while rows.next()?.is_some() {}
+                }
+                conn.execute_batch("PRAGMA optimize;")?;
+
+                let after: u64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+                Ok(MaintenanceReport {
+                    pages_freed: before.saturating_sub(after),
+                    pages_remaining: after,
+                    converted,
+                })
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to run database maintenance: {}", e),
+                },
+            })?;
+
+        if report.converted {
+            info!("Converted database to incremental vacuuming");
+        } else if report.pages_freed > 0 {
+            info!(
+                "Database maintenance freed {} pages, {} left",
+                report.pages_freed, report.pages_remaining
+            );
+        }
+        Ok(report)
+    }
+
+    /// Run `run_maintenance` once in each off-peak window until the
+    /// returned task is aborted
+    pub fn spawn_maintenance(self: Arc<Self>, policy: MaintenancePolicy) -> tokio::task::JoinHandle<()> {
+        let period = policy.check_interval.max(Duration::from_secs(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            // Start day of the last window maintenance ran in
+            let mut last_window: Option<NaiveDate> = None;
+            loop {
+                ticker.tick().await;
+                let now = Local::now();
+                if !policy.is_off_peak(now.hour()) {
+                    continue;
+                }
+                // A window wrapping past midnight belongs to the day it started
+                let window = if policy.off_peak_start > policy.off_peak_end && now.hour() < policy.off_peak_end {
+                    now.date_naive().pred_opt().unwrap_or(now.date_naive())
+                } else {
+                    now.date_naive()
+                };
+                if last_window == Some(window) {
+                    continue;
+                }
+                match self.run_maintenance(&policy).await {
+                    Ok(_) => last_window = Some(window),
+                    Err(e) => warn!("Database maintenance failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use crate::PageRepository;
+
+    #[test]
+    fn test_off_peak_hours() {
+        let policy = MaintenancePolicy::default();
+        assert!(policy.is_off_peak(2) && policy.is_off_peak(4));
+        assert!(!policy.is_off_peak(5) && !policy.is_off_peak(1));
+
+        let overnight = MaintenancePolicy { off_peak_start: 22, off_peak_end: 5, ..Default::default() };
+        assert!(overnight.is_off_peak(23) && overnight.is_off_peak(0) && overnight.is_off_peak(4));
+        assert!(!overnight.is_off_peak(12) && !overnight.is_off_peak(5));
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_frees_deleted_pages() {
+        let dir = temp_dir("maintenance");
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = DatabaseManager::new(dir.join("pages.db")).await.unwrap();
+
+        let pages: Vec<UnifiedPageInfo> = (0..300)
+            .map(|i| UnifiedPageInfo {
+                id: Uuid::new_v4(),
+                url: format!("https://example.com/{}", i),
+                title: "x".repeat(2000),
+                favicon_url: None,
+                content_summary: None,
+                keywords: vec![],
+                category: None,
+                source_type: PageSourceType::Bookmark {
+                    browser: BrowserType::Chrome,
+                    bookmark_id: BookmarkId::new(),
+                },
+                browser_info: None,
+                tab_info: None,
+                bookmark_info: None,
+                created_at: Utc::now(),
+                last_accessed: Utc::now(),
+                access_count: 0,
+            })
+            .collect();
+        let repo = db.page_repository();
+        repo.save_batch(&pages).await.unwrap();
+        for page in &pages {
+            repo.delete(&page.id).await.unwrap();
+        }
+        repo.purge_trash(Utc::now() + chrono::Duration::seconds(1)).await.unwrap();
+
+        let stats = db.stats().await.unwrap();
+        assert!(stats.free_pages > 0);
+        assert!(stats.fragmentation > 0.0);
+
+        let policy = MaintenancePolicy { max_vacuum_pages: 10, ..Default::default() };
+        let report = db.run_maintenance(&policy).await.unwrap();
+        assert!(!report.converted);
+        // Truncating the file can also release pointer-map pages
+        assert!(report.pages_freed >= 10);
+        assert!(report.pages_remaining > 0 && report.pages_remaining <= stats.free_pages - 10);
+
+        let policy = MaintenancePolicy { max_vacuum_pages: 0, ..Default::default() };
+        let report = db.run_maintenance(&policy).await.unwrap();
+        assert_eq!(report.pages_remaining, 0);
+        assert_eq!(db.stats().await.unwrap().free_pages, 0);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
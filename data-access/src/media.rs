@@ -0,0 +1,313 @@
+//! Favicon and thumbnail storage
+//!
+//! Images are stored once in `media_assets`, keyed by the SHA-1 of their
+//! bytes, so a favicon shared by every page of a site or a screenshot
+//! attached to both a page and its history entry costs one copy. Pages and
+//! history entries refer to assets by hash; triggers delete an asset with
+//! its last reference. Assets are returned as bytes with their MIME type,
+//! which any UI can render or turn into a data URL.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rusqlite::OptionalExtension;
+use tokio_rusqlite::Connection;
+use web_page_manager_core::*;
+
+use crate::compression::bytes_hash;
+
+/// What an image is used as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MediaKind {
+    Favicon,
+    Thumbnail,
+}
+
+impl MediaKind {
+    /// Column holding the reference on pages and history entries
+    fn column(self) -> &'static str {
+        match self {
+            MediaKind::Favicon => "favicon_hash",
+            MediaKind::Thumbnail => "thumbnail_hash",
+        }
+    }
+}
+
+/// A stored image
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaAsset {
+    /// Hex SHA-1 of `data`
+    pub hash: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Number and total size of stored images
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaUsage {
+    pub assets: usize,
+    pub bytes: u64,
+}
+
+/// Repository trait for favicons and thumbnails
+#[async_trait]
+pub trait MediaRepository: Send + Sync {
+    /// Store an image unless it is already stored, returning its hash
+    async fn store(&self, data: &[u8], mime_type: &str) -> Result<String>;
+    async fn get(&self, hash: &str) -> Result<Option<MediaAsset>>;
+    /// Store an image and attach it to a page, replacing the page's
+    /// previous one; false if the page does not exist
+    async fn set_page_media(&self, page_id: &Uuid, kind: MediaKind, data: &[u8], mime_type: &str) -> Result<bool>;
+    /// Detach a page's image; it is deleted if nothing else uses it
+    async fn clear_page_media(&self, page_id: &Uuid, kind: MediaKind) -> Result<()>;
+    async fn get_page_media(&self, page_id: &Uuid, kind: MediaKind) -> Result<Option<MediaAsset>>;
+    /// Store an image and attach it to a history entry; false if the entry
+    /// does not exist
+    async fn set_history_media(&self, id: &HistoryId, kind: MediaKind, data: &[u8], mime_type: &str) -> Result<bool>;
+    async fn clear_history_media(&self, id: &HistoryId, kind: MediaKind) -> Result<()>;
+    async fn get_history_media(&self, id: &HistoryId, kind: MediaKind) -> Result<Option<MediaAsset>>;
+    /// Delete images stored before `before` that nothing refers to
+    async fn prune_unreferenced(&self, before: DateTime<Utc>) -> Result<usize>;
+    async fn usage(&self) -> Result<MediaUsage>;
+}
+
+/// SQLite implementation of MediaRepository
+#[derive(Clone)]
+pub struct SqliteMediaRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteMediaRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+
+    /// Store `data` and point the `kind` reference of row `id` in `table` at it
+    async fn attach(&self, table: &'static str, id: String, kind: MediaKind, data: &[u8], mime_type: &str) -> Result<bool> {
+        let data = data.to_vec();
+        let mime_type = mime_type.to_string();
+
+        self.connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                let hash = store_asset(&tx, &data, &mime_type)?;
+                let updated = tx.execute(
+                    &format!("UPDATE {} SET {} = ?1 WHERE id = ?2", table, kind.column()),
+                    [&hash, &id],
+                )?;
+                // An image stored only for a missing row is not kept
+                if updated == 0 {
+                    tx.rollback()?;
+                } else {
+                    tx.commit()?;
+                }
+                Ok(updated > 0)
+            })
+            .await
+            .map_err(|e| media_error("attach media", e))
+    }
+
+    async fn detach(&self, table: &'static str, id: String, kind: MediaKind) -> Result<()> {
+        self.connection
+            .call(move |conn| {
+                conn.execute(&format!("UPDATE {} SET {} = NULL WHERE id = ?1", table, kind.column()), [id])?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| media_error("detach media", e))
+    }
+
+    async fn attached(&self, table: &'static str, id: String, kind: MediaKind) -> Result<Option<MediaAsset>> {
+        self.connection
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        &format!(
+                            "SELECT m.hash, m.mime_type, m.data, m.created_at FROM {} t \
+                             JOIN media_assets m ON m.hash = t.{} WHERE t.id = ?1",
+                            table,
+                            kind.column()
+                        ),
+                        [id],
+                        row_to_asset,
+                    )
+                    .optional()?)
+            })
+            .await
+            .map_err(|e| media_error("get media", e))
+    }
+}
+
+fn media_error(action: &str, e: tokio_rusqlite::Error) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Failed to {}: {}", action, e),
+        },
+    }
+}
+
+/// Store `data` unless it is already stored, returning its hash
+fn store_asset(conn: &rusqlite::Connection, data: &[u8], mime_type: &str) -> rusqlite::Result<String> {
+    let hash = bytes_hash(data);
+    conn.execute(
+        "INSERT OR IGNORE INTO media_assets (hash, mime_type, data, created_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![hash, mime_type, data, Utc::now().timestamp()],
+    )?;
+    Ok(hash)
+}
+
+/// Helper function to map a row to MediaAsset
+fn row_to_asset(row: &rusqlite::Row) -> rusqlite::Result<MediaAsset> {
+    let created_at_ts: i64 = row.get(3)?;
+    Ok(MediaAsset {
+        hash: row.get(0)?,
+        mime_type: row.get(1)?,
+        data: row.get(2)?,
+        created_at: DateTime::from_timestamp(created_at_ts, 0).unwrap_or_else(Utc::now),
+    })
+}
+
+#[async_trait]
+impl MediaRepository for SqliteMediaRepository {
+    async fn store(&self, data: &[u8], mime_type: &str) -> Result<String> {
+        let data = data.to_vec();
+        let mime_type = mime_type.to_string();
+
+        self.connection
+            .call(move |conn| Ok(store_asset(conn, &data, &mime_type)?))
+            .await
+            .map_err(|e| media_error("store media", e))
+    }
+
+    async fn get(&self, hash: &str) -> Result<Option<MediaAsset>> {
+        let hash = hash.to_string();
+
+        self.connection
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT hash, mime_type, data, created_at FROM media_assets WHERE hash = ?1",
+                        [hash],
+                        row_to_asset,
+                    )
+                    .optional()?)
+            })
+            .await
+            .map_err(|e| media_error("get media", e))
+    }
+
+    async fn set_page_media(&self, page_id: &Uuid, kind: MediaKind, data: &[u8], mime_type: &str) -> Result<bool> {
+        self.attach("unified_pages", page_id.to_string(), kind, data, mime_type).await
+    }
+
+    async fn clear_page_media(&self, page_id: &Uuid, kind: MediaKind) -> Result<()> {
+        self.detach("unified_pages", page_id.to_string(), kind).await
+    }
+
+    async fn get_page_media(&self, page_id: &Uuid, kind: MediaKind) -> Result<Option<MediaAsset>> {
+        self.attached("unified_pages", page_id.to_string(), kind).await
+    }
+
+    async fn set_history_media(&self, id: &HistoryId, kind: MediaKind, data: &[u8], mime_type: &str) -> Result<bool> {
+        self.attach("tab_history", id.0.to_string(), kind, data, mime_type).await
+    }
+
+    async fn clear_history_media(&self, id: &HistoryId, kind: MediaKind) -> Result<()> {
+        self.detach("tab_history", id.0.to_string(), kind).await
+    }
+
+    async fn get_history_media(&self, id: &HistoryId, kind: MediaKind) -> Result<Option<MediaAsset>> {
+        self.attached("tab_history", id.0.to_string(), kind).await
+    }
+
+    async fn prune_unreferenced(&self, before: DateTime<Utc>) -> Result<usize> {
+        let ts = before.timestamp();
+
+        self.connection
+            .call(move |conn| {
+                let pruned = conn.execute(
+                    "DELETE FROM media_assets WHERE created_at < ?1 \
+                     AND NOT EXISTS (SELECT 1 FROM unified_pages WHERE favicon_hash = hash OR thumbnail_hash = hash) \
+                     AND NOT EXISTS (SELECT 1 FROM tab_history WHERE favicon_hash = hash OR thumbnail_hash = hash)",
+                    [ts],
+                )?;
+                Ok(pruned)
+            })
+            .await
+            .map_err(|e| media_error("prune media", e))
+    }
+
+    async fn usage(&self) -> Result<MediaUsage> {
+        self.connection
+            .call(|conn| {
+                let (assets, bytes): (i64, i64) = conn.query_row(
+                    "SELECT COUNT(*), COALESCE(SUM(length(data)), 0) FROM media_assets",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )?;
+                Ok(MediaUsage {
+                    assets: assets as usize,
+                    bytes: bytes as u64,
+                })
+            })
+            .await
+            .map_err(|e| media_error("measure media", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DatabaseManager, HistoryRepository, PageRepository};
+    use crate::test_support::test_page;
+
+    #[tokio::test]
+    async fn test_assets_are_shared_and_released() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let pages = db.page_repository();
+        let media = db.media_repository();
+        let icon = b"\x89PNG icon".as_slice();
+
+        let (first, second) = (test_page("https://example.com/1", "Pictured"), test_page("https://example.com/2", "Pictured"));
+        pages.save_batch(&[first.clone(), second.clone()]).await.unwrap();
+        assert!(media.set_page_media(&first.id, MediaKind::Favicon, icon, "image/png").await.unwrap());
+        assert!(media.set_page_media(&second.id, MediaKind::Favicon, icon, "image/png").await.unwrap());
+        assert!(!media.set_page_media(&Uuid::new_v4(), MediaKind::Favicon, b"orphan", "image/png").await.unwrap());
+
+        let entry = HistoryEntry {
+            id: HistoryId::new(),
+            page_info: first.clone(),
+            browser_type: BrowserType::Chrome,
+            tab_id: None,
+            closed_at: Utc::now(),
+            session_info: None,
+            recall_hint: None,
+        };
+        db.history_repository().save(&entry).await.unwrap();
+        assert!(media.set_history_media(&entry.id, MediaKind::Thumbnail, b"jpeg shot", "image/jpeg").await.unwrap());
+        // Saving the entry again keeps its thumbnail
+        db.history_repository().save(&entry).await.unwrap();
+        let shot = media.get_history_media(&entry.id, MediaKind::Thumbnail).await.unwrap().unwrap();
+        assert_eq!((shot.data.as_slice(), shot.mime_type.as_str()), (b"jpeg shot".as_slice(), "image/jpeg"));
+
+        // Two favicons and a thumbnail are two stored images
+        assert_eq!(media.usage().await.unwrap().assets, 2);
+        let favicon = media.get_page_media(&second.id, MediaKind::Favicon).await.unwrap().unwrap();
+        assert_eq!(favicon.hash, bytes_hash(icon));
+        assert!(media.get_page_media(&second.id, MediaKind::Thumbnail).await.unwrap().is_none());
+
+        // The favicon stays while one page uses it
+        media.clear_page_media(&first.id, MediaKind::Favicon).await.unwrap();
+        assert!(media.get(&favicon.hash).await.unwrap().is_some());
+        media.clear_page_media(&second.id, MediaKind::Favicon).await.unwrap();
+        assert!(media.get(&favicon.hash).await.unwrap().is_none());
+
+        db.history_repository().delete_older_than(Utc::now() + chrono::Duration::seconds(1)).await.unwrap();
+        assert_eq!(media.usage().await.unwrap(), MediaUsage::default());
+
+        media.store(b"unused", "image/png").await.unwrap();
+        assert_eq!(media.prune_unreferenced(Utc::now() - chrono::Duration::hours(1)).await.unwrap(), 0);
+        assert_eq!(media.prune_unreferenced(Utc::now() + chrono::Duration::seconds(1)).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,205 @@
+//! Log of remote tab operations
+//!
+//! Tab operations, cross-browser migrations and applied bookmark merges
+//! are kept so they can be undone or rolled back after the app restarts.
+//! The log stores each record as JSON under its kind; the tab controller
+//! owns the record types and keeps only the latest records of each kind.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rusqlite::Row;
+use tokio_rusqlite::Connection;
+use web_page_manager_core::*;
+
+/// Kind of logged operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationLogKind {
+    /// Close, create or activate of tabs, or a batch of them
+    Tab,
+    /// Move of a tab to another browser
+    Migration,
+    /// Merge of duplicate bookmarks applied to their browsers
+    BookmarkMerge,
+}
+
+impl OperationLogKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperationLogKind::Tab => "tab",
+            OperationLogKind::Migration => "migration",
+            OperationLogKind::BookmarkMerge => "bookmark_merge",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "tab" => OperationLogKind::Tab,
+            "migration" => OperationLogKind::Migration,
+            "bookmark_merge" => OperationLogKind::BookmarkMerge,
+            _ => return None,
+        })
+    }
+}
+
+/// A logged operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationLogEntry {
+    pub id: Uuid,
+    pub kind: OperationLogKind,
+    pub executed_at: DateTime<Utc>,
+    /// The operation record as JSON
+    pub record: String,
+}
+
+/// Repository trait for the operation log
+#[async_trait]
+pub trait OperationLogRepository: Send + Sync {
+    /// Save an entry, replacing the record of an entry with the same ID
+    /// but keeping its place in the log
+    async fn save(&self, entry: &OperationLogEntry) -> Result<()>;
+    /// The latest `limit` entries of a kind, oldest first
+    async fn recent(&self, kind: OperationLogKind, limit: usize) -> Result<Vec<OperationLogEntry>>;
+    /// Drop all but the latest `keep` entries of a kind, returning how many
+    /// were removed
+    async fn prune(&self, kind: OperationLogKind, keep: usize) -> Result<usize>;
+    /// Drop every entry of a kind, returning how many were removed
+    async fn clear(&self, kind: OperationLogKind) -> Result<usize>;
+}
+
+/// SQLite implementation of OperationLogRepository
+#[derive(Clone)]
+pub struct SqliteOperationLogRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteOperationLogRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+}
+
+fn operation_log_error(action: &str, e: tokio_rusqlite::Error) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Failed to {}: {}", action, e),
+        },
+    }
+}
+
+/// Helper function to map a row to OperationLogEntry
+fn row_to_entry(row: &Row) -> rusqlite::Result<OperationLogEntry> {
+    let id: String = row.get(0)?;
+    let kind: String = row.get(1)?;
+    Ok(OperationLogEntry {
+        id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::new_v4()),
+        kind: OperationLogKind::parse(&kind).unwrap_or(OperationLogKind::Tab),
+        executed_at: DateTime::from_timestamp(row.get(2)?, 0).unwrap_or_else(Utc::now),
+        record: row.get(3)?,
+    })
+}
+
+#[async_trait]
+impl OperationLogRepository for SqliteOperationLogRepository {
+    async fn save(&self, entry: &OperationLogEntry) -> Result<()> {
+        let entry = entry.clone();
+
+        self.connection
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO operation_log (id, kind, executed_at, record) VALUES (?1, ?2, ?3, ?4) \
+                     ON CONFLICT(id) DO UPDATE SET record = excluded.record",
+                    rusqlite::params![
+                        entry.id.to_string(),
+                        entry.kind.as_str(),
+                        entry.executed_at.timestamp(),
+                        entry.record,
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| operation_log_error("save operation log entry", e))
+    }
+
+    async fn recent(&self, kind: OperationLogKind, limit: usize) -> Result<Vec<OperationLogEntry>> {
+        self.connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT id, kind, executed_at, record FROM operation_log WHERE kind = ?1 \
+                     ORDER BY executed_at DESC, rowid DESC LIMIT ?2",
+                )?;
+                let rows = stmt.query_map(rusqlite::params![kind.as_str(), limit as i64], row_to_entry)?;
+                let mut entries = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+                entries.reverse();
+                Ok(entries)
+            })
+            .await
+            .map_err(|e| operation_log_error("list operation log", e))
+    }
+
+    async fn prune(&self, kind: OperationLogKind, keep: usize) -> Result<usize> {
+        self.connection
+            .call(move |conn| {
+                Ok(conn.execute(
+                    "DELETE FROM operation_log WHERE kind = ?1 AND id NOT IN \
+                     (SELECT id FROM operation_log WHERE kind = ?1 ORDER BY executed_at DESC, rowid DESC LIMIT ?2)",
+                    rusqlite::params![kind.as_str(), keep as i64],
+                )?)
+            })
+            .await
+            .map_err(|e| operation_log_error("prune operation log", e))
+    }
+
+    async fn clear(&self, kind: OperationLogKind) -> Result<usize> {
+        self.connection
+            .call(move |conn| Ok(conn.execute("DELETE FROM operation_log WHERE kind = ?1", [kind.as_str()])?))
+            .await
+            .map_err(|e| operation_log_error("clear operation log", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DatabaseManager;
+
+    fn entry(kind: OperationLogKind, record: &str) -> OperationLogEntry {
+        OperationLogEntry {
+            id: Uuid::new_v4(),
+            kind,
+            executed_at: DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap(),
+            record: record.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_operation_log_keeps_latest_entries() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let log = db.operation_log_repository();
+
+        let mut first = entry(OperationLogKind::Tab, r#"{"n":1}"#);
+        let second = entry(OperationLogKind::Tab, r#"{"n":2}"#);
+        let third = entry(OperationLogKind::Tab, r#"{"n":3}"#);
+        let migration = entry(OperationLogKind::Migration, r#"{"n":4}"#);
+        for e in [&first, &second, &third, &migration] {
+            log.save(e).await.unwrap();
+        }
+
+        // Updating a record keeps its place
+        first.record = r#"{"n":1,"undone":true}"#.to_string();
+        log.save(&first).await.unwrap();
+        let recent = log.recent(OperationLogKind::Tab, 10).await.unwrap();
+        assert_eq!(recent, vec![first.clone(), second.clone(), third.clone()]);
+        assert_eq!(log.recent(OperationLogKind::Tab, 2).await.unwrap(), vec![second.clone(), third.clone()]);
+
+        assert_eq!(log.prune(OperationLogKind::Tab, 2).await.unwrap(), 1);
+        assert_eq!(log.recent(OperationLogKind::Tab, 10).await.unwrap(), vec![second, third]);
+        assert_eq!(log.recent(OperationLogKind::Migration, 10).await.unwrap(), vec![migration]);
+
+        assert_eq!(log.clear(OperationLogKind::Tab).await.unwrap(), 2);
+        assert!(log.recent(OperationLogKind::Tab, 10).await.unwrap().is_empty());
+        assert_eq!(log.recent(OperationLogKind::Migration, 10).await.unwrap().len(), 1);
+    }
+}
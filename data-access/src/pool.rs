@@ -0,0 +1,166 @@
+//! Read-only connection pool
+//!
+//! The main connection serializes every statement on its worker thread, so
+//! a burst of writes would otherwise hold up searches. In WAL mode readers
+//! see the last committed state without waiting for writers, so list and
+//! search queries run on a few read-only connections of their own, handed
+//! out round-robin. In-memory databases cannot be shared between
+//! connections and have no pool; their reads use the main connection.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use rusqlite::OpenFlags;
+use tokio_rusqlite::Connection;
+use web_page_manager_core::*;
+
+/// Read connections opened for a file database
+pub const DEFAULT_READ_CONNECTIONS: usize = 4;
+/// How long a statement waits for a lock before failing with `SQLITE_BUSY`
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Setup run on each read connection before use, such as setting the key
+/// of an encrypted database
+pub(crate) type ConnectionSetup = Arc<dyn Fn(&rusqlite::Connection) -> rusqlite::Result<()> + Send + Sync>;
+
+/// Pool of read-only connections to one database file
+pub struct ReadPool {
+    path: Option<PathBuf>,
+    connections: RwLock<Vec<Arc<Connection>>>,
+    next: AtomicUsize,
+}
+
+impl ReadPool {
+    /// Pool without connections, for in-memory databases
+    pub(crate) fn empty() -> Self {
+        Self {
+            path: None,
+            connections: RwLock::new(Vec::new()),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Open `size` read-only connections to the database at `path`
+    pub(crate) async fn open(path: &Path, size: usize, setup: ConnectionSetup) -> Result<Self> {
+        let pool = Self {
+            path: Some(path.to_path_buf()),
+            connections: RwLock::new(Vec::new()),
+            next: AtomicUsize::new(0),
+        };
+        let connections = pool.open_connections(size, setup).await?;
+        *pool.connections.write().unwrap_or_else(|e| e.into_inner()) = connections;
+        Ok(pool)
+    }
+
+    /// Replace every connection with a new one after the key changed
+    #[cfg(feature = "encryption")]
+    pub(crate) async fn reopen(&self, setup: ConnectionSetup) -> Result<()> {
+        let connections = self.open_connections(self.size(), setup).await?;
+        *self.connections.write().unwrap_or_else(|e| e.into_inner()) = connections;
+        Ok(())
+    }
+
+    async fn open_connections(&self, size: usize, setup: ConnectionSetup) -> Result<Vec<Arc<Connection>>> {
+        let Some(path) = &self.path else {
+            return Ok(Vec::new());
+        };
+
+        let mut connections = Vec::with_capacity(size);
+        for _ in 0..size {
+            let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX | OpenFlags::SQLITE_OPEN_URI;
+            let connection = Connection::open_with_flags(path, flags).await.map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to open read connection to {:?}: {}", path, e),
+                },
+            })?;
+            let setup = Arc::clone(&setup);
+            connection
+                .call(move |conn| {
+                    setup(conn)?;
+                    conn.busy_timeout(BUSY_TIMEOUT)?;
+                    conn.execute_batch("PRAGMA temp_store = MEMORY; PRAGMA mmap_size = 67108864;")?;
+                    // Parse the schema now rather than on the first query
+                    conn.query_row("SELECT count(*) FROM sqlite_schema", [], |_| Ok(()))?;
+                    Ok(())
+                })
+                .await
+                .map_err(|e| WebPageManagerError::System {
+                    source: SystemError::Configuration {
+                        details: format!("Failed to set up read connection to {:?}: {}", path, e),
+                    },
+                })?;
+            connections.push(Arc::new(connection));
+        }
+        Ok(connections)
+    }
+
+    /// Next read connection, or `None` if the pool has none
+    pub fn get(&self) -> Option<Arc<Connection>> {
+        let connections = self.connections.read().unwrap_or_else(|e| e.into_inner());
+        if connections.is_empty() {
+            return None;
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % connections.len();
+        Some(Arc::clone(&connections[index]))
+    }
+
+    /// Number of connections in the pool
+    pub fn size(&self) -> usize {
+        self.connections.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DatabaseManager, PageRepository};
+    use crate::test_support::{temp_dir, test_page};
+
+    #[tokio::test]
+    async fn test_reads_proceed_during_write_transaction() {
+        let dir = temp_dir("pool");
+        let db = DatabaseManager::new(dir.join("pages.db")).await.unwrap();
+        assert_eq!(db.read_pool().size(), super::DEFAULT_READ_CONNECTIONS);
+        let repo = db.page_repository();
+        repo.save(&test_page("https://example.com/first", "Pooled page")).await.unwrap();
+
+        // Readers are read-only
+        let reader = db.read_connection();
+        let write = reader
+            .call(|conn| Ok(conn.execute("DELETE FROM unified_pages", [])?))
+            .await;
+        assert!(write.is_err());
+
+        // Hold a write transaction open on the main connection
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let page = test_page("https://example.com/second", "Pooled page");
+        let writer = db.connection();
+        let write = tokio::spawn(async move {
+            writer
+                .call(move |conn| {
+                    let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+                    crate::repository::insert_page(&tx, &page)?;
+                    let _ = started_tx.send(());
+                    let _ = release_rx.recv();
+                    tx.commit()?;
+                    Ok(())
+                })
+                .await
+        });
+        started_rx.await.unwrap();
+
+        // Reads see the last committed state instead of waiting
+        assert_eq!(repo.count().await.unwrap(), 1);
+        assert_eq!(repo.search("pooled").await.unwrap().len(), 1);
+
+        release_tx.send(()).unwrap();
+        write.await.unwrap().unwrap();
+        assert_eq!(repo.count().await.unwrap(), 2);
+
+        drop(repo);
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
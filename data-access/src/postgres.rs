@@ -0,0 +1,1195 @@
+//! PostgreSQL storage backend
+//!
+//! Available with the `postgres` feature, for self-hosted deployments where
+//! a team shares one library. Pages, groups and history behave as in the
+//! SQLite repositories: pages are unique by canonical URL, deletions go to
+//! the trash and listings take the same keyset cursors. Full-text search
+//! uses PostgreSQL text search with prefix matching in place of FTS5.
+//!
+//! The schema is created on connect. A backend holds one connection: plain
+//! statements are pipelined on it while transactions have it to
+//! themselves, so a server with many users opens a backend per worker.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use tokio::sync::RwLock;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Client, GenericClient, NoTls, Row};
+use tracing::warn;
+use web_page_manager_core::*;
+
+use crate::backend::StorageBackend;
+use crate::canonical::canonical_url;
+use crate::repository::*;
+
+/// Key of the advisory lock serializing schema creation across backends
+const SCHEMA_LOCK: i64 = 0x7770_6d5f_7363_6865;
+
+/// Schema of the shared library, created if missing
+///
+/// Times are Unix seconds as in SQLite, so cursors from either backend
+/// compare the same way.
+const SCHEMA_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS unified_pages (
+    id UUID PRIMARY KEY,
+    url TEXT NOT NULL,
+    canonical_url TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    favicon_url TEXT,
+    content_summary JSONB,
+    keywords JSONB NOT NULL DEFAULT '[]',
+    category TEXT,
+    source_type JSONB NOT NULL,
+    browser_info JSONB,
+    tab_info JSONB,
+    bookmark_info JSONB,
+    created_at BIGINT NOT NULL,
+    last_accessed BIGINT NOT NULL,
+    access_count BIGINT NOT NULL DEFAULT 0,
+    deleted_at BIGINT,
+    search TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', title), 'A') ||
+        setweight(to_tsvector('simple', keywords::text), 'B') ||
+        setweight(to_tsvector('simple', coalesce(content_summary->>'summary_text', '')), 'C') ||
+        setweight(to_tsvector('simple', url), 'D')
+    ) STORED
+);
+CREATE INDEX IF NOT EXISTS idx_unified_pages_last_accessed ON unified_pages(last_accessed);
+CREATE INDEX IF NOT EXISTS idx_unified_pages_created_at ON unified_pages(created_at);
+CREATE INDEX IF NOT EXISTS idx_unified_pages_deleted_at ON unified_pages(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_unified_pages_search ON unified_pages USING GIN (search);
+
+CREATE TABLE IF NOT EXISTS smart_groups (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    group_type JSONB NOT NULL,
+    created_at BIGINT NOT NULL,
+    auto_generated BOOLEAN NOT NULL DEFAULT FALSE,
+    similarity_threshold REAL NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS idx_smart_groups_created_at ON smart_groups(created_at);
+
+CREATE TABLE IF NOT EXISTS page_group_relations (
+    page_id UUID NOT NULL REFERENCES unified_pages(id) ON DELETE CASCADE,
+    group_id UUID NOT NULL REFERENCES smart_groups(id) ON DELETE CASCADE,
+    added_at BIGINT NOT NULL,
+    confidence_score REAL,
+    PRIMARY KEY (page_id, group_id)
+);
+CREATE INDEX IF NOT EXISTS idx_page_group_relations_group ON page_group_relations(group_id);
+
+CREATE TABLE IF NOT EXISTS tab_history (
+    id UUID PRIMARY KEY,
+    page_id UUID,
+    url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    favicon_url TEXT,
+    browser_type JSONB NOT NULL,
+    tab_id UUID,
+    closed_at BIGINT NOT NULL,
+    session_info JSONB,
+    content_summary JSONB,
+    recall_hint TEXT,
+    deleted_at BIGINT,
+    search TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', title), 'A') ||
+        setweight(to_tsvector('simple', coalesce(recall_hint, '')), 'B') ||
+        setweight(to_tsvector('simple', url), 'D')
+    ) STORED
+);
+CREATE INDEX IF NOT EXISTS idx_tab_history_closed_at ON tab_history(closed_at);
+CREATE INDEX IF NOT EXISTS idx_tab_history_deleted_at ON tab_history(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_tab_history_search ON tab_history USING GIN (search);
+"#;
+
+const PAGE_COLUMNS: &str = "id, url, title, favicon_url, content_summary, keywords, category, \
+                            source_type, browser_info, tab_info, bookmark_info, created_at, last_accessed, access_count";
+
+const GROUP_COLUMNS: &str = "id, name, description, group_type, created_at, auto_generated, similarity_threshold";
+
+const HISTORY_COLUMNS: &str = "id, page_id, url, title, favicon_url, browser_type, tab_id, closed_at, \
+                               session_info, content_summary, recall_hint";
+
+/// Update a saved page in place; parameters as `INSERT_PAGE_SQL`
+const UPDATE_PAGE_SQL: &str = r#"
+UPDATE unified_pages SET
+    url = $2, title = $3, favicon_url = $4, content_summary = $5, keywords = $6, category = $7,
+    source_type = $8, browser_info = $9, tab_info = $10, bookmark_info = $11, created_at = $12,
+    last_accessed = $13, access_count = $14, canonical_url = $15, deleted_at = NULL
+WHERE id = $1
+"#;
+
+/// Insert a new page, merging it into a saved page with the same canonical URL
+const INSERT_PAGE_SQL: &str = r#"
+INSERT INTO unified_pages
+(id, url, title, favicon_url, content_summary, keywords, category,
+ source_type, browser_info, tab_info, bookmark_info, created_at, last_accessed, access_count, canonical_url)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+ON CONFLICT (canonical_url) DO UPDATE SET
+    url = excluded.url,
+    title = excluded.title,
+    favicon_url = COALESCE(excluded.favicon_url, unified_pages.favicon_url),
+    content_summary = COALESCE(excluded.content_summary, unified_pages.content_summary),
+    keywords = excluded.keywords,
+    category = COALESCE(excluded.category, unified_pages.category),
+    source_type = excluded.source_type,
+    browser_info = COALESCE(excluded.browser_info, unified_pages.browser_info),
+    tab_info = COALESCE(excluded.tab_info, unified_pages.tab_info),
+    bookmark_info = COALESCE(excluded.bookmark_info, unified_pages.bookmark_info),
+    created_at = LEAST(unified_pages.created_at, excluded.created_at),
+    last_accessed = GREATEST(unified_pages.last_accessed, excluded.last_accessed),
+    access_count = GREATEST(unified_pages.access_count, excluded.access_count),
+    deleted_at = NULL
+RETURNING id
+"#;
+
+fn postgres_error(action: &str, e: tokio_postgres::Error) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Failed to {}: {}", action, e),
+        },
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(value: Option<serde_json::Value>) -> Option<T> {
+    value.and_then(|v| serde_json::from_value(v).ok())
+}
+
+fn from_timestamp(ts: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(ts, 0).unwrap_or_else(Utc::now)
+}
+
+/// Statement parameters, numbered in the order they are pushed
+#[derive(Default)]
+struct Params(Vec<Box<dyn ToSql + Sync + Send>>);
+
+impl Params {
+    /// Add a parameter, returning its placeholder
+    fn push(&mut self, value: impl ToSql + Sync + Send + 'static) -> String {
+        self.0.push(Box::new(value));
+        format!("${}", self.0.len())
+    }
+
+    fn refs(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.0.iter().map(|p| p.as_ref() as &(dyn ToSql + Sync)).collect()
+    }
+}
+
+/// Text search query matching every word of `query` as a prefix, or `None`
+/// if it has no words
+fn prefix_tsquery(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("{}:*", term.to_lowercase()))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" & "))
+}
+
+/// Run a listing keyed on `key` with the `id` column breaking ties, as
+/// `repository::list_keyset` does for SQLite
+async fn list_keyset<T>(
+    client: &Client,
+    select: &str,
+    mut params: Params,
+    key: &str,
+    query: &ListQuery,
+    row_to_item: impl Fn(&Row) -> std::result::Result<T, tokio_postgres::Error>,
+    cursor_of: impl Fn(&T) -> Cursor,
+) -> std::result::Result<Paged<T>, tokio_postgres::Error> {
+    if query.limit == 0 {
+        return Ok(Paged { items: Vec::new(), next: None });
+    }
+
+    let direction = query.sort.sql();
+    let mut sql = format!("{} ", select);
+    if let Some(after) = &query.after {
+        let compare = match query.sort {
+            SortOrder::Descending => "<",
+            SortOrder::Ascending => ">",
+        };
+        let after_key = params.push(after.key);
+        let after_id = params.push(Uuid::parse_str(&after.id).unwrap_or_default());
+        sql.push_str(&format!(
+            "AND ({key} {compare} {after_key} OR ({key} = {after_key} AND id {compare} {after_id})) ",
+            key = key,
+            compare = compare,
+            after_key = after_key,
+            after_id = after_id
+        ));
+    }
+    sql.push_str(&format!(
+        "ORDER BY {key} {dir}, id {dir} LIMIT {limit} OFFSET {offset}",
+        key = key,
+        dir = direction,
+        limit = query.limit,
+        offset = query.offset
+    ));
+
+    let rows = client.query(&sql, &params.refs()).await?;
+    let items = rows.iter().map(row_to_item).collect::<std::result::Result<Vec<T>, _>>()?;
+    let next = if items.len() == query.limit { items.last().map(&cursor_of) } else { None };
+    Ok(Paged { items, next })
+}
+
+/// Shared PostgreSQL library
+#[derive(Clone)]
+pub struct PostgresBackend {
+    /// Plain statements share the client; transactions take it exclusively
+    client: Arc<RwLock<Client>>,
+}
+
+impl PostgresBackend {
+    /// Connect with a connection string, either `key=value` pairs such as
+    /// `host=db.internal user=wpm dbname=library` or a `postgresql://` URL,
+    /// and create the schema if it is missing
+    ///
+    /// The connection is not encrypted; keep the database on a private
+    /// network or behind a TLS-terminating proxy.
+    pub async fn connect(config: &str) -> Result<Self> {
+        let (mut client, connection) = tokio_postgres::connect(config, NoTls)
+            .await
+            .map_err(|e| postgres_error("connect to PostgreSQL", e))?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                warn!("PostgreSQL connection closed: {}", e);
+            }
+        });
+
+        // Backends starting together would otherwise race creating tables
+        let tx = client.transaction().await.map_err(|e| postgres_error("create schema", e))?;
+        tx.execute("SELECT pg_advisory_xact_lock($1)", &[&SCHEMA_LOCK])
+            .await
+            .map_err(|e| postgres_error("create schema", e))?;
+        tx.batch_execute(SCHEMA_SQL).await.map_err(|e| postgres_error("create schema", e))?;
+        tx.commit().await.map_err(|e| postgres_error("create schema", e))?;
+
+        Ok(Self { client: Arc::new(RwLock::new(client)) })
+    }
+
+    /// Create a page repository
+    pub fn page_repository(&self) -> PostgresPageRepository {
+        PostgresPageRepository { client: Arc::clone(&self.client) }
+    }
+
+    /// Create a group repository
+    pub fn group_repository(&self) -> PostgresGroupRepository {
+        PostgresGroupRepository { client: Arc::clone(&self.client) }
+    }
+
+    /// Create a history repository
+    pub fn history_repository(&self) -> PostgresHistoryRepository {
+        PostgresHistoryRepository { client: Arc::clone(&self.client) }
+    }
+}
+
+impl StorageBackend for PostgresBackend {
+    fn pages(&self) -> Arc<dyn PageRepository> {
+        Arc::new(self.page_repository())
+    }
+
+    fn groups(&self) -> Arc<dyn GroupRepository> {
+        Arc::new(self.group_repository())
+    }
+
+    fn history(&self) -> Arc<dyn HistoryRepository> {
+        Arc::new(self.history_repository())
+    }
+}
+
+/// Insert or update a page, returning the id it is stored under: its own,
+/// or that of the saved page with the same canonical URL it was merged into
+async fn upsert_page<C: GenericClient>(client: &C, page: &UnifiedPageInfo) -> std::result::Result<Uuid, tokio_postgres::Error> {
+    let content_summary = page.content_summary.as_ref().map(to_json);
+    let keywords = to_json(&page.keywords);
+    let source_type = to_json(&page.source_type);
+    let browser_info = page.browser_info.as_ref().map(to_json);
+    let tab_info = page.tab_info.as_ref().map(to_json);
+    let bookmark_info = page.bookmark_info.as_ref().map(to_json);
+    let (created_at, last_accessed) = (page.created_at.timestamp(), page.last_accessed.timestamp());
+    let access_count = page.access_count as i64;
+    let canonical = canonical_url(&page.url);
+    let params: [&(dyn ToSql + Sync); 15] = [
+        &page.id,
+        &page.url,
+        &page.title,
+        &page.favicon_url,
+        &content_summary,
+        &keywords,
+        &page.category,
+        &source_type,
+        &browser_info,
+        &tab_info,
+        &bookmark_info,
+        &created_at,
+        &last_accessed,
+        &access_count,
+        &canonical,
+    ];
+
+    // PostgreSQL takes a single conflict target, so a saved id is updated
+    // first and only new ids can merge by canonical URL
+    if client.execute(UPDATE_PAGE_SQL, &params).await? > 0 {
+        return Ok(page.id);
+    }
+    client.query_one(INSERT_PAGE_SQL, &params).await?.try_get(0)
+}
+
+fn row_to_page(row: &Row) -> std::result::Result<UnifiedPageInfo, tokio_postgres::Error> {
+    let access_count: i64 = row.try_get(13)?;
+    Ok(UnifiedPageInfo {
+        id: row.try_get(0)?,
+        url: row.try_get(1)?,
+        title: row.try_get(2)?,
+        favicon_url: row.try_get(3)?,
+        content_summary: from_json(row.try_get(4)?),
+        keywords: from_json(row.try_get(5)?).unwrap_or_default(),
+        category: row.try_get(6)?,
+        source_type: from_json(row.try_get(7)?).unwrap_or(PageSourceType::Bookmark {
+            browser: BrowserType::Chrome,
+            bookmark_id: BookmarkId::new(),
+        }),
+        browser_info: from_json(row.try_get(8)?),
+        tab_info: from_json(row.try_get(9)?),
+        bookmark_info: from_json(row.try_get(10)?),
+        created_at: from_timestamp(row.try_get(11)?),
+        last_accessed: from_timestamp(row.try_get(12)?),
+        access_count: access_count as u32,
+    })
+}
+
+/// `AND` conditions selecting the pages matched by `query`
+fn page_conditions(query: &PageQuery, params: &mut Params) -> Result<String> {
+    let mut sql = String::new();
+    if let Some(category) = &query.category {
+        sql.push_str(&format!(" AND category = {}", params.push(category.clone())));
+    }
+    if let Some(browser) = query.browser {
+        let name = serde_json::to_value(browser)
+            .ok()
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_default();
+        let name = params.push(name);
+        sql.push_str(&format!(
+            " AND (source_type->'ActiveTab'->>'browser' = {name} \
+             OR source_type->'Bookmark'->>'browser' = {name} \
+             OR browser_info->>'browser_type' = {name})",
+            name = name
+        ));
+    }
+    if let Some(source) = query.source {
+        let variant = match source {
+            PageRawSourceType::ActiveTab => "ActiveTab",
+            PageRawSourceType::Bookmark => "Bookmark",
+            PageRawSourceType::ClosedTab => "ClosedTab",
+            PageRawSourceType::ArchivedContent => "ArchivedContent",
+        };
+        sql.push_str(&format!(" AND source_type ? {}", params.push(variant)));
+    }
+    for (column, compare, bound) in [
+        ("created_at", ">=", query.created_after),
+        ("created_at", "<", query.created_before),
+        ("last_accessed", ">=", query.accessed_after),
+        ("last_accessed", "<", query.accessed_before),
+    ] {
+        if let Some(bound) = bound {
+            sql.push_str(&format!(" AND {} {} {}", column, compare, params.push(bound.timestamp())));
+        }
+    }
+    match query.has_summary {
+        Some(true) => sql.push_str(" AND content_summary IS NOT NULL"),
+        Some(false) => sql.push_str(" AND content_summary IS NULL"),
+        None => {}
+    }
+    if query.tag.is_some() {
+        return Err(WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: "Tag filters are not supported by the PostgreSQL backend".to_string(),
+            },
+        });
+    }
+    Ok(sql)
+}
+
+/// PostgreSQL implementation of PageRepository
+#[derive(Clone)]
+pub struct PostgresPageRepository {
+    client: Arc<RwLock<Client>>,
+}
+
+impl PostgresPageRepository {
+    /// Save pages in one transaction, returning the id each was stored
+    /// under, as `SqlitePageRepository::save_resolving` does
+    pub async fn save_resolving(&self, pages: &[UnifiedPageInfo]) -> Result<Vec<Uuid>> {
+        let mut client = self.client.write().await;
+        let run = async {
+            let tx = client.transaction().await?;
+            let mut ids = Vec::with_capacity(pages.len());
+            for page in pages {
+                ids.push(upsert_page(&tx, page).await?);
+            }
+            tx.commit().await?;
+            Ok(ids)
+        };
+        run.await.map_err(|e| postgres_error("save pages", e))
+    }
+
+    async fn query_pages(&self, action: &str, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<UnifiedPageInfo>> {
+        let client = self.client.read().await;
+        let rows = client.query(sql, params).await.map_err(|e| postgres_error(action, e))?;
+        rows.iter()
+            .map(row_to_page)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| postgres_error(action, e))
+    }
+}
+
+#[async_trait]
+impl PageRepository for PostgresPageRepository {
+    async fn save(&self, page: &UnifiedPageInfo) -> Result<()> {
+        let client = self.client.read().await;
+        upsert_page(&*client, page).await.map_err(|e| postgres_error("save page", e))?;
+        Ok(())
+    }
+
+    async fn save_batch(&self, pages: &[UnifiedPageInfo]) -> Result<()> {
+        if pages.is_empty() {
+            return Ok(());
+        }
+        self.save_resolving(pages).await?;
+        Ok(())
+    }
+
+    async fn get_by_id(&self, id: &Uuid) -> Result<Option<UnifiedPageInfo>> {
+        let sql = format!("SELECT {} FROM unified_pages WHERE id = $1 AND deleted_at IS NULL", PAGE_COLUMNS);
+        Ok(self.query_pages("get page", &sql, &[id]).await?.pop())
+    }
+
+    async fn get_by_url(&self, url: &str) -> Result<Option<UnifiedPageInfo>> {
+        let sql = format!("SELECT {} FROM unified_pages WHERE canonical_url = $1 AND deleted_at IS NULL", PAGE_COLUMNS);
+        Ok(self.query_pages("get page by URL", &sql, &[&canonical_url(url)]).await?.pop())
+    }
+
+    async fn get_all(&self) -> Result<Vec<UnifiedPageInfo>> {
+        let sql = format!(
+            "SELECT {} FROM unified_pages WHERE deleted_at IS NULL ORDER BY last_accessed DESC",
+            PAGE_COLUMNS
+        );
+        self.query_pages("get all pages", &sql, &[]).await
+    }
+
+    async fn get_paginated(&self, limit: usize, offset: usize) -> Result<Vec<UnifiedPageInfo>> {
+        let sql = format!(
+            "SELECT {} FROM unified_pages WHERE deleted_at IS NULL ORDER BY last_accessed DESC LIMIT $1 OFFSET $2",
+            PAGE_COLUMNS
+        );
+        self.query_pages("get paginated pages", &sql, &[&(limit as i64), &(offset as i64)]).await
+    }
+
+    async fn list(&self, query: &PageQuery) -> Result<Paged<UnifiedPageInfo>> {
+        let sort = query.sort;
+        let window = ListQuery {
+            after: query.after.clone(),
+            offset: query.offset,
+            limit: query.limit,
+            sort: query.order,
+        };
+        let mut params = Params::default();
+        let conditions = page_conditions(query, &mut params)?;
+        let select = format!("SELECT {} FROM unified_pages WHERE deleted_at IS NULL{}", PAGE_COLUMNS, conditions);
+
+        let client = self.client.read().await;
+        list_keyset(&client, &select, params, sort.column(), &window, row_to_page, |page| Cursor {
+            key: sort.key(page),
+            id: page.id.to_string(),
+        })
+        .await
+        .map_err(|e| postgres_error("list pages", e))
+    }
+
+    fn stream_all(&self) -> BoxStream<'static, Result<UnifiedPageInfo>> {
+        let repo = self.clone();
+        stream_keyset(move |after| {
+            let repo = repo.clone();
+            async move {
+                let query = PageQuery {
+                    after,
+                    limit: STREAM_BATCH_SIZE,
+                    sort: PageSort::CreatedAt,
+                    order: SortOrder::Ascending,
+                    ..Default::default()
+                };
+                repo.list(&query).await
+            }
+        })
+    }
+
+    async fn delete(&self, id: &Uuid) -> Result<()> {
+        let client = self.client.read().await;
+        client
+            .execute(
+                "UPDATE unified_pages SET deleted_at = $1 WHERE id = $2 AND deleted_at IS NULL",
+                &[&Utc::now().timestamp(), id],
+            )
+            .await
+            .map_err(|e| postgres_error("delete page", e))?;
+        Ok(())
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<UnifiedPageInfo>> {
+        self.search_with_limit(query, 100).await
+    }
+
+    async fn search_with_limit(&self, query: &str, limit: usize) -> Result<Vec<UnifiedPageInfo>> {
+        let hits = self.search_ranked(query, limit).await?;
+        Ok(hits.into_iter().map(|hit| hit.page).collect())
+    }
+
+    async fn search_ranked(&self, query: &str, limit: usize) -> Result<Vec<PageSearchHit>> {
+        let Some(tsquery) = prefix_tsquery(query) else {
+            return Ok(Vec::new());
+        };
+        let highlight = format!("HighlightAll=true, StartSel=\"{}\", StopSel=\"{}\"", HIGHLIGHT_START, HIGHLIGHT_END);
+        let snippet = format!("MaxWords=16, MinWords=5, StartSel=\"{}\", StopSel=\"{}\"", HIGHLIGHT_START, HIGHLIGHT_END);
+        let sql = format!(
+            r#"
+            SELECT {}, ts_rank(search, q)::FLOAT8 AS score,
+                   ts_headline('simple', title, q, $2),
+                   ts_headline('simple', concat_ws(' ', title, content_summary->>'summary_text', keywords::text, url), q, $3)
+            FROM unified_pages, to_tsquery('simple', $1) q
+            WHERE search @@ q AND deleted_at IS NULL
+            ORDER BY score DESC, last_accessed DESC
+            LIMIT $4
+            "#,
+            PAGE_COLUMNS
+        );
+
+        let client = self.client.read().await;
+        let rows = client
+            .query(&sql, &[&tsquery, &highlight, &snippet, &(limit as i64)])
+            .await
+            .map_err(|e| postgres_error("search pages", e))?;
+        rows.iter()
+            .map(|row| {
+                Ok(PageSearchHit {
+                    page: row_to_page(row)?,
+                    score: row.try_get(14)?,
+                    highlighted_title: row.try_get(15)?,
+                    snippet: row.try_get(16)?,
+                })
+            })
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| postgres_error("search pages", e))
+    }
+
+    async fn update_access(&self, id: &Uuid) -> Result<()> {
+        let client = self.client.read().await;
+        client
+            .execute(
+                "UPDATE unified_pages SET last_accessed = $1, access_count = access_count + 1 WHERE id = $2",
+                &[&Utc::now().timestamp(), id],
+            )
+            .await
+            .map_err(|e| postgres_error("update page access", e))?;
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<usize> {
+        let client = self.client.read().await;
+        let count: i64 = client
+            .query_one("SELECT COUNT(*) FROM unified_pages WHERE deleted_at IS NULL", &[])
+            .await
+            .and_then(|row| row.try_get(0))
+            .map_err(|e| postgres_error("count pages", e))?;
+        Ok(count as usize)
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<bool> {
+        let client = self.client.read().await;
+        let restored = client
+            .execute("UPDATE unified_pages SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL", &[id])
+            .await
+            .map_err(|e| postgres_error("restore page", e))?;
+        Ok(restored > 0)
+    }
+
+    async fn get_trash(&self) -> Result<Vec<Trashed<UnifiedPageInfo>>> {
+        let sql = format!(
+            "SELECT {}, deleted_at FROM unified_pages WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, id DESC",
+            PAGE_COLUMNS
+        );
+        let client = self.client.read().await;
+        let rows = client.query(&sql, &[]).await.map_err(|e| postgres_error("get trashed pages", e))?;
+        rows.iter()
+            .map(|row| {
+                Ok(Trashed {
+                    item: row_to_page(row)?,
+                    deleted_at: from_timestamp(row.try_get(14)?),
+                })
+            })
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| postgres_error("get trashed pages", e))
+    }
+
+    async fn purge_trash(&self, before: DateTime<Utc>) -> Result<usize> {
+        let client = self.client.read().await;
+        let purged = client
+            .execute(
+                "DELETE FROM unified_pages WHERE deleted_at IS NOT NULL AND deleted_at < $1",
+                &[&before.timestamp()],
+            )
+            .await
+            .map_err(|e| postgres_error("purge trashed pages", e))?;
+        Ok(purged as usize)
+    }
+}
+
+async fn upsert_group<C: GenericClient>(client: &C, group: &SmartGroup) -> std::result::Result<(), tokio_postgres::Error> {
+    client
+        .execute(
+            r#"
+            INSERT INTO smart_groups (id, name, description, group_type, created_at, auto_generated, similarity_threshold)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (id) DO UPDATE SET
+                name = excluded.name,
+                description = excluded.description,
+                group_type = excluded.group_type,
+                created_at = excluded.created_at,
+                auto_generated = excluded.auto_generated,
+                similarity_threshold = excluded.similarity_threshold
+            "#,
+            &[
+                &group.id,
+                &group.name,
+                &group.description,
+                &to_json(&group.group_type),
+                &group.created_at.timestamp(),
+                &group.auto_generated,
+                &group.similarity_threshold,
+            ],
+        )
+        .await?;
+    Ok(())
+}
+
+fn row_to_group(row: &Row) -> std::result::Result<SmartGroup, tokio_postgres::Error> {
+    let description: Option<String> = row.try_get(2)?;
+    Ok(SmartGroup {
+        id: row.try_get(0)?,
+        name: row.try_get(1)?,
+        description: description.unwrap_or_default(),
+        group_type: from_json(row.try_get(3)?).unwrap_or(GroupType::UserDefined),
+        pages: vec![], // Pages are loaded separately
+        created_at: from_timestamp(row.try_get(4)?),
+        auto_generated: row.try_get(5)?,
+        similarity_threshold: row.try_get(6)?,
+    })
+}
+
+/// PostgreSQL implementation of GroupRepository
+#[derive(Clone)]
+pub struct PostgresGroupRepository {
+    client: Arc<RwLock<Client>>,
+}
+
+impl PostgresGroupRepository {
+    async fn query_ids(&self, action: &str, sql: &str, id: &Uuid) -> Result<Vec<Uuid>> {
+        let client = self.client.read().await;
+        let rows = client.query(sql, &[id]).await.map_err(|e| postgres_error(action, e))?;
+        rows.iter()
+            .map(|row| row.try_get(0))
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| postgres_error(action, e))
+    }
+}
+
+#[async_trait]
+impl GroupRepository for PostgresGroupRepository {
+    async fn save(&self, group: &SmartGroup) -> Result<()> {
+        let client = self.client.read().await;
+        upsert_group(&*client, group).await.map_err(|e| postgres_error("save group", e))
+    }
+
+    async fn save_batch(&self, groups: &[SmartGroup]) -> Result<()> {
+        if groups.is_empty() {
+            return Ok(());
+        }
+        let mut client = self.client.write().await;
+        let run = async {
+            let tx = client.transaction().await?;
+            for group in groups {
+                upsert_group(&tx, group).await?;
+            }
+            tx.commit().await
+        };
+        run.await.map_err(|e| postgres_error("batch save groups", e))
+    }
+
+    async fn get_by_id(&self, id: &Uuid) -> Result<Option<SmartGroup>> {
+        let sql = format!("SELECT {} FROM smart_groups WHERE id = $1", GROUP_COLUMNS);
+        let client = self.client.read().await;
+        let row = client.query_opt(&sql, &[id]).await.map_err(|e| postgres_error("get group", e))?;
+        row.as_ref().map(row_to_group).transpose().map_err(|e| postgres_error("get group", e))
+    }
+
+    async fn get_all(&self) -> Result<Vec<SmartGroup>> {
+        let sql = format!("SELECT {} FROM smart_groups ORDER BY created_at DESC", GROUP_COLUMNS);
+        let client = self.client.read().await;
+        let rows = client.query(&sql, &[]).await.map_err(|e| postgres_error("get all groups", e))?;
+        rows.iter()
+            .map(row_to_group)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| postgres_error("get all groups", e))
+    }
+
+    async fn list(&self, query: &ListQuery) -> Result<Paged<SmartGroup>> {
+        let select = format!("SELECT {} FROM smart_groups WHERE TRUE", GROUP_COLUMNS);
+        let client = self.client.read().await;
+        list_keyset(&client, &select, Params::default(), "created_at", query, row_to_group, |group| Cursor {
+            key: group.created_at.timestamp(),
+            id: group.id.to_string(),
+        })
+        .await
+        .map_err(|e| postgres_error("list groups", e))
+    }
+
+    fn stream_all(&self) -> BoxStream<'static, Result<SmartGroup>> {
+        let repo = self.clone();
+        stream_keyset(move |after| {
+            let repo = repo.clone();
+            async move {
+                let query = ListQuery { after, limit: STREAM_BATCH_SIZE, sort: SortOrder::Ascending, ..Default::default() };
+                repo.list(&query).await
+            }
+        })
+    }
+
+    async fn delete(&self, id: &Uuid) -> Result<()> {
+        let client = self.client.read().await;
+        client
+            .execute("DELETE FROM smart_groups WHERE id = $1", &[id])
+            .await
+            .map_err(|e| postgres_error("delete group", e))?;
+        Ok(())
+    }
+
+    async fn add_page_to_group(&self, page_id: &Uuid, group_id: &Uuid, confidence: f32) -> Result<()> {
+        let client = self.client.read().await;
+        client
+            .execute(
+                r#"
+                INSERT INTO page_group_relations (page_id, group_id, added_at, confidence_score)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (page_id, group_id) DO UPDATE SET
+                    added_at = excluded.added_at,
+                    confidence_score = excluded.confidence_score
+                "#,
+                &[page_id, group_id, &Utc::now().timestamp(), &confidence],
+            )
+            .await
+            .map_err(|e| postgres_error("add page to group", e))?;
+        Ok(())
+    }
+
+    async fn remove_page_from_group(&self, page_id: &Uuid, group_id: &Uuid) -> Result<()> {
+        let client = self.client.read().await;
+        client
+            .execute(
+                "DELETE FROM page_group_relations WHERE page_id = $1 AND group_id = $2",
+                &[page_id, group_id],
+            )
+            .await
+            .map_err(|e| postgres_error("remove page from group", e))?;
+        Ok(())
+    }
+
+    async fn get_pages_in_group(&self, group_id: &Uuid) -> Result<Vec<Uuid>> {
+        self.query_ids(
+            "get pages in group",
+            "SELECT r.page_id FROM page_group_relations r JOIN unified_pages p ON p.id = r.page_id \
+             WHERE r.group_id = $1 AND p.deleted_at IS NULL ORDER BY r.confidence_score DESC",
+            group_id,
+        )
+        .await
+    }
+
+    async fn get_groups_for_page(&self, page_id: &Uuid) -> Result<Vec<Uuid>> {
+        self.query_ids(
+            "get groups for page",
+            "SELECT group_id FROM page_group_relations WHERE page_id = $1",
+            page_id,
+        )
+        .await
+    }
+}
+
+async fn upsert_history_entry<C: GenericClient>(client: &C, entry: &HistoryEntry) -> std::result::Result<(), tokio_postgres::Error> {
+    client
+        .execute(
+            r#"
+            INSERT INTO tab_history
+            (id, page_id, url, title, favicon_url, browser_type, tab_id, closed_at, session_info, content_summary, recall_hint)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (id) DO UPDATE SET
+                page_id = excluded.page_id,
+                url = excluded.url,
+                title = excluded.title,
+                favicon_url = excluded.favicon_url,
+                browser_type = excluded.browser_type,
+                tab_id = excluded.tab_id,
+                closed_at = excluded.closed_at,
+                session_info = excluded.session_info,
+                content_summary = excluded.content_summary,
+                recall_hint = excluded.recall_hint,
+                deleted_at = NULL
+            "#,
+            &[
+                &entry.id.0,
+                &entry.page_info.id,
+                &entry.page_info.url,
+                &entry.page_info.title,
+                &entry.page_info.favicon_url,
+                &to_json(&entry.browser_type),
+                &entry.tab_id.as_ref().map(|t| t.0),
+                &entry.closed_at.timestamp(),
+                &entry.session_info.as_ref().map(to_json),
+                &entry.page_info.content_summary.as_ref().map(to_json),
+                &entry.recall_hint,
+            ],
+        )
+        .await?;
+    Ok(())
+}
+
+fn row_to_history_entry(row: &Row) -> std::result::Result<HistoryEntry, tokio_postgres::Error> {
+    let id = HistoryId(row.try_get(0)?);
+    let page_id: Option<Uuid> = row.try_get(1)?;
+    let closed_at = from_timestamp(row.try_get(7)?);
+    let tab_id: Option<Uuid> = row.try_get(6)?;
+
+    let page_info = UnifiedPageInfo {
+        id: page_id.unwrap_or_else(Uuid::new_v4),
+        url: row.try_get(2)?,
+        title: row.try_get(3)?,
+        favicon_url: row.try_get(4)?,
+        content_summary: from_json(row.try_get(9)?),
+        keywords: vec![],
+        category: None,
+        source_type: PageSourceType::ClosedTab { history_id: id.clone() },
+        browser_info: None,
+        tab_info: None,
+        bookmark_info: None,
+        created_at: closed_at,
+        last_accessed: closed_at,
+        access_count: 0,
+    };
+
+    Ok(HistoryEntry {
+        id,
+        page_info,
+        browser_type: from_json(row.try_get(5)?).unwrap_or(BrowserType::Chrome),
+        tab_id: tab_id.map(TabId),
+        closed_at,
+        session_info: from_json(row.try_get(8)?),
+        recall_hint: row.try_get(10)?,
+    })
+}
+
+/// PostgreSQL implementation of HistoryRepository
+#[derive(Clone)]
+pub struct PostgresHistoryRepository {
+    client: Arc<RwLock<Client>>,
+}
+
+impl PostgresHistoryRepository {
+    async fn query_entries(&self, action: &str, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<HistoryEntry>> {
+        let client = self.client.read().await;
+        let rows = client.query(sql, params).await.map_err(|e| postgres_error(action, e))?;
+        rows.iter()
+            .map(row_to_history_entry)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| postgres_error(action, e))
+    }
+}
+
+#[async_trait]
+impl HistoryRepository for PostgresHistoryRepository {
+    async fn save(&self, entry: &HistoryEntry) -> Result<()> {
+        let client = self.client.read().await;
+        upsert_history_entry(&*client, entry).await.map_err(|e| postgres_error("save history entry", e))
+    }
+
+    async fn save_batch(&self, entries: &[HistoryEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let mut client = self.client.write().await;
+        let run = async {
+            let tx = client.transaction().await?;
+            for entry in entries {
+                upsert_history_entry(&tx, entry).await?;
+            }
+            tx.commit().await
+        };
+        run.await.map_err(|e| postgres_error("batch save history entries", e))
+    }
+
+    async fn get_by_id(&self, id: &HistoryId) -> Result<Option<HistoryEntry>> {
+        let sql = format!("SELECT {} FROM tab_history WHERE id = $1 AND deleted_at IS NULL", HISTORY_COLUMNS);
+        Ok(self.query_entries("get history entry", &sql, &[&id.0]).await?.pop())
+    }
+
+    async fn get_filtered(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>> {
+        let mut params = Params::default();
+        let mut sql = format!("SELECT {} FROM tab_history WHERE deleted_at IS NULL", HISTORY_COLUMNS);
+        if let Some(browser) = &filter.browser_type {
+            sql.push_str(&format!(" AND browser_type = {}", params.push(to_json(browser))));
+        }
+        if let Some(from) = filter.from_date {
+            sql.push_str(&format!(" AND closed_at >= {}", params.push(from.timestamp())));
+        }
+        if let Some(to) = filter.to_date {
+            sql.push_str(&format!(" AND closed_at <= {}", params.push(to.timestamp())));
+        }
+        if let Some(url_pattern) = &filter.url_pattern {
+            sql.push_str(&format!(" AND url ILIKE {}", params.push(format!("%{}%", url_pattern))));
+        }
+        if let Some(title_pattern) = &filter.title_pattern {
+            sql.push_str(&format!(" AND title ILIKE {}", params.push(format!("%{}%", title_pattern))));
+        }
+        sql.push_str(" ORDER BY closed_at DESC");
+        if let Some(limit) = filter.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        if let Some(offset) = filter.offset {
+            sql.push_str(&format!(" OFFSET {}", offset));
+        }
+        self.query_entries("get filtered history", &sql, &params.refs()).await
+    }
+
+    async fn list(&self, query: &ListQuery) -> Result<Paged<HistoryEntry>> {
+        let select = format!("SELECT {} FROM tab_history WHERE deleted_at IS NULL", HISTORY_COLUMNS);
+        let client = self.client.read().await;
+        list_keyset(&client, &select, Params::default(), "closed_at", query, row_to_history_entry, |entry| Cursor {
+            key: entry.closed_at.timestamp(),
+            id: entry.id.0.to_string(),
+        })
+        .await
+        .map_err(|e| postgres_error("list history", e))
+    }
+
+    fn stream_all(&self) -> BoxStream<'static, Result<HistoryEntry>> {
+        let repo = self.clone();
+        stream_keyset(move |after| {
+            let repo = repo.clone();
+            async move {
+                let query = ListQuery { after, limit: STREAM_BATCH_SIZE, sort: SortOrder::Ascending, ..Default::default() };
+                repo.list(&query).await
+            }
+        })
+    }
+
+    async fn delete(&self, id: &HistoryId) -> Result<()> {
+        let client = self.client.read().await;
+        client
+            .execute(
+                "UPDATE tab_history SET deleted_at = $1 WHERE id = $2 AND deleted_at IS NULL",
+                &[&Utc::now().timestamp(), &id.0],
+            )
+            .await
+            .map_err(|e| postgres_error("delete history entry", e))?;
+        Ok(())
+    }
+
+    async fn delete_older_than(&self, timestamp: DateTime<Utc>) -> Result<usize> {
+        let client = self.client.read().await;
+        let deleted = client
+            .execute("DELETE FROM tab_history WHERE closed_at < $1", &[&timestamp.timestamp()])
+            .await
+            .map_err(|e| postgres_error("delete old history", e))?;
+        Ok(deleted as usize)
+    }
+
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<HistoryEntry>> {
+        let Some(tsquery) = prefix_tsquery(query) else {
+            return Ok(Vec::new());
+        };
+        let sql = format!(
+            "SELECT {} FROM tab_history, to_tsquery('simple', $1) q \
+             WHERE search @@ q AND deleted_at IS NULL \
+             ORDER BY ts_rank(search, q) DESC, closed_at DESC LIMIT $2",
+            HISTORY_COLUMNS
+        );
+        self.query_entries("search history", &sql, &[&tsquery, &(limit as i64)]).await
+    }
+
+    async fn count(&self) -> Result<usize> {
+        let client = self.client.read().await;
+        let count: i64 = client
+            .query_one("SELECT COUNT(*) FROM tab_history WHERE deleted_at IS NULL", &[])
+            .await
+            .and_then(|row| row.try_get(0))
+            .map_err(|e| postgres_error("count history", e))?;
+        Ok(count as usize)
+    }
+
+    async fn set_recall_hint(&self, id: &HistoryId, hint: Option<&str>) -> Result<bool> {
+        let client = self.client.read().await;
+        let updated = client
+            .execute("UPDATE tab_history SET recall_hint = $1 WHERE id = $2", &[&hint, &id.0])
+            .await
+            .map_err(|e| postgres_error("set recall hint", e))?;
+        Ok(updated > 0)
+    }
+
+    async fn restore(&self, id: &HistoryId) -> Result<bool> {
+        let client = self.client.read().await;
+        let restored = client
+            .execute("UPDATE tab_history SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL", &[&id.0])
+            .await
+            .map_err(|e| postgres_error("restore history entry", e))?;
+        Ok(restored > 0)
+    }
+
+    async fn get_trash(&self) -> Result<Vec<Trashed<HistoryEntry>>> {
+        let sql = format!(
+            "SELECT {}, deleted_at FROM tab_history WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, id DESC",
+            HISTORY_COLUMNS
+        );
+        let client = self.client.read().await;
+        let rows = client.query(&sql, &[]).await.map_err(|e| postgres_error("get trashed history", e))?;
+        rows.iter()
+            .map(|row| {
+                Ok(Trashed {
+                    item: row_to_history_entry(row)?,
+                    deleted_at: from_timestamp(row.try_get(11)?),
+                })
+            })
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| postgres_error("get trashed history", e))
+    }
+
+    async fn purge_trash(&self, before: DateTime<Utc>) -> Result<usize> {
+        let client = self.client.read().await;
+        let purged = client
+            .execute(
+                "DELETE FROM tab_history WHERE deleted_at IS NOT NULL AND deleted_at < $1",
+                &[&before.timestamp()],
+            )
+            .await
+            .map_err(|e| postgres_error("purge trashed history", e))?;
+        Ok(purged as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_page;
+
+    /// Backend in a fresh schema of the database named by
+    /// `WPM_TEST_POSTGRES_URL` (`key=value` form), or `None` to skip
+    async fn test_backend() -> Option<PostgresBackend> {
+        let url = std::env::var("WPM_TEST_POSTGRES_URL").ok()?;
+        let schema = format!("wpm_test_{}", Uuid::new_v4().simple());
+        let (client, connection) = tokio_postgres::connect(&url, NoTls).await.unwrap();
+        tokio::spawn(connection);
+        client.batch_execute(&format!("CREATE SCHEMA {}", schema)).await.unwrap();
+        Some(
+            PostgresBackend::connect(&format!("{} options='-c search_path={}'", url, schema))
+                .await
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_prefix_tsquery() {
+        assert_eq!(prefix_tsquery("Rust async!"), Some("rust:* & async:*".to_string()));
+        assert_eq!(prefix_tsquery("  & | "), None);
+    }
+
+    #[tokio::test]
+    async fn test_pages_merge_by_canonical_url_and_search() {
+        let Some(backend) = test_backend().await else {
+            return;
+        };
+        let pages = backend.pages();
+
+        let page = test_page("https://example.com/guide", "Async Rust guide");
+        pages.save(&page).await.unwrap();
+        let again = test_page("https://Example.com/guide?utm_source=mail", "Async Rust guide, 2nd edition");
+        let ids = backend.page_repository().save_resolving(&[again]).await.unwrap();
+        assert_eq!(ids, vec![page.id]);
+        assert_eq!(pages.count().await.unwrap(), 1);
+
+        let hits = pages.search_ranked("asyn", 10).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].highlighted_title.contains("<mark>Async</mark>"));
+
+        let listed = pages.list(&PageQuery::new().browser(BrowserType::Chrome).source(PageRawSourceType::ActiveTab)).await.unwrap();
+        assert_eq!(listed.items.len(), 1);
+        assert!(pages.list(&PageQuery::new().browser(BrowserType::Firefox)).await.unwrap().items.is_empty());
+
+        pages.delete(&page.id).await.unwrap();
+        assert!(pages.get_by_url("https://example.com/guide").await.unwrap().is_none());
+        assert_eq!(pages.get_trash().await.unwrap().len(), 1);
+        assert!(pages.restore(&page.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_groups_and_history_listing() {
+        let Some(backend) = test_backend().await else {
+            return;
+        };
+        let page = test_page("https://example.com/", "Example");
+        backend.pages().save(&page).await.unwrap();
+
+        let group = SmartGroup {
+            id: Uuid::new_v4(),
+            name: "Reading".to_string(),
+            description: String::new(),
+            group_type: GroupType::UserDefined,
+            pages: vec![],
+            created_at: Utc::now(),
+            auto_generated: false,
+            similarity_threshold: 0.5,
+        };
+        let groups = backend.groups();
+        groups.save(&group).await.unwrap();
+        groups.add_page_to_group(&page.id, &group.id, 0.9).await.unwrap();
+        assert_eq!(groups.get_pages_in_group(&group.id).await.unwrap(), vec![page.id]);
+        assert_eq!(groups.get_by_id(&group.id).await.unwrap().unwrap().name, "Reading");
+
+        let history = backend.history();
+        let entries: Vec<HistoryEntry> = (0..5)
+            .map(|i| HistoryEntry {
+                id: HistoryId::new(),
+                page_info: test_page(&format!("https://example.com/{}", i), &format!("Closed tab {}", i)),
+                browser_type: BrowserType::Firefox,
+                tab_id: None,
+                closed_at: Utc::now() - chrono::Duration::seconds(i),
+                session_info: None,
+                recall_hint: None,
+            })
+            .collect();
+        history.save_batch(&entries).await.unwrap();
+
+        let first = history.list(&ListQuery { limit: 3, ..Default::default() }).await.unwrap();
+        let rest = history.list(&ListQuery { after: first.next.clone(), limit: 3, ..Default::default() }).await.unwrap();
+        let listed: Vec<HistoryId> = first.items.iter().chain(&rest.items).map(|e| e.id.clone()).collect();
+        assert_eq!(listed, entries.iter().map(|e| e.id.clone()).collect::<Vec<_>>());
+        assert!(rest.next.is_none());
+
+        assert!(history.set_recall_hint(&entries[2].id, Some("conference talk")).await.unwrap());
+        let found = history.search("confer", 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, entries[2].id);
+    }
+}
@@ -0,0 +1,250 @@
+//! Separate database profiles
+//!
+//! A profile is a complete, independent database (for example "work" and
+//! "personal") kept in its own subdirectory of the profiles root. Each open
+//! profile is its own `DatabaseManager`, so caches, the embedding index and
+//! the read pool are never shared between profiles.
+//!
+//! Switching profiles opens the new database before replacing the active
+//! one, so a failed switch leaves the current profile in use. Holders of the
+//! previous manager keep a working handle until they drop it; code that
+//! should follow the switch subscribes to the active profile. The active
+//! profile's name is stored in the root so the next start reopens it.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio::sync::{watch, Mutex};
+use tracing::info;
+use web_page_manager_core::*;
+
+use crate::{CacheConfig, DatabaseManager};
+
+/// Profile opened when none was chosen before
+pub const DEFAULT_PROFILE: &str = "default";
+/// Database file inside each profile directory
+const DATABASE_FILE: &str = "pages.db";
+/// File in the profiles root naming the active profile
+const ACTIVE_FILE: &str = "active_profile";
+
+/// A profile on disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    /// Path of the profile's database file
+    pub path: PathBuf,
+    pub active: bool,
+}
+
+/// The profile currently in use and its database
+#[derive(Clone)]
+pub struct ActiveProfile {
+    pub name: String,
+    pub database: Arc<DatabaseManager>,
+}
+
+/// Lists, creates and switches between database profiles
+pub struct ProfileManager {
+    root: PathBuf,
+    cache_config: CacheConfig,
+    active: watch::Sender<ActiveProfile>,
+    /// Serializes switches so two at once cannot interleave
+    switching: Mutex<()>,
+}
+
+fn profile_error(details: String) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration { details },
+    }
+}
+
+/// Check that `name` is usable as a directory name on every platform
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if valid {
+        Ok(())
+    } else {
+        Err(profile_error(format!(
+            "Invalid profile name '{}': use letters, digits, '-', '_' or '.'",
+            name
+        )))
+    }
+}
+
+impl ProfileManager {
+    /// Open the profiles under `root`, activating the profile that was
+    /// active last time, or the default profile on first use
+    pub async fn open<P: Into<PathBuf>>(root: P, cache_config: CacheConfig) -> Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)
+            .map_err(|e| profile_error(format!("Failed to create profiles directory: {}", e)))?;
+
+        let name = std::fs::read_to_string(root.join(ACTIVE_FILE))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|name| validate_name(name).is_ok() && root.join(name).join(DATABASE_FILE).exists())
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+
+        let database = Arc::new(Self::open_profile(&root, &name, &cache_config).await?);
+        info!("Opened profile '{}'", name);
+        let (active, _) = watch::channel(ActiveProfile { name, database });
+
+        Ok(Self {
+            root,
+            cache_config,
+            active,
+            switching: Mutex::new(()),
+        })
+    }
+
+    async fn open_profile(root: &Path, name: &str, cache_config: &CacheConfig) -> Result<DatabaseManager> {
+        let dir = root.join(name);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| profile_error(format!("Failed to create profile '{}': {}", name, e)))?;
+        DatabaseManager::with_cache_config(dir.join(DATABASE_FILE), cache_config.clone()).await
+    }
+
+    /// Directory holding the profiles
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Database of the active profile
+    pub fn active(&self) -> Arc<DatabaseManager> {
+        self.active.borrow().database.clone()
+    }
+
+    /// Name of the active profile
+    pub fn active_name(&self) -> String {
+        self.active.borrow().name.clone()
+    }
+
+    /// Follow the active profile; the receiver sees every switch
+    pub fn subscribe(&self) -> watch::Receiver<ActiveProfile> {
+        self.active.subscribe()
+    }
+
+    /// All profiles, sorted by name
+    pub fn list(&self) -> Result<Vec<ProfileInfo>> {
+        let active = self.active_name();
+        let entries = std::fs::read_dir(&self.root)
+            .map_err(|e| profile_error(format!("Failed to list profiles: {}", e)))?;
+
+        let mut profiles: Vec<ProfileInfo> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let path = entry.path().join(DATABASE_FILE);
+                (validate_name(&name).is_ok() && path.is_file()).then(|| ProfileInfo {
+                    active: name == active,
+                    name,
+                    path,
+                })
+            })
+            .collect();
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(profiles)
+    }
+
+    /// Create an empty profile without switching to it
+    pub async fn create(&self, name: &str) -> Result<ProfileInfo> {
+        validate_name(name)?;
+        let path = self.root.join(name).join(DATABASE_FILE);
+        if path.exists() {
+            return Err(profile_error(format!("Profile '{}' already exists", name)));
+        }
+
+        // Opening runs the migrations, leaving a ready database behind
+        Self::open_profile(&self.root, name, &self.cache_config).await?;
+        info!("Created profile '{}'", name);
+
+        Ok(ProfileInfo {
+            name: name.to_string(),
+            path,
+            active: false,
+        })
+    }
+
+    /// Make `name` the active profile and return its database
+    ///
+    /// The previous profile's cache is cleared; its database closes once
+    /// the last handle to it is dropped.
+    pub async fn switch_to(&self, name: &str) -> Result<Arc<DatabaseManager>> {
+        validate_name(name)?;
+        let _guard = self.switching.lock().await;
+
+        let current = self.active.borrow().clone();
+        if current.name == name {
+            return Ok(current.database);
+        }
+        if !self.root.join(name).join(DATABASE_FILE).is_file() {
+            return Err(profile_error(format!("Profile '{}' does not exist", name)));
+        }
+
+        let database = Arc::new(Self::open_profile(&self.root, name, &self.cache_config).await?);
+        std::fs::write(self.root.join(ACTIVE_FILE), name)
+            .map_err(|e| profile_error(format!("Failed to record active profile: {}", e)))?;
+
+        self.active.send_replace(ActiveProfile {
+            name: name.to_string(),
+            database: database.clone(),
+        });
+        current.database.clear_cache().await;
+        info!("Switched profile from '{}' to '{}'", current.name, name);
+
+        Ok(database)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CachedPageRepository, PageRepository};
+    use crate::test_support::{temp_dir, test_page};
+
+    #[tokio::test]
+    async fn test_profiles_are_isolated_and_switchable() {
+        let dir = temp_dir("profiles_switch");
+        let profiles = ProfileManager::open(&dir, CacheConfig::default()).await.unwrap();
+        assert_eq!(profiles.active_name(), DEFAULT_PROFILE);
+
+        let personal = test_page("https://example.com/personal", "Profiled");
+        let db = profiles.active();
+        let cached = CachedPageRepository::new(db.connection(), db.cache());
+        cached.save(&personal).await.unwrap();
+
+        profiles.create("work").await.unwrap();
+        assert!(profiles.create("work").await.is_err());
+        assert!(profiles.create("../escape").await.is_err());
+        assert!(profiles.switch_to("missing").await.is_err());
+        assert_eq!(profiles.active_name(), DEFAULT_PROFILE);
+
+        let mut changes = profiles.subscribe();
+        let work = profiles.switch_to("work").await.unwrap();
+        assert!(changes.has_changed().unwrap());
+        assert_eq!(changes.borrow_and_update().name, "work");
+
+        // Neither the database nor the cache of the other profile is visible
+        let work_cached = CachedPageRepository::new(work.connection(), work.cache());
+        assert!(work_cached.get_by_id(&personal.id).await.unwrap().is_none());
+        assert_eq!(work.page_repository().count().await.unwrap(), 0);
+        work.page_repository().save(&test_page("https://example.com/work", "Profiled")).await.unwrap();
+
+        let names: Vec<(String, bool)> = profiles.list().unwrap().into_iter().map(|p| (p.name, p.active)).collect();
+        assert_eq!(names, vec![("default".to_string(), false), ("work".to_string(), true)]);
+
+        // The active profile is remembered across restarts
+        drop((profiles, db, cached, work, work_cached, changes));
+        let reopened = ProfileManager::open(&dir, CacheConfig::default()).await.unwrap();
+        assert_eq!(reopened.active_name(), "work");
+        assert_eq!(reopened.active().page_repository().count().await.unwrap(), 1);
+
+        let back = reopened.switch_to(DEFAULT_PROFILE).await.unwrap();
+        assert!(back.page_repository().get_by_id(&personal.id).await.unwrap().is_some());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,218 @@
+//! Reader views of archived pages
+//!
+//! Alongside an archive's full HTML and snapshots, a reader view keeps the
+//! page's clean, readable version (title, byline, main content and its
+//! images) for the UI to render offline. A reader view belongs to its
+//! archive and goes with it.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rusqlite::{OptionalExtension, Row};
+use tokio_rusqlite::Connection;
+use web_page_manager_core::*;
+
+/// Reader version of an archived page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReaderView {
+    pub archive_id: ArchiveId,
+    pub title: String,
+    pub byline: Option<String>,
+    /// Main content as simple, self-contained HTML
+    pub content_html: String,
+    pub content_text: String,
+    /// Image URLs of the main content
+    pub images: Vec<String>,
+    pub word_count: usize,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Repository trait for reader views
+#[async_trait]
+pub trait ReaderViewRepository: Send + Sync {
+    /// Save the reader view of an archive, replacing an earlier one
+    async fn save(&self, view: &ReaderView) -> Result<()>;
+    async fn get(&self, archive_id: &ArchiveId) -> Result<Option<ReaderView>>;
+    /// Reader view of the latest archive of a page that has one
+    async fn get_for_page(&self, page_id: &Uuid) -> Result<Option<ReaderView>>;
+    /// Remove the reader view of an archive, returning whether it was there
+    async fn delete(&self, archive_id: &ArchiveId) -> Result<bool>;
+}
+
+/// SQLite implementation of ReaderViewRepository
+#[derive(Clone)]
+pub struct SqliteReaderViewRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteReaderViewRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+}
+
+fn reader_view_error(action: &str, e: tokio_rusqlite::Error) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Failed to {}: {}", action, e),
+        },
+    }
+}
+
+const VIEW_COLUMNS: &str = "v.archive_id, v.title, v.byline, v.content_html, v.content_text, v.images, v.word_count, v.created_at";
+
+/// Helper function to map a row to ReaderView
+fn row_to_view(row: &Row) -> rusqlite::Result<ReaderView> {
+    let archive_id: String = row.get(0)?;
+    let images: String = row.get(5)?;
+    Ok(ReaderView {
+        archive_id: ArchiveId(Uuid::parse_str(&archive_id).unwrap_or_else(|_| Uuid::new_v4())),
+        title: row.get(1)?,
+        byline: row.get(2)?,
+        content_html: row.get(3)?,
+        content_text: row.get(4)?,
+        images: serde_json::from_str(&images).unwrap_or_default(),
+        word_count: row.get::<_, i64>(6)? as usize,
+        created_at: DateTime::from_timestamp(row.get(7)?, 0).unwrap_or_else(Utc::now),
+    })
+}
+
+#[async_trait]
+impl ReaderViewRepository for SqliteReaderViewRepository {
+    async fn save(&self, view: &ReaderView) -> Result<()> {
+        let view = view.clone();
+
+        self.connection
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT OR REPLACE INTO reader_views \
+                     (archive_id, title, byline, content_html, content_text, images, word_count, created_at) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    rusqlite::params![
+                        view.archive_id.0.to_string(),
+                        view.title,
+                        view.byline,
+                        view.content_html,
+                        view.content_text,
+                        serde_json::to_string(&view.images).unwrap_or_else(|_| "[]".to_string()),
+                        view.word_count as i64,
+                        view.created_at.timestamp(),
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| reader_view_error("save reader view", e))
+    }
+
+    async fn get(&self, archive_id: &ArchiveId) -> Result<Option<ReaderView>> {
+        let archive_id = archive_id.0.to_string();
+
+        self.connection
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM reader_views v WHERE v.archive_id = ?1", VIEW_COLUMNS),
+                        [archive_id],
+                        row_to_view,
+                    )
+                    .optional()?)
+            })
+            .await
+            .map_err(|e| reader_view_error("get reader view", e))
+    }
+
+    async fn get_for_page(&self, page_id: &Uuid) -> Result<Option<ReaderView>> {
+        let page_id = page_id.to_string();
+
+        self.connection
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        &format!(
+                            "SELECT {} FROM reader_views v JOIN content_archives a ON a.id = v.archive_id \
+                             WHERE a.page_id = ?1 ORDER BY a.archived_at DESC, v.created_at DESC LIMIT 1",
+                            VIEW_COLUMNS
+                        ),
+                        [page_id],
+                        row_to_view,
+                    )
+                    .optional()?)
+            })
+            .await
+            .map_err(|e| reader_view_error("get reader view of page", e))
+    }
+
+    async fn delete(&self, archive_id: &ArchiveId) -> Result<bool> {
+        let archive_id = archive_id.0.to_string();
+
+        self.connection
+            .call(move |conn| Ok(conn.execute("DELETE FROM reader_views WHERE archive_id = ?1", [archive_id])? > 0))
+            .await
+            .map_err(|e| reader_view_error("delete reader view", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArchiveRepository, ContentArchive, DatabaseManager, PageRepository};
+
+    #[tokio::test]
+    async fn test_reader_views_follow_archives() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: "https://example.com/post".to_string(),
+            title: "Post".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Chrome,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        db.page_repository().save(&page).await.unwrap();
+        let archive = ContentArchive {
+            id: ArchiveId::new(),
+            page_id: page.id,
+            url: page.url.clone(),
+            title: page.title.clone(),
+            content_html: "<html><body><p>Post body</p></body></html>".to_string(),
+            content_text: "Post body".to_string(),
+            media_files: vec![],
+            archived_at: Utc::now(),
+            file_size: 42,
+            checksum: None,
+        };
+        db.archive_repository().save(&archive).await.unwrap();
+
+        let views = db.reader_view_repository();
+        let view = ReaderView {
+            archive_id: archive.id.clone(),
+            title: "Post".to_string(),
+            byline: Some("Sam".to_string()),
+            content_html: "<p>Post body</p>".to_string(),
+            content_text: "Post body".to_string(),
+            images: vec!["https://example.com/a.png".to_string()],
+            word_count: 2,
+            created_at: DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap(),
+        };
+        views.save(&view).await.unwrap();
+        assert_eq!(views.get(&archive.id).await.unwrap(), Some(view.clone()));
+        assert_eq!(views.get_for_page(&page.id).await.unwrap(), Some(view));
+        assert!(views.get_for_page(&Uuid::new_v4()).await.unwrap().is_none());
+
+        assert!(views.delete(&archive.id).await.unwrap());
+        assert!(!views.delete(&archive.id).await.unwrap());
+        assert!(views.get(&archive.id).await.unwrap().is_none());
+    }
+}
@@ -2,31 +2,61 @@
 
 use web_page_manager_core::*;
 use tokio_rusqlite::Connection;
+use std::collections::HashMap;
 use std::sync::Arc;
 use async_trait::async_trait;
-use rusqlite::Row;
+use rusqlite::{OptionalExtension, Row};
+use futures::stream::{self, BoxStream, StreamExt};
+
+use crate::canonical::canonical_url;
+use crate::compression::{compress, content_hash, decompress_column};
 
 /// Repository trait for unified pages
 #[async_trait]
 pub trait PageRepository: Send + Sync {
+    /// Save a page; a new page with the canonical URL of a saved one is
+    /// merged into that page instead of duplicating it
     async fn save(&self, page: &UnifiedPageInfo) -> Result<()>;
+    /// Save many pages in one transaction; none are saved if one fails
+    async fn save_batch(&self, pages: &[UnifiedPageInfo]) -> Result<()>;
     async fn get_by_id(&self, id: &Uuid) -> Result<Option<UnifiedPageInfo>>;
+    /// The live page with the same canonical URL as `url`
     async fn get_by_url(&self, url: &str) -> Result<Option<UnifiedPageInfo>>;
     async fn get_all(&self) -> Result<Vec<UnifiedPageInfo>>;
     async fn get_paginated(&self, limit: usize, offset: usize) -> Result<Vec<UnifiedPageInfo>>;
+    /// One page of pages, continuing from `query.after` if set
+    async fn list(&self, query: &PageQuery) -> Result<Paged<UnifiedPageInfo>>;
+    /// Every page, oldest first, read in batches
+    fn stream_all(&self) -> BoxStream<'static, Result<UnifiedPageInfo>>;
+    /// Move a page to the trash; it is hidden until restored or purged
     async fn delete(&self, id: &Uuid) -> Result<()>;
     async fn search(&self, query: &str) -> Result<Vec<UnifiedPageInfo>>;
     async fn search_with_limit(&self, query: &str, limit: usize) -> Result<Vec<UnifiedPageInfo>>;
+    /// Search with relevance scores and highlighted matches, best first
+    async fn search_ranked(&self, query: &str, limit: usize) -> Result<Vec<PageSearchHit>>;
     async fn update_access(&self, id: &Uuid) -> Result<()>;
     async fn count(&self) -> Result<usize>;
+    /// Take a page out of the trash; false if it is not in the trash
+    async fn restore(&self, id: &Uuid) -> Result<bool>;
+    /// Pages in the trash, most recently deleted first
+    async fn get_trash(&self) -> Result<Vec<Trashed<UnifiedPageInfo>>>;
+    /// Delete pages trashed before `before` for good, with their groups,
+    /// tags, notes and archives
+    async fn purge_trash(&self, before: DateTime<Utc>) -> Result<usize>;
 }
 
 /// Repository trait for smart groups
 #[async_trait]
 pub trait GroupRepository: Send + Sync {
     async fn save(&self, group: &SmartGroup) -> Result<()>;
+    /// Save many groups in one transaction; none are saved if one fails
+    async fn save_batch(&self, groups: &[SmartGroup]) -> Result<()>;
     async fn get_by_id(&self, id: &Uuid) -> Result<Option<SmartGroup>>;
     async fn get_all(&self) -> Result<Vec<SmartGroup>>;
+    /// One page of groups by creation time
+    async fn list(&self, query: &ListQuery) -> Result<Paged<SmartGroup>>;
+    /// Every group, oldest first, read in batches
+    fn stream_all(&self) -> BoxStream<'static, Result<SmartGroup>>;
     async fn delete(&self, id: &Uuid) -> Result<()>;
     async fn add_page_to_group(&self, page_id: &Uuid, group_id: &Uuid, confidence: f32) -> Result<()>;
     async fn remove_page_from_group(&self, page_id: &Uuid, group_id: &Uuid) -> Result<()>;
@@ -38,12 +68,28 @@ pub trait GroupRepository: Send + Sync {
 #[async_trait]
 pub trait HistoryRepository: Send + Sync {
     async fn save(&self, entry: &HistoryEntry) -> Result<()>;
+    /// Save many entries in one transaction; none are saved if one fails
+    async fn save_batch(&self, entries: &[HistoryEntry]) -> Result<()>;
     async fn get_by_id(&self, id: &HistoryId) -> Result<Option<HistoryEntry>>;
     async fn get_filtered(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>>;
+    /// One page of entries by closing time
+    async fn list(&self, query: &ListQuery) -> Result<Paged<HistoryEntry>>;
+    /// Every entry, oldest first, read in batches
+    fn stream_all(&self) -> BoxStream<'static, Result<HistoryEntry>>;
+    /// Move an entry to the trash; it is hidden until restored or purged
     async fn delete(&self, id: &HistoryId) -> Result<()>;
+    /// Permanently delete entries closed before `timestamp`, trashed or not
     async fn delete_older_than(&self, timestamp: DateTime<Utc>) -> Result<usize>;
     async fn search(&self, query: &str, limit: usize) -> Result<Vec<HistoryEntry>>;
     async fn count(&self) -> Result<usize>;
+    /// Attach or clear the recall hint of an entry, returning whether it exists
+    async fn set_recall_hint(&self, id: &HistoryId, hint: Option<&str>) -> Result<bool>;
+    /// Take an entry out of the trash; false if it is not in the trash
+    async fn restore(&self, id: &HistoryId) -> Result<bool>;
+    /// Entries in the trash, most recently deleted first
+    async fn get_trash(&self) -> Result<Vec<Trashed<HistoryEntry>>>;
+    /// Delete entries trashed before `before` for good
+    async fn purge_trash(&self, before: DateTime<Utc>) -> Result<usize>;
 }
 
 /// Repository trait for content archives
@@ -53,8 +99,94 @@ pub trait ArchiveRepository: Send + Sync {
     async fn get_by_id(&self, id: &ArchiveId) -> Result<Option<ContentArchive>>;
     async fn get_by_page_id(&self, page_id: &Uuid) -> Result<Option<ContentArchive>>;
     async fn delete(&self, id: &ArchiveId) -> Result<()>;
+    /// One page of archives by archiving time
+    ///
+    /// Like search results, listed archives have an empty `content_html`.
+    async fn list(&self, query: &ListQuery) -> Result<Paged<ContentArchive>>;
+    /// Every archive, oldest first, read in batches; `content_html` is
+    /// empty as in `list`
+    fn stream_all(&self) -> BoxStream<'static, Result<ContentArchive>>;
+    /// Search archive text, best match first
+    ///
+    /// Results have an empty `content_html`, so no content is decompressed;
+    /// load it with `get_html` when an archive is opened.
     async fn search(&self, query: &str, limit: usize) -> Result<Vec<ContentArchive>>;
+    /// HTML of one archive, decompressed
+    async fn get_html(&self, id: &ArchiveId) -> Result<Option<String>>;
     async fn get_total_size(&self) -> Result<u64>;
+    /// Space taken by archived HTML before and after compression
+    async fn storage_stats(&self) -> Result<ArchiveStorageStats>;
+    /// Compress archives still stored inline from before compression was
+    /// added; returns how many were compressed
+    async fn compact(&self) -> Result<usize>;
+}
+
+/// Repository trait for cached AI analysis results
+#[async_trait]
+pub trait AnalysisCacheRepository: Send + Sync {
+    async fn save(&self, entry: &AnalysisCacheEntry) -> Result<()>;
+    async fn save_batch(&self, entries: &[AnalysisCacheEntry]) -> Result<()>;
+    async fn get(&self, content_hash: &str) -> Result<Option<AnalysisCacheEntry>>;
+    async fn get_recent(&self, limit: usize) -> Result<Vec<AnalysisCacheEntry>>;
+    async fn delete_unused_since(&self, timestamp: DateTime<Utc>) -> Result<usize>;
+    async fn count(&self) -> Result<usize>;
+}
+
+/// Repository trait for user tags
+#[async_trait]
+pub trait TagRepository: Send + Sync {
+    /// Get the tag with `name` (ignoring case), creating it if needed
+    async fn get_or_create(&self, name: &str) -> Result<Tag>;
+    async fn get_by_id(&self, id: &Uuid) -> Result<Option<Tag>>;
+    async fn get_by_name(&self, name: &str) -> Result<Option<Tag>>;
+    /// All tags ordered by name
+    async fn get_all(&self) -> Result<Vec<Tag>>;
+    /// Delete a tag and remove it from its pages, moving its child tags up
+    /// to its parent
+    async fn delete(&self, id: &Uuid) -> Result<()>;
+    /// Rename a tag, returning whether it exists; fails if another tag has the name
+    async fn rename(&self, id: &Uuid, name: &str) -> Result<bool>;
+    /// Move the pages and child tags of `source` to `target` and delete
+    /// `source`, returning the number of pages newly tagged with `target`
+    async fn merge(&self, source: &Uuid, target: &Uuid) -> Result<usize>;
+    /// Nest a tag under `parent`, or make it top-level with None, returning
+    /// whether the tag exists; fails if the parent is missing or nested
+    /// under the tag
+    async fn set_parent(&self, id: &Uuid, parent: Option<&Uuid>) -> Result<bool>;
+    async fn tag_page(&self, page_id: &Uuid, tag_id: &Uuid) -> Result<()>;
+    async fn untag_page(&self, page_id: &Uuid, tag_id: &Uuid) -> Result<()>;
+    async fn get_tags_for_page(&self, page_id: &Uuid) -> Result<Vec<Tag>>;
+    async fn get_pages_with_tag(&self, tag_id: &Uuid) -> Result<Vec<Uuid>>;
+    /// Every tag with its number of pages, most used first
+    async fn tag_counts(&self) -> Result<Vec<TagCount>>;
+}
+
+/// Repository trait for page notes and highlights
+#[async_trait]
+pub trait NoteRepository: Send + Sync {
+    /// Store or replace a note
+    async fn save(&self, note: &PageNote) -> Result<()>;
+    async fn get_by_id(&self, id: &Uuid) -> Result<Option<PageNote>>;
+    /// Notes of a page, oldest first
+    async fn get_for_page(&self, page_id: &Uuid) -> Result<Vec<PageNote>>;
+    async fn delete(&self, id: &Uuid) -> Result<()>;
+    /// Search note text and quotes, best match first
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<PageNote>>;
+    async fn count(&self) -> Result<usize>;
+}
+
+/// Repository trait for saved tab sessions
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    /// Store a session, replacing its windows and tabs if it exists
+    async fn save(&self, session: &SavedSession) -> Result<()>;
+    async fn get_by_id(&self, id: &Uuid) -> Result<Option<SavedSession>>;
+    /// Summaries of every session, most recently updated first
+    async fn list(&self) -> Result<Vec<SessionSummary>>;
+    /// Rename a session; false if it does not exist
+    async fn rename(&self, id: &Uuid, name: &str) -> Result<bool>;
+    async fn delete(&self, id: &Uuid) -> Result<()>;
+    async fn count(&self) -> Result<usize>;
 }
 
 /// Content archive data structure
@@ -72,8 +204,764 @@ pub struct ContentArchive {
     pub checksum: Option<String>,
 }
 
+/// Deleted record waiting in the trash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trashed<T> {
+    pub item: T,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// Direction of a listing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortOrder {
+    /// Newest first
+    #[default]
+    Descending,
+    Ascending,
+}
+
+impl SortOrder {
+    pub(crate) fn sql(&self) -> &'static str {
+        match self {
+            SortOrder::Descending => "DESC",
+            SortOrder::Ascending => "ASC",
+        }
+    }
+}
+
+/// Position after the last item of a listed page, to continue from
+///
+/// Keyset cursors stay correct while rows are inserted or deleted between
+/// requests, unlike offsets, and cost the same however deep the listing is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub(crate) key: i64,
+    pub(crate) id: String,
+}
+
+/// Window of a history, group or archive listing, ordered by the time
+/// column of each: `closed_at`, `created_at` and `archived_at`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListQuery {
+    /// Continue after this cursor from a previous page
+    pub after: Option<Cursor>,
+    /// Rows to skip, after the cursor if one is given
+    pub offset: usize,
+    pub limit: usize,
+    pub sort: SortOrder,
+}
+
+impl Default for ListQuery {
+    fn default() -> Self {
+        Self {
+            after: None,
+            offset: 0,
+            limit: DEFAULT_LIST_LIMIT,
+            sort: SortOrder::default(),
+        }
+    }
+}
+
+/// Items listed when a query does not set a limit
+pub const DEFAULT_LIST_LIMIT: usize = 100;
+
+/// Column pages are listed by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PageSort {
+    /// Most recently accessed first, by default
+    #[default]
+    LastAccessed,
+    CreatedAt,
+    /// Most accessed first, by default
+    AccessCount,
+}
+
+impl PageSort {
+    pub(crate) fn column(&self) -> &'static str {
+        match self {
+            PageSort::LastAccessed => "last_accessed",
+            PageSort::CreatedAt => "created_at",
+            PageSort::AccessCount => "access_count",
+        }
+    }
+
+    pub(crate) fn key(&self, page: &UnifiedPageInfo) -> i64 {
+        match self {
+            PageSort::LastAccessed => page.last_accessed.timestamp(),
+            PageSort::CreatedAt => page.created_at.timestamp(),
+            PageSort::AccessCount => page.access_count as i64,
+        }
+    }
+}
+
+/// Filters, order and window of the page listing
+///
+/// Every filter is compiled into the SQL query, so only matching pages are
+/// read. Build one with the chained setters:
+///
+/// ```ignore
+/// let query = PageQuery::new()
+///     .browser(BrowserType::Firefox)
+///     .has_summary(true)
+///     .sort(PageSort::AccessCount, SortOrder::Descending)
+///     .limit(20);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageQuery {
+    /// Continue after this cursor from a previous page; it must come from
+    /// a query with the same filters and sort
+    pub after: Option<Cursor>,
+    /// Rows to skip, after the cursor if one is given
+    pub offset: usize,
+    pub limit: usize,
+    pub sort: PageSort,
+    pub order: SortOrder,
+    pub category: Option<String>,
+    /// Browser of the page's tab or bookmark, or of its browser info
+    pub browser: Option<BrowserType>,
+    pub source: Option<PageRawSourceType>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub accessed_after: Option<DateTime<Utc>>,
+    pub accessed_before: Option<DateTime<Utc>>,
+    pub has_summary: Option<bool>,
+    pub tag: Option<Uuid>,
+}
+
+impl Default for PageQuery {
+    fn default() -> Self {
+        Self {
+            after: None,
+            offset: 0,
+            limit: DEFAULT_LIST_LIMIT,
+            sort: PageSort::default(),
+            order: SortOrder::default(),
+            category: None,
+            browser: None,
+            source: None,
+            created_after: None,
+            created_before: None,
+            accessed_after: None,
+            accessed_before: None,
+            has_summary: None,
+            tag: None,
+        }
+    }
+}
+
+impl PageQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn after(mut self, cursor: Cursor) -> Self {
+        self.after = Some(cursor);
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn sort(mut self, sort: PageSort, order: SortOrder) -> Self {
+        self.sort = sort;
+        self.order = order;
+        self
+    }
+
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    pub fn browser(mut self, browser: BrowserType) -> Self {
+        self.browser = Some(browser);
+        self
+    }
+
+    pub fn source(mut self, source: PageRawSourceType) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Pages created at or after `start` and before `end`
+    pub fn created_between(mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        self.created_after = Some(start);
+        self.created_before = Some(end);
+        self
+    }
+
+    /// Pages last accessed at or after `start` and before `end`
+    pub fn accessed_between(mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        self.accessed_after = Some(start);
+        self.accessed_before = Some(end);
+        self
+    }
+
+    pub fn has_summary(mut self, has_summary: bool) -> Self {
+        self.has_summary = Some(has_summary);
+        self
+    }
+
+    pub fn tag(mut self, tag_id: Uuid) -> Self {
+        self.tag = Some(tag_id);
+        self
+    }
+
+    /// `AND`-joined conditions on `unified_pages` for the filters, with
+    /// their parameters
+    fn conditions(&self) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+        let mut sql = String::new();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(category) = &self.category {
+            sql.push_str(" AND category = ?");
+            params.push(Box::new(category.clone()));
+        }
+        if let Some(browser) = self.browser {
+            let name = serde_json::to_value(browser)
+                .ok()
+                .and_then(|v| v.as_str().map(String::from))
+                .unwrap_or_default();
+            sql.push_str(
+                " AND (json_extract(source_type, '$.ActiveTab.browser') = ? \
+                 OR json_extract(source_type, '$.Bookmark.browser') = ? \
+                 OR json_extract(browser_info, '$.browser_type') = ?)",
+            );
+            for _ in 0..3 {
+                params.push(Box::new(name.clone()));
+            }
+        }
+        if let Some(source) = self.source {
+            let variant = match source {
+                PageRawSourceType::ActiveTab => "$.ActiveTab",
+                PageRawSourceType::Bookmark => "$.Bookmark",
+                PageRawSourceType::ClosedTab => "$.ClosedTab",
+                PageRawSourceType::ArchivedContent => "$.ArchivedContent",
+            };
+            sql.push_str(" AND json_type(source_type, ?) IS NOT NULL");
+            params.push(Box::new(variant));
+        }
+        for (column, compare, bound) in [
+            ("created_at", ">=", self.created_after),
+            ("created_at", "<", self.created_before),
+            ("last_accessed", ">=", self.accessed_after),
+            ("last_accessed", "<", self.accessed_before),
+        ] {
+            if let Some(bound) = bound {
+                sql.push_str(&format!(" AND {} {} ?", column, compare));
+                params.push(Box::new(bound.timestamp()));
+            }
+        }
+        match self.has_summary {
+            Some(true) => sql.push_str(" AND content_summary IS NOT NULL"),
+            Some(false) => sql.push_str(" AND content_summary IS NULL"),
+            None => {}
+        }
+        if let Some(tag) = self.tag {
+            sql.push_str(" AND EXISTS (SELECT 1 FROM page_tags pt WHERE pt.page_id = unified_pages.id AND pt.tag_id = ?)");
+            params.push(Box::new(tag.to_string()));
+        }
+
+        (sql, params)
+    }
+}
+
+/// One page of a listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Paged<T> {
+    pub items: Vec<T>,
+    /// Cursor for the next page, `None` after the last one
+    pub next: Option<Cursor>,
+}
+
+/// Run a listing keyed on `key` with the `id` column breaking ties
+///
+/// `select` is a query ending in a `WHERE` condition with `?` placeholders
+/// bound to `params`, to which the cursor condition, ordering and limit are
+/// appended.
+pub(crate) fn list_keyset<T>(
+    conn: &rusqlite::Connection,
+    select: &str,
+    mut params: Vec<Box<dyn rusqlite::ToSql>>,
+    key: &str,
+    query: &ListQuery,
+    row_to_item: impl FnMut(&Row) -> rusqlite::Result<T>,
+    cursor_of: impl Fn(&T) -> Cursor,
+) -> rusqlite::Result<Paged<T>> {
+    if query.limit == 0 {
+        return Ok(Paged { items: Vec::new(), next: None });
+    }
+
+    let direction = query.sort.sql();
+    let mut sql = format!("{} ", select);
+    if let Some(after) = &query.after {
+        let compare = match query.sort {
+            SortOrder::Descending => "<",
+            SortOrder::Ascending => ">",
+        };
+        sql.push_str(&format!(
+            "AND ({key} {compare} ? OR ({key} = ? AND id {compare} ?)) ",
+            key = key,
+            compare = compare
+        ));
+        params.push(Box::new(after.key));
+        params.push(Box::new(after.key));
+        params.push(Box::new(after.id.clone()));
+    }
+    sql.push_str(&format!(
+        "ORDER BY {key} {dir}, id {dir} LIMIT {limit} OFFSET {offset}",
+        key = key,
+        dir = direction,
+        limit = query.limit,
+        offset = query.offset
+    ));
+
+    let mut stmt = conn.prepare(&sql)?;
+    let items: Vec<T> = stmt
+        .query_map(rusqlite::params_from_iter(params.iter()), row_to_item)?
+        .flatten()
+        .collect();
+    let next = if items.len() == query.limit { items.last().map(&cursor_of) } else { None };
+    Ok(Paged { items, next })
+}
+
+/// Rows fetched per query by `stream_all`
+pub const STREAM_BATCH_SIZE: usize = 500;
+
+/// Stream a listing batch by batch, following the cursor of each batch
+///
+/// Only one batch is held in memory, and no connection or transaction is
+/// held between batches, so writes can proceed while a stream is consumed.
+/// A failed batch ends the stream after yielding its error.
+pub(crate) fn stream_keyset<T, F, Fut>(fetch: F) -> BoxStream<'static, Result<T>>
+where
+    T: Send + 'static,
+    F: Fn(Option<Cursor>) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = Result<Paged<T>>> + Send + 'static,
+{
+    stream::unfold(Some(None), move |after: Option<Option<Cursor>>| {
+        let batch = after.map(&fetch);
+        async move {
+            match batch?.await {
+                Ok(page) => {
+                    let items: Vec<Result<T>> = page.items.into_iter().map(Ok).collect();
+                    Some((stream::iter(items), page.next.map(Some)))
+                }
+                Err(e) => Some((stream::iter(vec![Err(e)]), None)),
+            }
+        }
+    })
+    .flatten()
+    .boxed()
+}
+
+/// Storage taken by archived HTML
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveStorageStats {
+    pub archives: usize,
+    /// Distinct compressed contents shared by the archives
+    pub blobs: usize,
+    /// HTML size of every archive, uncompressed and counting duplicates
+    pub content_bytes: u64,
+    /// Bytes actually stored, compressed blobs plus HTML not yet compacted
+    pub stored_bytes: u64,
+}
+
+/// Page matched by a full-text search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageSearchHit {
+    pub page: UnifiedPageInfo,
+    /// BM25 relevance, higher is better; only comparable within one query
+    pub score: f64,
+    /// Title with matched terms wrapped in `HIGHLIGHT_START`/`HIGHLIGHT_END`
+    pub highlighted_title: String,
+    /// Excerpt around the best match in the title, summary, keywords or URL
+    pub snippet: String,
+}
+
+/// Markers around matched terms in search highlights and snippets
+pub const HIGHLIGHT_START: &str = "<mark>";
+pub const HIGHLIGHT_END: &str = "</mark>";
+/// Marks text cut from a snippet
+const SNIPPET_ELLIPSIS: &str = "…";
+/// Tokens shown in a snippet
+const SNIPPET_TOKENS: i64 = 16;
+
+/// Cached AI analysis result
+///
+/// `analysis` holds the JSON produced by the AI processor's cache export and
+/// is stored opaquely.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisCacheEntry {
+    pub content_hash: String,
+    pub analysis: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used: DateTime<Utc>,
+}
+
+/// User tag
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// Tag this one is nested under, None for a top-level tag
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+}
+
+/// Tag with the number of pages carrying it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: Tag,
+    pub page_count: usize,
+}
+
+/// Free-form note or highlighted quote attached to a page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageNote {
+    pub id: Uuid,
+    pub page_id: Uuid,
+    /// Note text, or the comment on a highlight (may be empty)
+    pub content: String,
+    /// Passage highlighted on the page, for highlights
+    pub quote: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PageNote {
+    /// New note on a page
+    pub fn note(page_id: Uuid, content: impl Into<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            page_id,
+            content: content.into(),
+            quote: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// New highlight of `quote` on a page, with an optional comment
+    pub fn highlight(page_id: Uuid, quote: impl Into<String>, comment: impl Into<String>) -> Self {
+        Self {
+            quote: Some(quote.into()),
+            ..Self::note(page_id, comment)
+        }
+    }
+
+    pub fn is_highlight(&self) -> bool {
+        self.quote.is_some()
+    }
+}
+
+/// Named set of browser windows and their tabs, saved to be restored later
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub id: Uuid,
+    pub name: String,
+    /// Windows in restore order
+    pub windows: Vec<SessionWindow>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Window of a saved session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionWindow {
+    pub browser: BrowserType,
+    /// Tabs in strip order
+    pub tabs: Vec<SessionTab>,
+}
+
+/// Tab of a saved session window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTab {
+    pub url: String,
+    pub title: String,
+    pub favicon_url: Option<String>,
+    pub pinned: bool,
+}
+
+/// Saved session without its tabs, for listing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub id: Uuid,
+    pub name: String,
+    pub window_count: usize,
+    pub tab_count: usize,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SavedSession {
+    /// New session of `windows`
+    pub fn new(name: impl Into<String>, windows: Vec<SessionWindow>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            windows,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn tab_count(&self) -> usize {
+        self.windows.iter().map(|w| w.tabs.len()).sum()
+    }
+}
+
+/// Characters a term needs to be looked up in a trigram index
+const MIN_TRIGRAM_CHARS: usize = 3;
+
+/// Full-text filter over an FTS table joined as `fts`
+///
+/// With `new`, over a trigram-tokenized table, terms of three or more
+/// characters are matched through the index, anywhere inside words and in
+/// any script. Shorter terms, such as two-character Chinese words, have no
+/// trigram; they are matched with `LIKE` on the indexed columns and cannot
+/// be ranked. With `words`, over a word-tokenized table, terms match word
+/// prefixes.
+struct FtsFilter {
+    /// FTS table the condition matches, joined as `fts`
+    table: String,
+    /// SQL condition with `?` placeholders
+    condition: String,
+    params: Vec<String>,
+    /// Whether the condition uses MATCH, so bm25(), highlight() and snippet() apply
+    ranked: bool,
+    /// Terms matched with `LIKE`
+    short_terms: Vec<String>,
+}
+
+impl FtsFilter {
+    /// Build the filter for a user query, or `None` if it has no terms
+    fn new(table: &str, columns: &[&str], query: &str) -> Option<Self> {
+        let terms: Vec<&str> = query
+            .split_whitespace()
+            .map(|term| term.trim_matches(|c| c == '"' || c == '*'))
+            .filter(|term| !term.is_empty())
+            .collect();
+        if terms.is_empty() {
+            return None;
+        }
+        let (long, short): (Vec<&str>, Vec<&str>) =
+            terms.into_iter().partition(|term| term.chars().count() >= MIN_TRIGRAM_CHARS);
+
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if !long.is_empty() {
+            conditions.push(format!("{} MATCH ?", table));
+            let phrases: Vec<String> = long.iter().map(|term| format!("\"{}\"", term.replace('"', "\"\""))).collect();
+            params.push(phrases.join(" "));
+        }
+        for term in &short {
+            let pattern = format!("%{}%", term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+            let any_column: Vec<String> = columns.iter().map(|column| format!("fts.{} LIKE ? ESCAPE '\\'", column)).collect();
+            conditions.push(format!("({})", any_column.join(" OR ")));
+            params.extend(std::iter::repeat_n(pattern, columns.len()));
+        }
+
+        Some(Self {
+            table: table.to_string(),
+            condition: conditions.join(" AND "),
+            params,
+            ranked: !long.is_empty(),
+            short_terms: short.into_iter().map(str::to_string).collect(),
+        })
+    }
+
+    /// Build a filter over a word-tokenized FTS table, matching each term as
+    /// a word prefix, or `None` if the query has no terms or CJK text,
+    /// whose words such an index cannot find
+    fn words(table: &str, query: &str) -> Option<Self> {
+        let phrases: Vec<String> = query
+            .split_whitespace()
+            .map(|term| term.trim_matches(|c| c == '"' || c == '*'))
+            .filter(|term| !term.is_empty())
+            .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+            .collect();
+        if phrases.is_empty() || query.chars().any(is_cjk) {
+            return None;
+        }
+
+        Some(Self {
+            table: table.to_string(),
+            condition: format!("{} MATCH ?", table),
+            params: vec![phrases.join(" ")],
+            ranked: true,
+            short_terms: Vec::new(),
+        })
+    }
+}
+
+/// Whether a character is Chinese, Japanese or Korean, scripts written
+/// without spaces between words
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11FF}'
+        | '\u{2E80}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF00}'..='\u{FFEF}'
+        | '\u{20000}'..='\u{2FFFF}')
+}
+
+/// Wrap occurrences of `terms` in highlight markers, ignoring ASCII case like `LIKE`
+fn highlight_terms(text: &str, terms: &[String]) -> String {
+    let mut highlighted = String::with_capacity(text.len());
+    let mut rest = text;
+    'scan: while let Some(c) = rest.chars().next() {
+        for term in terms {
+            if let Some(found) = rest.get(..term.len()).filter(|prefix| prefix.eq_ignore_ascii_case(term)) {
+                highlighted.push_str(HIGHLIGHT_START);
+                highlighted.push_str(found);
+                highlighted.push_str(HIGHLIGHT_END);
+                rest = &rest[term.len()..];
+                continue 'scan;
+            }
+        }
+        highlighted.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    highlighted
+}
+
+/// Filter for a page search: through the word index unless the query has
+/// CJK text, which only the trigram index can find
+fn page_filter(query: &str) -> Option<FtsFilter> {
+    FtsFilter::words("pages_word_fts", query)
+        .or_else(|| FtsFilter::new("pages_fts", &["title", "content_summary", "keywords", "url"], query))
+}
+
+/// Rowids, pages and scores of the best `limit` pages matching a filter,
+/// best first; unranked matches come most recently accessed first
+fn best_pages(conn: &rusqlite::Connection, filter: &FtsFilter, limit: usize) -> rusqlite::Result<Vec<(i64, UnifiedPageInfo, f64)>> {
+    // bm25() is lower for better matches; the weights are for title,
+    // content_summary, keywords and url
+    let (score, order) = if filter.ranked {
+        (format!("-bm25({}, 10.0, 2.0, 5.0, 1.0) AS score", filter.table), "score DESC")
+    } else {
+        ("0.0 AS score".to_string(), "p.last_accessed DESC")
+    };
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT p.id, p.url, p.title, p.favicon_url, p.content_summary, p.keywords, p.category,
+               p.source_type, p.browser_info, p.tab_info, p.bookmark_info, p.created_at, p.last_accessed, p.access_count,
+               {}, p.rowid
+        FROM unified_pages p
+        JOIN {} fts ON p.rowid = fts.rowid
+        WHERE ({}) AND p.deleted_at IS NULL
+        ORDER BY {}
+        LIMIT {}
+        "#,
+        score, filter.table, filter.condition, order, limit
+    ))?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(&filter.params), |row| {
+        Ok((row.get(15)?, row_to_page(row)?, row.get(14)?))
+    })?;
+    Ok(rows.flatten().collect())
+}
+
+/// Insert or replace a page row
+///
+/// A new page whose canonical URL is already saved is merged into that
+/// page. Returns the id the page is stored under: its own, or the merged
+/// page's.
+pub(crate) fn insert_page(conn: &rusqlite::Connection, page: &UnifiedPageInfo) -> rusqlite::Result<Uuid> {
+    let content_summary_json = page.content_summary
+        .as_ref()
+        .map(|s| serde_json::to_string(s).unwrap_or_default());
+    let keywords_json = serde_json::to_string(&page.keywords).unwrap_or_default();
+    let source_type_json = serde_json::to_string(&page.source_type).unwrap_or_default();
+    let browser_info_json = page.browser_info
+        .as_ref()
+        .map(|b| serde_json::to_string(b).unwrap_or_default());
+    let tab_info_json = page.tab_info
+        .as_ref()
+        .map(|t| serde_json::to_string(t).unwrap_or_default());
+    let bookmark_info_json = page.bookmark_info
+        .as_ref()
+        .map(|b| serde_json::to_string(b).unwrap_or_default());
+
+    // An upsert rather than REPLACE: deleting the old row would cascade to
+    // the page's groups, tags, notes and embedding. Saving a trashed page
+    // takes it out of the trash. Cached, as batches run it once per page.
+    let stored_id: String = conn.prepare_cached(
+        r#"
+        INSERT INTO unified_pages 
+        (id, url, title, favicon_url, content_summary, keywords, category, 
+         source_type, browser_info, tab_info, bookmark_info, created_at, last_accessed, access_count, canonical_url)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+        ON CONFLICT(id) DO UPDATE SET
+            url = excluded.url,
+            canonical_url = excluded.canonical_url,
+            title = excluded.title,
+            favicon_url = excluded.favicon_url,
+            content_summary = excluded.content_summary,
+            keywords = excluded.keywords,
+            category = excluded.category,
+            source_type = excluded.source_type,
+            browser_info = excluded.browser_info,
+            tab_info = excluded.tab_info,
+            bookmark_info = excluded.bookmark_info,
+            created_at = excluded.created_at,
+            last_accessed = excluded.last_accessed,
+            access_count = excluded.access_count,
+            deleted_at = NULL
+        ON CONFLICT(canonical_url) DO UPDATE SET
+            url = excluded.url,
+            title = excluded.title,
+            favicon_url = COALESCE(excluded.favicon_url, favicon_url),
+            content_summary = COALESCE(excluded.content_summary, content_summary),
+            keywords = excluded.keywords,
+            category = COALESCE(excluded.category, category),
+            source_type = excluded.source_type,
+            browser_info = COALESCE(excluded.browser_info, browser_info),
+            tab_info = COALESCE(excluded.tab_info, tab_info),
+            bookmark_info = COALESCE(excluded.bookmark_info, bookmark_info),
+            created_at = MIN(created_at, excluded.created_at),
+            last_accessed = MAX(last_accessed, excluded.last_accessed),
+            access_count = MAX(access_count, excluded.access_count),
+            deleted_at = NULL
+        RETURNING id
+        "#,
+    )?
+    .query_row(
+        rusqlite::params![
+            page.id.to_string(),
+            page.url,
+            page.title,
+            page.favicon_url,
+            content_summary_json,
+            keywords_json,
+            page.category,
+            source_type_json,
+            browser_info_json,
+            tab_info_json,
+            bookmark_info_json,
+            page.created_at.timestamp(),
+            page.last_accessed.timestamp(),
+            page.access_count,
+            canonical_url(&page.url),
+        ],
+        |row| row.get(0),
+    )?;
+    Ok(Uuid::parse_str(&stored_id).unwrap_or(page.id))
+}
+
 /// Helper function to map a row to UnifiedPageInfo
-fn row_to_page(row: &Row) -> rusqlite::Result<UnifiedPageInfo> {
+pub(crate) fn row_to_page(row: &Row) -> rusqlite::Result<UnifiedPageInfo> {
     let id_str: String = row.get(0)?;
     let url: String = row.get(1)?;
     let title: String = row.get(2)?;
@@ -123,8 +1011,33 @@ fn row_to_page(row: &Row) -> rusqlite::Result<UnifiedPageInfo> {
     })
 }
 
+/// Insert or replace a group row
+pub(crate) fn insert_group(conn: &rusqlite::Connection, group: &SmartGroup) -> rusqlite::Result<()> {
+    let group_type_json = serde_json::to_string(&group.group_type).unwrap_or_default();
+
+    conn.prepare_cached(
+        r#"
+        INSERT OR REPLACE INTO smart_groups 
+        (id, name, description, group_type, created_at, auto_generated, similarity_threshold)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#,
+    )?
+    .execute(
+        rusqlite::params![
+            group.id.to_string(),
+            group.name,
+            group.description,
+            group_type_json,
+            group.created_at.timestamp(),
+            group.auto_generated,
+            group.similarity_threshold,
+        ],
+    )?;
+    Ok(())
+}
+
 /// Helper function to map a row to SmartGroup
-fn row_to_group(row: &Row) -> rusqlite::Result<SmartGroup> {
+pub(crate) fn row_to_group(row: &Row) -> rusqlite::Result<SmartGroup> {
     let id_str: String = row.get(0)?;
     let name: String = row.get(1)?;
     let description: Option<String> = row.get(2)?;
@@ -150,13 +1063,45 @@ fn row_to_group(row: &Row) -> rusqlite::Result<SmartGroup> {
 }
 
 /// SQLite implementation of PageRepository
+#[derive(Clone)]
 pub struct SqlitePageRepository {
     connection: Arc<Connection>,
+    /// Connection for list and search queries
+    reader: Arc<Connection>,
 }
 
 impl SqlitePageRepository {
     pub fn new(connection: Arc<Connection>) -> Self {
-        Self { connection }
+        Self {
+            reader: Arc::clone(&connection),
+            connection,
+        }
+    }
+
+    /// Run list and search queries on a separate read-only connection
+    pub fn with_reader(connection: Arc<Connection>, reader: Arc<Connection>) -> Self {
+        Self { connection, reader }
+    }
+
+    /// Save pages in one transaction, returning the id each was stored
+    /// under: its own, or that of the page with the same canonical URL it
+    /// was merged into
+    pub async fn save_resolving(&self, pages: &[UnifiedPageInfo]) -> Result<Vec<Uuid>> {
+        let pages = pages.to_vec();
+
+        self.connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                let ids = pages.iter().map(|page| insert_page(&tx, page)).collect::<rusqlite::Result<Vec<_>>>()?;
+                tx.commit()?;
+                Ok(ids)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to save pages: {}", e),
+                },
+            })
     }
 }
 
@@ -167,45 +1112,7 @@ impl PageRepository for SqlitePageRepository {
         
         self.connection
             .call(move |conn| {
-                let content_summary_json = page_clone.content_summary
-                    .as_ref()
-                    .map(|s| serde_json::to_string(s).unwrap_or_default());
-                let keywords_json = serde_json::to_string(&page_clone.keywords).unwrap_or_default();
-                let source_type_json = serde_json::to_string(&page_clone.source_type).unwrap_or_default();
-                let browser_info_json = page_clone.browser_info
-                    .as_ref()
-                    .map(|b| serde_json::to_string(b).unwrap_or_default());
-                let tab_info_json = page_clone.tab_info
-                    .as_ref()
-                    .map(|t| serde_json::to_string(t).unwrap_or_default());
-                let bookmark_info_json = page_clone.bookmark_info
-                    .as_ref()
-                    .map(|b| serde_json::to_string(b).unwrap_or_default());
-                
-                conn.execute(
-                    r#"
-                    INSERT OR REPLACE INTO unified_pages 
-                    (id, url, title, favicon_url, content_summary, keywords, category, 
-                     source_type, browser_info, tab_info, bookmark_info, created_at, last_accessed, access_count)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
-                    "#,
-                    rusqlite::params![
-                        page_clone.id.to_string(),
-                        page_clone.url,
-                        page_clone.title,
-                        page_clone.favicon_url,
-                        content_summary_json,
-                        keywords_json,
-                        page_clone.category,
-                        source_type_json,
-                        browser_info_json,
-                        tab_info_json,
-                        bookmark_info_json,
-                        page_clone.created_at.timestamp(),
-                        page_clone.last_accessed.timestamp(),
-                        page_clone.access_count,
-                    ],
-                )?;
+                insert_page(conn, &page_clone)?;
                 Ok(())
             })
             .await
@@ -218,6 +1125,29 @@ impl PageRepository for SqlitePageRepository {
         Ok(())
     }
 
+    async fn save_batch(&self, pages: &[UnifiedPageInfo]) -> Result<()> {
+        if pages.is_empty() {
+            return Ok(());
+        }
+        let pages = pages.to_vec();
+        
+        self.connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                for item in &pages {
+                    insert_page(&tx, item)?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to batch save pages: {}", e),
+                },
+            })
+    }
+
     async fn get_by_id(&self, id: &Uuid) -> Result<Option<UnifiedPageInfo>> {
         let id_str = id.to_string();
         
@@ -226,7 +1156,7 @@ impl PageRepository for SqlitePageRepository {
                 let mut stmt = conn.prepare(
                     "SELECT id, url, title, favicon_url, content_summary, keywords, category, \
                      source_type, browser_info, tab_info, bookmark_info, created_at, last_accessed, access_count \
-                     FROM unified_pages WHERE id = ?1"
+                     FROM unified_pages WHERE id = ?1 AND deleted_at IS NULL"
                 )?;
                 
                 let result = stmt.query_row([&id_str], row_to_page);
@@ -253,10 +1183,10 @@ impl PageRepository for SqlitePageRepository {
                 let mut stmt = conn.prepare(
                     "SELECT id, url, title, favicon_url, content_summary, keywords, category, \
                      source_type, browser_info, tab_info, bookmark_info, created_at, last_accessed, access_count \
-                     FROM unified_pages WHERE url = ?1 LIMIT 1"
+                     FROM unified_pages WHERE canonical_url = ?1 AND deleted_at IS NULL"
                 )?;
                 
-                let result = stmt.query_row([&url_str], row_to_page);
+                let result = stmt.query_row([canonical_url(&url_str)], row_to_page);
                 
                 match result {
                     Ok(page) => Ok(Some(page)),
@@ -273,12 +1203,12 @@ impl PageRepository for SqlitePageRepository {
     }
 
     async fn get_all(&self) -> Result<Vec<UnifiedPageInfo>> {
-        self.connection
+        self.reader
             .call(|conn| {
                 let mut stmt = conn.prepare(
                     "SELECT id, url, title, favicon_url, content_summary, keywords, category, \
                      source_type, browser_info, tab_info, bookmark_info, created_at, last_accessed, access_count \
-                     FROM unified_pages ORDER BY last_accessed DESC"
+                     FROM unified_pages WHERE deleted_at IS NULL ORDER BY last_accessed DESC"
                 )?;
                 
                 let rows = stmt.query_map([], row_to_page)?;
@@ -299,12 +1229,12 @@ impl PageRepository for SqlitePageRepository {
     }
 
     async fn get_paginated(&self, limit: usize, offset: usize) -> Result<Vec<UnifiedPageInfo>> {
-        self.connection
+        self.reader
             .call(move |conn| {
                 let mut stmt = conn.prepare(
                     "SELECT id, url, title, favicon_url, content_summary, keywords, category, \
                      source_type, browser_info, tab_info, bookmark_info, created_at, last_accessed, access_count \
-                     FROM unified_pages ORDER BY last_accessed DESC LIMIT ?1 OFFSET ?2"
+                     FROM unified_pages WHERE deleted_at IS NULL ORDER BY last_accessed DESC LIMIT ?1 OFFSET ?2"
                 )?;
                 
                 let rows = stmt.query_map(rusqlite::params![limit as i64, offset as i64], row_to_page)?;
@@ -324,14 +1254,63 @@ impl PageRepository for SqlitePageRepository {
             })
     }
 
+    async fn list(&self, query: &PageQuery) -> Result<Paged<UnifiedPageInfo>> {
+        let query = query.clone();
+        let sort = query.sort;
+        let window = ListQuery {
+            after: query.after.clone(),
+            offset: query.offset,
+            limit: query.limit,
+            sort: query.order,
+        };
+        
+        self.reader
+            .call(move |conn| {
+                let (conditions, params) = query.conditions();
+                let select = format!(
+                    "SELECT id, url, title, favicon_url, content_summary, keywords, category, \
+                     source_type, browser_info, tab_info, bookmark_info, created_at, last_accessed, access_count \
+                     FROM unified_pages WHERE deleted_at IS NULL{}",
+                    conditions
+                );
+                Ok(list_keyset(conn, &select, params, sort.column(), &window, row_to_page, |page| Cursor {
+                    key: sort.key(page),
+                    id: page.id.to_string(),
+                })?)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to list pages: {}", e),
+                },
+            })
+    }
+
+    fn stream_all(&self) -> BoxStream<'static, Result<UnifiedPageInfo>> {
+        let repo = self.clone();
+        stream_keyset(move |after| {
+            let repo = repo.clone();
+            async move {
+                let query = PageQuery {
+                    after,
+                    limit: STREAM_BATCH_SIZE,
+                    sort: PageSort::CreatedAt,
+                    order: SortOrder::Ascending,
+                    ..Default::default()
+                };
+                repo.list(&query).await
+            }
+        })
+    }
+
     async fn delete(&self, id: &Uuid) -> Result<()> {
         let id_str = id.to_string();
         
         self.connection
             .call(move |conn| {
                 conn.execute(
-                    "DELETE FROM unified_pages WHERE id = ?1",
-                    [&id_str],
+                    "UPDATE unified_pages SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                    rusqlite::params![Utc::now().timestamp(), id_str],
                 )?;
                 Ok(())
             })
@@ -350,30 +1329,66 @@ impl PageRepository for SqlitePageRepository {
     }
 
     async fn search_with_limit(&self, query: &str, limit: usize) -> Result<Vec<UnifiedPageInfo>> {
-        let query_str = format!("{}*", query.replace('"', "\"\""));
+        let Some(filter) = page_filter(query) else {
+            return Ok(Vec::new());
+        };
+
+        self.reader
+            .call(move |conn| Ok(best_pages(conn, &filter, limit)?.into_iter().map(|(_, page, _)| page).collect()))
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to search pages: {}", e),
+                },
+            })
+    }
+
+    async fn search_ranked(&self, query: &str, limit: usize) -> Result<Vec<PageSearchHit>> {
+        let Some(filter) = page_filter(query) else {
+            return Ok(Vec::new());
+        };
         
-        self.connection
+        self.reader
             .call(move |conn| {
-                let mut stmt = conn.prepare(
-                    r#"
-                    SELECT p.id, p.url, p.title, p.favicon_url, p.content_summary, p.keywords, p.category,
-                           p.source_type, p.browser_info, p.tab_info, p.bookmark_info, p.created_at, p.last_accessed, p.access_count
-                    FROM unified_pages p
-                    JOIN pages_fts fts ON p.rowid = fts.rowid
-                    WHERE pages_fts MATCH ?1
-                    ORDER BY rank
-                    LIMIT ?2
-                    "#
-                )?;
-                
-                let rows = stmt.query_map(rusqlite::params![query_str, limit as i64], row_to_page)?;
-                let mut pages = Vec::new();
-                for row in rows {
-                    if let Ok(page) = row {
-                        pages.push(page);
-                    }
+                let pages = best_pages(conn, &filter, limit)?;
+
+                // highlight() and snippet() are costly, so they run only for
+                // the pages returned rather than every match
+                let mut matches: HashMap<i64, (String, String)> = HashMap::new();
+                if filter.ranked && !pages.is_empty() {
+                    let rowids: Vec<String> = pages.iter().map(|(rowid, _, _)| rowid.to_string()).collect();
+                    let mut stmt = conn.prepare(&format!(
+                        "SELECT fts.rowid, highlight({0}, 0, ?, ?), snippet({0}, -1, ?, ?, ?, {1}) \
+                         FROM {0} fts WHERE ({2}) AND fts.rowid IN ({3})",
+                        filter.table,
+                        SNIPPET_TOKENS,
+                        filter.condition,
+                        rowids.join(", ")
+                    ))?;
+                    let mut params: Vec<&str> = vec![HIGHLIGHT_START, HIGHLIGHT_END, HIGHLIGHT_START, HIGHLIGHT_END, SNIPPET_ELLIPSIS];
+                    params.extend(filter.params.iter().map(String::as_str));
+                    let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+                        Ok((row.get::<_, i64>(0)?, (row.get(1)?, row.get(2)?)))
+                    })?;
+                    matches.extend(rows.flatten());
                 }
-                Ok(pages)
+
+                Ok(pages
+                    .into_iter()
+                    .map(|(rowid, page, score)| {
+                        // Without MATCH the terms are highlighted here
+                        let (highlighted_title, snippet) = matches.remove(&rowid).unwrap_or_else(|| {
+                            let highlighted_title = highlight_terms(&page.title, &filter.short_terms);
+                            (highlighted_title.clone(), highlighted_title)
+                        });
+                        PageSearchHit {
+                            page,
+                            score,
+                            highlighted_title,
+                            snippet,
+                        }
+                    })
+                    .collect())
             })
             .await
             .map_err(|e| WebPageManagerError::System {
@@ -406,10 +1421,10 @@ impl PageRepository for SqlitePageRepository {
     }
 
     async fn count(&self) -> Result<usize> {
-        self.connection
+        self.reader
             .call(|conn| {
                 let count: i64 = conn.query_row(
-                    "SELECT COUNT(*) FROM unified_pages",
+                    "SELECT COUNT(*) FROM unified_pages WHERE deleted_at IS NULL",
                     [],
                     |row| row.get(0),
                 )?;
@@ -422,13 +1437,78 @@ impl PageRepository for SqlitePageRepository {
                 },
             })
     }
-}
-
-
-/// SQLite implementation of GroupRepository
-pub struct SqliteGroupRepository {
-    connection: Arc<Connection>,
-}
+
+    async fn restore(&self, id: &Uuid) -> Result<bool> {
+        let id_str = id.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                let restored = conn.execute(
+                    "UPDATE unified_pages SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+                    [&id_str],
+                )?;
+                Ok(restored > 0)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to restore page: {}", e),
+                },
+            })
+    }
+
+    async fn get_trash(&self) -> Result<Vec<Trashed<UnifiedPageInfo>>> {
+        self.reader
+            .call(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT id, url, title, favicon_url, content_summary, keywords, category, \
+                     source_type, browser_info, tab_info, bookmark_info, created_at, last_accessed, access_count, deleted_at \
+                     FROM unified_pages WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, rowid DESC"
+                )?;
+                
+                let rows = stmt.query_map([], |row| {
+                    let deleted_at_ts: i64 = row.get(14)?;
+                    Ok(Trashed {
+                        item: row_to_page(row)?,
+                        deleted_at: DateTime::from_timestamp(deleted_at_ts, 0).unwrap_or_else(Utc::now),
+                    })
+                })?;
+                Ok(rows.flatten().collect())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get trashed pages: {}", e),
+                },
+            })
+    }
+
+    async fn purge_trash(&self, before: DateTime<Utc>) -> Result<usize> {
+        let ts = before.timestamp();
+        
+        self.connection
+            .call(move |conn| {
+                let purged = conn.execute(
+                    "DELETE FROM unified_pages WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+                    [ts],
+                )?;
+                Ok(purged)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to purge trashed pages: {}", e),
+                },
+            })
+    }
+}
+
+
+/// SQLite implementation of GroupRepository
+#[derive(Clone)]
+pub struct SqliteGroupRepository {
+    connection: Arc<Connection>,
+}
 
 impl SqliteGroupRepository {
     pub fn new(connection: Arc<Connection>) -> Self {
@@ -443,24 +1523,7 @@ impl GroupRepository for SqliteGroupRepository {
         
         self.connection
             .call(move |conn| {
-                let group_type_json = serde_json::to_string(&group_clone.group_type).unwrap_or_default();
-                
-                conn.execute(
-                    r#"
-                    INSERT OR REPLACE INTO smart_groups 
-                    (id, name, description, group_type, created_at, auto_generated, similarity_threshold)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
-                    "#,
-                    rusqlite::params![
-                        group_clone.id.to_string(),
-                        group_clone.name,
-                        group_clone.description,
-                        group_type_json,
-                        group_clone.created_at.timestamp(),
-                        group_clone.auto_generated,
-                        group_clone.similarity_threshold,
-                    ],
-                )?;
+                insert_group(conn, &group_clone)?;
                 Ok(())
             })
             .await
@@ -473,6 +1536,29 @@ impl GroupRepository for SqliteGroupRepository {
         Ok(())
     }
 
+    async fn save_batch(&self, groups: &[SmartGroup]) -> Result<()> {
+        if groups.is_empty() {
+            return Ok(());
+        }
+        let groups = groups.to_vec();
+        
+        self.connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                for item in &groups {
+                    insert_group(&tx, item)?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to batch save groups: {}", e),
+                },
+            })
+    }
+
     async fn get_by_id(&self, id: &Uuid) -> Result<Option<SmartGroup>> {
         let id_str = id.to_string();
         
@@ -524,6 +1610,37 @@ impl GroupRepository for SqliteGroupRepository {
             })
     }
 
+    async fn list(&self, query: &ListQuery) -> Result<Paged<SmartGroup>> {
+        let query = query.clone();
+        
+        self.connection
+            .call(move |conn| {
+                let select = "SELECT id, name, description, group_type, created_at, auto_generated, similarity_threshold \
+                              FROM smart_groups WHERE 1=1";
+                Ok(list_keyset(conn, select, Vec::new(), "created_at", &query, row_to_group, |group| Cursor {
+                    key: group.created_at.timestamp(),
+                    id: group.id.to_string(),
+                })?)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to list groups: {}", e),
+                },
+            })
+    }
+
+    fn stream_all(&self) -> BoxStream<'static, Result<SmartGroup>> {
+        let repo = self.clone();
+        stream_keyset(move |after| {
+            let repo = repo.clone();
+            async move {
+                let query = ListQuery { after, limit: STREAM_BATCH_SIZE, sort: SortOrder::Ascending, ..Default::default() };
+                repo.list(&query).await
+            }
+        })
+    }
+
     async fn delete(&self, id: &Uuid) -> Result<()> {
         let id_str = id.to_string();
         
@@ -604,7 +1721,8 @@ impl GroupRepository for SqliteGroupRepository {
         self.connection
             .call(move |conn| {
                 let mut stmt = conn.prepare(
-                    "SELECT page_id FROM page_group_relations WHERE group_id = ?1 ORDER BY confidence_score DESC"
+                    "SELECT r.page_id FROM page_group_relations r JOIN unified_pages p ON p.id = r.page_id \
+                     WHERE r.group_id = ?1 AND p.deleted_at IS NULL ORDER BY r.confidence_score DESC"
                 )?;
                 
                 let rows = stmt.query_map([&group_id_str], |row| {
@@ -661,13 +1779,24 @@ impl GroupRepository for SqliteGroupRepository {
 
 
 /// SQLite implementation of HistoryRepository
+#[derive(Clone)]
 pub struct SqliteHistoryRepository {
     connection: Arc<Connection>,
+    /// Connection for list and search queries
+    reader: Arc<Connection>,
 }
 
 impl SqliteHistoryRepository {
     pub fn new(connection: Arc<Connection>) -> Self {
-        Self { connection }
+        Self {
+            reader: Arc::clone(&connection),
+            connection,
+        }
+    }
+
+    /// Run list and search queries on a separate read-only connection
+    pub fn with_reader(connection: Arc<Connection>, reader: Arc<Connection>) -> Self {
+        Self { connection, reader }
     }
 }
 
@@ -678,33 +1807,7 @@ impl HistoryRepository for SqliteHistoryRepository {
         
         self.connection
             .call(move |conn| {
-                let session_info_json = entry_clone.session_info
-                    .as_ref()
-                    .map(|s| serde_json::to_string(s).unwrap_or_default());
-                let content_summary_json = entry_clone.page_info.content_summary
-                    .as_ref()
-                    .map(|s| serde_json::to_string(s).unwrap_or_default());
-                let tab_id_str = entry_clone.tab_id.as_ref().map(|t| t.0.to_string());
-                
-                conn.execute(
-                    r#"
-                    INSERT OR REPLACE INTO tab_history 
-                    (id, page_id, url, title, favicon_url, browser_type, tab_id, closed_at, session_info, content_summary)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
-                    "#,
-                    rusqlite::params![
-                        entry_clone.id.0.to_string(),
-                        entry_clone.page_info.id.to_string(),
-                        entry_clone.page_info.url,
-                        entry_clone.page_info.title,
-                        entry_clone.page_info.favicon_url,
-                        serde_json::to_string(&entry_clone.browser_type).unwrap_or_default(),
-                        tab_id_str,
-                        entry_clone.closed_at.timestamp(),
-                        session_info_json,
-                        content_summary_json,
-                    ],
-                )?;
+                insert_history_entry(conn, &entry_clone)?;
                 Ok(())
             })
             .await
@@ -717,14 +1820,37 @@ impl HistoryRepository for SqliteHistoryRepository {
         Ok(())
     }
 
+    async fn save_batch(&self, entries: &[HistoryEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let entries = entries.to_vec();
+        
+        self.connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                for item in &entries {
+                    insert_history_entry(&tx, item)?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to batch save history entries: {}", e),
+                },
+            })
+    }
+
     async fn get_by_id(&self, id: &HistoryId) -> Result<Option<HistoryEntry>> {
         let id_str = id.0.to_string();
         
         self.connection
             .call(move |conn| {
                 let mut stmt = conn.prepare(
-                    "SELECT id, page_id, url, title, favicon_url, browser_type, tab_id, closed_at, session_info, content_summary \
-                     FROM tab_history WHERE id = ?1"
+                    "SELECT id, page_id, url, title, favicon_url, browser_type, tab_id, closed_at, session_info, content_summary, recall_hint \
+                     FROM tab_history WHERE id = ?1 AND deleted_at IS NULL"
                 )?;
                 
                 let result = stmt.query_row([&id_str], |row| {
@@ -748,11 +1874,11 @@ impl HistoryRepository for SqliteHistoryRepository {
     async fn get_filtered(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>> {
         let filter_clone = filter.clone();
         
-        self.connection
+        self.reader
             .call(move |conn| {
                 let mut sql = String::from(
-                    "SELECT id, page_id, url, title, favicon_url, browser_type, tab_id, closed_at, session_info, content_summary \
-                     FROM tab_history WHERE 1=1"
+                    "SELECT id, page_id, url, title, favicon_url, browser_type, tab_id, closed_at, session_info, content_summary, recall_hint \
+                     FROM tab_history WHERE deleted_at IS NULL"
                 );
                 let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
                 
@@ -811,14 +1937,45 @@ impl HistoryRepository for SqliteHistoryRepository {
             })
     }
 
+    async fn list(&self, query: &ListQuery) -> Result<Paged<HistoryEntry>> {
+        let query = query.clone();
+        
+        self.reader
+            .call(move |conn| {
+                let select = "SELECT id, page_id, url, title, favicon_url, browser_type, tab_id, closed_at, session_info, content_summary, recall_hint \
+                              FROM tab_history WHERE deleted_at IS NULL";
+                Ok(list_keyset(conn, select, Vec::new(), "closed_at", &query, row_to_history_entry, |entry| Cursor {
+                    key: entry.closed_at.timestamp(),
+                    id: entry.id.0.to_string(),
+                })?)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to list history: {}", e),
+                },
+            })
+    }
+
+    fn stream_all(&self) -> BoxStream<'static, Result<HistoryEntry>> {
+        let repo = self.clone();
+        stream_keyset(move |after| {
+            let repo = repo.clone();
+            async move {
+                let query = ListQuery { after, limit: STREAM_BATCH_SIZE, sort: SortOrder::Ascending, ..Default::default() };
+                repo.list(&query).await
+            }
+        })
+    }
+
     async fn delete(&self, id: &HistoryId) -> Result<()> {
         let id_str = id.0.to_string();
         
         self.connection
             .call(move |conn| {
                 conn.execute(
-                    "DELETE FROM tab_history WHERE id = ?1",
-                    [&id_str],
+                    "UPDATE tab_history SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                    rusqlite::params![Utc::now().timestamp(), id_str],
                 )?;
                 Ok(())
             })
@@ -852,23 +2009,27 @@ impl HistoryRepository for SqliteHistoryRepository {
     }
 
     async fn search(&self, query: &str, limit: usize) -> Result<Vec<HistoryEntry>> {
-        let query_str = format!("{}*", query.replace('"', "\"\""));
+        let Some(filter) = FtsFilter::new("history_fts", &["title", "url", "recall_hint"], query) else {
+            return Ok(Vec::new());
+        };
         
-        self.connection
+        self.reader
             .call(move |conn| {
-                let mut stmt = conn.prepare(
+                let order = if filter.ranked { "rank" } else { "h.closed_at DESC" };
+                let mut stmt = conn.prepare(&format!(
                     r#"
                     SELECT h.id, h.page_id, h.url, h.title, h.favicon_url, h.browser_type, h.tab_id, 
-                           h.closed_at, h.session_info, h.content_summary
+                           h.closed_at, h.session_info, h.content_summary, h.recall_hint
                     FROM tab_history h
                     JOIN history_fts fts ON h.rowid = fts.rowid
-                    WHERE history_fts MATCH ?1
-                    ORDER BY rank
-                    LIMIT ?2
-                    "#
-                )?;
+                    WHERE ({}) AND h.deleted_at IS NULL
+                    ORDER BY {}
+                    LIMIT {}
+                    "#,
+                    filter.condition, order, limit
+                ))?;
                 
-                let rows = stmt.query_map(rusqlite::params![query_str, limit as i64], row_to_history_entry)?;
+                let rows = stmt.query_map(rusqlite::params_from_iter(filter.params), row_to_history_entry)?;
                 let mut entries = Vec::new();
                 for row in rows {
                     if let Ok(entry) = row {
@@ -886,10 +2047,10 @@ impl HistoryRepository for SqliteHistoryRepository {
     }
 
     async fn count(&self) -> Result<usize> {
-        self.connection
+        self.reader
             .call(|conn| {
                 let count: i64 = conn.query_row(
-                    "SELECT COUNT(*) FROM tab_history",
+                    "SELECT COUNT(*) FROM tab_history WHERE deleted_at IS NULL",
                     [],
                     |row| row.get(0),
                 )?;
@@ -902,10 +2063,142 @@ impl HistoryRepository for SqliteHistoryRepository {
                 },
             })
     }
+
+    async fn set_recall_hint(&self, id: &HistoryId, hint: Option<&str>) -> Result<bool> {
+        let id_str = id.0.to_string();
+        let hint = hint.map(str::to_string);
+        
+        self.connection
+            .call(move |conn| {
+                let updated = conn.execute(
+                    "UPDATE tab_history SET recall_hint = ?1 WHERE id = ?2",
+                    rusqlite::params![hint, id_str],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to set recall hint: {}", e),
+                },
+            })
+    }
+
+    async fn restore(&self, id: &HistoryId) -> Result<bool> {
+        let id_str = id.0.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                let restored = conn.execute(
+                    "UPDATE tab_history SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+                    [&id_str],
+                )?;
+                Ok(restored > 0)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to restore history entry: {}", e),
+                },
+            })
+    }
+
+    async fn get_trash(&self) -> Result<Vec<Trashed<HistoryEntry>>> {
+        self.reader
+            .call(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT id, page_id, url, title, favicon_url, browser_type, tab_id, closed_at, session_info, content_summary, recall_hint, deleted_at \
+                     FROM tab_history WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, rowid DESC"
+                )?;
+                
+                let rows = stmt.query_map([], |row| {
+                    let deleted_at_ts: i64 = row.get(11)?;
+                    Ok(Trashed {
+                        item: row_to_history_entry(row)?,
+                        deleted_at: DateTime::from_timestamp(deleted_at_ts, 0).unwrap_or_else(Utc::now),
+                    })
+                })?;
+                Ok(rows.flatten().collect())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get trashed history: {}", e),
+                },
+            })
+    }
+
+    async fn purge_trash(&self, before: DateTime<Utc>) -> Result<usize> {
+        let ts = before.timestamp();
+        
+        self.connection
+            .call(move |conn| {
+                let purged = conn.execute(
+                    "DELETE FROM tab_history WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+                    [ts],
+                )?;
+                Ok(purged)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to purge trashed history: {}", e),
+                },
+            })
+    }
+}
+
+/// Insert or update a history entry row
+pub(crate) fn insert_history_entry(conn: &rusqlite::Connection, entry: &HistoryEntry) -> rusqlite::Result<()> {
+    let session_info_json = entry.session_info
+        .as_ref()
+        .map(|s| serde_json::to_string(s).unwrap_or_default());
+    let content_summary_json = entry.page_info.content_summary
+        .as_ref()
+        .map(|s| serde_json::to_string(s).unwrap_or_default());
+    let tab_id_str = entry.tab_id.as_ref().map(|t| t.0.to_string());
+
+    // An upsert rather than REPLACE, which would drop the entry's favicon
+    // and thumbnail. Saving a trashed entry takes it out of the trash.
+    conn.prepare_cached(
+        r#"
+        INSERT INTO tab_history 
+        (id, page_id, url, title, favicon_url, browser_type, tab_id, closed_at, session_info, content_summary, recall_hint)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        ON CONFLICT(id) DO UPDATE SET
+            page_id = excluded.page_id,
+            url = excluded.url,
+            title = excluded.title,
+            favicon_url = excluded.favicon_url,
+            browser_type = excluded.browser_type,
+            tab_id = excluded.tab_id,
+            closed_at = excluded.closed_at,
+            session_info = excluded.session_info,
+            content_summary = excluded.content_summary,
+            recall_hint = excluded.recall_hint,
+            deleted_at = NULL
+        "#,
+    )?
+    .execute(
+        rusqlite::params![
+            entry.id.0.to_string(),
+            entry.page_info.id.to_string(),
+            entry.page_info.url,
+            entry.page_info.title,
+            entry.page_info.favicon_url,
+            serde_json::to_string(&entry.browser_type).unwrap_or_default(),
+            tab_id_str,
+            entry.closed_at.timestamp(),
+            session_info_json,
+            content_summary_json,
+            entry.recall_hint,
+        ],
+    )?;
+    Ok(())
 }
 
 /// Helper function to map a row to HistoryEntry
-fn row_to_history_entry(row: &Row) -> rusqlite::Result<HistoryEntry> {
+pub(crate) fn row_to_history_entry(row: &Row) -> rusqlite::Result<HistoryEntry> {
     let id_str: String = row.get(0)?;
     let page_id_str: Option<String> = row.get(1)?;
     let url: String = row.get(2)?;
@@ -916,6 +2209,7 @@ fn row_to_history_entry(row: &Row) -> rusqlite::Result<HistoryEntry> {
     let closed_at_ts: i64 = row.get(7)?;
     let session_info_json: Option<String> = row.get(8)?;
     let content_summary_json: Option<String> = row.get(9)?;
+    let recall_hint: Option<String> = row.get(10)?;
 
     let id = HistoryId(Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4()));
     let page_id = page_id_str
@@ -953,18 +2247,30 @@ fn row_to_history_entry(row: &Row) -> rusqlite::Result<HistoryEntry> {
         tab_id,
         closed_at: DateTime::from_timestamp(closed_at_ts, 0).unwrap_or_else(Utc::now),
         session_info,
+        recall_hint,
     })
 }
 
 
 /// SQLite implementation of ArchiveRepository
+#[derive(Clone)]
 pub struct SqliteArchiveRepository {
     connection: Arc<Connection>,
+    /// Connection for list and search queries
+    reader: Arc<Connection>,
 }
 
 impl SqliteArchiveRepository {
     pub fn new(connection: Arc<Connection>) -> Self {
-        Self { connection }
+        Self {
+            reader: Arc::clone(&connection),
+            connection,
+        }
+    }
+
+    /// Run list and search queries on a separate read-only connection
+    pub fn with_reader(connection: Arc<Connection>, reader: Arc<Connection>) -> Self {
+        Self { connection, reader }
     }
 }
 
@@ -975,27 +2281,7 @@ impl ArchiveRepository for SqliteArchiveRepository {
         
         self.connection
             .call(move |conn| {
-                let media_files_json = serde_json::to_string(&archive_clone.media_files).unwrap_or_default();
-                
-                conn.execute(
-                    r#"
-                    INSERT OR REPLACE INTO content_archives 
-                    (id, page_id, url, title, content_html, content_text, media_files, archived_at, file_size, checksum)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
-                    "#,
-                    rusqlite::params![
-                        archive_clone.id.0.to_string(),
-                        archive_clone.page_id.to_string(),
-                        archive_clone.url,
-                        archive_clone.title,
-                        archive_clone.content_html,
-                        archive_clone.content_text,
-                        media_files_json,
-                        archive_clone.archived_at.timestamp(),
-                        archive_clone.file_size as i64,
-                        archive_clone.checksum,
-                    ],
-                )?;
+                insert_archive(conn, &archive_clone)?;
                 Ok(())
             })
             .await
@@ -1014,8 +2300,9 @@ impl ArchiveRepository for SqliteArchiveRepository {
         self.connection
             .call(move |conn| {
                 let mut stmt = conn.prepare(
-                    "SELECT id, page_id, url, title, content_html, content_text, media_files, archived_at, file_size, checksum \
-                     FROM content_archives WHERE id = ?1"
+                    "SELECT a.id, a.page_id, a.url, a.title, a.content_html, a.content_text, a.media_files, \
+                     a.archived_at, a.file_size, a.checksum, b.data \
+                     FROM content_archives a LEFT JOIN archive_blobs b ON b.hash = a.html_hash WHERE a.id = ?1"
                 )?;
                 
                 let result = stmt.query_row([&id_str], row_to_archive);
@@ -1040,8 +2327,10 @@ impl ArchiveRepository for SqliteArchiveRepository {
         self.connection
             .call(move |conn| {
                 let mut stmt = conn.prepare(
-                    "SELECT id, page_id, url, title, content_html, content_text, media_files, archived_at, file_size, checksum \
-                     FROM content_archives WHERE page_id = ?1 ORDER BY archived_at DESC LIMIT 1"
+                    "SELECT a.id, a.page_id, a.url, a.title, a.content_html, a.content_text, a.media_files, \
+                     a.archived_at, a.file_size, a.checksum, b.data \
+                     FROM content_archives a LEFT JOIN archive_blobs b ON b.hash = a.html_hash \
+                     WHERE a.page_id = ?1 ORDER BY a.archived_at DESC LIMIT 1"
                 )?;
                 
                 let result = stmt.query_row([&page_id_str], row_to_archive);
@@ -1081,24 +2370,59 @@ impl ArchiveRepository for SqliteArchiveRepository {
         Ok(())
     }
 
+    async fn list(&self, query: &ListQuery) -> Result<Paged<ContentArchive>> {
+        let query = query.clone();
+        
+        self.reader
+            .call(move |conn| {
+                let select = "SELECT id, page_id, url, title, '', content_text, media_files, archived_at, file_size, checksum, NULL \
+                              FROM content_archives WHERE 1=1";
+                Ok(list_keyset(conn, select, Vec::new(), "archived_at", &query, row_to_archive, |archive| Cursor {
+                    key: archive.archived_at.timestamp(),
+                    id: archive.id.0.to_string(),
+                })?)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to list archives: {}", e),
+                },
+            })
+    }
+
+    fn stream_all(&self) -> BoxStream<'static, Result<ContentArchive>> {
+        let repo = self.clone();
+        stream_keyset(move |after| {
+            let repo = repo.clone();
+            async move {
+                let query = ListQuery { after, limit: STREAM_BATCH_SIZE, sort: SortOrder::Ascending, ..Default::default() };
+                repo.list(&query).await
+            }
+        })
+    }
+
     async fn search(&self, query: &str, limit: usize) -> Result<Vec<ContentArchive>> {
-        let query_str = format!("{}*", query.replace('"', "\"\""));
+        let Some(filter) = FtsFilter::new("archives_fts", &["title", "content_text", "url"], query) else {
+            return Ok(Vec::new());
+        };
         
-        self.connection
+        self.reader
             .call(move |conn| {
-                let mut stmt = conn.prepare(
+                let order = if filter.ranked { "rank" } else { "a.archived_at DESC" };
+                let mut stmt = conn.prepare(&format!(
                     r#"
-                    SELECT a.id, a.page_id, a.url, a.title, a.content_html, a.content_text, 
-                           a.media_files, a.archived_at, a.file_size, a.checksum
+                    SELECT a.id, a.page_id, a.url, a.title, '', a.content_text, 
+                           a.media_files, a.archived_at, a.file_size, a.checksum, NULL
                     FROM content_archives a
                     JOIN archives_fts fts ON a.rowid = fts.rowid
-                    WHERE archives_fts MATCH ?1
-                    ORDER BY rank
-                    LIMIT ?2
-                    "#
-                )?;
+                    WHERE {}
+                    ORDER BY {}
+                    LIMIT {}
+                    "#,
+                    filter.condition, order, limit
+                ))?;
                 
-                let rows = stmt.query_map(rusqlite::params![query_str, limit as i64], row_to_archive)?;
+                let rows = stmt.query_map(rusqlite::params_from_iter(filter.params), row_to_archive)?;
                 let mut archives = Vec::new();
                 for row in rows {
                     if let Ok(archive) = row {
@@ -1115,8 +2439,34 @@ impl ArchiveRepository for SqliteArchiveRepository {
             })
     }
 
-    async fn get_total_size(&self) -> Result<u64> {
+    async fn get_html(&self, id: &ArchiveId) -> Result<Option<String>> {
+        let id_str = id.0.to_string();
+        
         self.connection
+            .call(move |conn| {
+                let html = conn
+                    .query_row(
+                        "SELECT a.content_html, b.data FROM content_archives a \
+                         LEFT JOIN archive_blobs b ON b.hash = a.html_hash WHERE a.id = ?1",
+                        [&id_str],
+                        |row| match row.get::<_, Option<Vec<u8>>>(1)? {
+                            Some(data) => decompress_column(&data, 1),
+                            None => row.get(0),
+                        },
+                    )
+                    .optional()?;
+                Ok(html)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get archive HTML: {}", e),
+                },
+            })
+    }
+
+    async fn get_total_size(&self) -> Result<u64> {
+        self.reader
             .call(|conn| {
                 let size: i64 = conn.query_row(
                     "SELECT COALESCE(SUM(file_size), 0) FROM content_archives",
@@ -1132,28 +2482,159 @@ impl ArchiveRepository for SqliteArchiveRepository {
                 },
             })
     }
-}
 
-/// Helper function to map a row to ContentArchive
-fn row_to_archive(row: &Row) -> rusqlite::Result<ContentArchive> {
-    let id_str: String = row.get(0)?;
-    let page_id_str: String = row.get(1)?;
-    let url: String = row.get(2)?;
-    let title: String = row.get(3)?;
-    let content_html: String = row.get(4)?;
-    let content_text: String = row.get(5)?;
-    let media_files_json: String = row.get(6)?;
-    let archived_at_ts: i64 = row.get(7)?;
-    let file_size: i64 = row.get(8)?;
-    let checksum: Option<String> = row.get(9)?;
-
-    let id = ArchiveId(Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4()));
-    let page_id = Uuid::parse_str(&page_id_str).unwrap_or_else(|_| Uuid::new_v4());
-    let media_files: Vec<String> = serde_json::from_str(&media_files_json).unwrap_or_default();
+    async fn storage_stats(&self) -> Result<ArchiveStorageStats> {
+        self.reader
+            .call(|conn| {
+                let (archives, content_bytes, inline_bytes): (i64, i64, i64) = conn.query_row(
+                    r#"
+                    SELECT COUNT(*),
+                           COALESCE(SUM(COALESCE(b.original_size, length(CAST(a.content_html AS BLOB)))), 0),
+                           COALESCE(SUM(length(CAST(a.content_html AS BLOB))), 0)
+                    FROM content_archives a
+                    LEFT JOIN archive_blobs b ON b.hash = a.html_hash
+                    "#,
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )?;
+                let (blobs, blob_bytes): (i64, i64) = conn.query_row(
+                    "SELECT COUNT(*), COALESCE(SUM(length(data)), 0) FROM archive_blobs",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )?;
+                Ok(ArchiveStorageStats {
+                    archives: archives as usize,
+                    blobs: blobs as usize,
+                    content_bytes: content_bytes as u64,
+                    stored_bytes: (blob_bytes + inline_bytes) as u64,
+                })
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get archive storage stats: {}", e),
+                },
+            })
+    }
 
-    Ok(ContentArchive {
-        id,
-        page_id,
+    async fn compact(&self) -> Result<usize> {
+        self.connection
+            .call(|conn| {
+                let tx = conn.transaction()?;
+                let inline: Vec<(String, String)> = {
+                    let mut stmt = tx.prepare(
+                        "SELECT id, content_html FROM content_archives WHERE html_hash IS NULL AND content_html != ''"
+                    )?;
+                    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                    rows.collect::<rusqlite::Result<_>>()?
+                };
+                for (id, html) in &inline {
+                    let hash = store_blob(&tx, html)?;
+                    tx.execute(
+                        "UPDATE content_archives SET content_html = '', html_hash = ?1 WHERE id = ?2",
+                        rusqlite::params![hash, id],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(inline.len())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to compact archives: {}", e),
+                },
+            })
+    }
+}
+
+/// Store compressed `html` unless an archive already shares it, returning
+/// its hash
+fn store_blob(conn: &rusqlite::Connection, html: &str) -> rusqlite::Result<String> {
+    let hash = content_hash(html);
+    let exists = conn
+        .query_row("SELECT 1 FROM archive_blobs WHERE hash = ?1", [&hash], |_| Ok(()))
+        .optional()?
+        .is_some();
+    if !exists {
+        let data = compress(html).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        conn.execute(
+            "INSERT INTO archive_blobs (hash, data, original_size) VALUES (?1, ?2, ?3)",
+            rusqlite::params![hash, data, html.len() as i64],
+        )?;
+    }
+    Ok(hash)
+}
+
+/// Insert or replace a archive row, storing its HTML compressed
+pub(crate) fn insert_archive(conn: &rusqlite::Connection, archive: &ContentArchive) -> rusqlite::Result<()> {
+    let media_files_json = serde_json::to_string(&archive.media_files).unwrap_or_default();
+    let html_hash = if archive.content_html.is_empty() {
+        None
+    } else {
+        Some(store_blob(conn, &archive.content_html)?)
+    };
+
+    // An update keeps the rowid for the FTS triggers and releases the
+    // previous blob through its trigger
+    conn.execute(
+        r#"
+        INSERT INTO content_archives 
+        (id, page_id, url, title, content_html, content_text, media_files, archived_at, file_size, checksum, html_hash)
+        VALUES (?1, ?2, ?3, ?4, '', ?5, ?6, ?7, ?8, ?9, ?10)
+        ON CONFLICT(id) DO UPDATE SET
+            page_id = excluded.page_id,
+            url = excluded.url,
+            title = excluded.title,
+            content_html = excluded.content_html,
+            content_text = excluded.content_text,
+            media_files = excluded.media_files,
+            archived_at = excluded.archived_at,
+            file_size = excluded.file_size,
+            checksum = excluded.checksum,
+            html_hash = excluded.html_hash
+        "#,
+        rusqlite::params![
+            archive.id.0.to_string(),
+            archive.page_id.to_string(),
+            archive.url,
+            archive.title,
+            archive.content_text,
+            media_files_json,
+            archive.archived_at.timestamp(),
+            archive.file_size as i64,
+            archive.checksum,
+            html_hash,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Helper function to map a row to ContentArchive
+///
+/// Column 10 is the compressed HTML blob, if any; otherwise the HTML is
+/// read inline from column 4.
+pub(crate) fn row_to_archive(row: &Row) -> rusqlite::Result<ContentArchive> {
+    let id_str: String = row.get(0)?;
+    let page_id_str: String = row.get(1)?;
+    let url: String = row.get(2)?;
+    let title: String = row.get(3)?;
+    let content_html = match row.get::<_, Option<Vec<u8>>>(10)? {
+        Some(data) => decompress_column(&data, 10)?,
+        None => row.get(4)?,
+    };
+    let content_text: String = row.get(5)?;
+    let media_files_json: String = row.get(6)?;
+    let archived_at_ts: i64 = row.get(7)?;
+    let file_size: i64 = row.get(8)?;
+    let checksum: Option<String> = row.get(9)?;
+
+    let id = ArchiveId(Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4()));
+    let page_id = Uuid::parse_str(&page_id_str).unwrap_or_else(|_| Uuid::new_v4());
+    let media_files: Vec<String> = serde_json::from_str(&media_files_json).unwrap_or_default();
+
+    Ok(ContentArchive {
+        id,
+        page_id,
         url,
         title,
         content_html,
@@ -1165,12 +2646,996 @@ fn row_to_archive(row: &Row) -> rusqlite::Result<ContentArchive> {
     })
 }
 
+/// SQLite implementation of AnalysisCacheRepository
+pub struct SqliteAnalysisCacheRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteAnalysisCacheRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+}
+
+#[async_trait]
+impl AnalysisCacheRepository for SqliteAnalysisCacheRepository {
+    async fn save(&self, entry: &AnalysisCacheEntry) -> Result<()> {
+        self.save_batch(std::slice::from_ref(entry)).await
+    }
+
+    async fn save_batch(&self, entries: &[AnalysisCacheEntry]) -> Result<()> {
+        let entries = entries.to_vec();
+        
+        self.connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                {
+                    let mut stmt = tx.prepare(
+                        r#"
+                        INSERT INTO analysis_cache (content_hash, analysis, created_at, last_used)
+                        VALUES (?1, ?2, ?3, ?4)
+                        ON CONFLICT(content_hash) DO UPDATE SET
+                            analysis = excluded.analysis,
+                            last_used = MAX(last_used, excluded.last_used)
+                        "#
+                    )?;
+                    for entry in &entries {
+                        stmt.execute(rusqlite::params![
+                            entry.content_hash,
+                            entry.analysis,
+                            entry.created_at.timestamp(),
+                            entry.last_used.timestamp(),
+                        ])?;
+                    }
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to save analysis cache entries: {}", e),
+                },
+            })
+    }
+
+    async fn get(&self, content_hash: &str) -> Result<Option<AnalysisCacheEntry>> {
+        let content_hash = content_hash.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                let now = Utc::now().timestamp();
+                conn.execute(
+                    "UPDATE analysis_cache SET last_used = ?1 WHERE content_hash = ?2",
+                    rusqlite::params![now, content_hash],
+                )?;
+                
+                let result = conn.query_row(
+                    "SELECT content_hash, analysis, created_at, last_used FROM analysis_cache WHERE content_hash = ?1",
+                    [&content_hash],
+                    row_to_analysis_cache_entry,
+                );
+                
+                match result {
+                    Ok(entry) => Ok(Some(entry)),
+                    Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                    Err(e) => Err(e.into()),
+                }
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get analysis cache entry: {}", e),
+                },
+            })
+    }
+
+    async fn get_recent(&self, limit: usize) -> Result<Vec<AnalysisCacheEntry>> {
+        self.connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT content_hash, analysis, created_at, last_used FROM analysis_cache \
+                     ORDER BY last_used DESC LIMIT ?1"
+                )?;
+                
+                let rows = stmt.query_map([limit as i64], row_to_analysis_cache_entry)?;
+                let mut entries = Vec::new();
+                for row in rows {
+                    entries.push(row?);
+                }
+                Ok(entries)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get analysis cache entries: {}", e),
+                },
+            })
+    }
+
+    async fn delete_unused_since(&self, timestamp: DateTime<Utc>) -> Result<usize> {
+        let ts = timestamp.timestamp();
+        
+        self.connection
+            .call(move |conn| {
+                let deleted = conn.execute(
+                    "DELETE FROM analysis_cache WHERE last_used < ?1",
+                    [ts],
+                )?;
+                Ok(deleted)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to prune analysis cache: {}", e),
+                },
+            })
+    }
+
+    async fn count(&self) -> Result<usize> {
+        self.connection
+            .call(|conn| {
+                let count: i64 = conn.query_row(
+                    "SELECT COUNT(*) FROM analysis_cache",
+                    [],
+                    |row| row.get(0),
+                )?;
+                Ok(count as usize)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to count analysis cache entries: {}", e),
+                },
+            })
+    }
+}
+
+/// Helper function to map a row to AnalysisCacheEntry
+fn row_to_analysis_cache_entry(row: &Row) -> rusqlite::Result<AnalysisCacheEntry> {
+    let created_at_ts: i64 = row.get(2)?;
+    let last_used_ts: i64 = row.get(3)?;
+
+    Ok(AnalysisCacheEntry {
+        content_hash: row.get(0)?,
+        analysis: row.get(1)?,
+        created_at: DateTime::from_timestamp(created_at_ts, 0).unwrap_or_else(Utc::now),
+        last_used: DateTime::from_timestamp(last_used_ts, 0).unwrap_or_else(Utc::now),
+    })
+}
+
+/// Helper function to map a row to Tag
+pub(crate) fn row_to_tag(row: &Row) -> rusqlite::Result<Tag> {
+    let id_str: String = row.get(0)?;
+    let created_at_ts: i64 = row.get(2)?;
+    let parent_id: Option<String> = row.get(3)?;
+
+    Ok(Tag {
+        id: Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4()),
+        name: row.get(1)?,
+        created_at: DateTime::from_timestamp(created_at_ts, 0).unwrap_or_else(Utc::now),
+        parent_id: parent_id.and_then(|id| Uuid::parse_str(&id).ok()),
+    })
+}
+
+/// Error for a tag name that is empty or taken by another tag
+fn tag_name_error(details: String) -> WebPageManagerError {
+    WebPageManagerError::DataConsistency {
+        source: DataConsistencyError::DatabaseIntegrityViolation { details },
+    }
+}
+
+/// SQLite implementation of TagRepository
+pub struct SqliteTagRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteTagRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+}
+
+#[async_trait]
+impl TagRepository for SqliteTagRepository {
+    async fn get_or_create(&self, name: &str) -> Result<Tag> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(tag_name_error("Tag name is empty".to_string()));
+        }
+        
+        self.connection
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT OR IGNORE INTO tags (id, name, created_at) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![Uuid::new_v4().to_string(), name, Utc::now().timestamp()],
+                )?;
+                let tag = conn.query_row(
+                    "SELECT id, name, created_at, parent_id FROM tags WHERE name = ?1",
+                    [&name],
+                    row_to_tag,
+                )?;
+                Ok(tag)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to create tag: {}", e),
+                },
+            })
+    }
+
+    async fn get_by_id(&self, id: &Uuid) -> Result<Option<Tag>> {
+        let id_str = id.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                let result = conn.query_row(
+                    "SELECT id, name, created_at, parent_id FROM tags WHERE id = ?1",
+                    [&id_str],
+                    row_to_tag,
+                );
+                
+                match result {
+                    Ok(tag) => Ok(Some(tag)),
+                    Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                    Err(e) => Err(e.into()),
+                }
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get tag: {}", e),
+                },
+            })
+    }
+
+    async fn get_by_name(&self, name: &str) -> Result<Option<Tag>> {
+        let name = name.trim().to_string();
+        
+        self.connection
+            .call(move |conn| {
+                let result = conn.query_row(
+                    "SELECT id, name, created_at, parent_id FROM tags WHERE name = ?1",
+                    [&name],
+                    row_to_tag,
+                );
+                
+                match result {
+                    Ok(tag) => Ok(Some(tag)),
+                    Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                    Err(e) => Err(e.into()),
+                }
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get tag: {}", e),
+                },
+            })
+    }
+
+    async fn get_all(&self) -> Result<Vec<Tag>> {
+        self.connection
+            .call(|conn| {
+                let mut stmt = conn.prepare("SELECT id, name, created_at, parent_id FROM tags ORDER BY name")?;
+                let rows = stmt.query_map([], row_to_tag)?;
+                let mut tags = Vec::new();
+                for row in rows {
+                    tags.push(row?);
+                }
+                Ok(tags)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get tags: {}", e),
+                },
+            })
+    }
+
+    async fn delete(&self, id: &Uuid) -> Result<()> {
+        let id_str = id.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute(
+                    "UPDATE tags SET parent_id = (SELECT parent_id FROM tags WHERE id = ?1) WHERE parent_id = ?1",
+                    [&id_str],
+                )?;
+                tx.execute("DELETE FROM page_tags WHERE tag_id = ?1", [&id_str])?;
+                tx.execute("DELETE FROM tags WHERE id = ?1", [&id_str])?;
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to delete tag: {}", e),
+                },
+            })
+    }
+
+    async fn rename(&self, id: &Uuid, name: &str) -> Result<bool> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(tag_name_error("Tag name is empty".to_string()));
+        }
+        let id_str = id.to_string();
+        
+        let renamed = self
+            .connection
+            .call(move |conn| {
+                let taken: Option<String> = conn
+                    .query_row(
+                        "SELECT name FROM tags WHERE name = ?1 AND id != ?2",
+                        [&name, &id_str],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if let Some(taken) = taken {
+                    return Ok(Err(taken));
+                }
+                let updated = conn.execute("UPDATE tags SET name = ?1 WHERE id = ?2", [&name, &id_str])?;
+                Ok(Ok(updated > 0))
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to rename tag: {}", e),
+                },
+            })?;
+        
+        renamed.map_err(|taken| tag_name_error(format!("Tag \"{}\" already exists; merge the tags instead", taken)))
+    }
+
+    async fn merge(&self, source: &Uuid, target: &Uuid) -> Result<usize> {
+        if source == target {
+            return Ok(0);
+        }
+        let source_str = source.to_string();
+        let target_str = target.to_string();
+        
+        let merged = self
+            .connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                let target_exists: bool =
+                    tx.query_row("SELECT EXISTS(SELECT 1 FROM tags WHERE id = ?1)", [&target_str], |row| row.get(0))?;
+                if !target_exists {
+                    return Ok(None);
+                }
+                let moved = tx.execute(
+                    "INSERT OR IGNORE INTO page_tags (page_id, tag_id, added_at) \
+                     SELECT page_id, ?2, added_at FROM page_tags WHERE tag_id = ?1",
+                    [&source_str, &target_str],
+                )?;
+                // A target nested under the source takes the source's place
+                tx.execute(
+                    "UPDATE tags SET parent_id = (SELECT parent_id FROM tags WHERE id = ?1) \
+                     WHERE id = ?2 AND ?1 IN ( \
+                         WITH RECURSIVE ancestors(id) AS ( \
+                             SELECT parent_id FROM tags WHERE id = ?2 \
+                             UNION SELECT t.parent_id FROM tags t JOIN ancestors a ON t.id = a.id \
+                         ) SELECT id FROM ancestors)",
+                    [&source_str, &target_str],
+                )?;
+                tx.execute("UPDATE tags SET parent_id = ?2 WHERE parent_id = ?1", [&source_str, &target_str])?;
+                tx.execute("DELETE FROM page_tags WHERE tag_id = ?1", [&source_str])?;
+                tx.execute("DELETE FROM tags WHERE id = ?1", [&source_str])?;
+                tx.commit()?;
+                Ok(Some(moved))
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to merge tags: {}", e),
+                },
+            })?;
+        
+        merged.ok_or_else(|| tag_name_error(format!("Tag {} does not exist", target)))
+    }
+
+    async fn set_parent(&self, id: &Uuid, parent: Option<&Uuid>) -> Result<bool> {
+        let id_str = id.to_string();
+        let parent_str = parent.map(Uuid::to_string);
+        
+        let updated = self
+            .connection
+            .call(move |conn| {
+                if let Some(parent_str) = &parent_str {
+                    // Walk up from the parent; reaching the tag would make a cycle
+                    let ancestors: Vec<String> = conn
+                        .prepare(
+                            "WITH RECURSIVE ancestors(id) AS ( \
+                                 SELECT id FROM tags WHERE id = ?1 \
+                                 UNION SELECT t.parent_id FROM tags t JOIN ancestors a ON t.id = a.id \
+                                 WHERE t.parent_id IS NOT NULL \
+                             ) SELECT id FROM ancestors",
+                        )?
+                        .query_map([parent_str], |row| row.get(0))?
+                        .collect::<rusqlite::Result<_>>()?;
+                    if ancestors.is_empty() {
+                        return Ok(Err(format!("Tag {} does not exist", parent_str)));
+                    }
+                    if ancestors.contains(&id_str) {
+                        return Ok(Err("A tag cannot be nested under itself or its children".to_string()));
+                    }
+                }
+                let updated = conn.execute(
+                    "UPDATE tags SET parent_id = ?1 WHERE id = ?2",
+                    rusqlite::params![parent_str, id_str],
+                )?;
+                Ok(Ok(updated > 0))
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to set tag parent: {}", e),
+                },
+            })?;
+        
+        updated.map_err(tag_name_error)
+    }
+
+    async fn tag_page(&self, page_id: &Uuid, tag_id: &Uuid) -> Result<()> {
+        let page_id_str = page_id.to_string();
+        let tag_id_str = tag_id.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT OR IGNORE INTO page_tags (page_id, tag_id, added_at) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![page_id_str, tag_id_str, Utc::now().timestamp()],
+                )?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to tag page: {}", e),
+                },
+            })
+    }
+
+    async fn untag_page(&self, page_id: &Uuid, tag_id: &Uuid) -> Result<()> {
+        let page_id_str = page_id.to_string();
+        let tag_id_str = tag_id.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                conn.execute(
+                    "DELETE FROM page_tags WHERE page_id = ?1 AND tag_id = ?2",
+                    [&page_id_str, &tag_id_str],
+                )?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to untag page: {}", e),
+                },
+            })
+    }
+
+    async fn get_tags_for_page(&self, page_id: &Uuid) -> Result<Vec<Tag>> {
+        let page_id_str = page_id.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT t.id, t.name, t.created_at, t.parent_id FROM tags t \
+                     JOIN page_tags pt ON pt.tag_id = t.id \
+                     WHERE pt.page_id = ?1 ORDER BY t.name"
+                )?;
+                let rows = stmt.query_map([&page_id_str], row_to_tag)?;
+                let mut tags = Vec::new();
+                for row in rows {
+                    tags.push(row?);
+                }
+                Ok(tags)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get tags for page: {}", e),
+                },
+            })
+    }
+
+    async fn get_pages_with_tag(&self, tag_id: &Uuid) -> Result<Vec<Uuid>> {
+        let tag_id_str = tag_id.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT pt.page_id FROM page_tags pt JOIN unified_pages p ON p.id = pt.page_id \
+                     WHERE pt.tag_id = ?1 AND p.deleted_at IS NULL ORDER BY pt.added_at DESC, pt.page_id"
+                )?;
+                let rows = stmt.query_map([&tag_id_str], |row| row.get::<_, String>(0))?;
+                Ok(rows.flatten().filter_map(|id| Uuid::parse_str(&id).ok()).collect())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get pages with tag: {}", e),
+                },
+            })
+    }
+
+    async fn tag_counts(&self) -> Result<Vec<TagCount>> {
+        self.connection
+            .call(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT t.id, t.name, t.created_at, t.parent_id, COUNT(p.id) AS page_count FROM tags t \
+                     LEFT JOIN page_tags pt ON pt.tag_id = t.id \
+                     LEFT JOIN unified_pages p ON p.id = pt.page_id AND p.deleted_at IS NULL \
+                     GROUP BY t.id ORDER BY page_count DESC, t.name"
+                )?;
+                let rows = stmt.query_map([], |row| {
+                    let page_count: i64 = row.get(4)?;
+                    Ok(TagCount {
+                        tag: row_to_tag(row)?,
+                        page_count: page_count as usize,
+                    })
+                })?;
+                let mut counts = Vec::new();
+                for row in rows {
+                    counts.push(row?);
+                }
+                Ok(counts)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to count tags: {}", e),
+                },
+            })
+    }
+}
+
+/// Insert or replace a note row
+pub(crate) fn insert_note(conn: &rusqlite::Connection, note: &PageNote) -> rusqlite::Result<()> {
+    // An update keeps the rowid so the FTS triggers see an update
+    conn.execute(
+        r#"
+        INSERT INTO page_notes (id, page_id, content, quote, created_at, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        ON CONFLICT(id) DO UPDATE SET
+            page_id = excluded.page_id,
+            content = excluded.content,
+            quote = excluded.quote,
+            updated_at = excluded.updated_at
+        "#,
+        rusqlite::params![
+            note.id.to_string(),
+            note.page_id.to_string(),
+            note.content,
+            note.quote,
+            note.created_at.timestamp(),
+            note.updated_at.timestamp(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Helper function to map a row to PageNote
+pub(crate) fn row_to_note(row: &Row) -> rusqlite::Result<PageNote> {
+    let id_str: String = row.get(0)?;
+    let page_id_str: String = row.get(1)?;
+    let created_at_ts: i64 = row.get(4)?;
+    let updated_at_ts: i64 = row.get(5)?;
+
+    Ok(PageNote {
+        id: Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4()),
+        page_id: Uuid::parse_str(&page_id_str).unwrap_or_else(|_| Uuid::new_v4()),
+        content: row.get(2)?,
+        quote: row.get(3)?,
+        created_at: DateTime::from_timestamp(created_at_ts, 0).unwrap_or_else(Utc::now),
+        updated_at: DateTime::from_timestamp(updated_at_ts, 0).unwrap_or_else(Utc::now),
+    })
+}
+
+/// SQLite implementation of NoteRepository
+pub struct SqliteNoteRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteNoteRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+}
+
+#[async_trait]
+impl NoteRepository for SqliteNoteRepository {
+    async fn save(&self, note: &PageNote) -> Result<()> {
+        let note_clone = note.clone();
+        
+        self.connection
+            .call(move |conn| {
+                insert_note(conn, &note_clone)?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to save note: {}", e),
+                },
+            })
+    }
+
+    async fn get_by_id(&self, id: &Uuid) -> Result<Option<PageNote>> {
+        let id_str = id.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                let result = conn.query_row(
+                    "SELECT id, page_id, content, quote, created_at, updated_at FROM page_notes WHERE id = ?1",
+                    [&id_str],
+                    row_to_note,
+                );
+                
+                match result {
+                    Ok(note) => Ok(Some(note)),
+                    Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                    Err(e) => Err(e.into()),
+                }
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get note: {}", e),
+                },
+            })
+    }
+
+    async fn get_for_page(&self, page_id: &Uuid) -> Result<Vec<PageNote>> {
+        let page_id_str = page_id.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT id, page_id, content, quote, created_at, updated_at FROM page_notes \
+                     WHERE page_id = ?1 ORDER BY created_at, rowid"
+                )?;
+                let rows = stmt.query_map([&page_id_str], row_to_note)?;
+                let mut notes = Vec::new();
+                for row in rows {
+                    notes.push(row?);
+                }
+                Ok(notes)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get notes for page: {}", e),
+                },
+            })
+    }
+
+    async fn delete(&self, id: &Uuid) -> Result<()> {
+        let id_str = id.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                conn.execute("DELETE FROM page_notes WHERE id = ?1", [&id_str])?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to delete note: {}", e),
+                },
+            })
+    }
+
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<PageNote>> {
+        let Some(filter) = FtsFilter::new("notes_fts", &["content", "quote"], query) else {
+            return Ok(Vec::new());
+        };
+        
+        self.connection
+            .call(move |conn| {
+                let order = if filter.ranked { "rank" } else { "n.updated_at DESC" };
+                let mut stmt = conn.prepare(&format!(
+                    r#"
+                    SELECT n.id, n.page_id, n.content, n.quote, n.created_at, n.updated_at
+                    FROM page_notes n
+                    JOIN notes_fts fts ON n.rowid = fts.rowid
+                    WHERE {}
+                    ORDER BY {}
+                    LIMIT {}
+                    "#,
+                    filter.condition, order, limit
+                ))?;
+                
+                let rows = stmt.query_map(rusqlite::params_from_iter(filter.params), row_to_note)?;
+                Ok(rows.flatten().collect())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to search notes: {}", e),
+                },
+            })
+    }
+
+    async fn count(&self) -> Result<usize> {
+        self.connection
+            .call(|conn| {
+                let count: i64 = conn.query_row("SELECT COUNT(*) FROM page_notes", [], |row| row.get(0))?;
+                Ok(count as usize)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to count notes: {}", e),
+                },
+            })
+    }
+}
+
+/// Insert or replace a session with its windows and tabs
+///
+/// Run inside a transaction so a session is never stored half-written.
+pub(crate) fn insert_session(conn: &rusqlite::Connection, session: &SavedSession) -> rusqlite::Result<()> {
+    let id_str = session.id.to_string();
+    conn.execute(
+        r#"
+        INSERT INTO sessions (id, name, created_at, updated_at)
+        VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            updated_at = excluded.updated_at
+        "#,
+        rusqlite::params![
+            id_str,
+            session.name,
+            session.created_at.timestamp(),
+            session.updated_at.timestamp(),
+        ],
+    )?;
+    // Removing the windows cascades to their tabs
+    conn.execute("DELETE FROM session_windows WHERE session_id = ?1", [&id_str])?;
+
+    let mut window_stmt = conn.prepare_cached(
+        "INSERT INTO session_windows (session_id, window_index, browser_type) VALUES (?1, ?2, ?3)",
+    )?;
+    let mut tab_stmt = conn.prepare_cached(
+        "INSERT INTO session_tabs (session_id, window_index, tab_index, url, title, favicon_url, pinned) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    for (window_index, window) in session.windows.iter().enumerate() {
+        window_stmt.execute(rusqlite::params![
+            id_str,
+            window_index as i64,
+            serde_json::to_string(&window.browser).unwrap_or_default(),
+        ])?;
+        for (tab_index, tab) in window.tabs.iter().enumerate() {
+            tab_stmt.execute(rusqlite::params![
+                id_str,
+                window_index as i64,
+                tab_index as i64,
+                tab.url,
+                tab.title,
+                tab.favicon_url,
+                tab.pinned,
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+/// Load a session with its windows and tabs
+pub(crate) fn load_session(conn: &rusqlite::Connection, id: &str) -> rusqlite::Result<Option<SavedSession>> {
+    let session = conn
+        .query_row(
+            "SELECT id, name, created_at, updated_at FROM sessions WHERE id = ?1",
+            [id],
+            |row| {
+                let id_str: String = row.get(0)?;
+                let created_at_ts: i64 = row.get(2)?;
+                let updated_at_ts: i64 = row.get(3)?;
+                Ok(SavedSession {
+                    id: Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4()),
+                    name: row.get(1)?,
+                    windows: Vec::new(),
+                    created_at: DateTime::from_timestamp(created_at_ts, 0).unwrap_or_else(Utc::now),
+                    updated_at: DateTime::from_timestamp(updated_at_ts, 0).unwrap_or_else(Utc::now),
+                })
+            },
+        )
+        .optional()?;
+    let Some(mut session) = session else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare_cached(
+        "SELECT browser_type FROM session_windows WHERE session_id = ?1 ORDER BY window_index",
+    )?;
+    let browsers = stmt.query_map([id], |row| row.get::<_, String>(0))?;
+    for browser in browsers {
+        session.windows.push(SessionWindow {
+            browser: serde_json::from_str(&browser?).unwrap_or(BrowserType::Chrome),
+            tabs: Vec::new(),
+        });
+    }
+
+    let mut stmt = conn.prepare_cached(
+        "SELECT window_index, url, title, favicon_url, pinned FROM session_tabs \
+         WHERE session_id = ?1 ORDER BY window_index, tab_index",
+    )?;
+    let tabs = stmt.query_map([id], |row| {
+        let window_index: i64 = row.get(0)?;
+        Ok((
+            window_index as usize,
+            SessionTab {
+                url: row.get(1)?,
+                title: row.get(2)?,
+                favicon_url: row.get(3)?,
+                pinned: row.get(4)?,
+            },
+        ))
+    })?;
+    for tab in tabs {
+        let (window_index, tab) = tab?;
+        if let Some(window) = session.windows.get_mut(window_index) {
+            window.tabs.push(tab);
+        }
+    }
+    Ok(Some(session))
+}
+
+/// SQLite implementation of SessionRepository
+pub struct SqliteSessionRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteSessionRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+}
+
+#[async_trait]
+impl SessionRepository for SqliteSessionRepository {
+    async fn save(&self, session: &SavedSession) -> Result<()> {
+        let session_clone = session.clone();
+        
+        self.connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                insert_session(&tx, &session_clone)?;
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to save session: {}", e),
+                },
+            })
+    }
+
+    async fn get_by_id(&self, id: &Uuid) -> Result<Option<SavedSession>> {
+        let id_str = id.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                let session = load_session(&tx, &id_str)?;
+                tx.finish()?;
+                Ok(session)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to get session: {}", e),
+                },
+            })
+    }
+
+    async fn list(&self) -> Result<Vec<SessionSummary>> {
+        self.connection
+            .call(|conn| {
+                let mut stmt = conn.prepare(
+                    r#"
+                    SELECT s.id, s.name, s.created_at, s.updated_at,
+                           (SELECT COUNT(*) FROM session_windows w WHERE w.session_id = s.id),
+                           (SELECT COUNT(*) FROM session_tabs t WHERE t.session_id = s.id)
+                    FROM sessions s
+                    ORDER BY s.updated_at DESC, s.rowid DESC
+                    "#,
+                )?;
+                let rows = stmt.query_map([], |row| {
+                    let id_str: String = row.get(0)?;
+                    let created_at_ts: i64 = row.get(2)?;
+                    let updated_at_ts: i64 = row.get(3)?;
+                    let window_count: i64 = row.get(4)?;
+                    let tab_count: i64 = row.get(5)?;
+                    Ok(SessionSummary {
+                        id: Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4()),
+                        name: row.get(1)?,
+                        window_count: window_count as usize,
+                        tab_count: tab_count as usize,
+                        created_at: DateTime::from_timestamp(created_at_ts, 0).unwrap_or_else(Utc::now),
+                        updated_at: DateTime::from_timestamp(updated_at_ts, 0).unwrap_or_else(Utc::now),
+                    })
+                })?;
+                let mut sessions = Vec::new();
+                for row in rows {
+                    sessions.push(row?);
+                }
+                Ok(sessions)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to list sessions: {}", e),
+                },
+            })
+    }
+
+    async fn rename(&self, id: &Uuid, name: &str) -> Result<bool> {
+        let id_str = id.to_string();
+        let name = name.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                let changed = conn.execute(
+                    "UPDATE sessions SET name = ?1, updated_at = ?2 WHERE id = ?3",
+                    rusqlite::params![name, Utc::now().timestamp(), id_str],
+                )?;
+                Ok(changed > 0)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to rename session: {}", e),
+                },
+            })
+    }
+
+    async fn delete(&self, id: &Uuid) -> Result<()> {
+        let id_str = id.to_string();
+        
+        self.connection
+            .call(move |conn| {
+                conn.execute("DELETE FROM sessions WHERE id = ?1", [&id_str])?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to delete session: {}", e),
+                },
+            })
+    }
+
+    async fn count(&self) -> Result<usize> {
+        self.connection
+            .call(|conn| {
+                let count: i64 = conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))?;
+                Ok(count as usize)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to count sessions: {}", e),
+                },
+            })
+    }
+}
+
 /// Unified search result across all data types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UnifiedSearchResult {
     Page(UnifiedPageInfo),
     History(HistoryEntry),
     Archive(ContentArchive),
+    Note(PageNote),
 }
 
 /// Unified search repository for cross-data-source searching
@@ -1178,6 +3643,7 @@ pub struct UnifiedSearchRepository {
     page_repo: SqlitePageRepository,
     history_repo: SqliteHistoryRepository,
     archive_repo: SqliteArchiveRepository,
+    note_repo: SqliteNoteRepository,
 }
 
 impl UnifiedSearchRepository {
@@ -1185,7 +3651,8 @@ impl UnifiedSearchRepository {
         Self {
             page_repo: SqlitePageRepository::new(Arc::clone(&connection)),
             history_repo: SqliteHistoryRepository::new(Arc::clone(&connection)),
-            archive_repo: SqliteArchiveRepository::new(connection),
+            archive_repo: SqliteArchiveRepository::new(Arc::clone(&connection)),
+            note_repo: SqliteNoteRepository::new(connection),
         }
     }
 
@@ -1194,6 +3661,7 @@ impl UnifiedSearchRepository {
         let pages = self.page_repo.search_with_limit(query, limit_per_source).await?;
         let history = self.history_repo.search(query, limit_per_source).await?;
         let archives = self.archive_repo.search(query, limit_per_source).await?;
+        let notes = self.note_repo.search(query, limit_per_source).await?;
 
         let mut results: Vec<UnifiedSearchResult> = Vec::new();
         
@@ -1208,6 +3676,10 @@ impl UnifiedSearchRepository {
         for archive in archives {
             results.push(UnifiedSearchResult::Archive(archive));
         }
+        
+        for note in notes {
+            results.push(UnifiedSearchResult::Note(note));
+        }
 
         Ok(results)
     }
@@ -0,0 +1,270 @@
+//! Storage retention rules
+//!
+//! Retention bounds how much the database keeps: history older than an age,
+//! archives beyond a total size (oldest dropped first), trash older than an
+//! age and old change log entries. Rules are enforced in one transaction,
+//! either on demand or on a schedule. A preview runs the same deletes and
+//! rolls them back, so it reports exactly what enforcing would remove.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{info, warn};
+use web_page_manager_core::*;
+
+use crate::DatabaseManager;
+
+/// What the database keeps; `None` keeps everything of that kind
+#[derive(Debug, Clone)]
+pub struct RetentionRules {
+    /// Age after which closed-tab history is deleted, trashed or not
+    pub history_max_age: Option<Duration>,
+    /// Total size of archives to keep; the oldest go first
+    pub archives_max_bytes: Option<u64>,
+    /// Time deleted pages and history stay in the trash
+    pub trash_max_age: Option<Duration>,
+    /// Age after which change log entries are pruned
+    pub change_log_max_age: Option<Duration>,
+    /// Time between scheduled enforcements
+    pub interval: Duration,
+}
+
+impl Default for RetentionRules {
+    /// Half a year of history, 1 GB of archives and 30 days of trash,
+    /// enforced daily; the change log is kept for sync
+    fn default() -> Self {
+        const DAY: u64 = 24 * 60 * 60;
+        Self {
+            history_max_age: Some(Duration::from_secs(180 * DAY)),
+            archives_max_bytes: Some(1024 * 1024 * 1024),
+            trash_max_age: Some(Duration::from_secs(30 * DAY)),
+            change_log_max_age: None,
+            interval: Duration::from_secs(DAY),
+        }
+    }
+}
+
+/// Records removed, or that would be removed, by enforcing retention
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionReport {
+    /// Nothing was deleted; the counts are what enforcing would remove
+    pub dry_run: bool,
+    pub history: usize,
+    pub archives: usize,
+    /// Size of the removed archives, as recorded when archived
+    pub archive_bytes: u64,
+    pub trashed_pages: usize,
+    pub trashed_history: usize,
+    pub change_log: usize,
+}
+
+impl RetentionReport {
+    /// Whether anything was, or would be, removed
+    pub fn is_empty(&self) -> bool {
+        self.history + self.archives + self.trashed_pages + self.trashed_history + self.change_log == 0
+    }
+}
+
+/// Unix time `age` before now, or `None` for no rule
+fn cutoff(age: Option<Duration>) -> Option<i64> {
+    let age = chrono::Duration::from_std(age?).unwrap_or(chrono::Duration::MAX);
+    Some(Utc::now().checked_sub_signed(age).unwrap_or(DateTime::<Utc>::MIN_UTC).timestamp())
+}
+
+impl DatabaseManager {
+    /// Delete what the rules do not keep, or with `dry_run` only report it
+    pub async fn enforce_retention(&self, rules: &RetentionRules, dry_run: bool) -> Result<RetentionReport> {
+        let history_before = cutoff(rules.history_max_age);
+        let trash_before = cutoff(rules.trash_max_age);
+        let change_log_before = cutoff(rules.change_log_max_age);
+        let archives_max_bytes = rules.archives_max_bytes.map(|b| b.min(i64::MAX as u64) as i64);
+
+        let report = self
+            .connection
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                let mut report = RetentionReport { dry_run, ..Default::default() };
+
+                if let Some(ts) = history_before {
+                    report.history = tx.execute("DELETE FROM tab_history WHERE closed_at < ?1", [ts])?;
+                }
+                if let Some(ts) = trash_before {
+                    report.trashed_history = tx.execute(
+                        "DELETE FROM tab_history WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+                        [ts],
+                    )?;
+                    report.trashed_pages = tx.execute(
+                        "DELETE FROM unified_pages WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+                        [ts],
+                    )?;
+                }
+                // Measured after the purge, whose pages take their archives along
+                if let Some(max_bytes) = archives_max_bytes {
+                    let over_budget = "SELECT id, file_size FROM (\
+                         SELECT id, file_size, SUM(COALESCE(file_size, 0)) \
+                         OVER (ORDER BY archived_at DESC, id DESC) AS kept FROM content_archives\
+                         ) WHERE kept > ?1";
+                    report.archive_bytes = tx.query_row(
+                        &format!("SELECT COALESCE(SUM(file_size), 0) FROM ({})", over_budget),
+                        [max_bytes],
+                        |row| row.get::<_, i64>(0),
+                    )? as u64;
+                    report.archives = tx.execute(
+                        &format!("DELETE FROM content_archives WHERE id IN (SELECT id FROM ({}))", over_budget),
+                        [max_bytes],
+                    )?;
+                }
+                if let Some(ts) = change_log_before {
+                    report.change_log = tx.execute("DELETE FROM change_log WHERE changed_at < ?1", [ts])?;
+                }
+
+                if dry_run {
+                    tx.rollback()?;
+                } else {
+                    tx.commit()?;
+                }
+                Ok(report)
+            })
+            .await
+            .map_err(|e| WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to enforce retention: {}", e),
+                },
+            })?;
+
+        if !dry_run && !report.is_empty() {
+            self.cache.clear_all().await;
+            if report.trashed_pages > 0 {
+                self.embeddings.invalidate().await;
+            }
+            info!(
+                "Retention removed {} history entries, {} archives ({} bytes), {} trashed pages, \
+                 {} trashed history entries and {} change log entries",
+                report.history,
+                report.archives,
+                report.archive_bytes,
+                report.trashed_pages,
+                report.trashed_history,
+                report.change_log
+            );
+        }
+        Ok(report)
+    }
+
+    /// Run `enforce_retention` in the background once per interval until
+    /// the returned task is aborted
+    pub fn spawn_retention(self: Arc<Self>, rules: RetentionRules) -> tokio::task::JoinHandle<()> {
+        let period = rules.interval.max(Duration::from_secs(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.enforce_retention(&rules, false).await {
+                    warn!("Retention enforcement failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArchiveRepository, ContentArchive, HistoryRepository, PageRepository};
+    use crate::test_support::test_page;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn test_archive(page: &UnifiedPageInfo, days_ago: i64) -> ContentArchive {
+        ContentArchive {
+            id: ArchiveId::new(),
+            page_id: page.id,
+            url: page.url.clone(),
+            title: page.title.clone(),
+            content_html: "<p>archived</p>".to_string(),
+            content_text: "archived".to_string(),
+            media_files: vec![],
+            archived_at: Utc::now() - chrono::Duration::days(days_ago),
+            file_size: 400,
+            checksum: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_preview_matches_enforcement() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let pages = db.page_repository();
+        let history = db.history_repository();
+        let archives = db.archive_repository();
+
+        let kept: Vec<UnifiedPageInfo> = (0..3)
+            .map(|n| test_page(&format!("https://example.com/{}", n), &format!("Retained page {}", n)))
+            .collect();
+        pages.save_batch(&kept).await.unwrap();
+        for (page, days_ago) in kept.iter().zip([200, 10, 1]) {
+            history
+                .save(&HistoryEntry {
+                    id: HistoryId::new(),
+                    page_info: page.clone(),
+                    browser_type: BrowserType::Chrome,
+                    tab_id: None,
+                    closed_at: Utc::now() - chrono::Duration::days(days_ago),
+                    session_info: None,
+                    recall_hint: None,
+                })
+                .await
+                .unwrap();
+            archives.save(&test_archive(page, days_ago)).await.unwrap();
+        }
+
+        // A page trashed long ago, with an archive of its own
+        let trashed = test_page("https://example.com/9", "Retained page 9");
+        pages.save(&trashed).await.unwrap();
+        archives.save(&test_archive(&trashed, 0)).await.unwrap();
+        pages.delete(&trashed.id).await.unwrap();
+        let long_ago = (Utc::now() - chrono::Duration::days(60)).timestamp();
+        db.connection()
+            .call(move |conn| Ok(conn.execute("UPDATE unified_pages SET deleted_at = ?1 WHERE deleted_at IS NOT NULL", [long_ago])?))
+            .await
+            .unwrap();
+
+        let rules = RetentionRules {
+            archives_max_bytes: Some(1000),
+            ..Default::default()
+        };
+        let preview = db.enforce_retention(&rules, true).await.unwrap();
+        assert_eq!(
+            preview,
+            RetentionReport {
+                dry_run: true,
+                history: 1,
+                // The trashed page's archive goes with it; of the other
+                // three only the two newest fit in 1000 bytes
+                archives: 1,
+                archive_bytes: 400,
+                trashed_pages: 1,
+                trashed_history: 0,
+                change_log: 0,
+            }
+        );
+        assert_eq!(history.count().await.unwrap(), 3);
+        assert_eq!(archives.get_total_size().await.unwrap(), 1600);
+
+        let report = db.enforce_retention(&rules, false).await.unwrap();
+        assert_eq!(report, RetentionReport { dry_run: false, ..preview });
+        assert_eq!(history.count().await.unwrap(), 2);
+        assert_eq!(archives.get_total_size().await.unwrap(), 800);
+        assert!(pages.get_trash().await.unwrap().is_empty());
+        assert!(db.enforce_retention(&rules, false).await.unwrap().is_empty());
+
+        // Pruning the change log only removes entries older than its age
+        let log_rules = RetentionRules {
+            history_max_age: None,
+            archives_max_bytes: None,
+            trash_max_age: None,
+            change_log_max_age: Some(Duration::from_secs(DAY)),
+            interval: Duration::from_secs(DAY),
+        };
+        assert_eq!(db.enforce_retention(&log_rules, true).await.unwrap().change_log, 0);
+    }
+}
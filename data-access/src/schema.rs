@@ -1,7 +1,7 @@
 //! Database schema definitions and migrations
 
 /// Current schema version
-pub const SCHEMA_VERSION: u32 = 1;
+pub const SCHEMA_VERSION: u32 = 25;
 
 /// SQL schema for the Web Page Manager database
 pub const SCHEMA_SQL: &str = r#"
@@ -180,11 +180,1449 @@ CREATE INDEX IF NOT EXISTS idx_content_archives_page_id ON content_archives(page
 CREATE INDEX IF NOT EXISTS idx_page_group_relations_group_id ON page_group_relations(group_id);
 "#;
 
+/// Cache of AI analysis results keyed by content hash
+pub const ANALYSIS_CACHE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS analysis_cache (
+    content_hash TEXT PRIMARY KEY,
+    analysis TEXT NOT NULL, -- JSON
+    created_at INTEGER NOT NULL,
+    last_used INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_analysis_cache_last_used ON analysis_cache(last_used);
+"#;
+
+/// Reverts `ANALYSIS_CACHE_SQL`
+pub const ANALYSIS_CACHE_DOWN_SQL: &str = r#"
+DROP TABLE IF EXISTS analysis_cache;
+"#;
+
+/// Recall hints on history entries, indexed for full-text search
+pub const HISTORY_RECALL_HINT_SQL: &str = r#"
+ALTER TABLE tab_history ADD COLUMN recall_hint TEXT;
+
+DROP TRIGGER IF EXISTS history_fts_insert;
+DROP TRIGGER IF EXISTS history_fts_delete;
+DROP TRIGGER IF EXISTS history_fts_update;
+DROP TABLE IF EXISTS history_fts;
+
+CREATE VIRTUAL TABLE history_fts USING fts5(
+    title,
+    url,
+    recall_hint,
+    content='tab_history',
+    content_rowid='rowid',
+    tokenize='porter unicode61'
+);
+INSERT INTO history_fts(history_fts) VALUES ('rebuild');
+
+CREATE TRIGGER history_fts_insert AFTER INSERT ON tab_history BEGIN
+    INSERT INTO history_fts(rowid, title, url, recall_hint) 
+    VALUES (new.rowid, new.title, new.url, new.recall_hint);
+END;
+
+CREATE TRIGGER history_fts_delete AFTER DELETE ON tab_history BEGIN
+    INSERT INTO history_fts(history_fts, rowid, title, url, recall_hint) 
+    VALUES ('delete', old.rowid, old.title, old.url, old.recall_hint);
+END;
+
+CREATE TRIGGER history_fts_update AFTER UPDATE ON tab_history BEGIN
+    INSERT INTO history_fts(history_fts, rowid, title, url, recall_hint) 
+    VALUES ('delete', old.rowid, old.title, old.url, old.recall_hint);
+    INSERT INTO history_fts(rowid, title, url, recall_hint) 
+    VALUES (new.rowid, new.title, new.url, new.recall_hint);
+END;
+"#;
+
+/// Reverts `HISTORY_RECALL_HINT_SQL`, dropping recall hints
+pub const HISTORY_RECALL_HINT_DOWN_SQL: &str = r#"
+DROP TRIGGER IF EXISTS history_fts_insert;
+DROP TRIGGER IF EXISTS history_fts_delete;
+DROP TRIGGER IF EXISTS history_fts_update;
+DROP TABLE IF EXISTS history_fts;
+
+ALTER TABLE tab_history DROP COLUMN recall_hint;
+
+CREATE VIRTUAL TABLE history_fts USING fts5(
+    title,
+    url,
+    content='tab_history',
+    content_rowid='rowid',
+    tokenize='porter unicode61'
+);
+INSERT INTO history_fts(history_fts) VALUES ('rebuild');
+
+CREATE TRIGGER history_fts_insert AFTER INSERT ON tab_history BEGIN
+    INSERT INTO history_fts(rowid, title, url) 
+    VALUES (new.rowid, new.title, new.url);
+END;
+
+CREATE TRIGGER history_fts_delete AFTER DELETE ON tab_history BEGIN
+    INSERT INTO history_fts(history_fts, rowid, title, url) 
+    VALUES ('delete', old.rowid, old.title, old.url);
+END;
+
+CREATE TRIGGER history_fts_update AFTER UPDATE ON tab_history BEGIN
+    INSERT INTO history_fts(history_fts, rowid, title, url) 
+    VALUES ('delete', old.rowid, old.title, old.url);
+    INSERT INTO history_fts(rowid, title, url) 
+    VALUES (new.rowid, new.title, new.url);
+END;
+"#;
+
+/// Trigram full-text indexes
+///
+/// `porter unicode61` keeps a run of Chinese or Japanese characters as one
+/// token, so words inside CJK titles cannot be found. The trigram tokenizer
+/// indexes every three-character sequence instead, matching substrings in any
+/// script. Rebuilds all three indexes from their content tables.
+pub const TRIGRAM_FTS_SQL: &str = r#"
+DROP TRIGGER IF EXISTS pages_fts_insert;
+DROP TRIGGER IF EXISTS pages_fts_delete;
+DROP TRIGGER IF EXISTS pages_fts_update;
+DROP TABLE IF EXISTS pages_fts;
+
+CREATE VIRTUAL TABLE pages_fts USING fts5(
+    title,
+    content_summary,
+    keywords,
+    url,
+    content='unified_pages',
+    content_rowid='rowid',
+    tokenize='trigram'
+);
+INSERT INTO pages_fts(pages_fts) VALUES ('rebuild');
+
+CREATE TRIGGER pages_fts_insert AFTER INSERT ON unified_pages BEGIN
+    INSERT INTO pages_fts(rowid, title, content_summary, keywords, url) 
+    VALUES (new.rowid, new.title, new.content_summary, new.keywords, new.url);
+END;
+
+CREATE TRIGGER pages_fts_delete AFTER DELETE ON unified_pages BEGIN
+    INSERT INTO pages_fts(pages_fts, rowid, title, content_summary, keywords, url) 
+    VALUES ('delete', old.rowid, old.title, old.content_summary, old.keywords, old.url);
+END;
+
+CREATE TRIGGER pages_fts_update AFTER UPDATE ON unified_pages BEGIN
+    INSERT INTO pages_fts(pages_fts, rowid, title, content_summary, keywords, url) 
+    VALUES ('delete', old.rowid, old.title, old.content_summary, old.keywords, old.url);
+    INSERT INTO pages_fts(rowid, title, content_summary, keywords, url) 
+    VALUES (new.rowid, new.title, new.content_summary, new.keywords, new.url);
+END;
+
+DROP TRIGGER IF EXISTS archives_fts_insert;
+DROP TRIGGER IF EXISTS archives_fts_delete;
+DROP TRIGGER IF EXISTS archives_fts_update;
+DROP TABLE IF EXISTS archives_fts;
+
+CREATE VIRTUAL TABLE archives_fts USING fts5(
+    title,
+    content_text,
+    url,
+    content='content_archives',
+    content_rowid='rowid',
+    tokenize='trigram'
+);
+INSERT INTO archives_fts(archives_fts) VALUES ('rebuild');
+
+CREATE TRIGGER archives_fts_insert AFTER INSERT ON content_archives BEGIN
+    INSERT INTO archives_fts(rowid, title, content_text, url) 
+    VALUES (new.rowid, new.title, new.content_text, new.url);
+END;
+
+CREATE TRIGGER archives_fts_delete AFTER DELETE ON content_archives BEGIN
+    INSERT INTO archives_fts(archives_fts, rowid, title, content_text, url) 
+    VALUES ('delete', old.rowid, old.title, old.content_text, old.url);
+END;
+
+CREATE TRIGGER archives_fts_update AFTER UPDATE ON content_archives BEGIN
+    INSERT INTO archives_fts(archives_fts, rowid, title, content_text, url) 
+    VALUES ('delete', old.rowid, old.title, old.content_text, old.url);
+    INSERT INTO archives_fts(rowid, title, content_text, url) 
+    VALUES (new.rowid, new.title, new.content_text, new.url);
+END;
+
+DROP TRIGGER IF EXISTS history_fts_insert;
+DROP TRIGGER IF EXISTS history_fts_delete;
+DROP TRIGGER IF EXISTS history_fts_update;
+DROP TABLE IF EXISTS history_fts;
+
+CREATE VIRTUAL TABLE history_fts USING fts5(
+    title,
+    url,
+    recall_hint,
+    content='tab_history',
+    content_rowid='rowid',
+    tokenize='trigram'
+);
+INSERT INTO history_fts(history_fts) VALUES ('rebuild');
+
+CREATE TRIGGER history_fts_insert AFTER INSERT ON tab_history BEGIN
+    INSERT INTO history_fts(rowid, title, url, recall_hint) 
+    VALUES (new.rowid, new.title, new.url, new.recall_hint);
+END;
+
+CREATE TRIGGER history_fts_delete AFTER DELETE ON tab_history BEGIN
+    INSERT INTO history_fts(history_fts, rowid, title, url, recall_hint) 
+    VALUES ('delete', old.rowid, old.title, old.url, old.recall_hint);
+END;
+
+CREATE TRIGGER history_fts_update AFTER UPDATE ON tab_history BEGIN
+    INSERT INTO history_fts(history_fts, rowid, title, url, recall_hint) 
+    VALUES ('delete', old.rowid, old.title, old.url, old.recall_hint);
+    INSERT INTO history_fts(rowid, title, url, recall_hint) 
+    VALUES (new.rowid, new.title, new.url, new.recall_hint);
+END;
+"#;
+
+/// Reverts `TRIGRAM_FTS_SQL`, rebuilding the indexes with `porter unicode61`
+pub const TRIGRAM_FTS_DOWN_SQL: &str = r#"
+DROP TRIGGER IF EXISTS pages_fts_insert;
+DROP TRIGGER IF EXISTS pages_fts_delete;
+DROP TRIGGER IF EXISTS pages_fts_update;
+DROP TABLE IF EXISTS pages_fts;
+
+CREATE VIRTUAL TABLE pages_fts USING fts5(
+    title,
+    content_summary,
+    keywords,
+    url,
+    content='unified_pages',
+    content_rowid='rowid',
+    tokenize='porter unicode61'
+);
+INSERT INTO pages_fts(pages_fts) VALUES ('rebuild');
+
+CREATE TRIGGER pages_fts_insert AFTER INSERT ON unified_pages BEGIN
+    INSERT INTO pages_fts(rowid, title, content_summary, keywords, url) 
+    VALUES (new.rowid, new.title, new.content_summary, new.keywords, new.url);
+END;
+
+CREATE TRIGGER pages_fts_delete AFTER DELETE ON unified_pages BEGIN
+    INSERT INTO pages_fts(pages_fts, rowid, title, content_summary, keywords, url) 
+    VALUES ('delete', old.rowid, old.title, old.content_summary, old.keywords, old.url);
+END;
+
+CREATE TRIGGER pages_fts_update AFTER UPDATE ON unified_pages BEGIN
+    INSERT INTO pages_fts(pages_fts, rowid, title, content_summary, keywords, url) 
+    VALUES ('delete', old.rowid, old.title, old.content_summary, old.keywords, old.url);
+    INSERT INTO pages_fts(rowid, title, content_summary, keywords, url) 
+    VALUES (new.rowid, new.title, new.content_summary, new.keywords, new.url);
+END;
+
+DROP TRIGGER IF EXISTS archives_fts_insert;
+DROP TRIGGER IF EXISTS archives_fts_delete;
+DROP TRIGGER IF EXISTS archives_fts_update;
+DROP TABLE IF EXISTS archives_fts;
+
+CREATE VIRTUAL TABLE archives_fts USING fts5(
+    title,
+    content_text,
+    url,
+    content='content_archives',
+    content_rowid='rowid',
+    tokenize='porter unicode61'
+);
+INSERT INTO archives_fts(archives_fts) VALUES ('rebuild');
+
+CREATE TRIGGER archives_fts_insert AFTER INSERT ON content_archives BEGIN
+    INSERT INTO archives_fts(rowid, title, content_text, url) 
+    VALUES (new.rowid, new.title, new.content_text, new.url);
+END;
+
+CREATE TRIGGER archives_fts_delete AFTER DELETE ON content_archives BEGIN
+    INSERT INTO archives_fts(archives_fts, rowid, title, content_text, url) 
+    VALUES ('delete', old.rowid, old.title, old.content_text, old.url);
+END;
+
+CREATE TRIGGER archives_fts_update AFTER UPDATE ON content_archives BEGIN
+    INSERT INTO archives_fts(archives_fts, rowid, title, content_text, url) 
+    VALUES ('delete', old.rowid, old.title, old.content_text, old.url);
+    INSERT INTO archives_fts(rowid, title, content_text, url) 
+    VALUES (new.rowid, new.title, new.content_text, new.url);
+END;
+
+DROP TRIGGER IF EXISTS history_fts_insert;
+DROP TRIGGER IF EXISTS history_fts_delete;
+DROP TRIGGER IF EXISTS history_fts_update;
+DROP TABLE IF EXISTS history_fts;
+
+CREATE VIRTUAL TABLE history_fts USING fts5(
+    title,
+    url,
+    recall_hint,
+    content='tab_history',
+    content_rowid='rowid',
+    tokenize='porter unicode61'
+);
+INSERT INTO history_fts(history_fts) VALUES ('rebuild');
+
+CREATE TRIGGER history_fts_insert AFTER INSERT ON tab_history BEGIN
+    INSERT INTO history_fts(rowid, title, url, recall_hint) 
+    VALUES (new.rowid, new.title, new.url, new.recall_hint);
+END;
+
+CREATE TRIGGER history_fts_delete AFTER DELETE ON tab_history BEGIN
+    INSERT INTO history_fts(history_fts, rowid, title, url, recall_hint) 
+    VALUES ('delete', old.rowid, old.title, old.url, old.recall_hint);
+END;
+
+CREATE TRIGGER history_fts_update AFTER UPDATE ON tab_history BEGIN
+    INSERT INTO history_fts(history_fts, rowid, title, url, recall_hint) 
+    VALUES ('delete', old.rowid, old.title, old.url, old.recall_hint);
+    INSERT INTO history_fts(rowid, title, url, recall_hint) 
+    VALUES (new.rowid, new.title, new.url, new.recall_hint);
+END;
+"#;
+
+/// Page embeddings with their nearest-neighbor graph links
+///
+/// Vectors are little-endian `f32` blobs. `neighbors` holds the page ids
+/// linked on each graph layer as a JSON array of arrays, so the index is
+/// persisted with the data and reloaded without recomputing it.
+pub const PAGE_EMBEDDINGS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS page_embeddings (
+    page_id TEXT PRIMARY KEY,
+    dimensions INTEGER NOT NULL,
+    vector BLOB NOT NULL,
+    neighbors TEXT NOT NULL, -- JSON
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (page_id) REFERENCES unified_pages(id) ON DELETE CASCADE
+);
+"#;
+
+/// Reverts `PAGE_EMBEDDINGS_SQL`, dropping embeddings
+pub const PAGE_EMBEDDINGS_DOWN_SQL: &str = r#"
+DROP TABLE IF EXISTS page_embeddings;
+"#;
+
+/// User tags and their pages
+///
+/// Tag names are unique regardless of case; the spelling of the first use
+/// is kept.
+pub const TAGS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS tags (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS page_tags (
+    page_id TEXT NOT NULL,
+    tag_id TEXT NOT NULL,
+    added_at INTEGER NOT NULL,
+    PRIMARY KEY (page_id, tag_id),
+    FOREIGN KEY (page_id) REFERENCES unified_pages(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_page_tags_tag ON page_tags(tag_id);
+"#;
+
+/// Reverts `TAGS_SQL`, dropping tags
+pub const TAGS_DOWN_SQL: &str = r#"
+DROP TABLE IF EXISTS page_tags;
+DROP TABLE IF EXISTS tags;
+"#;
+
+/// Notes and highlighted quotes attached to pages, with a trigram
+/// full-text index like the other searchable tables
+pub const PAGE_NOTES_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS page_notes (
+    id TEXT PRIMARY KEY,
+    page_id TEXT NOT NULL,
+    content TEXT NOT NULL,
+    quote TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (page_id) REFERENCES unified_pages(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_page_notes_page ON page_notes(page_id, created_at);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+    content,
+    quote,
+    content='page_notes',
+    content_rowid='rowid',
+    tokenize='trigram'
+);
+
+CREATE TRIGGER IF NOT EXISTS notes_fts_insert AFTER INSERT ON page_notes BEGIN
+    INSERT INTO notes_fts(rowid, content, quote)
+    VALUES (new.rowid, new.content, new.quote);
+END;
+
+CREATE TRIGGER IF NOT EXISTS notes_fts_delete AFTER DELETE ON page_notes BEGIN
+    INSERT INTO notes_fts(notes_fts, rowid, content, quote)
+    VALUES ('delete', old.rowid, old.content, old.quote);
+END;
+
+CREATE TRIGGER IF NOT EXISTS notes_fts_update AFTER UPDATE ON page_notes BEGIN
+    INSERT INTO notes_fts(notes_fts, rowid, content, quote)
+    VALUES ('delete', old.rowid, old.content, old.quote);
+    INSERT INTO notes_fts(rowid, content, quote)
+    VALUES (new.rowid, new.content, new.quote);
+END;
+"#;
+
+/// Reverts `PAGE_NOTES_SQL`, dropping notes
+pub const PAGE_NOTES_DOWN_SQL: &str = r#"
+DROP TRIGGER IF EXISTS notes_fts_insert;
+DROP TRIGGER IF EXISTS notes_fts_delete;
+DROP TRIGGER IF EXISTS notes_fts_update;
+DROP TABLE IF EXISTS notes_fts;
+DROP TABLE IF EXISTS page_notes;
+"#;
+
+/// Saved tab sessions: each session has ordered windows, each window
+/// ordered tabs
+pub const SESSIONS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS sessions (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS session_windows (
+    session_id TEXT NOT NULL,
+    window_index INTEGER NOT NULL,
+    browser_type TEXT NOT NULL,
+    PRIMARY KEY (session_id, window_index),
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS session_tabs (
+    session_id TEXT NOT NULL,
+    window_index INTEGER NOT NULL,
+    tab_index INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    favicon_url TEXT,
+    pinned INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (session_id, window_index, tab_index),
+    FOREIGN KEY (session_id, window_index) REFERENCES session_windows(session_id, window_index) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_updated ON sessions(updated_at);
+"#;
+
+/// Reverts `SESSIONS_SQL`, dropping saved sessions
+pub const SESSIONS_DOWN_SQL: &str = r#"
+DROP TABLE IF EXISTS session_tabs;
+DROP TABLE IF EXISTS session_windows;
+DROP TABLE IF EXISTS sessions;
+"#;
+
+/// Compressed archive HTML, stored once per distinct content
+///
+/// Archives saved before this migration keep their HTML inline in
+/// `content_html` until compacted; compressed ones have an empty
+/// `content_html` and an `html_hash`. A blob is dropped with the last
+/// archive referring to it.
+pub const ARCHIVE_BLOBS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS archive_blobs (
+    hash TEXT PRIMARY KEY,
+    data BLOB NOT NULL,
+    original_size INTEGER NOT NULL
+);
+
+ALTER TABLE content_archives ADD COLUMN html_hash TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_content_archives_html_hash ON content_archives(html_hash);
+
+CREATE TRIGGER IF NOT EXISTS archive_blobs_release_delete AFTER DELETE ON content_archives
+WHEN old.html_hash IS NOT NULL BEGIN
+    DELETE FROM archive_blobs WHERE hash = old.html_hash
+        AND NOT EXISTS (SELECT 1 FROM content_archives WHERE html_hash = old.html_hash);
+END;
+
+CREATE TRIGGER IF NOT EXISTS archive_blobs_release_update AFTER UPDATE OF html_hash ON content_archives
+WHEN old.html_hash IS NOT NULL AND old.html_hash IS NOT new.html_hash BEGIN
+    DELETE FROM archive_blobs WHERE hash = old.html_hash
+        AND NOT EXISTS (SELECT 1 FROM content_archives WHERE html_hash = old.html_hash);
+END;
+"#;
+
+/// Trash for pages and history: deleted rows keep their data and a
+/// `deleted_at` time until restored or purged
+pub const TRASH_SQL: &str = r#"
+ALTER TABLE unified_pages ADD COLUMN deleted_at INTEGER;
+ALTER TABLE tab_history ADD COLUMN deleted_at INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_unified_pages_deleted_at ON unified_pages(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_tab_history_deleted_at ON tab_history(deleted_at);
+"#;
+
+/// Reverts `TRASH_SQL`; trashed rows become live again
+pub const TRASH_DOWN_SQL: &str = r#"
+DROP INDEX IF EXISTS idx_unified_pages_deleted_at;
+DROP INDEX IF EXISTS idx_tab_history_deleted_at;
+
+ALTER TABLE unified_pages DROP COLUMN deleted_at;
+ALTER TABLE tab_history DROP COLUMN deleted_at;
+"#;
+
+/// Change log for sync and undo
+///
+/// Triggers record every insert, update and delete of the user's data with
+/// the time and a hash of the new row (`NULL` for deletes), in the order the
+/// changes were made. Moving to and from the trash is an update. Derived
+/// tables such as indexes, embeddings and the analysis cache are not logged.
+/// `change_hash` is registered on the writing connection by
+/// `changelog::register_functions`; `CHANGE_LOG_PAYLOADS_SQL` later moves
+/// the hashing out of the triggers.
+pub const CHANGE_LOG_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS change_log (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    op TEXT NOT NULL, -- insert, update or delete
+    changed_at INTEGER NOT NULL,
+    payload_hash TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_change_log_entity ON change_log(entity, entity_id);
+CREATE INDEX IF NOT EXISTS idx_change_log_changed_at ON change_log(changed_at);
+
+CREATE TRIGGER IF NOT EXISTS change_log_unified_pages_insert AFTER INSERT ON unified_pages BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.url, new.title, new.favicon_url, new.content_summary, new.keywords, new.category, new.source_type, new.browser_info, new.tab_info, new.bookmark_info, new.created_at, new.last_accessed, new.access_count, new.deleted_at)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_unified_pages_update AFTER UPDATE ON unified_pages BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.url, new.title, new.favicon_url, new.content_summary, new.keywords, new.category, new.source_type, new.browser_info, new.tab_info, new.bookmark_info, new.created_at, new.last_accessed, new.access_count, new.deleted_at)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_unified_pages_delete AFTER DELETE ON unified_pages BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page', old.id, 'delete', CAST(strftime('%s', 'now') AS INTEGER),
+            NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_smart_groups_insert AFTER INSERT ON smart_groups BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('group', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.name, new.description, new.group_type, new.created_at, new.auto_generated, new.similarity_threshold)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_smart_groups_update AFTER UPDATE ON smart_groups BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('group', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.name, new.description, new.group_type, new.created_at, new.auto_generated, new.similarity_threshold)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_smart_groups_delete AFTER DELETE ON smart_groups BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('group', old.id, 'delete', CAST(strftime('%s', 'now') AS INTEGER),
+            NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_page_group_relations_insert AFTER INSERT ON page_group_relations BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page_group', new.page_id || ':' || new.group_id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.page_id, new.group_id, new.added_at, new.confidence_score)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_page_group_relations_update AFTER UPDATE ON page_group_relations BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page_group', new.page_id || ':' || new.group_id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.page_id, new.group_id, new.added_at, new.confidence_score)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_page_group_relations_delete AFTER DELETE ON page_group_relations BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page_group', old.page_id || ':' || old.group_id, 'delete', CAST(strftime('%s', 'now') AS INTEGER),
+            NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_tab_history_insert AFTER INSERT ON tab_history BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('history', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.page_id, new.url, new.title, new.favicon_url, new.browser_type, new.tab_id, new.closed_at, new.session_info, new.content_summary, new.recall_hint, new.deleted_at)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_tab_history_update AFTER UPDATE ON tab_history BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('history', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.page_id, new.url, new.title, new.favicon_url, new.browser_type, new.tab_id, new.closed_at, new.session_info, new.content_summary, new.recall_hint, new.deleted_at)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_tab_history_delete AFTER DELETE ON tab_history BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('history', old.id, 'delete', CAST(strftime('%s', 'now') AS INTEGER),
+            NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_content_archives_insert AFTER INSERT ON content_archives BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('archive', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.page_id, new.url, new.title, new.content_html, new.content_text, new.media_files, new.archived_at, new.file_size, new.checksum, new.html_hash)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_content_archives_update AFTER UPDATE ON content_archives BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('archive', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.page_id, new.url, new.title, new.content_html, new.content_text, new.media_files, new.archived_at, new.file_size, new.checksum, new.html_hash)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_content_archives_delete AFTER DELETE ON content_archives BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('archive', old.id, 'delete', CAST(strftime('%s', 'now') AS INTEGER),
+            NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_tags_insert AFTER INSERT ON tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('tag', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.name, new.created_at)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_tags_update AFTER UPDATE ON tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('tag', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.name, new.created_at)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_tags_delete AFTER DELETE ON tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('tag', old.id, 'delete', CAST(strftime('%s', 'now') AS INTEGER),
+            NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_page_tags_insert AFTER INSERT ON page_tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page_tag', new.page_id || ':' || new.tag_id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.page_id, new.tag_id, new.added_at)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_page_tags_update AFTER UPDATE ON page_tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page_tag', new.page_id || ':' || new.tag_id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.page_id, new.tag_id, new.added_at)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_page_tags_delete AFTER DELETE ON page_tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page_tag', old.page_id || ':' || old.tag_id, 'delete', CAST(strftime('%s', 'now') AS INTEGER),
+            NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_page_notes_insert AFTER INSERT ON page_notes BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('note', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.page_id, new.content, new.quote, new.created_at, new.updated_at)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_page_notes_update AFTER UPDATE ON page_notes BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('note', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.page_id, new.content, new.quote, new.created_at, new.updated_at)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_page_notes_delete AFTER DELETE ON page_notes BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('note', old.id, 'delete', CAST(strftime('%s', 'now') AS INTEGER),
+            NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_sessions_insert AFTER INSERT ON sessions BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('session', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.name, new.created_at, new.updated_at)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_sessions_update AFTER UPDATE ON sessions BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('session', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.name, new.created_at, new.updated_at)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_sessions_delete AFTER DELETE ON sessions BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('session', old.id, 'delete', CAST(strftime('%s', 'now') AS INTEGER),
+            NULL);
+END;
+"#;
+
+/// Reverts `CHANGE_LOG_SQL`
+pub const CHANGE_LOG_DOWN_SQL: &str = r#"
+DROP TRIGGER IF EXISTS change_log_unified_pages_insert;
+DROP TRIGGER IF EXISTS change_log_unified_pages_update;
+DROP TRIGGER IF EXISTS change_log_unified_pages_delete;
+DROP TRIGGER IF EXISTS change_log_smart_groups_insert;
+DROP TRIGGER IF EXISTS change_log_smart_groups_update;
+DROP TRIGGER IF EXISTS change_log_smart_groups_delete;
+DROP TRIGGER IF EXISTS change_log_page_group_relations_insert;
+DROP TRIGGER IF EXISTS change_log_page_group_relations_update;
+DROP TRIGGER IF EXISTS change_log_page_group_relations_delete;
+DROP TRIGGER IF EXISTS change_log_tab_history_insert;
+DROP TRIGGER IF EXISTS change_log_tab_history_update;
+DROP TRIGGER IF EXISTS change_log_tab_history_delete;
+DROP TRIGGER IF EXISTS change_log_content_archives_insert;
+DROP TRIGGER IF EXISTS change_log_content_archives_update;
+DROP TRIGGER IF EXISTS change_log_content_archives_delete;
+DROP TRIGGER IF EXISTS change_log_tags_insert;
+DROP TRIGGER IF EXISTS change_log_tags_update;
+DROP TRIGGER IF EXISTS change_log_tags_delete;
+DROP TRIGGER IF EXISTS change_log_page_tags_insert;
+DROP TRIGGER IF EXISTS change_log_page_tags_update;
+DROP TRIGGER IF EXISTS change_log_page_tags_delete;
+DROP TRIGGER IF EXISTS change_log_page_notes_insert;
+DROP TRIGGER IF EXISTS change_log_page_notes_update;
+DROP TRIGGER IF EXISTS change_log_page_notes_delete;
+DROP TRIGGER IF EXISTS change_log_sessions_insert;
+DROP TRIGGER IF EXISTS change_log_sessions_update;
+DROP TRIGGER IF EXISTS change_log_sessions_delete;
+DROP TABLE IF EXISTS change_log;
+"#;
+
+/// Usage analytics
+///
+/// Raw events record page accesses, searches and the lifetime of each tab.
+/// Triggers keep per-day rollups of the events, so dashboards read one row
+/// per day (and domain) instead of scanning events or history. Days are UTC
+/// dates. Pruning raw events leaves the rollups in place.
+pub const ANALYTICS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS page_access_events (
+    id INTEGER PRIMARY KEY,
+    page_id TEXT,
+    url TEXT NOT NULL,
+    domain TEXT NOT NULL,
+    accessed_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_page_access_events_time ON page_access_events(accessed_at);
+
+CREATE TABLE IF NOT EXISTS search_queries (
+    id INTEGER PRIMARY KEY,
+    query TEXT NOT NULL,
+    result_count INTEGER NOT NULL,
+    searched_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_queries_time ON search_queries(searched_at);
+
+CREATE TABLE IF NOT EXISTS tab_lifetimes (
+    tab_id TEXT PRIMARY KEY,
+    browser_type TEXT NOT NULL,
+    domain TEXT NOT NULL,
+    opened_at INTEGER NOT NULL,
+    closed_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_tab_lifetimes_closed ON tab_lifetimes(closed_at);
+
+CREATE TABLE IF NOT EXISTS usage_daily (
+    day TEXT PRIMARY KEY, -- YYYY-MM-DD
+    page_accesses INTEGER NOT NULL DEFAULT 0,
+    searches INTEGER NOT NULL DEFAULT 0,
+    tabs_opened INTEGER NOT NULL DEFAULT 0,
+    tabs_closed INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS domain_usage_daily (
+    day TEXT NOT NULL,
+    domain TEXT NOT NULL,
+    accesses INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, domain)
+);
+
+CREATE TRIGGER IF NOT EXISTS usage_page_access AFTER INSERT ON page_access_events BEGIN
+    INSERT INTO usage_daily (day, page_accesses) VALUES (date(new.accessed_at, 'unixepoch'), 1)
+        ON CONFLICT(day) DO UPDATE SET page_accesses = page_accesses + 1;
+    INSERT INTO domain_usage_daily (day, domain, accesses) VALUES (date(new.accessed_at, 'unixepoch'), new.domain, 1)
+        ON CONFLICT(day, domain) DO UPDATE SET accesses = accesses + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS usage_search AFTER INSERT ON search_queries BEGIN
+    INSERT INTO usage_daily (day, searches) VALUES (date(new.searched_at, 'unixepoch'), 1)
+        ON CONFLICT(day) DO UPDATE SET searches = searches + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS usage_tab_opened AFTER INSERT ON tab_lifetimes BEGIN
+    INSERT INTO usage_daily (day, tabs_opened) VALUES (date(new.opened_at, 'unixepoch'), 1)
+        ON CONFLICT(day) DO UPDATE SET tabs_opened = tabs_opened + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS usage_tab_closed_on_insert AFTER INSERT ON tab_lifetimes
+WHEN new.closed_at IS NOT NULL BEGIN
+    INSERT INTO usage_daily (day, tabs_closed) VALUES (date(new.closed_at, 'unixepoch'), 1)
+        ON CONFLICT(day) DO UPDATE SET tabs_closed = tabs_closed + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS usage_tab_closed AFTER UPDATE OF closed_at ON tab_lifetimes
+WHEN old.closed_at IS NULL AND new.closed_at IS NOT NULL BEGIN
+    INSERT INTO usage_daily (day, tabs_closed) VALUES (date(new.closed_at, 'unixepoch'), 1)
+        ON CONFLICT(day) DO UPDATE SET tabs_closed = tabs_closed + 1;
+END;
+"#;
+
+/// Reverts `ANALYTICS_SQL`, dropping all usage data
+pub const ANALYTICS_DOWN_SQL: &str = r#"
+DROP TABLE IF EXISTS page_access_events;
+DROP TABLE IF EXISTS search_queries;
+DROP TABLE IF EXISTS tab_lifetimes;
+DROP TABLE IF EXISTS usage_daily;
+DROP TABLE IF EXISTS domain_usage_daily;
+"#;
+
+/// Canonical page URLs
+///
+/// Pages become unique by `canonical_url` (see `canonical::canonical_url`,
+/// registered as an SQL function on the writing connection). Existing
+/// duplicates are merged into one page per canonical URL: a live page
+/// before a trashed one, then the most recently accessed. The other pages'
+/// groups, tags, notes, archives, history and analytics move to it before
+/// they are deleted; their embeddings are dropped.
+pub const CANONICAL_URLS_SQL: &str = r#"
+ALTER TABLE unified_pages ADD COLUMN canonical_url TEXT;
+
+UPDATE unified_pages SET canonical_url = canonical_url(url);
+
+CREATE TEMP TABLE page_merges AS
+SELECT p.id AS duplicate,
+       (SELECT s.id FROM unified_pages s WHERE s.canonical_url = p.canonical_url
+        ORDER BY s.deleted_at IS NOT NULL, s.last_accessed DESC, s.rowid LIMIT 1) AS survivor
+FROM unified_pages p;
+
+DELETE FROM page_merges WHERE duplicate = survivor;
+
+UPDATE unified_pages SET
+    access_count = access_count + (SELECT COALESCE(SUM(d.access_count), 0) FROM unified_pages d
+        JOIN page_merges m ON m.duplicate = d.id WHERE m.survivor = unified_pages.id),
+    created_at = MIN(created_at, (SELECT COALESCE(MIN(d.created_at), created_at) FROM unified_pages d
+        JOIN page_merges m ON m.duplicate = d.id WHERE m.survivor = unified_pages.id))
+WHERE id IN (SELECT survivor FROM page_merges);
+
+UPDATE OR IGNORE page_group_relations SET page_id = (SELECT survivor FROM page_merges WHERE duplicate = page_id)
+WHERE page_id IN (SELECT duplicate FROM page_merges);
+UPDATE OR IGNORE page_tags SET page_id = (SELECT survivor FROM page_merges WHERE duplicate = page_id)
+WHERE page_id IN (SELECT duplicate FROM page_merges);
+UPDATE page_notes SET page_id = (SELECT survivor FROM page_merges WHERE duplicate = page_id)
+WHERE page_id IN (SELECT duplicate FROM page_merges);
+UPDATE content_archives SET page_id = (SELECT survivor FROM page_merges WHERE duplicate = page_id)
+WHERE page_id IN (SELECT duplicate FROM page_merges);
+UPDATE tab_history SET page_id = (SELECT survivor FROM page_merges WHERE duplicate = page_id)
+WHERE page_id IN (SELECT duplicate FROM page_merges);
+UPDATE page_access_events SET page_id = (SELECT survivor FROM page_merges WHERE duplicate = page_id)
+WHERE page_id IN (SELECT duplicate FROM page_merges);
+
+DELETE FROM unified_pages WHERE id IN (SELECT duplicate FROM page_merges);
+DROP TABLE page_merges;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_unified_pages_canonical_url ON unified_pages(canonical_url);
+"#;
+
+/// Reverts `CANONICAL_URLS_SQL`; merged pages stay merged
+pub const CANONICAL_URLS_DOWN_SQL: &str = r#"
+DROP INDEX IF EXISTS idx_unified_pages_canonical_url;
+
+ALTER TABLE unified_pages DROP COLUMN canonical_url;
+"#;
+
+/// Favicons and thumbnails
+///
+/// Images are stored once in `media_assets`, keyed by the SHA-1 of their
+/// bytes, and pages and history entries refer to them by hash. An asset is
+/// deleted with the last page or history entry referring to it.
+pub const MEDIA_ASSETS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS media_assets (
+    hash TEXT PRIMARY KEY,
+    mime_type TEXT NOT NULL,
+    data BLOB NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+ALTER TABLE unified_pages ADD COLUMN favicon_hash TEXT;
+ALTER TABLE unified_pages ADD COLUMN thumbnail_hash TEXT;
+ALTER TABLE tab_history ADD COLUMN favicon_hash TEXT;
+ALTER TABLE tab_history ADD COLUMN thumbnail_hash TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_unified_pages_favicon_hash ON unified_pages(favicon_hash);
+CREATE INDEX IF NOT EXISTS idx_unified_pages_thumbnail_hash ON unified_pages(thumbnail_hash);
+CREATE INDEX IF NOT EXISTS idx_tab_history_favicon_hash ON tab_history(favicon_hash);
+CREATE INDEX IF NOT EXISTS idx_tab_history_thumbnail_hash ON tab_history(thumbnail_hash);
+
+CREATE TRIGGER IF NOT EXISTS media_assets_release_page_delete AFTER DELETE ON unified_pages
+WHEN old.favicon_hash IS NOT NULL OR old.thumbnail_hash IS NOT NULL BEGIN
+    DELETE FROM media_assets WHERE hash IN (old.favicon_hash, old.thumbnail_hash)
+        AND NOT EXISTS (SELECT 1 FROM unified_pages WHERE favicon_hash = hash OR thumbnail_hash = hash)
+        AND NOT EXISTS (SELECT 1 FROM tab_history WHERE favicon_hash = hash OR thumbnail_hash = hash);
+END;
+
+CREATE TRIGGER IF NOT EXISTS media_assets_release_page_update AFTER UPDATE OF favicon_hash, thumbnail_hash ON unified_pages
+WHEN old.favicon_hash IS NOT NULL OR old.thumbnail_hash IS NOT NULL BEGIN
+    DELETE FROM media_assets WHERE hash IN (old.favicon_hash, old.thumbnail_hash)
+        AND NOT EXISTS (SELECT 1 FROM unified_pages WHERE favicon_hash = hash OR thumbnail_hash = hash)
+        AND NOT EXISTS (SELECT 1 FROM tab_history WHERE favicon_hash = hash OR thumbnail_hash = hash);
+END;
+
+CREATE TRIGGER IF NOT EXISTS media_assets_release_history_delete AFTER DELETE ON tab_history
+WHEN old.favicon_hash IS NOT NULL OR old.thumbnail_hash IS NOT NULL BEGIN
+    DELETE FROM media_assets WHERE hash IN (old.favicon_hash, old.thumbnail_hash)
+        AND NOT EXISTS (SELECT 1 FROM unified_pages WHERE favicon_hash = hash OR thumbnail_hash = hash)
+        AND NOT EXISTS (SELECT 1 FROM tab_history WHERE favicon_hash = hash OR thumbnail_hash = hash);
+END;
+
+CREATE TRIGGER IF NOT EXISTS media_assets_release_history_update AFTER UPDATE OF favicon_hash, thumbnail_hash ON tab_history
+WHEN old.favicon_hash IS NOT NULL OR old.thumbnail_hash IS NOT NULL BEGIN
+    DELETE FROM media_assets WHERE hash IN (old.favicon_hash, old.thumbnail_hash)
+        AND NOT EXISTS (SELECT 1 FROM unified_pages WHERE favicon_hash = hash OR thumbnail_hash = hash)
+        AND NOT EXISTS (SELECT 1 FROM tab_history WHERE favicon_hash = hash OR thumbnail_hash = hash);
+END;
+"#;
+
+/// Reverts `MEDIA_ASSETS_SQL`, dropping stored images
+pub const MEDIA_ASSETS_DOWN_SQL: &str = r#"
+DROP TRIGGER IF EXISTS media_assets_release_page_delete;
+DROP TRIGGER IF EXISTS media_assets_release_page_update;
+DROP TRIGGER IF EXISTS media_assets_release_history_delete;
+DROP TRIGGER IF EXISTS media_assets_release_history_update;
+
+DROP INDEX IF EXISTS idx_unified_pages_favicon_hash;
+DROP INDEX IF EXISTS idx_unified_pages_thumbnail_hash;
+DROP INDEX IF EXISTS idx_tab_history_favicon_hash;
+DROP INDEX IF EXISTS idx_tab_history_thumbnail_hash;
+
+ALTER TABLE unified_pages DROP COLUMN favicon_hash;
+ALTER TABLE unified_pages DROP COLUMN thumbnail_hash;
+ALTER TABLE tab_history DROP COLUMN favicon_hash;
+ALTER TABLE tab_history DROP COLUMN thumbnail_hash;
+
+DROP TABLE IF EXISTS media_assets;
+"#;
+
+/// Search history and saved searches
+///
+/// `search_history` keeps one row per query, matched ignoring case and
+/// surrounding spaces, for recent searches and autocompletion. Saved
+/// searches store their filter as JSON defined by the search module.
+pub const SEARCHES_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS search_history (
+    query_key TEXT PRIMARY KEY, -- lowercased, trimmed query
+    query TEXT NOT NULL,
+    result_count INTEGER NOT NULL,
+    search_count INTEGER NOT NULL DEFAULT 1,
+    last_searched_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_history_last_searched ON search_history(last_searched_at);
+
+CREATE TABLE IF NOT EXISTS saved_searches (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    query TEXT NOT NULL,
+    filter TEXT NOT NULL, -- JSON
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    last_used_at INTEGER
+);
+"#;
+
+/// Reverts `SEARCHES_SQL`, dropping search history and saved searches
+pub const SEARCHES_DOWN_SQL: &str = r#"
+DROP TABLE IF EXISTS search_history;
+DROP TABLE IF EXISTS saved_searches;
+"#;
+
+/// Snapshots of archived pages stored as files
+///
+/// MHTML captures, screenshots and PDFs live in a content-addressed store
+/// on disk; each row refers to its file by the SHA-1 of the contents.
+/// Rows go with their archive, files are removed by garbage collection
+/// once no row refers to them.
+pub const SNAPSHOTS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS archive_snapshots (
+    id TEXT PRIMARY KEY,
+    archive_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (archive_id) REFERENCES content_archives(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_archive_snapshots_archive_id ON archive_snapshots(archive_id);
+CREATE INDEX IF NOT EXISTS idx_archive_snapshots_hash ON archive_snapshots(hash);
+"#;
+
+/// Reverts `SNAPSHOTS_SQL`; the files are left for the caller to remove
+pub const SNAPSHOTS_DOWN_SQL: &str = r#"
+DROP TABLE IF EXISTS archive_snapshots;
+"#;
+
+/// Tabs closed until a wake time, when they reopen in their browser
+pub const SNOOZED_TABS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS snoozed_tabs (
+    id TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    favicon_url TEXT,
+    browser_type TEXT NOT NULL,
+    snoozed_at INTEGER NOT NULL,
+    wake_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_snoozed_tabs_wake_at ON snoozed_tabs(wake_at);
+"#;
+
+/// Reverts `SNOOZED_TABS_SQL`, dropping snoozed tabs
+pub const SNOOZED_TABS_DOWN_SQL: &str = r#"
+DROP TABLE IF EXISTS snoozed_tabs;
+"#;
+
+/// Parent tags, nesting tags into a hierarchy
+///
+/// Kept without a foreign key so the column can be dropped again; the tag
+/// repository reparents the children of a deleted or merged tag itself.
+pub const TAG_HIERARCHY_SQL: &str = r#"
+ALTER TABLE tags ADD COLUMN parent_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_tags_parent ON tags(parent_id);
+"#;
+
+/// Reverts `TAG_HIERARCHY_SQL`, flattening tags
+pub const TAG_HIERARCHY_DOWN_SQL: &str = r#"
+DROP INDEX IF EXISTS idx_tags_parent;
+ALTER TABLE tags DROP COLUMN parent_id;
+"#;
+
+/// User pins and exclusions of pages in smart groups
+///
+/// Saving a group replaces its row, so overrides are not tied to the group
+/// by a foreign key; they are cleared when the generator drops the group.
+pub const GROUP_OVERRIDES_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS group_overrides (
+    group_id TEXT NOT NULL,
+    page_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (group_id, page_id),
+    FOREIGN KEY (page_id) REFERENCES unified_pages(id) ON DELETE CASCADE
+);
+"#;
+
+/// Reverts `GROUP_OVERRIDES_SQL`, dropping overrides
+pub const GROUP_OVERRIDES_DOWN_SQL: &str = r#"
+DROP TABLE IF EXISTS group_overrides;
+"#;
+
+/// Archived smart groups and the manual order of pages within groups
+///
+/// Like overrides, neither table references `smart_groups`, since saving a
+/// group replaces its row; the state is cleared when a group is deleted.
+pub const GROUP_STATE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS archived_groups (
+    group_id TEXT PRIMARY KEY,
+    archived_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS group_page_order (
+    group_id TEXT NOT NULL,
+    page_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (group_id, page_id),
+    FOREIGN KEY (page_id) REFERENCES unified_pages(id) ON DELETE CASCADE
+);
+"#;
+
+/// Reverts `GROUP_STATE_SQL`, dropping archive state and page order
+pub const GROUP_STATE_DOWN_SQL: &str = r#"
+DROP TABLE IF EXISTS group_page_order;
+DROP TABLE IF EXISTS archived_groups;
+"#;
+
+/// Reader versions of archived pages, one per archive
+pub const READER_VIEWS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS reader_views (
+    archive_id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    byline TEXT,
+    content_html TEXT NOT NULL,
+    content_text TEXT NOT NULL,
+    images TEXT NOT NULL DEFAULT '[]',
+    word_count INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (archive_id) REFERENCES content_archives(id) ON DELETE CASCADE
+);
+"#;
+
+/// Reverts `READER_VIEWS_SQL`, dropping reader views
+pub const READER_VIEWS_DOWN_SQL: &str = r#"
+DROP TABLE IF EXISTS reader_views;
+"#;
+
+/// Log of remote tab operations and migrations, kept for undo
+pub const OPERATION_LOG_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS operation_log (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    executed_at INTEGER NOT NULL,
+    record TEXT NOT NULL -- JSON
+);
+CREATE INDEX IF NOT EXISTS idx_operation_log_kind ON operation_log(kind, executed_at);
+"#;
+
+/// Reverts `OPERATION_LOG_SQL`, dropping the operation log
+pub const OPERATION_LOG_DOWN_SQL: &str = r#"
+DROP TABLE IF EXISTS operation_log;
+"#;
+
+/// Word full-text index for pages
+///
+/// Matching every term through trigrams costs about twice as much as a word
+/// index on the common case of Latin-script queries, and pages are the most
+/// searched table. `pages_word_fts` indexes words with `porter unicode61`
+/// for those queries; `pages_fts` keeps the trigrams for CJK ones. The
+/// index is only touched when an indexed column changes.
+pub const PAGES_WORD_FTS_SQL: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS pages_word_fts USING fts5(
+    title,
+    content_summary,
+    keywords,
+    url,
+    content='unified_pages',
+    content_rowid='rowid',
+    tokenize='porter unicode61'
+);
+INSERT INTO pages_word_fts(pages_word_fts) VALUES ('rebuild');
+
+CREATE TRIGGER IF NOT EXISTS pages_word_fts_insert AFTER INSERT ON unified_pages BEGIN
+    INSERT INTO pages_word_fts(rowid, title, content_summary, keywords, url)
+    VALUES (new.rowid, new.title, new.content_summary, new.keywords, new.url);
+END;
+
+CREATE TRIGGER IF NOT EXISTS pages_word_fts_delete AFTER DELETE ON unified_pages BEGIN
+    INSERT INTO pages_word_fts(pages_word_fts, rowid, title, content_summary, keywords, url)
+    VALUES ('delete', old.rowid, old.title, old.content_summary, old.keywords, old.url);
+END;
+
+CREATE TRIGGER IF NOT EXISTS pages_word_fts_update AFTER UPDATE OF title, content_summary, keywords, url ON unified_pages BEGIN
+    INSERT INTO pages_word_fts(pages_word_fts, rowid, title, content_summary, keywords, url)
+    VALUES ('delete', old.rowid, old.title, old.content_summary, old.keywords, old.url);
+    INSERT INTO pages_word_fts(rowid, title, content_summary, keywords, url)
+    VALUES (new.rowid, new.title, new.content_summary, new.keywords, new.url);
+END;
+"#;
+
+/// Reverts `PAGES_WORD_FTS_SQL`, dropping the word index
+pub const PAGES_WORD_FTS_DOWN_SQL: &str = r#"
+DROP TRIGGER IF EXISTS pages_word_fts_insert;
+DROP TRIGGER IF EXISTS pages_word_fts_delete;
+DROP TRIGGER IF EXISTS pages_word_fts_update;
+DROP TABLE IF EXISTS pages_word_fts;
+"#;
+
+/// Page trigram index maintained only when an indexed column changes
+///
+/// `pages_fts_update` fired on every update, so moving a page to the trash
+/// or counting an access deleted and reinserted all of its trigrams.
+pub const PAGES_FTS_UPDATE_COLUMNS_SQL: &str = r#"
+DROP TRIGGER IF EXISTS pages_fts_update;
+
+CREATE TRIGGER pages_fts_update AFTER UPDATE OF title, content_summary, keywords, url ON unified_pages BEGIN
+    INSERT INTO pages_fts(pages_fts, rowid, title, content_summary, keywords, url) 
+    VALUES ('delete', old.rowid, old.title, old.content_summary, old.keywords, old.url);
+    INSERT INTO pages_fts(rowid, title, content_summary, keywords, url) 
+    VALUES (new.rowid, new.title, new.content_summary, new.keywords, new.url);
+END;
+"#;
+
+/// Reverts `PAGES_FTS_UPDATE_COLUMNS_SQL`, firing on every update again
+pub const PAGES_FTS_UPDATE_COLUMNS_DOWN_SQL: &str = r#"
+DROP TRIGGER IF EXISTS pages_fts_update;
+
+CREATE TRIGGER pages_fts_update AFTER UPDATE ON unified_pages BEGIN
+    INSERT INTO pages_fts(pages_fts, rowid, title, content_summary, keywords, url) 
+    VALUES ('delete', old.rowid, old.title, old.content_summary, old.keywords, old.url);
+    INSERT INTO pages_fts(rowid, title, content_summary, keywords, url) 
+    VALUES (new.rowid, new.title, new.content_summary, new.keywords, new.url);
+END;
+"#;
+
+/// Change log payloads stored by the triggers and hashed when read
+///
+/// The triggers of `CHANGE_LOG_SQL` called `change_hash`, a function only
+/// `DatabaseManager` connections register, so any other writer failed with
+/// "no such function". They now store the row as JSON in `payload`;
+/// `changelog::hash_pending_payloads` replaces it with its hash when the log
+/// is read or maintained.
+pub const CHANGE_LOG_PAYLOADS_SQL: &str = r#"
+ALTER TABLE change_log ADD COLUMN payload TEXT;
+
+DROP TRIGGER IF EXISTS change_log_unified_pages_insert;
+CREATE TRIGGER change_log_unified_pages_insert AFTER INSERT ON unified_pages BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('page', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.url, new.title, new.favicon_url, new.content_summary, new.keywords, new.category, new.source_type, new.browser_info, new.tab_info, new.bookmark_info, new.created_at, new.last_accessed, new.access_count, new.deleted_at));
+END;
+
+DROP TRIGGER IF EXISTS change_log_unified_pages_update;
+CREATE TRIGGER change_log_unified_pages_update AFTER UPDATE ON unified_pages BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('page', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.url, new.title, new.favicon_url, new.content_summary, new.keywords, new.category, new.source_type, new.browser_info, new.tab_info, new.bookmark_info, new.created_at, new.last_accessed, new.access_count, new.deleted_at));
+END;
+
+DROP TRIGGER IF EXISTS change_log_smart_groups_insert;
+CREATE TRIGGER change_log_smart_groups_insert AFTER INSERT ON smart_groups BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('group', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.name, new.description, new.group_type, new.created_at, new.auto_generated, new.similarity_threshold));
+END;
+
+DROP TRIGGER IF EXISTS change_log_smart_groups_update;
+CREATE TRIGGER change_log_smart_groups_update AFTER UPDATE ON smart_groups BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('group', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.name, new.description, new.group_type, new.created_at, new.auto_generated, new.similarity_threshold));
+END;
+
+DROP TRIGGER IF EXISTS change_log_page_group_relations_insert;
+CREATE TRIGGER change_log_page_group_relations_insert AFTER INSERT ON page_group_relations BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('page_group', new.page_id || ':' || new.group_id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.page_id, new.group_id, new.added_at, new.confidence_score));
+END;
+
+DROP TRIGGER IF EXISTS change_log_page_group_relations_update;
+CREATE TRIGGER change_log_page_group_relations_update AFTER UPDATE ON page_group_relations BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('page_group', new.page_id || ':' || new.group_id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.page_id, new.group_id, new.added_at, new.confidence_score));
+END;
+
+DROP TRIGGER IF EXISTS change_log_tab_history_insert;
+CREATE TRIGGER change_log_tab_history_insert AFTER INSERT ON tab_history BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('history', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.page_id, new.url, new.title, new.favicon_url, new.browser_type, new.tab_id, new.closed_at, new.session_info, new.content_summary, new.recall_hint, new.deleted_at));
+END;
+
+DROP TRIGGER IF EXISTS change_log_tab_history_update;
+CREATE TRIGGER change_log_tab_history_update AFTER UPDATE ON tab_history BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('history', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.page_id, new.url, new.title, new.favicon_url, new.browser_type, new.tab_id, new.closed_at, new.session_info, new.content_summary, new.recall_hint, new.deleted_at));
+END;
+
+DROP TRIGGER IF EXISTS change_log_content_archives_insert;
+CREATE TRIGGER change_log_content_archives_insert AFTER INSERT ON content_archives BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('archive', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.page_id, new.url, new.title, new.content_html, new.content_text, new.media_files, new.archived_at, new.file_size, new.checksum, new.html_hash));
+END;
+
+DROP TRIGGER IF EXISTS change_log_content_archives_update;
+CREATE TRIGGER change_log_content_archives_update AFTER UPDATE ON content_archives BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('archive', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.page_id, new.url, new.title, new.content_html, new.content_text, new.media_files, new.archived_at, new.file_size, new.checksum, new.html_hash));
+END;
+
+DROP TRIGGER IF EXISTS change_log_tags_insert;
+CREATE TRIGGER change_log_tags_insert AFTER INSERT ON tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('tag', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.name, new.created_at));
+END;
+
+DROP TRIGGER IF EXISTS change_log_tags_update;
+CREATE TRIGGER change_log_tags_update AFTER UPDATE ON tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('tag', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.name, new.created_at));
+END;
+
+DROP TRIGGER IF EXISTS change_log_page_tags_insert;
+CREATE TRIGGER change_log_page_tags_insert AFTER INSERT ON page_tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('page_tag', new.page_id || ':' || new.tag_id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.page_id, new.tag_id, new.added_at));
+END;
+
+DROP TRIGGER IF EXISTS change_log_page_tags_update;
+CREATE TRIGGER change_log_page_tags_update AFTER UPDATE ON page_tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('page_tag', new.page_id || ':' || new.tag_id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.page_id, new.tag_id, new.added_at));
+END;
+
+DROP TRIGGER IF EXISTS change_log_page_notes_insert;
+CREATE TRIGGER change_log_page_notes_insert AFTER INSERT ON page_notes BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('note', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.page_id, new.content, new.quote, new.created_at, new.updated_at));
+END;
+
+DROP TRIGGER IF EXISTS change_log_page_notes_update;
+CREATE TRIGGER change_log_page_notes_update AFTER UPDATE ON page_notes BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('note', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.page_id, new.content, new.quote, new.created_at, new.updated_at));
+END;
+
+DROP TRIGGER IF EXISTS change_log_sessions_insert;
+CREATE TRIGGER change_log_sessions_insert AFTER INSERT ON sessions BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('session', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.name, new.created_at, new.updated_at));
+END;
+
+DROP TRIGGER IF EXISTS change_log_sessions_update;
+CREATE TRIGGER change_log_sessions_update AFTER UPDATE ON sessions BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload)
+    VALUES ('session', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            json_array(new.id, new.name, new.created_at, new.updated_at));
+END;
+"#;
+
+/// Reverts `CHANGE_LOG_PAYLOADS_SQL`, hashing pending payloads first
+pub const CHANGE_LOG_PAYLOADS_DOWN_SQL: &str = r#"
+UPDATE change_log SET payload_hash = change_hash(payload), payload = NULL WHERE payload IS NOT NULL;
+
+DROP TRIGGER IF EXISTS change_log_unified_pages_insert;
+CREATE TRIGGER change_log_unified_pages_insert AFTER INSERT ON unified_pages BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.url, new.title, new.favicon_url, new.content_summary, new.keywords, new.category, new.source_type, new.browser_info, new.tab_info, new.bookmark_info, new.created_at, new.last_accessed, new.access_count, new.deleted_at)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_unified_pages_update;
+CREATE TRIGGER change_log_unified_pages_update AFTER UPDATE ON unified_pages BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.url, new.title, new.favicon_url, new.content_summary, new.keywords, new.category, new.source_type, new.browser_info, new.tab_info, new.bookmark_info, new.created_at, new.last_accessed, new.access_count, new.deleted_at)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_smart_groups_insert;
+CREATE TRIGGER change_log_smart_groups_insert AFTER INSERT ON smart_groups BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('group', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.name, new.description, new.group_type, new.created_at, new.auto_generated, new.similarity_threshold)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_smart_groups_update;
+CREATE TRIGGER change_log_smart_groups_update AFTER UPDATE ON smart_groups BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('group', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.name, new.description, new.group_type, new.created_at, new.auto_generated, new.similarity_threshold)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_page_group_relations_insert;
+CREATE TRIGGER change_log_page_group_relations_insert AFTER INSERT ON page_group_relations BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page_group', new.page_id || ':' || new.group_id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.page_id, new.group_id, new.added_at, new.confidence_score)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_page_group_relations_update;
+CREATE TRIGGER change_log_page_group_relations_update AFTER UPDATE ON page_group_relations BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page_group', new.page_id || ':' || new.group_id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.page_id, new.group_id, new.added_at, new.confidence_score)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_tab_history_insert;
+CREATE TRIGGER change_log_tab_history_insert AFTER INSERT ON tab_history BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('history', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.page_id, new.url, new.title, new.favicon_url, new.browser_type, new.tab_id, new.closed_at, new.session_info, new.content_summary, new.recall_hint, new.deleted_at)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_tab_history_update;
+CREATE TRIGGER change_log_tab_history_update AFTER UPDATE ON tab_history BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('history', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.page_id, new.url, new.title, new.favicon_url, new.browser_type, new.tab_id, new.closed_at, new.session_info, new.content_summary, new.recall_hint, new.deleted_at)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_content_archives_insert;
+CREATE TRIGGER change_log_content_archives_insert AFTER INSERT ON content_archives BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('archive', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.page_id, new.url, new.title, new.content_html, new.content_text, new.media_files, new.archived_at, new.file_size, new.checksum, new.html_hash)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_content_archives_update;
+CREATE TRIGGER change_log_content_archives_update AFTER UPDATE ON content_archives BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('archive', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.page_id, new.url, new.title, new.content_html, new.content_text, new.media_files, new.archived_at, new.file_size, new.checksum, new.html_hash)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_tags_insert;
+CREATE TRIGGER change_log_tags_insert AFTER INSERT ON tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('tag', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.name, new.created_at)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_tags_update;
+CREATE TRIGGER change_log_tags_update AFTER UPDATE ON tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('tag', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.name, new.created_at)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_page_tags_insert;
+CREATE TRIGGER change_log_page_tags_insert AFTER INSERT ON page_tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page_tag', new.page_id || ':' || new.tag_id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.page_id, new.tag_id, new.added_at)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_page_tags_update;
+CREATE TRIGGER change_log_page_tags_update AFTER UPDATE ON page_tags BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('page_tag', new.page_id || ':' || new.tag_id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.page_id, new.tag_id, new.added_at)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_page_notes_insert;
+CREATE TRIGGER change_log_page_notes_insert AFTER INSERT ON page_notes BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('note', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.page_id, new.content, new.quote, new.created_at, new.updated_at)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_page_notes_update;
+CREATE TRIGGER change_log_page_notes_update AFTER UPDATE ON page_notes BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('note', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.page_id, new.content, new.quote, new.created_at, new.updated_at)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_sessions_insert;
+CREATE TRIGGER change_log_sessions_insert AFTER INSERT ON sessions BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('session', new.id, 'insert', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.name, new.created_at, new.updated_at)));
+END;
+
+DROP TRIGGER IF EXISTS change_log_sessions_update;
+CREATE TRIGGER change_log_sessions_update AFTER UPDATE ON sessions BEGIN
+    INSERT INTO change_log (entity, entity_id, op, changed_at, payload_hash)
+    VALUES ('session', new.id, 'update', CAST(strftime('%s', 'now') AS INTEGER),
+            change_hash(json_array(new.id, new.name, new.created_at, new.updated_at)));
+END;
+
+ALTER TABLE change_log DROP COLUMN payload;
+"#;
+
 /// Migration definitions
 pub struct Migration {
     pub version: u32,
     pub description: &'static str,
     pub sql: &'static str,
+    /// SQL undoing `sql`, or `None` if the migration cannot be reverted
+    pub down: Option<&'static str>,
 }
 
 /// List of all migrations
@@ -193,10 +1631,270 @@ pub const MIGRATIONS: &[Migration] = &[
         version: 1,
         description: "Initial schema",
         sql: SCHEMA_SQL,
+        down: None,
+    },
+    Migration {
+        version: 2,
+        description: "AI analysis result cache",
+        sql: ANALYSIS_CACHE_SQL,
+        down: Some(ANALYSIS_CACHE_DOWN_SQL),
+    },
+    Migration {
+        version: 3,
+        description: "History recall hints",
+        sql: HISTORY_RECALL_HINT_SQL,
+        down: Some(HISTORY_RECALL_HINT_DOWN_SQL),
+    },
+    Migration {
+        version: 4,
+        description: "Trigram full-text indexes for CJK search",
+        sql: TRIGRAM_FTS_SQL,
+        down: Some(TRIGRAM_FTS_DOWN_SQL),
+    },
+    Migration {
+        version: 5,
+        description: "Page embeddings for similarity search",
+        sql: PAGE_EMBEDDINGS_SQL,
+        down: Some(PAGE_EMBEDDINGS_DOWN_SQL),
+    },
+    Migration {
+        version: 6,
+        description: "Tags",
+        sql: TAGS_SQL,
+        down: Some(TAGS_DOWN_SQL),
+    },
+    Migration {
+        version: 7,
+        description: "Page notes and highlights",
+        sql: PAGE_NOTES_SQL,
+        down: Some(PAGE_NOTES_DOWN_SQL),
+    },
+    Migration {
+        version: 8,
+        description: "Saved tab sessions",
+        sql: SESSIONS_SQL,
+        down: Some(SESSIONS_DOWN_SQL),
+    },
+    Migration {
+        version: 9,
+        description: "Compressed, deduplicated archive content",
+        sql: ARCHIVE_BLOBS_SQL,
+        down: None,
+    },
+    Migration {
+        version: 10,
+        description: "Trash for pages and history",
+        sql: TRASH_SQL,
+        down: Some(TRASH_DOWN_SQL),
+    },
+    Migration {
+        version: 11,
+        description: "Change log for sync and undo",
+        sql: CHANGE_LOG_SQL,
+        down: Some(CHANGE_LOG_DOWN_SQL),
+    },
+    Migration {
+        version: 12,
+        description: "Usage analytics",
+        sql: ANALYTICS_SQL,
+        down: Some(ANALYTICS_DOWN_SQL),
+    },
+    Migration {
+        version: 13,
+        description: "Canonical page URLs",
+        sql: CANONICAL_URLS_SQL,
+        down: Some(CANONICAL_URLS_DOWN_SQL),
+    },
+    Migration {
+        version: 14,
+        description: "Favicon and thumbnail storage",
+        sql: MEDIA_ASSETS_SQL,
+        down: Some(MEDIA_ASSETS_DOWN_SQL),
+    },
+    Migration {
+        version: 15,
+        description: "Search history and saved searches",
+        sql: SEARCHES_SQL,
+        down: Some(SEARCHES_DOWN_SQL),
+    },
+    Migration {
+        version: 16,
+        description: "File-backed archive snapshots",
+        sql: SNAPSHOTS_SQL,
+        down: Some(SNAPSHOTS_DOWN_SQL),
+    },
+    Migration {
+        version: 17,
+        description: "Snoozed tabs",
+        sql: SNOOZED_TABS_SQL,
+        down: Some(SNOOZED_TABS_DOWN_SQL),
+    },
+    Migration {
+        version: 18,
+        description: "Tag hierarchy",
+        sql: TAG_HIERARCHY_SQL,
+        down: Some(TAG_HIERARCHY_DOWN_SQL),
+    },
+    Migration {
+        version: 19,
+        description: "Smart group overrides",
+        sql: GROUP_OVERRIDES_SQL,
+        down: Some(GROUP_OVERRIDES_DOWN_SQL),
+    },
+    Migration {
+        version: 20,
+        description: "Group archiving and page order",
+        sql: GROUP_STATE_SQL,
+        down: Some(GROUP_STATE_DOWN_SQL),
+    },
+    Migration {
+        version: 21,
+        description: "Reader views",
+        sql: READER_VIEWS_SQL,
+        down: Some(READER_VIEWS_DOWN_SQL),
+    },
+    Migration {
+        version: 22,
+        description: "Operation log",
+        sql: OPERATION_LOG_SQL,
+        down: Some(OPERATION_LOG_DOWN_SQL),
+    },
+    Migration {
+        version: 23,
+        description: "Word full-text index for pages",
+        sql: PAGES_WORD_FTS_SQL,
+        down: Some(PAGES_WORD_FTS_DOWN_SQL),
+    },
+    Migration {
+        version: 24,
+        description: "Page trigram index updated only for indexed columns",
+        sql: PAGES_FTS_UPDATE_COLUMNS_SQL,
+        down: Some(PAGES_FTS_UPDATE_COLUMNS_DOWN_SQL),
+    },
+    Migration {
+        version: 25,
+        description: "Change log payloads hashed when read",
+        sql: CHANGE_LOG_PAYLOADS_SQL,
+        down: Some(CHANGE_LOG_PAYLOADS_DOWN_SQL),
     },
 ];
 
+/// Direction a migration is applied in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationDirection {
+    Up,
+    Down,
+}
+
+/// One migration applied or reverted by `DatabaseManager::migrate_to`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStep {
+    pub version: u32,
+    pub description: &'static str,
+    pub direction: MigrationDirection,
+}
+
+/// Steps taking a database from version `from` to version `to`, in the
+/// order they run
+///
+/// Fails if `to` is newer than this build supports or a migration on the
+/// way down cannot be reverted.
+pub fn migration_plan(from: u32, to: u32) -> Result<Vec<MigrationStep>, String> {
+    if to > SCHEMA_VERSION {
+        return Err(format!("Schema version {} is newer than the supported {}", to, SCHEMA_VERSION));
+    }
+
+    let mut steps = Vec::new();
+    if to >= from {
+        for version in (from + 1)..=to {
+            let migration = get_migration(version).ok_or_else(|| format!("Migration {} not found", version))?;
+            steps.push(MigrationStep {
+                version,
+                description: migration.description,
+                direction: MigrationDirection::Up,
+            });
+        }
+    } else {
+        for version in ((to + 1)..=from).rev() {
+            let migration = get_migration(version).ok_or_else(|| format!("Migration {} not found", version))?;
+            if migration.down.is_none() {
+                return Err(format!("Migration {} ({}) cannot be reverted", version, migration.description));
+            }
+            steps.push(MigrationStep {
+                version,
+                description: migration.description,
+                direction: MigrationDirection::Down,
+            });
+        }
+    }
+    Ok(steps)
+}
+
 /// Get migration by version
 pub fn get_migration(version: u32) -> Option<&'static Migration> {
     MIGRATIONS.iter().find(|m| m.version == version)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrate(conn: &rusqlite::Connection, versions: impl Iterator<Item = u32>, direction: MigrationDirection) {
+        for version in versions {
+            let migration = get_migration(version).unwrap();
+            let sql = match direction {
+                MigrationDirection::Up => migration.sql,
+                MigrationDirection::Down => migration.down.unwrap(),
+            };
+            conn.execute_batch(sql)
+                .unwrap_or_else(|e| panic!("{:?} migration {} failed: {}", direction, version, e));
+        }
+    }
+
+    #[test]
+    fn test_down_migrations_revert_up_migrations() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::changelog::register_functions(&conn).unwrap();
+        crate::canonical::register_functions(&conn).unwrap();
+
+        // Every reversible migration survives a round trip
+        migrate(&conn, 1..=8, MigrationDirection::Up);
+        conn.execute_batch(
+            "INSERT INTO unified_pages (id, url, title, source_type, created_at, last_accessed) \
+             VALUES ('p1', 'https://example.com', 'Example', '{}', 0, 0); \
+             INSERT INTO tab_history (id, url, title, browser_type, closed_at, recall_hint) \
+             VALUES ('h1', 'https://example.com', 'Example', 'Chrome', 0, 'hint');",
+        )
+        .unwrap();
+        migrate(&conn, (2..=8).rev(), MigrationDirection::Down);
+        migrate(&conn, 2..=SCHEMA_VERSION, MigrationDirection::Up);
+        migrate(&conn, (10..=SCHEMA_VERSION).rev(), MigrationDirection::Down);
+        migrate(&conn, 10..=SCHEMA_VERSION, MigrationDirection::Up);
+
+        let found: i64 = conn
+            .query_row("SELECT COUNT(*) FROM history_fts WHERE history_fts MATCH 'Example'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(found, 1);
+        let pages: i64 = conn.query_row("SELECT COUNT(*) FROM unified_pages", [], |row| row.get(0)).unwrap();
+        assert_eq!(pages, 1);
+    }
+
+    #[test]
+    fn test_migration_plan() {
+        let up = migration_plan(8, 10).unwrap();
+        assert_eq!(up.iter().map(|s| s.version).collect::<Vec<_>>(), vec![9, 10]);
+        assert!(up.iter().all(|s| s.direction == MigrationDirection::Up));
+
+        let down = migration_plan(SCHEMA_VERSION, 9).unwrap();
+        assert_eq!(
+            down.iter().map(|s| s.version).collect::<Vec<_>>(),
+            (10..=SCHEMA_VERSION).rev().collect::<Vec<_>>()
+        );
+        assert!(down.iter().all(|s| s.direction == MigrationDirection::Down));
+
+        assert!(migration_plan(SCHEMA_VERSION, SCHEMA_VERSION).unwrap().is_empty());
+        assert!(migration_plan(SCHEMA_VERSION, SCHEMA_VERSION + 1).is_err());
+        // Archive compression cannot be reverted
+        assert!(migration_plan(SCHEMA_VERSION, 8).is_err());
+    }
+}
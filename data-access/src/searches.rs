@@ -0,0 +1,407 @@
+//! Search history and saved searches
+//!
+//! Executed queries are kept once each, matched ignoring case and
+//! surrounding spaces, with how often and when they were last run; they
+//! back the recent searches list and autocompletion. Saved searches are
+//! named queries with a filter whose JSON shape belongs to the search
+//! module, so this crate stores it without interpreting it.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rusqlite::{OptionalExtension, Row};
+use tokio_rusqlite::Connection;
+use web_page_manager_core::*;
+
+/// A query from the search history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentSearch {
+    /// The query as last typed
+    pub query: String,
+    /// Results the latest run returned
+    pub result_count: usize,
+    /// Times the query was run
+    pub search_count: u32,
+    pub last_searched_at: DateTime<Utc>,
+}
+
+/// A named search the user kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: Uuid,
+    /// Unique regardless of case
+    pub name: String,
+    pub query: String,
+    /// Filter definition as serialized by the search module
+    pub filter: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl SavedSearch {
+    pub fn new(name: impl Into<String>, query: impl Into<String>, filter: serde_json::Value) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            query: query.into(),
+            filter,
+            created_at: now,
+            updated_at: now,
+            last_used_at: None,
+        }
+    }
+}
+
+/// Repository trait for search history and saved searches
+#[async_trait]
+pub trait SearchRecordRepository: Send + Sync {
+    /// Add a run of `query` to the history; blank queries are ignored
+    async fn record_query(&self, query: &str, result_count: usize) -> Result<()>;
+    /// Most recently run queries first
+    async fn recent(&self, limit: usize) -> Result<Vec<RecentSearch>>;
+    /// Queries from the history starting with `prefix`, most often run first
+    async fn complete(&self, prefix: &str, limit: usize) -> Result<Vec<RecentSearch>>;
+    /// Remove one query from the history, returning whether it was there
+    async fn forget_query(&self, query: &str) -> Result<bool>;
+    /// Keep only the `keep` most recent queries, returning how many went
+    async fn trim_history(&self, keep: usize) -> Result<usize>;
+    async fn clear_history(&self) -> Result<()>;
+
+    /// Store or replace a saved search; fails if another has its name
+    async fn save_search(&self, search: &SavedSearch) -> Result<()>;
+    async fn get_saved(&self, id: &Uuid) -> Result<Option<SavedSearch>>;
+    async fn get_saved_by_name(&self, name: &str) -> Result<Option<SavedSearch>>;
+    /// All saved searches ordered by name
+    async fn list_saved(&self) -> Result<Vec<SavedSearch>>;
+    /// Note that a saved search was run; false if it does not exist
+    async fn mark_used(&self, id: &Uuid) -> Result<bool>;
+    async fn delete_saved(&self, id: &Uuid) -> Result<bool>;
+}
+
+/// SQLite implementation of SearchRecordRepository
+#[derive(Clone)]
+pub struct SqliteSearchRecordRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteSearchRecordRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+}
+
+fn search_error(action: &str, e: tokio_rusqlite::Error) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Failed to {}: {}", action, e),
+        },
+    }
+}
+
+fn name_error(details: String) -> WebPageManagerError {
+    WebPageManagerError::DataConsistency {
+        source: DataConsistencyError::DatabaseIntegrityViolation { details },
+    }
+}
+
+/// Key matching repeats of a query
+fn query_key(query: &str) -> String {
+    query.trim().to_lowercase()
+}
+
+/// Escape `%`, `_` and `\` for a LIKE pattern with `ESCAPE '\'`
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+fn timestamp(ts: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(ts, 0).unwrap_or_else(Utc::now)
+}
+
+/// Helper function to map a row to RecentSearch
+fn row_to_recent(row: &Row) -> rusqlite::Result<RecentSearch> {
+    Ok(RecentSearch {
+        query: row.get(0)?,
+        result_count: row.get::<_, i64>(1)? as usize,
+        search_count: row.get(2)?,
+        last_searched_at: timestamp(row.get(3)?),
+    })
+}
+
+/// Helper function to map a row to SavedSearch
+fn row_to_saved(row: &Row) -> rusqlite::Result<SavedSearch> {
+    let id_str: String = row.get(0)?;
+    let filter_json: String = row.get(3)?;
+    let last_used_at: Option<i64> = row.get(6)?;
+    Ok(SavedSearch {
+        id: Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4()),
+        name: row.get(1)?,
+        query: row.get(2)?,
+        filter: serde_json::from_str(&filter_json).unwrap_or(serde_json::Value::Null),
+        created_at: timestamp(row.get(4)?),
+        updated_at: timestamp(row.get(5)?),
+        last_used_at: last_used_at.map(timestamp),
+    })
+}
+
+const SAVED_COLUMNS: &str = "id, name, query, filter, created_at, updated_at, last_used_at";
+
+#[async_trait]
+impl SearchRecordRepository for SqliteSearchRecordRepository {
+    async fn record_query(&self, query: &str, result_count: usize) -> Result<()> {
+        let key = query_key(query);
+        if key.is_empty() {
+            return Ok(());
+        }
+        let query = query.trim().to_string();
+        let now = Utc::now().timestamp();
+
+        self.connection
+            .call(move |conn| {
+                conn.prepare_cached(
+                    "INSERT INTO search_history (query_key, query, result_count, last_searched_at) \
+                     VALUES (?1, ?2, ?3, ?4) \
+                     ON CONFLICT(query_key) DO UPDATE SET query = excluded.query, \
+                     result_count = excluded.result_count, search_count = search_count + 1, \
+                     last_searched_at = excluded.last_searched_at",
+                )?
+                .execute(rusqlite::params![key, query, result_count as i64, now])?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| search_error("record search", e))
+    }
+
+    async fn recent(&self, limit: usize) -> Result<Vec<RecentSearch>> {
+        self.connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT query, result_count, search_count, last_searched_at FROM search_history \
+                     ORDER BY last_searched_at DESC, rowid DESC LIMIT ?1",
+                )?;
+                let rows = stmt.query_map([limit as i64], row_to_recent)?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
+            .await
+            .map_err(|e| search_error("get recent searches", e))
+    }
+
+    async fn complete(&self, prefix: &str, limit: usize) -> Result<Vec<RecentSearch>> {
+        let pattern = format!("{}%", escape_like(&query_key(prefix)));
+
+        self.connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT query, result_count, search_count, last_searched_at FROM search_history \
+                     WHERE query_key LIKE ?1 ESCAPE '\\' \
+                     ORDER BY search_count DESC, last_searched_at DESC LIMIT ?2",
+                )?;
+                let rows = stmt.query_map(rusqlite::params![pattern, limit as i64], row_to_recent)?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
+            .await
+            .map_err(|e| search_error("complete search", e))
+    }
+
+    async fn forget_query(&self, query: &str) -> Result<bool> {
+        let key = query_key(query);
+
+        self.connection
+            .call(move |conn| Ok(conn.execute("DELETE FROM search_history WHERE query_key = ?1", [key])? > 0))
+            .await
+            .map_err(|e| search_error("forget search", e))
+    }
+
+    async fn trim_history(&self, keep: usize) -> Result<usize> {
+        self.connection
+            .call(move |conn| {
+                let trimmed = conn.execute(
+                    "DELETE FROM search_history WHERE rowid NOT IN (\
+                     SELECT rowid FROM search_history ORDER BY last_searched_at DESC, rowid DESC LIMIT ?1)",
+                    [keep as i64],
+                )?;
+                Ok(trimmed)
+            })
+            .await
+            .map_err(|e| search_error("trim search history", e))
+    }
+
+    async fn clear_history(&self) -> Result<()> {
+        self.connection
+            .call(|conn| {
+                conn.execute("DELETE FROM search_history", [])?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| search_error("clear search history", e))
+    }
+
+    async fn save_search(&self, search: &SavedSearch) -> Result<()> {
+        let name = search.name.trim().to_string();
+        if name.is_empty() {
+            return Err(name_error("Saved search name is empty".to_string()));
+        }
+        let search = search.clone();
+        let filter_json = serde_json::to_string(&search.filter).unwrap_or_default();
+
+        let saved = self
+            .connection
+            .call(move |conn| {
+                let id = search.id.to_string();
+                let taken: Option<String> = conn
+                    .query_row(
+                        "SELECT name FROM saved_searches WHERE name = ?1 AND id != ?2",
+                        [&name, &id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if let Some(taken) = taken {
+                    return Ok(Err(taken));
+                }
+                conn.execute(
+                    "INSERT INTO saved_searches (id, name, query, filter, created_at, updated_at, last_used_at) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7) \
+                     ON CONFLICT(id) DO UPDATE SET name = excluded.name, query = excluded.query, \
+                     filter = excluded.filter, updated_at = excluded.updated_at, last_used_at = excluded.last_used_at",
+                    rusqlite::params![
+                        id,
+                        name,
+                        search.query,
+                        filter_json,
+                        search.created_at.timestamp(),
+                        search.updated_at.timestamp(),
+                        search.last_used_at.map(|t| t.timestamp()),
+                    ],
+                )?;
+                Ok(Ok(()))
+            })
+            .await
+            .map_err(|e| search_error("save search", e))?;
+
+        saved.map_err(|taken| name_error(format!("A saved search named \"{}\" already exists", taken)))
+    }
+
+    async fn get_saved(&self, id: &Uuid) -> Result<Option<SavedSearch>> {
+        let id_str = id.to_string();
+
+        self.connection
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM saved_searches WHERE id = ?1", SAVED_COLUMNS),
+                        [id_str],
+                        row_to_saved,
+                    )
+                    .optional()?)
+            })
+            .await
+            .map_err(|e| search_error("get saved search", e))
+    }
+
+    async fn get_saved_by_name(&self, name: &str) -> Result<Option<SavedSearch>> {
+        let name = name.trim().to_string();
+
+        self.connection
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM saved_searches WHERE name = ?1", SAVED_COLUMNS),
+                        [name],
+                        row_to_saved,
+                    )
+                    .optional()?)
+            })
+            .await
+            .map_err(|e| search_error("get saved search", e))
+    }
+
+    async fn list_saved(&self) -> Result<Vec<SavedSearch>> {
+        self.connection
+            .call(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {} FROM saved_searches ORDER BY name COLLATE NOCASE",
+                    SAVED_COLUMNS
+                ))?;
+                let rows = stmt.query_map([], row_to_saved)?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
+            .await
+            .map_err(|e| search_error("list saved searches", e))
+    }
+
+    async fn mark_used(&self, id: &Uuid) -> Result<bool> {
+        let id_str = id.to_string();
+        let now = Utc::now().timestamp();
+
+        self.connection
+            .call(move |conn| {
+                let updated = conn.execute(
+                    "UPDATE saved_searches SET last_used_at = ?1 WHERE id = ?2",
+                    rusqlite::params![now, id_str],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+            .map_err(|e| search_error("mark saved search used", e))
+    }
+
+    async fn delete_saved(&self, id: &Uuid) -> Result<bool> {
+        let id_str = id.to_string();
+
+        self.connection
+            .call(move |conn| Ok(conn.execute("DELETE FROM saved_searches WHERE id = ?1", [id_str])? > 0))
+            .await
+            .map_err(|e| search_error("delete saved search", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_search_history_and_saved_searches() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let searches = db.search_record_repository();
+
+        for (query, results) in [("rust async", 4), ("Rust Async ", 6), ("rustdoc", 2), ("50%_off", 1), ("  ", 0)] {
+            searches.record_query(query, results).await.unwrap();
+        }
+        let recent = searches.recent(10).await.unwrap();
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent.iter().find(|r| r.query == "Rust Async").map(|r| (r.search_count, r.result_count)), Some((2, 6)));
+
+        // Repeated queries complete first; LIKE wildcards match literally
+        let completed: Vec<String> = searches.complete("RU", 10).await.unwrap().into_iter().map(|r| r.query).collect();
+        assert_eq!(completed, vec!["Rust Async", "rustdoc"]);
+        assert_eq!(searches.complete("50%_", 10).await.unwrap().len(), 1);
+        assert!(searches.complete("5_", 10).await.unwrap().is_empty());
+
+        assert!(searches.forget_query("RUSTDOC").await.unwrap());
+        assert_eq!(searches.trim_history(1).await.unwrap(), 1);
+        assert_eq!(searches.recent(10).await.unwrap().len(), 1);
+        searches.clear_history().await.unwrap();
+        assert!(searches.recent(10).await.unwrap().is_empty());
+
+        let filter = serde_json::json!({ "source_types": ["History"], "category": "docs" });
+        let mut saved = SavedSearch::new("Docs", "rust", filter.clone());
+        searches.save_search(&saved).await.unwrap();
+        assert!(searches.save_search(&SavedSearch::new("docs", "other", filter.clone())).await.is_err());
+        assert!(searches.save_search(&SavedSearch::new(" ", "blank", filter.clone())).await.is_err());
+
+        saved.query = "rust book".to_string();
+        searches.save_search(&saved).await.unwrap();
+        assert!(searches.mark_used(&saved.id).await.unwrap());
+        let loaded = searches.get_saved_by_name("DOCS").await.unwrap().unwrap();
+        assert_eq!((loaded.query.as_str(), &loaded.filter), ("rust book", &filter));
+        assert!(loaded.last_used_at.is_some());
+        assert_eq!(searches.list_saved().await.unwrap().len(), 1);
+
+        assert!(searches.delete_saved(&saved.id).await.unwrap());
+        assert!(searches.get_saved(&saved.id).await.unwrap().is_none());
+        assert!(!searches.mark_used(&saved.id).await.unwrap());
+    }
+}
@@ -0,0 +1,475 @@
+//! File-backed snapshots of archived pages
+//!
+//! MHTML captures, screenshots and PDFs are too large for the database, so
+//! their bytes go to a content-addressed store on disk: each file is named
+//! by the SHA-1 of its contents under a two-character fan-out directory,
+//! and identical snapshots share one file. `archive_snapshots` rows attach
+//! files to archives and go with their archive. Files no row refers to are
+//! removed by `collect_garbage`, which spares files written within a grace
+//! period so a snapshot being stored is never collected before its row is
+//! written.
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use rusqlite::OptionalExtension;
+use tokio_rusqlite::Connection;
+use tracing::info;
+use web_page_manager_core::*;
+
+use crate::compression::bytes_hash;
+
+/// Files written within this time are never collected
+pub const DEFAULT_GC_GRACE: Duration = Duration::from_secs(60 * 60);
+
+/// Suffix of files being written, renamed into place when complete
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// What a snapshot captures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SnapshotKind {
+    /// Single-file web archive of the page and its resources
+    Mhtml,
+    Screenshot,
+    Pdf,
+}
+
+impl SnapshotKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            SnapshotKind::Mhtml => "mhtml",
+            SnapshotKind::Screenshot => "screenshot",
+            SnapshotKind::Pdf => "pdf",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "mhtml" => Some(SnapshotKind::Mhtml),
+            "screenshot" => Some(SnapshotKind::Screenshot),
+            "pdf" => Some(SnapshotKind::Pdf),
+            _ => None,
+        }
+    }
+}
+
+/// Snapshot attached to an archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub id: Uuid,
+    pub archive_id: ArchiveId,
+    pub kind: SnapshotKind,
+    /// Hex SHA-1 of the file contents
+    pub hash: String,
+    pub mime_type: String,
+    pub size: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Outcome of a garbage collection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotCollection {
+    pub files_removed: usize,
+    pub bytes_freed: u64,
+}
+
+/// Snapshot rows and the files they refer to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotUsage {
+    pub snapshots: usize,
+    pub files: usize,
+    /// Size of the files on disk, each counted once
+    pub bytes: u64,
+}
+
+/// Content-addressed directory of snapshot files
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    root: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of the file holding contents with `hash`
+    pub fn path_of(&self, hash: &str) -> PathBuf {
+        let (prefix, rest) = hash.split_at(2.min(hash.len()));
+        self.root.join(prefix).join(rest)
+    }
+
+    /// Write `data` unless a file with the same contents exists, returning
+    /// its hash
+    ///
+    /// The file is written under a temporary name and renamed, so a file
+    /// under its final name is always complete. An existing file has its
+    /// modification time renewed, which keeps it out of a concurrent
+    /// collection.
+    fn write(&self, data: &[u8]) -> std::io::Result<String> {
+        let hash = bytes_hash(data);
+        let path = self.path_of(&hash);
+        if path.exists() {
+            std::fs::File::options().append(true).open(&path)?.set_modified(SystemTime::now())?;
+            return Ok(hash);
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_file_name(format!("{}.{}{}", hash, Uuid::new_v4().simple(), PARTIAL_SUFFIX));
+        let mut file = std::fs::File::create(&partial)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        std::fs::rename(&partial, &path)?;
+        Ok(hash)
+    }
+
+    /// Remove files, finished or partial, whose hash is not in `keep` and
+    /// that were last written before `grace` ago
+    fn collect(&self, keep: &HashSet<String>, grace: Duration) -> std::io::Result<SnapshotCollection> {
+        let mut collection = SnapshotCollection::default();
+        let Ok(prefixes) = std::fs::read_dir(&self.root) else {
+            return Ok(collection);
+        };
+        let cutoff = SystemTime::now().checked_sub(grace).unwrap_or(SystemTime::UNIX_EPOCH);
+        for prefix in prefixes {
+            let prefix = prefix?;
+            if !prefix.file_type()?.is_dir() {
+                continue;
+            }
+            let prefix_name = prefix.file_name().to_string_lossy().into_owned();
+            for file in std::fs::read_dir(prefix.path())? {
+                let file = file?;
+                let metadata = file.metadata()?;
+                if !metadata.is_file() || metadata.modified()? >= cutoff {
+                    continue;
+                }
+                let name = file.file_name().to_string_lossy().into_owned();
+                let hash = format!("{}{}", prefix_name, name);
+                if !name.ends_with(PARTIAL_SUFFIX) && keep.contains(&hash) {
+                    continue;
+                }
+                std::fs::remove_file(file.path())?;
+                collection.files_removed += 1;
+                collection.bytes_freed += metadata.len();
+            }
+        }
+        Ok(collection)
+    }
+}
+
+/// Repository trait for archive snapshots
+#[async_trait]
+pub trait SnapshotRepository: Send + Sync {
+    /// Write a snapshot to the store and attach it to an archive; `None` if
+    /// the archive does not exist
+    async fn store(&self, archive_id: &ArchiveId, kind: SnapshotKind, data: &[u8], mime_type: &str) -> Result<Option<Snapshot>>;
+    async fn get(&self, id: &Uuid) -> Result<Option<Snapshot>>;
+    /// Snapshots of an archive, oldest first
+    async fn list_for_archive(&self, archive_id: &ArchiveId) -> Result<Vec<Snapshot>>;
+    /// Contents of a snapshot; `None` if it does not exist
+    async fn read(&self, id: &Uuid) -> Result<Option<Vec<u8>>>;
+    /// Detach a snapshot; its file is removed by the next collection if
+    /// nothing else refers to it
+    async fn delete(&self, id: &Uuid) -> Result<bool>;
+    /// Remove files no snapshot refers to, sparing those written within
+    /// `grace`
+    async fn collect_garbage(&self, grace: Duration) -> Result<SnapshotCollection>;
+    async fn usage(&self) -> Result<SnapshotUsage>;
+}
+
+/// SQLite implementation of SnapshotRepository, with files in a
+/// `SnapshotStore`
+#[derive(Clone)]
+pub struct SqliteSnapshotRepository {
+    connection: Arc<Connection>,
+    store: SnapshotStore,
+}
+
+impl SqliteSnapshotRepository {
+    pub fn new(connection: Arc<Connection>, store: SnapshotStore) -> Self {
+        Self { connection, store }
+    }
+
+    pub fn file_store(&self) -> &SnapshotStore {
+        &self.store
+    }
+
+    /// Path of a snapshot's file, for streaming it instead of reading it
+    /// whole
+    pub fn path_of(&self, snapshot: &Snapshot) -> PathBuf {
+        self.store.path_of(&snapshot.hash)
+    }
+
+    /// Hashes referred to by snapshot rows
+    async fn referenced(&self) -> Result<HashSet<String>> {
+        self.connection
+            .call(|conn| {
+                let mut stmt = conn.prepare("SELECT DISTINCT hash FROM archive_snapshots")?;
+                let hashes = stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<HashSet<String>>>()?;
+                Ok(hashes)
+            })
+            .await
+            .map_err(|e| snapshot_error("list snapshot files", e))
+    }
+}
+
+fn snapshot_error(action: &str, e: impl std::fmt::Display) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Failed to {}: {}", action, e),
+        },
+    }
+}
+
+fn io_error(e: std::io::Error) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::IO { source: e },
+    }
+}
+
+/// Run blocking file work off the async runtime
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> std::io::Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| snapshot_error("run snapshot file task", e))?
+        .map_err(io_error)
+}
+
+/// Helper function to map a row to Snapshot
+fn row_to_snapshot(row: &rusqlite::Row) -> rusqlite::Result<Snapshot> {
+    let id: String = row.get(0)?;
+    let archive_id: String = row.get(1)?;
+    let kind: String = row.get(2)?;
+    let size: i64 = row.get(5)?;
+    let created_at_ts: i64 = row.get(6)?;
+    Ok(Snapshot {
+        id: Uuid::parse_str(&id).unwrap_or_default(),
+        archive_id: ArchiveId(Uuid::parse_str(&archive_id).unwrap_or_default()),
+        kind: SnapshotKind::parse(&kind).unwrap_or(SnapshotKind::Mhtml),
+        hash: row.get(3)?,
+        mime_type: row.get(4)?,
+        size: size as u64,
+        created_at: DateTime::from_timestamp(created_at_ts, 0).unwrap_or_else(Utc::now),
+    })
+}
+
+#[async_trait]
+impl SnapshotRepository for SqliteSnapshotRepository {
+    async fn store(&self, archive_id: &ArchiveId, kind: SnapshotKind, data: &[u8], mime_type: &str) -> Result<Option<Snapshot>> {
+        let store = self.store.clone();
+        let bytes = data.to_vec();
+        // A file written for a missing archive is left to the collector
+        let hash = blocking(move || store.write(&bytes)).await?;
+
+        let snapshot = Snapshot {
+            id: Uuid::new_v4(),
+            archive_id: archive_id.clone(),
+            kind,
+            hash,
+            mime_type: mime_type.to_string(),
+            size: data.len() as u64,
+            created_at: Utc::now(),
+        };
+        let row = snapshot.clone();
+        let inserted = self
+            .connection
+            .call(move |conn| {
+                let inserted = conn.execute(
+                    "INSERT INTO archive_snapshots (id, archive_id, kind, hash, mime_type, size, created_at) \
+                     SELECT ?1, id, ?3, ?4, ?5, ?6, ?7 FROM content_archives WHERE id = ?2",
+                    rusqlite::params![
+                        row.id.to_string(),
+                        row.archive_id.0.to_string(),
+                        row.kind.as_str(),
+                        row.hash,
+                        row.mime_type,
+                        row.size as i64,
+                        row.created_at.timestamp(),
+                    ],
+                )?;
+                Ok(inserted > 0)
+            })
+            .await
+            .map_err(|e| snapshot_error("store snapshot", e))?;
+        Ok(inserted.then_some(snapshot))
+    }
+
+    async fn get(&self, id: &Uuid) -> Result<Option<Snapshot>> {
+        let id = id.to_string();
+
+        self.connection
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT id, archive_id, kind, hash, mime_type, size, created_at FROM archive_snapshots WHERE id = ?1",
+                        [id],
+                        row_to_snapshot,
+                    )
+                    .optional()?)
+            })
+            .await
+            .map_err(|e| snapshot_error("get snapshot", e))
+    }
+
+    async fn list_for_archive(&self, archive_id: &ArchiveId) -> Result<Vec<Snapshot>> {
+        let archive_id = archive_id.0.to_string();
+
+        self.connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT id, archive_id, kind, hash, mime_type, size, created_at FROM archive_snapshots \
+                     WHERE archive_id = ?1 ORDER BY created_at, rowid",
+                )?;
+                let snapshots = stmt.query_map([archive_id], row_to_snapshot)?.collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(snapshots)
+            })
+            .await
+            .map_err(|e| snapshot_error("list snapshots", e))
+    }
+
+    async fn read(&self, id: &Uuid) -> Result<Option<Vec<u8>>> {
+        let Some(snapshot) = self.get(id).await? else {
+            return Ok(None);
+        };
+        let path = self.path_of(&snapshot);
+        let data = blocking(move || std::fs::read(path)).await?;
+        if bytes_hash(&data) != snapshot.hash {
+            return Err(WebPageManagerError::DataConsistency {
+                source: DataConsistencyError::DatabaseIntegrityViolation {
+                    details: format!("Snapshot file {} does not match its hash", snapshot.hash),
+                },
+            });
+        }
+        Ok(Some(data))
+    }
+
+    async fn delete(&self, id: &Uuid) -> Result<bool> {
+        let id = id.to_string();
+
+        self.connection
+            .call(move |conn| Ok(conn.execute("DELETE FROM archive_snapshots WHERE id = ?1", [id])? > 0))
+            .await
+            .map_err(|e| snapshot_error("delete snapshot", e))
+    }
+
+    async fn collect_garbage(&self, grace: Duration) -> Result<SnapshotCollection> {
+        let keep = self.referenced().await?;
+        let store = self.store.clone();
+        let collection = blocking(move || store.collect(&keep, grace)).await?;
+        if collection.files_removed > 0 {
+            info!(
+                "Removed {} unreferenced snapshot files ({} bytes)",
+                collection.files_removed, collection.bytes_freed
+            );
+        }
+        Ok(collection)
+    }
+
+    async fn usage(&self) -> Result<SnapshotUsage> {
+        self.connection
+            .call(|conn| {
+                let (snapshots, files, bytes): (i64, i64, i64) = conn.query_row(
+                    "SELECT (SELECT COUNT(*) FROM archive_snapshots), COUNT(*), COALESCE(SUM(size), 0) \
+                     FROM (SELECT hash, MAX(size) AS size FROM archive_snapshots GROUP BY hash)",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )?;
+                Ok(SnapshotUsage {
+                    snapshots: snapshots as usize,
+                    files: files as usize,
+                    bytes: bytes as u64,
+                })
+            })
+            .await
+            .map_err(|e| snapshot_error("measure snapshots", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use crate::{ArchiveRepository, ContentArchive, DatabaseManager, PageRepository};
+
+    fn test_archive(page_id: Uuid) -> ContentArchive {
+        ContentArchive {
+            id: ArchiveId::new(),
+            page_id,
+            url: "https://example.com/".to_string(),
+            title: "Example".to_string(),
+            content_html: "<p>Example</p>".to_string(),
+            content_text: "Example".to_string(),
+            media_files: vec![],
+            archived_at: Utc::now(),
+            file_size: 14,
+            checksum: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_are_deduplicated_and_collected() {
+        let dir = temp_dir("snapshots");
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let snapshots = db.snapshot_repository(&dir);
+        let page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: "https://example.com/".to_string(),
+            title: "Example".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::ArchivedContent { archive_id: ArchiveId::new() },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        db.page_repository().save(&page).await.unwrap();
+        let (first, second) = (test_archive(page.id), test_archive(page.id));
+        db.archive_repository().save(&first).await.unwrap();
+        db.archive_repository().save(&second).await.unwrap();
+
+        let mhtml = b"MIME-Version: 1.0\r\n\r\n<html>Example</html>".to_vec();
+        let kept = snapshots.store(&first.id, SnapshotKind::Mhtml, &mhtml, "multipart/related").await.unwrap().unwrap();
+        let shared = snapshots.store(&second.id, SnapshotKind::Mhtml, &mhtml, "multipart/related").await.unwrap().unwrap();
+        let screenshot = snapshots.store(&second.id, SnapshotKind::Screenshot, b"\x89PNG", "image/png").await.unwrap().unwrap();
+        assert_eq!(kept.hash, shared.hash);
+        assert!(snapshots.path_of(&kept).exists());
+        assert!(snapshots.store(&ArchiveId::new(), SnapshotKind::Pdf, b"%PDF", "application/pdf").await.unwrap().is_none());
+
+        assert_eq!(snapshots.read(&kept.id).await.unwrap().unwrap(), mhtml);
+        let listed: Vec<Uuid> = snapshots.list_for_archive(&second.id).await.unwrap().iter().map(|s| s.id).collect();
+        assert_eq!(listed, vec![shared.id, screenshot.id]);
+        let usage = snapshots.usage().await.unwrap();
+        assert_eq!((usage.snapshots, usage.files, usage.bytes), (3, 2, mhtml.len() as u64 + 4));
+
+        // Deleting the archive drops its rows; the shared file stays in use
+        db.archive_repository().delete(&second.id).await.unwrap();
+        assert!(snapshots.get(&screenshot.id).await.unwrap().is_none());
+        // Recent files are spared until the grace period has passed
+        assert_eq!(snapshots.collect_garbage(DEFAULT_GC_GRACE).await.unwrap(), SnapshotCollection::default());
+        let collected = snapshots.collect_garbage(Duration::ZERO).await.unwrap();
+        // The screenshot and the PDF stored for a missing archive
+        assert_eq!(collected, SnapshotCollection { files_removed: 2, bytes_freed: 8 });
+        assert!(snapshots.path_of(&kept).exists());
+        assert!(!snapshots.path_of(&screenshot).exists());
+
+        assert!(snapshots.delete(&kept.id).await.unwrap());
+        snapshots.collect_garbage(Duration::ZERO).await.unwrap();
+        assert!(!snapshots.path_of(&kept).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,265 @@
+//! Snoozed tabs
+//!
+//! A snoozed tab was closed to come back later: it is kept with the
+//! browser it was open in and the time it should reopen at, until the
+//! snooze scheduler reopens it or the user cancels the snooze.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rusqlite::{OptionalExtension, Row};
+use tokio_rusqlite::Connection;
+use web_page_manager_core::*;
+
+/// A tab closed until its wake time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnoozedTab {
+    pub id: Uuid,
+    pub url: String,
+    pub title: String,
+    pub favicon_url: Option<String>,
+    /// Browser the tab reopens in
+    pub browser: BrowserType,
+    pub snoozed_at: DateTime<Utc>,
+    pub wake_at: DateTime<Utc>,
+}
+
+impl SnoozedTab {
+    /// Snooze `tab` until `wake_at`
+    pub fn new(tab: &TabInfo, wake_at: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            url: tab.url.clone(),
+            title: tab.title.clone(),
+            favicon_url: tab.favicon_url.clone(),
+            browser: tab.browser_type,
+            snoozed_at: Utc::now(),
+            wake_at,
+        }
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.wake_at <= now
+    }
+}
+
+/// Repository trait for snoozed tabs
+#[async_trait]
+pub trait SnoozeRepository: Send + Sync {
+    /// Store or replace a snoozed tab
+    async fn save(&self, snooze: &SnoozedTab) -> Result<()>;
+    async fn get_by_id(&self, id: &Uuid) -> Result<Option<SnoozedTab>>;
+    /// All snoozed tabs, soonest to wake first
+    async fn pending(&self) -> Result<Vec<SnoozedTab>>;
+    /// Snoozed tabs whose wake time is at or before `now`, soonest first
+    async fn due(&self, now: DateTime<Utc>) -> Result<Vec<SnoozedTab>>;
+    /// Move a snooze's wake time; false if it does not exist
+    async fn reschedule(&self, id: &Uuid, wake_at: DateTime<Utc>) -> Result<bool>;
+    /// Remove a snooze, returning whether it was there
+    async fn delete(&self, id: &Uuid) -> Result<bool>;
+    async fn count(&self) -> Result<usize>;
+}
+
+/// SQLite implementation of SnoozeRepository
+#[derive(Clone)]
+pub struct SqliteSnoozeRepository {
+    connection: Arc<Connection>,
+}
+
+impl SqliteSnoozeRepository {
+    pub fn new(connection: Arc<Connection>) -> Self {
+        Self { connection }
+    }
+}
+
+fn snooze_error(action: &str, e: tokio_rusqlite::Error) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Failed to {}: {}", action, e),
+        },
+    }
+}
+
+fn timestamp(ts: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(ts, 0).unwrap_or_else(Utc::now)
+}
+
+/// Helper function to map a row to SnoozedTab
+fn row_to_snooze(row: &Row) -> rusqlite::Result<SnoozedTab> {
+    let id_str: String = row.get(0)?;
+    let browser: String = row.get(4)?;
+    Ok(SnoozedTab {
+        id: Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4()),
+        url: row.get(1)?,
+        title: row.get(2)?,
+        favicon_url: row.get(3)?,
+        browser: serde_json::from_str(&browser).unwrap_or(BrowserType::Chrome),
+        snoozed_at: timestamp(row.get(5)?),
+        wake_at: timestamp(row.get(6)?),
+    })
+}
+
+const SNOOZE_COLUMNS: &str = "id, url, title, favicon_url, browser_type, snoozed_at, wake_at";
+
+#[async_trait]
+impl SnoozeRepository for SqliteSnoozeRepository {
+    async fn save(&self, snooze: &SnoozedTab) -> Result<()> {
+        let snooze = snooze.clone();
+        let browser = serde_json::to_string(&snooze.browser).unwrap_or_default();
+
+        self.connection
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO snoozed_tabs (id, url, title, favicon_url, browser_type, snoozed_at, wake_at) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7) \
+                     ON CONFLICT(id) DO UPDATE SET url = excluded.url, title = excluded.title, \
+                     favicon_url = excluded.favicon_url, browser_type = excluded.browser_type, \
+                     wake_at = excluded.wake_at",
+                    rusqlite::params![
+                        snooze.id.to_string(),
+                        snooze.url,
+                        snooze.title,
+                        snooze.favicon_url,
+                        browser,
+                        snooze.snoozed_at.timestamp(),
+                        snooze.wake_at.timestamp(),
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| snooze_error("save snoozed tab", e))
+    }
+
+    async fn get_by_id(&self, id: &Uuid) -> Result<Option<SnoozedTab>> {
+        let id_str = id.to_string();
+
+        self.connection
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM snoozed_tabs WHERE id = ?1", SNOOZE_COLUMNS),
+                        [id_str],
+                        row_to_snooze,
+                    )
+                    .optional()?)
+            })
+            .await
+            .map_err(|e| snooze_error("get snoozed tab", e))
+    }
+
+    async fn pending(&self) -> Result<Vec<SnoozedTab>> {
+        self.connection
+            .call(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {} FROM snoozed_tabs ORDER BY wake_at, rowid",
+                    SNOOZE_COLUMNS
+                ))?;
+                let rows = stmt.query_map([], row_to_snooze)?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
+            .await
+            .map_err(|e| snooze_error("list snoozed tabs", e))
+    }
+
+    async fn due(&self, now: DateTime<Utc>) -> Result<Vec<SnoozedTab>> {
+        let now = now.timestamp();
+
+        self.connection
+            .call(move |conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {} FROM snoozed_tabs WHERE wake_at <= ?1 ORDER BY wake_at, rowid",
+                    SNOOZE_COLUMNS
+                ))?;
+                let rows = stmt.query_map([now], row_to_snooze)?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
+            .await
+            .map_err(|e| snooze_error("get due snoozed tabs", e))
+    }
+
+    async fn reschedule(&self, id: &Uuid, wake_at: DateTime<Utc>) -> Result<bool> {
+        let id_str = id.to_string();
+
+        self.connection
+            .call(move |conn| {
+                let updated = conn.execute(
+                    "UPDATE snoozed_tabs SET wake_at = ?1 WHERE id = ?2",
+                    rusqlite::params![wake_at.timestamp(), id_str],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+            .map_err(|e| snooze_error("reschedule snoozed tab", e))
+    }
+
+    async fn delete(&self, id: &Uuid) -> Result<bool> {
+        let id_str = id.to_string();
+
+        self.connection
+            .call(move |conn| Ok(conn.execute("DELETE FROM snoozed_tabs WHERE id = ?1", [id_str])? > 0))
+            .await
+            .map_err(|e| snooze_error("delete snoozed tab", e))
+    }
+
+    async fn count(&self) -> Result<usize> {
+        self.connection
+            .call(|conn| {
+                let count: i64 = conn.query_row("SELECT COUNT(*) FROM snoozed_tabs", [], |row| row.get(0))?;
+                Ok(count as usize)
+            })
+            .await
+            .map_err(|e| snooze_error("count snoozed tabs", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DatabaseManager;
+
+    fn tab(url: &str, browser: BrowserType) -> TabInfo {
+        TabInfo {
+            id: TabId::new(),
+            url: url.to_string(),
+            title: url.to_string(),
+            favicon_url: None,
+            browser_type: browser,
+            is_private: false,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snoozed_tabs() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let snoozes = db.snooze_repository();
+        let now = Utc::now();
+
+        let later = SnoozedTab::new(&tab("https://later.example.com", BrowserType::Firefox), now + chrono::Duration::hours(3));
+        let soon = SnoozedTab::new(&tab("https://soon.example.com", BrowserType::Edge), now - chrono::Duration::minutes(1));
+        snoozes.save(&later).await.unwrap();
+        snoozes.save(&soon).await.unwrap();
+
+        let pending = snoozes.pending().await.unwrap();
+        assert_eq!(pending.iter().map(|s| s.url.as_str()).collect::<Vec<_>>(), vec![
+            "https://soon.example.com",
+            "https://later.example.com",
+        ]);
+        assert_eq!(pending[1].browser, BrowserType::Firefox);
+
+        let due = snoozes.due(now).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert!(due[0].is_due(now));
+
+        assert!(snoozes.reschedule(&later.id, now - chrono::Duration::minutes(5)).await.unwrap());
+        assert!(!snoozes.reschedule(&Uuid::new_v4(), now).await.unwrap());
+        assert_eq!(snoozes.due(now).await.unwrap()[0].id, later.id);
+
+        assert!(snoozes.delete(&soon.id).await.unwrap());
+        assert!(!snoozes.delete(&soon.id).await.unwrap());
+        assert_eq!(snoozes.count().await.unwrap(), 1);
+        assert!(snoozes.get_by_id(&later.id).await.unwrap().is_some());
+    }
+}
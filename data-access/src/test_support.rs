@@ -0,0 +1,36 @@
+//! Fixtures shared by the tests of this crate
+
+use std::path::PathBuf;
+
+use web_page_manager_core::*;
+
+/// Page open in a Chrome tab, accessed now
+pub(crate) fn test_page(url: &str, title: &str) -> UnifiedPageInfo {
+    UnifiedPageInfo {
+        id: Uuid::new_v4(),
+        url: url.to_string(),
+        title: title.to_string(),
+        favicon_url: None,
+        content_summary: None,
+        keywords: vec![],
+        category: None,
+        source_type: PageSourceType::ActiveTab {
+            browser: BrowserType::Chrome,
+            tab_id: TabId::new(),
+        },
+        browser_info: None,
+        tab_info: None,
+        bookmark_info: None,
+        created_at: Utc::now(),
+        last_accessed: Utc::now(),
+        access_count: 0,
+    }
+}
+
+/// Empty directory path for one test, unique to `name` and this process;
+/// created by the caller, which removes it when done
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("wpm_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
@@ -0,0 +1,133 @@
+//! Trash retention
+//!
+//! Deleting a page or history entry only moves it to the trash, where it
+//! can be restored. A purge permanently deletes what has been in the trash
+//! longer than the retention period; it can run on a schedule like backups.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{info, warn};
+use web_page_manager_core::*;
+
+use crate::{DatabaseManager, HistoryRepository, PageRepository};
+
+/// How long deleted records stay in the trash
+#[derive(Debug, Clone)]
+pub struct TrashPolicy {
+    /// Time a record stays restorable after deletion
+    pub retention: Duration,
+    /// Time between scheduled purges
+    pub interval: Duration,
+}
+
+impl Default for TrashPolicy {
+    /// Keep deleted records for 30 days, purging daily
+    fn default() -> Self {
+        Self {
+            retention: Duration::from_secs(30 * 24 * 60 * 60),
+            interval: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// Records removed by a purge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrashPurge {
+    pub pages: usize,
+    pub history: usize,
+}
+
+impl DatabaseManager {
+    /// Permanently delete pages and history entries trashed longer than the
+    /// policy's retention
+    pub async fn purge_trash(&self, policy: &TrashPolicy) -> Result<TrashPurge> {
+        let retention = chrono::Duration::from_std(policy.retention).unwrap_or(chrono::Duration::MAX);
+        let before = Utc::now().checked_sub_signed(retention).unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+        let purge = TrashPurge {
+            pages: self.page_repository().purge_trash(before).await?,
+            history: self.history_repository().purge_trash(before).await?,
+        };
+        if purge != TrashPurge::default() {
+            info!("Purged {} pages and {} history entries from the trash", purge.pages, purge.history);
+        }
+        Ok(purge)
+    }
+
+    /// Run `purge_trash` in the background once per interval until the
+    /// returned task is aborted
+    pub fn spawn_trash_purge(self: Arc<Self>, policy: TrashPolicy) -> tokio::task::JoinHandle<()> {
+        let period = policy.interval.max(Duration::from_secs(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.purge_trash(&policy).await {
+                    warn!("Trash purge failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_page;
+
+    async fn set_deleted_at(db: &DatabaseManager, table: &'static str, id: String, deleted_at: DateTime<Utc>) {
+        db.connection()
+            .call(move |conn| {
+                conn.execute(
+                    &format!("UPDATE {} SET deleted_at = ?1 WHERE id = ?2", table),
+                    rusqlite::params![deleted_at.timestamp(), id],
+                )?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_purge_removes_only_expired_trash() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let pages = db.page_repository();
+        let history = db.history_repository();
+        let (live, recent, expired) = (
+            test_page("https://example.com/live", "Trashed page"),
+            test_page("https://example.com/recent", "Trashed page"),
+            test_page("https://example.com/expired", "Trashed page"),
+        );
+        for page in [&live, &recent, &expired] {
+            pages.save(page).await.unwrap();
+        }
+        let entry = HistoryEntry {
+            id: HistoryId::new(),
+            page_info: live.clone(),
+            browser_type: BrowserType::Firefox,
+            tab_id: None,
+            closed_at: Utc::now(),
+            session_info: None,
+            recall_hint: None,
+        };
+        history.save(&entry).await.unwrap();
+
+        pages.delete(&recent.id).await.unwrap();
+        pages.delete(&expired.id).await.unwrap();
+        history.delete(&entry.id).await.unwrap();
+        let long_ago = Utc::now() - chrono::Duration::days(40);
+        set_deleted_at(&db, "unified_pages", expired.id.to_string(), long_ago).await;
+        set_deleted_at(&db, "tab_history", entry.id.0.to_string(), long_ago).await;
+
+        let purge = db.purge_trash(&TrashPolicy::default()).await.unwrap();
+        assert_eq!(purge, TrashPurge { pages: 1, history: 1 });
+        let trash: Vec<Uuid> = pages.get_trash().await.unwrap().into_iter().map(|t| t.item.id).collect();
+        assert_eq!(trash, vec![recent.id]);
+        assert!(history.get_trash().await.unwrap().is_empty());
+        assert_eq!(pages.count().await.unwrap(), 1);
+
+        // Nothing left to purge
+        assert_eq!(db.purge_trash(&TrashPolicy::default()).await.unwrap(), TrashPurge::default());
+    }
+}
@@ -1,6 +1,7 @@
 /// Unified error handler for centralized error management
 
 use web_page_manager_core::errors::WebPageManagerError;
+use data_access::{IntegrityOutcome, IntegrityReport};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{error, warn, info};
@@ -79,6 +80,33 @@ impl UnifiedErrorHandler {
         self.add_error_entry(entry).await;
     }
 
+    /// Record a database integrity report that found problems
+    ///
+    /// The severity follows how far recovery got: repaired indexes are a
+    /// warning, salvaged data an error since rows may be lost, and
+    /// remaining corruption critical.
+    pub async fn handle_integrity_report(&self, report: &IntegrityReport) {
+        let severity = match report.outcome {
+            IntegrityOutcome::Healthy => return,
+            IntegrityOutcome::Repaired => ErrorSeverity::Warning,
+            IntegrityOutcome::Recovered => ErrorSeverity::Error,
+            IntegrityOutcome::Unrecovered => ErrorSeverity::Critical,
+        };
+        let summary = report.summary();
+        match severity {
+            ErrorSeverity::Critical | ErrorSeverity::Error => error!("Database integrity: {}", summary),
+            _ => warn!("Database integrity: {}", summary),
+        }
+
+        self.add_error_entry(ErrorEntry {
+            error: summary,
+            severity,
+            timestamp: report.checked_at,
+            context: "database integrity".to_string(),
+        })
+        .await;
+    }
+
     /// Classify error severity
     fn classify_error(&self, error: &WebPageManagerError) -> ErrorSeverity {
         use WebPageManagerError::*;
@@ -187,4 +215,30 @@ mod tests {
         assert_eq!(errors.len(), 1);
         assert_eq!(errors[0].context, "test_context");
     }
+
+    #[tokio::test]
+    async fn test_handle_integrity_report() {
+        let handler = UnifiedErrorHandler::new();
+        let mut report = IntegrityReport {
+            checked_at: chrono::Utc::now(),
+            outcome: IntegrityOutcome::Healthy,
+            problems: vec![],
+            remaining: vec![],
+            rebuilt: false,
+            salvage: None,
+        };
+        handler.handle_integrity_report(&report).await;
+        assert_eq!(handler.get_error_stats().await.total, 0);
+
+        report.outcome = IntegrityOutcome::Unrecovered;
+        report.problems = vec!["Tree 7 page 12: btreeInitPage() returns error code 11".to_string()];
+        report.remaining = report.problems.clone();
+        handler.handle_integrity_report(&report).await;
+
+        let errors = handler.get_recent_errors().await;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].severity, ErrorSeverity::Critical);
+        assert_eq!(errors[0].context, "database integrity");
+        assert!(errors[0].error.contains("btreeInitPage"));
+    }
 }
@@ -75,6 +75,9 @@ pub struct AppContext {
 
     /// Application configuration
     pub config: Arc<RwLock<AppConfig>>,
+
+    /// Scheduled database integrity checks, for file databases
+    integrity_checks: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl AppContext {
@@ -82,11 +85,12 @@ impl AppContext {
     pub async fn new(config: AppConfig) -> Result<Self> {
         info!("Initializing application context");
 
-        // Initialize database
+        // Initialize database, with its caches held to the configured size
+        let cache_config = data_access::CacheConfig::default().with_memory_budget_mb(config.cache_size_mb);
         let database = if let Some(path) = &config.database_path {
-            Arc::new(data_access::DatabaseManager::new(path).await?)
+            Arc::new(data_access::DatabaseManager::with_cache_config(path, cache_config).await?)
         } else {
-            Arc::new(data_access::DatabaseManager::in_memory().await?)
+            Arc::new(data_access::DatabaseManager::in_memory_with_cache(cache_config).await?)
         };
         info!("Database initialized");
 
@@ -94,9 +98,14 @@ impl AppContext {
         let browser_manager = Arc::new(browser_connector::BrowserConnectorManager::new());
         info!("Browser connector manager initialized");
 
-        // Initialize page manager
-        let page_manager = Arc::new(page_manager::PageUnifiedManager::new());
-        info!("Page manager initialized");
+        // Initialize page manager, restoring the pages of the last run
+        let page_manager = Arc::new(
+            page_manager::PageUnifiedManager::new()
+                .with_page_repository(Arc::new(database.page_repository()))
+                .with_note_repository(Arc::new(database.note_repository())),
+        );
+        let loaded = page_manager.load().await?;
+        info!("Page manager initialized with {} saved pages", loaded);
 
         // Initialize UI manager
         let ui_manager = Arc::new(RwLock::new(
@@ -107,6 +116,19 @@ impl AppContext {
         // Initialize error handler
         let error_handler = Arc::new(UnifiedErrorHandler::new());
 
+        // Check the database now and daily, reporting problems to the error handler
+        let integrity_checks = config.database_path.as_ref().map(|path| {
+            let salvage_dir = path.parent().unwrap_or(std::path::Path::new(".")).join("salvage");
+            let handler = error_handler.clone();
+            database.clone().spawn_integrity_checks(
+                data_access::IntegrityPolicy::daily(salvage_dir),
+                move |report| {
+                    let handler = handler.clone();
+                    tokio::spawn(async move { handler.handle_integrity_report(&report).await });
+                },
+            )
+        });
+
         let config = Arc::new(RwLock::new(config));
 
         info!("Application context initialized successfully");
@@ -118,6 +140,7 @@ impl AppContext {
             ui_manager,
             error_handler,
             config,
+            integrity_checks,
         })
     }
 
@@ -125,6 +148,10 @@ impl AppContext {
     pub async fn shutdown(&self) -> Result<()> {
         info!("Shutting down application context");
 
+        if let Some(task) = &self.integrity_checks {
+            task.abort();
+        }
+
         // Disconnect all browsers
         if let Err(e) = self.browser_manager.disconnect_all().await {
             warn!("Error disconnecting browsers: {}", e);
@@ -198,4 +225,36 @@ mod tests {
         let result = context.shutdown().await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_pages_survive_restart() {
+        let dir = std::env::temp_dir().join(format!("app-context-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = AppConfig {
+            database_path: Some(dir.join("pages.db")),
+            ..AppConfig::default()
+        };
+
+        let context = AppContext::new(config.clone()).await.unwrap();
+        let bookmark = BookmarkInfo {
+            id: BookmarkId::new(),
+            url: "https://example.com/docs".to_string(),
+            title: "Docs".to_string(),
+            favicon_url: None,
+            browser_type: BrowserType::Chrome,
+            folder_path: vec![],
+            created_at: chrono::Utc::now(),
+            last_accessed: None,
+        };
+        context.page_manager.update_bookmarks(vec![bookmark.clone()]).await;
+        context.shutdown().await.unwrap();
+        drop(context);
+
+        let restarted = AppContext::new(config).await.unwrap();
+        let pages = restarted.get_all_pages().await;
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].url, bookmark.url);
+        restarted.shutdown().await.unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
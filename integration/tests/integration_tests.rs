@@ -118,6 +118,7 @@ async fn test_all_components_initialized() {
     // Database
     let db_stats = context.database.stats().await.unwrap();
     assert!(db_stats.cache_stats.pages_max > 0);
+    assert_eq!(db_stats.cache_stats.memory_budget_bytes, 10 * 1024 * 1024);
 
     // Browser manager
     let browsers = context.browser_manager.get_connected_browsers().await;
@@ -901,7 +901,7 @@ impl ContentArchiver {
     }
 
     /// Generate a filename for a media file based on its URL
-    fn generate_media_filename(&self, url: &str) -> String {
+    pub(crate) fn generate_media_filename(&self, url: &str) -> String {
         // Extract filename from URL
         let path = url.split('?').next().unwrap_or(url);
         let filename = path.rsplit('/').next().unwrap_or("media");
@@ -929,7 +929,7 @@ impl ContentArchiver {
     }
 
     /// Guess MIME type from URL
-    fn guess_mime_type(&self, url: &str) -> Option<String> {
+    pub(crate) fn guess_mime_type(&self, url: &str) -> Option<String> {
         let lower_url = url.to_lowercase();
         let path = lower_url.split('?').next().unwrap_or(&lower_url);
         
@@ -0,0 +1,470 @@
+//! Page Relationship Graph
+//!
+//! Connects saved pages by how they relate: a hyperlink from one page to
+//! another, taken from the links extracted from its content; a shared
+//! domain; and having been open together in a session, saved or closed.
+//! Traversals answer questions like "what did I open from this page?", and
+//! the graph exports to DOT or JSON for visualization.
+
+use web_page_manager_core::*;
+use data_access::SavedSession;
+use crate::history::ClosedSession;
+use crate::matcher::TabBookmarkMatcher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+use tracing::debug;
+
+/// How two pages are related
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PageRelation {
+    /// The first page links to the second
+    Link,
+    /// Both pages are on the same domain
+    SameDomain,
+    /// The pages were open together in a session
+    CoOpened,
+}
+
+impl PageRelation {
+    /// Whether the relation goes one way, from the edge's `from` page
+    pub fn is_directed(self) -> bool {
+        matches!(self, PageRelation::Link)
+    }
+}
+
+/// A page in the graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageNode {
+    pub id: Uuid,
+    pub url: String,
+    pub title: String,
+    pub domain: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A relation between two pages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageEdge {
+    pub from: Uuid,
+    pub to: Uuid,
+    pub relation: PageRelation,
+    /// Number of sessions the pages were open in together, 1 for other
+    /// relations
+    pub weight: u32,
+}
+
+/// A page related to another, seen from that page
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageNeighbor {
+    pub page_id: Uuid,
+    pub relation: PageRelation,
+    pub weight: u32,
+    /// Whether the relation points away from the page, always true for
+    /// undirected relations
+    pub outgoing: bool,
+}
+
+/// Builder collecting the pages and relations of a graph
+pub struct PageGraphBuilder {
+    matcher: TabBookmarkMatcher,
+    pages: Vec<UnifiedPageInfo>,
+    links: HashMap<Uuid, Vec<String>>,
+    sessions: Vec<Vec<String>>,
+    max_domain_pages: usize,
+}
+
+impl PageGraphBuilder {
+    /// Start a graph of `pages`
+    pub fn new(pages: &[UnifiedPageInfo]) -> Self {
+        Self {
+            matcher: TabBookmarkMatcher::new(),
+            pages: pages.to_vec(),
+            links: HashMap::new(),
+            sessions: Vec::new(),
+            max_domain_pages: 25,
+        }
+    }
+
+    /// Match URLs with `matcher` instead of the default one
+    pub fn with_matcher(mut self, matcher: TabBookmarkMatcher) -> Self {
+        self.matcher = matcher;
+        self
+    }
+
+    /// Add the hyperlinks extracted from pages, by page ID
+    pub fn with_links<I>(mut self, links: I) -> Self
+    where
+        I: IntoIterator<Item = (Uuid, Vec<String>)>,
+    {
+        for (page_id, urls) in links {
+            self.links.entry(page_id).or_default().extend(urls);
+        }
+        self
+    }
+
+    /// Add the links of a page's extracted content
+    pub fn with_content(self, page_id: Uuid, content: &PageContent) -> Self {
+        self.with_links([(page_id, content.links.clone())])
+    }
+
+    /// Relate the pages open together in each saved session
+    pub fn with_saved_sessions(mut self, sessions: &[SavedSession]) -> Self {
+        self.sessions.extend(sessions.iter().map(|s| {
+            s.windows.iter().flat_map(|w| w.tabs.iter().map(|t| t.url.clone())).collect()
+        }));
+        self
+    }
+
+    /// Relate the pages closed together in each closed session
+    pub fn with_closed_sessions(mut self, sessions: &[ClosedSession]) -> Self {
+        self.sessions
+            .extend(sessions.iter().map(|s| s.entries.iter().map(|e| e.page_info.url.clone()).collect()));
+        self
+    }
+
+    /// Relate pages by domain only on domains with at most `max` pages, so
+    /// a site with many saved pages does not connect them all (default 25)
+    pub fn with_max_domain_pages(mut self, max: usize) -> Self {
+        self.max_domain_pages = max;
+        self
+    }
+
+    /// Build the graph
+    pub fn build(self) -> PageGraph {
+        let mut graph = PageGraph::default();
+        let mut by_url: HashMap<String, Uuid> = HashMap::new();
+        for page in &self.pages {
+            if graph.index.contains_key(&page.id) {
+                continue;
+            }
+            by_url.entry(self.matcher.normalize_url(&page.url)).or_insert(page.id);
+            graph.index.insert(page.id, graph.nodes.len());
+            graph.nodes.push(PageNode {
+                id: page.id,
+                url: page.url.clone(),
+                title: page.title.clone(),
+                domain: self.matcher.extract_domain(&page.url),
+                created_at: page.created_at,
+            });
+        }
+        let resolve = |url: &str| by_url.get(&self.matcher.normalize_url(url)).copied();
+
+        // Hyperlinks, in page order
+        for node in &graph.nodes {
+            let mut targets = HashSet::new();
+            for url in self.links.get(&node.id).into_iter().flatten() {
+                if let Some(target) = resolve(url).filter(|t| *t != node.id) {
+                    if targets.insert(target) {
+                        graph.edges.push(PageEdge {
+                            from: node.id,
+                            to: target,
+                            relation: PageRelation::Link,
+                            weight: 1,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Shared domains
+        let mut domains: HashMap<&str, Vec<Uuid>> = HashMap::new();
+        for node in &graph.nodes {
+            if let Some(domain) = &node.domain {
+                domains.entry(domain.as_str()).or_default().push(node.id);
+            }
+        }
+        let mut domain_edges = Vec::new();
+        for node in &graph.nodes {
+            let Some(members) = node.domain.as_deref().and_then(|d| domains.get(d)) else {
+                continue;
+            };
+            if members.len() > self.max_domain_pages {
+                continue;
+            }
+            for other in members.iter().skip_while(|m| **m != node.id).skip(1) {
+                domain_edges.push(PageEdge {
+                    from: node.id,
+                    to: *other,
+                    relation: PageRelation::SameDomain,
+                    weight: 1,
+                });
+            }
+        }
+        graph.edges.extend(domain_edges);
+
+        // Sessions, counting each pair once per session
+        let mut together: HashMap<(Uuid, Uuid), u32> = HashMap::new();
+        let mut pairs = Vec::new();
+        for session in &self.sessions {
+            let mut members: Vec<Uuid> = session.iter().filter_map(|url| resolve(url)).collect();
+            members.sort_by_key(|id| graph.index[id]);
+            members.dedup();
+            for (i, a) in members.iter().enumerate() {
+                for b in &members[i + 1..] {
+                    let count = together.entry((*a, *b)).or_insert(0);
+                    if *count == 0 {
+                        pairs.push((*a, *b));
+                    }
+                    *count += 1;
+                }
+            }
+        }
+        graph.edges.extend(pairs.into_iter().map(|(from, to)| PageEdge {
+            from,
+            to,
+            relation: PageRelation::CoOpened,
+            weight: together[&(from, to)],
+        }));
+
+        debug!("Built page graph with {} pages and {} edges", graph.nodes.len(), graph.edges.len());
+        graph
+    }
+}
+
+/// Graph of saved pages and their relations
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageGraph {
+    nodes: Vec<PageNode>,
+    edges: Vec<PageEdge>,
+    #[serde(skip)]
+    index: HashMap<Uuid, usize>,
+}
+
+impl PageGraph {
+    /// Pages in the order they were given
+    pub fn nodes(&self) -> &[PageNode] {
+        &self.nodes
+    }
+
+    /// Relations: links first, then shared domains, then sessions
+    pub fn edges(&self) -> &[PageEdge] {
+        &self.edges
+    }
+
+    pub fn node(&self, id: &Uuid) -> Option<&PageNode> {
+        self.index.get(id).map(|&i| &self.nodes[i])
+    }
+
+    /// Pages related to `id`, in edge order
+    pub fn neighbors(&self, id: &Uuid) -> Vec<PageNeighbor> {
+        self.edges
+            .iter()
+            .filter_map(|edge| {
+                let (page_id, outgoing) = if edge.from == *id {
+                    (edge.to, true)
+                } else if edge.to == *id {
+                    (edge.from, !edge.relation.is_directed())
+                } else {
+                    return None;
+                };
+                Some(PageNeighbor {
+                    page_id,
+                    relation: edge.relation,
+                    weight: edge.weight,
+                    outgoing,
+                })
+            })
+            .collect()
+    }
+
+    /// Pages `id` links to
+    pub fn links_from(&self, id: &Uuid) -> Vec<Uuid> {
+        self.neighbors(id)
+            .into_iter()
+            .filter(|n| n.relation == PageRelation::Link && n.outgoing)
+            .map(|n| n.page_id)
+            .collect()
+    }
+
+    /// Pages linking to `id`
+    pub fn links_to(&self, id: &Uuid) -> Vec<Uuid> {
+        self.neighbors(id)
+            .into_iter()
+            .filter(|n| n.relation == PageRelation::Link && !n.outgoing)
+            .map(|n| n.page_id)
+            .collect()
+    }
+
+    /// Pages likely opened from `id`: pages it links to that were first
+    /// saved after it, in the order they were saved
+    pub fn opened_from(&self, id: &Uuid) -> Vec<Uuid> {
+        let Some(source) = self.node(id) else {
+            return Vec::new();
+        };
+        let mut opened: Vec<&PageNode> = self
+            .links_from(id)
+            .iter()
+            .filter_map(|p| self.node(p))
+            .filter(|p| p.created_at >= source.created_at)
+            .collect();
+        opened.sort_by_key(|p| p.created_at);
+        opened.into_iter().map(|p| p.id).collect()
+    }
+
+    /// Pages within `depth` steps of `id` over `relations` (every relation
+    /// if empty), following links only forwards, with their distance,
+    /// nearest first
+    pub fn reachable(&self, id: &Uuid, depth: usize, relations: &[PageRelation]) -> Vec<(Uuid, usize)> {
+        if !self.index.contains_key(id) {
+            return Vec::new();
+        }
+        let mut seen = HashSet::from([*id]);
+        let mut queue = VecDeque::from([(*id, 0)]);
+        let mut found = Vec::new();
+        while let Some((page, distance)) = queue.pop_front() {
+            if distance == depth {
+                continue;
+            }
+            for neighbor in self.neighbors(&page) {
+                if !neighbor.outgoing || !(relations.is_empty() || relations.contains(&neighbor.relation)) {
+                    continue;
+                }
+                if seen.insert(neighbor.page_id) {
+                    found.push((neighbor.page_id, distance + 1));
+                    queue.push_back((neighbor.page_id, distance + 1));
+                }
+            }
+        }
+        found
+    }
+
+    /// The graph in Graphviz DOT format
+    ///
+    /// Links are solid arrows, shared domains dotted lines and sessions
+    /// dashed lines labeled with their count.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph pages {\n    node [shape=box];\n");
+        for node in &self.nodes {
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\", tooltip=\"{}\"];",
+                node.id,
+                dot_escape(if node.title.is_empty() { &node.url } else { &node.title }),
+                dot_escape(&node.url)
+            );
+        }
+        for edge in &self.edges {
+            let attributes = match edge.relation {
+                PageRelation::Link => String::new(),
+                PageRelation::SameDomain => " [dir=none, style=dotted]".to_string(),
+                PageRelation::CoOpened => format!(" [dir=none, style=dashed, label=\"{}\"]", edge.weight),
+            };
+            let _ = writeln!(dot, "    \"{}\" -> \"{}\"{};", edge.from, edge.to, attributes);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The graph as JSON with `nodes` and `edges`
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Failed to serialize page graph: {}", e),
+            },
+        })
+    }
+}
+
+/// Escape a string for a quoted DOT identifier
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_page;
+    use chrono::Duration;
+    use data_access::{SessionTab, SessionWindow};
+
+    fn page(url: &str, title: &str, minutes_ago: i64) -> UnifiedPageInfo {
+        UnifiedPageInfo {
+            created_at: Utc::now() - Duration::minutes(minutes_ago),
+            ..test_page(url, title, &[])
+        }
+    }
+
+    fn session(urls: &[&str]) -> SavedSession {
+        SavedSession {
+            id: Uuid::new_v4(),
+            name: "Work".to_string(),
+            windows: vec![SessionWindow {
+                browser: BrowserType::Chrome,
+                tabs: urls
+                    .iter()
+                    .map(|url| SessionTab {
+                        url: url.to_string(),
+                        title: String::new(),
+                        favicon_url: None,
+                        pinned: false,
+                    })
+                    .collect(),
+            }],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_page_graph_relations_and_export() {
+        let docs = page("https://docs.rs/tokio", "Tokio \"docs\"", 30);
+        let tutorial = page("https://tokio.rs/tutorial", "Tutorial", 20);
+        let older = page("https://tokio.rs/blog", "Blog", 60);
+        let news = page("https://news.example.com", "News", 10);
+        let pages = vec![docs.clone(), tutorial.clone(), older.clone(), news.clone()];
+
+        let graph = PageGraphBuilder::new(&pages)
+            .with_links([(
+                docs.id,
+                vec![
+                    "https://tokio.rs/tutorial/".to_string(),
+                    "https://tokio.rs/blog#latest".to_string(),
+                    "https://unsaved.example.com".to_string(),
+                    "https://docs.rs/tokio".to_string(),
+                ],
+            )])
+            .with_saved_sessions(&[
+                session(&["https://docs.rs/tokio", "https://news.example.com"]),
+                session(&["https://news.example.com", "https://docs.rs/tokio", "https://tokio.rs/blog"]),
+            ])
+            .build();
+
+        // Links resolve through URL normalization, self-links are dropped
+        assert_eq!(graph.links_from(&docs.id), vec![tutorial.id, older.id]);
+        assert_eq!(graph.links_to(&tutorial.id), vec![docs.id]);
+        // The blog was saved before the docs, so it was not opened from them
+        assert_eq!(graph.opened_from(&docs.id), vec![tutorial.id]);
+
+        let same_domain: Vec<&PageEdge> =
+            graph.edges().iter().filter(|e| e.relation == PageRelation::SameDomain).collect();
+        assert_eq!(same_domain.len(), 1);
+        assert_eq!((same_domain[0].from, same_domain[0].to), (tutorial.id, older.id));
+
+        let co_opened: Vec<PageNeighbor> = graph
+            .neighbors(&news.id)
+            .into_iter()
+            .filter(|n| n.relation == PageRelation::CoOpened)
+            .collect();
+        assert_eq!(co_opened.len(), 2);
+        assert_eq!((co_opened[0].page_id, co_opened[0].weight), (docs.id, 2));
+        assert!(co_opened.iter().all(|n| n.outgoing));
+
+        // Links are followed forwards only
+        assert_eq!(graph.reachable(&tutorial.id, 2, &[PageRelation::Link]), vec![]);
+        assert_eq!(
+            graph.reachable(&tutorial.id, 2, &[]),
+            vec![(older.id, 1), (docs.id, 2), (news.id, 2)]
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph pages {"));
+        assert!(dot.contains("label=\"Tokio \\\"docs\\\"\""));
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\";", docs.id, tutorial.id)));
+        assert!(dot.contains("style=dashed, label=\"2\""));
+
+        let json: serde_json::Value = serde_json::from_str(&graph.to_json().unwrap()).unwrap();
+        assert_eq!(json["nodes"].as_array().unwrap().len(), 4);
+        assert_eq!(json["edges"].as_array().unwrap().len(), graph.edges().len());
+        assert_eq!(json["edges"][0]["relation"], "Link");
+    }
+}
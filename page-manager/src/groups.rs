@@ -0,0 +1,274 @@
+//! Smart Group Lifecycle
+//!
+//! Operations users apply to groups once they exist: merging two groups,
+//! splitting selected pages out into a new group, archiving a group whose
+//! project is finished and ordering the pages within a group by hand.
+//!
+//! Merged and split groups become user-defined, so regenerating smart
+//! groups no longer changes them. Archived groups are left out of the
+//! active group list but are still found by search.
+
+use web_page_manager_core::*;
+use data_access::{ArchivedGroup, GroupRepository, GroupStateRepository};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::info;
+
+/// Manager for merging, splitting, archiving and ordering smart groups
+pub struct GroupManager {
+    groups: Arc<dyn GroupRepository>,
+    state: Arc<dyn GroupStateRepository>,
+}
+
+impl GroupManager {
+    /// Create a manager over the groups of `groups`, keeping archive state
+    /// and page order in `state`
+    pub fn new(groups: Arc<dyn GroupRepository>, state: Arc<dyn GroupStateRepository>) -> Self {
+        Self { groups, state }
+    }
+
+    /// Move every page of `source` into `target` and delete `source`,
+    /// returning the merged group
+    ///
+    /// The source's pages follow the target's in the merged order.
+    pub async fn merge(&self, source: &Uuid, target: &Uuid) -> Result<SmartGroup> {
+        if source == target {
+            return Err(group_error(format!("Cannot merge group {} into itself", source)));
+        }
+        let from = self.load(source).await?;
+        let mut into = self.load(target).await?;
+
+        let mut order = self.pages_in_order(target).await?;
+        for page in self.pages_in_order(source).await? {
+            if !order.contains(&page) {
+                order.push(page);
+            }
+        }
+        into.pages = order.clone();
+        into.auto_generated = false;
+        self.save(&into).await?;
+        self.state.set_page_order(target, &order).await?;
+
+        self.groups.delete(source).await?;
+        self.state.clear(source).await?;
+        info!("Merged group '{}' into '{}'", from.name, into.name);
+        Ok(into)
+    }
+
+    /// Move the pages of `page_ids` out of a group into a new group named
+    /// `name`, returning the new group
+    ///
+    /// Pages not in the group are ignored; it is an error if none are.
+    pub async fn split(&self, group_id: &Uuid, page_ids: &[Uuid], name: &str) -> Result<SmartGroup> {
+        let mut original = self.load(group_id).await?;
+        let order = self.pages_in_order(group_id).await?;
+        let (moved, kept): (Vec<Uuid>, Vec<Uuid>) = order.into_iter().partition(|p| page_ids.contains(p));
+        if moved.is_empty() {
+            return Err(group_error(format!("None of the pages are in group {}", group_id)));
+        }
+
+        let split = SmartGroup {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: format!("Split from {}", original.name),
+            group_type: GroupType::UserDefined,
+            pages: moved,
+            created_at: Utc::now(),
+            auto_generated: false,
+            similarity_threshold: original.similarity_threshold,
+        };
+        self.save(&split).await?;
+
+        let had_order = !self.state.page_order(group_id).await?.is_empty();
+        original.pages = kept;
+        original.auto_generated = false;
+        self.save(&original).await?;
+        if had_order {
+            self.state.set_page_order(group_id, &original.pages).await?;
+            self.state.set_page_order(&split.id, &split.pages).await?;
+        }
+
+        info!("Split {} pages out of group '{}' into '{}'", split.pages.len(), original.name, split.name);
+        Ok(split)
+    }
+
+    /// Archive a finished group, hiding it from the active groups
+    pub async fn archive(&self, group_id: &Uuid) -> Result<ArchivedGroup> {
+        self.load(group_id).await?;
+        self.state.archive(group_id).await
+    }
+
+    /// Bring a group back from the archive; false if it was not archived
+    pub async fn unarchive(&self, group_id: &Uuid) -> Result<bool> {
+        self.state.unarchive(group_id).await
+    }
+
+    /// Groups not archived, most recent first
+    pub async fn active_groups(&self) -> Result<Vec<SmartGroup>> {
+        let archived: Vec<Uuid> = self.state.archived().await?.into_iter().map(|a| a.group_id).collect();
+        Ok(self.groups.get_all().await?.into_iter().filter(|g| !archived.contains(&g.id)).collect())
+    }
+
+    /// Archived groups, most recently archived first
+    pub async fn archived_groups(&self) -> Result<Vec<(SmartGroup, ArchivedGroup)>> {
+        let mut groups: HashMap<Uuid, SmartGroup> = self.groups.get_all().await?.into_iter().map(|g| (g.id, g)).collect();
+        Ok(self
+            .state
+            .archived()
+            .await?
+            .into_iter()
+            .filter_map(|a| groups.remove(&a.group_id).map(|g| (g, a)))
+            .collect())
+    }
+
+    /// Groups whose name or description contains `query`, ignoring case,
+    /// archived groups included
+    pub async fn search_groups(&self, query: &str) -> Result<Vec<SmartGroup>> {
+        let query = query.trim().to_lowercase();
+        Ok(self
+            .groups
+            .get_all()
+            .await?
+            .into_iter()
+            .filter(|g| g.name.to_lowercase().contains(&query) || g.description.to_lowercase().contains(&query))
+            .collect())
+    }
+
+    /// Put the pages of a group in the order of `page_ids`
+    ///
+    /// Pages not in the group are ignored; the group's other pages follow
+    /// the listed ones.
+    pub async fn reorder_pages(&self, group_id: &Uuid, page_ids: &[Uuid]) -> Result<()> {
+        let group = self.load(group_id).await?;
+        let order: Vec<Uuid> = page_ids.iter().filter(|p| group.pages.contains(p)).copied().collect();
+        self.state.set_page_order(group_id, &order).await
+    }
+
+    /// Pages of a group, manually ordered pages first
+    pub async fn pages_in_order(&self, group_id: &Uuid) -> Result<Vec<Uuid>> {
+        let mut pages = self.groups.get_pages_in_group(group_id).await?;
+        let order: HashMap<Uuid, usize> = self
+            .state
+            .page_order(group_id)
+            .await?
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| (p, i))
+            .collect();
+        // Stable, so unordered pages keep the repository's order
+        pages.sort_by_key(|p| order.get(p).copied().unwrap_or(usize::MAX));
+        Ok(pages)
+    }
+
+    /// Load a group with its pages, failing if it does not exist
+    async fn load(&self, id: &Uuid) -> Result<SmartGroup> {
+        let mut group = self
+            .groups
+            .get_by_id(id)
+            .await?
+            .ok_or_else(|| group_error(format!("Group not found: {}", id)))?;
+        group.pages = self.groups.get_pages_in_group(id).await?;
+        Ok(group)
+    }
+
+    /// Save a group with exactly its pages; saving replaces the group's
+    /// row, which drops its memberships
+    async fn save(&self, group: &SmartGroup) -> Result<()> {
+        self.groups.save(group).await?;
+        for page in &group.pages {
+            self.groups.add_page_to_group(page, &group.id, 1.0).await?;
+        }
+        Ok(())
+    }
+}
+
+fn group_error(details: String) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration { details },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_page;
+    use data_access::{DatabaseManager, PageRepository};
+
+    fn group(name: &str, pages: &[Uuid]) -> SmartGroup {
+        SmartGroup {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: format!("{} pages", name),
+            group_type: GroupType::Topic(name.to_string()),
+            pages: pages.to_vec(),
+            created_at: Utc::now(),
+            auto_generated: true,
+            similarity_threshold: 0.5,
+        }
+    }
+
+    fn sorted(mut ids: Vec<Uuid>) -> Vec<Uuid> {
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_group_lifecycle() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let mut pages = Vec::new();
+        for i in 0..5 {
+            let url = format!("https://example.com/{}", i);
+            let page = test_page(&url, &url, &[]);
+            db.page_repository().save(&page).await.unwrap();
+            pages.push(page.id);
+        }
+        let repository = Arc::new(db.group_repository());
+        let rust = group("Rust", &pages[..3]);
+        let tokio = group("Tokio", &pages[3..]);
+        for g in [&rust, &tokio] {
+            repository.save(g).await.unwrap();
+            for p in &g.pages {
+                repository.add_page_to_group(p, &g.id, 0.8).await.unwrap();
+            }
+        }
+        let manager = GroupManager::new(repository.clone(), Arc::new(db.group_state_repository()));
+
+        // Manual order is kept, unlisted pages follow
+        manager.reorder_pages(&rust.id, &[pages[2], pages[4], pages[0]]).await.unwrap();
+        let order = manager.pages_in_order(&rust.id).await.unwrap();
+        assert_eq!(order, vec![pages[2], pages[0], pages[1]]);
+
+        // Merging appends the source's pages and deletes it
+        manager.reorder_pages(&tokio.id, &[pages[4], pages[3]]).await.unwrap();
+        let merged = manager.merge(&tokio.id, &rust.id).await.unwrap();
+        assert!(!merged.auto_generated);
+        assert_eq!(
+            manager.pages_in_order(&rust.id).await.unwrap(),
+            vec![pages[2], pages[0], pages[1], pages[4], pages[3]]
+        );
+        assert!(repository.get_by_id(&tokio.id).await.unwrap().is_none());
+        assert!(manager.merge(&rust.id, &rust.id).await.is_err());
+
+        // Splitting moves the selected pages, keeping their order
+        let split = manager.split(&rust.id, &[pages[3], pages[0], Uuid::new_v4()], "Async").await.unwrap();
+        assert_eq!(manager.pages_in_order(&split.id).await.unwrap(), vec![pages[0], pages[3]]);
+        assert_eq!(manager.pages_in_order(&rust.id).await.unwrap(), vec![pages[2], pages[1], pages[4]]);
+        assert!(manager.split(&rust.id, &[pages[0]], "Empty").await.is_err());
+        let original = repository.get_by_id(&rust.id).await.unwrap().unwrap();
+        assert!(!original.auto_generated);
+        assert_eq!(sorted(repository.get_pages_in_group(&split.id).await.unwrap()), sorted(vec![pages[0], pages[3]]));
+
+        // Archived groups are hidden but still found
+        let archived = manager.archive(&split.id).await.unwrap();
+        let active: Vec<Uuid> = manager.active_groups().await.unwrap().iter().map(|g| g.id).collect();
+        assert_eq!(active, vec![rust.id]);
+        let listed = manager.archived_groups().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!((listed[0].0.id, &listed[0].1), (split.id, &archived));
+        let found = manager.search_groups("split from").await.unwrap();
+        assert_eq!(found.iter().map(|g| g.id).collect::<Vec<_>>(), vec![split.id]);
+        assert!(manager.unarchive(&split.id).await.unwrap());
+        assert_eq!(manager.active_groups().await.unwrap().len(), 2);
+        assert!(manager.archive(&Uuid::new_v4()).await.is_err());
+    }
+}
@@ -4,9 +4,11 @@
 //! - Automatic saving of closed tab information
 //! - History record query and filtering
 //! - Rich history information with content summaries and tags
-//! - Tab restoration to specified browsers
-//! - Automatic cleanup strategies based on time and importance
-//! - History export and backup functionality
+//! - Tab restoration to specified browsers, one tab or a whole closed
+//!   session at a time
+//! - Automatic cleanup strategies based on time, importance, category and size,
+//!   with pinned entries exempt and a preview before deleting
+//! - History export to JSON, CSV, an HTML report and Netscape bookmark files
 //!
 //! # Requirements Implemented
 //! - 7.1: Auto-save closed tab complete information to history
@@ -17,7 +19,7 @@
 
 use web_page_manager_core::*;
 use browser_connector::{TabEvent, TabMonitor, BrowserConnector};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::path::Path;
 use tokio::sync::RwLock;
@@ -96,6 +98,21 @@ pub struct RestoreResult {
     pub restored_at: DateTime<Utc>,
 }
 
+/// Tabs closed together: from one window of a browser, in one burst
+#[derive(Debug, Clone)]
+pub struct ClosedSession {
+    /// Browser the tabs were closed in
+    pub browser_type: BrowserType,
+    /// Window the tabs were closed from, if known
+    pub window_id: Option<String>,
+    /// The closed tabs in their order in the window
+    pub entries: Vec<HistoryEntry>,
+    /// When the first tab was closed
+    pub first_closed: DateTime<Utc>,
+    /// When the last tab was closed
+    pub last_closed: DateTime<Utc>,
+}
+
 /// Cleanup result containing statistics about the cleanup operation
 #[derive(Debug, Clone)]
 pub struct CleanupResult {
@@ -103,14 +120,37 @@ pub struct CleanupResult {
     pub deleted_by_age: usize,
     /// Number of entries deleted due to max entries limit
     pub deleted_by_limit: usize,
+    /// Number of entries deleted to fit the size cap
+    pub deleted_by_size: usize,
     /// Number of important entries preserved
     pub preserved_important: usize,
+    /// Number of pinned or bookmarked entries kept past their age
+    pub preserved_exempt: usize,
     /// Total entries remaining after cleanup
     pub remaining_entries: usize,
     /// Timestamp of the cleanup
     pub cleaned_at: DateTime<Utc>,
 }
 
+/// Entries a cleanup would delete, and why, without deleting them
+#[derive(Debug, Clone, Default)]
+pub struct CleanupPreview {
+    /// The entries that would be deleted, oldest first
+    pub entries: Vec<HistoryEntry>,
+    /// Number of entries past their age
+    pub deleted_by_age: usize,
+    /// Number of entries over the max entries limit
+    pub deleted_by_limit: usize,
+    /// Number of entries over the size cap
+    pub deleted_by_size: usize,
+    /// Number of old entries kept as important
+    pub preserved_important: usize,
+    /// Number of pinned or bookmarked entries kept past their age
+    pub preserved_exempt: usize,
+    /// Serialized size of the deleted entries in bytes
+    pub bytes_freed: u64,
+}
+
 /// Export format for history data
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ExportFormat {
@@ -120,6 +160,8 @@ pub enum ExportFormat {
     Csv,
     /// HTML format for browser viewing
     Html,
+    /// Netscape bookmark file, which browsers import as bookmarks
+    NetscapeBookmarks,
 }
 
 /// Exported history data structure
@@ -162,6 +204,8 @@ pub struct TabHistoryManager {
     stats: Arc<RwLock<HistoryManagerStats>>,
     /// Reference to tab monitor for event subscription
     tab_monitor: Option<Arc<TabMonitor>>,
+    /// Entries exempt from cleanup
+    pinned: Arc<RwLock<HashSet<HistoryId>>>,
 }
 
 impl TabHistoryManager {
@@ -178,6 +222,7 @@ impl TabHistoryManager {
             content_summaries: Arc::new(RwLock::new(HashMap::new())),
             stats: Arc::new(RwLock::new(HistoryManagerStats::default())),
             tab_monitor: None,
+            pinned: Arc::new(RwLock::new(HashSet::new())),
         }
     }
 
@@ -279,6 +324,20 @@ impl TabHistoryManager {
         &self,
         tab: TabInfo,
         close_time: DateTime<Utc>,
+    ) -> Result<HistoryId> {
+        self.save_closed_tab_in_session(tab, close_time, None).await
+    }
+
+    /// Save a closed tab to history with where it was in its window
+    ///
+    /// The window, position and pinned state let `restore_session` put
+    /// the tab back where it was. Without session info the tab gets a
+    /// session of its own.
+    pub async fn save_closed_tab_in_session(
+        &self,
+        tab: TabInfo,
+        close_time: DateTime<Utc>,
+        session_info: Option<SessionInfo>,
     ) -> Result<HistoryId> {
         let history_id = HistoryId::new();
 
@@ -312,12 +371,13 @@ impl TabHistoryManager {
         };
 
         // Create session info
-        let session_info = SessionInfo {
+        let session_info = session_info.unwrap_or_else(|| SessionInfo {
             session_id: uuid::Uuid::new_v4().to_string(),
             window_id: None,
             tab_index: None,
             scroll_position: None,
-        };
+            pinned: false,
+        });
 
         let entry = HistoryEntry {
             id: history_id.clone(),
@@ -326,6 +386,7 @@ impl TabHistoryManager {
             tab_id: Some(tab.id),
             closed_at: close_time,
             session_info: Some(session_info),
+            recall_hint: None,
         };
 
         // Add to cache
@@ -387,6 +448,55 @@ impl TabHistoryManager {
         summaries.insert(url.to_string(), summary);
     }
 
+    /// Attach a "why you saved this" hint to a history entry
+    ///
+    /// Returns false if the entry does not exist. Hints are matched by `search`.
+    pub async fn set_recall_hint(&self, id: &HistoryId, hint: Option<String>) -> bool {
+        let mut cache = self.history_cache.write().await;
+        match cache.iter_mut().find(|e| &e.id == id) {
+            Some(entry) => {
+                entry.recall_hint = hint;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the category of a history entry, which selects its retention
+    ///
+    /// Returns false if the entry does not exist.
+    pub async fn set_category(&self, id: &HistoryId, category: Option<String>) -> bool {
+        let mut cache = self.history_cache.write().await;
+        match cache.iter_mut().find(|e| &e.id == id) {
+            Some(entry) => {
+                entry.page_info.category = category;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Pin or unpin a history entry; pinned entries are never cleaned up
+    ///
+    /// Returns false if the entry does not exist.
+    pub async fn set_pinned(&self, id: &HistoryId, pinned: bool) -> bool {
+        if !self.history_cache.read().await.iter().any(|e| &e.id == id) {
+            return false;
+        }
+        let mut pins = self.pinned.write().await;
+        if pinned {
+            pins.insert(id.clone());
+        } else {
+            pins.remove(id);
+        }
+        true
+    }
+
+    /// Whether a history entry is pinned
+    pub async fn is_pinned(&self, id: &HistoryId) -> bool {
+        self.pinned.read().await.contains(id)
+    }
+
     /// Get content summary for a URL
     async fn get_content_summary(&self, url: &str) -> Option<ContentSummary> {
         let summaries = self.content_summaries.read().await;
@@ -520,7 +630,7 @@ impl TabHistoryManager {
 
     /// Search history by text query
     ///
-    /// Searches across title, URL, and content summary.
+    /// Searches across title, URL, content summary, keywords and recall hint.
     pub async fn search(&self, query: &str, limit: usize) -> Vec<HistoryEntry> {
         let cache = self.history_cache.read().await;
         let query_lower = query.to_lowercase();
@@ -541,6 +651,11 @@ impl TabHistoryManager {
                         .keywords
                         .iter()
                         .any(|k| k.to_lowercase().contains(&query_lower))
+                    || entry
+                        .recall_hint
+                        .as_ref()
+                        .map(|h| h.to_lowercase().contains(&query_lower))
+                        .unwrap_or(false)
             })
             .cloned()
             .collect();
@@ -624,42 +739,10 @@ impl TabHistoryManager {
     /// Apply retention policy to clean up old history
     ///
     /// This implements automatic cleanup based on age and entry count.
+    /// Returns the number of deleted entries; see `cleanup_with_policy`.
     pub async fn apply_retention_policy(&self, policy: &RetentionPolicy) -> usize {
-        let mut total_deleted = 0;
-
-        // Delete entries older than max_age_days
-        let cutoff = Utc::now() - Duration::days(policy.max_age_days as i64);
-        total_deleted += self.delete_older_than(cutoff).await;
-
-        // Trim to max_entries if needed
-        let mut cache = self.history_cache.write().await;
-        if cache.len() > policy.max_entries {
-            // Sort by importance if preserving important entries
-            if policy.preserve_important {
-                cache.sort_by(|a, b| {
-                    let a_importance = self.calculate_importance(a);
-                    let b_importance = self.calculate_importance(b);
-                    b_importance
-                        .partial_cmp(&a_importance)
-                        .unwrap_or(std::cmp::Ordering::Equal)
-                });
-            }
-
-            let to_remove = cache.len() - policy.max_entries;
-            cache.truncate(policy.max_entries);
-            total_deleted += to_remove;
-        }
-
-        if total_deleted > 0 {
-            drop(cache);
-            self.update_cache_stats().await;
-            info!(
-                "Applied retention policy, deleted {} entries",
-                total_deleted
-            );
-        }
-
-        total_deleted
+        let result = self.cleanup_with_policy(policy).await;
+        result.deleted_by_age + result.deleted_by_limit + result.deleted_by_size
     }
 
     /// Calculate importance score for a history entry
@@ -764,11 +847,23 @@ impl TabHistoryManager {
             }
         })?;
 
+        Ok(self.restore_entry(&entry, connector, None, false).await)
+    }
+
+    /// Reopen `entry` in `window_id` of the connector's browser
+    async fn restore_entry<C: BrowserConnector>(
+        &self,
+        entry: &HistoryEntry,
+        connector: &C,
+        window_id: Option<&str>,
+        pinned: bool,
+    ) -> RestoreResult {
+        let history_id = &entry.id;
         let target_browser = connector.browser_type();
         let url = &entry.page_info.url;
 
         // Attempt to create the tab in the target browser
-        match connector.create_tab(url).await {
+        match connector.create_tab_with(url, window_id, pinned).await {
             Ok(new_tab_id) => {
                 // Update stats
                 {
@@ -781,14 +876,14 @@ impl TabHistoryManager {
                     entry.page_info.title, target_browser
                 );
 
-                Ok(RestoreResult {
+                RestoreResult {
                     history_id: history_id.clone(),
                     new_tab_id: Some(new_tab_id),
                     target_browser,
                     success: true,
                     error: None,
                     restored_at: Utc::now(),
-                })
+                }
             }
             Err(e) => {
                 warn!(
@@ -797,14 +892,14 @@ impl TabHistoryManager {
                     e
                 );
 
-                Ok(RestoreResult {
+                RestoreResult {
                     history_id: history_id.clone(),
                     new_tab_id: None,
                     target_browser,
                     success: false,
                     error: Some(e.to_string()),
                     restored_at: Utc::now(),
-                })
+                }
             }
         }
     }
@@ -837,6 +932,66 @@ impl TabHistoryManager {
         results
     }
 
+    /// Group history into closed sessions, most recently closed first
+    ///
+    /// Tabs closed from the same window of a browser form a session as
+    /// long as each was closed within `burst_gap` of the previous one;
+    /// tabs closed from an unknown window are grouped by browser alone.
+    pub async fn get_closed_sessions(&self, burst_gap: Duration) -> Vec<ClosedSession> {
+        let entries = self.history_cache.read().await.clone();
+        group_sessions(entries, Some(burst_gap))
+    }
+
+    /// Reopen every tab of a closed session in the connector's browser
+    ///
+    /// The tabs open in a new window, if the browser can open one, in
+    /// their original order and with their pinned state.
+    pub async fn restore_session<C: BrowserConnector>(
+        &self,
+        session: &ClosedSession,
+        connector: &C,
+    ) -> Vec<RestoreResult> {
+        let window = match connector.create_window().await {
+            Ok(window) => window,
+            Err(e) => {
+                warn!("Failed to open a window to restore a session: {}", e);
+                None
+            }
+        };
+
+        let mut results = Vec::with_capacity(session.entries.len());
+        for entry in &session.entries {
+            let pinned = entry.session_info.as_ref().is_some_and(|s| s.pinned);
+            results.push(self.restore_entry(entry, connector, window.as_deref(), pinned).await);
+        }
+        results
+    }
+
+    /// Reopen everything closed in the last `within_minutes`, or only
+    /// what was closed from `window_id`
+    ///
+    /// Each original window is restored as a session of its own, oldest
+    /// first, so windows keep their tabs together.
+    pub async fn restore_recently_closed<C: BrowserConnector>(
+        &self,
+        within_minutes: i64,
+        window_id: Option<&str>,
+        connector: &C,
+    ) -> Vec<RestoreResult> {
+        let mut entries = self.get_recently_closed(within_minutes).await;
+        if let Some(window_id) = window_id {
+            entries.retain(|e| {
+                e.session_info.as_ref().and_then(|s| s.window_id.as_deref()) == Some(window_id)
+            });
+        }
+
+        let mut results = Vec::new();
+        for session in group_sessions(entries, None).iter().rev() {
+            results.extend(self.restore_session(session, connector).await);
+        }
+        results
+    }
+
     /// Get the URL for a history entry (for manual restoration)
     ///
     /// This is useful when automatic restoration fails and the user
@@ -876,74 +1031,21 @@ impl TabHistoryManager {
 
     /// Run cleanup with a specific retention policy
     ///
-    /// This provides more control over the cleanup process.
+    /// This provides more control over the cleanup process. The entries
+    /// deleted are those `cleanup_preview` reports.
     pub async fn cleanup_with_policy(&self, policy: &RetentionPolicy) -> CleanupResult {
-        let deleted_by_age: usize;
-        let mut deleted_by_limit = 0;
-        let mut preserved_important = 0;
-
-        // Step 1: Delete entries older than max_age_days
-        let cutoff = Utc::now() - Duration::days(policy.max_age_days as i64);
-        
-        {
-            let mut cache = self.history_cache.write().await;
-            let initial_len = cache.len();
-
-            if policy.preserve_important {
-                // Keep important entries even if old
-                let mut temp_preserved = 0;
-                cache.retain(|e| {
-                    if e.closed_at >= cutoff {
-                        true
-                    } else {
-                        let importance = self.calculate_importance(e);
-                        if importance >= policy.importance_threshold {
-                            temp_preserved += 1;
-                            true
-                        } else {
-                            false
-                        }
-                    }
-                });
-                preserved_important = temp_preserved;
-                deleted_by_age = initial_len.saturating_sub(cache.len());
-            } else {
-                cache.retain(|e| e.closed_at >= cutoff);
-                deleted_by_age = initial_len.saturating_sub(cache.len());
-            }
-        }
-
-        // Step 2: Trim to max_entries if needed
-        {
+        let (preview, remaining_entries) = {
             let mut cache = self.history_cache.write().await;
-
-            if cache.len() > policy.max_entries {
-                if policy.preserve_important {
-                    // Sort by importance (descending) to keep most important
-                    cache.sort_by(|a, b| {
-                        let a_importance = self.calculate_importance(a);
-                        let b_importance = self.calculate_importance(b);
-                        b_importance
-                            .partial_cmp(&a_importance)
-                            .unwrap_or(std::cmp::Ordering::Equal)
-                    });
-                }
-
-                let to_remove = cache.len() - policy.max_entries;
-                cache.truncate(policy.max_entries);
-                deleted_by_limit = to_remove;
-            }
-        }
-
-        // Update stats
-        let remaining_entries = {
-            let cache = self.history_cache.read().await;
-            cache.len()
+            let preview = self.plan_cleanup(&cache, &*self.pinned.read().await, policy);
+            let deleted: HashSet<&HistoryId> = preview.entries.iter().map(|e| &e.id).collect();
+            cache.retain(|e| !deleted.contains(&e.id));
+            (preview, cache.len())
         };
+        let deleted_total = preview.entries.len();
 
         {
             let mut stats = self.stats.write().await;
-            stats.session_cleanups += deleted_by_age + deleted_by_limit;
+            stats.session_cleanups += deleted_total;
             stats.last_cleanup = Some(Utc::now());
             stats.cached_entries = remaining_entries;
         }
@@ -951,16 +1053,23 @@ impl TabHistoryManager {
         self.update_cache_stats().await;
 
         let result = CleanupResult {
-            deleted_by_age,
-            deleted_by_limit,
-            preserved_important,
+            deleted_by_age: preview.deleted_by_age,
+            deleted_by_limit: preview.deleted_by_limit,
+            deleted_by_size: preview.deleted_by_size,
+            preserved_important: preview.preserved_important,
+            preserved_exempt: preview.preserved_exempt,
             remaining_entries,
             cleaned_at: Utc::now(),
         };
 
         info!(
-            "Cleanup completed: {} by age, {} by limit, {} preserved, {} remaining",
-            deleted_by_age, deleted_by_limit, preserved_important, remaining_entries
+            "Cleanup completed: {} by age, {} by limit, {} by size, {} preserved, {} exempt, {} remaining",
+            result.deleted_by_age,
+            result.deleted_by_limit,
+            result.deleted_by_size,
+            result.preserved_important,
+            result.preserved_exempt,
+            remaining_entries
         );
 
         result
@@ -968,17 +1077,7 @@ impl TabHistoryManager {
 
     /// Check if cleanup is needed based on current state
     pub async fn needs_cleanup(&self) -> bool {
-        let cache = self.history_cache.read().await;
-        let policy = &self.config.default_retention_policy;
-
-        // Check if over max entries
-        if cache.len() > policy.max_entries {
-            return true;
-        }
-
-        // Check if any entries are older than max_age_days
-        let cutoff = Utc::now() - Duration::days(policy.max_age_days as i64);
-        cache.iter().any(|e| e.closed_at < cutoff)
+        !self.cleanup_preview(&self.config.default_retention_policy).await.entries.is_empty()
     }
 
     /// Get entries that would be deleted by the current retention policy
@@ -991,49 +1090,95 @@ impl TabHistoryManager {
 
     /// Preview cleanup with a specific policy
     pub async fn preview_cleanup_with_policy(&self, policy: &RetentionPolicy) -> Vec<HistoryEntry> {
-        let cache = self.history_cache.read().await;
-        let cutoff = Utc::now() - Duration::days(policy.max_age_days as i64);
+        self.cleanup_preview(policy).await.entries
+    }
 
-        let mut to_delete: Vec<HistoryEntry> = cache
-            .iter()
-            .filter(|e| {
-                if e.closed_at >= cutoff {
-                    false
-                } else if policy.preserve_important {
-                    let importance = self.calculate_importance(e);
-                    importance < policy.importance_threshold
-                } else {
-                    true
-                }
-            })
-            .cloned()
-            .collect();
+    /// Entries `policy` would delete with the reasons and bytes freed,
+    /// e.g. to confirm a cleanup before running it
+    pub async fn cleanup_preview(&self, policy: &RetentionPolicy) -> CleanupPreview {
+        let cache = self.history_cache.read().await;
+        self.plan_cleanup(&cache, &*self.pinned.read().await, policy)
+    }
 
-        // Also include entries that would be deleted due to max_entries limit
-        let remaining_after_age = cache.len() - to_delete.len();
-        if remaining_after_age > policy.max_entries {
-            let mut remaining: Vec<&HistoryEntry> = cache
-                .iter()
-                .filter(|e| !to_delete.iter().any(|d| d.id == e.id))
-                .collect();
-
-            if policy.preserve_important {
-                remaining.sort_by(|a, b| {
-                    let a_importance = self.calculate_importance(a);
-                    let b_importance = self.calculate_importance(b);
-                    a_importance
-                        .partial_cmp(&b_importance)
-                        .unwrap_or(std::cmp::Ordering::Equal)
-                });
+    /// Work out which of `entries` `policy` deletes
+    ///
+    /// Entries past the maximum age of their category go first, unless
+    /// important. If more than `max_entries` or `max_total_bytes` remain,
+    /// the least important of the rest go next, or the oldest when not
+    /// preserving important entries. Pinned entries, and bookmarked ones
+    /// if the policy keeps them, are never deleted.
+    fn plan_cleanup(
+        &self,
+        entries: &[HistoryEntry],
+        pinned: &HashSet<HistoryId>,
+        policy: &RetentionPolicy,
+    ) -> CleanupPreview {
+        let now = Utc::now();
+        let exempt = |e: &HistoryEntry| {
+            pinned.contains(&e.id) || (policy.keep_bookmarked && e.page_info.bookmark_info.is_some())
+        };
+        let mut preview = CleanupPreview::default();
+        let mut deleted: Vec<&HistoryEntry> = Vec::new();
+        let mut kept: Vec<&HistoryEntry> = Vec::new();
+
+        for entry in entries {
+            let max_age = policy.max_age_days_for(entry.page_info.category.as_deref());
+            if now - entry.closed_at <= Duration::days(max_age as i64) {
+                kept.push(entry);
+            } else if exempt(entry) {
+                preview.preserved_exempt += 1;
+                kept.push(entry);
+            } else if policy.preserve_important
+                && self.calculate_importance(entry) >= policy.importance_threshold
+            {
+                preview.preserved_important += 1;
+                kept.push(entry);
+            } else {
+                preview.deleted_by_age += 1;
+                deleted.push(entry);
             }
+        }
 
-            let excess = remaining_after_age - policy.max_entries;
-            for entry in remaining.into_iter().take(excess) {
-                to_delete.push(entry.clone());
+        // Entries the count and size limits may delete, first to go first
+        let mut removable: Vec<&HistoryEntry> = kept.iter().copied().filter(|e| !exempt(e)).collect();
+        if policy.preserve_important {
+            removable.sort_by(|a, b| {
+                self.calculate_importance(a)
+                    .total_cmp(&self.calculate_importance(b))
+                    .then(a.closed_at.cmp(&b.closed_at))
+            });
+        } else {
+            removable.sort_by_key(|e| e.closed_at);
+        }
+        let mut removable = removable.into_iter();
+
+        let mut remaining = kept.len();
+        while remaining > policy.max_entries {
+            let Some(entry) = removable.next() else { break };
+            deleted.push(entry);
+            preview.deleted_by_limit += 1;
+            remaining -= 1;
+        }
+
+        if let Some(max_bytes) = policy.max_total_bytes {
+            let deleted_ids: HashSet<&HistoryId> = deleted.iter().map(|e| &e.id).collect();
+            let mut total: u64 = kept
+                .iter()
+                .filter(|e| !deleted_ids.contains(&e.id))
+                .map(|e| entry_size(e))
+                .sum();
+            while total > max_bytes {
+                let Some(entry) = removable.next() else { break };
+                total -= entry_size(entry);
+                deleted.push(entry);
+                preview.deleted_by_size += 1;
             }
         }
 
-        to_delete
+        deleted.sort_by_key(|e| e.closed_at);
+        preview.bytes_freed = deleted.iter().map(|e| entry_size(e)).sum();
+        preview.entries = deleted.into_iter().cloned().collect();
+        preview
     }
 
     // =========================================================================
@@ -1086,6 +1231,7 @@ impl TabHistoryManager {
             }
             ExportFormat::Csv => self.export_to_csv(&exported),
             ExportFormat::Html => self.export_to_html(&exported),
+            ExportFormat::NetscapeBookmarks => self.export_to_bookmarks(&exported),
         }
     }
 
@@ -1094,44 +1240,55 @@ impl TabHistoryManager {
         let mut csv = String::new();
         
         // Header
-        csv.push_str("id,url,title,browser_type,closed_at,has_summary,keywords\n");
+        csv.push_str("id,url,title,browser_type,closed_at,has_summary,keywords,category,summary,recall_hint\n");
         
         // Data rows
         for entry in &exported.entries {
-            let keywords = entry.page_info.keywords.join(";");
-            let has_summary = entry.page_info.content_summary.is_some();
-            
-            csv.push_str(&format!(
-                "\"{}\",\"{}\",\"{}\",\"{:?}\",\"{}\",{},\"{}\"\n",
-                entry.id.0,
-                entry.page_info.url.replace('"', "\"\""),
-                entry.page_info.title.replace('"', "\"\""),
-                entry.browser_type,
+            let page = &entry.page_info;
+            let summary = page.content_summary.as_ref().map(|s| s.summary_text.as_str());
+            let fields = [
+                entry.id.0.to_string(),
+                page.url.clone(),
+                page.title.clone(),
+                format!("{:?}", entry.browser_type),
                 entry.closed_at.to_rfc3339(),
-                has_summary,
-                keywords.replace('"', "\"\""),
-            ));
+                summary.is_some().to_string(),
+                page.keywords.join(";"),
+                page.category.clone().unwrap_or_default(),
+                summary.unwrap_or_default().to_string(),
+                entry.recall_hint.clone().unwrap_or_default(),
+            ];
+            let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
         }
         
         Ok(csv)
     }
 
-    /// Export to HTML format
+    /// Export to a styled HTML report, one table per day
     fn export_to_html(&self, exported: &ExportedHistory) -> Result<String> {
         let mut html = String::new();
         
         html.push_str("<!DOCTYPE html>\n<html>\n<head>\n");
         html.push_str("<meta charset=\"UTF-8\">\n");
+        html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
         html.push_str("<title>Tab History Export</title>\n");
         html.push_str("<style>\n");
-        html.push_str("body { font-family: Arial, sans-serif; margin: 20px; }\n");
+        html.push_str("body { font-family: -apple-system, 'Segoe UI', Arial, sans-serif; margin: 24px; color: #222; }\n");
+        html.push_str("h1 { font-size: 1.6em; margin-bottom: 4px; }\n");
+        html.push_str("h2 { font-size: 1.1em; margin: 28px 0 8px; color: #444; }\n");
         html.push_str("table { border-collapse: collapse; width: 100%; }\n");
-        html.push_str("th, td { border: 1px solid #ddd; padding: 8px; text-align: left; }\n");
-        html.push_str("th { background-color: #4CAF50; color: white; }\n");
-        html.push_str("tr:nth-child(even) { background-color: #f2f2f2; }\n");
-        html.push_str("a { color: #1a73e8; }\n");
+        html.push_str("th, td { border-bottom: 1px solid #e4e4e4; padding: 8px; text-align: left; vertical-align: top; }\n");
+        html.push_str("th { background-color: #f5f7fa; color: #555; font-weight: 600; }\n");
+        html.push_str("tr:hover td { background-color: #fafbfc; }\n");
+        html.push_str("a { color: #1a73e8; text-decoration: none; }\n");
+        html.push_str(".favicon { width: 16px; height: 16px; vertical-align: middle; margin-right: 6px; }\n");
+        html.push_str(".summary { color: #666; font-size: 0.9em; margin-top: 4px; }\n");
+        html.push_str(".tag { display: inline-block; background: #e8f0fe; color: #1967d2; border-radius: 10px; padding: 1px 8px; margin: 4px 4px 0 0; font-size: 0.8em; }\n");
         html.push_str(".metadata { margin-bottom: 20px; color: #666; }\n");
         html.push_str("</style>\n</head>\n<body>\n");
+        html.push_str("<h1>Tab History</h1>\n");
         
         // Metadata
         html.push_str("<div class=\"metadata\">\n");
@@ -1144,37 +1301,104 @@ impl TabHistoryManager {
                 newest.format("%Y-%m-%d")
             ));
         }
+        let mut browsers: Vec<(String, usize)> = Vec::new();
+        for entry in &exported.entries {
+            let name = format!("{:?}", entry.browser_type);
+            match browsers.iter_mut().find(|(b, _)| *b == name) {
+                Some((_, count)) => *count += 1,
+                None => browsers.push((name, 1)),
+            }
+        }
+        if !browsers.is_empty() {
+            let counts: Vec<String> = browsers.iter().map(|(b, n)| format!("{} {}", b, n)).collect();
+            html.push_str(&format!("<p>Browsers: {}</p>\n", counts.join(", ")));
+        }
         html.push_str("</div>\n");
         
-        // Table
-        html.push_str("<table>\n<tr>\n");
-        html.push_str("<th>Title</th><th>URL</th><th>Browser</th><th>Closed At</th>\n");
-        html.push_str("</tr>\n");
-        
-        for entry in &exported.entries {
-            html.push_str("<tr>\n");
-            html.push_str(&format!(
-                "<td>{}</td>\n",
-                html_escape(&entry.page_info.title)
-            ));
-            html.push_str(&format!(
-                "<td><a href=\"{}\">{}</a></td>\n",
-                html_escape(&entry.page_info.url),
-                html_escape(&truncate_url(&entry.page_info.url, 50))
-            ));
-            html.push_str(&format!("<td>{:?}</td>\n", entry.browser_type));
-            html.push_str(&format!(
-                "<td>{}</td>\n",
-                entry.closed_at.format("%Y-%m-%d %H:%M")
-            ));
+        for (day, entries) in entries_by_day(&exported.entries) {
+            html.push_str(&format!("<h2>{}</h2>\n", day));
+            html.push_str("<table>\n<tr>\n");
+            html.push_str("<th>Title</th><th>URL</th><th>Browser</th><th>Closed At</th>\n");
             html.push_str("</tr>\n");
+
+            for entry in entries {
+                let page = &entry.page_info;
+                html.push_str("<tr>\n<td>");
+                if let Some(favicon) = &page.favicon_url {
+                    html.push_str(&format!("<img class=\"favicon\" src=\"{}\" alt=\"\">", html_escape(favicon)));
+                }
+                html.push_str(&html_escape(&page.title));
+                if let Some(summary) = &page.content_summary {
+                    html.push_str(&format!(
+                        "<div class=\"summary\">{}</div>",
+                        html_escape(&summary.summary_text)
+                    ));
+                }
+                for keyword in &page.keywords {
+                    html.push_str(&format!("<span class=\"tag\">{}</span>", html_escape(keyword)));
+                }
+                html.push_str("</td>\n");
+                html.push_str(&format!(
+                    "<td><a href=\"{}\">{}</a></td>\n",
+                    html_escape(&page.url),
+                    html_escape(&truncate_url(&page.url, 50))
+                ));
+                html.push_str(&format!("<td>{:?}</td>\n", entry.browser_type));
+                html.push_str(&format!(
+                    "<td>{}</td>\n",
+                    entry.closed_at.format("%H:%M")
+                ));
+                html.push_str("</tr>\n");
+            }
+
+            html.push_str("</table>\n");
         }
         
-        html.push_str("</table>\n</body>\n</html>");
+        html.push_str("</body>\n</html>");
         
         Ok(html)
     }
 
+    /// Export to a Netscape bookmark file, one folder per day
+    ///
+    /// Every browser imports this format, so closed tabs can be brought
+    /// back as bookmarks. Summaries become bookmark descriptions.
+    fn export_to_bookmarks(&self, exported: &ExportedHistory) -> Result<String> {
+        let mut html = String::from(
+            "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+             <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+             <TITLE>Bookmarks</TITLE>\n\
+             <H1>Bookmarks</H1>\n\
+             <DL><p>\n",
+        );
+        html.push_str(&format!(
+            "<DT><H3 ADD_DATE=\"{}\">Tab History</H3>\n<DL><p>\n",
+            exported.metadata.exported_at.timestamp()
+        ));
+
+        for (day, entries) in entries_by_day(&exported.entries) {
+            html.push_str(&format!("<DT><H3>{}</H3>\n<DL><p>\n", day));
+            for entry in entries {
+                let page = &entry.page_info;
+                let title = if page.title.is_empty() { &page.url } else { &page.title };
+                html.push_str(&format!(
+                    "<DT><A HREF=\"{}\" ADD_DATE=\"{}\" LAST_VISIT=\"{}\">{}</A>\n",
+                    html_escape(&page.url),
+                    page.created_at.timestamp(),
+                    entry.closed_at.timestamp(),
+                    html_escape(title)
+                ));
+                if let Some(summary) = &page.content_summary {
+                    html.push_str(&format!("<DD>{}\n", html_escape(&summary.summary_text)));
+                }
+            }
+            html.push_str("</DL><p>\n");
+        }
+
+        html.push_str("</DL><p>\n</DL><p>\n");
+        Ok(html)
+    }
+
     /// Import history from exported JSON data
     pub async fn import(&self, json_data: &str) -> Result<usize> {
         let exported: ExportedHistory = serde_json::from_str(json_data).map_err(|e| {
@@ -1249,11 +1473,41 @@ fn html_escape(s: &str) -> String {
 
 /// Helper function to truncate URLs for display
 fn truncate_url(url: &str, max_len: usize) -> String {
-    if url.len() <= max_len {
+    if url.chars().count() <= max_len {
         url.to_string()
     } else {
-        format!("{}...", &url[..max_len - 3])
+        let kept: String = url.chars().take(max_len - 3).collect();
+        format!("{}...", kept)
+    }
+}
+
+/// Quote a CSV field, doubling quotes
+///
+/// Fields starting like a formula get a leading `'` so spreadsheets show
+/// them as text instead of evaluating them.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// Entries grouped by the UTC day they were closed, newest first
+fn entries_by_day(entries: &[HistoryEntry]) -> Vec<(String, Vec<&HistoryEntry>)> {
+    let mut sorted: Vec<&HistoryEntry> = entries.iter().collect();
+    sorted.sort_by_key(|e| std::cmp::Reverse(e.closed_at));
+
+    let mut days: Vec<(String, Vec<&HistoryEntry>)> = Vec::new();
+    for entry in sorted {
+        let day = entry.closed_at.format("%Y-%m-%d").to_string();
+        match days.last_mut() {
+            Some((last, group)) if *last == day => group.push(entry),
+            _ => days.push((day, vec![entry])),
+        }
     }
+    days
 }
 
 impl Default for TabHistoryManager {
@@ -1263,9 +1517,57 @@ impl Default for TabHistoryManager {
 }
 
 
+/// Group entries by browser and window into sessions, most recently
+/// closed first, splitting a window's entries where more than `burst_gap`
+/// passes between closes
+///
+/// Entries with an unknown window are grouped as if from one window.
+fn group_sessions(mut entries: Vec<HistoryEntry>, burst_gap: Option<Duration>) -> Vec<ClosedSession> {
+    entries.sort_by_key(|e| e.closed_at);
+    let window_of = |e: &HistoryEntry| e.session_info.as_ref().and_then(|s| s.window_id.clone());
+
+    let mut sessions: Vec<ClosedSession> = Vec::new();
+    for entry in entries {
+        let window_id = window_of(&entry);
+        let open = sessions.iter_mut().rev().find(|s| {
+            s.window_id == window_id
+                && s.browser_type == entry.browser_type
+                && burst_gap.is_none_or(|gap| entry.closed_at - s.last_closed <= gap)
+        });
+        match open {
+            Some(session) => {
+                session.last_closed = entry.closed_at;
+                session.entries.push(entry);
+            }
+            None => sessions.push(ClosedSession {
+                browser_type: entry.browser_type,
+                window_id,
+                first_closed: entry.closed_at,
+                last_closed: entry.closed_at,
+                entries: vec![entry],
+            }),
+        }
+    }
+
+    for session in &mut sessions {
+        // Stable, so tabs without a position stay in closing order
+        session
+            .entries
+            .sort_by_key(|e| e.session_info.as_ref().and_then(|s| s.tab_index).unwrap_or(u32::MAX));
+    }
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.last_closed));
+    sessions
+}
+
+/// Serialized size of a history entry in bytes
+fn entry_size(entry: &HistoryEntry) -> u64 {
+    serde_json::to_vec(entry).map_or(0, |bytes| bytes.len() as u64)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::MockConnector;
 
     fn create_test_tab(url: &str, title: &str, browser_type: BrowserType) -> TabInfo {
         TabInfo {
@@ -1422,6 +1724,22 @@ mod tests {
         assert_eq!(results.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_recall_hint_is_searchable() {
+        let manager = TabHistoryManager::new();
+        let tab = create_test_tab("https://example.com/trams", "Tram 28", BrowserType::Chrome);
+        let history_id = manager.save_closed_tab(tab, Utc::now()).await.unwrap();
+        assert!(manager.search("lisbon", 10).await.is_empty());
+
+        let hint = "Saved from the \"Lisbon trip\" tab group".to_string();
+        assert!(manager.set_recall_hint(&history_id, Some(hint.clone())).await);
+        assert!(!manager.set_recall_hint(&HistoryId::new(), None).await);
+
+        let results = manager.search("lisbon", 10).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].recall_hint.as_ref(), Some(&hint));
+    }
+
     #[tokio::test]
     async fn test_delete_entry() {
         let manager = TabHistoryManager::new();
@@ -1606,6 +1924,7 @@ mod tests {
             max_entries: 3,
             preserve_important: false,
             importance_threshold: 0.5,
+            ..Default::default()
         };
 
         let deleted = manager.apply_retention_policy(&policy).await;
@@ -1729,6 +2048,7 @@ mod tests {
             max_entries: 3,
             preserve_important: false,
             importance_threshold: 0.5,
+            ..Default::default()
         };
 
         let result = manager.cleanup_with_policy(&policy).await;
@@ -1771,6 +2091,7 @@ mod tests {
             max_entries: 10,
             preserve_important: true,
             importance_threshold: 0.2, // Low threshold to preserve the important entry
+            ..Default::default()
         };
 
         let result = manager.cleanup_with_policy(&policy).await;
@@ -1788,6 +2109,7 @@ mod tests {
                 max_entries: 3,
                 preserve_important: false,
                 importance_threshold: 0.5,
+                ..Default::default()
             },
             ..Default::default()
         };
@@ -1810,6 +2132,45 @@ mod tests {
         assert!(manager.needs_cleanup().await);
     }
 
+    #[tokio::test]
+    async fn test_cleanup_categories_pins_and_size_cap() {
+        let manager = TabHistoryManager::new();
+        let save = |url: &str, days: i64| {
+            let tab = create_test_tab(url, url, BrowserType::Chrome);
+            manager.save_closed_tab(tab, Utc::now() - Duration::days(days))
+        };
+        let news = save("https://news.example.com", 10).await.unwrap();
+        save("https://docs.example.com", 10).await.unwrap();
+        let pinned = save("https://pinned.example.com", 40).await.unwrap();
+        save("https://old.example.com", 40).await.unwrap();
+        assert!(manager.set_category(&news, Some("news".to_string())).await);
+        assert!(manager.set_pinned(&pinned, true).await);
+        assert!(manager.is_pinned(&pinned).await);
+        assert!(!manager.set_pinned(&HistoryId::new(), true).await);
+
+        let policy = RetentionPolicy {
+            max_age_days: 30,
+            preserve_important: false,
+            category_max_age_days: HashMap::from([("News".to_string(), 7)]),
+            ..Default::default()
+        };
+        let preview = manager.cleanup_preview(&policy).await;
+        let urls: Vec<&str> = preview.entries.iter().map(|e| e.page_info.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://old.example.com", "https://news.example.com"]);
+        assert_eq!((preview.deleted_by_age, preview.preserved_exempt), (2, 1));
+        assert!(preview.bytes_freed > 0);
+        assert_eq!(manager.total_count().await, 4);
+
+        let result = manager.cleanup_with_policy(&policy).await;
+        assert_eq!((result.deleted_by_age, result.preserved_exempt, result.remaining_entries), (2, 1, 2));
+
+        // The size cap deletes everything but the pinned entry
+        let capped = RetentionPolicy { max_total_bytes: Some(1), ..policy };
+        let result = manager.cleanup_with_policy(&capped).await;
+        assert_eq!((result.deleted_by_size, result.remaining_entries), (1, 1));
+        assert!(manager.get_by_id(&pinned).await.is_some());
+    }
+
     #[tokio::test]
     async fn test_preview_cleanup() {
         let manager = TabHistoryManager::new();
@@ -1829,6 +2190,7 @@ mod tests {
             max_entries: 3,
             preserve_important: false,
             importance_threshold: 0.5,
+            ..Default::default()
         };
 
         let to_delete = manager.preview_cleanup_with_policy(&policy).await;
@@ -1848,6 +2210,7 @@ mod tests {
                 max_entries: 3,
                 preserve_important: false,
                 importance_threshold: 0.5,
+                ..Default::default()
             },
             ..Default::default()
         };
@@ -1905,6 +2268,87 @@ mod tests {
         assert!(csv.contains("CSV Test"));
     }
 
+    #[tokio::test]
+    async fn test_closed_sessions_restore_by_window() {
+        let manager = TabHistoryManager::new();
+        let now = Utc::now();
+        let close = |url: &str, window: &str, index: u32, pinned: bool, minutes_ago: i64| {
+            let session = SessionInfo {
+                session_id: "s".to_string(),
+                window_id: Some(window.to_string()),
+                tab_index: Some(index),
+                scroll_position: None,
+                pinned,
+            };
+            let tab = create_test_tab(url, url, BrowserType::Chrome);
+            manager.save_closed_tab_in_session(tab, now - Duration::minutes(minutes_ago), Some(session))
+        };
+        // Window 1 closed tab by tab, right to left, then a burst an hour earlier
+        close("https://b.example.com", "1", 1, false, 2).await.unwrap();
+        close("https://a.example.com", "1", 0, true, 1).await.unwrap();
+        close("https://old.example.com", "1", 0, false, 60).await.unwrap();
+        close("https://c.example.com", "2", 0, false, 3).await.unwrap();
+
+        let sessions = manager.get_closed_sessions(Duration::minutes(5)).await;
+        assert_eq!(sessions.len(), 3);
+        let urls: Vec<&str> = sessions[0].entries.iter().map(|e| e.page_info.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://a.example.com", "https://b.example.com"]);
+        assert_eq!(sessions[1].window_id.as_deref(), Some("2"));
+
+        let connector = MockConnector::new(BrowserType::Firefox);
+        let results = manager.restore_session(&sessions[0], &connector).await;
+        assert!(results.iter().all(|r| r.success));
+        assert_eq!(*connector.opened.lock().unwrap(), vec![
+            ("https://a.example.com".to_string(), Some("Firefox-window-1".to_string()), true),
+            ("https://b.example.com".to_string(), Some("Firefox-window-1".to_string()), false),
+        ]);
+
+        // Everything closed in the last ten minutes, one window per original window
+        let connector = MockConnector::new(BrowserType::Firefox);
+        let results = manager.restore_recently_closed(10, None, &connector).await;
+        assert_eq!(results.len(), 3);
+        let opened = connector.opened.lock().unwrap().clone();
+        assert_eq!(opened[0], ("https://c.example.com".to_string(), Some("Firefox-window-1".to_string()), false));
+        assert_eq!(opened[1].1.as_deref(), Some("Firefox-window-2"));
+
+        let connector = MockConnector::new(BrowserType::Firefox);
+        assert_eq!(manager.restore_recently_closed(10, Some("2"), &connector).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_export_csv_guards_formulas() {
+        let manager = TabHistoryManager::new();
+
+        let tab = create_test_tab("https://formula.example.com", "=HYPERLINK(\"x\")", BrowserType::Chrome);
+        manager.save_closed_tab(tab, Utc::now()).await.unwrap();
+
+        let csv = manager.export(ExportFormat::Csv).await.unwrap();
+        assert!(csv.contains("\"'=HYPERLINK(\"\"x\"\")\""));
+    }
+
+    #[tokio::test]
+    async fn test_export_bookmark_file() {
+        let manager = TabHistoryManager::new();
+
+        let tab = create_test_tab("https://a.example.com/?q=1&r=2", "Fish & Chips", BrowserType::Chrome);
+        manager.save_closed_tab(tab, Utc::now()).await.unwrap();
+        let tab = create_test_tab("https://b.example.com", "", BrowserType::Firefox);
+        manager.save_closed_tab(tab, Utc::now() - Duration::days(2)).await.unwrap();
+
+        let bookmarks = manager.export(ExportFormat::NetscapeBookmarks).await.unwrap();
+        assert!(bookmarks.starts_with("<!DOCTYPE NETSCAPE-Bookmark-file-1>"));
+        assert!(bookmarks.contains("HREF=\"https://a.example.com/?q=1&amp;r=2\""));
+        assert!(bookmarks.contains(">Fish &amp; Chips</A>"));
+        // Untitled pages are named by their URL
+        assert!(bookmarks.contains(">https://b.example.com</A>"));
+        // One folder per day, newest first
+        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let earlier = (Utc::now() - Duration::days(2)).format("%Y-%m-%d").to_string();
+        let today_at = bookmarks.find(&format!("<H3>{}</H3>", today)).unwrap();
+        assert!(today_at < bookmarks.find(&format!("<H3>{}</H3>", earlier)).unwrap());
+        assert_eq!(bookmarks.matches("<DL><p>").count(), bookmarks.matches("</DL><p>").count());
+    }
+
     #[tokio::test]
     async fn test_export_html() {
         let manager = TabHistoryManager::new();
@@ -1999,6 +2443,7 @@ mod tests {
             max_entries: 3,
             preserve_important: false,
             importance_threshold: 0.5,
+            ..Default::default()
         };
 
         manager.cleanup_with_policy(&policy).await;
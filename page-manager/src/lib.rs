@@ -4,18 +4,31 @@
 //! handling data merging, association matching, and synchronization.
 //!
 //! # Features
-//! - Unified page information management system
+//! - Unified page information management system, with notes and highlights on pages
 //! - Tab and bookmark association matching
 //! - Data synchronization and update mechanism
 //! - Cross-reference recommendations
-//! - Unified search across all data sources
+//! - Unified search across all data sources, with facet counts and typo-tolerant autocomplete
+//! - Search query language with field filters and boolean operators
 //! - Tab history management with rich information
-//! - Tab restoration to specified browsers
+//! - Tab restoration to specified browsers, including whole closed sessions
 //! - Automatic cleanup strategies based on time and importance
 //! - History export and backup functionality
 //! - Remote tab control with operation history and undo
+//! - URL export fallbacks for migrations, written to files or the clipboard
+//! - Named tab sessions saved and restored across browsers
+//! - Tab snoozing with scheduled reopening
+//! - Rules automating tab and bookmark actions, with dry runs and an execution log
+//! - Stale tab recommendations with batch cleanup actions
+//! - Tag curation: rename, merge, nesting and bulk re-tagging
+//! - Smart groups clustered from page embeddings, stable across regenerations
+//! - Smart group merging, splitting, archiving and manual page order
+//! - Page relationship graph of links, shared domains and sessions, exported to DOT and JSON
 //! - Content archiving with HTML extraction and media download
+//! - Full-page archiving of closed tabs and flagged bookmarks, with MHTML captures
+//! - WARC export of archived pages with their media and snapshots
 //! - Page change detection and version management
+//! - Scheduled background synchronization with connected browsers
 //!
 //! # Requirements Implemented
 //! - 1.5: Execute remote control operations (close, activate, create tabs)
@@ -36,19 +49,47 @@ pub mod unified_manager;
 pub mod matcher;
 pub mod sync;
 pub mod search;
+pub mod search_query;
 pub mod history;
 pub mod remote_controller;
+pub mod url_export;
 pub mod content_archiver;
 pub mod change_detector;
+pub mod scheduler;
+pub mod sessions;
+pub mod snooze;
+pub mod rules;
+pub mod stale_tabs;
+pub mod tags;
+pub mod smart_groups;
+pub mod groups;
+pub mod graph;
+pub mod page_archiver;
+pub mod warc_export;
+#[cfg(test)]
+mod test_support;
 
 pub use unified_manager::*;
 pub use matcher::*;
 pub use sync::*;
 pub use search::*;
+pub use search_query::*;
 pub use history::*;
 pub use remote_controller::*;
+pub use url_export::*;
 pub use content_archiver::*;
 pub use change_detector::*;
+pub use scheduler::*;
+pub use sessions::*;
+pub use snooze::*;
+pub use rules::*;
+pub use stale_tabs::*;
+pub use tags::*;
+pub use smart_groups::*;
+pub use groups::*;
+pub use graph::*;
+pub use page_archiver::*;
+pub use warc_export::*;
 
 // Re-export commonly used types
 pub use web_page_manager_core::*;
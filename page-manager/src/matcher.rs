@@ -3,27 +3,116 @@
 //! Provides functionality for matching tabs with bookmarks based on URL,
 //! domain, and content similarity.
 //!
+//! URLs are compared after normalization, so cosmetic differences such as
+//! tracking parameters, fragments, a `www.` prefix or a trailing slash do
+//! not prevent an exact match. Known redirects are followed as well: a
+//! bookmark of a URL that now redirects elsewhere matches a tab open on the
+//! redirect target.
+//!
+//! Sites that encode a page's identity in its query or path, such as
+//! YouTube's `v=` parameter, can be given a [`DomainRule`] that is
+//! consulted before the generic normalization.
+//!
+//! Matches fall into certainty tiers ([`MatchTier`]): the same normalized
+//! URL is exact, the same recorded content hash under another URL is
+//! strong, and a similar title or just the same domain is weak.
+//!
 //! # Requirements
 //! - 6.1: Display bookmark association marks when tab URL matches existing bookmark
 //! - 6.2: Detect tab content changes and offer bookmark info update options
 
 use web_page_manager_core::*;
 use url::Url;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Longest redirect chain followed before giving up
+const MAX_REDIRECT_HOPS: usize = 10;
+
+/// Confidence of a match on the same normalized URL
+const EXACT_CONFIDENCE: f32 = 1.0;
+/// Confidence of a match on the same content hash
+const SAME_CONTENT_CONFIDENCE: f32 = 0.9;
+/// Confidence range of title matches, scaled by similarity
+const SIMILAR_TITLE_CONFIDENCE: (f32, f32) = (0.55, 0.8);
+/// Confidence of a match on the domain alone
+const SAME_DOMAIN_CONFIDENCE: f32 = 0.5;
 
 /// Configuration for the matcher
 #[derive(Debug, Clone)]
 pub struct MatcherConfig {
-    /// Minimum similarity score for content-based matching (0.0 - 1.0)
+    /// Minimum title similarity for a weak match on the same domain
+    /// (0.0 - 1.0)
     pub content_similarity_threshold: f32,
     /// Whether to match by exact URL
     pub match_exact_url: bool,
     /// Whether to match by domain
     pub match_domain: bool,
-    /// Whether to match by content similarity
+    /// Whether to match by content hash and title similarity
     pub match_content: bool,
     /// Whether to normalize URLs before matching (remove trailing slashes, etc.)
     pub normalize_urls: bool,
+    /// Whether normalization drops tracking query parameters such as `utm_*`
+    pub strip_tracking_params: bool,
+    /// Whether normalization treats `www.example.com` as `example.com`
+    pub ignore_www: bool,
+    /// Whether URLs are resolved through recorded redirects before matching
+    pub follow_redirects: bool,
+    /// Matching rules by domain, applying to its subdomains as well
+    pub domain_rules: HashMap<String, DomainRule>,
+}
+
+impl MatcherConfig {
+    /// Add a matching rule for `domain` and its subdomains
+    pub fn with_domain_rule(mut self, domain: &str, rule: DomainRule) -> Self {
+        let domain = domain.trim().trim_start_matches("www.").to_lowercase();
+        self.domain_rules.insert(domain, rule);
+        self
+    }
+
+    /// Rule for `host`, from the most specific configured domain
+    pub fn domain_rule(&self, host: &str) -> Option<&DomainRule> {
+        let host = host.to_lowercase();
+        let mut domain = host.strip_prefix("www.").unwrap_or(&host);
+        loop {
+            if let Some(rule) = self.domain_rules.get(domain) {
+                return Some(rule);
+            }
+            domain = domain.split_once('.')?.1;
+        }
+    }
+}
+
+/// How URLs of one domain are normalized for matching
+///
+/// Parts a rule leaves unset get the generic normalization.
+#[derive(Debug, Clone, Default)]
+pub struct DomainRule {
+    /// Query parameters that identify a page, e.g. `v` on YouTube; all
+    /// others are dropped and these are sorted by name
+    pub significant_params: Option<Vec<String>>,
+    /// Number of leading path segments that identify a page, e.g. 2 for
+    /// Jira's `/browse/KEY-123`
+    pub path_depth: Option<usize>,
+    /// Whether path and query keep their case, for case-sensitive ids
+    pub case_sensitive: bool,
+}
+
+impl DomainRule {
+    /// Rule keeping only the given query parameters
+    pub fn significant_params(params: &[&str]) -> Self {
+        Self {
+            significant_params: Some(params.iter().map(|p| p.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    /// Rule keeping only the first `depth` path segments
+    pub fn path_depth(depth: usize) -> Self {
+        Self {
+            path_depth: Some(depth),
+            ..Default::default()
+        }
+    }
 }
 
 impl Default for MatcherConfig {
@@ -34,6 +123,10 @@ impl Default for MatcherConfig {
             match_domain: true,
             match_content: true,
             normalize_urls: true,
+            strip_tracking_params: true,
+            ignore_www: true,
+            follow_redirects: true,
+            domain_rules: HashMap::new(),
         }
     }
 }
@@ -44,19 +137,25 @@ impl Default for MatcherConfig {
 /// exact URL match, domain match, and content similarity.
 pub struct TabBookmarkMatcher {
     config: MatcherConfig,
+    /// Recorded redirects, from normalized source to normalized target
+    redirects: HashMap<String, String>,
+    /// Recorded content hashes by resolved URL
+    content_hashes: HashMap<String, String>,
 }
 
 impl TabBookmarkMatcher {
     /// Create a new matcher with default configuration
     pub fn new() -> Self {
-        Self {
-            config: MatcherConfig::default(),
-        }
+        Self::with_config(MatcherConfig::default())
     }
 
     /// Create a new matcher with custom configuration
     pub fn with_config(config: MatcherConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            redirects: HashMap::new(),
+            content_hashes: HashMap::new(),
+        }
     }
 
     /// Get the current configuration
@@ -64,10 +163,77 @@ impl TabBookmarkMatcher {
         &self.config
     }
 
+    /// Record that `from` redirects to `to`
+    ///
+    /// Redirects are typically found when bookmarks are checked for
+    /// accessibility. Chains are followed by recording each hop.
+    pub fn record_redirect(&mut self, from: &str, to: &str) {
+        let from = self.normalize_url(from);
+        let to = self.normalize_url(to);
+        if from == to {
+            self.redirects.remove(&from);
+        } else {
+            self.redirects.insert(from, to);
+        }
+    }
+
+    /// Record a batch of `(from, to)` redirects
+    pub fn with_redirects<'a, I>(mut self, redirects: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        for (from, to) in redirects {
+            self.record_redirect(from, to);
+        }
+        self
+    }
+
+    /// Number of recorded redirects
+    pub fn redirect_count(&self) -> usize {
+        self.redirects.len()
+    }
+
+    /// Record the hash of the content served at `url`, e.g. from its
+    /// archive
+    pub fn record_content_hash(&mut self, url: &str, hash: &str) {
+        let url = self.resolve_url(url);
+        self.content_hashes.insert(url, hash.to_string());
+    }
+
+    /// Content hash recorded for `url`, if any
+    pub fn content_hash(&self, url: &str) -> Option<&str> {
+        self.content_hashes.get(&self.resolve_url(url)).map(String::as_str)
+    }
+
+    /// Normalized form of `url` after following recorded redirects
+    ///
+    /// Cycles and chains longer than [`MAX_REDIRECT_HOPS`] stop at the last
+    /// URL reached.
+    pub fn resolve_url(&self, url: &str) -> String {
+        let mut current = self.normalize_url(url);
+        if !self.config.follow_redirects {
+            return current;
+        }
+
+        let mut seen = HashSet::new();
+        while seen.len() < MAX_REDIRECT_HOPS {
+            let Some(next) = self.redirects.get(&current) else {
+                break;
+            };
+            if !seen.insert(current.clone()) {
+                break;
+            }
+            current = next.clone();
+        }
+        current
+    }
+
     /// Normalize a URL for comparison
     ///
-    /// This removes trailing slashes, normalizes the scheme, and
-    /// handles common URL variations.
+    /// This removes trailing slashes and fragments, normalizes the scheme,
+    /// and, as configured, drops tracking parameters and a `www.` prefix.
+    /// A [`DomainRule`] for the host decides which path segments and query
+    /// parameters are kept.
     pub fn normalize_url(&self, url: &str) -> String {
         if !self.config.normalize_urls {
             return url.to_string();
@@ -75,11 +241,14 @@ impl TabBookmarkMatcher {
 
         // Try to parse the URL
         if let Ok(parsed) = Url::parse(url) {
-            let mut normalized = format!(
-                "{}://{}",
-                parsed.scheme(),
-                parsed.host_str().unwrap_or("")
-            );
+            let mut host = parsed.host_str().unwrap_or("").to_lowercase();
+            if self.config.ignore_www {
+                if let Some(stripped) = host.strip_prefix("www.") {
+                    host = stripped.to_string();
+                }
+            }
+            let rule = self.config.domain_rule(&host);
+            let mut normalized = format!("{}://{}", parsed.scheme(), host);
 
             // Add port if non-standard
             if let Some(port) = parsed.port() {
@@ -92,20 +261,48 @@ impl TabBookmarkMatcher {
                 }
             }
 
+            let mut rest = String::new();
+
             // Add path, removing trailing slash
             let path = parsed.path();
-            if path != "/" {
-                let trimmed = path.trim_end_matches('/');
-                normalized.push_str(trimmed);
+            match rule.and_then(|r| r.path_depth) {
+                Some(depth) => {
+                    for segment in path.split('/').filter(|s| !s.is_empty()).take(depth) {
+                        rest.push('/');
+                        rest.push_str(segment);
+                    }
+                }
+                None if path != "/" => rest.push_str(path.trim_end_matches('/')),
+                None => {}
             }
 
-            // Add query string if present
+            // Add query string if present, without tracking parameters
             if let Some(query) = parsed.query() {
-                normalized.push('?');
-                normalized.push_str(query);
+                let mut params: Vec<&str> = query
+                    .split('&')
+                    .filter(|pair| !pair.is_empty())
+                    .filter(|pair| {
+                        !self.config.strip_tracking_params || !data_access::is_tracking_param(pair)
+                    })
+                    .collect();
+                if let Some(significant) = rule.and_then(|r| r.significant_params.as_ref()) {
+                    params.retain(|pair| {
+                        let name = pair.split('=').next().unwrap_or_default();
+                        significant.iter().any(|p| p.eq_ignore_ascii_case(name))
+                    });
+                    params.sort_by_key(|pair| pair.split('=').next().unwrap_or_default().to_lowercase());
+                }
+                if !params.is_empty() {
+                    rest.push('?');
+                    rest.push_str(&params.join("&"));
+                }
             }
 
-            normalized.to_lowercase()
+            if rule.is_some_and(|r| r.case_sensitive) {
+                normalized.to_lowercase() + &rest
+            } else {
+                (normalized + &rest).to_lowercase()
+            }
         } else {
             url.to_lowercase()
         }
@@ -118,9 +315,10 @@ impl TabBookmarkMatcher {
             .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
     }
 
-    /// Check if two URLs match exactly (after normalization)
+    /// Check if two URLs match exactly (after normalization and
+    /// following recorded redirects)
     pub fn urls_match_exact(&self, url1: &str, url2: &str) -> bool {
-        self.normalize_url(url1) == self.normalize_url(url2)
+        self.resolve_url(url1) == self.resolve_url(url2)
     }
 
     /// Check if two URLs are from the same domain
@@ -131,6 +329,36 @@ impl TabBookmarkMatcher {
         }
     }
 
+    /// Check if two URLs have the same recorded content hash
+    pub fn urls_match_content(&self, url1: &str, url2: &str) -> bool {
+        match (self.content_hash(url1), self.content_hash(url2)) {
+            (Some(h1), Some(h2)) => h1 == h2,
+            _ => false,
+        }
+    }
+
+    /// Similarity of two titles, from 0.0 (no words shared) to 1.0
+    ///
+    /// Compares the sets of lowercase words, ignoring punctuation.
+    pub fn title_similarity(&self, title1: &str, title2: &str) -> f32 {
+        fn words(title: &str) -> HashSet<String> {
+            title
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|w| !w.is_empty())
+                .map(str::to_lowercase)
+                .collect()
+        }
+
+        let words1 = words(title1);
+        let words2 = words(title2);
+        if words1.is_empty() || words2.is_empty() {
+            return 0.0;
+        }
+        let intersection = words1.intersection(&words2).count();
+        let union = words1.union(&words2).count();
+        intersection as f32 / union as f32
+    }
+
     /// Find all bookmarks that match a given tab
     ///
     /// Returns a list of MatchInfo for all matching bookmarks,
@@ -164,26 +392,40 @@ impl TabBookmarkMatcher {
         bookmark: &BookmarkInfo,
         now: chrono::DateTime<chrono::Utc>,
     ) -> Option<MatchInfo> {
+        let matched = |match_type: MatchType, confidence: f32| MatchInfo {
+            tab_id: tab.id.clone(),
+            bookmark_id: bookmark.id.clone(),
+            match_type,
+            confidence,
+            matched_at: now,
+        };
+
         // Check exact URL match first (highest priority)
         if self.config.match_exact_url && self.urls_match_exact(&tab.url, &bookmark.url) {
-            return Some(MatchInfo {
-                tab_id: tab.id.clone(),
-                bookmark_id: bookmark.id.clone(),
-                match_type: MatchType::ExactUrl,
-                confidence: 1.0,
-                matched_at: now,
-            });
+            return Some(matched(MatchType::ExactUrl, EXACT_CONFIDENCE));
+        }
+
+        // The same content under another URL
+        if self.config.match_content && self.urls_match_content(&tab.url, &bookmark.url) {
+            return Some(matched(MatchType::SameContent, SAME_CONTENT_CONFIDENCE));
+        }
+
+        if !self.urls_match_domain(&tab.url, &bookmark.url) {
+            return None;
+        }
+
+        // A similar title on the same domain
+        if self.config.match_content {
+            let similarity = self.title_similarity(&tab.title, &bookmark.title);
+            if similarity >= self.config.content_similarity_threshold {
+                let (low, high) = SIMILAR_TITLE_CONFIDENCE;
+                return Some(matched(MatchType::SimilarTitle, low + (high - low) * similarity));
+            }
         }
 
         // Check domain match
-        if self.config.match_domain && self.urls_match_domain(&tab.url, &bookmark.url) {
-            return Some(MatchInfo {
-                tab_id: tab.id.clone(),
-                bookmark_id: bookmark.id.clone(),
-                match_type: MatchType::SameDomain,
-                confidence: 0.5,
-                matched_at: now,
-            });
+        if self.config.match_domain {
+            return Some(matched(MatchType::SameDomain, SAME_DOMAIN_CONFIDENCE));
         }
 
         None
@@ -241,10 +483,10 @@ impl TabBookmarkMatcher {
 
     /// Check if a tab has any matching bookmark
     pub fn tab_has_bookmark_match(&self, tab: &TabInfo, bookmarks: &[BookmarkInfo]) -> bool {
-        bookmarks.iter().any(|b| {
-            (self.config.match_exact_url && self.urls_match_exact(&tab.url, &b.url))
-                || (self.config.match_domain && self.urls_match_domain(&tab.url, &b.url))
-        })
+        let now = chrono::Utc::now();
+        bookmarks
+            .iter()
+            .any(|b| self.match_tab_bookmark(tab, b, now).is_some())
     }
 }
 
@@ -384,6 +626,127 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cosmetic_url_differences_match_exactly() {
+        let matcher = TabBookmarkMatcher::new();
+        let tab = create_test_tab(
+            "https://www.example.com/article/?utm_source=feed&id=7#comments",
+            "Article",
+        );
+        let bookmark = create_test_bookmark("https://example.com/article?id=7", "Article");
+
+        let matches = matcher.find_matches_for_tab(&tab, &[bookmark]);
+        assert_eq!(matches.len(), 1);
+        assert!(matches!(matches[0].match_type, MatchType::ExactUrl));
+
+        let strict = TabBookmarkMatcher::with_config(MatcherConfig {
+            strip_tracking_params: false,
+            ignore_www: false,
+            ..Default::default()
+        });
+        assert!(!strict.urls_match_exact(&tab.url, "https://example.com/article?id=7"));
+    }
+
+    #[test]
+    fn test_redirect_chain_match() {
+        let mut matcher = TabBookmarkMatcher::new();
+        matcher.record_redirect("http://old.example.com/post", "https://example.com/p/1");
+        matcher.record_redirect("https://example.com/p/1", "https://blog.example.org/post-1");
+        // Cycles stop instead of looping
+        matcher.record_redirect("https://a.test/", "https://b.test/");
+        matcher.record_redirect("https://b.test/", "https://a.test/");
+
+        let tab = create_test_tab("https://blog.example.org/post-1", "Post");
+        let bookmark = create_test_bookmark("http://old.example.com/post/", "Post");
+        let matches = matcher.find_matches_for_tab(&tab, &[bookmark]);
+        assert_eq!(matches.len(), 1);
+        assert!(matches!(matches[0].match_type, MatchType::ExactUrl));
+        assert!(!matcher.urls_match_exact("https://a.test/", "https://c.test/"));
+
+        let no_redirects = TabBookmarkMatcher::with_config(MatcherConfig {
+            follow_redirects: false,
+            ..Default::default()
+        })
+        .with_redirects([("http://old.example.com/post", "https://blog.example.org/post-1")]);
+        assert_eq!(no_redirects.redirect_count(), 1);
+        assert!(!no_redirects.urls_match_exact(&tab.url, "http://old.example.com/post"));
+    }
+
+    #[test]
+    fn test_match_tiers() {
+        let mut matcher = TabBookmarkMatcher::new();
+        matcher.record_content_hash("https://example.com/a?id=1", "abc123");
+        matcher.record_content_hash("https://mirror.example.org/a", "abc123");
+
+        let tab = create_test_tab("https://example.com/a?id=1", "Rust Async Book: Pinning");
+        let bookmarks = [
+            create_test_bookmark("https://www.example.com/a?id=1", "Old title"),
+            create_test_bookmark("https://mirror.example.org/a", "Mirror"),
+            create_test_bookmark("https://example.com/b", "Pinning - Rust Async Book"),
+            create_test_bookmark("https://example.com/c", "Unrelated"),
+            create_test_bookmark("https://other.org/d", "Rust Async Book: Pinning"),
+        ];
+
+        let matches = matcher.find_matches_for_tab(&tab, &bookmarks);
+        assert_eq!(matches.len(), 4);
+        assert!(matches!(matches[0].match_type, MatchType::ExactUrl));
+        assert!(matches!(matches[1].match_type, MatchType::SameContent));
+        assert!(matches!(matches[2].match_type, MatchType::SimilarTitle));
+        assert!(matches!(matches[3].match_type, MatchType::SameDomain));
+        let tiers: Vec<MatchTier> = matches.iter().map(MatchInfo::tier).collect();
+        assert_eq!(tiers, vec![MatchTier::Exact, MatchTier::Strong, MatchTier::Weak, MatchTier::Weak]);
+        assert!(matches.windows(2).all(|w| w[0].confidence > w[1].confidence));
+
+        let no_content = TabBookmarkMatcher::with_config(MatcherConfig {
+            match_content: false,
+            match_domain: false,
+            ..Default::default()
+        });
+        assert_eq!(no_content.find_matches_for_tab(&tab, &bookmarks).len(), 1);
+    }
+
+    #[test]
+    fn test_domain_rules() {
+        let config = MatcherConfig::default()
+            .with_domain_rule(
+                "youtube.com",
+                DomainRule {
+                    case_sensitive: true,
+                    ..DomainRule::significant_params(&["v"])
+                },
+            )
+            .with_domain_rule(
+                "jira.example.com",
+                DomainRule {
+                    significant_params: Some(vec![]),
+                    ..DomainRule::path_depth(2)
+                },
+            );
+        let matcher = TabBookmarkMatcher::with_config(config);
+
+        assert!(matcher.config().domain_rule("m.youtube.com").is_some());
+        assert!(matcher.config().domain_rule("notyoutube.com").is_none());
+        assert!(matcher.urls_match_exact(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtube.com/watch?t=42&v=dQw4w9WgXcQ&list=PL1",
+        ));
+        assert!(!matcher.urls_match_exact(
+            "https://youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtube.com/watch?v=DQW4W9WGXCQ",
+        ));
+        assert!(matcher.urls_match_exact(
+            "https://jira.example.com/browse/WPM-12/comments?focused=1",
+            "https://jira.example.com/browse/wpm-12",
+        ));
+        assert!(!matcher.urls_match_exact(
+            "https://jira.example.com/browse/WPM-12",
+            "https://jira.example.com/browse/WPM-13",
+        ));
+
+        // Domains without a rule keep the generic normalization
+        assert!(!matcher.urls_match_exact("https://example.com/a?v=1&t=2", "https://example.com/a?v=1"));
+    }
+
     #[test]
     fn test_exact_url_match() {
         let matcher = TabBookmarkMatcher::new();
@@ -0,0 +1,595 @@
+//! Full-Page Archiving
+//!
+//! Archives whole pages so a dead link still has its content: when a tab
+//! is closed and when a bookmark is flagged for archiving, the page is
+//! fetched and archived with its text and media by the content archiver,
+//! which makes the text searchable. A page still open in a tab is also
+//! captured as rendered, in MHTML through the browser's DevTools, and the
+//! capture is stored as a snapshot of the archive. When the page can no
+//! longer be fetched, the HTML inside the capture is archived instead.
+//! Each archive can also keep a reader version of its page, with the title,
+//! byline, main content and images, for the UI to render offline.
+
+use web_page_manager_core::*;
+use browser_connector::{BrowserConnector, BrowserConnectorManager, TabEvent};
+use data_access::{
+    ContentArchive, PageRepository, ReaderView, ReaderViewRepository, Snapshot, SnapshotKind, SnapshotRepository,
+};
+use ai_processor_ffi::reader;
+use crate::content_archiver::{ArchiveResult, ContentArchiver};
+use chrono::Duration;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Configuration for full-page archiving
+#[derive(Debug, Clone)]
+pub struct PageArchiverConfig {
+    /// Whether closed tabs are archived
+    pub archive_closed_tabs: bool,
+    /// Bookmarks in a folder with this name, ignoring case, are archived
+    pub archive_folder: String,
+    /// Days before a page archived earlier is archived again
+    pub rearchive_after_days: u32,
+}
+
+impl Default for PageArchiverConfig {
+    fn default() -> Self {
+        Self {
+            archive_closed_tabs: true,
+            archive_folder: "archive".to_string(),
+            rearchive_after_days: 7,
+        }
+    }
+}
+
+/// What caused a page to be archived
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArchiveTrigger {
+    TabClosed,
+    BookmarkFlagged,
+    /// Archived on request
+    Manual,
+}
+
+/// Outcome of archiving one page
+#[derive(Debug, Clone)]
+pub struct PageArchiveOutcome {
+    pub url: String,
+    pub trigger: ArchiveTrigger,
+    /// The archive, None if the page was skipped or archiving failed
+    pub archive: Option<ArchiveResult>,
+    /// The MHTML capture of the rendered page, if the browser took one
+    pub snapshot: Option<Snapshot>,
+    /// The reader version of the page, None if it has too little readable
+    /// content
+    pub reader_view: Option<ReaderView>,
+    /// Why the page was skipped, None if it was archived or failed
+    pub skipped: Option<String>,
+    /// Why archiving failed, None if it succeeded
+    pub error: Option<String>,
+}
+
+impl PageArchiveOutcome {
+    fn new(url: &str, trigger: ArchiveTrigger) -> Self {
+        Self {
+            url: url.to_string(),
+            trigger,
+            archive: None,
+            snapshot: None,
+            reader_view: None,
+            skipped: None,
+            error: None,
+        }
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archive.is_some()
+    }
+}
+
+/// Archiver capturing complete pages on tab close and flagged bookmarks
+pub struct PageArchiver {
+    config: PageArchiverConfig,
+    archiver: Arc<ContentArchiver>,
+    pages: Arc<dyn PageRepository>,
+    snapshots: Option<Arc<dyn SnapshotRepository>>,
+    reader_views: Option<Arc<dyn ReaderViewRepository>>,
+}
+
+impl PageArchiver {
+    /// Create an archiver storing archives through `archiver`, for pages
+    /// of `pages`
+    ///
+    /// `archiver` needs an archive repository for archives to be kept and
+    /// searchable.
+    pub fn new(archiver: Arc<ContentArchiver>, pages: Arc<dyn PageRepository>) -> Self {
+        Self {
+            config: PageArchiverConfig::default(),
+            archiver,
+            pages,
+            snapshots: None,
+            reader_views: None,
+        }
+    }
+
+    /// Use a custom configuration
+    pub fn with_config(mut self, config: PageArchiverConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Keep MHTML captures in `snapshots`; without it pages are archived
+    /// without a capture
+    pub fn with_snapshots(mut self, snapshots: Arc<dyn SnapshotRepository>) -> Self {
+        self.snapshots = Some(snapshots);
+        self
+    }
+
+    /// Keep reader versions of archived pages in `reader_views`
+    pub fn with_reader_views(mut self, reader_views: Arc<dyn ReaderViewRepository>) -> Self {
+        self.reader_views = Some(reader_views);
+        self
+    }
+
+    /// Get the current configuration
+    pub fn config(&self) -> &PageArchiverConfig {
+        &self.config
+    }
+
+    /// Whether a bookmark is flagged for archiving by its folder
+    pub fn is_flagged(&self, bookmark: &BookmarkInfo) -> bool {
+        bookmark.folder_path.iter().any(|f| f.eq_ignore_ascii_case(&self.config.archive_folder))
+    }
+
+    /// Archive an open tab, capturing it as rendered
+    pub async fn archive_tab<C: BrowserConnector + ?Sized>(
+        &self,
+        connector: &C,
+        tab: &TabInfo,
+        trigger: ArchiveTrigger,
+    ) -> PageArchiveOutcome {
+        let mut outcome = PageArchiveOutcome::new(&tab.url, trigger);
+        if tab.is_private {
+            outcome.skipped = Some("Private tabs are not archived".to_string());
+            return outcome;
+        }
+        let mhtml = match connector.capture_mhtml(&tab.id).await {
+            Ok(mhtml) => mhtml,
+            Err(e) => {
+                warn!("Failed to capture {} as MHTML: {}", tab.url, e);
+                None
+            }
+        };
+        self.archive(connector, &tab.url, &tab.title, page_source(tab), mhtml, outcome).await
+    }
+
+    /// Archive the closed tabs among `events`
+    ///
+    /// A closed tab can no longer be captured, so its page is fetched.
+    pub async fn handle_tab_events<C: BrowserConnector + ?Sized>(
+        &self,
+        connector: &C,
+        events: &[TabEvent],
+    ) -> Vec<PageArchiveOutcome> {
+        let mut outcomes = Vec::new();
+        if !self.config.archive_closed_tabs {
+            return outcomes;
+        }
+        for event in events {
+            if let TabEvent::Closed { last_known_info: Some(tab), .. } = event {
+                let mut outcome = PageArchiveOutcome::new(&tab.url, ArchiveTrigger::TabClosed);
+                if tab.is_private {
+                    outcome.skipped = Some("Private tabs are not archived".to_string());
+                    outcomes.push(outcome);
+                    continue;
+                }
+                outcomes.push(self.archive(connector, &tab.url, &tab.title, page_source(tab), None, outcome).await);
+            }
+        }
+        outcomes
+    }
+
+    /// Archive the bookmarks of `bookmarks` flagged for archiving
+    pub async fn archive_flagged_bookmarks<C: BrowserConnector + ?Sized>(
+        &self,
+        connector: &C,
+        bookmarks: &[BookmarkInfo],
+    ) -> Vec<PageArchiveOutcome> {
+        let mut outcomes = Vec::new();
+        for bookmark in bookmarks.iter().filter(|b| self.is_flagged(b)) {
+            let source = PageSourceType::Bookmark {
+                browser: bookmark.browser_type,
+                bookmark_id: bookmark.id.clone(),
+            };
+            let outcome = PageArchiveOutcome::new(&bookmark.url, ArchiveTrigger::BookmarkFlagged);
+            outcomes.push(self.archive(connector, &bookmark.url, &bookmark.title, source, None, outcome).await);
+        }
+        let archived = outcomes.iter().filter(|o| o.is_archived()).count();
+        info!("Archived {} of {} flagged bookmarks", archived, outcomes.len());
+        outcomes
+    }
+
+    /// Archive an open tab through the connector of its browser
+    pub async fn archive_tab_via_manager(
+        &self,
+        manager: &BrowserConnectorManager,
+        tab: &TabInfo,
+        trigger: ArchiveTrigger,
+    ) -> PageArchiveOutcome {
+        let mut outcome = PageArchiveOutcome::new(&tab.url, trigger);
+        if tab.is_private {
+            outcome.skipped = Some("Private tabs are not archived".to_string());
+            return outcome;
+        }
+        let mhtml = manager.capture_mhtml(tab.browser_type, &tab.id).await.unwrap_or_else(|e| {
+            warn!("Failed to capture {} as MHTML: {}", tab.url, e);
+            None
+        });
+        let html = manager.fetch_page_content(tab.browser_type, &tab.url).await.map(|c| c.html);
+        self.store(&tab.url, &tab.title, page_source(tab), html, mhtml, outcome).await
+    }
+
+    /// Archives whose text matches `query`, best match first
+    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<ContentArchive>> {
+        self.archiver.search_archives(query, limit).await
+    }
+
+    /// Reader version of an archive, if one was kept
+    pub async fn reader_view(&self, archive_id: &ArchiveId) -> Result<Option<ReaderView>> {
+        match &self.reader_views {
+            Some(reader_views) => reader_views.get(archive_id).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn archive<C: BrowserConnector + ?Sized>(
+        &self,
+        connector: &C,
+        url: &str,
+        title: &str,
+        source: PageSourceType,
+        mhtml: Option<Vec<u8>>,
+        outcome: PageArchiveOutcome,
+    ) -> PageArchiveOutcome {
+        let html = connector.fetch_page_content(url).await.map(|c| c.html);
+        self.store(url, title, source, html, mhtml, outcome).await
+    }
+
+    /// Archive fetched `html`, or the HTML of `mhtml` when the fetch failed
+    async fn store(
+        &self,
+        url: &str,
+        title: &str,
+        source: PageSourceType,
+        html: Result<String>,
+        mhtml: Option<Vec<u8>>,
+        mut outcome: PageArchiveOutcome,
+    ) -> PageArchiveOutcome {
+        let result: Result<Option<ArchiveResult>> = async {
+            let page = self.page_for(url, title, source).await?;
+            if let Some(previous) = self.archiver.get_archive_by_page(&page.id).await? {
+                if Utc::now() - previous.archived_at < Duration::days(i64::from(self.config.rearchive_after_days)) {
+                    return Ok(None);
+                }
+            }
+
+            let html = match (html, mhtml.as_deref().and_then(mhtml_html)) {
+                (Ok(html), _) => html,
+                (Err(e), Some(captured)) => {
+                    warn!("Failed to fetch {}, archiving its capture: {}", url, e);
+                    captured
+                }
+                (Err(e), None) => return Err(e),
+            };
+            let archived = self.archiver.archive_page(page.id, url, &html).await?;
+            outcome.snapshot = match (&self.snapshots, mhtml) {
+                (Some(snapshots), Some(mhtml)) => {
+                    snapshots
+                        .store(&archived.archive.id, SnapshotKind::Mhtml, &mhtml, "multipart/related")
+                        .await?
+                }
+                _ => None,
+            };
+            outcome.reader_view = self.store_reader_view(&archived.archive.id, url, &html).await?;
+            Ok(Some(archived))
+        }
+        .await;
+
+        match result {
+            Ok(Some(archived)) => outcome.archive = Some(archived),
+            Ok(None) => outcome.skipped = Some("Archived recently".to_string()),
+            Err(e) => {
+                warn!("Failed to archive {}: {}", url, e);
+                outcome.error = Some(e.to_string());
+            }
+        }
+        outcome
+    }
+
+    /// Extract and keep the reader version of an archived page
+    async fn store_reader_view(&self, archive_id: &ArchiveId, url: &str, html: &str) -> Result<Option<ReaderView>> {
+        let Some(reader_views) = &self.reader_views else {
+            return Ok(None);
+        };
+        let Some(document) = reader::extract(html, Some(url)) else {
+            return Ok(None);
+        };
+        let view = ReaderView {
+            archive_id: archive_id.clone(),
+            title: document.title,
+            byline: document.byline,
+            content_html: document.content_html,
+            content_text: document.text,
+            images: document.images,
+            word_count: document.word_count,
+            created_at: Utc::now(),
+        };
+        reader_views.save(&view).await?;
+        Ok(Some(view))
+    }
+
+    /// The saved page of `url`, saving one if there is none
+    async fn page_for(&self, url: &str, title: &str, source: PageSourceType) -> Result<UnifiedPageInfo> {
+        if let Some(page) = self.pages.get_by_url(url).await? {
+            return Ok(page);
+        }
+        let page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: url.to_string(),
+            title: title.to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: source,
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        self.pages.save(&page).await?;
+        // Saving merges pages with the same canonical URL
+        Ok(self.pages.get_by_url(url).await?.unwrap_or(page))
+    }
+}
+
+fn page_source(tab: &TabInfo) -> PageSourceType {
+    PageSourceType::ActiveTab {
+        browser: tab.browser_type,
+        tab_id: tab.id.clone(),
+    }
+}
+
+/// The HTML document of an MHTML capture, decoding quoted-printable and
+/// base64 parts
+pub fn mhtml_html(mhtml: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(mhtml);
+    let boundary = text
+        .split("boundary=")
+        .nth(1)?
+        .trim_start_matches('"')
+        .split(['"', ';', '\r', '\n'])
+        .next()?
+        .to_string();
+
+    for part in text.split(&format!("--{}", boundary)).skip(1) {
+        let Some((headers, body)) = part.split_once("\r\n\r\n").or_else(|| part.split_once("\n\n")) else {
+            continue;
+        };
+        let headers = headers.to_lowercase();
+        if !headers.contains("content-type: text/html") {
+            continue;
+        }
+        let body = if headers.contains("content-transfer-encoding: quoted-printable") {
+            decode_quoted_printable(body)
+        } else if headers.contains("content-transfer-encoding: base64") {
+            decode_base64(body)?
+        } else {
+            body.as_bytes().to_vec()
+        };
+        return Some(String::from_utf8_lossy(&body).trim().to_string());
+    }
+    None
+}
+
+fn decode_quoted_printable(body: &str) -> Vec<u8> {
+    let bytes = body.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'=' {
+            out.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        // Soft line break
+        if bytes[i + 1..].starts_with(b"\r\n") {
+            i += 3;
+        } else if bytes[i + 1..].starts_with(b"\n") {
+            i += 2;
+        } else if let Some(byte) = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(b'=');
+            i += 1;
+        }
+    }
+    out
+}
+
+fn decode_base64(body: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in body.bytes().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_archiver::ContentArchiverConfig;
+    use crate::test_support::{tab, MockConnector};
+    use data_access::DatabaseManager;
+    use std::collections::HashMap;
+
+    const MHTML: &str = "From: <Saved by Blink>\r\n\
+        Subject: Guide\r\n\
+        MIME-Version: 1.0\r\n\
+        Content-Type: multipart/related;\r\n\
+        \ttype=\"text/html\";\r\n\
+        \tboundary=\"----MultipartBoundary--abc\"\r\n\
+        \r\n\
+        ------MultipartBoundary--abc\r\n\
+        Content-Type: text/html\r\n\
+        Content-Transfer-Encoding: quoted-printable\r\n\
+        \r\n\
+        <html><head><title>Guide</title></head><body><p class=3D\"x\">Rendered ga=\r\n\
+        rden guide</p></body></html>\r\n\
+        ------MultipartBoundary--abc\r\n\
+        Content-Type: text/css\r\n\
+        \r\n\
+        p { color: red; }\r\n\
+        ------MultipartBoundary--abc--\r\n";
+
+    #[test]
+    fn test_mhtml_html() {
+        let html = mhtml_html(MHTML.as_bytes()).unwrap();
+        assert!(html.starts_with("<html>"));
+        assert!(html.contains("<p class=\"x\">Rendered garden guide</p>"));
+        assert!(!html.contains("color: red"));
+
+        let base64 = "Content-Type: multipart/related; boundary=b\r\n\r\n--b\r\n\
+            Content-Type: text/html\r\nContent-Transfer-Encoding: base64\r\n\r\nPHA+aGk8L3A+\r\n--b--\r\n";
+        assert_eq!(mhtml_html(base64.as_bytes()).unwrap(), "<p>hi</p>");
+        assert!(mhtml_html(b"<html></html>").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_archive_closed_tabs_and_flagged_bookmarks() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let dir = std::env::temp_dir().join(format!("page-archiver-{}", Uuid::new_v4()));
+        let content = Arc::new(
+            ContentArchiver::with_config(ContentArchiverConfig {
+                media_storage_path: dir.join("media"),
+                ..Default::default()
+            })
+            .with_repository(Arc::new(db.archive_repository())),
+        );
+        let archiver = PageArchiver::new(content, Arc::new(db.page_repository()))
+            .with_snapshots(Arc::new(db.snapshot_repository(&dir)))
+            .with_reader_views(Arc::new(db.reader_view_repository()));
+        let connector = MockConnector {
+            pages: HashMap::from([
+                (
+                    "https://recipes.example.com/bread".to_string(),
+                    "<html><head><title>Bread</title></head><body><nav><a href=\"/\">Home</a></nav>\
+                     <article><h1>Bread</h1><p>Sourdough starter feeding keeps the culture of wild yeast active, \
+                     so feed it flour and water every day before baking a loaf.</p>\
+                     <img src=\"/loaf.jpg\" alt=\"Loaf\"></article></body></html>"
+                        .to_string(),
+                ),
+                (
+                    "https://news.example.com".to_string(),
+                    "<html><body><p>Headlines</p></body></html>".to_string(),
+                ),
+            ]),
+            mhtml: Some(MHTML.as_bytes().to_vec()),
+            ..MockConnector::new(BrowserType::Chrome)
+        };
+
+        // Closed tabs are fetched; private ones are skipped
+        let closed = tab("https://recipes.example.com/bread", BrowserType::Chrome);
+        let events = vec![
+            TabEvent::Closed {
+                tab_id: closed.id.clone(),
+                browser_type: BrowserType::Chrome,
+                timestamp: Utc::now(),
+                last_known_info: Some(closed.clone()),
+            },
+            TabEvent::Closed {
+                tab_id: TabId::new(),
+                browser_type: BrowserType::Chrome,
+                timestamp: Utc::now(),
+                last_known_info: Some(TabInfo {
+                    is_private: true,
+                    ..tab("https://private.example.com", BrowserType::Chrome)
+                }),
+            },
+        ];
+        let outcomes = archiver.handle_tab_events(&connector, &events).await;
+        assert!(outcomes[0].is_archived());
+        assert!(outcomes[0].snapshot.is_none());
+        assert!(outcomes[1].skipped.is_some());
+        let view = outcomes[0].reader_view.clone().unwrap();
+        assert_eq!(view.title, "Bread");
+        assert!(view.content_text.starts_with("Sourdough starter feeding"));
+        assert_eq!(view.images, vec!["https://recipes.example.com/loaf.jpg"]);
+        let archive_id = &outcomes[0].archive.as_ref().unwrap().archive.id;
+        assert_eq!(archiver.reader_view(archive_id).await.unwrap().unwrap().content_html, view.content_html);
+        let found = archiver.search("sourdough", 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].url, closed.url);
+
+        // Only flagged bookmarks are archived, and a page archived recently
+        // is not archived again
+        let bookmark = |url: &str, folder: &str| BookmarkInfo {
+            id: BookmarkId::new(),
+            url: url.to_string(),
+            title: url.to_string(),
+            favicon_url: None,
+            browser_type: BrowserType::Chrome,
+            folder_path: vec!["Bookmarks bar".to_string(), folder.to_string()],
+            created_at: Utc::now(),
+            last_accessed: None,
+        };
+        let outcomes = archiver
+            .archive_flagged_bookmarks(
+                &connector,
+                &[
+                    bookmark("https://news.example.com", "Archive"),
+                    bookmark("https://recipes.example.com/bread", "archive"),
+                    bookmark("https://other.example.com", "Reading"),
+                ],
+            )
+            .await;
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes[0].is_archived());
+        // Too little text for a reader version
+        assert!(outcomes[0].reader_view.is_none());
+        assert_eq!(outcomes[1].skipped.as_deref(), Some("Archived recently"));
+
+        // An open tab whose page is gone is archived from its capture
+        let gone = tab("https://gone.example.com/garden", BrowserType::Chrome);
+        let outcome = archiver.archive_tab(&connector, &gone, ArchiveTrigger::Manual).await;
+        assert!(outcome.is_archived());
+        let snapshot = outcome.snapshot.unwrap();
+        assert_eq!(snapshot.kind, SnapshotKind::Mhtml);
+        assert_eq!(db.snapshot_repository(&dir).read(&snapshot.id).await.unwrap().unwrap(), MHTML.as_bytes());
+        let found = archiver.search("garden", 10).await.unwrap();
+        assert_eq!(found.iter().map(|a| a.url.as_str()).collect::<Vec<_>>(), vec![gone.url.as_str()]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -2,13 +2,17 @@
 //!
 //! Provides functionality for remotely controlling browser tabs, including:
 //! - Tab close, activate, and create operations
+//! - Batches of tab operations with rollback and a single undo entry
 //! - Operation result verification and error handling
-//! - Operation history and undo mechanism
+//! - Operation history and undo mechanism, optionally kept across restarts
 //! - Cross-browser tab migration with session state preservation
+//! - Migration of whole windows and groups, undone as one
 //! - Fallback mechanisms for API-limited operations
+//! - Merge suggestions for duplicate bookmarks applied to their browsers, with undo
 //!
 //! # Requirements Implemented
 //! - 1.5: Execute remote control operations (close, activate, create tabs)
+//! - 2.5: Apply bookmark merge and deduplication suggestions
 //! - 8.2: Cross-browser tab migration with safe movement
 //! - 8.3: Session state and login information preservation
 //! - 8.4: Fallback solutions for API-limited operations
@@ -21,8 +25,11 @@
 //! - Property 24: Operation verification and rollback reliability
 
 use web_page_manager_core::*;
-use browser_connector::{BrowserConnector, BrowserConnectorManager};
-use std::collections::VecDeque;
+use browser_connector::{BrowserConnector, BrowserConnectorManager, MergeSuggestion};
+use data_access::{OperationLogEntry, OperationLogKind, OperationLogRepository};
+use crate::url_export::{FallbackDelivery, UrlExporter};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
@@ -68,6 +75,8 @@ pub enum TabOperationType {
     Activate,
     /// Create a new tab
     Create,
+    /// Several operations executed as one
+    Batch,
 }
 
 impl std::fmt::Display for TabOperationType {
@@ -76,6 +85,7 @@ impl std::fmt::Display for TabOperationType {
             TabOperationType::Close => write!(f, "Close"),
             TabOperationType::Activate => write!(f, "Activate"),
             TabOperationType::Create => write!(f, "Create"),
+            TabOperationType::Batch => write!(f, "Batch"),
         }
     }
 }
@@ -128,6 +138,9 @@ pub struct TabOperationRecord {
     pub undoable: bool,
     /// Related operation ID (e.g., the original operation for an undo)
     pub related_operation_id: Option<uuid::Uuid>,
+    /// Records of the operations of a batch, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub operations: Vec<TabOperationRecord>,
 }
 
 impl TabOperationRecord {
@@ -150,6 +163,7 @@ impl TabOperationRecord {
             executed_at: Utc::now(),
             undoable: matches!(operation_type, TabOperationType::Close | TabOperationType::Create),
             related_operation_id: None,
+            operations: Vec::new(),
         }
     }
 
@@ -196,6 +210,58 @@ impl TabOperationResult {
     }
 }
 
+/// An operation of a batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TabBatchOperation {
+    /// Close an open tab
+    Close { tab_id: TabId },
+    /// Create a tab, in the current window if `window_id` is None
+    Create {
+        url: String,
+        window_id: Option<String>,
+        pinned: bool,
+    },
+    /// Activate an open tab
+    Activate { tab_id: TabId },
+}
+
+impl TabBatchOperation {
+    /// Create a plain tab in the current window
+    pub fn create(url: impl Into<String>) -> Self {
+        TabBatchOperation::Create {
+            url: url.into(),
+            window_id: None,
+            pinned: false,
+        }
+    }
+}
+
+/// Result of a batch of tab operations
+#[derive(Debug, Clone)]
+pub struct TabBatchResult {
+    /// The batch record, kept in history as a single undo entry
+    pub record: TabOperationRecord,
+    /// Result of each operation, in order
+    pub results: Vec<TabOperationResult>,
+}
+
+impl TabBatchResult {
+    /// Check if every operation of the batch succeeded
+    pub fn is_success(&self) -> bool {
+        self.record.status.is_success()
+    }
+
+    /// Get the error of the operation that failed the batch
+    pub fn error_message(&self) -> Option<&str> {
+        self.results.iter().find_map(|r| r.error_message())
+    }
+
+    /// IDs of the tabs the batch created
+    pub fn new_tab_ids(&self) -> Vec<&TabId> {
+        self.results.iter().filter_map(|r| r.new_tab_id.as_ref()).collect()
+    }
+}
+
 /// Statistics about the remote tab controller
 #[derive(Debug, Clone, Default)]
 pub struct RemoteControllerStats {
@@ -217,6 +283,8 @@ pub struct RemoteControllerStats {
     pub cross_browser_migrations: usize,
     /// Fallback operations used
     pub fallback_operations: usize,
+    /// Bookmark merge suggestions applied
+    pub bookmark_merges: usize,
 }
 
 // =========================================================================
@@ -362,6 +430,15 @@ pub struct MigrationRecord {
     pub completed_at: Option<DateTime<Utc>>,
     /// Whether this migration can be rolled back
     pub rollbackable: bool,
+    /// Whether the source tab was closed, so a rollback reopens it
+    #[serde(default)]
+    pub source_closed: bool,
+    /// Whether the tab was pinned, kept when it is moved or reopened
+    #[serde(default)]
+    pub pinned: bool,
+    /// Window or group migration the tab was moved in
+    #[serde(default)]
+    pub batch_id: Option<uuid::Uuid>,
     /// Error message if migration failed
     pub error_message: Option<String>,
 }
@@ -390,6 +467,9 @@ impl MigrationRecord {
             initiated_at: Utc::now(),
             completed_at: None,
             rollbackable: false,
+            source_closed: false,
+            pinned: false,
+            batch_id: None,
             error_message: None,
         }
     }
@@ -457,6 +537,41 @@ impl MigrationResult {
     }
 }
 
+/// Tab of a window or group to migrate
+#[derive(Debug, Clone)]
+pub struct WindowTab {
+    pub tab: TabInfo,
+    pub pinned: bool,
+}
+
+/// Result of migrating a window or group of tabs
+#[derive(Debug, Clone)]
+pub struct WindowMigrationResult {
+    /// Identifies the migration for `rollback_window_migration`
+    pub batch_id: uuid::Uuid,
+    /// Window the tabs were opened in, None for the current window
+    pub window_id: Option<String>,
+    /// Result of each tab, in order
+    pub results: Vec<MigrationResult>,
+}
+
+impl WindowMigrationResult {
+    /// Tabs moved successfully
+    pub fn migrated_count(&self) -> usize {
+        self.results.iter().filter(|r| r.is_success()).count()
+    }
+
+    /// Tabs that stayed in their browser
+    pub fn failed_count(&self) -> usize {
+        self.results.len() - self.migrated_count()
+    }
+
+    /// Check if every tab was moved
+    pub fn is_success(&self) -> bool {
+        self.failed_count() == 0
+    }
+}
+
 /// Fallback data for when direct migration is not possible
 /// 
 /// Implements Requirement 8.4: Provide alternative solutions like URL export/import
@@ -472,6 +587,9 @@ pub struct FallbackData {
     pub export_content: Option<String>,
     /// Instructions for the user
     pub instructions: String,
+    /// Where the export was written or copied to, None if it was not
+    #[serde(default)]
+    pub delivered_to: Option<FallbackDelivery>,
 }
 
 /// Type of fallback operation
@@ -521,6 +639,9 @@ pub struct MigrationConfig {
     pub auto_fallback: bool,
     /// Preferred fallback type
     pub preferred_fallback: FallbackType,
+    /// Directory export fallbacks are written to; None leaves writing them
+    /// to the caller. Clipboard copies need no directory.
+    pub export_dir: Option<PathBuf>,
 }
 
 impl Default for MigrationConfig {
@@ -532,6 +653,48 @@ impl Default for MigrationConfig {
             timeout_ms: 10000,
             auto_fallback: true,
             preferred_fallback: FallbackType::UrlExport,
+            export_dir: None,
+        }
+    }
+}
+
+// =========================================================================
+// Bookmark Merge Types (Requirement 2.5)
+// =========================================================================
+
+/// Record of a merge suggestion applied to the browsers, for undo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookmarkMergeRecord {
+    /// Unique identifier for this merge
+    pub id: uuid::Uuid,
+    /// Duplicate group the suggestion was made for
+    pub group_id: uuid::Uuid,
+    /// The kept bookmark as it was before the merge
+    pub kept: BookmarkInfo,
+    /// Whether the kept bookmark's title and folder were changed
+    pub kept_updated: bool,
+    /// Redundant bookmarks deleted from their browsers, as they were
+    pub deleted: Vec<BookmarkInfo>,
+    /// Status of the merge
+    pub status: OperationStatus,
+    /// Timestamp when the merge was applied
+    pub executed_at: DateTime<Utc>,
+    /// Whether this merge can be undone
+    pub undoable: bool,
+}
+
+impl BookmarkMergeRecord {
+    /// Create a record of a merge about to be applied
+    pub fn new(suggestion: &MergeSuggestion) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4(),
+            group_id: suggestion.group_id,
+            kept: suggestion.keep_bookmark.clone(),
+            kept_updated: false,
+            deleted: Vec::new(),
+            status: OperationStatus::PendingVerification,
+            executed_at: Utc::now(),
+            undoable: false,
         }
     }
 }
@@ -549,8 +712,14 @@ pub struct RemoteTabController {
     operation_history: Arc<RwLock<VecDeque<TabOperationRecord>>>,
     /// Migration history for cross-browser operations
     migration_history: Arc<RwLock<VecDeque<MigrationRecord>>>,
+    /// History of bookmark merges applied to the browsers
+    merge_history: Arc<RwLock<VecDeque<BookmarkMergeRecord>>>,
     /// Statistics
     stats: Arc<RwLock<RemoteControllerStats>>,
+    /// Log keeping the histories across restarts
+    operation_log: Option<Arc<dyn OperationLogRepository>>,
+    /// Exporter delivering the export fallbacks of migrations
+    url_exporter: Option<UrlExporter>,
 }
 
 impl RemoteTabController {
@@ -565,8 +734,53 @@ impl RemoteTabController {
             config,
             operation_history: Arc::new(RwLock::new(VecDeque::new())),
             migration_history: Arc::new(RwLock::new(VecDeque::new())),
+            merge_history: Arc::new(RwLock::new(VecDeque::new())),
             stats: Arc::new(RwLock::new(RemoteControllerStats::default())),
+            operation_log: None,
+            url_exporter: None,
+        }
+    }
+
+    /// Deliver the export fallbacks of migrations through `exporter`: to
+    /// the clipboard, or into the configured export directory
+    pub fn with_url_exporter(mut self, exporter: UrlExporter) -> Self {
+        self.url_exporter = Some(exporter);
+        self
+    }
+
+    /// Keep the operation, migration and bookmark merge history in a log,
+    /// so undo and rollback work after a restart
+    ///
+    /// Call `load_history` to bring back the history of a previous run.
+    pub fn with_operation_log(mut self, log: Arc<dyn OperationLogRepository>) -> Self {
+        self.operation_log = Some(log);
+        self
+    }
+
+    /// Load the operation, migration and bookmark merge history kept in the
+    /// operation log, replacing the current history; returns how many
+    /// records were loaded
+    pub async fn load_history(&self) -> Result<usize> {
+        let Some(log) = &self.operation_log else {
+            return Ok(0);
+        };
+        let limit = self.config.max_history_size;
+        let operations: VecDeque<TabOperationRecord> = decode_entries(log.recent(OperationLogKind::Tab, limit).await?);
+        let migrations: VecDeque<MigrationRecord> = decode_entries(log.recent(OperationLogKind::Migration, limit).await?);
+        let merges: VecDeque<BookmarkMergeRecord> =
+            decode_entries(log.recent(OperationLogKind::BookmarkMerge, limit).await?);
+        let loaded = operations.len() + migrations.len() + merges.len();
+
+        {
+            let mut stats = self.stats.write().await;
+            stats.history_size = operations.len();
         }
+        *self.operation_history.write().await = operations;
+        *self.migration_history.write().await = migrations;
+        *self.merge_history.write().await = merges;
+
+        info!("Loaded {} operations from the operation log", loaded);
+        Ok(loaded)
     }
 
     /// Get the current configuration
@@ -695,6 +909,19 @@ impl RemoteTabController {
         &self,
         connector: &C,
         url: &str,
+    ) -> Result<TabOperationResult> {
+        self.create_tab_with(connector, url, None, false).await
+    }
+
+    /// Create a new tab in a window of the specified browser, pinned or not
+    ///
+    /// The tab opens in the current window if `window_id` is None.
+    pub async fn create_tab_with<C: BrowserConnector + ?Sized>(
+        &self,
+        connector: &C,
+        url: &str,
+        window_id: Option<&str>,
+        pinned: bool,
     ) -> Result<TabOperationResult> {
         let browser_type = connector.browser_type();
 
@@ -711,7 +938,7 @@ impl RemoteTabController {
         info!("Creating tab with URL {} in {:?}", url, browser_type);
 
         // Execute the create operation
-        let new_tab_id = match connector.create_tab(url).await {
+        let new_tab_id = match connector.create_tab_with(url, window_id, pinned).await {
             Ok(tab_id) => {
                 record.tab_id = tab_id.clone();
                 record.mark_success();
@@ -864,46 +1091,291 @@ impl RemoteTabController {
     }
 
     // =========================================================================
-    // Operation History and Undo
+    // Batch Operations
     // =========================================================================
 
-    /// Record an operation in history
-    async fn record_operation(&self, record: &TabOperationRecord) {
-        // Update statistics
-        {
-            let mut stats = self.stats.write().await;
-            stats.total_operations += 1;
+    /// Close, create and activate tabs as one logical operation
+    ///
+    /// Every operation is first checked against the browser's open tabs, so
+    /// a batch closing or activating a tab that is not open changes nothing.
+    /// Operations then run in order; if one fails, the rest are skipped and
+    /// the earlier ones are rolled back where possible: created tabs are
+    /// closed and closed tabs reopened. Activations are left as they are.
+    ///
+    /// The batch is kept in history as a single record, so one undo reverts
+    /// all of it.
+    pub async fn execute_batch<C: BrowserConnector + ?Sized>(
+        &self,
+        connector: &C,
+        operations: &[TabBatchOperation],
+    ) -> Result<TabBatchResult> {
+        let browser_type = connector.browser_type();
+        let mut batch = TabOperationRecord::new(
+            TabOperationType::Batch,
+            browser_type,
+            TabId::new(),
+            None,
+            None,
+        );
+        if operations.is_empty() {
+            batch.mark_success();
+            return Ok(TabBatchResult { record: batch, results: Vec::new() });
+        }
+
+        info!("Executing batch of {} tab operations in {:?}", operations.len(), browser_type);
+
+        let mut open: HashMap<TabId, TabInfo> = connector
+            .get_tabs()
+            .await?
+            .into_iter()
+            .map(|tab| (tab.id.clone(), tab))
+            .collect();
+        let mut missing = None;
+        let mut results = Vec::with_capacity(operations.len());
+        for (index, operation) in operations.iter().enumerate() {
+            let record = match operation {
+                TabBatchOperation::Close { tab_id } => {
+                    // A tab closed earlier in the batch is no longer open
+                    let tab = open.remove(tab_id);
+                    if tab.is_none() {
+                        missing.get_or_insert((index, tab_id.clone()));
+                    }
+                    TabOperationRecord::new(
+                        TabOperationType::Close,
+                        browser_type,
+                        tab_id.clone(),
+                        tab.as_ref().map(|t| t.url.clone()),
+                        tab.map(|t| t.title),
+                    )
+                }
+                TabBatchOperation::Activate { tab_id } => {
+                    if !open.contains_key(tab_id) {
+                        missing.get_or_insert((index, tab_id.clone()));
+                    }
+                    let mut record = TabOperationRecord::new(
+                        TabOperationType::Activate,
+                        browser_type,
+                        tab_id.clone(),
+                        None,
+                        None,
+                    );
+                    record.undoable = false;
+                    record
+                }
+                TabBatchOperation::Create { url, .. } => TabOperationRecord::new(
+                    TabOperationType::Create,
+                    browser_type,
+                    TabId::new(),
+                    Some(url.clone()),
+                    None,
+                ),
+            };
+            results.push(TabOperationResult {
+                record,
+                new_tab_id: None,
+                verified: false,
+            });
+        }
+
+        if let Some((index, tab_id)) = missing {
+            for (i, result) in results.iter_mut().enumerate() {
+                result.record.mark_failed(if i == index {
+                    format!("Tab {:?} is not open", tab_id)
+                } else {
+                    "Batch not executed".to_string()
+                });
+            }
+            batch.mark_failed(format!("Operation {} refers to a tab that is not open", index + 1));
+            warn!("Batch not executed: tab {:?} is not open", tab_id);
+            return Ok(self.finish_batch(batch, results).await);
+        }
+
+        let mut failed = None;
+        for (index, (operation, result)) in operations.iter().zip(results.iter_mut()).enumerate() {
+            let outcome = match operation {
+                TabBatchOperation::Close { tab_id } => connector.close_tab(tab_id).await,
+                TabBatchOperation::Activate { tab_id } => connector.activate_tab(tab_id).await,
+                TabBatchOperation::Create { url, window_id, pinned } => connector
+                    .create_tab_with(url, window_id.as_deref(), *pinned)
+                    .await
+                    .map(|tab_id| {
+                        result.record.tab_id = tab_id.clone();
+                        result.new_tab_id = Some(tab_id);
+                    }),
+            };
+            match outcome {
+                Ok(()) => result.record.mark_success(),
+                Err(e) => {
+                    warn!("Batch operation {} failed: {}", index + 1, e);
+                    result.record.mark_failed(e.to_string());
+                    failed = Some(index);
+                }
+            }
+            self.count_operation(&result.record).await;
+            if failed.is_some() {
+                break;
+            }
+        }
+
+        match failed {
+            Some(index) => {
+                for result in &mut results[index + 1..] {
+                    result.record.mark_failed(format!("Skipped after operation {} failed", index + 1));
+                }
+                if self.roll_back_batch(connector, &mut results[..index]).await {
+                    batch.mark_rolled_back();
+                } else {
+                    batch.mark_failed("Batch failed and could not be fully rolled back".to_string());
+                }
+            }
+            None => {
+                batch.mark_success();
+                batch.undoable = results.iter().any(|r| r.record.undoable);
+                debug!("Batch of {} tab operations succeeded", results.len());
+            }
+        }
+
+        Ok(self.finish_batch(batch, results).await)
+    }
 
-            if record.status.is_success() {
-                stats.successful_operations += 1;
-            } else if record.status.is_failed() {
-                stats.failed_operations += 1;
+    /// Revert the executed operations of a failed batch, latest first,
+    /// returning whether all of them were reverted
+    async fn roll_back_batch<C: BrowserConnector + ?Sized>(
+        &self,
+        connector: &C,
+        executed: &mut [TabOperationResult],
+    ) -> bool {
+        let mut restored = true;
+        for result in executed.iter_mut().rev() {
+            let record = &mut result.record;
+            let reverted = match (record.operation_type, &record.url) {
+                (TabOperationType::Close, Some(url)) => connector.create_tab(url).await.map(|_| ()),
+                (TabOperationType::Create, _) => connector.close_tab(&record.tab_id).await,
+                _ => continue,
+            };
+            match reverted {
+                Ok(()) => record.mark_rolled_back(),
+                Err(e) => {
+                    warn!("Failed to roll back {} of tab {:?}: {}", record.operation_type, record.tab_id, e);
+                    restored = false;
+                }
             }
+        }
+        restored
+    }
 
-            *stats
-                .operations_by_type
-                .entry(record.operation_type.to_string())
-                .or_insert(0) += 1;
+    /// Keep a batch in history with the records of its operations
+    async fn finish_batch(
+        &self,
+        mut batch: TabOperationRecord,
+        results: Vec<TabOperationResult>,
+    ) -> TabBatchResult {
+        batch.operations = results.iter().map(|r| r.record.clone()).collect();
+        self.push_history(&batch).await;
+        TabBatchResult { record: batch, results }
+    }
 
-            *stats
-                .operations_by_browser
-                .entry(record.browser_type)
-                .or_insert(0) += 1;
+    // =========================================================================
+    // Operation History and Undo
+    // =========================================================================
+
+    /// Record an operation in statistics and history
+    async fn record_operation(&self, record: &TabOperationRecord) {
+        self.count_operation(record).await;
+        self.push_history(record).await;
+    }
+
+    /// Update statistics with an operation
+    async fn count_operation(&self, record: &TabOperationRecord) {
+        let mut stats = self.stats.write().await;
+        stats.total_operations += 1;
+
+        if record.status.is_success() {
+            stats.successful_operations += 1;
+        } else if record.status.is_failed() {
+            stats.failed_operations += 1;
         }
 
+        *stats
+            .operations_by_type
+            .entry(record.operation_type.to_string())
+            .or_insert(0) += 1;
+
+        *stats
+            .operations_by_browser
+            .entry(record.browser_type)
+            .or_insert(0) += 1;
+    }
+
+    /// Add an operation to history
+    async fn push_history(&self, record: &TabOperationRecord) {
         // Add to history if undo is enabled
         if self.config.enable_undo {
-            let mut history = self.operation_history.write().await;
-            history.push_back(record.clone());
+            {
+                let mut history = self.operation_history.write().await;
+                history.push_back(record.clone());
 
-            // Trim history if needed
-            while history.len() > self.config.max_history_size {
-                history.pop_front();
+                // Trim history if needed
+                while history.len() > self.config.max_history_size {
+                    history.pop_front();
+                }
+
+                // Update history size in stats
+                let mut stats = self.stats.write().await;
+                stats.history_size = history.len();
             }
 
-            // Update history size in stats
-            let mut stats = self.stats.write().await;
-            stats.history_size = history.len();
+            self.log_record(OperationLogKind::Tab, record.id, record.executed_at, record).await;
+        }
+    }
+
+    /// Mark an operation in history as no longer undoable
+    async fn mark_not_undoable(&self, operation_id: uuid::Uuid) {
+        let updated = {
+            let mut history = self.operation_history.write().await;
+            history.iter_mut().find(|r| r.id == operation_id).map(|original| {
+                original.undoable = false;
+                original.clone()
+            })
+        };
+        if let Some(record) = updated {
+            self.log_record(OperationLogKind::Tab, record.id, record.executed_at, &record).await;
+        }
+    }
+
+    /// Write a record to the operation log, if there is one
+    ///
+    /// Failures are logged rather than returned: the operation itself has
+    /// already been carried out.
+    async fn log_record<T: Serialize>(
+        &self,
+        kind: OperationLogKind,
+        id: uuid::Uuid,
+        executed_at: DateTime<Utc>,
+        record: &T,
+    ) {
+        let Some(log) = &self.operation_log else {
+            return;
+        };
+        let record = match serde_json::to_string(record) {
+            Ok(record) => record,
+            Err(e) => {
+                warn!("Failed to serialize operation {}: {}", id, e);
+                return;
+            }
+        };
+        let entry = OperationLogEntry {
+            id,
+            kind,
+            executed_at,
+            record,
+        };
+        if let Err(e) = log.save(&entry).await {
+            warn!("Failed to log operation {}: {}", id, e);
+            return;
+        }
+        if let Err(e) = log.prune(kind, self.config.max_history_size).await {
+            warn!("Failed to prune the operation log: {}", e);
         }
     }
 
@@ -983,12 +1455,7 @@ impl RemoteTabController {
         result.record.related_operation_id = Some(operation_id);
 
         // Mark the original operation as no longer undoable
-        {
-            let mut history = self.operation_history.write().await;
-            if let Some(original) = history.iter_mut().find(|r| r.id == operation_id) {
-                original.undoable = false;
-            }
-        }
+        self.mark_not_undoable(operation_id).await;
 
         // Update undo stats
         if result.is_success() {
@@ -1045,12 +1512,7 @@ impl RemoteTabController {
         result.record.related_operation_id = Some(operation_id);
 
         // Mark the original operation as no longer undoable
-        {
-            let mut history = self.operation_history.write().await;
-            if let Some(original) = history.iter_mut().find(|r| r.id == operation_id) {
-                original.undoable = false;
-            }
-        }
+        self.mark_not_undoable(operation_id).await;
 
         // Update undo stats
         if result.is_success() {
@@ -1063,6 +1525,72 @@ impl RemoteTabController {
         Ok(result)
     }
 
+    /// Undo a batch by reverting its operations, latest first, as one batch
+    ///
+    /// Closed tabs are reopened and created tabs closed.
+    pub async fn undo_batch<C: BrowserConnector + ?Sized>(
+        &self,
+        connector: &C,
+        operation_id: uuid::Uuid,
+    ) -> Result<TabBatchResult> {
+        let operation = {
+            let history = self.operation_history.read().await;
+            history.iter().find(|r| r.id == operation_id).cloned()
+        };
+
+        let operation = operation.ok_or_else(|| {
+            WebPageManagerError::History {
+                source: HistoryError::EntryNotFound {
+                    history_id: operation_id.to_string(),
+                },
+            }
+        })?;
+
+        if operation.operation_type != TabOperationType::Batch {
+            return Err(WebPageManagerError::History {
+                source: HistoryError::RestoreFailed {
+                    reason: "Can only undo batch operations with this method".to_string(),
+                },
+            });
+        }
+
+        if !operation.undoable {
+            return Err(WebPageManagerError::History {
+                source: HistoryError::RestoreFailed {
+                    reason: "Operation cannot be undone".to_string(),
+                },
+            });
+        }
+
+        let inverse: Vec<TabBatchOperation> = operation
+            .operations
+            .iter()
+            .rev()
+            .filter(|r| r.undoable && r.status.is_success())
+            .filter_map(|r| match r.operation_type {
+                TabOperationType::Close => r.url.clone().map(TabBatchOperation::create),
+                TabOperationType::Create => Some(TabBatchOperation::Close { tab_id: r.tab_id.clone() }),
+                _ => None,
+            })
+            .collect();
+
+        let mut result = self.execute_batch(connector, &inverse).await?;
+
+        // Link the undo batch to the original
+        result.record.related_operation_id = Some(operation_id);
+
+        if result.is_success() {
+            self.mark_not_undoable(operation_id).await;
+
+            let mut stats = self.stats.write().await;
+            stats.undo_operations += 1;
+        }
+
+        info!("Undid batch operation: {:?}", operation_id);
+
+        Ok(result)
+    }
+
     /// Undo the most recent undoable operation
     pub async fn undo_last<C: BrowserConnector>(
         &self,
@@ -1083,6 +1611,14 @@ impl RemoteTabController {
                 let result = match op.operation_type {
                     TabOperationType::Close => self.undo_close(connector, op.id).await?,
                     TabOperationType::Create => self.undo_create(connector, op.id).await?,
+                    TabOperationType::Batch => {
+                        let batch = self.undo_batch(connector, op.id).await?;
+                        TabOperationResult {
+                            record: batch.record,
+                            new_tab_id: None,
+                            verified: false,
+                        }
+                    }
                     TabOperationType::Activate => {
                         // Activate operations cannot be undone
                         return Ok(None);
@@ -1108,10 +1644,203 @@ impl RemoteTabController {
         let mut history = self.operation_history.write().await;
         history.clear();
 
-        let mut stats = self.stats.write().await;
-        stats.history_size = 0;
+        let mut stats = self.stats.write().await;
+        stats.history_size = 0;
+
+        if let Some(log) = &self.operation_log {
+            if let Err(e) = log.clear(OperationLogKind::Tab).await {
+                warn!("Failed to clear the operation log: {}", e);
+            }
+        }
+
+        info!("Cleared operation history");
+    }
+
+    // =========================================================================
+    // Bookmark Merges (Requirement 2.5)
+    // =========================================================================
+
+    /// Apply a merge suggestion to the browsers
+    ///
+    /// The kept bookmark takes the best title and suggested folder of the
+    /// merged metadata, then the redundant bookmarks are deleted from their
+    /// browsers. If the kept bookmark cannot be updated nothing changes and
+    /// the error is returned. Redundant bookmarks that cannot be deleted
+    /// fail the merge but stay in place; the rest of the merge can still be
+    /// undone with `undo_merge`.
+    pub async fn apply_merge_suggestion(
+        &self,
+        manager: &BrowserConnectorManager,
+        suggestion: &MergeSuggestion,
+    ) -> Result<BookmarkMergeRecord> {
+        let kept = &suggestion.keep_bookmark;
+        let metadata = &suggestion.merged_metadata;
+        let title = if metadata.best_title.is_empty() {
+            &kept.title
+        } else {
+            &metadata.best_title
+        };
+        let folder_path = if metadata.suggested_folder_path.is_empty() {
+            &kept.folder_path
+        } else {
+            &metadata.suggested_folder_path
+        };
+
+        info!(
+            "Applying merge of {} bookmarks into {}",
+            suggestion.remove_bookmarks.len(),
+            kept.url
+        );
+
+        let mut record = BookmarkMergeRecord::new(suggestion);
+
+        // Step 1: Update the kept bookmark
+        if *title != kept.title || *folder_path != kept.folder_path {
+            manager.update_bookmark(kept.browser_type, &kept.id, title, folder_path).await?;
+            record.kept_updated = true;
+        }
+
+        // Step 2: Delete the redundant bookmarks
+        let mut failures = Vec::new();
+        for bookmark in &suggestion.remove_bookmarks {
+            if bookmark.id == kept.id && bookmark.browser_type == kept.browser_type {
+                continue;
+            }
+            match manager.delete_bookmark(bookmark.browser_type, &bookmark.id).await {
+                Ok(()) => record.deleted.push(bookmark.clone()),
+                Err(e) => {
+                    warn!("Failed to delete bookmark {}: {}", bookmark.url, e);
+                    failures.push(format!("{}: {}", bookmark.url, e));
+                }
+            }
+        }
+
+        record.status = if failures.is_empty() {
+            OperationStatus::Success
+        } else {
+            OperationStatus::Failed(failures.join("; "))
+        };
+        record.undoable = record.kept_updated || !record.deleted.is_empty();
+        self.record_merge(&record).await;
+
+        Ok(record)
+    }
+
+    /// Undo an applied merge
+    ///
+    /// The kept bookmark gets back its title and folder, and the deleted
+    /// bookmarks are created again. Bookmarks that cannot be created fail
+    /// the undo; undoing again retries them. Returns how many bookmarks
+    /// were created again.
+    pub async fn undo_merge(&self, manager: &BrowserConnectorManager, merge_id: uuid::Uuid) -> Result<usize> {
+        let record = {
+            let history = self.merge_history.read().await;
+            history.iter().find(|r| r.id == merge_id).cloned()
+        };
+        let mut record = record.ok_or_else(|| WebPageManagerError::History {
+            source: HistoryError::EntryNotFound {
+                history_id: merge_id.to_string(),
+            },
+        })?;
+        if !record.undoable {
+            return Err(WebPageManagerError::History {
+                source: HistoryError::RestoreFailed {
+                    reason: "Merge cannot be undone".to_string(),
+                },
+            });
+        }
+
+        // Step 1: Restore the kept bookmark
+        if record.kept_updated {
+            let kept = &record.kept;
+            manager
+                .update_bookmark(kept.browser_type, &kept.id, &kept.title, &kept.folder_path)
+                .await?;
+            record.kept_updated = false;
+        }
+
+        // Step 2: Create the deleted bookmarks again
+        let mut restored = 0;
+        let mut failures = Vec::new();
+        for bookmark in std::mem::take(&mut record.deleted) {
+            match manager.create_bookmark(bookmark.browser_type, &bookmark).await {
+                Ok(_) => restored += 1,
+                Err(e) => {
+                    error!("Failed to restore bookmark {}: {}", bookmark.url, e);
+                    failures.push(format!("{}: {}", bookmark.url, e));
+                    record.deleted.push(bookmark);
+                }
+            }
+        }
+
+        record.undoable = !record.deleted.is_empty();
+        if !record.undoable {
+            record.status = OperationStatus::RolledBack;
+            let mut stats = self.stats.write().await;
+            stats.undo_operations += 1;
+        }
+        self.update_merge(&record).await;
+
+        if !failures.is_empty() {
+            return Err(WebPageManagerError::History {
+                source: HistoryError::RestoreFailed {
+                    reason: failures.join("; "),
+                },
+            });
+        }
+
+        info!("Undid bookmark merge {:?}", merge_id);
+        Ok(restored)
+    }
+
+    /// Get the history of applied bookmark merges
+    pub async fn get_merge_history(&self) -> Vec<BookmarkMergeRecord> {
+        let history = self.merge_history.read().await;
+        history.iter().cloned().collect()
+    }
+
+    /// Clear the bookmark merge history
+    pub async fn clear_merge_history(&self) {
+        let mut history = self.merge_history.write().await;
+        history.clear();
+
+        if let Some(log) = &self.operation_log {
+            if let Err(e) = log.clear(OperationLogKind::BookmarkMerge).await {
+                warn!("Failed to clear the bookmark merge log: {}", e);
+            }
+        }
+
+        info!("Cleared bookmark merge history");
+    }
+
+    /// Record a merge in statistics and history
+    async fn record_merge(&self, record: &BookmarkMergeRecord) {
+        {
+            let mut stats = self.stats.write().await;
+            stats.bookmark_merges += 1;
+        }
+
+        {
+            let mut history = self.merge_history.write().await;
+            history.push_back(record.clone());
+
+            while history.len() > self.config.max_history_size {
+                history.pop_front();
+            }
+        }
+
+        self.log_record(OperationLogKind::BookmarkMerge, record.id, record.executed_at, record).await;
+    }
 
-        info!("Cleared operation history");
+    /// Replace a merge in history
+    async fn update_merge(&self, record: &BookmarkMergeRecord) {
+        {
+            let mut history = self.merge_history.write().await;
+            if let Some(stored) = history.iter_mut().find(|r| r.id == record.id) {
+                *stored = record.clone();
+            }
+        }
+        self.log_record(OperationLogKind::BookmarkMerge, record.id, record.executed_at, record).await;
     }
 
     // =========================================================================
@@ -1198,6 +1927,12 @@ impl RemoteTabController {
 
         match migration_result {
             Ok(new_tab_id) => {
+                // Steps 3 and 4: Close source tab and activate target tab,
+                // rolling back if the migration cannot complete
+                if let Err(reason) = self.complete_migration(manager, &mut record, &new_tab_id, &config).await {
+                    return Err(self.fail_migration(&mut record, reason).await);
+                }
+
                 // Migration successful
                 let session_preserved = session_state.as_ref()
                     .map(|s| s.has_preserved_data())
@@ -1205,21 +1940,6 @@ impl RemoteTabController {
                 
                 record.mark_success(new_tab_id.clone(), session_preserved);
 
-                // Step 3: Close source tab if configured
-                if config.close_source_tab {
-                    if let Err(e) = manager.close_tab(source_browser, tab_id).await {
-                        warn!("Failed to close source tab after migration: {}", e);
-                        // Don't fail the migration, just log the warning
-                    }
-                }
-
-                // Step 4: Activate target tab if configured
-                if config.activate_target_tab {
-                    if let Err(e) = manager.activate_tab(target_browser, &new_tab_id).await {
-                        warn!("Failed to activate target tab after migration: {}", e);
-                    }
-                }
-
                 // Record the migration
                 self.record_migration(&record).await;
 
@@ -1320,6 +2040,155 @@ impl RemoteTabController {
         results
     }
 
+    /// Move the tabs of a window to a new window of another browser
+    ///
+    /// The tabs open in their order and keep their pinned state where the
+    /// target can pin tabs: Firefox can, while Chrome and Edge open the
+    /// window over CDP, which cannot pin. Every tab is opened before any
+    /// source tab is closed, and a tab whose source cannot be closed is
+    /// closed again, so each tab is either moved or left where it was; tabs
+    /// that fail stay in their browser, without fallbacks. Tabs already in
+    /// the target browser are left out. The whole migration is rolled back
+    /// with `rollback_window_migration`.
+    pub async fn migrate_window(
+        &self,
+        manager: &BrowserConnectorManager,
+        tabs: &[WindowTab],
+        target_browser: BrowserType,
+        config: Option<MigrationConfig>,
+    ) -> Result<WindowMigrationResult> {
+        let config = config.unwrap_or_default();
+        let batch_id = uuid::Uuid::new_v4();
+        let tabs: Vec<&WindowTab> = tabs.iter().filter(|t| t.tab.browser_type != target_browser).collect();
+        if tabs.is_empty() {
+            return Ok(WindowMigrationResult {
+                batch_id,
+                window_id: None,
+                results: Vec::new(),
+            });
+        }
+
+        info!("Migrating {} tabs to a new {:?} window", tabs.len(), target_browser);
+
+        let window_id = match manager.create_window(target_browser).await {
+            Ok(window_id) => window_id,
+            Err(e) => {
+                warn!("Failed to open a window in {:?}, using the current one: {}", target_browser, e);
+                None
+            }
+        };
+
+        // Step 1: Open every tab in the target window
+        let mut opened = Vec::with_capacity(tabs.len());
+        for window_tab in &tabs {
+            let tab = &window_tab.tab;
+            let mut record = MigrationRecord::new(
+                tab.browser_type,
+                target_browser,
+                tab.id.clone(),
+                tab.url.clone(),
+                tab.title.clone(),
+            );
+            record.pinned = window_tab.pinned;
+            record.batch_id = Some(batch_id);
+            if config.preserve_session_state {
+                record.session_state = self
+                    .capture_session_state(manager, tab.browser_type, &tab.id, &tab.url, &tab.title)
+                    .await;
+            }
+
+            let target_tab_id = match manager
+                .create_tab_with(target_browser, &tab.url, window_id.as_deref(), window_tab.pinned)
+                .await
+            {
+                Ok(tab_id) => Some(tab_id),
+                Err(e) => {
+                    warn!("Failed to open {} in {:?}: {}", tab.url, target_browser, e);
+                    record.mark_failed(e.to_string());
+                    None
+                }
+            };
+            opened.push((record, target_tab_id));
+        }
+
+        // Step 2: Close the source tabs, rolling back tabs that cannot move
+        let tab_config = MigrationConfig {
+            activate_target_tab: false,
+            ..config.clone()
+        };
+        let mut results = Vec::with_capacity(opened.len());
+        for (mut record, target_tab_id) in opened {
+            if let Some(target_tab_id) = target_tab_id {
+                match self.complete_migration(manager, &mut record, &target_tab_id, &tab_config).await {
+                    Ok(()) => {
+                        let session_preserved = record.session_state.as_ref().is_some_and(|s| s.has_preserved_data());
+                        record.mark_success(target_tab_id, session_preserved);
+                    }
+                    Err(reason) => record.mark_failed(reason),
+                }
+            }
+            self.record_migration(&record).await;
+            results.push(MigrationResult {
+                record,
+                used_fallback: false,
+                fallback_data: None,
+            });
+        }
+
+        // Step 3: Activate the first moved tab
+        if config.activate_target_tab {
+            if let Some(first) = results.iter().find_map(|r| r.new_tab_id().filter(|_| r.is_success())) {
+                if let Err(e) = manager.activate_tab(target_browser, first).await {
+                    warn!("Failed to activate target tab after migration: {}", e);
+                }
+            }
+        }
+
+        let result = WindowMigrationResult {
+            batch_id,
+            window_id,
+            results,
+        };
+        info!(
+            "Migrated {} of {} tabs to {:?}",
+            result.migrated_count(),
+            result.results.len(),
+            target_browser
+        );
+        Ok(result)
+    }
+
+    /// Move the open tabs of a group's pages to a new window of another
+    /// browser, in the group's page order
+    ///
+    /// Browsers report no tab groups, so the group moves into a window of
+    /// its own. Its pages keep their URLs, so they stay in the group.
+    /// `pages` are the unified pages, with their tabs, to look the group's
+    /// pages up in; `pinned` lists the URLs of pinned tabs.
+    pub async fn migrate_group(
+        &self,
+        manager: &BrowserConnectorManager,
+        group: &SmartGroup,
+        pages: &[UnifiedPageInfo],
+        pinned: &[String],
+        target_browser: BrowserType,
+        config: Option<MigrationConfig>,
+    ) -> Result<WindowMigrationResult> {
+        let pages: HashMap<Uuid, &UnifiedPageInfo> = pages.iter().map(|p| (p.id, p)).collect();
+        let tabs: Vec<WindowTab> = group
+            .pages
+            .iter()
+            .filter_map(|id| pages.get(id)?.tab_info.clone())
+            .map(|tab| WindowTab {
+                pinned: pinned.contains(&tab.url),
+                tab,
+            })
+            .collect();
+
+        info!("Migrating group '{}' with {} open tabs", group.name, tabs.len());
+        self.migrate_window(manager, &tabs, target_browser, config).await
+    }
+
     /// Generate fallback export data for tabs that cannot be directly migrated
     ///
     /// Implements Requirement 8.4: Provide alternative solutions like URL export/import
@@ -1386,10 +2255,16 @@ impl RemoteTabController {
             format,
             export_content,
             instructions,
+            delivered_to: None,
         }
     }
 
-    /// Rollback a migration by closing the target tab and reopening in source browser
+    /// Rollback a migration by reopening the source tab and closing the target tab
+    ///
+    /// The source tab is reopened first, at the URL of its captured session
+    /// state where there is one, so a failed rollback never loses the page;
+    /// it is only reopened if the migration closed it. A target tab that is
+    /// already gone does not fail the rollback.
     ///
     /// Implements Requirement 8.5: Verify operation results and provide rollback options
     ///
@@ -1410,7 +2285,7 @@ impl RemoteTabController {
             history.iter().find(|m| m.id == migration_id).cloned()
         };
 
-        let migration = migration.ok_or_else(|| {
+        let mut migration = migration.ok_or_else(|| {
             WebPageManagerError::CrossBrowser {
                 source: CrossBrowserError::RollbackFailed {
                     reason: "Migration record not found".to_string(),
@@ -1428,41 +2303,112 @@ impl RemoteTabController {
 
         info!("Rolling back migration: {:?}", migration_id);
 
-        // Step 1: Close the target tab if it exists
+        // Step 1: Reopen the tab in the source browser
+        if migration.source_closed {
+            let restored = self.reopen_source_tab(manager, &mut migration, None).await.map_err(|e| {
+                error!("Failed to restore tab during rollback: {}", e);
+                WebPageManagerError::CrossBrowser {
+                    source: CrossBrowserError::RollbackFailed {
+                        reason: format!("Failed to restore tab: {}", e),
+                    },
+                }
+            })?;
+
+            // Activate the restored tab
+            let _ = manager.activate_tab(migration.source_browser, &restored).await;
+        }
+
+        // Step 2: Close the target tab if it exists
         if let Some(target_tab_id) = &migration.target_tab_id {
             if let Err(e) = manager.close_tab(migration.target_browser, target_tab_id).await {
                 warn!("Failed to close target tab during rollback: {}", e);
             }
         }
 
-        // Step 2: Reopen the tab in the source browser
-        let new_tab_result = manager.create_tab(migration.source_browser, &migration.url).await;
-        
-        match new_tab_result {
-            Ok(new_tab_id) => {
-                // Activate the restored tab
-                let _ = manager.activate_tab(migration.source_browser, &new_tab_id).await;
+        // Update the migration record
+        migration.mark_rolled_back();
+        self.update_migration(&migration).await;
 
-                // Update the migration record
-                {
-                    let mut history = self.migration_history.write().await;
-                    if let Some(record) = history.iter_mut().find(|m| m.id == migration_id) {
-                        record.mark_rolled_back();
+        info!("Successfully rolled back migration: {:?}", migration_id);
+        Ok(())
+    }
+
+    /// Rollback a window or group migration as a whole
+    ///
+    /// Closed source tabs reopen in a new window of their browser, in their
+    /// order and with their pinned state, and the moved tabs are closed.
+    /// Tabs that cannot be reopened keep their moved tab and fail the
+    /// rollback; rolling back again retries them. Returns how many tabs
+    /// were rolled back.
+    pub async fn rollback_window_migration(
+        &self,
+        manager: &BrowserConnectorManager,
+        batch_id: uuid::Uuid,
+    ) -> Result<usize> {
+        let migrations: Vec<MigrationRecord> = {
+            let history = self.migration_history.read().await;
+            history
+                .iter()
+                .filter(|m| m.batch_id == Some(batch_id) && m.rollbackable)
+                .cloned()
+                .collect()
+        };
+        if migrations.is_empty() {
+            return Err(WebPageManagerError::CrossBrowser {
+                source: CrossBrowserError::RollbackFailed {
+                    reason: "No migrated tabs to roll back".to_string(),
+                },
+            });
+        }
+
+        info!("Rolling back window migration {:?} of {} tabs", batch_id, migrations.len());
+
+        let mut windows: HashMap<BrowserType, Option<String>> = HashMap::new();
+        let mut rolled_back = 0;
+        let mut failures = Vec::new();
+        for mut migration in migrations {
+            // Step 1: Reopen the tab in a new window of its browser
+            if migration.source_closed {
+                let window_id = match windows.get(&migration.source_browser) {
+                    Some(window_id) => window_id.clone(),
+                    None => {
+                        let window_id = manager.create_window(migration.source_browser).await.unwrap_or_else(|e| {
+                            warn!("Failed to open a window in {:?}: {}", migration.source_browser, e);
+                            None
+                        });
+                        windows.insert(migration.source_browser, window_id.clone());
+                        window_id
                     }
+                };
+                if let Err(e) = self.reopen_source_tab(manager, &mut migration, window_id.as_deref()).await {
+                    error!("Failed to restore {} during rollback: {}", migration.url, e);
+                    failures.push(format!("{}: {}", migration.url, e));
+                    continue;
                 }
-
-                info!("Successfully rolled back migration: {:?}", migration_id);
-                Ok(())
             }
-            Err(e) => {
-                error!("Failed to restore tab during rollback: {}", e);
-                Err(WebPageManagerError::CrossBrowser {
-                    source: CrossBrowserError::RollbackFailed {
-                        reason: format!("Failed to restore tab: {}", e),
-                    },
-                })
+
+            // Step 2: Close the moved tab if it exists
+            if let Some(target_tab_id) = &migration.target_tab_id {
+                if let Err(e) = manager.close_tab(migration.target_browser, target_tab_id).await {
+                    warn!("Failed to close target tab during rollback: {}", e);
+                }
             }
+
+            migration.mark_rolled_back();
+            self.update_migration(&migration).await;
+            rolled_back += 1;
         }
+
+        if !failures.is_empty() {
+            return Err(WebPageManagerError::CrossBrowser {
+                source: CrossBrowserError::RollbackFailed {
+                    reason: format!("Failed to restore tabs: {}", failures.join("; ")),
+                },
+            });
+        }
+
+        info!("Rolled back {} tabs of window migration {:?}", rolled_back, batch_id);
+        Ok(rolled_back)
     }
 
     /// Verify a migration was successful by checking if the target tab exists
@@ -1530,6 +2476,13 @@ impl RemoteTabController {
     pub async fn clear_migration_history(&self) {
         let mut history = self.migration_history.write().await;
         history.clear();
+
+        if let Some(log) = &self.operation_log {
+            if let Err(e) = log.clear(OperationLogKind::Migration).await {
+                warn!("Failed to clear the migration log: {}", e);
+            }
+        }
+
         info!("Cleared migration history");
     }
 
@@ -1565,6 +2518,97 @@ impl RemoteTabController {
         manager.create_tab(target_browser, url).await
     }
 
+    /// Close the source tab and activate the target tab of a migration
+    /// whose target tab was created, keeping the migration all-or-nothing
+    ///
+    /// If the source tab cannot be closed, the target tab is closed again.
+    /// If the target tab is gone once the source tab was closed, the source
+    /// tab is reopened. Either way the returned reason fails the migration.
+    async fn complete_migration(
+        &self,
+        manager: &BrowserConnectorManager,
+        record: &mut MigrationRecord,
+        target_tab_id: &TabId,
+        config: &MigrationConfig,
+    ) -> std::result::Result<(), String> {
+        if config.close_source_tab {
+            if let Err(e) = manager.close_tab(record.source_browser, &record.source_tab_id).await {
+                warn!("Failed to close source tab, rolling back migration: {}", e);
+                return Err(match manager.close_tab(record.target_browser, target_tab_id).await {
+                    Ok(()) => format!("Failed to close source tab: {}", e),
+                    Err(close) => format!(
+                        "Failed to close source tab: {}; the new tab could not be closed: {}",
+                        e, close
+                    ),
+                });
+            }
+            record.source_closed = true;
+
+            if self.config.verify_operations && !self.tab_exists(manager, record.target_browser, target_tab_id).await {
+                warn!("Target tab of migration {:?} is gone, reopening source tab", record.id);
+                return Err(match self.reopen_source_tab(manager, record, None).await {
+                    Ok(_) => "Target tab was not created".to_string(),
+                    Err(e) => format!(
+                        "Target tab was not created and the source tab could not be reopened: {}",
+                        e
+                    ),
+                });
+            }
+        }
+
+        if config.activate_target_tab {
+            if let Err(e) = manager.activate_tab(record.target_browser, target_tab_id).await {
+                warn!("Failed to activate target tab after migration: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Mark a migration as failed and record it, returning its error
+    async fn fail_migration(&self, record: &mut MigrationRecord, reason: String) -> WebPageManagerError {
+        record.mark_failed(reason.clone());
+        self.record_migration(record).await;
+
+        WebPageManagerError::CrossBrowser {
+            source: CrossBrowserError::MigrationFailed {
+                source_browser: record.source_browser,
+                target_browser: record.target_browser,
+                reason,
+            },
+        }
+    }
+
+    /// Reopen the closed source tab of a migration, pinned if it was, in
+    /// a window or the current one
+    ///
+    /// Only the URL of the captured session state can be restored through
+    /// the browser APIs; the rest of it needs deeper browser integration.
+    async fn reopen_source_tab(
+        &self,
+        manager: &BrowserConnectorManager,
+        record: &mut MigrationRecord,
+        window_id: Option<&str>,
+    ) -> Result<TabId> {
+        let url = record.session_state.as_ref().map_or(&record.url, |state| &state.url);
+        let tab_id = manager
+            .create_tab_with(record.source_browser, url, window_id, record.pinned)
+            .await?;
+        record.source_closed = false;
+        Ok(tab_id)
+    }
+
+    /// Whether a tab is open, assuming it is if the browser cannot tell
+    async fn tab_exists(&self, manager: &BrowserConnectorManager, browser: BrowserType, tab_id: &TabId) -> bool {
+        match manager.get_tabs(browser).await {
+            Ok(tabs) => tabs.iter().any(|t| &t.id == tab_id),
+            Err(e) => {
+                debug!("Cannot verify tab {:?} in {:?}: {}", tab_id, browser, e);
+                true
+            }
+        }
+    }
+
     /// Handle migration fallback when direct migration fails
     async fn handle_migration_fallback(
         &self,
@@ -1587,15 +2631,11 @@ impl RemoteTabController {
         // Try URL-only migration as first fallback
         match manager.create_tab(target_browser, url).await {
             Ok(new_tab_id) => {
-                record.mark_success_with_fallback(new_tab_id.clone(), "url_only");
-
-                if config.close_source_tab {
-                    let _ = manager.close_tab(source_browser, &record.source_tab_id).await;
+                if let Err(reason) = self.complete_migration(manager, record, &new_tab_id, config).await {
+                    return Err(self.fail_migration(record, reason).await);
                 }
 
-                if config.activate_target_tab {
-                    let _ = manager.activate_tab(target_browser, &new_tab_id).await;
-                }
+                record.mark_success_with_fallback(new_tab_id.clone(), "url_only");
 
                 self.record_migration(record).await;
 
@@ -1626,7 +2666,7 @@ impl RemoteTabController {
                     last_accessed: Utc::now(),
                 };
 
-                let fallback_data = self.generate_fallback_export(
+                let mut fallback_data = self.generate_fallback_export(
                     &[tab_info],
                     source_browser,
                     match config.preferred_fallback {
@@ -1635,6 +2675,10 @@ impl RemoteTabController {
                         _ => MigrationExportFormat::PlainText,
                     },
                 );
+                if config.preferred_fallback == FallbackType::ClipboardCopy {
+                    fallback_data.fallback_type = FallbackType::ClipboardCopy;
+                }
+                self.deliver_fallback(&mut fallback_data, config);
 
                 record.mark_success_with_fallback(TabId::new(), "export");
                 self.record_migration(record).await;
@@ -1654,6 +2698,35 @@ impl RemoteTabController {
         }
     }
 
+    /// Deliver fallback data through the URL exporter, if there is one
+    ///
+    /// A failed delivery leaves the data to the caller, as without an
+    /// exporter.
+    fn deliver_fallback(&self, data: &mut FallbackData, config: &MigrationConfig) {
+        let Some(exporter) = &self.url_exporter else {
+            return;
+        };
+        let delivered = match (data.fallback_type, &config.export_dir) {
+            (FallbackType::ClipboardCopy, _) => exporter.copy_to_clipboard(data).map(|()| FallbackDelivery::Clipboard),
+            (_, Some(directory)) => exporter.deliver(data, directory),
+            (_, None) => return,
+        };
+        match delivered {
+            Ok(FallbackDelivery::Clipboard) => {
+                data.instructions = "The URLs were copied to the clipboard. Paste them into your target \
+                                     browser's address bar one by one, or use a browser extension to open \
+                                     multiple URLs at once."
+                    .to_string();
+                data.delivered_to = Some(FallbackDelivery::Clipboard);
+            }
+            Ok(FallbackDelivery::File(path)) => {
+                data.instructions = format!("Saved to {}. {}", path.display(), data.instructions);
+                data.delivered_to = Some(FallbackDelivery::File(path));
+            }
+            Err(e) => warn!("Failed to deliver URL export: {}", e),
+        }
+    }
+
     /// Generate HTML bookmark export content
     fn generate_html_bookmark_export(&self, urls: &[UrlExportEntry]) -> String {
         let mut html = String::from(
@@ -1669,8 +2742,8 @@ impl RemoteTabController {
         for entry in urls {
             html.push_str(&format!(
                 "<DT><A HREF=\"{}\">{}</A>\n",
-                entry.url,
-                entry.title.replace('<', "&lt;").replace('>', "&gt;")
+                entry.url.replace('&', "&amp;").replace('"', "&quot;"),
+                entry.title.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
             ));
         }
 
@@ -1687,13 +2760,38 @@ impl RemoteTabController {
         }
 
         // Add to history
-        let mut history = self.migration_history.write().await;
-        history.push_back(record.clone());
+        {
+            let mut history = self.migration_history.write().await;
+            history.push_back(record.clone());
 
-        // Trim history if needed (use same limit as operation history)
-        while history.len() > self.config.max_history_size {
-            history.pop_front();
+            // Trim history if needed (use same limit as operation history)
+            while history.len() > self.config.max_history_size {
+                history.pop_front();
+            }
+        }
+
+        self.log_migration(record).await;
+    }
+
+    /// Replace a migration in history
+    async fn update_migration(&self, record: &MigrationRecord) {
+        {
+            let mut history = self.migration_history.write().await;
+            if let Some(stored) = history.iter_mut().find(|m| m.id == record.id) {
+                *stored = record.clone();
+            }
         }
+        self.log_migration(record).await;
+    }
+
+    /// Write a migration to the operation log without its captured session
+    /// state, which may hold cookies and storage
+    async fn log_migration(&self, record: &MigrationRecord) {
+        let record = MigrationRecord {
+            session_state: None,
+            ..record.clone()
+        };
+        self.log_record(OperationLogKind::Migration, record.id, record.initiated_at, &record).await;
     }
 
     /// Get an operation by ID
@@ -1739,6 +2837,20 @@ impl RemoteTabController {
     }
 }
 
+/// Decode the records of operation log entries, skipping unreadable ones
+fn decode_entries<T: serde::de::DeserializeOwned>(entries: Vec<OperationLogEntry>) -> VecDeque<T> {
+    entries
+        .into_iter()
+        .filter_map(|entry| match serde_json::from_str(&entry.record) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                warn!("Skipping unreadable operation log entry {}: {}", entry.id, e);
+                None
+            }
+        })
+        .collect()
+}
+
 impl Default for RemoteTabController {
     fn default() -> Self {
         Self::new()
@@ -1748,6 +2860,7 @@ impl Default for RemoteTabController {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{tab, MockConnector};
 
     #[test]
     fn test_operation_record_creation() {
@@ -1840,10 +2953,315 @@ mod tests {
         assert_eq!(config.max_retry_attempts, 2);
     }
 
+    /// Connector with tabs open at `urls`, failing to open or delete
+    /// `https://fail.example.com`
+    fn connector(browser: BrowserType, urls: &[&str]) -> MockConnector {
+        MockConnector {
+            fail_url: "https://fail.example.com",
+            ..MockConnector::with_tabs(browser, urls)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_batch_and_undo() {
+        let controller = RemoteTabController::new();
+        let connector = connector(BrowserType::Chrome, &["https://a.example.com", "https://b.example.com"]);
+        let a = connector.tab_id("https://a.example.com");
+        let b = connector.tab_id("https://b.example.com");
+
+        let batch = controller
+            .execute_batch(
+                &connector,
+                &[
+                    TabBatchOperation::Close { tab_id: a.clone() },
+                    TabBatchOperation::create("https://c.example.com"),
+                    TabBatchOperation::Activate { tab_id: b.clone() },
+                ],
+            )
+            .await
+            .unwrap();
+        assert!(batch.is_success());
+        assert_eq!(batch.new_tab_ids().len(), 1);
+        assert_eq!(connector.urls(), vec!["https://b.example.com", "https://c.example.com"]);
+
+        // The batch is a single undo entry
+        let undoable = controller.get_undoable_operations().await;
+        assert_eq!(undoable.len(), 1);
+        assert_eq!(undoable[0].operation_type, TabOperationType::Batch);
+        assert_eq!(undoable[0].operations.len(), 3);
+        assert_eq!(controller.get_stats().await.total_operations, 3);
+
+        let undone = controller.undo_last(&connector).await.unwrap().unwrap();
+        assert!(undone.is_success());
+        assert_eq!(undone.record.related_operation_id, Some(batch.record.id));
+        assert_eq!(connector.urls(), vec!["https://a.example.com", "https://b.example.com"]);
+        assert!(controller.undo_batch(&connector, batch.record.id).await.is_err());
+
+        // A failing operation rolls back the ones before it
+        let b_closed = controller
+            .execute_batch(
+                &connector,
+                &[
+                    TabBatchOperation::Close { tab_id: b.clone() },
+                    TabBatchOperation::create("https://fail.example.com"),
+                    TabBatchOperation::create("https://d.example.com"),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(b_closed.record.status, OperationStatus::RolledBack);
+        assert_eq!(b_closed.results[0].record.status, OperationStatus::RolledBack);
+        assert!(b_closed.error_message().unwrap().contains("Browser not running"));
+        assert!(b_closed.results[2].record.status.is_failed());
+        assert_eq!(connector.urls(), vec!["https://a.example.com", "https://b.example.com"]);
+
+        // A batch referring to a closed tab does nothing
+        let before = connector.urls();
+        let rejected = controller
+            .execute_batch(
+                &connector,
+                &[
+                    TabBatchOperation::create("https://e.example.com"),
+                    TabBatchOperation::Activate { tab_id: a },
+                ],
+            )
+            .await
+            .unwrap();
+        assert!(rejected.record.status.is_failed());
+        assert!(rejected.results.iter().all(|r| r.record.status.is_failed()));
+        assert_eq!(connector.urls(), before);
+    }
+
+    #[tokio::test]
+    async fn test_history_survives_restart() {
+        let db = data_access::DatabaseManager::in_memory().await.unwrap();
+        let log: Arc<dyn OperationLogRepository> = Arc::new(db.operation_log_repository());
+        let connector = connector(BrowserType::Chrome, &["https://a.example.com", "https://b.example.com"]);
+
+        let controller = RemoteTabController::new().with_operation_log(log.clone());
+        let a = connector.tabs.lock().unwrap()[0].clone();
+        let closed = controller.close_tab(&connector, &a.id, Some(&a)).await.unwrap();
+        let mut migration = MigrationRecord::new(
+            BrowserType::Chrome,
+            BrowserType::Firefox,
+            TabId::new(),
+            "https://b.example.com".to_string(),
+            "B".to_string(),
+        );
+        migration.session_state = Some(SessionState::basic(migration.url.clone(), migration.title.clone()));
+        migration.mark_success(TabId::new(), false);
+        controller.record_migration(&migration).await;
+        drop(controller);
+
+        // A new controller on the same log can undo the close
+        let restarted = RemoteTabController::new().with_operation_log(log.clone());
+        assert_eq!(restarted.load_history().await.unwrap(), 2);
+        assert_eq!(restarted.get_stats().await.history_size, 1);
+        let migrations = restarted.get_rollbackable_migrations().await;
+        assert_eq!(migrations.len(), 1);
+        assert!(migrations[0].session_state.is_none());
+
+        let undone = restarted.undo_last(&connector).await.unwrap().unwrap();
+        assert!(undone.is_success());
+        assert_eq!(connector.urls(), vec!["https://a.example.com", "https://b.example.com"]);
+
+        // The close stays undone after another restart
+        let restarted = RemoteTabController::new().with_operation_log(log.clone());
+        restarted.load_history().await.unwrap();
+        let original = restarted.get_operation(closed.record.id).await.unwrap();
+        assert!(!original.undoable);
+        assert_eq!(restarted.get_history().await.len(), 2);
+
+        restarted.clear_history().await;
+        restarted.clear_migration_history().await;
+        assert!(log.recent(OperationLogKind::Tab, 10).await.unwrap().is_empty());
+        assert!(log.recent(OperationLogKind::Migration, 10).await.unwrap().is_empty());
+    }
+
     // =========================================================================
     // Cross-Browser Migration Tests (Requirements 8.2, 8.3, 8.4)
     // =========================================================================
 
+    async fn connect_browsers(chrome: &[&str]) -> (BrowserConnectorManager, MockConnector, MockConnector) {
+        let manager = BrowserConnectorManager::new();
+        let chrome = connector(BrowserType::Chrome, chrome);
+        let firefox = MockConnector {
+            lost_url: "https://lost.example.com",
+            ..connector(BrowserType::Firefox, &[])
+        };
+        manager.add_connector(Box::new(chrome.clone())).await;
+        manager.add_connector(Box::new(firefox.clone())).await;
+        (manager, chrome, firefox)
+    }
+
+    #[tokio::test]
+    async fn test_migration_rollback() {
+        let controller = RemoteTabController::new();
+        let (manager, chrome, firefox) = connect_browsers(&["https://a.example.com"]).await;
+        let source = chrome.tabs.lock().unwrap()[0].clone();
+
+        let migration = controller
+            .migrate_tab(&manager, BrowserType::Chrome, BrowserType::Firefox, &source.id, None, None)
+            .await
+            .unwrap();
+        assert!(migration.is_success());
+        assert!(migration.record.source_closed);
+        assert!(chrome.urls().is_empty());
+        assert_eq!(firefox.urls(), vec!["https://a.example.com"]);
+
+        controller.rollback_migration(&manager, migration.record.id).await.unwrap();
+        assert_eq!(chrome.urls(), vec!["https://a.example.com"]);
+        assert!(firefox.urls().is_empty());
+        let history = controller.get_migration_history().await;
+        assert_eq!(history[0].status, MigrationStatus::RolledBack);
+        assert!(!history[0].source_closed);
+        assert!(controller.rollback_migration(&manager, migration.record.id).await.is_err());
+
+        // A migration that kept its source tab only closes the target tab
+        let source = chrome.tabs.lock().unwrap()[0].clone();
+        let config = MigrationConfig { close_source_tab: false, ..Default::default() };
+        let kept = controller
+            .migrate_tab(&manager, BrowserType::Chrome, BrowserType::Firefox, &source.id, Some(&source), Some(config))
+            .await
+            .unwrap();
+        controller.rollback_migration(&manager, kept.record.id).await.unwrap();
+        assert_eq!(chrome.urls(), vec!["https://a.example.com"]);
+        assert!(firefox.urls().is_empty());
+    }
+
+    fn window_tabs(connector: &MockConnector, pinned: &[&str]) -> Vec<WindowTab> {
+        connector
+            .tabs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|tab| WindowTab {
+                tab: tab.clone(),
+                pinned: pinned.contains(&tab.url.as_str()),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_migrate_window_and_roll_back() {
+        let controller = RemoteTabController::new();
+        let (manager, chrome, firefox) =
+            connect_browsers(&["https://a.example.com", "https://fail.example.com", "https://c.example.com"]).await;
+        let tabs = window_tabs(&chrome, &["https://a.example.com"]);
+
+        let migration = controller
+            .migrate_window(&manager, &tabs, BrowserType::Firefox, None)
+            .await
+            .unwrap();
+        assert_eq!(migration.window_id.as_deref(), Some("Firefox-window-1"));
+        assert_eq!((migration.migrated_count(), migration.failed_count()), (2, 1));
+        assert!(migration.results[1].record.status.is_failed());
+        let window = Some("Firefox-window-1".to_string());
+        assert_eq!(
+            *firefox.opened.lock().unwrap(),
+            vec![
+                ("https://a.example.com".to_string(), window.clone(), true),
+                ("https://c.example.com".to_string(), window, false),
+            ]
+        );
+        assert_eq!(chrome.urls(), vec!["https://fail.example.com"]);
+
+        // One rollback brings the window back in order
+        assert_eq!(controller.rollback_window_migration(&manager, migration.batch_id).await.unwrap(), 2);
+        assert!(firefox.urls().is_empty());
+        let window = Some("Chrome-window-1".to_string());
+        assert_eq!(
+            *chrome.opened.lock().unwrap(),
+            vec![
+                ("https://a.example.com".to_string(), window.clone(), true),
+                ("https://c.example.com".to_string(), window, false),
+            ]
+        );
+        assert!(controller.rollback_window_migration(&manager, migration.batch_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_group_in_page_order() {
+        let controller = RemoteTabController::new();
+        let (manager, chrome, firefox) = connect_browsers(&["https://a.example.com", "https://b.example.com"]).await;
+        let page = |tab: Option<TabInfo>, url: &str| UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: url.to_string(),
+            title: url.to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::ActiveTab {
+                browser: BrowserType::Chrome,
+                tab_id: TabId::new(),
+            },
+            browser_info: None,
+            tab_info: tab,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        let tabs = chrome.tabs.lock().unwrap().clone();
+        let pages = vec![
+            page(Some(tabs[0].clone()), "https://a.example.com"),
+            page(Some(tabs[1].clone()), "https://b.example.com"),
+            page(None, "https://closed.example.com"),
+        ];
+        let group = SmartGroup {
+            id: Uuid::new_v4(),
+            name: "Reading".to_string(),
+            description: String::new(),
+            group_type: GroupType::UserDefined,
+            pages: vec![pages[2].id, pages[1].id, pages[0].id],
+            created_at: Utc::now(),
+            auto_generated: false,
+            similarity_threshold: 0.0,
+        };
+
+        let migration = controller
+            .migrate_group(&manager, &group, &pages, &["https://b.example.com".to_string()], BrowserType::Firefox, None)
+            .await
+            .unwrap();
+        assert!(migration.is_success());
+        let opened: Vec<(String, bool)> =
+            firefox.opened.lock().unwrap().iter().map(|(url, _, pinned)| (url.clone(), *pinned)).collect();
+        assert_eq!(
+            opened,
+            vec![("https://b.example.com".to_string(), true), ("https://a.example.com".to_string(), false)]
+        );
+        assert!(chrome.urls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_migration_rolls_back() {
+        let controller = RemoteTabController::new();
+        let (manager, chrome, firefox) = connect_browsers(&["https://lost.example.com"]).await;
+
+        // The source tab cannot be closed, so the new tab is closed again
+        let missing = tab("https://b.example.com", BrowserType::Chrome);
+        let result = controller
+            .migrate_tab(&manager, BrowserType::Chrome, BrowserType::Firefox, &missing.id, Some(&missing), None)
+            .await;
+        assert!(result.is_err());
+        assert!(firefox.urls().is_empty());
+
+        // The new tab is gone after the source tab was closed, so the source
+        // tab is reopened
+        let source = chrome.tabs.lock().unwrap()[0].clone();
+        let result = controller
+            .migrate_tab(&manager, BrowserType::Chrome, BrowserType::Firefox, &source.id, Some(&source), None)
+            .await;
+        assert!(result.unwrap_err().to_string().contains("Target tab was not created"));
+        assert_eq!(chrome.urls(), vec!["https://lost.example.com"]);
+        assert!(firefox.urls().is_empty());
+
+        let history = controller.get_migration_history().await;
+        assert_eq!(history.len(), 2);
+        assert!(history.iter().all(|m| m.status.is_failed() && !m.rollbackable));
+    }
+
     #[test]
     fn test_migration_record_creation() {
         let record = MigrationRecord::new(
@@ -2170,4 +3588,104 @@ mod tests {
         assert_eq!(stats.cross_browser_migrations, 0);
         assert_eq!(stats.fallback_operations, 0);
     }
+
+    // =========================================================================
+    // Bookmark Merge Tests (Requirement 2.5)
+    // =========================================================================
+
+    fn bookmark(browser: BrowserType, url: &str, title: &str, folder: &str) -> BookmarkInfo {
+        BookmarkInfo {
+            id: BookmarkId::new(),
+            url: url.to_string(),
+            title: title.to_string(),
+            favicon_url: None,
+            browser_type: browser,
+            folder_path: vec![folder.to_string()],
+            created_at: Utc::now(),
+            last_accessed: None,
+        }
+    }
+
+    fn merge_suggestion(keep: &BookmarkInfo, remove: &[&BookmarkInfo], title: &str, folder: &str) -> MergeSuggestion {
+        MergeSuggestion {
+            group_id: uuid::Uuid::new_v4(),
+            keep_bookmark: keep.clone(),
+            remove_bookmarks: remove.iter().map(|b| (*b).clone()).collect(),
+            reason: "Exact URL duplicates".to_string(),
+            confidence: 1.0,
+            merged_metadata: browser_connector::MergedBookmarkMetadata {
+                best_title: title.to_string(),
+                combined_keywords: vec![],
+                suggested_folder_path: vec![folder.to_string()],
+                combined_description: None,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_merge_suggestion_and_undo() {
+        let controller = RemoteTabController::new();
+        let (manager, chrome, firefox) = connect_browsers(&[]).await;
+        let kept = bookmark(BrowserType::Chrome, "https://a.example.com", "a", "Misc");
+        let chrome_copy = bookmark(BrowserType::Chrome, "https://a.example.com/", "A page", "Reading");
+        let firefox_copy = bookmark(BrowserType::Firefox, "https://a.example.com", "A", "Toolbar");
+        chrome.bookmarks.lock().unwrap().extend([kept.clone(), chrome_copy.clone()]);
+        firefox.bookmarks.lock().unwrap().push(firefox_copy.clone());
+
+        let suggestion = merge_suggestion(&kept, &[&chrome_copy, &firefox_copy], "A page", "Work");
+        let merge = controller.apply_merge_suggestion(&manager, &suggestion).await.unwrap();
+        assert!(merge.status.is_success());
+        assert!(merge.undoable);
+        assert_eq!(merge.deleted.len(), 2);
+        let bookmarks = chrome.bookmarks.lock().unwrap().clone();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].title, "A page");
+        assert_eq!(bookmarks[0].folder_path, vec!["Work"]);
+        assert!(firefox.bookmarks.lock().unwrap().is_empty());
+        assert_eq!(controller.get_stats().await.bookmark_merges, 1);
+
+        assert_eq!(controller.undo_merge(&manager, merge.id).await.unwrap(), 2);
+        let mut bookmarks: Vec<(String, String)> = chrome
+            .bookmarks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|b| (b.title.clone(), b.folder_path[0].clone()))
+            .collect();
+        bookmarks.sort();
+        assert_eq!(
+            bookmarks,
+            vec![("A page".to_string(), "Reading".to_string()), ("a".to_string(), "Misc".to_string())]
+        );
+        assert_eq!(firefox.bookmarks.lock().unwrap()[0].title, "A");
+        assert_eq!(controller.get_merge_history().await[0].status, OperationStatus::RolledBack);
+        assert!(controller.undo_merge(&manager, merge.id).await.is_err());
+
+        // A bookmark that cannot be deleted fails the merge and stays
+        let stuck = bookmark(BrowserType::Firefox, "https://fail.example.com", "Fail", "Toolbar");
+        firefox.bookmarks.lock().unwrap().push(stuck.clone());
+        let firefox_copy = firefox.bookmarks.lock().unwrap()[0].clone();
+        let suggestion = merge_suggestion(&kept, &[&firefox_copy, &stuck], "a", "Misc");
+        let merge = controller.apply_merge_suggestion(&manager, &suggestion).await.unwrap();
+        assert!(merge.status.is_failed());
+        assert!(!merge.kept_updated);
+        assert_eq!(merge.deleted.len(), 1);
+        assert_eq!(firefox.bookmarks.lock().unwrap().len(), 1);
+        assert_eq!(controller.undo_merge(&manager, merge.id).await.unwrap(), 1);
+        assert_eq!(firefox.bookmarks.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_merge_in_firefox_without_extension_reply_fails() {
+        let controller = RemoteTabController::new();
+        let manager = BrowserConnectorManager::new();
+        manager.add_connector(Box::new(browser_connector::FirefoxConnector::new())).await;
+        let kept = bookmark(BrowserType::Firefox, "https://a.example.com", "a", "Toolbar");
+        let copy = bookmark(BrowserType::Firefox, "https://a.example.com/", "A page", "Reading");
+
+        let suggestion = merge_suggestion(&kept, &[&copy], "A page", "Work");
+        assert!(controller.apply_merge_suggestion(&manager, &suggestion).await.is_err());
+        assert!(controller.get_merge_history().await.is_empty());
+        assert_eq!(controller.get_stats().await.bookmark_merges, 0);
+    }
 }
@@ -0,0 +1,617 @@
+//! Rules Engine
+//!
+//! Automates tab and bookmark housekeeping with user-defined rules. Each
+//! rule has a trigger saying when it is checked, a condition on the tab or
+//! page, and actions run in order on every match, for example "close tabs
+//! from example.com idle for over two hours and save them to history" or
+//! "tag everything from arxiv.org as research".
+//!
+//! Triggers fire from the entry points: `on_tab_events` for opened tabs,
+//! `check_idle_tabs` for idle ones and `on_pages_saved` for pages. Each
+//! can run dry, reporting what would happen without doing it. Executions
+//! are kept in a bounded log, and rules are saved to and loaded from JSON.
+
+use web_page_manager_core::*;
+use browser_connector::{BrowserConnectorManager, TabEvent};
+use data_access::{PageRepository, TagRepository};
+use crate::history::TabHistoryManager;
+use crate::remote_controller::RemoteTabController;
+use crate::search::url_domain;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, info};
+
+/// Executions kept in the log
+const DEFAULT_MAX_LOG_ENTRIES: usize = 500;
+
+/// When a rule is checked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleTrigger {
+    /// A tab was opened
+    TabOpened,
+    /// A tab has not been used for at least `minutes`
+    TabIdle { minutes: u32 },
+    /// A page or bookmark was saved
+    PageSaved,
+}
+
+/// What a tab or page must satisfy for a rule to act on it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RuleCondition {
+    /// Matches everything
+    Always,
+    /// The URL is on the domain or one of its subdomains
+    Domain(String),
+    /// The URL contains the text, ignoring case
+    UrlContains(String),
+    /// The title contains the text, ignoring case
+    TitleContains(String),
+    /// The tab or page is in the browser
+    Browser(BrowserType),
+    /// Every condition matches
+    All(Vec<RuleCondition>),
+    /// At least one condition matches
+    Any(Vec<RuleCondition>),
+    /// The condition does not match
+    Not(Box<RuleCondition>),
+}
+
+impl RuleCondition {
+    /// Whether `target` satisfies the condition
+    pub fn matches(&self, target: &RuleTarget) -> bool {
+        match self {
+            RuleCondition::Always => true,
+            RuleCondition::Domain(domain) => {
+                let domain = domain.trim_start_matches("www.").to_lowercase();
+                url_domain(target.url()).is_some_and(|host| {
+                    host == domain || host.strip_suffix(&domain).is_some_and(|sub| sub.ends_with('.'))
+                })
+            }
+            RuleCondition::UrlContains(text) => target.url().to_lowercase().contains(&text.to_lowercase()),
+            RuleCondition::TitleContains(text) => target.title().to_lowercase().contains(&text.to_lowercase()),
+            RuleCondition::Browser(browser) => target.browser() == Some(*browser),
+            RuleCondition::All(conditions) => conditions.iter().all(|c| c.matches(target)),
+            RuleCondition::Any(conditions) => conditions.iter().any(|c| c.matches(target)),
+            RuleCondition::Not(condition) => !condition.matches(target),
+        }
+    }
+}
+
+/// What a rule does to a matching tab or page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleAction {
+    /// Close the tab
+    CloseTab,
+    /// Save the tab to the closed tab history
+    SaveToHistory,
+    /// Tag the saved page
+    AddTag(String),
+    /// Set the saved page's category
+    SetCategory(String),
+}
+
+/// A trigger, condition and actions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: Uuid,
+    pub name: String,
+    pub enabled: bool,
+    pub trigger: RuleTrigger,
+    pub condition: RuleCondition,
+    /// Run in order, stopping at the first that fails
+    pub actions: Vec<RuleAction>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Rule {
+    /// New enabled rule
+    pub fn new(
+        name: impl Into<String>,
+        trigger: RuleTrigger,
+        condition: RuleCondition,
+        actions: Vec<RuleAction>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            enabled: true,
+            trigger,
+            condition,
+            actions,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Tab or page a rule is checked against
+#[derive(Debug, Clone)]
+pub enum RuleTarget {
+    Tab(TabInfo),
+    Page(Box<UnifiedPageInfo>),
+}
+
+impl RuleTarget {
+    pub fn url(&self) -> &str {
+        match self {
+            RuleTarget::Tab(tab) => &tab.url,
+            RuleTarget::Page(page) => &page.url,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        match self {
+            RuleTarget::Tab(tab) => &tab.title,
+            RuleTarget::Page(page) => &page.title,
+        }
+    }
+
+    /// Browser of the tab, or of the page's tab or bookmark
+    pub fn browser(&self) -> Option<BrowserType> {
+        match self {
+            RuleTarget::Tab(tab) => Some(tab.browser_type),
+            RuleTarget::Page(page) => page
+                .tab_info
+                .as_ref()
+                .map(|t| t.browser_type)
+                .or(page.bookmark_info.as_ref().map(|b| b.browser_type)),
+        }
+    }
+
+    /// The open tab, if any
+    pub fn tab(&self) -> Option<&TabInfo> {
+        match self {
+            RuleTarget::Tab(tab) => Some(tab),
+            RuleTarget::Page(page) => page.tab_info.as_ref(),
+        }
+    }
+}
+
+/// Result of one action of an execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionOutcome {
+    pub action: RuleAction,
+    /// Why the action failed, None if it succeeded or was only planned
+    pub error: Option<String>,
+}
+
+/// A rule acting on one tab or page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleExecution {
+    pub rule_id: Uuid,
+    pub rule_name: String,
+    pub url: String,
+    pub executed_at: DateTime<Utc>,
+    /// Whether the actions were only planned, not run
+    pub dry_run: bool,
+    /// Actions attempted, up to the first failure; all of them in a dry run
+    pub outcomes: Vec<ActionOutcome>,
+}
+
+impl RuleExecution {
+    /// Whether every action succeeded
+    pub fn is_success(&self) -> bool {
+        self.outcomes.iter().all(|o| o.error.is_none())
+    }
+}
+
+/// Engine checking rules and running their actions
+pub struct RulesEngine {
+    rules: Arc<RwLock<Vec<Rule>>>,
+    /// Most recent executions, oldest first
+    log: Arc<RwLock<VecDeque<RuleExecution>>>,
+    max_log_entries: usize,
+    controller: Option<Arc<RemoteTabController>>,
+    connectors: Option<Arc<BrowserConnectorManager>>,
+    history: Option<Arc<TabHistoryManager>>,
+    tag_repository: Option<Arc<dyn TagRepository>>,
+    page_repository: Option<Arc<dyn PageRepository>>,
+}
+
+impl RulesEngine {
+    /// Create an engine without rules; actions fail until the components
+    /// they need are set
+    pub fn new() -> Self {
+        Self {
+            rules: Arc::new(RwLock::new(Vec::new())),
+            log: Arc::new(RwLock::new(VecDeque::new())),
+            max_log_entries: DEFAULT_MAX_LOG_ENTRIES,
+            controller: None,
+            connectors: None,
+            history: None,
+            tag_repository: None,
+            page_repository: None,
+        }
+    }
+
+    /// Close tabs through `controller`
+    pub fn with_tab_control(mut self, controller: Arc<RemoteTabController>, connectors: Arc<BrowserConnectorManager>) -> Self {
+        self.controller = Some(controller);
+        self.connectors = Some(connectors);
+        self
+    }
+
+    /// Save tabs to `history`
+    pub fn with_history(mut self, history: Arc<TabHistoryManager>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Tag pages in `repository`
+    pub fn with_tag_repository(mut self, repository: Arc<dyn TagRepository>) -> Self {
+        self.tag_repository = Some(repository);
+        self
+    }
+
+    /// Look up and update saved pages in `repository`
+    pub fn with_page_repository(mut self, repository: Arc<dyn PageRepository>) -> Self {
+        self.page_repository = Some(repository);
+        self
+    }
+
+    /// Set how many executions the log keeps
+    pub fn with_max_log_entries(mut self, max: usize) -> Self {
+        self.max_log_entries = max;
+        self
+    }
+
+    // =========================================================================
+    // Rule Management
+    // =========================================================================
+
+    /// All rules in the order they run
+    pub async fn rules(&self) -> Vec<Rule> {
+        self.rules.read().await.clone()
+    }
+
+    /// Add a rule, running after the existing ones
+    pub async fn add_rule(&self, rule: Rule) {
+        self.rules.write().await.push(rule);
+    }
+
+    /// Replace the rule with `rule`'s ID; false if it does not exist
+    pub async fn update_rule(&self, rule: Rule) -> bool {
+        let mut rules = self.rules.write().await;
+        match rules.iter_mut().find(|r| r.id == rule.id) {
+            Some(existing) => {
+                *existing = rule;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Enable or disable a rule; false if it does not exist
+    pub async fn set_enabled(&self, id: &Uuid, enabled: bool) -> bool {
+        let mut rules = self.rules.write().await;
+        match rules.iter_mut().find(|r| r.id == *id) {
+            Some(rule) => {
+                rule.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a rule; false if it does not exist
+    pub async fn remove_rule(&self, id: &Uuid) -> bool {
+        let mut rules = self.rules.write().await;
+        let before = rules.len();
+        rules.retain(|r| r.id != *id);
+        rules.len() < before
+    }
+
+    /// Export all rules as JSON
+    pub async fn export_rules(&self) -> Result<String> {
+        serde_json::to_string_pretty(&*self.rules.read().await).map_err(|e| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Failed to serialize rules: {}", e),
+            },
+        })
+    }
+
+    /// Import rules from JSON, replacing rules with the same ID, and
+    /// return how many were imported
+    pub async fn import_rules(&self, json_data: &str) -> Result<usize> {
+        let imported: Vec<Rule> = serde_json::from_str(json_data).map_err(|e| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Failed to parse rules: {}", e),
+            },
+        })?;
+
+        let count = imported.len();
+        let mut rules = self.rules.write().await;
+        for rule in imported {
+            match rules.iter_mut().find(|r| r.id == rule.id) {
+                Some(existing) => *existing = rule,
+                None => rules.push(rule),
+            }
+        }
+        info!("Imported {} rules", count);
+        Ok(count)
+    }
+
+    /// Save all rules to a file
+    pub async fn save_to_file(&self, path: &Path) -> Result<()> {
+        let content = self.export_rules().await?;
+        tokio::fs::write(path, content).await.map_err(|e| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Failed to write rules file: {}", e),
+            },
+        })
+    }
+
+    /// Load rules from a file
+    pub async fn load_from_file(&self, path: &Path) -> Result<usize> {
+        let content = tokio::fs::read_to_string(path).await.map_err(|e| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Failed to read rules file: {}", e),
+            },
+        })?;
+        self.import_rules(&content).await
+    }
+
+    // =========================================================================
+    // Triggers
+    // =========================================================================
+
+    /// Run `TabOpened` rules on the tabs created in `events`
+    pub async fn on_tab_events(&self, events: &[TabEvent], dry_run: bool) -> Vec<RuleExecution> {
+        let targets = events
+            .iter()
+            .filter_map(|event| match event {
+                TabEvent::Created { tab, .. } => Some(RuleTarget::Tab(tab.clone())),
+                _ => None,
+            })
+            .collect();
+        self.fire(targets, |trigger, _| *trigger == RuleTrigger::TabOpened, dry_run).await
+    }
+
+    /// Run `TabIdle` rules on the tabs of `tabs` idle long enough at `now`
+    pub async fn check_idle_tabs(&self, tabs: &[TabInfo], now: DateTime<Utc>, dry_run: bool) -> Vec<RuleExecution> {
+        let targets = tabs.iter().cloned().map(RuleTarget::Tab).collect();
+        self.fire(
+            targets,
+            |trigger, target| match (trigger, target.tab()) {
+                (RuleTrigger::TabIdle { minutes }, Some(tab)) => {
+                    now - tab.last_accessed >= chrono::Duration::minutes(i64::from(*minutes))
+                }
+                _ => false,
+            },
+            dry_run,
+        )
+        .await
+    }
+
+    /// Run `PageSaved` rules on `pages`
+    pub async fn on_pages_saved(&self, pages: &[UnifiedPageInfo], dry_run: bool) -> Vec<RuleExecution> {
+        let targets = pages.iter().map(|p| RuleTarget::Page(Box::new(p.clone()))).collect();
+        self.fire(targets, |trigger, _| *trigger == RuleTrigger::PageSaved, dry_run).await
+    }
+
+    /// Run every enabled rule `applies` to on each matching target
+    async fn fire(
+        &self,
+        targets: Vec<RuleTarget>,
+        applies: impl Fn(&RuleTrigger, &RuleTarget) -> bool,
+        dry_run: bool,
+    ) -> Vec<RuleExecution> {
+        let rules = self.rules().await;
+        let mut executions = Vec::new();
+        for target in &targets {
+            for rule in rules.iter().filter(|r| r.enabled) {
+                if !applies(&rule.trigger, target) || !rule.condition.matches(target) {
+                    continue;
+                }
+                let mut outcomes = Vec::with_capacity(rule.actions.len());
+                for action in &rule.actions {
+                    let error = if dry_run { None } else { self.perform(action, target).await.err() };
+                    let failed = error.is_some();
+                    outcomes.push(ActionOutcome { action: action.clone(), error });
+                    if failed {
+                        break;
+                    }
+                }
+                executions.push(RuleExecution {
+                    rule_id: rule.id,
+                    rule_name: rule.name.clone(),
+                    url: target.url().to_string(),
+                    executed_at: Utc::now(),
+                    dry_run,
+                    outcomes,
+                });
+            }
+        }
+
+        if !dry_run && !executions.is_empty() {
+            debug!("Rules ran {} times", executions.len());
+            let mut log = self.log.write().await;
+            log.extend(executions.iter().cloned());
+            while log.len() > self.max_log_entries {
+                log.pop_front();
+            }
+        }
+        executions
+    }
+
+    /// Run one action on `target`, returning why it failed
+    async fn perform(&self, action: &RuleAction, target: &RuleTarget) -> std::result::Result<(), String> {
+        match action {
+            RuleAction::CloseTab => {
+                let tab = target.tab().ok_or("Not an open tab")?;
+                let (Some(controller), Some(connectors)) = (&self.controller, &self.connectors) else {
+                    return Err("Tab control is not available".to_string());
+                };
+                let result = controller
+                    .close_tab_via_manager(connectors, tab.browser_type, &tab.id, Some(tab))
+                    .await
+                    .map_err(|e| e.to_string())?;
+                match result.error_message() {
+                    Some(error) => Err(error.to_string()),
+                    None => Ok(()),
+                }
+            }
+            RuleAction::SaveToHistory => {
+                let tab = target.tab().ok_or("Not an open tab")?;
+                let history = self.history.as_ref().ok_or("History is not available")?;
+                history.save_closed_tab(tab.clone(), Utc::now()).await.map(|_| ()).map_err(|e| e.to_string())
+            }
+            RuleAction::AddTag(name) => {
+                let tags = self.tag_repository.as_ref().ok_or("Tags are not available")?;
+                let page = self.saved_page(target).await?;
+                let tag = tags.get_or_create(name).await.map_err(|e| e.to_string())?;
+                tags.tag_page(&page.id, &tag.id).await.map_err(|e| e.to_string())
+            }
+            RuleAction::SetCategory(category) => {
+                let pages = self.page_repository.as_ref().ok_or("Pages are not available")?;
+                let mut page = self.saved_page(target).await?;
+                page.category = Some(category.clone());
+                pages.save(&page).await.map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    /// The saved page of `target`, looked up by URL
+    async fn saved_page(&self, target: &RuleTarget) -> std::result::Result<UnifiedPageInfo, String> {
+        let lookup = match (target, &self.page_repository) {
+            (_, Some(pages)) => pages.get_by_url(target.url()).await.map_err(|e| e.to_string())?,
+            (RuleTarget::Page(page), None) => Some(page.as_ref().clone()),
+            (RuleTarget::Tab(_), None) => None,
+        };
+        lookup.ok_or_else(|| format!("No saved page for {}", target.url()))
+    }
+
+    // =========================================================================
+    // Execution Log
+    // =========================================================================
+
+    /// Up to `limit` executions, most recent first
+    pub async fn execution_log(&self, limit: usize) -> Vec<RuleExecution> {
+        self.log.read().await.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Executions of one rule, most recent first
+    pub async fn executions_of(&self, rule_id: &Uuid) -> Vec<RuleExecution> {
+        self.log.read().await.iter().rev().filter(|e| e.rule_id == *rule_id).cloned().collect()
+    }
+
+    pub async fn clear_log(&self) {
+        self.log.write().await.clear();
+    }
+}
+
+impl Default for RulesEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_page;
+    use data_access::DatabaseManager;
+
+    fn tab(url: &str, idle_minutes: i64) -> TabInfo {
+        TabInfo {
+            id: TabId::new(),
+            url: url.to_string(),
+            title: format!("Title of {}", url),
+            favicon_url: None,
+            browser_type: BrowserType::Chrome,
+            is_private: false,
+            created_at: Utc::now() - chrono::Duration::days(1),
+            last_accessed: Utc::now() - chrono::Duration::minutes(idle_minutes),
+        }
+    }
+
+    #[test]
+    fn test_conditions() {
+        let target = RuleTarget::Tab(tab("https://www.docs.example.com/guide", 0));
+        assert!(RuleCondition::Domain("example.com".to_string()).matches(&target));
+        assert!(RuleCondition::Domain("www.docs.example.com".to_string()).matches(&target));
+        assert!(!RuleCondition::Domain("ample.com".to_string()).matches(&target));
+        assert!(RuleCondition::UrlContains("GUIDE".to_string()).matches(&target));
+
+        let either = RuleCondition::Any(vec![
+            RuleCondition::Browser(BrowserType::Firefox),
+            RuleCondition::TitleContains("title".to_string()),
+        ]);
+        assert!(either.matches(&target));
+        let both = RuleCondition::All(vec![either, RuleCondition::Browser(BrowserType::Firefox)]);
+        assert!(!both.matches(&target));
+        assert!(RuleCondition::Not(Box::new(both)).matches(&target));
+    }
+
+    #[tokio::test]
+    async fn test_idle_rule_dry_run_and_log() {
+        let history = Arc::new(TabHistoryManager::new());
+        let engine = RulesEngine::new().with_history(history.clone());
+        let rule = Rule::new(
+            "Archive idle docs",
+            RuleTrigger::TabIdle { minutes: 120 },
+            RuleCondition::Domain("example.com".to_string()),
+            vec![RuleAction::SaveToHistory, RuleAction::CloseTab],
+        );
+        engine.add_rule(rule.clone()).await;
+
+        let tabs = vec![
+            tab("https://example.com/old", 180),
+            tab("https://example.com/fresh", 30),
+            tab("https://other.org/old", 180),
+        ];
+        let planned = engine.check_idle_tabs(&tabs, Utc::now(), true).await;
+        assert_eq!(planned.len(), 1);
+        assert!(planned[0].dry_run && planned[0].outcomes.len() == 2);
+        assert_eq!(history.total_count().await, 0);
+        assert!(engine.execution_log(10).await.is_empty());
+
+        // Saving works, closing fails without tab control
+        let executions = engine.check_idle_tabs(&tabs, Utc::now(), false).await;
+        assert_eq!(executions.len(), 1);
+        assert!(!executions[0].is_success());
+        assert_eq!(executions[0].outcomes[0].error, None);
+        assert!(executions[0].outcomes[1].error.is_some());
+        assert_eq!(history.total_count().await, 1);
+        assert_eq!(engine.executions_of(&rule.id).await.len(), 1);
+
+        assert!(engine.set_enabled(&rule.id, false).await);
+        assert!(engine.check_idle_tabs(&tabs, Utc::now(), false).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_page_rules_tag_and_persist() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let pages = Arc::new(db.page_repository());
+        let tags = Arc::new(db.tag_repository());
+        let engine = RulesEngine::new()
+            .with_page_repository(pages.clone())
+            .with_tag_repository(tags.clone());
+        engine
+            .add_rule(Rule::new(
+                "arXiv is research",
+                RuleTrigger::PageSaved,
+                RuleCondition::Domain("arxiv.org".to_string()),
+                vec![RuleAction::AddTag("research".to_string()), RuleAction::SetCategory("papers".to_string())],
+            ))
+            .await;
+
+        let paper = test_page("https://arxiv.org/abs/1706.03762", "Paper", &[]);
+        pages.save(&paper).await.unwrap();
+        let executions = engine.on_pages_saved(&[paper.clone(), test_page("https://example.com", "Paper", &[])], false).await;
+        assert_eq!(executions.len(), 1);
+        assert!(executions[0].is_success());
+        let tagged = tags.get_tags_for_page(&paper.id).await.unwrap();
+        assert_eq!(tagged.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(), vec!["research"]);
+        let saved = pages.get_by_id(&paper.id).await.unwrap().unwrap();
+        assert_eq!(saved.category.as_deref(), Some("papers"));
+
+        // Rules survive an export and import into another engine
+        let json = engine.export_rules().await.unwrap();
+        let restored = RulesEngine::new();
+        assert_eq!(restored.import_rules(&json).await.unwrap(), 1);
+        assert_eq!(restored.rules().await, engine.rules().await);
+        assert!(restored.import_rules("not json").await.is_err());
+    }
+}
@@ -0,0 +1,326 @@
+//! Background Synchronization Scheduler
+//!
+//! Periodically pulls tabs and bookmarks from every connected browser,
+//! merges and matches them in the [`PageUnifiedManager`], and persists the
+//! resulting unified pages, so the library stays current without an
+//! explicit sync.
+//!
+//! Most passes are deltas: the browsers' tabs are diffed by the tab
+//! monitor and only the resulting [`TabEvent`](browser_connector::TabEvent)s
+//! are applied. A full pass, which also refreshes bookmarks, runs first
+//! and then every `full_sync_every` passes.
+//!
+//! Passes are spread out by a random jitter, so several instances started
+//! together do not poll browsers in lockstep, and can be paused while the
+//! machine runs on battery.
+
+use web_page_manager_core::*;
+use browser_connector::BrowserConnectorManager;
+use data_access::PageRepository;
+use crate::unified_manager::PageUnifiedManager;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Configuration for the sync scheduler
+#[derive(Debug, Clone)]
+pub struct SyncSchedulerConfig {
+    /// Time between sync passes
+    pub interval: Duration,
+    /// Maximum random delay added to each interval
+    pub jitter: Duration,
+    /// Whether to skip passes while running on battery power
+    pub pause_on_battery: bool,
+    /// Passes between full re-merges of all tabs and bookmarks; every
+    /// pass is full if 0 or 1
+    pub full_sync_every: u32,
+}
+
+impl Default for SyncSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5 * 60),
+            jitter: Duration::from_secs(30),
+            pause_on_battery: true,
+            full_sync_every: 12,
+        }
+    }
+}
+
+/// Outcome of one sync pass
+#[derive(Debug, Clone)]
+pub struct SyncRunReport {
+    /// Whether all tabs and bookmarks were re-merged, rather than only
+    /// tab events applied
+    pub full: bool,
+    /// Tab events applied in a delta pass
+    pub events: usize,
+    /// Tabs pulled from all browsers
+    pub tabs: usize,
+    /// Bookmarks pulled from all browsers
+    pub bookmarks: usize,
+    /// Unified pages persisted to the repository, only the changed ones
+    /// in a delta pass
+    pub pages_saved: usize,
+    /// Conflicts waiting for the user after the pass
+    pub conflicts: usize,
+    /// When the pass finished
+    pub finished_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Scheduler running sync passes in the background
+pub struct SyncScheduler {
+    config: SyncSchedulerConfig,
+    connector: Arc<BrowserConnectorManager>,
+    manager: Arc<PageUnifiedManager>,
+    page_repository: Option<Arc<dyn PageRepository>>,
+    /// Reports whether the machine runs on battery
+    power_probe: fn() -> bool,
+    last_run: Arc<RwLock<Option<SyncRunReport>>>,
+    /// Passes run so far
+    passes: AtomicU32,
+}
+
+impl SyncScheduler {
+    /// Create a scheduler syncing `connector`'s browsers into `manager`
+    pub fn new(
+        connector: Arc<BrowserConnectorManager>,
+        manager: Arc<PageUnifiedManager>,
+        config: SyncSchedulerConfig,
+    ) -> Self {
+        Self {
+            config,
+            connector,
+            manager,
+            page_repository: None,
+            power_probe: on_battery_power,
+            last_run: Arc::new(RwLock::new(None)),
+            passes: AtomicU32::new(0),
+        }
+    }
+
+    /// Set the repository unified pages are persisted to
+    pub fn with_repository(mut self, repository: Arc<dyn PageRepository>) -> Self {
+        self.page_repository = Some(repository);
+        self
+    }
+
+    /// Replace the battery check, e.g. with a platform-specific one
+    pub fn with_power_probe(mut self, probe: fn() -> bool) -> Self {
+        self.power_probe = probe;
+        self
+    }
+
+    /// Get the current configuration
+    pub fn config(&self) -> &SyncSchedulerConfig {
+        &self.config
+    }
+
+    /// Report of the last completed pass
+    pub async fn last_run(&self) -> Option<SyncRunReport> {
+        self.last_run.read().await.clone()
+    }
+
+    /// Whether a pass should run now, i.e. it is not paused for battery
+    pub fn should_run(&self) -> bool {
+        !(self.config.pause_on_battery && (self.power_probe)())
+    }
+
+    /// Delay before the next pass: the interval plus a random jitter
+    pub fn next_delay(&self) -> Duration {
+        let jitter_ms = self.config.jitter.as_millis() as u64;
+        if jitter_ms == 0 {
+            return self.config.interval;
+        }
+        // Sub-second clock noise is random enough to spread out instances
+        let noise = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos() as u64)
+            .unwrap_or(0);
+        self.config.interval + Duration::from_millis(noise % (jitter_ms + 1))
+    }
+
+    /// Run one sync pass now, full or delta as scheduled
+    pub async fn run_once(&self) -> Result<SyncRunReport> {
+        let pass = self.passes.fetch_add(1, Ordering::Relaxed);
+        if pass.is_multiple_of(self.config.full_sync_every.max(1)) {
+            self.run_full().await
+        } else {
+            self.run_delta().await
+        }
+    }
+
+    /// Re-merge all tabs and bookmarks
+    pub async fn run_full(&self) -> Result<SyncRunReport> {
+        let browser_tabs = self.connector.get_all_tabs().await;
+        // Prime the monitor, so the next delta only sees later changes
+        self.connector.tab_monitor().update_tabs(browser_tabs.clone()).await;
+        let tabs: Vec<TabInfo> = browser_tabs.into_values().flatten().collect();
+        let bookmarks: Vec<BookmarkInfo> = self
+            .connector
+            .get_all_bookmarks()
+            .await
+            .into_values()
+            .flatten()
+            .collect();
+        let (tab_count, bookmark_count) = (tabs.len(), bookmarks.len());
+
+        self.manager.update_all(tabs, bookmarks).await;
+        let pages = self.manager.get_unified_pages().await;
+        self.finish(true, 0, tab_count, bookmark_count, &pages).await
+    }
+
+    /// Apply the tab events since the last pass
+    pub async fn run_delta(&self) -> Result<SyncRunReport> {
+        let events = self.connector.update_tab_monitor().await;
+        let pages = self.manager.apply_tab_events(&events).await;
+        let tab_count = self.manager.get_cached_tabs().await.len();
+        let bookmark_count = self.manager.get_cached_bookmarks().await.len();
+        self.finish(false, events.len(), tab_count, bookmark_count, &pages).await
+    }
+
+    /// Persist `pages` and record the report
+    async fn finish(
+        &self,
+        full: bool,
+        events: usize,
+        tabs: usize,
+        bookmarks: usize,
+        pages: &[UnifiedPageInfo],
+    ) -> Result<SyncRunReport> {
+        let mut pages_saved = 0;
+        if let Some(ref repo) = self.page_repository {
+            if !pages.is_empty() {
+                repo.save_batch(pages).await?;
+            }
+            pages_saved = pages.len();
+        }
+
+        let report = SyncRunReport {
+            full,
+            events,
+            tabs,
+            bookmarks,
+            pages_saved,
+            conflicts: self.manager.get_sync_conflicts().await.len(),
+            finished_at: chrono::Utc::now(),
+        };
+        debug!(
+            "{} sync pass: {} tabs, {} bookmarks, {} pages saved",
+            if full { "Full" } else { "Delta" },
+            report.tabs,
+            report.bookmarks,
+            report.pages_saved
+        );
+        *self.last_run.write().await = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Run sync passes until the returned task is aborted
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            info!("Starting background sync every {:?}", self.config.interval);
+            loop {
+                if self.should_run() {
+                    if let Err(e) = self.run_once().await {
+                        warn!("Background sync failed: {}", e);
+                    }
+                } else {
+                    debug!("Skipping background sync on battery power");
+                }
+                tokio::time::sleep(self.next_delay().max(Duration::from_secs(1))).await;
+            }
+        })
+    }
+}
+
+/// Whether the machine runs on battery power
+///
+/// Reads the power supplies in sysfs on Linux: on battery when a battery
+/// reports discharging and no mains adapter is online. Always false on
+/// other platforms.
+pub fn on_battery_power() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let Ok(entries) = std::fs::read_dir("/sys/class/power_supply") else {
+            return false;
+        };
+        let read = |path: &std::path::Path, name: &str| {
+            std::fs::read_to_string(path.join(name))
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default()
+        };
+
+        let mut discharging = false;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match read(&path, "type").as_str() {
+                "Mains" if read(&path, "online") == "1" => return false,
+                "Battery" if read(&path, "status") == "Discharging" => discharging = true,
+                _ => {}
+            }
+        }
+        discharging
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_access::DatabaseManager;
+
+    fn scheduler(config: SyncSchedulerConfig) -> SyncScheduler {
+        SyncScheduler::new(
+            Arc::new(BrowserConnectorManager::new()),
+            Arc::new(PageUnifiedManager::new()),
+            config,
+        )
+    }
+
+    #[test]
+    fn test_delay_and_battery_pause() {
+        let config = SyncSchedulerConfig {
+            interval: Duration::from_secs(60),
+            jitter: Duration::from_secs(10),
+            pause_on_battery: true,
+            full_sync_every: 12,
+        };
+        let delay = scheduler(config.clone()).next_delay();
+        assert!(delay >= Duration::from_secs(60) && delay <= Duration::from_secs(70));
+
+        assert!(!scheduler(config.clone()).with_power_probe(|| true).should_run());
+        assert!(scheduler(config.clone()).with_power_probe(|| false).should_run());
+        let keep_going = SyncSchedulerConfig { pause_on_battery: false, ..config };
+        assert!(scheduler(keep_going).with_power_probe(|| true).should_run());
+    }
+
+    #[tokio::test]
+    async fn test_run_once_persists_pages() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let manager = Arc::new(PageUnifiedManager::new());
+        let scheduler = SyncScheduler::new(
+            Arc::new(BrowserConnectorManager::new()),
+            manager.clone(),
+            SyncSchedulerConfig::default(),
+        )
+        .with_repository(Arc::new(db.page_repository()));
+        assert!(scheduler.last_run().await.is_none());
+
+        // No browsers are connected, so the pass pulls nothing
+        let report = scheduler.run_once().await.unwrap();
+        assert!(report.full);
+        assert_eq!((report.tabs, report.bookmarks, report.pages_saved), (0, 0, 0));
+        assert!(scheduler.last_run().await.is_some());
+        let report = scheduler.run_once().await.unwrap();
+        assert!(!report.full);
+        assert_eq!(report.events, 0);
+        assert!(db.page_repository().get_all().await.unwrap().is_empty());
+    }
+}
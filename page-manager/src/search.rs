@@ -1,23 +1,35 @@
 //! Unified Search Module
 //!
 //! Provides cross-data-source unified search functionality for tabs, bookmarks,
-//! history, and archived content.
+//! history, and archived content. Executed queries and saved searches are
+//! stored in the database. Queries may use the field filters and boolean
+//! operators of [`SearchQuery`](crate::search_query::SearchQuery). Results come
+//! with facet counts for refining the search. Misspelled queries fall back to
+//! typo-tolerant matching, which also completes titles, domains and tags as
+//! the user types. The semantic [`SearchMode`] also finds pages and history
+//! by embedding similarity when their words differ from the query.
+//! Relevance is boosted for recently accessed, frequently visited and open
+//! pages, with configurable [`RankingWeights`].
 //!
 //! # Requirements
 //! - 6.5: Unified search across tabs and bookmarks with comprehensive results
 
 use web_page_manager_core::*;
 use data_access::{
-    PageRepository, HistoryRepository, ArchiveRepository,
-    SqlitePageRepository, SqliteHistoryRepository, SqliteArchiveRepository,
-    DatabaseManager,
+    PageRepository, HistoryRepository, ArchiveRepository, SearchRecordRepository, TagRepository,
+    SqlitePageRepository, SqliteHistoryRepository, SqliteArchiveRepository, SqliteSearchRecordRepository,
+    SqliteTagRepository, EmbeddingRepository, SqliteEmbeddingRepository,
+    DatabaseManager, PageQuery, SavedSearch,
 };
+use ai_processor_ffi::embedding::{cosine_similarity, EmbeddingModel, HashingEmbedder};
+use crate::search_query::SearchQuery;
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 /// Maximum number of search history entries to keep
 const MAX_SEARCH_HISTORY: usize = 100;
@@ -25,6 +37,30 @@ const MAX_SEARCH_HISTORY: usize = 100;
 /// Maximum number of search suggestions to return
 const MAX_SUGGESTIONS: usize = 10;
 
+/// Relevance of a result matched only by forgiving typos, scaled by how
+/// close the match is
+const FUZZY_RELEVANCE: f32 = 0.4;
+
+/// Share of the relevance of a semantic search given by vector
+/// similarity, the rest by the keyword score
+const SEMANTIC_WEIGHT: f32 = 0.6;
+
+/// Similarity to the query a result needs to be found by meaning alone
+const MIN_SEMANTIC_SIMILARITY: f32 = 0.25;
+
+/// Maximum number of pages looked up in the embedding index per query
+const SEMANTIC_CANDIDATES: usize = 50;
+
+/// Visits at which the frequency boost is full
+const FREQUENT_VISITS: u32 = 50;
+
+/// Maximum number of tag values returned in search facets
+const MAX_TAG_FACETS: usize = 20;
+
+/// Maximum number of pages or history entries listed for a query without
+/// text
+const BROWSE_LIMIT: usize = 500;
+
 /// Unified search result item
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResultItem {
@@ -44,8 +80,14 @@ pub struct SearchResultItem {
     pub snippet: Option<String>,
     /// Keywords associated with this result
     pub keywords: Vec<String>,
+    /// Category of the page, if analyzed
+    #[serde(default)]
+    pub category: Option<String>,
     /// When this item was last accessed
     pub last_accessed: DateTime<Utc>,
+    /// Times the page was visited, if known
+    #[serde(default)]
+    pub access_count: u32,
     /// Browser type (if applicable)
     pub browser_type: Option<BrowserType>,
 }
@@ -66,7 +108,8 @@ pub enum SearchResultSource {
 }
 
 /// Search filter options
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SearchFilter {
     /// Filter by source types (empty means all sources)
     pub source_types: Vec<SearchResultSource>,
@@ -165,6 +208,13 @@ impl SearchFilter {
             }
         }
 
+        // Check category filter
+        if let Some(ref category) = self.category {
+            if !result.category.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(category)) {
+                return false;
+            }
+        }
+
         // Check keywords filter
         if !self.keywords.is_empty() {
             let has_keyword = self.keywords.iter().any(|k| {
@@ -195,6 +245,17 @@ pub enum SearchSortOrder {
     TitleDesc,
 }
 
+/// How results are found and ranked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Results must contain the query's words
+    #[default]
+    Keyword,
+    /// Pages and history similar in meaning to the query are found too,
+    /// and relevance combines embedding similarity with the keyword score
+    Semantic,
+}
+
 /// Search options
 #[derive(Debug, Clone)]
 pub struct SearchOptions {
@@ -208,6 +269,8 @@ pub struct SearchOptions {
     pub filter: SearchFilter,
     /// Whether to include snippets in results
     pub include_snippets: bool,
+    /// Keyword or semantic search
+    pub mode: SearchMode,
 }
 
 impl Default for SearchOptions {
@@ -218,10 +281,75 @@ impl Default for SearchOptions {
             sort_order: SearchSortOrder::Relevance,
             filter: SearchFilter::default(),
             include_snippets: true,
+            mode: SearchMode::Keyword,
         }
     }
 }
 
+/// Weights blending a result's relevance to the query with how current
+/// the page is
+///
+/// The ranked relevance is the weighted average of the relevance, a
+/// recency score halving every `recency_half_life_days`, a visit frequency
+/// score and whether the page is open in a tab, so it stays between 0.0
+/// and 1.0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RankingWeights {
+    /// Weight of the relevance to the query
+    pub relevance: f32,
+    /// Weight of how recently the result was accessed
+    pub recency: f32,
+    /// Weight of how often the page was visited
+    pub frequency: f32,
+    /// Weight of the page being open in a tab
+    pub open_tab: f32,
+    /// Days after which the recency score halves
+    pub recency_half_life_days: f32,
+}
+
+impl Default for RankingWeights {
+    fn default() -> Self {
+        Self {
+            relevance: 1.0,
+            recency: 0.25,
+            frequency: 0.15,
+            open_tab: 0.2,
+            recency_half_life_days: 30.0,
+        }
+    }
+}
+
+impl RankingWeights {
+    /// Rank by relevance to the query alone
+    pub fn relevance_only() -> Self {
+        Self {
+            recency: 0.0,
+            frequency: 0.0,
+            open_tab: 0.0,
+            ..Self::default()
+        }
+    }
+
+    /// Ranked relevance of `item` as seen at `now`
+    pub fn rank(&self, item: &SearchResultItem, open: bool, now: DateTime<Utc>) -> f32 {
+        let total = self.relevance + self.recency + self.frequency + self.open_tab;
+        if total <= 0.0 {
+            return item.relevance_score;
+        }
+
+        let age_days = (now - item.last_accessed).num_seconds().max(0) as f32 / 86_400.0;
+        let recency = 0.5f32.powf(age_days / self.recency_half_life_days.max(f32::EPSILON));
+        let frequency = ((1.0 + item.access_count as f32).ln() / (1.0 + FREQUENT_VISITS as f32).ln()).min(1.0);
+        let open = if open { 1.0 } else { 0.0 };
+
+        (self.relevance * item.relevance_score
+            + self.recency * recency
+            + self.frequency * frequency
+            + self.open_tab * open)
+            / total
+    }
+}
+
 /// Search history entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchHistoryEntry {
@@ -245,7 +373,7 @@ pub struct SearchSuggestion {
 }
 
 /// Type of search suggestion
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SuggestionType {
     /// From search history
     History,
@@ -255,6 +383,8 @@ pub enum SuggestionType {
     Keyword,
     /// From URLs/domains
     Url,
+    /// From user tags
+    Tag,
 }
 
 /// Unified search results
@@ -268,6 +398,8 @@ pub struct SearchResults {
     pub search_time_ms: u64,
     /// Applied filters
     pub filter: SearchFilter,
+    /// Result counts per source, browser, category, tag and age
+    pub facets: SearchFacets,
 }
 
 impl SearchResults {
@@ -281,6 +413,274 @@ impl SearchResults {
     }
 }
 
+/// Age of a result's last access, for the time facet
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TimeBucket {
+    /// Within the last day
+    Today,
+    /// Within the last week
+    PastWeek,
+    /// Within the last 30 days
+    PastMonth,
+    /// Within the last year
+    PastYear,
+    /// More than a year ago
+    Older,
+}
+
+impl TimeBucket {
+    /// Bucket of `time` as seen at `now`
+    pub fn of(time: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        let age = now - time;
+        if age < chrono::Duration::days(1) {
+            TimeBucket::Today
+        } else if age < chrono::Duration::weeks(1) {
+            TimeBucket::PastWeek
+        } else if age < chrono::Duration::days(30) {
+            TimeBucket::PastMonth
+        } else if age < chrono::Duration::days(365) {
+            TimeBucket::PastYear
+        } else {
+            TimeBucket::Older
+        }
+    }
+}
+
+/// Number of results sharing a facet value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FacetCount<T> {
+    /// The facet value
+    pub value: T,
+    /// Results with this value
+    pub count: usize,
+}
+
+/// Result counts for refining a search
+///
+/// Each facet lists its values by descending count. Categories and tags
+/// are compared without case and reported in lower case; only the
+/// `MAX_TAG_FACETS` most common tags are kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchFacets {
+    /// Results per source
+    pub source_types: Vec<FacetCount<SearchResultSource>>,
+    /// Results per browser, for results from a browser
+    pub browsers: Vec<FacetCount<BrowserType>>,
+    /// Results per category, for analyzed results
+    pub categories: Vec<FacetCount<String>>,
+    /// Results per keyword
+    pub tags: Vec<FacetCount<String>>,
+    /// Results per age of the last access
+    pub time_buckets: Vec<FacetCount<TimeBucket>>,
+}
+
+impl SearchFacets {
+    /// Count the facets of `items` with ages as seen at `now`
+    pub fn from_items(items: &[SearchResultItem], now: DateTime<Utc>) -> Self {
+        let mut tags = count_values(items.iter().flat_map(|item| {
+            let mut keywords: Vec<String> = item.keywords.iter().map(|k| k.to_lowercase()).collect();
+            keywords.sort();
+            keywords.dedup();
+            keywords
+        }));
+        tags.truncate(MAX_TAG_FACETS);
+
+        Self {
+            source_types: count_values(items.iter().map(|item| item.source_type)),
+            browsers: count_values(items.iter().filter_map(|item| item.browser_type)),
+            categories: count_values(items.iter().filter_map(|item| item.category.as_ref().map(|c| c.to_lowercase()))),
+            tags,
+            time_buckets: count_values(items.iter().map(|item| TimeBucket::of(item.last_accessed, now))),
+        }
+    }
+}
+
+/// Count each value, most common first and ties in order of appearance
+fn count_values<T: Hash + Eq + Clone>(values: impl Iterator<Item = T>) -> Vec<FacetCount<T>> {
+    let mut index: HashMap<T, usize> = HashMap::new();
+    let mut counts: Vec<FacetCount<T>> = Vec::new();
+    for value in values {
+        match index.get(&value) {
+            Some(&i) => counts[i].count += 1,
+            None => {
+                index.insert(value.clone(), counts.len());
+                counts.push(FacetCount { value, count: 1 });
+            }
+        }
+    }
+    counts.sort_by_key(|c| std::cmp::Reverse(c.count));
+    counts
+}
+
+/// Summary text cut to 200 characters for a snippet
+fn summary_snippet(summary: &ContentSummary) -> String {
+    snippet_text(&summary.summary_text)
+}
+
+/// Text cut to 200 characters, on a character boundary, for a snippet
+fn snippet_text(text: &str) -> String {
+    match text.char_indices().nth(200) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Search result for a unified page
+fn page_result(page: UnifiedPageInfo, relevance_score: f32) -> SearchResultItem {
+    let snippet = page.content_summary.as_ref().map(summary_snippet);
+
+    let browser_type = match &page.source_type {
+        PageSourceType::ActiveTab { browser, .. } => Some(*browser),
+        PageSourceType::Bookmark { browser, .. } => Some(*browser),
+        _ => page.browser_info.as_ref().map(|b| b.browser_type),
+    };
+
+    SearchResultItem {
+        id: page.id,
+        url: page.url,
+        title: page.title,
+        favicon_url: page.favicon_url,
+        source_type: SearchResultSource::UnifiedPage,
+        relevance_score,
+        snippet,
+        keywords: page.keywords,
+        category: page.category,
+        last_accessed: page.last_accessed,
+        access_count: page.access_count,
+        browser_type,
+    }
+}
+
+/// Domain of `url` without a leading `www.`
+pub(crate) fn url_domain(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    let host = parsed.host_str()?;
+    Some(host.strip_prefix("www.").unwrap_or(host).to_lowercase())
+}
+
+/// Edit distance between two strings, counting inserted, deleted and
+/// substituted characters and swapped neighbours
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // d[i][j] is the distance between the first i chars of a and j of b
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Typos forgiven in a typed word: none up to 4 characters, so short
+/// words do not match unrelated ones, one up to 8 and two beyond
+fn allowed_typos(word: &str) -> usize {
+    match word.chars().count() {
+        0..=4 => 0,
+        5..=8 => 1,
+        _ => 2,
+    }
+}
+
+/// How well a typed word matches a lowercase word
+///
+/// 1.0 for the same word and 0.9 for a prefix of it. A word within the
+/// allowed typos of the whole word or of its prefix scores 0.2 less per
+/// typo; anything further off does not match.
+pub fn fuzzy_word_score(typed: &str, word: &str) -> Option<f32> {
+    if word == typed {
+        return Some(1.0);
+    }
+    if word.starts_with(typed) {
+        return Some(0.9);
+    }
+    let typos = allowed_typos(typed);
+    if typos == 0 {
+        return None;
+    }
+    // A typo in the part of a longer word typed so far
+    let prefix: String = word.chars().take(typed.chars().count()).collect();
+    let distance = edit_distance(typed, word).min(edit_distance(typed, &prefix));
+    (distance <= typos).then_some(0.9 - 0.2 * distance as f32)
+}
+
+/// How well every word of `query` matches a word of `text`, on average,
+/// from 0.0 to 1.0; None if a word matches nothing
+///
+/// Words are compared without case or punctuation using
+/// [`fuzzy_word_score`], so typos and unfinished words still match.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<f32> {
+    fn words(text: &str) -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    }
+
+    let query_words = words(query);
+    if query_words.is_empty() {
+        return None;
+    }
+    let text_words = words(text);
+    let mut total = 0.0;
+    for typed in &query_words {
+        total += text_words
+            .iter()
+            .filter_map(|word| fuzzy_word_score(typed, word))
+            .max_by(f32::total_cmp)?;
+    }
+    Some(total / query_words.len() as f32)
+}
+
+/// Text a page's embedding is computed from
+pub(crate) fn page_embedding_text(page: &UnifiedPageInfo) -> String {
+    embedding_text(&page_result(page.clone(), 0.0))
+}
+
+/// Text of a result compared with the query in a semantic search
+fn embedding_text(item: &SearchResultItem) -> String {
+    let mut text = item.title.clone();
+    if let Some(snippet) = &item.snippet {
+        text.push('\n');
+        text.push_str(snippet);
+    }
+    if !item.keywords.is_empty() {
+        text.push('\n');
+        text.push_str(&item.keywords.join(" "));
+    }
+    text
+}
+
+/// Search result for a tab history entry
+fn history_result(entry: HistoryEntry, relevance_score: f32) -> SearchResultItem {
+    let snippet = entry.page_info.content_summary.as_ref().map(summary_snippet);
+
+    SearchResultItem {
+        id: entry.id.0,
+        url: entry.page_info.url,
+        title: entry.page_info.title,
+        favicon_url: entry.page_info.favicon_url,
+        source_type: SearchResultSource::History,
+        relevance_score,
+        snippet,
+        keywords: entry.page_info.keywords,
+        category: entry.page_info.category,
+        last_accessed: entry.closed_at,
+        access_count: entry.page_info.access_count,
+        browser_type: Some(entry.browser_type),
+    }
+}
 
 /// Unified Search Manager
 ///
@@ -296,8 +696,16 @@ pub struct UnifiedSearchManager {
     history_repo: SqliteHistoryRepository,
     /// Archive repository for searching archived content
     archive_repo: SqliteArchiveRepository,
-    /// Search history
-    search_history: Arc<RwLock<Vec<SearchHistoryEntry>>>,
+    /// Search history and saved searches
+    search_records: SqliteSearchRecordRepository,
+    /// User tags for autocompletion
+    tag_repo: SqliteTagRepository,
+    /// Page embeddings for semantic search
+    embedding_repo: SqliteEmbeddingRepository,
+    /// Model embedding queries and pages for semantic search
+    embedder: Arc<dyn EmbeddingModel>,
+    /// Recency, frequency and open tab boosts
+    ranking: RankingWeights,
     /// Cached tabs for in-memory search
     cached_tabs: Arc<RwLock<Vec<TabInfo>>>,
     /// Cached bookmarks for in-memory search
@@ -311,12 +719,41 @@ impl UnifiedSearchManager {
             page_repo: db_manager.page_repository(),
             history_repo: db_manager.history_repository(),
             archive_repo: db_manager.archive_repository(),
-            search_history: Arc::new(RwLock::new(Vec::new())),
+            search_records: db_manager.search_record_repository(),
+            tag_repo: db_manager.tag_repository(),
+            embedding_repo: db_manager.embedding_repository(),
+            embedder: Arc::new(HashingEmbedder::default()),
+            ranking: RankingWeights::default(),
             cached_tabs: Arc::new(RwLock::new(Vec::new())),
             cached_bookmarks: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
+    /// Use `embedder` for semantic search instead of the hashing embedder
+    ///
+    /// Pages indexed with another model should be indexed again.
+    pub fn with_embedder(mut self, embedder: Arc<dyn EmbeddingModel>) -> Self {
+        self.embedder = embedder;
+        self
+    }
+
+    /// Use `ranking` to boost recent, frequent and open results
+    pub fn with_ranking(mut self, ranking: RankingWeights) -> Self {
+        self.ranking = ranking;
+        self
+    }
+
+    /// Store the embedding of a page so semantic search can find it
+    pub async fn index_page(&self, page: &UnifiedPageInfo) -> Result<()> {
+        let text = page_embedding_text(page);
+        let embedding = self.embedder.embed(&text).map_err(|e| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Failed to embed page: {}", e),
+            },
+        })?;
+        self.embedding_repo.save(&page.id, &embedding).await
+    }
+
     /// Update cached tabs for in-memory search
     pub async fn update_tabs(&self, tabs: Vec<TabInfo>) {
         let mut cached = self.cached_tabs.write().await;
@@ -339,6 +776,89 @@ impl UnifiedSearchManager {
     /// - Archived content (database with FTS)
     pub async fn search(&self, query: &str, options: SearchOptions) -> SearchResults {
         let start_time = std::time::Instant::now();
+        let items = self.collect_results(query, &options).await;
+        let search_time_ms = start_time.elapsed().as_millis() as u64;
+
+        // Record search in history
+        self.record_search(query, items.len()).await;
+
+        SearchResults {
+            query: query.to_string(),
+            facets: SearchFacets::from_items(&items, Utc::now()),
+            items,
+            search_time_ms,
+            filter: options.filter,
+        }
+    }
+
+    /// Search with the query language of [`SearchQuery`]
+    ///
+    /// Required words, titles and domains narrow the full-text search, and
+    /// required browser, category and date conditions narrow the filter.
+    /// A query without text lists pages and history through repository
+    /// filters instead. Every result is then checked against the whole
+    /// query, including `OR` and `NOT` parts.
+    pub async fn search_query(&self, input: &str, mut options: SearchOptions) -> Result<SearchResults> {
+        let start_time = std::time::Instant::now();
+        let query = SearchQuery::parse(input)?;
+        query.narrow_filter(&mut options.filter);
+
+        let text = query.text();
+        let mut items = if text.is_empty() {
+            self.browse(&query, &options).await?
+        } else {
+            self.collect_results(&text, &options).await
+        };
+        items.retain(|item| query.matches(item));
+        let search_time_ms = start_time.elapsed().as_millis() as u64;
+
+        self.record_search(input, items.len()).await;
+
+        Ok(SearchResults {
+            query: input.to_string(),
+            facets: SearchFacets::from_items(&items, Utc::now()),
+            items,
+            search_time_ms,
+            filter: options.filter,
+        })
+    }
+
+    /// Candidates for a query without text: cached tabs and bookmarks, and
+    /// pages and history matching the query's repository filters
+    async fn browse(&self, query: &SearchQuery, options: &SearchOptions) -> Result<Vec<SearchResultItem>> {
+        let wants = |source: SearchResultSource| {
+            options.filter.source_types.is_empty() || options.filter.source_types.contains(&source)
+        };
+        let mut results: Vec<SearchResultItem> = Vec::new();
+
+        if wants(SearchResultSource::ActiveTab) {
+            results.extend(self.search_tabs("").await);
+        }
+        if wants(SearchResultSource::Bookmark) {
+            results.extend(self.search_bookmarks("").await);
+        }
+        if wants(SearchResultSource::UnifiedPage) {
+            let page_query = PageQuery {
+                limit: BROWSE_LIMIT,
+                ..query.page_query()
+            };
+            let pages = self.page_repo.list(&page_query).await?.items;
+            results.extend(pages.into_iter().map(|page| page_result(page, 0.5)));
+        }
+        if wants(SearchResultSource::History) {
+            let entries = self.history_repo.get_filtered(&query.history_filter(BROWSE_LIMIT)).await?;
+            results.extend(entries.into_iter().map(|entry| history_result(entry, 0.5)));
+        }
+
+        let mut results = self.deduplicate_results(results);
+        self.apply_ranking(&mut results).await;
+        results.retain(|r| options.filter.matches(r));
+        self.sort_results(&mut results, options.sort_order);
+        Ok(results)
+    }
+
+    /// Search every selected source, then deduplicate, filter and sort
+    async fn collect_results(&self, query: &str, options: &SearchOptions) -> Vec<SearchResultItem> {
         let query_lower = query.to_lowercase();
         let mut all_results: Vec<SearchResultItem> = Vec::new();
 
@@ -385,8 +905,13 @@ impl UnifiedSearchManager {
             }
         }
 
+        if options.mode == SearchMode::Semantic {
+            all_results = self.rank_semantically(query, all_results, options).await;
+        }
+
         // Deduplicate by URL (keep highest relevance score)
         all_results = self.deduplicate_results(all_results);
+        self.apply_ranking(&mut all_results).await;
 
         // Apply filters
         all_results.retain(|r| options.filter.matches(r));
@@ -394,8 +919,6 @@ impl UnifiedSearchManager {
         // Sort results
         self.sort_results(&mut all_results, options.sort_order);
 
-        let total_count = all_results.len();
-
         // Apply pagination
         // let items: Vec<SearchResultItem> = all_results
         //     .into_iter()
@@ -403,16 +926,104 @@ impl UnifiedSearchManager {
         //     .take(options.limit)
         //     .collect();
 
-        let search_time_ms = start_time.elapsed().as_millis() as u64;
+        all_results
+    }
 
-        // Record search in history
-        self.record_search(query, total_count).await;
+    /// Add pages and history similar in meaning to `query`, and blend
+    /// every result's keyword score with its similarity to the query
+    ///
+    /// Pages are found through the embedding index, recent history is
+    /// embedded on the fly. Results found by meaning alone need
+    /// `MIN_SEMANTIC_SIMILARITY`. If the query cannot be embedded the
+    /// keyword results are kept as they are.
+    async fn rank_semantically(
+        &self,
+        query: &str,
+        keyword_results: Vec<SearchResultItem>,
+        options: &SearchOptions,
+    ) -> Vec<SearchResultItem> {
+        let query_embedding = match self.embedder.embed(query) {
+            Ok(embedding) if embedding.iter().any(|v| *v != 0.0) => embedding,
+            Ok(_) => return keyword_results,
+            Err(e) => {
+                warn!("Failed to embed search query: {}", e);
+                return keyword_results;
+            }
+        };
+        let wants = |source: SearchResultSource| {
+            options.filter.source_types.is_empty() || options.filter.source_types.contains(&source)
+        };
+        let similarity_to_query = |item: &SearchResultItem| {
+            self.embedder
+                .embed(&embedding_text(item))
+                .map(|embedding| cosine_similarity(&query_embedding, &embedding).max(0.0) as f32)
+                .unwrap_or(0.0)
+        };
 
-        SearchResults {
-            query: query.to_string(),
-            items: all_results,
-            search_time_ms,
-            filter: options.filter,
+        let mut results: Vec<SearchResultItem> = keyword_results
+            .into_iter()
+            .map(|mut item| {
+                let similarity = similarity_to_query(&item);
+                item.relevance_score =
+                    SEMANTIC_WEIGHT * similarity + (1.0 - SEMANTIC_WEIGHT) * item.relevance_score;
+                item
+            })
+            .collect();
+
+        if wants(SearchResultSource::UnifiedPage) {
+            match self.embedding_repo.find_nearest(&query_embedding, SEMANTIC_CANDIDATES).await {
+                Ok(similar) => {
+                    for hit in similar.into_iter().filter(|h| h.similarity >= MIN_SEMANTIC_SIMILARITY) {
+                        match self.page_repo.get_by_id(&hit.page_id).await {
+                            Ok(Some(page)) => results.push(page_result(page, SEMANTIC_WEIGHT * hit.similarity)),
+                            Ok(None) => {}
+                            Err(e) => warn!("Failed to load semantically similar page: {}", e),
+                        }
+                    }
+                }
+                Err(e) => warn!("Failed to search page embeddings: {}", e),
+            }
+        }
+        if wants(SearchResultSource::History) {
+            let filter = HistoryFilter {
+                limit: Some(BROWSE_LIMIT),
+                ..HistoryFilter::default()
+            };
+            match self.history_repo.get_filtered(&filter).await {
+                Ok(entries) => {
+                    for entry in entries {
+                        let item = history_result(entry, 0.0);
+                        let similarity = similarity_to_query(&item);
+                        if similarity >= MIN_SEMANTIC_SIMILARITY {
+                            results.push(SearchResultItem {
+                                relevance_score: SEMANTIC_WEIGHT * similarity,
+                                ..item
+                            });
+                        }
+                    }
+                }
+                Err(e) => warn!("Failed to list history for semantic search: {}", e),
+            }
+        }
+
+        results
+    }
+
+    /// Blend recency, visit frequency and open tab boosts into the
+    /// relevance of `results`
+    async fn apply_ranking(&self, results: &mut [SearchResultItem]) {
+        let open_urls: std::collections::HashSet<String> = self
+            .cached_tabs
+            .read()
+            .await
+            .iter()
+            .filter(|t| !t.is_private)
+            .map(|t| t.url.to_lowercase())
+            .collect();
+        let now = Utc::now();
+        for result in results.iter_mut() {
+            let open = open_urls.contains(&result.url.to_lowercase());
+            result.relevance_score = self.ranking.rank(result, open, now);
         }
     }
 
@@ -437,7 +1048,9 @@ impl UnifiedSearchManager {
                     relevance_score: relevance,
                     snippet: None,
                     keywords: vec![],
+                    category: None,
                     last_accessed: tab.last_accessed,
+                    access_count: 0,
                     browser_type: Some(tab.browser_type),
                 });
             }
@@ -463,7 +1076,9 @@ impl UnifiedSearchManager {
                     relevance_score: relevance,
                     snippet: None,
                     keywords: bookmark.folder_path.clone(),
+                    category: None,
                     last_accessed: bookmark.last_accessed.unwrap_or(bookmark.created_at),
+                    access_count: 0,
                     browser_type: Some(bookmark.browser_type),
                 });
             }
@@ -473,65 +1088,44 @@ impl UnifiedSearchManager {
     }
 
     /// Search unified pages in database using FTS
+    ///
+    /// Without any FTS match, e.g. for a misspelled query, recently
+    /// accessed pages are matched forgiving typos instead.
     async fn search_pages(&self, query: &str) -> Result<Vec<SearchResultItem>> {
         let pages = self.page_repo.search_with_limit(query, 100).await?;
-        
-        Ok(pages.into_iter().map(|page| {
-            let snippet = page.content_summary.as_ref().map(|s| {
-                if s.summary_text.len() > 200 {
-                    format!("{}...", &s.summary_text[..200])
-                } else {
-                    s.summary_text.clone()
-                }
-            });
-
-            let browser_type = match &page.source_type {
-                PageSourceType::ActiveTab { browser, .. } => Some(*browser),
-                PageSourceType::Bookmark { browser, .. } => Some(*browser),
-                _ => page.browser_info.as_ref().map(|b| b.browser_type),
-            };
+        if pages.is_empty() {
+            return self.fuzzy_search_pages(query).await;
+        }
+        // FTS results have good relevance
+        Ok(pages.into_iter().map(|page| page_result(page, 0.8)).collect())
+    }
 
-            SearchResultItem {
-                id: page.id.clone(),
-                url: page.url,
-                title: page.title,
-                favicon_url: page.favicon_url,
-                source_type: SearchResultSource::UnifiedPage,
-                relevance_score: 0.8, // FTS results have good relevance
-                snippet,
-                keywords: page.keywords,
-                last_accessed: page.last_accessed,
-                browser_type,
-            }
-        }).collect())
+    /// Match recently accessed pages' titles and domains against `query`,
+    /// forgiving typos
+    async fn fuzzy_search_pages(&self, query: &str) -> Result<Vec<SearchResultItem>> {
+        let page_query = PageQuery {
+            limit: BROWSE_LIMIT,
+            ..PageQuery::default()
+        };
+        let pages = self.page_repo.list(&page_query).await?.items;
+        Ok(pages
+            .into_iter()
+            .filter_map(|page| {
+                let domain = url_domain(&page.url).unwrap_or_default();
+                let score = fuzzy_match(query, &page.title)
+                    .into_iter()
+                    .chain(fuzzy_match(query, &domain))
+                    .max_by(f32::total_cmp)?;
+                Some(page_result(page, FUZZY_RELEVANCE * score))
+            })
+            .collect())
     }
 
     /// Search tab history in database using FTS
     async fn search_history(&self, query: &str) -> Result<Vec<SearchResultItem>> {
         let entries = self.history_repo.search(query, 100).await?;
-        
-        Ok(entries.into_iter().map(|entry| {
-            let snippet = entry.page_info.content_summary.as_ref().map(|s| {
-                if s.summary_text.len() > 200 {
-                    format!("{}...", &s.summary_text[..200])
-                } else {
-                    s.summary_text.clone()
-                }
-            });
-
-            SearchResultItem {
-                id: entry.id.0.clone(),
-                url: entry.page_info.url,
-                title: entry.page_info.title,
-                favicon_url: entry.page_info.favicon_url,
-                source_type: SearchResultSource::History,
-                relevance_score: 0.6, // History results have lower priority
-                snippet,
-                keywords: entry.page_info.keywords,
-                last_accessed: entry.closed_at,
-                browser_type: Some(entry.browser_type),
-            }
-        }).collect())
+        // History results have lower priority
+        Ok(entries.into_iter().map(|entry| history_result(entry, 0.6)).collect())
     }
 
     /// Search archived content in database using FTS
@@ -539,12 +1133,10 @@ impl UnifiedSearchManager {
         let archives = self.archive_repo.search(query, 100).await?;
         
         Ok(archives.into_iter().map(|archive| {
-            let snippet = if archive.content_text.len() > 200 {
-                Some(format!("{}...", &archive.content_text[..200]))
-            } else if !archive.content_text.is_empty() {
-                Some(archive.content_text.clone())
-            } else {
+            let snippet = if archive.content_text.is_empty() {
                 None
+            } else {
+                Some(snippet_text(&archive.content_text))
             };
 
             SearchResultItem {
@@ -556,7 +1148,9 @@ impl UnifiedSearchManager {
                 relevance_score: 0.7, // Archives have medium priority
                 snippet,
                 keywords: vec![],
+                category: None,
                 last_accessed: archive.archived_at,
+                access_count: 0,
                 browser_type: None,
             }
         }).collect())
@@ -603,6 +1197,13 @@ impl UnifiedSearchManager {
             }
         }
 
+        // Forgive typos in the title
+        if score == 0.0 {
+            if let Some(fuzzy) = fuzzy_match(&query_lower, &title_lower) {
+                score = FUZZY_RELEVANCE * fuzzy;
+            }
+        }
+
         score.min(1.0)
     }
 
@@ -668,88 +1269,105 @@ impl UnifiedSearchManager {
     }
 
     /// Record a search in history
+    ///
+    /// Repeats of a query replace its earlier entry. Failing to record is
+    /// logged rather than failing the search.
     async fn record_search(&self, query: &str, result_count: usize) {
-        let mut history = self.search_history.write().await;
-        
-        // Remove duplicate queries (keep most recent)
-        history.retain(|e| e.query.to_lowercase() != query.to_lowercase());
-        
-        // Add new entry
-        history.push(SearchHistoryEntry {
-            query: query.to_string(),
-            searched_at: Utc::now(),
-            result_count,
-        });
-
-        // Trim to max size
-        let len = history.len();
-        if len > MAX_SEARCH_HISTORY {
-            history.drain(0..len - MAX_SEARCH_HISTORY);
+        let recorded = match self.search_records.record_query(query, result_count).await {
+            Ok(()) => self.search_records.trim_history(MAX_SEARCH_HISTORY).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = recorded {
+            warn!("Failed to record search history: {}", e);
         }
     }
 
-    /// Get search history
+    /// Get search history, most recent first
     pub async fn get_search_history(&self, limit: usize) -> Vec<SearchHistoryEntry> {
-        let history = self.search_history.read().await;
-        history.iter()
-            .rev()
-            .take(limit)
-            .cloned()
-            .collect()
+        match self.search_records.recent(limit).await {
+            Ok(recent) => recent
+                .into_iter()
+                .map(|r| SearchHistoryEntry {
+                    query: r.query,
+                    searched_at: r.last_searched_at,
+                    result_count: r.result_count,
+                })
+                .collect(),
+            Err(e) => {
+                warn!("Failed to read search history: {}", e);
+                Vec::new()
+            }
+        }
     }
 
     /// Clear search history
     pub async fn clear_search_history(&self) {
-        let mut history = self.search_history.write().await;
-        history.clear();
+        if let Err(e) = self.search_records.clear_history().await {
+            warn!("Failed to clear search history: {}", e);
+        }
+    }
+
+    /// Save a search under `name`, replacing the saved search of that name
+    pub async fn save_search(&self, name: &str, query: &str, filter: &SearchFilter) -> Result<SavedSearch> {
+        let filter = serde_json::to_value(filter).map_err(|e| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Failed to serialize search filter: {}", e),
+            },
+        })?;
+        let search = match self.search_records.get_saved_by_name(name).await? {
+            Some(existing) => SavedSearch {
+                name: name.trim().to_string(),
+                query: query.to_string(),
+                filter,
+                updated_at: Utc::now(),
+                ..existing
+            },
+            None => SavedSearch::new(name.trim(), query, filter),
+        };
+        self.search_records.save_search(&search).await?;
+        Ok(search)
+    }
+
+    /// Saved searches ordered by name
+    pub async fn saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        self.search_records.list_saved().await
+    }
+
+    /// Delete a saved search, returning whether it existed
+    pub async fn delete_saved_search(&self, id: &Uuid) -> Result<bool> {
+        self.search_records.delete_saved(id).await
+    }
+
+    /// Run a saved search with its filter in place of `options.filter`;
+    /// `None` if it does not exist
+    pub async fn run_saved_search(&self, id: &Uuid, options: SearchOptions) -> Result<Option<SearchResults>> {
+        let Some(saved) = self.search_records.get_saved(id).await? else {
+            return Ok(None);
+        };
+        // A filter saved by an older version keeps the fields it can read
+        let filter = serde_json::from_value(saved.filter).unwrap_or_default();
+        self.search_records.mark_used(id).await?;
+        Ok(Some(self.search(&saved.query, SearchOptions { filter, ..options }).await))
     }
 
     /// Get search suggestions based on partial query
+    ///
+    /// Matching past queries come first, then [`autocomplete`](Self::autocomplete)
+    /// completions.
     pub async fn get_suggestions(&self, partial_query: &str) -> Vec<SearchSuggestion> {
-        let query_lower = partial_query.to_lowercase();
         let mut suggestions: Vec<SearchSuggestion> = Vec::new();
 
         // Suggestions from search history
-        {
-            let history = self.search_history.read().await;
-            for entry in history.iter().rev() {
-                if entry.query.to_lowercase().starts_with(&query_lower) {
-                    suggestions.push(SearchSuggestion {
-                        query: entry.query.clone(),
-                        suggestion_type: SuggestionType::History,
-                        score: 1.0,
-                    });
-                }
-            }
+        match self.search_records.complete(partial_query, MAX_SUGGESTIONS).await {
+            Ok(completions) => suggestions.extend(completions.into_iter().map(|r| SearchSuggestion {
+                query: r.query,
+                suggestion_type: SuggestionType::History,
+                score: 1.0,
+            })),
+            Err(e) => warn!("Failed to complete from search history: {}", e),
         }
 
-        // Suggestions from tab titles
-        {
-            let tabs = self.cached_tabs.read().await;
-            for tab in tabs.iter() {
-                if tab.title.to_lowercase().contains(&query_lower) {
-                    suggestions.push(SearchSuggestion {
-                        query: tab.title.clone(),
-                        suggestion_type: SuggestionType::Title,
-                        score: 0.8,
-                    });
-                }
-            }
-        }
-
-        // Suggestions from bookmark titles
-        {
-            let bookmarks = self.cached_bookmarks.read().await;
-            for bookmark in bookmarks.iter() {
-                if bookmark.title.to_lowercase().contains(&query_lower) {
-                    suggestions.push(SearchSuggestion {
-                        query: bookmark.title.clone(),
-                        suggestion_type: SuggestionType::Title,
-                        score: 0.7,
-                    });
-                }
-            }
-        }
+        suggestions.extend(self.autocomplete(partial_query, MAX_SUGGESTIONS).await);
 
         // Deduplicate and sort by score
         let mut seen = std::collections::HashSet::new();
@@ -761,6 +1379,64 @@ impl UnifiedSearchManager {
         suggestions.truncate(MAX_SUGGESTIONS);
         suggestions
     }
+
+    /// Complete a partially typed query, for an as-you-type dropdown
+    ///
+    /// Titles and domains of open tabs, bookmarks and recently accessed
+    /// pages, and user tags, are matched on word prefixes while forgiving
+    /// typos. Domains and tags complete to `domain:` and `tag:` filters.
+    pub async fn autocomplete(&self, partial_query: &str, limit: usize) -> Vec<SearchSuggestion> {
+        // Candidate query, the text matched against, its type and its
+        // weight before matching
+        let mut candidates: Vec<(String, String, SuggestionType, f32)> = Vec::new();
+        let mut add_page = |title: &str, url: &str, weight: f32| {
+            candidates.push((title.to_string(), title.to_string(), SuggestionType::Title, weight));
+            if let Some(domain) = url_domain(url) {
+                candidates.push((format!("domain:{}", domain), domain, SuggestionType::Url, 0.6));
+            }
+        };
+
+        for tab in self.cached_tabs.read().await.iter().filter(|t| !t.is_private) {
+            add_page(&tab.title, &tab.url, 0.8);
+        }
+        for bookmark in self.cached_bookmarks.read().await.iter() {
+            add_page(&bookmark.title, &bookmark.url, 0.7);
+        }
+        let page_query = PageQuery {
+            limit: BROWSE_LIMIT,
+            ..PageQuery::default()
+        };
+        match self.page_repo.list(&page_query).await {
+            Ok(pages) => {
+                for page in &pages.items {
+                    add_page(&page.title, &page.url, 0.6);
+                }
+            }
+            Err(e) => warn!("Failed to list pages for autocompletion: {}", e),
+        }
+        match self.tag_repo.tag_counts().await {
+            Ok(tags) => candidates.extend(
+                tags.into_iter()
+                    .map(|t| (format!("tag:{}", t.tag.name), t.tag.name, SuggestionType::Tag, 0.7)),
+            ),
+            Err(e) => warn!("Failed to list tags for autocompletion: {}", e),
+        }
+
+        let mut suggestions: Vec<SearchSuggestion> = candidates
+            .into_iter()
+            .filter_map(|(query, text, suggestion_type, weight)| {
+                let score = weight * fuzzy_match(partial_query, &text)?;
+                Some(SearchSuggestion { query, suggestion_type, score })
+            })
+            .collect();
+
+        // Best-scored first, keeping the best of each query
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+        let mut seen = std::collections::HashSet::new();
+        suggestions.retain(|s| seen.insert(s.query.to_lowercase()));
+        suggestions.truncate(limit);
+        suggestions
+    }
 }
 
 #[cfg(test)]
@@ -778,7 +1454,9 @@ mod tests {
             relevance_score: 0.8,
             snippet: None,
             keywords: vec!["rust".to_string()],
+            category: None,
             last_accessed: Utc::now(),
+            access_count: 0,
             browser_type: Some(BrowserType::Chrome),
         };
 
@@ -813,7 +1491,9 @@ mod tests {
                 relevance_score: 0.5,
                 snippet: None,
                 keywords: vec![],
+                category: None,
                 last_accessed: Utc::now() - chrono::Duration::hours(1),
+                access_count: 0,
                 browser_type: None,
             },
             SearchResultItem {
@@ -825,7 +1505,9 @@ mod tests {
                 relevance_score: 0.9,
                 snippet: None,
                 keywords: vec![],
+                category: None,
                 last_accessed: Utc::now(),
+                access_count: 0,
                 browser_type: None,
             },
         ];
@@ -871,7 +1553,9 @@ mod tests {
                     relevance_score: 0.8,
                     snippet: None,
                     keywords: vec![],
+                    category: None,
                     last_accessed: Utc::now(),
+                    access_count: 0,
                     browser_type: None,
                 },
                 SearchResultItem {
@@ -883,7 +1567,9 @@ mod tests {
                     relevance_score: 0.7,
                     snippet: None,
                     keywords: vec![],
+                    category: None,
                     last_accessed: Utc::now(),
+                    access_count: 0,
                     browser_type: None,
                 },
                 SearchResultItem {
@@ -895,16 +1581,395 @@ mod tests {
                     relevance_score: 0.6,
                     snippet: None,
                     keywords: vec![],
+                    category: None,
                     last_accessed: Utc::now(),
+                    access_count: 0,
                     browser_type: None,
                 },
             ],
             search_time_ms: 10,
             filter: SearchFilter::default(),
+            facets: SearchFacets::default(),
         };
 
         let groups = results.group_by_source();
         assert_eq!(groups.get(&SearchResultSource::ActiveTab).map(|v| v.len()), Some(2));
         assert_eq!(groups.get(&SearchResultSource::Bookmark).map(|v| v.len()), Some(1));
     }
+
+    #[test]
+    fn test_search_facets() {
+        let now = Utc::now();
+        let item = |source_type, browser_type, category: Option<&str>, keywords: &[&str], days: i64| SearchResultItem {
+            id: Uuid::new_v4(),
+            url: format!("https://example.com/{}", Uuid::new_v4()),
+            title: "Example".to_string(),
+            favicon_url: None,
+            source_type,
+            relevance_score: 0.5,
+            snippet: None,
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            category: category.map(str::to_string),
+            last_accessed: now - chrono::Duration::days(days),
+            access_count: 0,
+            browser_type,
+        };
+        let items = vec![
+            item(SearchResultSource::ActiveTab, Some(BrowserType::Chrome), Some("Programming"), &["rust", "Rust"], 0),
+            item(SearchResultSource::Bookmark, Some(BrowserType::Firefox), Some("programming"), &["rust"], 3),
+            item(SearchResultSource::Bookmark, Some(BrowserType::Chrome), None, &["python"], 3),
+            item(SearchResultSource::Archive, None, Some("News"), &[], 400),
+        ];
+
+        let facets = SearchFacets::from_items(&items, now);
+        fn count<T>(value: T, count: usize) -> FacetCount<T> {
+            FacetCount { value, count }
+        }
+        assert_eq!(facets.source_types, vec![
+            count(SearchResultSource::Bookmark, 2),
+            count(SearchResultSource::ActiveTab, 1),
+            count(SearchResultSource::Archive, 1),
+        ]);
+        assert_eq!(facets.browsers, vec![count(BrowserType::Chrome, 2), count(BrowserType::Firefox, 1)]);
+        assert_eq!(facets.categories, vec![count("programming".to_string(), 2), count("news".to_string(), 1)]);
+        // A keyword repeated on one result counts once
+        assert_eq!(facets.tags, vec![count("rust".to_string(), 2), count("python".to_string(), 1)]);
+        assert_eq!(facets.time_buckets, vec![
+            count(TimeBucket::PastWeek, 2),
+            count(TimeBucket::Today, 1),
+            count(TimeBucket::Older, 1),
+        ]);
+        assert_eq!(SearchFacets::from_items(&[], now), SearchFacets::default());
+    }
+
+    #[tokio::test]
+    async fn test_search_query_language() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let page = |url: &str, title: &str, keywords: &[&str], accessed: DateTime<Utc>| UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: url.to_string(),
+            title: title.to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            category: Some("Programming".to_string()),
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Firefox,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: accessed,
+            last_accessed: accessed,
+            access_count: 0,
+        };
+        let recent = Utc::now();
+        let old = Utc::now() - chrono::Duration::days(3 * 365);
+        db.page_repository()
+            .save_batch(&[
+                page("https://github.com/tokio-rs/tokio", "Tokio runtime", &["rust", "async"], recent),
+                page("https://github.com/psf/requests", "Requests", &["python"], recent),
+                page("https://github.com/rust-lang/rust", "Rust compiler", &["rust"], old),
+                page("https://docs.rs/tokio", "tokio docs", &["rust"], recent),
+            ])
+            .await
+            .unwrap();
+        let manager = UnifiedSearchManager::new(&db);
+
+        let after = (Utc::now() - chrono::Duration::days(365)).format("%Y-%m-%d").to_string();
+        let titles = |results: SearchResults| {
+            let mut titles: Vec<String> = results.items.into_iter().map(|i| i.title).collect();
+            titles.sort();
+            titles
+        };
+
+        let results = manager
+            .search_query(&format!("domain:github.com tag:rust after:{}", after), SearchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(titles(results), vec!["Tokio runtime"]);
+
+        // Without text, pages are listed through repository filters
+        let results = manager
+            .search_query("tag:rust -domain:docs.rs category:programming", SearchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(results.facets.time_buckets.len(), 2);
+        assert_eq!(titles(results), vec!["Rust compiler", "Tokio runtime"]);
+
+        let results = manager.search_query("tokio OR title:requests", SearchOptions::default()).await.unwrap();
+        assert_eq!(titles(results), vec!["Requests", "Tokio runtime", "tokio docs"]);
+
+        assert!(manager.search_query("browser:netscape", SearchOptions::default()).await.is_err());
+        assert_eq!(manager.get_search_history(1).await[0].query, "tokio OR title:requests");
+    }
+
+    #[test]
+    fn test_snippet_text_cuts_characters() {
+        let cjk = "浏览器标签页管理".repeat(30);
+        let snippet = snippet_text(&cjk);
+        assert_eq!(snippet.chars().count(), 203);
+        assert!(snippet.ends_with("..."));
+        assert_eq!(snippet_text("短摘要"), "短摘要");
+    }
+
+    #[test]
+    fn test_fuzzy_matching() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("rust", "rust"), 0);
+        assert_eq!(edit_distance("pyhton", "python"), 1);
+
+        assert_eq!(fuzzy_word_score("rust", "rust"), Some(1.0));
+        assert_eq!(fuzzy_word_score("prog", "programming"), Some(0.9));
+        // One typo in a whole word or in the typed part of a longer one
+        assert!(fuzzy_word_score("pyhton", "python").is_some());
+        assert!(fuzzy_word_score("progrm", "programming").is_some());
+        // Short words must match exactly
+        assert_eq!(fuzzy_word_score("rsu", "rust"), None);
+        assert_eq!(fuzzy_word_score("java", "rust"), None);
+
+        assert!(fuzzy_match("Rust progr", "The Rust Programming Language").unwrap() > 0.9);
+        assert!(fuzzy_match("langauge", "The Rust Programming Language").is_some());
+        assert_eq!(fuzzy_match("rust python", "The Rust Programming Language"), None);
+        assert_eq!(fuzzy_match("", "anything"), None);
+    }
+
+    #[tokio::test]
+    async fn test_typo_tolerant_search_and_autocomplete() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let manager = UnifiedSearchManager::new(&db);
+        manager
+            .update_tabs(vec![TabInfo {
+                id: TabId::new(),
+                url: "https://www.github.com/tokio-rs/tokio".to_string(),
+                title: "Tokio asynchronous runtime".to_string(),
+                favicon_url: None,
+                browser_type: BrowserType::Chrome,
+                is_private: false,
+                created_at: Utc::now(),
+                last_accessed: Utc::now(),
+            }])
+            .await;
+        let tags = db.tag_repository();
+        let tag = tags.get_or_create("Rustlang").await.unwrap();
+        let page = UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: "https://doc.rust-lang.org/book".to_string(),
+            title: "The Rust Programming Language".to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: vec![],
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Firefox,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        db.page_repository().save(&page).await.unwrap();
+        tags.tag_page(&page.id, &tag.id).await.unwrap();
+
+        // Misspelled queries still find the tab and the page
+        let results = manager.search("asynchronuos", SearchOptions::default()).await;
+        assert_eq!(results.items.len(), 1);
+        assert_eq!(results.items[0].source_type, SearchResultSource::ActiveTab);
+        let results = manager.search("programing", SearchOptions::default()).await;
+        assert_eq!(results.items.len(), 1);
+        assert_eq!(results.items[0].title, "The Rust Programming Language");
+        assert!(results.items[0].relevance_score < 0.8);
+
+        let queries = |suggestions: Vec<SearchSuggestion>| {
+            suggestions.into_iter().map(|s| s.query).collect::<Vec<_>>()
+        };
+        assert_eq!(queries(manager.autocomplete("tok", 10).await), vec!["Tokio asynchronous runtime"]);
+        assert_eq!(queries(manager.autocomplete("githb", 10).await), vec!["domain:github.com"]);
+        assert_eq!(
+            queries(manager.autocomplete("rust", 10).await),
+            vec!["tag:Rustlang", "The Rust Programming Language", "domain:doc.rust-lang.org"]
+        );
+        assert_eq!(manager.autocomplete("rust", 1).await.len(), 1);
+        assert!(manager.autocomplete("zzz", 10).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let manager = UnifiedSearchManager::new(&db);
+        let page = |url: &str, title: &str, keywords: &[&str]| UnifiedPageInfo {
+            id: Uuid::new_v4(),
+            url: url.to_string(),
+            title: title.to_string(),
+            favicon_url: None,
+            content_summary: None,
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            category: None,
+            source_type: PageSourceType::Bookmark {
+                browser: BrowserType::Firefox,
+                bookmark_id: BookmarkId::new(),
+            },
+            browser_info: None,
+            tab_info: None,
+            bookmark_info: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+        };
+        let article = page("https://blog.example.com/cancel", "Async cancellation in Rust", &["tokio"]);
+        let bread = page("https://bread.example.com", "Baking sourdough bread", &["baking"]);
+        for p in [&article, &bread] {
+            db.page_repository().save(p).await.unwrap();
+            manager.index_page(p).await.unwrap();
+        }
+        // A closed tab whose page was never indexed
+        let talk = page("https://talks.example.com/concurrency", "Structured concurrency and cancellation", &[]);
+        db.page_repository().save(&talk).await.unwrap();
+        db.history_repository()
+            .save(&HistoryEntry {
+                id: HistoryId::new(),
+                page_info: talk,
+                browser_type: BrowserType::Chrome,
+                tab_id: None,
+                closed_at: Utc::now(),
+                session_info: None,
+                recall_hint: None,
+            })
+            .await
+            .unwrap();
+
+        let query = "that article about async cancellation patterns";
+        assert!(manager.search(query, SearchOptions::default()).await.items.is_empty());
+
+        let semantic = SearchOptions { mode: SearchMode::Semantic, ..SearchOptions::default() };
+        let results = manager.search(query, semantic.clone()).await;
+        let urls: Vec<&str> = results.items.iter().map(|i| i.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://blog.example.com/cancel", "https://talks.example.com/concurrency"]);
+
+        // Keyword matches rank above results found by meaning alone
+        let results = manager.search("async cancellation", semantic).await;
+        assert_eq!(results.items.len(), 2);
+        assert_eq!(results.items[0].url, "https://blog.example.com/cancel");
+        assert!(results.items[0].relevance_score > results.items[1].relevance_score);
+    }
+
+    #[test]
+    fn test_ranking_weights() {
+        let now = Utc::now();
+        let item = |days: i64, access_count: u32| SearchResultItem {
+            id: Uuid::new_v4(),
+            url: "https://example.com".to_string(),
+            title: "Example".to_string(),
+            favicon_url: None,
+            source_type: SearchResultSource::UnifiedPage,
+            relevance_score: 0.8,
+            snippet: None,
+            keywords: vec![],
+            category: None,
+            last_accessed: now - chrono::Duration::days(days),
+            access_count,
+            browser_type: None,
+        };
+        let weights = RankingWeights::default();
+
+        let fresh = weights.rank(&item(0, 0), false, now);
+        let stale = weights.rank(&item(5 * 365, 0), false, now);
+        assert!(fresh > stale);
+        // A month old page has about half the recency boost
+        let month = weights.rank(&item(30, 0), false, now);
+        assert!((fresh - month - 0.5 * weights.recency / 1.6).abs() < 0.01);
+        assert!(weights.rank(&item(5 * 365, 100), false, now) > stale);
+        assert!(weights.rank(&item(5 * 365, 0), true, now) > stale);
+        assert!(weights.rank(&item(0, 1000), true, now) <= 1.0);
+
+        let plain = RankingWeights::relevance_only();
+        assert_eq!(plain.rank(&item(5 * 365, 0), true, now), 0.8);
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_current_pages_first() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let old = Utc::now() - chrono::Duration::days(6 * 365);
+        let manager = UnifiedSearchManager::new(&db);
+        cache_old_bookmark_and_open_tab(&manager, old).await;
+
+        let results = manager.search("async book", SearchOptions::default()).await;
+        assert_eq!(results.items.len(), 2);
+        assert_eq!(results.items[0].url, "https://rust-lang.github.io/async-book");
+        assert!(results.items[0].relevance_score > results.items[1].relevance_score);
+
+        // Without boosts the old bookmark's better title match wins
+        let manager = UnifiedSearchManager::new(&db).with_ranking(RankingWeights::relevance_only());
+        cache_old_bookmark_and_open_tab(&manager, old).await;
+        let results = manager.search("async book", SearchOptions::default()).await;
+        assert_eq!(results.items[0].url, "https://example.com/async-book-2019");
+    }
+
+    /// Cache an old bookmark titled like the query and an open tab
+    /// matching it less closely
+    async fn cache_old_bookmark_and_open_tab(manager: &UnifiedSearchManager, old: DateTime<Utc>) {
+        manager
+            .update_bookmarks(vec![BookmarkInfo {
+                id: BookmarkId::new(),
+                url: "https://example.com/async-book-2019".to_string(),
+                title: "Async book".to_string(),
+                favicon_url: None,
+                browser_type: BrowserType::Firefox,
+                folder_path: vec![],
+                created_at: old,
+                last_accessed: Some(old),
+            }])
+            .await;
+        manager
+            .update_tabs(vec![TabInfo {
+                id: TabId::new(),
+                url: "https://rust-lang.github.io/async-book".to_string(),
+                title: "Asynchronous Programming in Rust: the async book".to_string(),
+                favicon_url: None,
+                browser_type: BrowserType::Chrome,
+                is_private: false,
+                created_at: Utc::now(),
+                last_accessed: Utc::now(),
+            }])
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_search_history_and_saved_searches_persist() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let manager = UnifiedSearchManager::new(&db);
+
+        manager.search("rust async", SearchOptions::default()).await;
+        manager.search("Rust Async", SearchOptions::default()).await;
+        manager.search("python", SearchOptions::default()).await;
+        let history = manager.get_search_history(10).await;
+        assert_eq!(history.iter().map(|e| e.query.as_str()).collect::<Vec<_>>(), vec!["python", "Rust Async"]);
+        let suggestions = manager.get_suggestions("ru").await;
+        assert!(matches!(suggestions[0].suggestion_type, SuggestionType::History));
+
+        // A second manager on the same database sees the history and saved searches
+        let filter = SearchFilter::history_only().with_browser(BrowserType::Firefox);
+        let saved = manager.save_search("Firefox history", "rust", &filter).await.unwrap();
+        let other = UnifiedSearchManager::new(&db);
+        assert_eq!(other.get_search_history(10).await.len(), 2);
+        let listed = other.saved_searches().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(serde_json::from_value::<SearchFilter>(listed[0].filter.clone()).unwrap(), filter);
+
+        // Saving under the same name replaces the search
+        let replaced = other.save_search("firefox history", "rust book", &filter).await.unwrap();
+        assert_eq!(replaced.id, saved.id);
+        let results = other.run_saved_search(&saved.id, SearchOptions::default()).await.unwrap().unwrap();
+        assert_eq!((results.query.as_str(), &results.filter), ("rust book", &filter));
+
+        assert!(other.delete_saved_search(&saved.id).await.unwrap());
+        assert!(other.run_saved_search(&saved.id, SearchOptions::default()).await.unwrap().is_none());
+        other.clear_search_history().await;
+        assert!(manager.get_search_history(10).await.is_empty());
+    }
 }
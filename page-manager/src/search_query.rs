@@ -0,0 +1,467 @@
+//! Search Query Language
+//!
+//! Parses queries such as `domain:github.com tag:rust after:2024-01-01`
+//! for power users of unified search.
+//!
+//! # Syntax
+//! - Words and `"quoted phrases"` match titles, URLs, keywords and snippets
+//! - `title:`, `domain:` (or `site:`), `tag:` and `category:` filter on one
+//!   field; values may be quoted
+//! - `browser:chrome|firefox|edge|safari`
+//! - `before:` and `after:` take `YYYY`, `YYYY-MM`, `YYYY-MM-DD` or an
+//!   RFC 3339 time; `after:` includes the given day, `before:` excludes it
+//! - Terms are combined with `AND` by default; `OR`, `NOT` (or a leading
+//!   `-`) and parentheses group them, with `NOT` binding tightest and `OR`
+//!   loosest
+//!
+//! A field prefix that is not one of the above, as in `https://...`, is
+//! part of a plain word.
+
+use web_page_manager_core::*;
+use crate::search::{SearchFilter, SearchResultItem};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use data_access::PageQuery;
+
+/// A single condition of a search query
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryTerm {
+    /// Word or phrase anywhere in the title, URL, keywords or snippet
+    Text(String),
+    Title(String),
+    /// Host or one of its parent domains, ignoring `www.`
+    Domain(String),
+    /// Keyword or tag
+    Tag(String),
+    Category(String),
+    Browser(BrowserType),
+    /// Last accessed before this time
+    Before(DateTime<Utc>),
+    /// Last accessed at or after this time
+    After(DateTime<Utc>),
+}
+
+/// Boolean combination of query terms
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryExpr {
+    Term(QueryTerm),
+    And(Vec<QueryExpr>),
+    Or(Vec<QueryExpr>),
+    Not(Box<QueryExpr>),
+}
+
+impl QueryExpr {
+    /// Check if a search result satisfies the expression
+    pub fn matches(&self, item: &SearchResultItem) -> bool {
+        match self {
+            QueryExpr::Term(term) => term.matches(item),
+            QueryExpr::And(exprs) => exprs.iter().all(|e| e.matches(item)),
+            QueryExpr::Or(exprs) => exprs.iter().any(|e| e.matches(item)),
+            QueryExpr::Not(expr) => !expr.matches(item),
+        }
+    }
+
+    /// Terms every match must satisfy: those ANDed at the top level
+    fn required_terms(&self) -> Vec<&QueryTerm> {
+        match self {
+            QueryExpr::Term(term) => vec![term],
+            QueryExpr::And(exprs) => exprs.iter().flat_map(|e| e.required_terms()).collect(),
+            QueryExpr::Or(_) | QueryExpr::Not(_) => vec![],
+        }
+    }
+}
+
+impl QueryTerm {
+    /// Check if a search result satisfies the term
+    pub fn matches(&self, item: &SearchResultItem) -> bool {
+        let contains = |haystack: &str, needle: &str| haystack.to_lowercase().contains(&needle.to_lowercase());
+        match self {
+            QueryTerm::Text(text) => {
+                contains(&item.title, text)
+                    || contains(&item.url, text)
+                    || item.keywords.iter().any(|k| contains(k, text))
+                    || item.snippet.as_deref().is_some_and(|s| contains(s, text))
+            }
+            QueryTerm::Title(text) => contains(&item.title, text),
+            QueryTerm::Domain(domain) => url::Url::parse(&item.url)
+                .ok()
+                .and_then(|u| u.host_str().map(str::to_lowercase))
+                .is_some_and(|host| {
+                    let host = host.strip_prefix("www.").unwrap_or(&host);
+                    host == domain || host.ends_with(&format!(".{}", domain))
+                }),
+            QueryTerm::Tag(tag) => item.keywords.iter().any(|k| k.eq_ignore_ascii_case(tag)),
+            QueryTerm::Category(category) => {
+                item.category.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(category))
+            }
+            QueryTerm::Browser(browser) => item.browser_type == Some(*browser),
+            QueryTerm::Before(time) => item.last_accessed < *time,
+            QueryTerm::After(time) => item.last_accessed >= *time,
+        }
+    }
+}
+
+/// Parsed search query
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SearchQuery {
+    /// The query, None if it was empty
+    pub expr: Option<QueryExpr>,
+}
+
+impl SearchQuery {
+    /// Parse a query string
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, position: 0 };
+        if parser.tokens.is_empty() {
+            return Ok(Self::default());
+        }
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.position) {
+            return Err(query_error(format!("unexpected {:?}", token)));
+        }
+        Ok(Self { expr: Some(expr) })
+    }
+
+    /// Check if a search result satisfies the query; an empty query
+    /// matches everything
+    pub fn matches(&self, item: &SearchResultItem) -> bool {
+        self.expr.as_ref().is_none_or(|e| e.matches(item))
+    }
+
+    fn required_terms(&self) -> Vec<&QueryTerm> {
+        self.expr.as_ref().map(|e| e.required_terms()).unwrap_or_default()
+    }
+
+    /// Full-text query narrowing the candidates: the required words,
+    /// titles and domains, empty if there are none
+    pub fn text(&self) -> String {
+        self.required_terms()
+            .into_iter()
+            .filter_map(|term| match term {
+                QueryTerm::Text(text) | QueryTerm::Title(text) | QueryTerm::Domain(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Narrow `filter` by the required browser, date and category
+    /// conditions
+    ///
+    /// Conditions already set on the filter are kept, except that dates
+    /// are narrowed to the tighter bound.
+    pub fn narrow_filter(&self, filter: &mut SearchFilter) {
+        for term in self.required_terms() {
+            match term {
+                QueryTerm::Browser(browser) if filter.browser_type.is_none() => {
+                    filter.browser_type = Some(*browser)
+                }
+                QueryTerm::Category(category) if filter.category.is_none() => {
+                    filter.category = Some(category.clone())
+                }
+                QueryTerm::After(time) => {
+                    filter.from_date = Some(filter.from_date.map_or(*time, |from| from.max(*time)))
+                }
+                QueryTerm::Before(time) => {
+                    filter.to_date = Some(filter.to_date.map_or(*time, |to| to.min(*time)))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Page repository query with the required conditions it supports
+    ///
+    /// Categories are left out, as the repository compares them with case
+    /// and the query language does not.
+    pub fn page_query(&self) -> PageQuery {
+        let mut query = PageQuery::default();
+        for term in self.required_terms() {
+            match term {
+                QueryTerm::Browser(browser) => query.browser = Some(*browser),
+                QueryTerm::After(time) => query.accessed_after = Some(*time),
+                QueryTerm::Before(time) => query.accessed_before = Some(*time),
+                _ => {}
+            }
+        }
+        query
+    }
+
+    /// History repository filter with the required conditions it supports
+    pub fn history_filter(&self, limit: usize) -> HistoryFilter {
+        let mut filter = HistoryFilter {
+            limit: Some(limit),
+            ..Default::default()
+        };
+        for term in self.required_terms() {
+            match term {
+                QueryTerm::Browser(browser) => filter.browser_type = Some(*browser),
+                QueryTerm::After(time) => filter.from_date = Some(*time),
+                QueryTerm::Before(time) => filter.to_date = Some(*time),
+                _ => {}
+            }
+        }
+        filter
+    }
+}
+
+fn query_error(details: String) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Invalid search query: {}", details),
+        },
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(QueryTerm),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '(' || c == ')' {
+            chars.next();
+            tokens.push(if c == '(' { Token::LParen } else { Token::RParen });
+            continue;
+        }
+        if c == '-' {
+            chars.next();
+            tokens.push(Token::Not);
+            continue;
+        }
+
+        // A word, possibly `field:value`, where either part may be quoted
+        let mut word = String::new();
+        let mut quoted = false;
+        while let Some(&c) = chars.peek() {
+            if c == '"' {
+                chars.next();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        closed = true;
+                        break;
+                    }
+                    word.push(c);
+                }
+                if !closed {
+                    return Err(query_error("unterminated quote".to_string()));
+                }
+                quoted = true;
+            } else if c.is_whitespace() || c == '(' || c == ')' {
+                break;
+            } else {
+                word.push(c);
+                chars.next();
+            }
+        }
+
+        let token = match word.as_str() {
+            "AND" if !quoted => Token::And,
+            "OR" if !quoted => Token::Or,
+            "NOT" if !quoted => Token::Not,
+            _ => Token::Term(parse_term(&word)?),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_term(word: &str) -> Result<QueryTerm> {
+    let Some((field, value)) = word.split_once(':') else {
+        return Ok(QueryTerm::Text(word.to_string()));
+    };
+    let value = value.trim();
+    let field = field.to_lowercase();
+    let known = matches!(
+        field.as_str(),
+        "title" | "domain" | "site" | "tag" | "category" | "browser" | "before" | "after"
+    );
+    if !known {
+        return Ok(QueryTerm::Text(word.to_string()));
+    }
+    if value.is_empty() {
+        return Err(query_error(format!("missing value for {}:", field)));
+    }
+
+    Ok(match field.as_str() {
+        "title" => QueryTerm::Title(value.to_string()),
+        "domain" | "site" => {
+            let domain = value.to_lowercase();
+            QueryTerm::Domain(domain.strip_prefix("www.").unwrap_or(&domain).to_string())
+        }
+        "tag" => QueryTerm::Tag(value.to_string()),
+        "category" => QueryTerm::Category(value.to_string()),
+        "browser" => QueryTerm::Browser(match value.to_lowercase().as_str() {
+            "chrome" => BrowserType::Chrome,
+            "firefox" => BrowserType::Firefox,
+            "edge" => BrowserType::Edge,
+            "safari" => BrowserType::Safari,
+            other => return Err(query_error(format!("unknown browser '{}'", other))),
+        }),
+        "before" => QueryTerm::Before(parse_date(value)?),
+        _ => QueryTerm::After(parse_date(value)?),
+    })
+}
+
+/// Start of the year, month or day given, or an exact RFC 3339 time
+fn parse_date(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let date = match value.split('-').collect::<Vec<_>>().as_slice() {
+        [year] => year.parse().ok().and_then(|y| NaiveDate::from_ymd_opt(y, 1, 1)),
+        [year, month] => match (year.parse(), month.parse()) {
+            (Ok(y), Ok(m)) => NaiveDate::from_ymd_opt(y, m, 1),
+            _ => None,
+        },
+        _ => NaiveDate::parse_from_str(value, "%Y-%m-%d").ok(),
+    };
+    date.and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|t| Utc.from_utc_datetime(&t))
+        .ok_or_else(|| query_error(format!("invalid date '{}'", value)))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<QueryExpr> {
+        let mut exprs = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            exprs.push(self.parse_and()?);
+        }
+        Ok(if exprs.len() == 1 { exprs.remove(0) } else { QueryExpr::Or(exprs) })
+    }
+
+    fn parse_and(&mut self) -> Result<QueryExpr> {
+        let mut exprs = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(Token::Or) | Some(Token::RParen) | None => break,
+                _ => {}
+            }
+            exprs.push(self.parse_unary()?);
+        }
+        Ok(if exprs.len() == 1 { exprs.remove(0) } else { QueryExpr::And(exprs) })
+    }
+
+    fn parse_unary(&mut self) -> Result<QueryExpr> {
+        match self.next() {
+            Some(Token::Not) => Ok(QueryExpr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err(query_error("missing closing parenthesis".to_string())),
+                }
+            }
+            Some(Token::Term(term)) => Ok(QueryExpr::Term(term)),
+            Some(token) => Err(query_error(format!("unexpected {:?}", token))),
+            None => Err(query_error("unexpected end of query".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(url: &str, title: &str, keywords: &[&str]) -> SearchResultItem {
+        SearchResultItem {
+            id: Uuid::new_v4(),
+            url: url.to_string(),
+            title: title.to_string(),
+            favicon_url: None,
+            source_type: crate::search::SearchResultSource::Bookmark,
+            relevance_score: 0.5,
+            snippet: None,
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            category: Some("Programming".to_string()),
+            last_accessed: Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap(),
+            access_count: 0,
+            browser_type: Some(BrowserType::Firefox),
+        }
+    }
+
+    #[test]
+    fn test_parse_fields_and_operators() {
+        let query = SearchQuery::parse("domain:www.GitHub.com tag:rust after:2024-01-01 \"async book\"").unwrap();
+        let january = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            query.expr,
+            Some(QueryExpr::And(vec![
+                QueryExpr::Term(QueryTerm::Domain("github.com".to_string())),
+                QueryExpr::Term(QueryTerm::Tag("rust".to_string())),
+                QueryExpr::Term(QueryTerm::After(january)),
+                QueryExpr::Term(QueryTerm::Text("async book".to_string())),
+            ]))
+        );
+        assert_eq!(query.text(), "github.com async book");
+        assert_eq!(query.page_query().accessed_after, Some(january));
+
+        let query = SearchQuery::parse("rust OR go -title:beginner").unwrap();
+        assert!(matches!(query.expr, Some(QueryExpr::Or(ref e)) if matches!(e[1], QueryExpr::And(_))));
+        // Only top-level conjuncts narrow the search
+        assert_eq!(query.text(), "");
+
+        let query = SearchQuery::parse("(browser:Firefox OR browser:edge) NOT category:news https://x.org").unwrap();
+        assert_eq!(query.text(), "https://x.org");
+        assert!(SearchQuery::parse("").unwrap().expr.is_none());
+
+        for invalid in ["browser:opera", "after:2024-13", "(rust", "\"open", "rust OR", "title:"] {
+            assert!(SearchQuery::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_query_matches_results() {
+        let page = item("https://docs.github.com/rust", "Rust on GitHub Actions", &["Rust", "ci"]);
+        let matches = |q: &str| SearchQuery::parse(q).unwrap().matches(&page);
+
+        assert!(matches("domain:github.com tag:rust after:2024-01-01"));
+        assert!(!matches("domain:hub.com"));
+        assert!(matches("title:actions browser:firefox category:programming"));
+        assert!(!matches("before:2024-03"));
+        assert!(matches("before:2024-04 after:2024"));
+        assert!(matches("python OR \"github actions\""));
+        assert!(!matches("rust -tag:ci"));
+        assert!(matches("NOT (tag:python OR browser:chrome)"));
+
+        let mut filter = SearchFilter::new();
+        SearchQuery::parse("browser:firefox after:2024-01-01 before:2025").unwrap().narrow_filter(&mut filter);
+        assert_eq!(filter.browser_type, Some(BrowserType::Firefox));
+        assert!(filter.from_date.is_some() && filter.to_date.is_some());
+        assert!(filter.matches(&page));
+    }
+}
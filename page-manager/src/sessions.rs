@@ -0,0 +1,255 @@
+//! Named Session Manager
+//!
+//! Snapshots the open tabs of every browser under a name, lists the saved
+//! sessions, and restores one later through the [`RemoteTabController`],
+//! either into the browsers the tabs came from or all into another one.
+//!
+//! Tabs report no window, so a snapshot holds one window per browser, in
+//! the order the browsers' tabs are listed. Sessions built elsewhere may
+//! hold several windows per browser; restoring through a connector opens
+//! a new window for each, while restoring through the connector manager
+//! opens every tab in the browser's current window.
+
+use web_page_manager_core::*;
+use browser_connector::{BrowserConnector, BrowserConnectorManager};
+use data_access::{SavedSession, SessionRepository, SessionSummary, SessionTab, SessionWindow};
+use crate::remote_controller::{RemoteTabController, TabOperationResult};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Outcome of restoring a saved session
+#[derive(Debug, Clone)]
+pub struct SessionRestoreResult {
+    /// The restored session
+    pub session_id: Uuid,
+    /// One create operation per tab, in restore order
+    pub operations: Vec<TabOperationResult>,
+}
+
+impl SessionRestoreResult {
+    /// Tabs opened successfully
+    pub fn restored_count(&self) -> usize {
+        self.operations.iter().filter(|op| op.is_success()).count()
+    }
+
+    /// Tabs that failed to open
+    pub fn failed_count(&self) -> usize {
+        self.operations.len() - self.restored_count()
+    }
+}
+
+/// Manager saving and restoring named tab sessions
+pub struct SessionManager {
+    repository: Arc<dyn SessionRepository>,
+    controller: Arc<RemoteTabController>,
+}
+
+impl SessionManager {
+    /// Create a manager storing sessions in `repository` and opening tabs
+    /// through `controller`
+    pub fn new(repository: Arc<dyn SessionRepository>, controller: Arc<RemoteTabController>) -> Self {
+        Self { repository, controller }
+    }
+
+    /// Save the tabs of every connected browser under `name`
+    pub async fn snapshot(&self, name: &str, connector: &BrowserConnectorManager) -> Result<SavedSession> {
+        let mut all_tabs: Vec<(BrowserType, Vec<TabInfo>)> = connector.get_all_tabs().await.into_iter().collect();
+        // Keep the windows in a stable order across snapshots
+        all_tabs.sort_by_key(|(browser, _)| format!("{:?}", browser));
+        let tabs: Vec<TabInfo> = all_tabs.into_iter().flat_map(|(_, tabs)| tabs).collect();
+        self.save_tabs(name, &tabs).await
+    }
+
+    /// Save `tabs` under `name`, one window per browser
+    ///
+    /// Private tabs are left out.
+    pub async fn save_tabs(&self, name: &str, tabs: &[TabInfo]) -> Result<SavedSession> {
+        let mut windows: Vec<SessionWindow> = Vec::new();
+        for tab in tabs.iter().filter(|t| !t.is_private) {
+            let session_tab = SessionTab {
+                url: tab.url.clone(),
+                title: tab.title.clone(),
+                favicon_url: tab.favicon_url.clone(),
+                pinned: false,
+            };
+            match windows.iter_mut().find(|w| w.browser == tab.browser_type) {
+                Some(window) => window.tabs.push(session_tab),
+                None => windows.push(SessionWindow {
+                    browser: tab.browser_type,
+                    tabs: vec![session_tab],
+                }),
+            }
+        }
+
+        let session = SavedSession::new(name.trim(), windows);
+        self.repository.save(&session).await?;
+        info!(
+            "Saved session '{}' with {} tabs in {} windows",
+            session.name,
+            session.tab_count(),
+            session.windows.len()
+        );
+        Ok(session)
+    }
+
+    /// Summaries of all saved sessions, most recently updated first
+    pub async fn list_sessions(&self) -> Result<Vec<SessionSummary>> {
+        self.repository.list().await
+    }
+
+    /// Get a saved session with its windows and tabs
+    pub async fn get_session(&self, id: &Uuid) -> Result<Option<SavedSession>> {
+        self.repository.get_by_id(id).await
+    }
+
+    /// Rename a session; false if it does not exist
+    pub async fn rename_session(&self, id: &Uuid, name: &str) -> Result<bool> {
+        self.repository.rename(id, name.trim()).await
+    }
+
+    /// Delete a saved session
+    pub async fn delete_session(&self, id: &Uuid) -> Result<()> {
+        self.repository.delete(id).await
+    }
+
+    /// Restore a session into `connector`'s browser, whichever browsers
+    /// its tabs came from, opening a new window for each saved window
+    pub async fn restore<C: BrowserConnector + ?Sized>(
+        &self,
+        id: &Uuid,
+        connector: &C,
+    ) -> Result<SessionRestoreResult> {
+        let session = self.load(id).await?;
+        let mut operations = Vec::with_capacity(session.tab_count());
+        for window in &session.windows {
+            let window_id = connector.create_window().await.unwrap_or_else(|e| {
+                warn!("Failed to open a window, restoring into the current one: {}", e);
+                None
+            });
+            for tab in &window.tabs {
+                operations.push(
+                    self.controller
+                        .create_tab_with(connector, &tab.url, window_id.as_deref(), tab.pinned)
+                        .await?,
+                );
+            }
+        }
+        Ok(self.finish(&session, operations))
+    }
+
+    /// Restore a session through the connector manager, into `target` or,
+    /// if None, each window into the browser it came from
+    pub async fn restore_via_manager(
+        &self,
+        id: &Uuid,
+        manager: &BrowserConnectorManager,
+        target: Option<BrowserType>,
+    ) -> Result<SessionRestoreResult> {
+        let session = self.load(id).await?;
+        let mut operations = Vec::with_capacity(session.tab_count());
+        for window in &session.windows {
+            let browser = target.unwrap_or(window.browser);
+            for tab in &window.tabs {
+                operations.push(self.controller.create_tab_via_manager(manager, browser, &tab.url).await?);
+            }
+        }
+        Ok(self.finish(&session, operations))
+    }
+
+    /// Load a session, failing if it does not exist
+    async fn load(&self, id: &Uuid) -> Result<SavedSession> {
+        self.repository.get_by_id(id).await?.ok_or_else(|| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Session not found: {}", id),
+            },
+        })
+    }
+
+    fn finish(&self, session: &SavedSession, operations: Vec<TabOperationResult>) -> SessionRestoreResult {
+        let result = SessionRestoreResult {
+            session_id: session.id,
+            operations,
+        };
+        info!(
+            "Restored session '{}': {} tabs opened, {} failed",
+            session.name,
+            result.restored_count(),
+            result.failed_count()
+        );
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{tab, MockConnector};
+    use data_access::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_save_list_and_restore_session() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let manager = SessionManager::new(Arc::new(db.session_repository()), Arc::new(RemoteTabController::new()));
+
+        let tabs = vec![
+            tab("https://a.example.com", BrowserType::Chrome),
+            tab("https://b.example.com", BrowserType::Firefox),
+            TabInfo {
+                is_private: true,
+                ..tab("https://secret.example.com", BrowserType::Chrome)
+            },
+            tab("https://c.example.com", BrowserType::Chrome),
+        ];
+        let session = manager.save_tabs(" Research ", &tabs).await.unwrap();
+        assert_eq!(session.name, "Research");
+        assert_eq!(session.windows.len(), 2);
+        assert_eq!(session.windows[0].browser, BrowserType::Chrome);
+        let urls: Vec<&str> = session.windows[0].tabs.iter().map(|t| t.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://a.example.com", "https://c.example.com"]);
+
+        let summaries = manager.list_sessions().await.unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!((summaries[0].window_count, summaries[0].tab_count), (2, 3));
+        assert!(manager.rename_session(&session.id, "Reading").await.unwrap());
+        assert_eq!(manager.get_session(&session.id).await.unwrap().unwrap().name, "Reading");
+
+        // Chrome and Firefox tabs all open in Edge, one new window each
+        let edge = MockConnector {
+            fail_url: "https://b.example.com",
+            ..MockConnector::new(BrowserType::Edge)
+        };
+        let result = manager.restore(&session.id, &edge).await.unwrap();
+        assert_eq!((result.restored_count(), result.failed_count()), (2, 1));
+        assert_eq!(*edge.opened.lock().unwrap(), vec![
+            ("https://a.example.com".to_string(), Some("Edge-window-1".to_string()), false),
+            ("https://c.example.com".to_string(), Some("Edge-window-1".to_string()), false),
+        ]);
+        assert_eq!(*edge.windows.lock().unwrap(), 2);
+
+        manager.delete_session(&session.id).await.unwrap();
+        assert!(manager.list_sessions().await.unwrap().is_empty());
+        assert!(manager.restore(&session.id, &edge).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_restore_via_manager_reports_disconnected_browsers() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let manager = SessionManager::new(Arc::new(db.session_repository()), Arc::new(RemoteTabController::new()));
+        let connectors = BrowserConnectorManager::new();
+
+        // Nothing is connected, so the snapshot is empty
+        let empty = manager.snapshot("Empty", &connectors).await.unwrap();
+        assert_eq!(empty.tab_count(), 0);
+
+        let session = manager
+            .save_tabs("Work", &[tab("https://a.example.com", BrowserType::Chrome)])
+            .await
+            .unwrap();
+        let result = manager
+            .restore_via_manager(&session.id, &connectors, Some(BrowserType::Firefox))
+            .await
+            .unwrap();
+        assert_eq!(result.failed_count(), 1);
+        assert_eq!(result.operations[0].record.browser_type, BrowserType::Firefox);
+    }
+}
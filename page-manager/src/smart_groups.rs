@@ -0,0 +1,539 @@
+//! Smart Group Generation
+//!
+//! Groups pages by clustering their embeddings: the average-linkage
+//! dendrogram of the pages is cut at the similarity threshold, and every
+//! cluster of at least `min_group_size` pages becomes a group.
+//!
+//! Groups keep their identity across regenerations. Each new cluster is
+//! matched to the previously generated group it overlaps most (by Jaccard
+//! similarity of their pages) and takes over its ID, name and creation
+//! time, so a regeneration is reported as a diff: pages added to and
+//! removed from existing groups, new groups and groups that dissolved.
+//!
+//! Users correct groups with overrides. A page pinned to a group is always
+//! in it, and in no other generated group; an excluded page is never put
+//! back. A group with pins is kept even when its cluster dissolves.
+//! Archived groups are left as they are.
+
+use web_page_manager_core::*;
+use ai_processor_ffi::clustering::Dendrogram;
+use ai_processor_ffi::embedding::{EmbeddingModel, HashingEmbedder};
+use ai_processor_ffi::progress::Progress;
+use data_access::{
+    EmbeddingRepository, GroupOverrideKind, GroupOverrideRepository, GroupRepository, GroupStateRepository,
+};
+use crate::search::{page_embedding_text, url_domain};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Algorithm name recorded on generated groups
+const ALGORITHM: &str = "embedding-average-linkage";
+
+/// Configuration for smart group generation
+#[derive(Debug, Clone)]
+pub struct SmartGroupConfig {
+    /// Minimum average similarity of a group's pages
+    pub similarity_threshold: f64,
+    /// Smallest group generated, pins aside
+    pub min_group_size: usize,
+    /// Minimum Jaccard overlap for a cluster to continue an earlier group
+    pub min_overlap: f32,
+}
+
+impl Default for SmartGroupConfig {
+    fn default() -> Self {
+        Self {
+            similarity_threshold: 0.5,
+            min_group_size: 2,
+            min_overlap: 0.3,
+        }
+    }
+}
+
+/// Pages added to and removed from a group by a regeneration
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupMembershipChange {
+    pub group_id: Uuid,
+    pub name: String,
+    pub added: Vec<Uuid>,
+    pub removed: Vec<Uuid>,
+}
+
+/// Changes made by a regeneration
+#[derive(Debug, Clone, Default)]
+pub struct GroupDiff {
+    /// Groups generated for the first time
+    pub created: Vec<SmartGroup>,
+    /// Existing groups whose pages changed
+    pub changed: Vec<GroupMembershipChange>,
+    /// Existing groups that dissolved
+    pub removed: Vec<SmartGroup>,
+    /// Existing groups left as they were
+    pub unchanged: Vec<Uuid>,
+}
+
+impl GroupDiff {
+    /// Whether the regeneration changed nothing
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Pins and exclusions of one group
+#[derive(Default)]
+struct Overrides {
+    pinned: Vec<Uuid>,
+    excluded: HashSet<Uuid>,
+}
+
+/// Outcome of a regeneration before it is saved
+struct Plan {
+    diff: GroupDiff,
+    /// Every generated group after the regeneration, with its pages
+    groups: Vec<SmartGroup>,
+}
+
+/// Generator of smart groups with stable identities
+pub struct SmartGroupGenerator {
+    config: SmartGroupConfig,
+    groups: Arc<dyn GroupRepository>,
+    overrides: Arc<dyn GroupOverrideRepository>,
+    embedder: Arc<dyn EmbeddingModel>,
+    embeddings: Option<Arc<dyn EmbeddingRepository>>,
+    state: Option<Arc<dyn GroupStateRepository>>,
+}
+
+impl SmartGroupGenerator {
+    /// Create a generator storing groups in `groups` and reading user
+    /// corrections from `overrides`
+    pub fn new(groups: Arc<dyn GroupRepository>, overrides: Arc<dyn GroupOverrideRepository>) -> Self {
+        Self {
+            config: SmartGroupConfig::default(),
+            groups,
+            overrides,
+            embedder: Arc::new(HashingEmbedder::default()),
+            embeddings: None,
+            state: None,
+        }
+    }
+
+    /// Use a custom configuration
+    pub fn with_config(mut self, config: SmartGroupConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Embed pages with `embedder` instead of the hashing embedder
+    pub fn with_embedder(mut self, embedder: Arc<dyn EmbeddingModel>) -> Self {
+        self.embedder = embedder;
+        self
+    }
+
+    /// Prefer the embeddings stored for semantic search, embedding only
+    /// pages without one
+    pub fn with_embedding_repository(mut self, embeddings: Arc<dyn EmbeddingRepository>) -> Self {
+        self.embeddings = Some(embeddings);
+        self
+    }
+
+    /// Leave the groups archived in `state` alone
+    pub fn with_group_state(mut self, state: Arc<dyn GroupStateRepository>) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Get the current configuration
+    pub fn config(&self) -> &SmartGroupConfig {
+        &self.config
+    }
+
+    /// Generated groups not archived, with their pages, most recent first
+    pub async fn generated_groups(&self) -> Result<Vec<SmartGroup>> {
+        let archived: HashSet<Uuid> = match &self.state {
+            Some(state) => state.archived().await?.into_iter().map(|a| a.group_id).collect(),
+            None => HashSet::new(),
+        };
+        let mut groups = Vec::new();
+        for mut group in self
+            .groups
+            .get_all()
+            .await?
+            .into_iter()
+            .filter(|g| g.auto_generated && !archived.contains(&g.id))
+        {
+            group.pages = self.groups.get_pages_in_group(&group.id).await?;
+            groups.push(group);
+        }
+        Ok(groups)
+    }
+
+    /// Changes regenerating the groups of `pages` would make, without
+    /// saving them
+    pub async fn preview(&self, pages: &[UnifiedPageInfo]) -> Result<GroupDiff> {
+        Ok(self.plan(pages).await?.diff)
+    }
+
+    /// Regenerate the groups of `pages` and save them
+    pub async fn regenerate(&self, pages: &[UnifiedPageInfo]) -> Result<GroupDiff> {
+        let plan = self.plan(pages).await?;
+
+        self.groups.save_batch(&plan.groups).await?;
+        for group in &plan.groups {
+            self.sync_pages(group).await?;
+        }
+        for group in &plan.diff.removed {
+            self.groups.delete(&group.id).await?;
+            self.overrides.clear_group(&group.id).await?;
+        }
+
+        info!(
+            "Regenerated smart groups: {} created, {} changed, {} removed, {} unchanged",
+            plan.diff.created.len(),
+            plan.diff.changed.len(),
+            plan.diff.removed.len(),
+            plan.diff.unchanged.len()
+        );
+        Ok(plan.diff)
+    }
+
+    /// Keep a page in a group across regenerations, adding it now
+    pub async fn pin_page(&self, group_id: &Uuid, page_id: &Uuid) -> Result<()> {
+        self.overrides.set(group_id, page_id, GroupOverrideKind::Pin).await?;
+        self.groups.add_page_to_group(page_id, group_id, 1.0).await
+    }
+
+    /// Keep a page out of a group across regenerations, removing it now
+    pub async fn exclude_page(&self, group_id: &Uuid, page_id: &Uuid) -> Result<()> {
+        self.overrides.set(group_id, page_id, GroupOverrideKind::Exclude).await?;
+        self.groups.remove_page_from_group(page_id, group_id).await
+    }
+
+    /// Let the generator decide about a page again; false if it had no
+    /// override in the group
+    pub async fn clear_override(&self, group_id: &Uuid, page_id: &Uuid) -> Result<bool> {
+        self.overrides.remove(group_id, page_id).await
+    }
+
+    async fn plan(&self, pages: &[UnifiedPageInfo]) -> Result<Plan> {
+        let existing = self.generated_groups().await?;
+        let mut overrides: HashMap<Uuid, Overrides> = HashMap::new();
+        // Overrides of archived or user-defined groups do not apply
+        for item in self.overrides.get_all().await?.into_iter().filter(|o| existing.iter().any(|g| g.id == o.group_id)) {
+            let entry = overrides.entry(item.group_id).or_default();
+            match item.kind {
+                GroupOverrideKind::Pin => entry.pinned.push(item.page_id),
+                GroupOverrideKind::Exclude => {
+                    entry.excluded.insert(item.page_id);
+                }
+            }
+        }
+
+        let clusters = self.cluster(pages).await?;
+        let order: HashMap<Uuid, usize> = pages.iter().enumerate().map(|(i, p)| (p.id, i)).collect();
+
+        // Greedily pair clusters with the earlier groups they overlap most
+        let mut pairs: Vec<(f32, usize, usize)> = Vec::new();
+        for (c, (members, _)) in clusters.iter().enumerate() {
+            for (g, group) in existing.iter().enumerate() {
+                let overlap = jaccard(members, &group.pages);
+                if overlap >= self.config.min_overlap {
+                    pairs.push((overlap, c, g));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+        let mut cluster_group: HashMap<usize, usize> = HashMap::new();
+        let mut matched_groups: HashSet<usize> = HashSet::new();
+        for (_, c, g) in pairs {
+            if !cluster_group.contains_key(&c) && !matched_groups.contains(&g) {
+                cluster_group.insert(c, g);
+                matched_groups.insert(g);
+            }
+        }
+
+        // Continued and new groups with their clustered pages, then the
+        // earlier groups nothing continued, emptied
+        let mut planned: Vec<(SmartGroup, Option<usize>)> = Vec::new();
+        for (c, (members, similarity)) in clusters.iter().enumerate() {
+            let group = match cluster_group.get(&c) {
+                Some(&g) => SmartGroup {
+                    pages: members.clone(),
+                    group_type: generated_type(*similarity),
+                    similarity_threshold: self.config.similarity_threshold as f32,
+                    ..existing[g].clone()
+                },
+                None => self.new_group(pages, members, *similarity),
+            };
+            planned.push((group, cluster_group.get(&c).copied()));
+        }
+        for (g, group) in existing.iter().enumerate().filter(|(g, _)| !matched_groups.contains(g)) {
+            planned.push((SmartGroup { pages: Vec::new(), ..group.clone() }, Some(g)));
+        }
+
+        // A page pinned to one group leaves the others
+        let pinned_to: HashMap<Uuid, HashSet<Uuid>> = overrides.iter().fold(HashMap::new(), |mut map, (group, o)| {
+            for page in &o.pinned {
+                map.entry(*page).or_default().insert(*group);
+            }
+            map
+        });
+        for (group, _) in &mut planned {
+            let rules = overrides.get(&group.id);
+            group.pages.retain(|page| {
+                !rules.is_some_and(|o| o.excluded.contains(page))
+                    && pinned_to.get(page).is_none_or(|groups| groups.contains(&group.id))
+            });
+            if let Some(rules) = rules {
+                for page in &rules.pinned {
+                    if !group.pages.contains(page) {
+                        group.pages.push(*page);
+                    }
+                }
+            }
+            group.pages.sort_by_key(|page| order.get(page).copied().unwrap_or(usize::MAX));
+        }
+
+        let mut plan = Plan {
+            diff: GroupDiff::default(),
+            groups: Vec::new(),
+        };
+        for (group, previous) in planned {
+            let pinned = overrides.get(&group.id).is_some_and(|o| !o.pinned.is_empty());
+            let keep = pinned || group.pages.len() >= self.config.min_group_size.max(1);
+            match previous.map(|g| &existing[g]) {
+                None if keep => plan.diff.created.push(group.clone()),
+                None => continue,
+                Some(old) if !keep => {
+                    plan.diff.removed.push(old.clone());
+                    continue;
+                }
+                Some(old) => {
+                    let added: Vec<Uuid> = group.pages.iter().filter(|p| !old.pages.contains(p)).copied().collect();
+                    let removed: Vec<Uuid> = old.pages.iter().filter(|p| !group.pages.contains(p)).copied().collect();
+                    if added.is_empty() && removed.is_empty() {
+                        plan.diff.unchanged.push(group.id);
+                    } else {
+                        plan.diff.changed.push(GroupMembershipChange {
+                            group_id: group.id,
+                            name: group.name.clone(),
+                            added,
+                            removed,
+                        });
+                    }
+                }
+            }
+            plan.groups.push(group);
+        }
+        Ok(plan)
+    }
+
+    /// Clusters of page IDs with the similarity they merged at
+    async fn cluster(&self, pages: &[UnifiedPageInfo]) -> Result<Vec<(Vec<Uuid>, f64)>> {
+        let mut ids = Vec::with_capacity(pages.len());
+        let mut vectors = Vec::with_capacity(pages.len());
+        for page in pages {
+            let stored = match &self.embeddings {
+                Some(repository) => repository.get(&page.id).await?,
+                None => None,
+            };
+            let vector = match stored.filter(|v| v.len() == self.embedder.dimensions()) {
+                Some(vector) => vector,
+                None => match self.embedder.embed(&page_embedding_text(page)) {
+                    Ok(vector) => vector,
+                    Err(e) => {
+                        debug!("Leaving {} out of smart groups: {}", page.url, e);
+                        continue;
+                    }
+                },
+            };
+            ids.push(page.id);
+            vectors.push(vector);
+        }
+
+        let progress = Progress::silent();
+        let dendrogram = Dendrogram::build(&vectors, &progress.stage("group", vectors.len().saturating_sub(1)));
+        Ok(dendrogram
+            .cut(self.config.similarity_threshold)
+            .into_iter()
+            .map(|cluster| (cluster.members.iter().map(|&i| ids[i]).collect(), cluster.similarity))
+            .collect())
+    }
+
+    /// A group for a cluster no earlier group continues, named after the
+    /// keywords or domain its pages share
+    fn new_group(&self, pages: &[UnifiedPageInfo], members: &[Uuid], similarity: f64) -> SmartGroup {
+        let members_info: Vec<&UnifiedPageInfo> = pages.iter().filter(|p| members.contains(&p.id)).collect();
+        let name = shared_label(members_info.iter().flat_map(|p| {
+            let mut keywords: Vec<String> = p.keywords.iter().map(|k| k.trim().to_lowercase()).collect();
+            keywords.sort();
+            keywords.dedup();
+            keywords
+        }))
+        .or_else(|| shared_label(members_info.iter().filter_map(|p| url_domain(&p.url))))
+        .unwrap_or_else(|| "Similar pages".to_string());
+
+        SmartGroup {
+            id: Uuid::new_v4(),
+            name,
+            description: format!("{} similar pages", members.len()),
+            group_type: generated_type(similarity),
+            pages: members.to_vec(),
+            created_at: Utc::now(),
+            auto_generated: true,
+            similarity_threshold: self.config.similarity_threshold as f32,
+        }
+    }
+
+    /// Bring a saved group's pages in line with `group.pages`
+    async fn sync_pages(&self, group: &SmartGroup) -> Result<()> {
+        let current = self.groups.get_pages_in_group(&group.id).await?;
+        for page in current.iter().filter(|p| !group.pages.contains(p)) {
+            self.groups.remove_page_from_group(page, &group.id).await?;
+        }
+        let confidence = match group.group_type {
+            GroupType::AIGenerated { confidence, .. } => confidence,
+            _ => 1.0,
+        };
+        for page in group.pages.iter().filter(|p| !current.contains(p)) {
+            self.groups.add_page_to_group(page, &group.id, confidence).await?;
+        }
+        Ok(())
+    }
+}
+
+fn generated_type(similarity: f64) -> GroupType {
+    GroupType::AIGenerated {
+        algorithm: ALGORITHM.to_string(),
+        confidence: similarity as f32,
+    }
+}
+
+/// Jaccard similarity of two page sets
+fn jaccard(a: &[Uuid], b: &[Uuid]) -> f32 {
+    let shared = a.iter().filter(|p| b.contains(p)).count();
+    let total = a.len() + b.len() - shared;
+    if total == 0 {
+        0.0
+    } else {
+        shared as f32 / total as f32
+    }
+}
+
+/// The one or two labels most pages share, joined, if any is shared
+fn shared_label(labels: impl Iterator<Item = String>) -> Option<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for label in labels.filter(|l| !l.is_empty()) {
+        *counts.entry(label).or_default() += 1;
+    }
+    let mut shared: Vec<(String, usize)> = counts.into_iter().filter(|(_, n)| *n >= 2).collect();
+    shared.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    if shared.is_empty() {
+        return None;
+    }
+    Some(shared.into_iter().take(2).map(|(label, _)| label).collect::<Vec<_>>().join(" & "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_page;
+    use data_access::{DatabaseManager, PageRepository};
+
+    /// Embeds pages by the topic word their text contains
+    struct TopicEmbedder;
+
+    impl EmbeddingModel for TopicEmbedder {
+        fn embed(&self, text: &str) -> std::result::Result<Vec<f32>, AIProcessingError> {
+            let text = text.to_lowercase();
+            Ok(if text.contains("rust") {
+                vec![1.0, 0.0, 0.0]
+            } else if text.contains("recipe") {
+                vec![0.0, 1.0, 0.0]
+            } else {
+                vec![0.0, 0.0, 1.0]
+            })
+        }
+        fn dimensions(&self) -> usize {
+            3
+        }
+        fn name(&self) -> &str {
+            "topic"
+        }
+    }
+
+    fn sorted(mut ids: Vec<Uuid>) -> Vec<Uuid> {
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_regeneration_keeps_group_ids_and_overrides() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let mut pages = vec![
+            test_page("https://doc.rust-lang.org/book", "The Rust Book", &["rust", "book"]),
+            test_page("https://tokio.rs", "Tokio, async Rust", &["rust", "async"]),
+            test_page("https://cooking.example.com/bread", "Bread recipe", &["baking"]),
+            test_page("https://cooking.example.com/soup", "Soup recipe", &["soup"]),
+            test_page("https://news.example.com", "Headlines", &[]),
+        ];
+        for p in &pages {
+            db.page_repository().save(p).await.unwrap();
+        }
+        let generator = SmartGroupGenerator::new(
+            Arc::new(db.group_repository()),
+            Arc::new(db.group_override_repository()),
+        )
+        .with_embedder(Arc::new(TopicEmbedder))
+        .with_group_state(Arc::new(db.group_state_repository()));
+
+        let diff = generator.regenerate(&pages).await.unwrap();
+        assert_eq!(diff.created.len(), 2);
+        let names: Vec<&str> = diff.created.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["rust", "cooking.example.com"]);
+        let rust_group = diff.created[0].id;
+        let cooking_group = diff.created[1].id;
+
+        // Nothing changed, so regenerating is a no-op
+        let diff = generator.regenerate(&pages).await.unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged.len(), 2);
+
+        // A new Rust page joins the same group, reported as a diff
+        let serde = test_page("https://serde.rs", "Serde for Rust", &["rust"]);
+        db.page_repository().save(&serde).await.unwrap();
+        pages.push(serde.clone());
+        let diff = generator.preview(&pages).await.unwrap();
+        assert!(diff.created.is_empty());
+        assert_eq!(diff.changed, vec![GroupMembershipChange {
+            group_id: rust_group,
+            name: "rust".to_string(),
+            added: vec![serde.id],
+            removed: vec![],
+        }]);
+        generator.regenerate(&pages).await.unwrap();
+
+        // Overrides outlast regeneration
+        generator.exclude_page(&rust_group, &serde.id).await.unwrap();
+        generator.pin_page(&cooking_group, &pages[4].id).await.unwrap();
+        let diff = generator.regenerate(&pages).await.unwrap();
+        assert!(diff.is_empty());
+        let groups = generator.generated_groups().await.unwrap();
+        let members = |id: Uuid| sorted(groups.iter().find(|g| g.id == id).unwrap().pages.clone());
+        assert_eq!(members(rust_group), sorted(vec![pages[0].id, pages[1].id]));
+        assert_eq!(members(cooking_group), sorted(vec![pages[2].id, pages[3].id, pages[4].id]));
+
+        // A pinned group survives its cluster dissolving, the other does not
+        let remaining = vec![pages[0].clone(), pages[4].clone()];
+        let diff = generator.regenerate(&remaining).await.unwrap();
+        assert_eq!(diff.removed.iter().map(|g| g.id).collect::<Vec<_>>(), vec![rust_group]);
+        assert_eq!(diff.changed[0].group_id, cooking_group);
+        assert_eq!(sorted(diff.changed[0].removed.clone()), sorted(vec![pages[2].id, pages[3].id]));
+        assert!(generator.clear_override(&cooking_group, &pages[4].id).await.unwrap());
+
+        // Archived groups are left alone even once unpinned
+        db.group_state_repository().archive(&cooking_group).await.unwrap();
+        assert!(generator.regenerate(&remaining).await.unwrap().is_empty());
+        assert!(db.group_repository().get_by_id(&cooking_group).await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,248 @@
+//! Tab Snoozing
+//!
+//! Snoozing closes a tab now and keeps it in the snooze repository with a
+//! wake time, picked from presets such as tonight or next Monday or given
+//! exactly. A background task reopens snoozed tabs through the
+//! [`RemoteTabController`] in the browser they were open in once they are
+//! due. A tab whose browser is not running stays snoozed and is retried on
+//! the next check.
+
+use web_page_manager_core::*;
+use browser_connector::{BrowserConnector, BrowserConnectorManager};
+use data_access::{SnoozeRepository, SnoozedTab};
+use crate::remote_controller::{RemoteTabController, TabOperationResult};
+use chrono::{Datelike, Days, Local, NaiveTime, TimeZone, Timelike};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Local hour "tonight" wakes at
+const EVENING_HOUR: u32 = 19;
+/// Local hour morning presets wake at
+const MORNING_HOUR: u32 = 9;
+
+/// When a snoozed tab should reopen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnoozeUntil {
+    /// This evening, or tomorrow evening once the evening has begun
+    Tonight,
+    /// Tomorrow morning
+    Tomorrow,
+    /// Monday morning of next week
+    NextMonday,
+    /// An exact time
+    At(DateTime<Utc>),
+}
+
+impl SnoozeUntil {
+    /// Wake time for a snooze made at `now`, whose time zone the presets
+    /// are read in
+    pub fn wake_time<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> DateTime<Utc> {
+        let today = now.date_naive();
+        let (day, hour) = match self {
+            SnoozeUntil::At(time) => return *time,
+            SnoozeUntil::Tonight if now.hour() < EVENING_HOUR => (today, EVENING_HOUR),
+            SnoozeUntil::Tonight => (today + Days::new(1), EVENING_HOUR),
+            SnoozeUntil::Tomorrow => (today + Days::new(1), MORNING_HOUR),
+            SnoozeUntil::NextMonday => {
+                let days = 7 - u64::from(now.weekday().num_days_from_monday());
+                (today + Days::new(days), MORNING_HOUR)
+            }
+        };
+        let local = day.and_time(NaiveTime::from_hms_opt(hour, 0, 0).unwrap_or_default());
+        // A wake time skipped by a clock change falls back to UTC
+        now.timezone()
+            .from_local_datetime(&local)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_else(|| local.and_utc())
+    }
+}
+
+/// Manager snoozing tabs and reopening them when due
+pub struct SnoozeManager {
+    repository: Arc<dyn SnoozeRepository>,
+    controller: Arc<RemoteTabController>,
+}
+
+impl SnoozeManager {
+    /// Create a manager storing snoozes in `repository` and closing and
+    /// reopening tabs through `controller`
+    pub fn new(repository: Arc<dyn SnoozeRepository>, controller: Arc<RemoteTabController>) -> Self {
+        Self { repository, controller }
+    }
+
+    /// Close `tab` and keep it until `until`, in the local time zone
+    ///
+    /// Nothing is kept if the tab fails to close.
+    pub async fn snooze_tab<C: BrowserConnector>(
+        &self,
+        connector: &C,
+        tab: &TabInfo,
+        until: SnoozeUntil,
+    ) -> Result<SnoozedTab> {
+        let snooze = SnoozedTab::new(tab, until.wake_time(&Local::now()));
+        // Stored first, so a closed tab is never lost
+        self.repository.save(&snooze).await?;
+        let result = self.controller.close_tab(connector, &tab.id, Some(tab)).await?;
+        self.finish_snooze(snooze, result).await
+    }
+
+    /// Close `tab` through the connector manager and keep it until `until`
+    pub async fn snooze_tab_via_manager(
+        &self,
+        manager: &BrowserConnectorManager,
+        tab: &TabInfo,
+        until: SnoozeUntil,
+    ) -> Result<SnoozedTab> {
+        let snooze = SnoozedTab::new(tab, until.wake_time(&Local::now()));
+        self.repository.save(&snooze).await?;
+        let result = self
+            .controller
+            .close_tab_via_manager(manager, tab.browser_type, &tab.id, Some(tab))
+            .await?;
+        self.finish_snooze(snooze, result).await
+    }
+
+    /// Keep `snooze` if its tab closed, otherwise drop it and fail
+    async fn finish_snooze(&self, snooze: SnoozedTab, result: TabOperationResult) -> Result<SnoozedTab> {
+        if let Some(error) = result.error_message() {
+            self.repository.delete(&snooze.id).await?;
+            return Err(WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: format!("Failed to close snoozed tab: {}", error),
+                },
+            });
+        }
+        info!("Snoozed {} until {}", snooze.url, snooze.wake_at);
+        Ok(snooze)
+    }
+
+    /// Snoozed tabs, soonest to wake first
+    pub async fn pending(&self) -> Result<Vec<SnoozedTab>> {
+        self.repository.pending().await
+    }
+
+    /// Move a snooze to a new wake time; false if it does not exist
+    pub async fn reschedule(&self, id: &Uuid, until: SnoozeUntil) -> Result<bool> {
+        self.repository.reschedule(id, until.wake_time(&Local::now())).await
+    }
+
+    /// Drop a snooze without reopening its tab; false if it does not exist
+    pub async fn cancel(&self, id: &Uuid) -> Result<bool> {
+        self.repository.delete(id).await
+    }
+
+    /// Reopen a snoozed tab in `connector`'s browser now, whether due or not
+    pub async fn wake<C: BrowserConnector + ?Sized>(&self, snooze: &SnoozedTab, connector: &C) -> Result<TabOperationResult> {
+        let result = self.controller.create_tab_with(connector, &snooze.url, None, false).await?;
+        self.finish_wake(snooze, result).await
+    }
+
+    /// Reopen every tab due at `now` in the browser it was snoozed from
+    ///
+    /// Tabs that fail to open stay snoozed.
+    pub async fn wake_due(&self, manager: &BrowserConnectorManager, now: DateTime<Utc>) -> Result<Vec<TabOperationResult>> {
+        let mut results = Vec::new();
+        for snooze in self.repository.due(now).await? {
+            let result = self
+                .controller
+                .create_tab_via_manager(manager, snooze.browser, &snooze.url)
+                .await?;
+            results.push(self.finish_wake(&snooze, result).await?);
+        }
+        Ok(results)
+    }
+
+    /// Drop `snooze` if its tab reopened
+    async fn finish_wake(&self, snooze: &SnoozedTab, result: TabOperationResult) -> Result<TabOperationResult> {
+        match result.error_message() {
+            None => {
+                self.repository.delete(&snooze.id).await?;
+                info!("Reopened snoozed tab {}", snooze.url);
+            }
+            Some(error) => debug!("Snoozed tab {} stays snoozed: {}", snooze.url, error),
+        }
+        Ok(result)
+    }
+
+    /// Reopen due tabs every `interval` until the returned task is aborted
+    pub fn spawn(self: Arc<Self>, manager: Arc<BrowserConnectorManager>, interval: Duration) -> JoinHandle<()> {
+        let period = interval.max(Duration::from_secs(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.wake_due(&manager, Utc::now()).await {
+                    warn!("Failed to reopen snoozed tabs: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MockConnector;
+    use chrono::FixedOffset;
+    use data_access::DatabaseManager;
+
+    #[test]
+    fn test_wake_times() {
+        let zone = FixedOffset::east_opt(2 * 3600).unwrap();
+        // Wednesday afternoon, 14:30 local
+        let afternoon = zone.with_ymd_and_hms(2024, 5, 15, 14, 30, 0).unwrap();
+        let local = |d: u32, h: u32| zone.with_ymd_and_hms(2024, 5, d, h, 0, 0).unwrap().with_timezone(&Utc);
+
+        assert_eq!(SnoozeUntil::Tonight.wake_time(&afternoon), local(15, 19));
+        assert_eq!(SnoozeUntil::Tomorrow.wake_time(&afternoon), local(16, 9));
+        assert_eq!(SnoozeUntil::NextMonday.wake_time(&afternoon), local(20, 9));
+
+        let late = zone.with_ymd_and_hms(2024, 5, 15, 21, 0, 0).unwrap();
+        assert_eq!(SnoozeUntil::Tonight.wake_time(&late), local(16, 19));
+        // On a Monday, next Monday is a week away
+        let monday = zone.with_ymd_and_hms(2024, 5, 20, 8, 0, 0).unwrap();
+        assert_eq!(SnoozeUntil::NextMonday.wake_time(&monday), local(27, 9));
+
+        let exact = Utc::now();
+        assert_eq!(SnoozeUntil::At(exact).wake_time(&afternoon), exact);
+    }
+
+    #[tokio::test]
+    async fn test_snooze_and_wake() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let manager = SnoozeManager::new(Arc::new(db.snooze_repository()), Arc::new(RemoteTabController::new()));
+        let connector = MockConnector::with_tabs(
+            BrowserType::Firefox,
+            &["https://reading.example.com", "https://later.example.com"],
+        );
+        let open_tab = |url: &str| connector.tabs.lock().unwrap().iter().find(|t| t.url == url).cloned().unwrap();
+
+        let now = Utc::now();
+        let reading = open_tab("https://reading.example.com");
+        let snooze = manager
+            .snooze_tab(&connector, &reading, SnoozeUntil::At(now + chrono::Duration::hours(1)))
+            .await
+            .unwrap();
+        let next_week = open_tab("https://later.example.com");
+        let later = manager.snooze_tab(&connector, &next_week, SnoozeUntil::NextMonday).await.unwrap();
+        assert!(connector.urls().is_empty());
+        let pending = manager.pending().await.unwrap();
+        assert_eq!(pending.iter().map(|s| s.id).collect::<Vec<_>>(), vec![snooze.id, later.id]);
+
+        // Nothing is due yet, and the due tab's browser is not connected
+        let browsers = BrowserConnectorManager::new();
+        assert!(manager.wake_due(&browsers, now).await.unwrap().is_empty());
+        let results = manager.wake_due(&browsers, now + chrono::Duration::hours(2)).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].is_success());
+        assert_eq!(manager.pending().await.unwrap().len(), 2);
+
+        assert!(manager.wake(&snooze, &connector).await.unwrap().is_success());
+        assert_eq!(connector.opened_urls(), vec!["https://reading.example.com"]);
+        assert!(manager.cancel(&later.id).await.unwrap());
+        assert!(manager.pending().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+//! Stale Tab Detection
+//!
+//! Recommends open tabs to clean up: tabs not used for a number of days,
+//! by the tab monitor's last-active times, and tabs duplicating a
+//! bookmark. Recommendations are acted on in batches: close and archive
+//! to the closed tab history, bookmark and close, or keep. A kept tab is
+//! not recommended again until it has been left unused for as long again.
+
+use web_page_manager_core::*;
+use browser_connector::{BrowserConnectorManager, TabMonitor};
+use crate::history::TabHistoryManager;
+use crate::remote_controller::RemoteTabController;
+use crate::unified_manager::PageUnifiedManager;
+use chrono::Duration;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::info;
+
+/// Tabs by browser and ID
+type TabKey = (BrowserType, TabId);
+
+/// Configuration for stale tab detection
+#[derive(Debug, Clone)]
+pub struct StaleTabConfig {
+    /// Days without use after which a tab is stale
+    pub inactive_days: u32,
+    /// Whether tabs duplicating a bookmark are stale however recently used
+    pub flag_bookmarked: bool,
+    /// Folder `BookmarkAndClose` bookmarks tabs into
+    pub bookmark_folder: Vec<String>,
+}
+
+impl Default for StaleTabConfig {
+    fn default() -> Self {
+        Self {
+            inactive_days: 7,
+            flag_bookmarked: true,
+            bookmark_folder: vec!["Stale tabs".to_string()],
+        }
+    }
+}
+
+/// Why a tab is recommended for cleanup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaleReason {
+    /// Not used for `days` days
+    Inactive { days: i64 },
+    /// The page is already bookmarked
+    Bookmarked { bookmark_id: BookmarkId },
+}
+
+/// What to do with a stale tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StaleTabAction {
+    /// Save the tab to the closed tab history and close it
+    CloseAndArchive,
+    /// Bookmark the tab and close it
+    BookmarkAndClose,
+    /// Leave the tab open and stop recommending it for now
+    Keep,
+}
+
+/// A tab recommended for cleanup
+#[derive(Debug, Clone)]
+pub struct StaleTabRecommendation {
+    pub tab: TabInfo,
+    pub last_active: DateTime<Utc>,
+    pub reasons: Vec<StaleReason>,
+    /// Closing archives a bookmarked tab, other tabs are bookmarked first
+    pub suggested_action: StaleTabAction,
+}
+
+/// Outcome of acting on one stale tab
+#[derive(Debug, Clone)]
+pub struct StaleTabActionResult {
+    pub tab_id: TabId,
+    pub action: StaleTabAction,
+    /// Why the action failed, None if it succeeded
+    pub error: Option<String>,
+}
+
+impl StaleTabActionResult {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Detector recommending stale tabs and acting on them
+pub struct StaleTabDetector {
+    config: StaleTabConfig,
+    monitor: Arc<TabMonitor>,
+    manager: Arc<PageUnifiedManager>,
+    history: Option<Arc<TabHistoryManager>>,
+    controller: Option<Arc<RemoteTabController>>,
+    connectors: Option<Arc<BrowserConnectorManager>>,
+    /// When each tab was last kept
+    kept: Arc<RwLock<HashMap<TabKey, DateTime<Utc>>>>,
+}
+
+impl StaleTabDetector {
+    /// Create a detector over the tabs of `monitor`, matched against the
+    /// bookmarks of `manager`
+    pub fn new(monitor: Arc<TabMonitor>, manager: Arc<PageUnifiedManager>, config: StaleTabConfig) -> Self {
+        Self {
+            config,
+            monitor,
+            manager,
+            history: None,
+            controller: None,
+            connectors: None,
+            kept: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Archive closed tabs to `history`
+    pub fn with_history(mut self, history: Arc<TabHistoryManager>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Close tabs through `controller`
+    pub fn with_tab_control(mut self, controller: Arc<RemoteTabController>, connectors: Arc<BrowserConnectorManager>) -> Self {
+        self.controller = Some(controller);
+        self.connectors = Some(connectors);
+        self
+    }
+
+    /// Get the current configuration
+    pub fn config(&self) -> &StaleTabConfig {
+        &self.config
+    }
+
+    /// Stale tabs at `now`, least recently used first
+    pub async fn recommendations(&self, now: DateTime<Utc>) -> Vec<StaleTabRecommendation> {
+        let kept = self.kept.read().await.clone();
+        let mut recommendations = Vec::new();
+
+        for (tab, last_active) in self.monitor.get_tab_activity().await {
+            let kept_at = kept.get(&(tab.browser_type, tab.id.clone())).copied();
+            let mut reasons = Vec::new();
+
+            let idle_since = kept_at.map_or(last_active, |k| k.max(last_active));
+            let days = (now - idle_since).num_days();
+            if now - idle_since >= Duration::days(i64::from(self.config.inactive_days)) {
+                reasons.push(StaleReason::Inactive { days });
+            }
+            if self.config.flag_bookmarked && kept_at.is_none() {
+                let duplicate = self
+                    .manager
+                    .find_bookmarks_for_tab(&tab.id)
+                    .await
+                    .into_iter()
+                    .filter(|m| m.tier() <= MatchTier::Strong)
+                    .max_by(|a, b| a.confidence.total_cmp(&b.confidence));
+                if let Some(duplicate) = duplicate {
+                    reasons.push(StaleReason::Bookmarked { bookmark_id: duplicate.bookmark_id });
+                }
+            }
+            if reasons.is_empty() {
+                continue;
+            }
+
+            let bookmarked = reasons.iter().any(|r| matches!(r, StaleReason::Bookmarked { .. }));
+            recommendations.push(StaleTabRecommendation {
+                tab,
+                last_active,
+                reasons,
+                suggested_action: if bookmarked {
+                    StaleTabAction::CloseAndArchive
+                } else {
+                    StaleTabAction::BookmarkAndClose
+                },
+            });
+        }
+
+        recommendations.sort_by_key(|r| r.last_active);
+        recommendations
+    }
+
+    /// Act on each tab with its chosen action
+    pub async fn apply(&self, choices: &[(TabInfo, StaleTabAction)]) -> Vec<StaleTabActionResult> {
+        let mut results = Vec::with_capacity(choices.len());
+        for (tab, action) in choices {
+            let error = self.perform(tab, *action).await.err();
+            results.push(StaleTabActionResult {
+                tab_id: tab.id.clone(),
+                action: *action,
+                error,
+            });
+        }
+
+        let failed = results.iter().filter(|r| !r.is_success()).count();
+        info!("Cleaned up {} stale tabs, {} failed", results.len() - failed, failed);
+        results
+    }
+
+    /// Act on every recommendation with its suggested action
+    pub async fn apply_suggested(&self, recommendations: &[StaleTabRecommendation]) -> Vec<StaleTabActionResult> {
+        let choices: Vec<(TabInfo, StaleTabAction)> = recommendations
+            .iter()
+            .map(|r| (r.tab.clone(), r.suggested_action))
+            .collect();
+        self.apply(&choices).await
+    }
+
+    async fn perform(&self, tab: &TabInfo, action: StaleTabAction) -> std::result::Result<(), String> {
+        match action {
+            StaleTabAction::Keep => {
+                self.kept.write().await.insert((tab.browser_type, tab.id.clone()), Utc::now());
+                return Ok(());
+            }
+            // Saved before closing, so a failed close loses nothing
+            StaleTabAction::CloseAndArchive => {
+                let history = self.history.as_ref().ok_or("History is not available")?;
+                history.save_closed_tab(tab.clone(), Utc::now()).await.map_err(|e| e.to_string())?;
+            }
+            StaleTabAction::BookmarkAndClose => {
+                self.manager
+                    .create_bookmark_from_tab(&tab.id, self.config.bookmark_folder.clone())
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+
+        let (Some(controller), Some(connectors)) = (&self.controller, &self.connectors) else {
+            return Err("Tab control is not available".to_string());
+        };
+        let result = controller
+            .close_tab_via_manager(connectors, tab.browser_type, &tab.id, Some(tab))
+            .await
+            .map_err(|e| e.to_string())?;
+        match result.error_message() {
+            Some(error) => Err(error.to_string()),
+            None => {
+                self.kept.write().await.remove(&(tab.browser_type, tab.id.clone()));
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tab(url: &str, idle_days: i64) -> TabInfo {
+        TabInfo {
+            id: TabId::new(),
+            url: url.to_string(),
+            title: url.to_string(),
+            favicon_url: None,
+            browser_type: BrowserType::Chrome,
+            is_private: false,
+            created_at: Utc::now() - Duration::days(30),
+            last_accessed: Utc::now() - Duration::days(idle_days),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stale_tab_recommendations_and_actions() {
+        let old = tab("https://old.example.com", 10);
+        let bookmarked = tab("https://docs.example.com/guide", 0);
+        let fresh = tab("https://fresh.example.com", 1);
+        let bookmark = BookmarkInfo {
+            id: BookmarkId::new(),
+            url: bookmarked.url.clone(),
+            title: "Guide".to_string(),
+            favicon_url: None,
+            browser_type: BrowserType::Chrome,
+            folder_path: vec![],
+            created_at: Utc::now(),
+            last_accessed: None,
+        };
+        let tabs = vec![old.clone(), bookmarked.clone(), fresh.clone()];
+
+        let monitor = Arc::new(TabMonitor::new());
+        monitor.update_tabs(HashMap::from([(BrowserType::Chrome, tabs.clone())])).await;
+        let manager = Arc::new(PageUnifiedManager::new());
+        manager.update_all(tabs, vec![bookmark.clone()]).await;
+        let history = Arc::new(TabHistoryManager::new());
+        let detector = StaleTabDetector::new(monitor.clone(), manager.clone(), StaleTabConfig::default())
+            .with_history(history.clone());
+
+        let recommendations = detector.recommendations(Utc::now()).await;
+        assert_eq!(recommendations.len(), 2);
+        assert_eq!(recommendations[0].tab.id, old.id);
+        assert_eq!(recommendations[0].reasons, vec![StaleReason::Inactive { days: 10 }]);
+        assert_eq!(recommendations[0].suggested_action, StaleTabAction::BookmarkAndClose);
+        assert_eq!(recommendations[1].reasons, vec![StaleReason::Bookmarked { bookmark_id: bookmark.id }]);
+        assert_eq!(recommendations[1].suggested_action, StaleTabAction::CloseAndArchive);
+
+        // Using a tab makes it fresh again
+        monitor.record_activation(BrowserType::Chrome, &old.id).await;
+        assert_eq!(detector.recommendations(Utc::now()).await.len(), 1);
+
+        // Kept tabs come back only after going unused again
+        let results = detector.apply(&[(bookmarked.clone(), StaleTabAction::Keep)]).await;
+        assert!(results[0].is_success());
+        assert!(detector.recommendations(Utc::now()).await.is_empty());
+        assert_eq!(detector.recommendations(Utc::now() + Duration::days(8)).await.len(), 3);
+
+        // Archiving works, closing fails without tab control
+        let results = detector.apply(&[(fresh.clone(), StaleTabAction::CloseAndArchive)]).await;
+        assert_eq!(results[0].error.as_deref(), Some("Tab control is not available"));
+        assert_eq!(history.total_count().await, 1);
+    }
+}
@@ -3,6 +3,12 @@
 //! Provides functionality for synchronizing data between tabs and bookmarks,
 //! including update propagation and data inheritance.
 //!
+//! Sync is bidirectional: [`SyncState`] remembers what each matched tab and
+//! bookmark looked like when they were last in sync, so a change on either
+//! side is told apart from a stale value on the other. When both sides
+//! changed, a [`SyncConflict`] is produced and resolved by the configured
+//! [`ConflictStrategy`] instead of one side silently overwriting the other.
+//!
 //! # Requirements
 //! - 6.2: Detect tab content changes and offer bookmark info update options
 //! - 6.3: Auto-inherit analyzed content summary and tags when adding tab as bookmark
@@ -89,6 +95,145 @@ impl SyncResult {
     }
 }
 
+/// How conflicting tab and bookmark changes are resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictStrategy {
+    /// Keep whichever side was accessed more recently
+    PreferNewer,
+    /// Keep the bookmark's metadata
+    PreferBookmark,
+    /// Leave conflicts for the user to resolve
+    #[default]
+    Manual,
+}
+
+/// Side that wins a conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Update the bookmark with the tab's title and favicon
+    UseTab,
+    /// Keep the bookmark as it is
+    UseBookmark,
+}
+
+/// Tab and bookmark metadata as of their last sync
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncSnapshot {
+    pub tab_title: String,
+    pub tab_favicon: Option<String>,
+    pub bookmark_title: String,
+    pub bookmark_favicon: Option<String>,
+    pub synced_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl SyncSnapshot {
+    fn of(tab: &TabInfo, bookmark: &BookmarkInfo) -> Self {
+        Self {
+            tab_title: tab.title.clone(),
+            tab_favicon: tab.favicon_url.clone(),
+            bookmark_title: bookmark.title.clone(),
+            bookmark_favicon: bookmark.favicon_url.clone(),
+            synced_at: chrono::Utc::now(),
+        }
+    }
+
+    fn tab_changed(&self, tab: &TabInfo) -> bool {
+        self.tab_title != tab.title || self.tab_favicon != tab.favicon_url
+    }
+
+    fn bookmark_changed(&self, bookmark: &BookmarkInfo) -> bool {
+        self.bookmark_title != bookmark.title || self.bookmark_favicon != bookmark.favicon_url
+    }
+}
+
+/// Last synced state of each matched bookmark
+#[derive(Debug, Clone, Default)]
+pub struct SyncState {
+    snapshots: HashMap<BookmarkId, SyncSnapshot>,
+}
+
+impl SyncState {
+    /// Create an empty sync state
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `tab` and `bookmark` are in sync as they are now
+    pub fn record(&mut self, tab: &TabInfo, bookmark: &BookmarkInfo) {
+        self.snapshots.insert(bookmark.id.clone(), SyncSnapshot::of(tab, bookmark));
+    }
+
+    /// Snapshot of the last sync of a bookmark
+    pub fn get(&self, bookmark_id: &BookmarkId) -> Option<&SyncSnapshot> {
+        self.snapshots.get(bookmark_id)
+    }
+
+    /// Forget a bookmark, e.g. after it was deleted
+    pub fn remove(&mut self, bookmark_id: &BookmarkId) -> Option<SyncSnapshot> {
+        self.snapshots.remove(bookmark_id)
+    }
+
+    /// Number of bookmarks with a recorded sync
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Check if no sync has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+}
+
+/// Tab and bookmark that both changed since their last sync
+#[derive(Debug, Clone)]
+pub struct SyncConflict {
+    pub tab_id: TabId,
+    pub bookmark_id: BookmarkId,
+    /// Both sides as of the last sync
+    pub base: SyncSnapshot,
+    pub tab_title: String,
+    pub tab_favicon: Option<String>,
+    pub bookmark_title: String,
+    pub bookmark_favicon: Option<String>,
+    /// Resolution chosen by the strategy, None when left to the user
+    pub resolution: Option<ConflictResolution>,
+    pub detected_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl SyncConflict {
+    /// Action that applies `resolution`, if anything needs to change
+    pub fn action(&self, resolution: ConflictResolution) -> Option<SyncAction> {
+        match resolution {
+            ConflictResolution::UseTab => Some(SyncAction::UpdateBookmark {
+                bookmark_id: self.bookmark_id.clone(),
+                new_title: (self.tab_title != self.bookmark_title).then(|| self.tab_title.clone()),
+                new_favicon: if self.tab_favicon != self.bookmark_favicon {
+                    self.tab_favicon.clone()
+                } else {
+                    None
+                },
+            }),
+            ConflictResolution::UseBookmark => None,
+        }
+    }
+}
+
+/// Outcome of a bidirectional sync pass
+#[derive(Debug, Clone, Default)]
+pub struct SyncPlan {
+    /// Updates to propagate, including automatically resolved conflicts
+    pub actions: Vec<SyncAction>,
+    /// Conflicts found, resolved or left for the user
+    pub conflicts: Vec<SyncConflict>,
+}
+
+impl SyncPlan {
+    /// Conflicts waiting for the user
+    pub fn unresolved(&self) -> impl Iterator<Item = &SyncConflict> {
+        self.conflicts.iter().filter(|c| c.resolution.is_none())
+    }
+}
+
 /// Data synchronization manager
 ///
 /// Handles synchronization between tabs, bookmarks, and unified pages.
@@ -114,6 +259,11 @@ impl DataSyncManager {
         &self.matcher
     }
 
+    /// Get a mutable matcher reference, e.g. to record redirects
+    pub fn matcher_mut(&mut self) -> &mut TabBookmarkMatcher {
+        &mut self.matcher
+    }
+
     /// Generate sync actions for detected content changes
     ///
     /// This analyzes tabs and bookmarks to find changes that need
@@ -153,6 +303,103 @@ impl DataSyncManager {
         actions
     }
 
+    /// Plan a bidirectional sync of matched tabs and bookmarks
+    ///
+    /// Compares each exact match against its last synced snapshot in
+    /// `state`:
+    /// - only the tab changed: the bookmark is updated from the tab
+    /// - only the bookmark changed: the bookmark is kept
+    /// - both changed differently: a conflict is recorded and resolved by
+    ///   `strategy`
+    ///
+    /// A pair without a snapshot is treated as a tab change, as the tab
+    /// is the more current source. Pairs that end up in agreement are
+    /// recorded in `state`; pending updates and conflicts are recorded once
+    /// applied or resolved.
+    pub fn plan_sync(
+        &self,
+        tabs: &[TabInfo],
+        bookmarks: &[BookmarkInfo],
+        state: &mut SyncState,
+        strategy: ConflictStrategy,
+    ) -> SyncPlan {
+        let mut plan = SyncPlan::default();
+        let match_map = self.matcher.build_match_map(tabs, bookmarks);
+        let tab_map: HashMap<&TabId, &TabInfo> = tabs.iter().map(|t| (&t.id, t)).collect();
+        let bookmark_map: HashMap<&BookmarkId, &BookmarkInfo> =
+            bookmarks.iter().map(|b| (&b.id, b)).collect();
+
+        for (tab_id, match_infos) in &match_map {
+            let Some(tab) = tab_map.get(tab_id) else {
+                continue;
+            };
+            for match_info in match_infos {
+                if !matches!(match_info.match_type, MatchType::ExactUrl) {
+                    continue;
+                }
+                let Some(bookmark) = bookmark_map.get(&match_info.bookmark_id) else {
+                    continue;
+                };
+
+                let change = ContentChangeDetector::detect_changes(tab, bookmark);
+                if !change.has_changes() {
+                    state.record(tab, bookmark);
+                    continue;
+                }
+
+                let Some(base) = state.get(&bookmark.id).cloned() else {
+                    plan.actions.push(Self::update_from_change(change));
+                    continue;
+                };
+                match (base.tab_changed(tab), base.bookmark_changed(bookmark)) {
+                    (true, false) => plan.actions.push(Self::update_from_change(change)),
+                    (false, _) => state.record(tab, bookmark),
+                    (true, true) => {
+                        let resolution = match strategy {
+                            ConflictStrategy::PreferNewer => {
+                                let bookmark_time = bookmark.last_accessed.unwrap_or(bookmark.created_at);
+                                Some(if tab.last_accessed > bookmark_time {
+                                    ConflictResolution::UseTab
+                                } else {
+                                    ConflictResolution::UseBookmark
+                                })
+                            }
+                            ConflictStrategy::PreferBookmark => Some(ConflictResolution::UseBookmark),
+                            ConflictStrategy::Manual => None,
+                        };
+                        let conflict = SyncConflict {
+                            tab_id: tab.id.clone(),
+                            bookmark_id: bookmark.id.clone(),
+                            base,
+                            tab_title: tab.title.clone(),
+                            tab_favicon: tab.favicon_url.clone(),
+                            bookmark_title: bookmark.title.clone(),
+                            bookmark_favicon: bookmark.favicon_url.clone(),
+                            resolution,
+                            detected_at: chrono::Utc::now(),
+                        };
+                        match resolution {
+                            Some(ConflictResolution::UseBookmark) => state.record(tab, bookmark),
+                            Some(resolution) => plan.actions.extend(conflict.action(resolution)),
+                            None => {}
+                        }
+                        plan.conflicts.push(conflict);
+                    }
+                }
+            }
+        }
+
+        plan
+    }
+
+    fn update_from_change(change: ContentChangeDetection) -> SyncAction {
+        SyncAction::UpdateBookmark {
+            bookmark_id: change.bookmark_id,
+            new_title: change.title_changed.then_some(change.new_title),
+            new_favicon: if change.favicon_changed { change.new_favicon } else { None },
+        }
+    }
+
     /// Apply a bookmark update from tab changes
     ///
     /// Returns the updated BookmarkInfo.
@@ -539,6 +786,60 @@ mod tests {
         assert!(example.bookmark_info.is_some());
     }
 
+    #[test]
+    fn test_plan_sync_directions_and_conflicts() {
+        let sync_manager = DataSyncManager::new();
+        let mut state = SyncState::new();
+        let mut tab = create_test_tab("https://example.com/a", "Original");
+        let mut bookmark = create_test_bookmark("https://example.com/a", "Original");
+
+        // In sync: recorded, nothing to do
+        let plan = sync_manager.plan_sync(&[tab.clone()], &[bookmark.clone()], &mut state, ConflictStrategy::Manual);
+        assert!(plan.actions.is_empty() && plan.conflicts.is_empty());
+        assert_eq!(state.len(), 1);
+
+        // Only the bookmark changed: it is kept, not overwritten by the tab
+        bookmark.title = "Renamed by user".to_string();
+        let plan = sync_manager.plan_sync(&[tab.clone()], &[bookmark.clone()], &mut state, ConflictStrategy::Manual);
+        assert!(plan.actions.is_empty() && plan.conflicts.is_empty());
+        assert_eq!(state.get(&bookmark.id).unwrap().bookmark_title, "Renamed by user");
+
+        // Only the tab changed: the bookmark follows
+        tab.title = "Updated page".to_string();
+        bookmark.title = "Original".to_string();
+        state.record(&create_test_tab("https://example.com/a", "Original"), &bookmark);
+        let plan = sync_manager.plan_sync(&[tab.clone()], &[bookmark.clone()], &mut state, ConflictStrategy::Manual);
+        assert_eq!(plan.actions.len(), 1);
+        assert!(plan.conflicts.is_empty());
+
+        // Both changed: a conflict, resolved by the strategy
+        bookmark.title = "Renamed by user".to_string();
+        let plan = sync_manager.plan_sync(&[tab.clone()], &[bookmark.clone()], &mut state, ConflictStrategy::Manual);
+        assert!(plan.actions.is_empty());
+        assert_eq!(plan.unresolved().count(), 1);
+        let conflict = &plan.conflicts[0];
+        assert_eq!(conflict.base.tab_title, "Original");
+        assert_eq!((conflict.tab_title.as_str(), conflict.bookmark_title.as_str()), ("Updated page", "Renamed by user"));
+        assert!(conflict.action(ConflictResolution::UseBookmark).is_none());
+        assert!(matches!(
+            conflict.action(ConflictResolution::UseTab),
+            Some(SyncAction::UpdateBookmark { new_title: Some(ref t), .. }) if t == "Updated page"
+        ));
+
+        let mut newer = state.clone();
+        bookmark.last_accessed = Some(tab.last_accessed - chrono::Duration::hours(1));
+        let plan = sync_manager.plan_sync(&[tab.clone()], &[bookmark.clone()], &mut newer, ConflictStrategy::PreferNewer);
+        assert_eq!(plan.conflicts[0].resolution, Some(ConflictResolution::UseTab));
+        assert_eq!(plan.actions.len(), 1);
+
+        let plan = sync_manager.plan_sync(&[tab.clone()], &[bookmark.clone()], &mut state, ConflictStrategy::PreferBookmark);
+        assert_eq!(plan.conflicts[0].resolution, Some(ConflictResolution::UseBookmark));
+        assert!(plan.actions.is_empty());
+        // The resolution is recorded, so the conflict does not come back
+        let plan = sync_manager.plan_sync(&[tab], &[bookmark], &mut state, ConflictStrategy::Manual);
+        assert!(plan.actions.is_empty() && plan.conflicts.is_empty());
+    }
+
     #[test]
     fn test_sync_queue() {
         let mut queue = SyncQueue::new();
@@ -0,0 +1,250 @@
+//! Tag Management
+//!
+//! Curates tags into a taxonomy: renaming and merging tags, nesting them
+//! under parent tags, and tagging or re-tagging many pages at once. The
+//! keywords content analysis generates for pages can be adopted as tags,
+//! so they can then be cleaned up with the same operations.
+
+use web_page_manager_core::*;
+use data_access::{Tag, TagRepository};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::info;
+
+/// A tag with its page count and child tags
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagNode {
+    pub tag: Tag,
+    /// Pages carrying the tag itself
+    pub page_count: usize,
+    /// Child tags, ordered by name
+    pub children: Vec<TagNode>,
+}
+
+impl TagNode {
+    /// Pages carrying the tag or any tag under it, counting a page once
+    /// per tag
+    pub fn total_page_count(&self) -> usize {
+        self.page_count + self.children.iter().map(TagNode::total_page_count).sum::<usize>()
+    }
+}
+
+/// Manager for renaming, merging, nesting and bulk-applying tags
+pub struct TagManager {
+    repository: Arc<dyn TagRepository>,
+}
+
+impl TagManager {
+    /// Create a manager over the tags of `repository`
+    pub fn new(repository: Arc<dyn TagRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Rename a tag; false if it does not exist, an error if the name is
+    /// taken by another tag
+    pub async fn rename(&self, id: &Uuid, name: &str) -> Result<bool> {
+        self.repository.rename(id, name).await
+    }
+
+    /// Merge every tag of `sources` into `target`, returning the number of
+    /// pages newly tagged with `target`
+    pub async fn merge(&self, sources: &[Uuid], target: &Uuid) -> Result<usize> {
+        let mut moved = 0;
+        for source in sources {
+            moved += self.repository.merge(source, target).await?;
+        }
+        info!("Merged {} tags, {} pages newly tagged", sources.len(), moved);
+        Ok(moved)
+    }
+
+    /// Nest a tag under `parent`, or make it top-level with None
+    pub async fn set_parent(&self, id: &Uuid, parent: Option<&Uuid>) -> Result<bool> {
+        self.repository.set_parent(id, parent).await
+    }
+
+    /// Get or create the tag `name` and nest it under `parent`
+    pub async fn create_child(&self, parent: &Uuid, name: &str) -> Result<Tag> {
+        let mut tag = self.repository.get_or_create(name).await?;
+        self.repository.set_parent(&tag.id, Some(parent)).await?;
+        tag.parent_id = Some(*parent);
+        Ok(tag)
+    }
+
+    /// Every tag as a tree of top-level tags, ordered by name
+    pub async fn tree(&self) -> Result<Vec<TagNode>> {
+        let counts = self.repository.tag_counts().await?;
+        let ids: HashSet<Uuid> = counts.iter().map(|c| c.tag.id).collect();
+        let mut children: HashMap<Option<Uuid>, Vec<(Tag, usize)>> = HashMap::new();
+        for count in counts {
+            // A tag whose parent is gone is shown at the top
+            let parent = count.tag.parent_id.filter(|p| ids.contains(p));
+            children.entry(parent).or_default().push((count.tag, count.page_count));
+        }
+        Ok(build_nodes(&mut children, None))
+    }
+
+    /// The tag and the tags nested under it, at any depth
+    pub async fn descendants(&self, id: &Uuid) -> Result<Vec<Uuid>> {
+        let tags = self.repository.get_all().await?;
+        let mut found = vec![*id];
+        let mut i = 0;
+        while i < found.len() {
+            let parent = found[i];
+            found.extend(
+                tags.iter()
+                    .filter(|t| t.parent_id == Some(parent) && !found.contains(&t.id))
+                    .map(|t| t.id)
+                    .collect::<Vec<_>>(),
+            );
+            i += 1;
+        }
+        Ok(found)
+    }
+
+    /// Pages carrying the tag or any tag nested under it
+    pub async fn pages_under(&self, id: &Uuid) -> Result<Vec<Uuid>> {
+        let mut pages = Vec::new();
+        for tag_id in self.descendants(id).await? {
+            for page in self.repository.get_pages_with_tag(&tag_id).await? {
+                if !pages.contains(&page) {
+                    pages.push(page);
+                }
+            }
+        }
+        Ok(pages)
+    }
+
+    /// Tag every page of `page_ids` with `name`, creating the tag if needed
+    pub async fn tag_pages(&self, page_ids: &[Uuid], name: &str) -> Result<Tag> {
+        let tag = self.repository.get_or_create(name).await?;
+        for page_id in page_ids {
+            self.repository.tag_page(page_id, &tag.id).await?;
+        }
+        Ok(tag)
+    }
+
+    /// Remove a tag from every page of `page_ids`
+    pub async fn untag_pages(&self, page_ids: &[Uuid], tag_id: &Uuid) -> Result<()> {
+        for page_id in page_ids {
+            self.repository.untag_page(page_id, tag_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Move the pages of `page_ids` tagged `from` over to `to`, returning
+    /// the number of pages moved
+    ///
+    /// Unlike merging, `from` is kept for its other pages.
+    pub async fn retag_pages(&self, page_ids: &[Uuid], from: &Uuid, to: &Uuid) -> Result<usize> {
+        let tagged: HashSet<Uuid> = self.repository.get_pages_with_tag(from).await?.into_iter().collect();
+        let mut moved = 0;
+        for page_id in page_ids.iter().filter(|p| tagged.contains(p)) {
+            self.repository.tag_page(page_id, to).await?;
+            self.repository.untag_page(page_id, from).await?;
+            moved += 1;
+        }
+        Ok(moved)
+    }
+
+    /// Tag pages with their generated keywords, for keywords shared by at
+    /// least `min_pages` of `pages`, returning the tags used
+    ///
+    /// Keywords are matched ignoring case, like tag names.
+    pub async fn adopt_keywords(&self, pages: &[UnifiedPageInfo], min_pages: usize) -> Result<Vec<Tag>> {
+        let mut by_keyword: HashMap<String, (String, Vec<Uuid>)> = HashMap::new();
+        for page in pages {
+            for keyword in page.keywords.iter().map(|k| k.trim()).filter(|k| !k.is_empty()) {
+                let (_, page_ids) = by_keyword
+                    .entry(keyword.to_lowercase())
+                    .or_insert_with(|| (keyword.to_string(), Vec::new()));
+                if !page_ids.contains(&page.id) {
+                    page_ids.push(page.id);
+                }
+            }
+        }
+
+        let mut adopted: Vec<(String, Vec<Uuid>)> = by_keyword
+            .into_values()
+            .filter(|(_, page_ids)| page_ids.len() >= min_pages.max(1))
+            .collect();
+        adopted.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut tags = Vec::with_capacity(adopted.len());
+        for (keyword, page_ids) in adopted {
+            tags.push(self.tag_pages(&page_ids, &keyword).await?);
+        }
+        info!("Adopted {} keywords as tags", tags.len());
+        Ok(tags)
+    }
+}
+
+/// Nodes for the children of `parent`, taking them out of `children`
+fn build_nodes(children: &mut HashMap<Option<Uuid>, Vec<(Tag, usize)>>, parent: Option<Uuid>) -> Vec<TagNode> {
+    let mut level = children.remove(&parent).unwrap_or_default();
+    level.sort_by_key(|(tag, _)| tag.name.to_lowercase());
+    level
+        .into_iter()
+        .map(|(tag, page_count)| {
+            let id = tag.id;
+            TagNode {
+                tag,
+                page_count,
+                children: build_nodes(children, Some(id)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_page;
+    use data_access::{DatabaseManager, PageRepository};
+
+    #[tokio::test]
+    async fn test_curate_keyword_tags() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let repository = db.page_repository();
+        let pages = vec![
+            test_page("https://tokio.rs", "https://tokio.rs", &["Rust", "async"]),
+            test_page("https://docs.rs/serde", "https://docs.rs/serde", &["rust", "serde"]),
+            test_page("https://go.dev", "https://go.dev", &["golang"]),
+        ];
+        for p in &pages {
+            repository.save(p).await.unwrap();
+        }
+        let manager = TagManager::new(Arc::new(db.tag_repository()));
+
+        // Keywords on a single page are left out
+        let adopted = manager.adopt_keywords(&pages, 2).await.unwrap();
+        assert_eq!(adopted.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(), vec!["Rust"]);
+        let rust = adopted[0].clone();
+
+        let programming = manager.tag_pages(&[pages[2].id], "Programming").await.unwrap();
+        assert!(manager.set_parent(&rust.id, Some(&programming.id)).await.unwrap());
+        let async_tag = manager.create_child(&rust.id, "Async").await.unwrap();
+        manager.tag_pages(&[pages[0].id], "async").await.unwrap();
+
+        let tree = manager.tree().await.unwrap();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].tag.name, "Programming");
+        assert_eq!(tree[0].children[0].tag.name, "Rust");
+        assert_eq!(tree[0].children[0].children[0].tag.id, async_tag.id);
+        assert_eq!(tree[0].total_page_count(), 4);
+        assert_eq!(manager.pages_under(&programming.id).await.unwrap().len(), 3);
+
+        // Re-tagging moves only the selected pages
+        let serde = manager.tag_pages(&[], "Serialization").await.unwrap();
+        assert_eq!(manager.retag_pages(&[pages[1].id, pages[2].id], &rust.id, &serde.id).await.unwrap(), 1);
+        assert_eq!(manager.pages_under(&rust.id).await.unwrap(), vec![pages[0].id]);
+
+        // Merging and renaming finish the cleanup
+        assert_eq!(manager.merge(&[async_tag.id], &rust.id).await.unwrap(), 0);
+        assert!(manager.rename(&rust.id, "Rust language").await.unwrap());
+        let tree = manager.tree().await.unwrap();
+        assert_eq!(tree[0].children[0].tag.name, "Rust language");
+        assert!(tree[0].children[0].children.is_empty());
+        manager.untag_pages(&[pages[0].id], &rust.id).await.unwrap();
+        assert!(manager.pages_under(&rust.id).await.unwrap().is_empty());
+    }
+}
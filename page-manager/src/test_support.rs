@@ -0,0 +1,210 @@
+//! Browser connector shared by the tests of this crate
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use browser_connector::BrowserConnector;
+use web_page_manager_core::*;
+
+/// URL, window and pinned state of a tab a connector opened
+pub(crate) type OpenedTab = (String, Option<String>, bool);
+
+/// Connector keeping its tabs and bookmarks in memory and recording the
+/// tabs it opens
+///
+/// Opening or deleting `fail_url` fails, and tabs opened at `lost_url`
+/// are gone right away. Pages are served from `pages`; anything the
+/// connector cannot do fails rather than panicking.
+#[derive(Clone)]
+pub(crate) struct MockConnector {
+    pub browser: BrowserType,
+    pub fail_url: &'static str,
+    pub lost_url: &'static str,
+    pub tabs: Arc<Mutex<Vec<TabInfo>>>,
+    pub opened: Arc<Mutex<Vec<OpenedTab>>>,
+    pub bookmarks: Arc<Mutex<Vec<BookmarkInfo>>>,
+    /// HTML of the pages `fetch_page_content` serves, by URL
+    pub pages: HashMap<String, String>,
+    /// Snapshot `capture_mhtml` returns for every tab
+    pub mhtml: Option<Vec<u8>>,
+    /// Number of windows opened so far
+    pub windows: Arc<Mutex<usize>>,
+}
+
+impl MockConnector {
+    pub fn new(browser: BrowserType) -> Self {
+        Self::with_tabs(browser, &[])
+    }
+
+    /// Connector with a tab open at each of `urls`
+    pub fn with_tabs(browser: BrowserType, urls: &[&str]) -> Self {
+        Self {
+            browser,
+            fail_url: "",
+            lost_url: "",
+            tabs: Arc::new(Mutex::new(urls.iter().map(|url| tab(url, browser)).collect())),
+            opened: Arc::default(),
+            bookmarks: Arc::default(),
+            pages: HashMap::new(),
+            mhtml: None,
+            windows: Arc::default(),
+        }
+    }
+
+    /// URLs of the open tabs, sorted
+    pub fn urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = self.tabs.lock().unwrap().iter().map(|t| t.url.clone()).collect();
+        urls.sort();
+        urls
+    }
+
+    /// URLs of the tabs opened, in order
+    pub fn opened_urls(&self) -> Vec<String> {
+        self.opened.lock().unwrap().iter().map(|(url, _, _)| url.clone()).collect()
+    }
+
+    /// ID of the tab open at `url`
+    pub fn tab_id(&self, url: &str) -> TabId {
+        self.tabs.lock().unwrap().iter().find(|t| t.url == url).unwrap().id.clone()
+    }
+
+    fn not_found(&self) -> WebPageManagerError {
+        WebPageManagerError::BrowserConnection {
+            source: BrowserConnectionError::InvalidResponse { browser: self.browser },
+        }
+    }
+}
+
+/// Page bookmarked in Chrome, created and accessed now
+pub(crate) fn test_page(url: &str, title: &str, keywords: &[&str]) -> UnifiedPageInfo {
+    UnifiedPageInfo {
+        id: Uuid::new_v4(),
+        url: url.to_string(),
+        title: title.to_string(),
+        favicon_url: None,
+        content_summary: None,
+        keywords: keywords.iter().map(|k| k.to_string()).collect(),
+        category: None,
+        source_type: PageSourceType::Bookmark {
+            browser: BrowserType::Chrome,
+            bookmark_id: BookmarkId::new(),
+        },
+        browser_info: None,
+        tab_info: None,
+        bookmark_info: None,
+        created_at: Utc::now(),
+        last_accessed: Utc::now(),
+        access_count: 0,
+    }
+}
+
+/// Tab at `url` in `browser`, titled with its URL
+pub(crate) fn tab(url: &str, browser: BrowserType) -> TabInfo {
+    TabInfo {
+        id: TabId::new(),
+        url: url.to_string(),
+        title: url.to_string(),
+        favicon_url: None,
+        browser_type: browser,
+        is_private: false,
+        created_at: Utc::now(),
+        last_accessed: Utc::now(),
+    }
+}
+
+#[async_trait::async_trait]
+impl BrowserConnector for MockConnector {
+    fn browser_type(&self) -> BrowserType {
+        self.browser
+    }
+    async fn connect(&self) -> Result<()> {
+        Ok(())
+    }
+    async fn disconnect(&self) -> Result<()> {
+        Ok(())
+    }
+    fn is_connected(&self) -> bool {
+        true
+    }
+    async fn get_tabs(&self) -> Result<Vec<TabInfo>> {
+        Ok(self.tabs.lock().unwrap().clone())
+    }
+    async fn get_bookmarks(&self) -> Result<Vec<BookmarkInfo>> {
+        Ok(self.bookmarks.lock().unwrap().clone())
+    }
+    async fn fetch_page_content(&self, url: &str) -> Result<PageContent> {
+        let html = self.pages.get(url).cloned().ok_or_else(|| WebPageManagerError::AIProcessing {
+            source: AIProcessingError::ContentFetchFailed { url: url.to_string() },
+        })?;
+        Ok(PageContent {
+            html,
+            text: String::new(),
+            title: String::new(),
+            description: None,
+            keywords: vec![],
+            images: vec![],
+            links: vec![],
+            extracted_at: Utc::now(),
+        })
+    }
+    async fn create_bookmark(&self, bookmark: &BookmarkInfo) -> Result<BookmarkId> {
+        let created = BookmarkInfo {
+            id: BookmarkId::new(),
+            ..bookmark.clone()
+        };
+        let id = created.id.clone();
+        self.bookmarks.lock().unwrap().push(created);
+        Ok(id)
+    }
+    async fn update_bookmark(&self, bookmark_id: &BookmarkId, title: &str, folder_path: &[String]) -> Result<()> {
+        let mut bookmarks = self.bookmarks.lock().unwrap();
+        let bookmark = bookmarks.iter_mut().find(|b| &b.id == bookmark_id).ok_or_else(|| self.not_found())?;
+        bookmark.title = title.to_string();
+        bookmark.folder_path = folder_path.to_vec();
+        Ok(())
+    }
+    async fn delete_bookmark(&self, bookmark_id: &BookmarkId) -> Result<()> {
+        let mut bookmarks = self.bookmarks.lock().unwrap();
+        let index = bookmarks
+            .iter()
+            .position(|b| &b.id == bookmark_id && b.url != self.fail_url)
+            .ok_or_else(|| self.not_found())?;
+        bookmarks.remove(index);
+        Ok(())
+    }
+    async fn close_tab(&self, tab_id: &TabId) -> Result<()> {
+        let mut tabs = self.tabs.lock().unwrap();
+        let index = tabs.iter().position(|t| &t.id == tab_id).ok_or_else(|| self.not_found())?;
+        tabs.remove(index);
+        Ok(())
+    }
+    async fn activate_tab(&self, tab_id: &TabId) -> Result<()> {
+        let tabs = self.tabs.lock().unwrap();
+        tabs.iter().find(|t| &t.id == tab_id).map(|_| ()).ok_or_else(|| self.not_found())
+    }
+    async fn create_tab(&self, url: &str) -> Result<TabId> {
+        self.create_tab_with(url, None, false).await
+    }
+    async fn create_window(&self) -> Result<Option<String>> {
+        let mut windows = self.windows.lock().unwrap();
+        *windows += 1;
+        Ok(Some(format!("{:?}-window-{}", self.browser, windows)))
+    }
+    async fn create_tab_with(&self, url: &str, window_id: Option<&str>, pinned: bool) -> Result<TabId> {
+        if url == self.fail_url {
+            return Err(WebPageManagerError::BrowserConnection {
+                source: BrowserConnectionError::BrowserNotRunning { browser: self.browser },
+            });
+        }
+        let tab = tab(url, self.browser);
+        let id = tab.id.clone();
+        self.opened.lock().unwrap().push((url.to_string(), window_id.map(str::to_string), pinned));
+        if url != self.lost_url {
+            self.tabs.lock().unwrap().push(tab);
+        }
+        Ok(id)
+    }
+    async fn capture_mhtml(&self, _tab_id: &TabId) -> Result<Option<Vec<u8>>> {
+        Ok(self.mhtml.clone())
+    }
+}
@@ -7,17 +7,35 @@
 //! - Unified page information management
 //! - Tab-bookmark association detection and display
 //! - Content change detection and sync suggestions
+//! - Conflict detection when tab and bookmark both changed
+//! - Incremental updates from tab events, without re-merging every tab
 //! - Data inheritance when creating bookmarks from tabs
+//! - Notes and highlights on pages, kept by URL so they follow a page
+//!   from tab to history or bookmark
+//! - Page events for subscribers, so the UI can update incrementally
+//! - Sorted, filtered windows of the pages, so large lists can be
+//!   virtualized
+//! - Optional persistence: pages and notes are loaded from the repositories
+//!   on start and written through on every change
 
 use web_page_manager_core::*;
+use browser_connector::TabEvent;
+use data_access::{NoteRepository, PageNote, PageRepository, SortOrder};
 use crate::matcher::{
     ContentChangeDetection, ContentChangeDetector, MatcherConfig, TabBookmarkMatcher,
 };
-use crate::sync::{DataSyncManager, SyncAction, SyncQueue, SyncResult};
+use crate::sync::{
+    ConflictResolution, ConflictStrategy, DataSyncManager, SyncAction, SyncConflict, SyncQueue,
+    SyncResult, SyncState,
+};
 use std::collections::HashMap;
+use std::ops::Range;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, info, warn};
+
+/// Page events buffered per subscriber before the oldest are dropped
+const EVENT_CAPACITY: usize = 256;
 
 /// Configuration for the Page Unified Manager
 #[derive(Debug, Clone)]
@@ -28,6 +46,8 @@ pub struct PageUnifiedManagerConfig {
     pub auto_detect_changes: bool,
     /// Maximum number of pending sync items to keep
     pub max_pending_sync_items: usize,
+    /// How conflicts between tab and bookmark changes are resolved
+    pub conflict_strategy: ConflictStrategy,
 }
 
 impl Default for PageUnifiedManagerConfig {
@@ -36,6 +56,7 @@ impl Default for PageUnifiedManagerConfig {
             matcher_config: MatcherConfig::default(),
             auto_detect_changes: true,
             max_pending_sync_items: 100,
+            conflict_strategy: ConflictStrategy::default(),
         }
     }
 }
@@ -55,6 +76,14 @@ pub struct TabAssociationStatus {
     pub pending_changes: Option<ContentChangeDetection>,
 }
 
+impl TabAssociationStatus {
+    /// Certainty of the association, so the UI can tell certain
+    /// associations from guesses
+    pub fn tier(&self) -> Option<MatchTier> {
+        self.matching_bookmark.as_ref().map(MatchInfo::tier)
+    }
+}
+
 /// Statistics about the unified page manager state
 #[derive(Debug, Clone, Default)]
 pub struct UnifiedManagerStats {
@@ -72,6 +101,59 @@ pub struct UnifiedManagerStats {
     pub detected_changes_count: usize,
 }
 
+/// Change to the unified pages, sent to subscribers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PageEvent {
+    Added(UnifiedPageInfo),
+    /// A page's content changed; merges that only touch its access time
+    /// and count are not reported
+    Updated(UnifiedPageInfo),
+    Removed { id: uuid::Uuid, url: String },
+    /// A tab was associated with a bookmark, or with a different one
+    Associated(MatchInfo),
+}
+
+/// Field unified pages are listed by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnifiedPageSortKey {
+    #[default]
+    LastAccessed,
+    CreatedAt,
+    AccessCount,
+    /// Title, ignoring case
+    Title,
+}
+
+/// Order of a unified page listing; pages with equal keys are ordered by ID
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnifiedPageSort {
+    pub key: UnifiedPageSortKey,
+    pub order: SortOrder,
+}
+
+/// Filter of a unified page listing; unset fields match every page
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UnifiedPageFilter {
+    /// Text in the title, URL, keywords or notes, ignoring case
+    pub query: Option<String>,
+    pub browser: Option<BrowserType>,
+    /// Whether the page is open in a tab
+    pub has_tab: Option<bool>,
+    /// Whether the page is bookmarked
+    pub has_bookmark: Option<bool>,
+    pub category: Option<String>,
+}
+
+/// Window of a sorted, filtered listing of unified pages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnifiedPageWindow {
+    pub pages: Vec<UnifiedPageInfo>,
+    /// Positions of `pages` in the listing, clamped to its length
+    pub range: Range<usize>,
+    /// Pages matching the filter, for sizing a virtualized list
+    pub total: usize,
+}
+
 /// Page Unified Manager
 ///
 /// The main component for unified management of tabs and bookmarks.
@@ -80,6 +162,10 @@ pub struct PageUnifiedManager {
     config: PageUnifiedManagerConfig,
     sync_manager: DataSyncManager,
     sync_queue: Arc<RwLock<SyncQueue>>,
+    /// Last synced state of matched tabs and bookmarks
+    sync_state: Arc<RwLock<SyncState>>,
+    /// Conflicts waiting for the user, by bookmark
+    sync_conflicts: Arc<RwLock<HashMap<BookmarkId, SyncConflict>>>,
     /// Cached unified pages
     unified_pages: Arc<RwLock<Vec<UnifiedPageInfo>>>,
     /// Cached tabs
@@ -88,6 +174,14 @@ pub struct PageUnifiedManager {
     bookmarks: Arc<RwLock<Vec<BookmarkInfo>>>,
     /// Tab association status cache
     association_cache: Arc<RwLock<HashMap<TabId, TabAssociationStatus>>>,
+    /// Notes and highlights, by normalized page URL
+    notes: Arc<RwLock<HashMap<String, Vec<PageNote>>>>,
+    /// Sender of page events to subscribers
+    events: broadcast::Sender<PageEvent>,
+    /// Store pages are loaded from and written through to
+    page_repository: Option<Arc<dyn PageRepository>>,
+    /// Store notes are loaded from and written through to
+    note_repository: Option<Arc<dyn NoteRepository>>,
 }
 
 impl PageUnifiedManager {
@@ -103,13 +197,75 @@ impl PageUnifiedManager {
             config,
             sync_manager: DataSyncManager::with_matcher(matcher),
             sync_queue: Arc::new(RwLock::new(SyncQueue::new())),
+            sync_state: Arc::new(RwLock::new(SyncState::new())),
+            sync_conflicts: Arc::new(RwLock::new(HashMap::new())),
             unified_pages: Arc::new(RwLock::new(Vec::new())),
             tabs: Arc::new(RwLock::new(Vec::new())),
             bookmarks: Arc::new(RwLock::new(Vec::new())),
             association_cache: Arc::new(RwLock::new(HashMap::new())),
+            notes: Arc::new(RwLock::new(HashMap::new())),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            page_repository: None,
+            note_repository: None,
         }
     }
 
+    /// Persist unified pages in `repository`
+    ///
+    /// Added and updated pages are saved as they change. A page removed
+    /// from the unified pages stays in the library, without its tab and
+    /// bookmark.
+    pub fn with_page_repository(mut self, repository: Arc<dyn PageRepository>) -> Self {
+        self.page_repository = Some(repository);
+        self
+    }
+
+    /// Persist notes and highlights in `repository`; notes refer to their
+    /// page, so this needs a page repository too
+    pub fn with_note_repository(mut self, repository: Arc<dyn NoteRepository>) -> Self {
+        self.note_repository = Some(repository);
+        self
+    }
+
+    /// Load the unified pages saved by an earlier run, with their tabs,
+    /// bookmarks and notes, returning the number of pages loaded
+    ///
+    /// Stored pages without a tab or bookmark are library pages, not
+    /// unified ones, and are left out. The loaded tabs stand until the
+    /// next update from the browsers replaces them.
+    pub async fn load(&self) -> Result<usize> {
+        let Some(repository) = &self.page_repository else {
+            return Ok(0);
+        };
+        let pages: Vec<UnifiedPageInfo> = repository
+            .get_all()
+            .await?
+            .into_iter()
+            .filter(|p| p.tab_info.is_some() || p.bookmark_info.is_some())
+            .collect();
+
+        let mut notes: HashMap<String, Vec<PageNote>> = HashMap::new();
+        if let Some(note_repository) = &self.note_repository {
+            for page in &pages {
+                let page_notes = note_repository.get_for_page(&page.id).await?;
+                if !page_notes.is_empty() {
+                    let key = self.sync_manager.matcher().normalize_url(&page.url);
+                    notes.entry(key).or_default().extend(page_notes);
+                }
+            }
+        }
+
+        *self.tabs.write().await = pages.iter().filter_map(|p| p.tab_info.clone()).collect();
+        *self.bookmarks.write().await = pages.iter().filter_map(|p| p.bookmark_info.clone()).collect();
+        *self.notes.write().await = notes;
+        let count = pages.len();
+        *self.unified_pages.write().await = pages;
+        self.refresh_associations().await;
+
+        info!("Loaded {} unified pages", count);
+        Ok(count)
+    }
+
     /// Get the current configuration
     pub fn config(&self) -> &PageUnifiedManagerConfig {
         &self.config
@@ -120,6 +276,66 @@ impl PageUnifiedManager {
         &self.sync_manager
     }
 
+    /// Subscribe to page events
+    ///
+    /// The receiver sees every page added, updated or removed and every new
+    /// tab-bookmark association from now on. A subscriber falling more than
+    /// `EVENT_CAPACITY` events behind misses the oldest and should re-fetch
+    /// `get_unified_pages()`.
+    pub fn subscribe(&self) -> broadcast::Receiver<PageEvent> {
+        self.events.subscribe()
+    }
+
+    /// Send an event to the subscribers, if there are any
+    fn emit(&self, event: PageEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Write page changes through to the repository, then send them to
+    /// the subscribers
+    async fn publish(&self, events: Vec<PageEvent>) {
+        for mut event in events {
+            if let Some(repository) = &self.page_repository {
+                if let Err(e) = self.write_through(repository.as_ref(), &mut event).await {
+                    warn!("Failed to persist page change: {}", e);
+                }
+            }
+            self.emit(event);
+        }
+    }
+
+    async fn write_through(&self, repository: &dyn PageRepository, event: &mut PageEvent) -> Result<()> {
+        match event {
+            PageEvent::Added(page) => {
+                repository.save(page).await?;
+                // Saving merges a page into a stored one with the same
+                // canonical URL, whose ID and analysis the page then takes
+                let Some(stored) = repository.get_by_url(&page.url).await? else {
+                    return Ok(());
+                };
+                if stored.id != page.id {
+                    let mut pages = self.unified_pages.write().await;
+                    if !pages.iter().any(|p| p.id == stored.id) {
+                        if let Some(cached) = pages.iter_mut().find(|p| p.id == page.id) {
+                            *cached = stored.clone();
+                        }
+                        *page = stored;
+                    }
+                }
+            }
+            PageEvent::Updated(page) => repository.save(page).await?,
+            PageEvent::Removed { id, .. } => {
+                if let Some(mut stored) = repository.get_by_id(id).await? {
+                    stored.tab_info = None;
+                    stored.bookmark_info = None;
+                    repository.save(&stored).await?;
+                }
+            }
+            PageEvent::Associated(_) => {}
+        }
+        Ok(())
+    }
+
     // =========================================================================
     // Data Management Methods
     // =========================================================================
@@ -185,6 +401,7 @@ impl PageUnifiedManager {
         let bookmarks = self.bookmarks.read().await;
         let mut cache = self.association_cache.write().await;
 
+        let previous = associated_bookmarks(&cache);
         cache.clear();
 
         // Build match map
@@ -217,6 +434,40 @@ impl PageUnifiedManager {
                 },
             );
         }
+
+        for status in cache.values() {
+            self.emit_association(status, &previous);
+        }
+    }
+
+    /// Send an `Associated` event if a tab's bookmark differs from the one
+    /// in `previous`
+    fn emit_association(&self, status: &TabAssociationStatus, previous: &HashMap<TabId, BookmarkId>) {
+        if let Some(info) = &status.matching_bookmark {
+            if previous.get(&status.tab_id) != Some(&info.bookmark_id) {
+                self.emit(PageEvent::Associated(info.clone()));
+            }
+        }
+    }
+
+    /// Association status of one tab against the cached bookmarks
+    fn association_status(&self, tab: &TabInfo, bookmarks: &[BookmarkInfo]) -> TabAssociationStatus {
+        let matches = self.sync_manager.matcher().find_matches_for_tab(tab, bookmarks);
+        let pending_changes = matches
+            .iter()
+            .filter(|m| matches!(m.match_type, MatchType::ExactUrl))
+            .filter_map(|m| bookmarks.iter().find(|b| b.id == m.bookmark_id))
+            .map(|b| ContentChangeDetector::detect_changes(tab, b))
+            .rfind(|c| c.has_changes());
+        let best_match = matches.into_iter().next();
+
+        TabAssociationStatus {
+            tab_id: tab.id.clone(),
+            has_bookmark: best_match.is_some(),
+            matching_bookmark: best_match,
+            has_pending_changes: pending_changes.is_some(),
+            pending_changes,
+        }
     }
 
     /// Refresh unified pages by merging tabs and bookmarks
@@ -227,8 +478,23 @@ impl PageUnifiedManager {
 
         let merged = self.sync_manager.batch_merge(&tabs, &bookmarks, &existing);
 
-        let mut pages = self.unified_pages.write().await;
-        *pages = merged;
+        let mut changes = Vec::new();
+        let before: HashMap<uuid::Uuid, &UnifiedPageInfo> = existing.iter().map(|p| (p.id, p)).collect();
+        for page in &merged {
+            match before.get(&page.id) {
+                None => changes.push(PageEvent::Added(page.clone())),
+                Some(old) if page_changed(old, page) => changes.push(PageEvent::Updated(page.clone())),
+                Some(_) => {}
+            }
+        }
+        for old in existing.iter().filter(|old| !merged.iter().any(|p| p.id == old.id)) {
+            changes.push(PageEvent::Removed { id: old.id, url: old.url.clone() });
+        }
+
+        *self.unified_pages.write().await = merged;
+        drop(tabs);
+        drop(bookmarks);
+        self.publish(changes).await;
     }
 
     /// Detect changes and add them to the sync queue
@@ -236,8 +502,24 @@ impl PageUnifiedManager {
         let tabs = self.tabs.read().await;
         let bookmarks = self.bookmarks.read().await;
 
-        let actions = self.sync_manager.generate_sync_actions(&tabs, &bookmarks);
+        let plan = {
+            let mut state = self.sync_state.write().await;
+            self.sync_manager
+                .plan_sync(&tabs, &bookmarks, &mut state, self.config.conflict_strategy)
+        };
 
+        {
+            let mut conflicts = self.sync_conflicts.write().await;
+            *conflicts = plan
+                .unresolved()
+                .map(|c| (c.bookmark_id.clone(), c.clone()))
+                .collect();
+            if !conflicts.is_empty() {
+                info!("{} sync conflicts need resolving", conflicts.len());
+            }
+        }
+
+        let actions = plan.actions;
         if !actions.is_empty() {
             let mut queue = self.sync_queue.write().await;
 
@@ -264,6 +546,150 @@ impl PageUnifiedManager {
         }
     }
 
+    // =========================================================================
+    // Incremental Update Methods
+    // =========================================================================
+
+    /// Apply tab events to the unified store incrementally
+    ///
+    /// Only the pages of the tabs an event touches are re-merged, instead
+    /// of every tab as `update_tabs` does, which keeps large libraries
+    /// responsive. Returns the pages that were created or updated; a page
+    /// left without a tab or bookmark is removed.
+    pub async fn apply_tab_events(&self, events: &[TabEvent]) -> Vec<UnifiedPageInfo> {
+        let mut touched_urls: Vec<String> = Vec::new();
+        let mut touched_tabs: Vec<TabId> = Vec::new();
+
+        {
+            let mut tabs = self.tabs.write().await;
+            for event in events {
+                match event {
+                    TabEvent::Created { tab, .. } => {
+                        tabs.retain(|t| !(t.id == tab.id && t.browser_type == tab.browser_type));
+                        tabs.push(tab.clone());
+                        touched_urls.push(tab.url.clone());
+                        touched_tabs.push(tab.id.clone());
+                    }
+                    TabEvent::Closed { tab_id, browser_type, last_known_info, .. } => {
+                        let position = tabs
+                            .iter()
+                            .position(|t| &t.id == tab_id && &t.browser_type == browser_type);
+                        let closed = position.map(|i| tabs.remove(i)).or_else(|| last_known_info.clone());
+                        if let Some(closed) = closed {
+                            touched_urls.push(closed.url);
+                        }
+                        self.association_cache.write().await.remove(tab_id);
+                    }
+                    TabEvent::Navigated { tab_id, browser_type, old_url, new_url, timestamp } => {
+                        if let Some(tab) = tabs
+                            .iter_mut()
+                            .find(|t| &t.id == tab_id && &t.browser_type == browser_type)
+                        {
+                            tab.url = new_url.clone();
+                            tab.last_accessed = *timestamp;
+                        }
+                        touched_urls.push(old_url.clone());
+                        touched_urls.push(new_url.clone());
+                        touched_tabs.push(tab_id.clone());
+                    }
+                    TabEvent::TitleChanged { tab_id, browser_type, new_title, .. } => {
+                        if let Some(tab) = tabs
+                            .iter_mut()
+                            .find(|t| &t.id == tab_id && &t.browser_type == browser_type)
+                        {
+                            tab.title = new_title.clone();
+                            touched_urls.push(tab.url.clone());
+                        }
+                        touched_tabs.push(tab_id.clone());
+                    }
+                    TabEvent::Activated { tab_id, browser_type, timestamp } => {
+                        if let Some(tab) = tabs
+                            .iter_mut()
+                            .find(|t| &t.id == tab_id && &t.browser_type == browser_type)
+                        {
+                            tab.last_accessed = *timestamp;
+                            touched_urls.push(tab.url.clone());
+                        }
+                    }
+                    TabEvent::LoadingStateChanged { .. } => {}
+                }
+            }
+        }
+
+        let tabs = self.tabs.read().await;
+        let bookmarks = self.bookmarks.read().await;
+        let matcher = self.sync_manager.matcher();
+
+        // Re-merge the page of each touched URL once
+        let mut updated = Vec::new();
+        let mut changes = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        {
+            let mut pages = self.unified_pages.write().await;
+            for url in &touched_urls {
+                let key = matcher.normalize_url(url);
+                if !seen.insert(key.clone()) {
+                    continue;
+                }
+
+                let tab = tabs.iter().find(|t| matcher.normalize_url(&t.url) == key);
+                let bookmark = bookmarks.iter().find(|b| matcher.urls_match_exact(&b.url, url));
+                let position = pages.iter().position(|p| matcher.normalize_url(&p.url) == key);
+
+                if tab.is_none() && bookmark.is_none() {
+                    if let Some(i) = position {
+                        let removed = pages.remove(i);
+                        changes.push(PageEvent::Removed { id: removed.id, url: removed.url });
+                    }
+                    continue;
+                }
+
+                let page = self
+                    .sync_manager
+                    .merge_to_unified_page(tab, bookmark, position.map(|i| &pages[i]));
+                match position {
+                    Some(i) => {
+                        if page_changed(&pages[i], &page) {
+                            changes.push(PageEvent::Updated(page.clone()));
+                        }
+                        pages[i] = page.clone();
+                    }
+                    None => {
+                        changes.push(PageEvent::Added(page.clone()));
+                        pages.push(page.clone());
+                    }
+                }
+                updated.push(page);
+            }
+        }
+
+        {
+            let mut cache = self.association_cache.write().await;
+            let previous = associated_bookmarks(&cache);
+            for tab in tabs.iter().filter(|t| touched_tabs.contains(&t.id)) {
+                let status = self.association_status(tab, &bookmarks);
+                self.emit_association(&status, &previous);
+                cache.insert(tab.id.clone(), status);
+            }
+        }
+        drop(tabs);
+        drop(bookmarks);
+
+        self.publish(changes).await;
+        if self.page_repository.is_some() {
+            // Added pages may have taken the ID of a stored page
+            let pages = self.unified_pages.read().await;
+            for page in &mut updated {
+                if let Some(cached) = pages.iter().find(|p| p.url == page.url) {
+                    *page = cached.clone();
+                }
+            }
+        }
+
+        debug!("Applied {} tab events, updated {} pages", events.len(), updated.len());
+        updated
+    }
+
     // =========================================================================
     // Query Methods
     // =========================================================================
@@ -273,6 +699,62 @@ impl PageUnifiedManager {
         self.unified_pages.read().await.clone()
     }
 
+    /// Get the pages at positions `range` of the unified pages matching
+    /// `filter`, sorted by `sort`
+    ///
+    /// Only the pages of the window are copied, so a UI can show a large
+    /// library by fetching the rows it displays.
+    pub async fn get_pages(
+        &self,
+        range: Range<usize>,
+        sort: UnifiedPageSort,
+        filter: &UnifiedPageFilter,
+    ) -> UnifiedPageWindow {
+        let query = filter.query.as_deref().map(str::trim).filter(|q| !q.is_empty()).map(str::to_lowercase);
+        let noted = match &query {
+            Some(query) => self.urls_with_matching_notes(query).await,
+            None => Default::default(),
+        };
+        let pages = self.unified_pages.read().await;
+
+        let mut matching: Vec<&UnifiedPageInfo> = pages
+            .iter()
+            .filter(|page| {
+                query.as_ref().is_none_or(|q| {
+                    page.title.to_lowercase().contains(q)
+                        || page.url.to_lowercase().contains(q)
+                        || page.keywords.iter().any(|k| k.to_lowercase().contains(q))
+                        || noted.contains(&self.sync_manager.matcher().normalize_url(&page.url))
+                }) && filter.browser.is_none_or(|b| page_browser(page) == Some(b))
+                    && filter.has_tab.is_none_or(|t| page.tab_info.is_some() == t)
+                    && filter.has_bookmark.is_none_or(|b| page.bookmark_info.is_some() == b)
+                    && filter.category.as_ref().is_none_or(|c| page.category.as_ref() == Some(c))
+            })
+            .collect();
+
+        matching.sort_by(|a, b| {
+            let ordering = match sort.key {
+                UnifiedPageSortKey::LastAccessed => a.last_accessed.cmp(&b.last_accessed),
+                UnifiedPageSortKey::CreatedAt => a.created_at.cmp(&b.created_at),
+                UnifiedPageSortKey::AccessCount => a.access_count.cmp(&b.access_count),
+                UnifiedPageSortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            }
+            .then_with(|| a.id.cmp(&b.id));
+            match sort.order {
+                SortOrder::Ascending => ordering,
+                SortOrder::Descending => ordering.reverse(),
+            }
+        });
+
+        let total = matching.len();
+        let range = range.start.min(total)..range.end.clamp(range.start.min(total), total);
+        UnifiedPageWindow {
+            pages: matching[range.clone()].iter().map(|p| (*p).clone()).collect(),
+            range,
+            total,
+        }
+    }
+
     /// Get a unified page by ID
     pub async fn get_unified_page_by_id(&self, id: &uuid::Uuid) -> Option<UnifiedPageInfo> {
         self.unified_pages
@@ -403,6 +885,7 @@ impl PageUnifiedManager {
 
         let mut performed_actions = Vec::new();
         let mut errors = Vec::new();
+        let mut changes = Vec::new();
 
         for item in approved {
             match &item.suggested_action {
@@ -470,6 +953,7 @@ impl PageUnifiedManager {
                             page.category = category.clone();
                         }
                         page.last_accessed = chrono::Utc::now();
+                        changes.push(PageEvent::Updated(page.clone()));
                         performed_actions.push(item.suggested_action.clone());
                         info!("Updated unified page {:?}", page_id);
                     } else {
@@ -479,6 +963,8 @@ impl PageUnifiedManager {
             }
         }
 
+        self.publish(changes).await;
+
         // Refresh after sync
         self.refresh_associations().await;
         self.refresh_unified_pages().await;
@@ -491,6 +977,45 @@ impl PageUnifiedManager {
         self.sync_queue.write().await.clear();
     }
 
+    /// Get conflicts between tab and bookmark changes left for the user
+    pub async fn get_sync_conflicts(&self) -> Vec<SyncConflict> {
+        self.sync_conflicts.read().await.values().cloned().collect()
+    }
+
+    /// Resolve a sync conflict, updating the bookmark if the tab wins
+    ///
+    /// Returns false if there is no conflict for the bookmark.
+    pub async fn resolve_sync_conflict(
+        &self,
+        bookmark_id: &BookmarkId,
+        resolution: ConflictResolution,
+    ) -> bool {
+        let Some(conflict) = self.sync_conflicts.write().await.remove(bookmark_id) else {
+            return false;
+        };
+
+        let tabs = self.tabs.read().await;
+        let mut bookmarks = self.bookmarks.write().await;
+        let tab = tabs.iter().find(|t| t.id == conflict.tab_id);
+        if let Some(bookmark) = bookmarks.iter_mut().find(|b| &b.id == bookmark_id) {
+            if let Some(SyncAction::UpdateBookmark { new_title, new_favicon, .. }) =
+                conflict.action(resolution)
+            {
+                *bookmark = self.sync_manager.apply_bookmark_update(bookmark, new_title, new_favicon);
+            }
+            if let Some(tab) = tab {
+                self.sync_state.write().await.record(tab, bookmark);
+            }
+        }
+        drop(bookmarks);
+        drop(tabs);
+
+        info!("Resolved sync conflict for bookmark {:?} with {:?}", bookmark_id, resolution);
+        self.refresh_associations().await;
+        self.refresh_unified_pages().await;
+        true
+    }
+
     // =========================================================================
     // Bookmark Creation Methods
     // =========================================================================
@@ -535,6 +1060,8 @@ impl PageUnifiedManager {
             let mut pages = self.unified_pages.write().await;
             pages.push(bookmark_page.clone());
         }
+        drop(tabs);
+        self.publish(vec![PageEvent::Added(bookmark_page.clone())]).await;
 
         // Refresh associations
         self.refresh_associations().await;
@@ -544,6 +1071,134 @@ impl PageUnifiedManager {
         Ok((bookmark, bookmark_page))
     }
 
+    // =========================================================================
+    // Note Methods
+    // =========================================================================
+
+    /// Attach a note to a unified page
+    ///
+    /// Notes are kept by the page's URL, so they stay with the page when
+    /// its tab is closed to history or saved as a bookmark.
+    pub async fn add_note(&self, page_id: &uuid::Uuid, content: &str) -> Result<PageNote> {
+        let page = self.page_for_note(page_id).await?;
+        self.insert_note(&page.url, PageNote::note(page.id, content.trim())).await
+    }
+
+    /// Attach a highlighted excerpt of a page, with an optional comment
+    pub async fn add_highlight(&self, page_id: &uuid::Uuid, quote: &str, comment: &str) -> Result<PageNote> {
+        if quote.trim().is_empty() {
+            return Err(WebPageManagerError::System {
+                source: SystemError::Configuration {
+                    details: "Highlight has no text".to_string(),
+                },
+            });
+        }
+        let page = self.page_for_note(page_id).await?;
+        self.insert_note(&page.url, PageNote::highlight(page.id, quote.trim(), comment.trim()))
+            .await
+    }
+
+    /// Notes and highlights of a unified page, oldest first
+    pub async fn get_notes(&self, page_id: &uuid::Uuid) -> Vec<PageNote> {
+        match self.get_unified_page_by_id(page_id).await {
+            Some(page) => self.get_notes_for_url(&page.url).await,
+            None => Vec::new(),
+        }
+    }
+
+    /// Notes and highlights taken on `url`, whether it is open, in the
+    /// history or bookmarked
+    pub async fn get_notes_for_url(&self, url: &str) -> Vec<PageNote> {
+        let key = self.sync_manager.matcher().normalize_url(url);
+        self.notes.read().await.get(&key).cloned().unwrap_or_default()
+    }
+
+    /// Replace the text of a note, or the comment of a highlight; false if
+    /// the note does not exist
+    pub async fn update_note(&self, note_id: &uuid::Uuid, content: &str) -> bool {
+        let mut notes = self.notes.write().await;
+        let Some(note) = notes.values_mut().flatten().find(|n| &n.id == note_id) else {
+            return false;
+        };
+        note.content = content.trim().to_string();
+        note.updated_at = Utc::now();
+        if let Some(repository) = &self.note_repository {
+            if let Err(e) = repository.save(note).await {
+                warn!("Failed to persist note {}: {}", note_id, e);
+            }
+        }
+        true
+    }
+
+    /// Delete a note or highlight; false if it does not exist
+    pub async fn delete_note(&self, note_id: &uuid::Uuid) -> bool {
+        let mut notes = self.notes.write().await;
+        let Some(key) = notes
+            .iter()
+            .find(|(_, page_notes)| page_notes.iter().any(|n| &n.id == note_id))
+            .map(|(key, _)| key.clone())
+        else {
+            return false;
+        };
+        if let Some(page_notes) = notes.get_mut(&key) {
+            page_notes.retain(|n| &n.id != note_id);
+            if page_notes.is_empty() {
+                notes.remove(&key);
+            }
+        }
+        if let Some(repository) = &self.note_repository {
+            if let Err(e) = repository.delete(note_id).await {
+                warn!("Failed to delete stored note {}: {}", note_id, e);
+            }
+        }
+        true
+    }
+
+    /// Notes and highlights whose text or quote contains `query`
+    pub async fn search_notes(&self, query: &str) -> Vec<PageNote> {
+        let query_lower = query.to_lowercase();
+        let mut matches: Vec<PageNote> = self
+            .notes
+            .read()
+            .await
+            .values()
+            .flatten()
+            .filter(|n| note_matches(n, &query_lower))
+            .cloned()
+            .collect();
+        matches.sort_by_key(|p| std::cmp::Reverse(p.updated_at));
+        matches
+    }
+
+    async fn page_for_note(&self, page_id: &uuid::Uuid) -> Result<UnifiedPageInfo> {
+        self.get_unified_page_by_id(page_id).await.ok_or_else(|| WebPageManagerError::System {
+            source: SystemError::Configuration {
+                details: format!("Page {} not found", page_id),
+            },
+        })
+    }
+
+    async fn insert_note(&self, url: &str, note: PageNote) -> Result<PageNote> {
+        if let Some(repository) = &self.note_repository {
+            repository.save(&note).await?;
+        }
+        let key = self.sync_manager.matcher().normalize_url(url);
+        self.notes.write().await.entry(key).or_default().push(note.clone());
+        debug!("Added {} to page {}", if note.is_highlight() { "highlight" } else { "note" }, url);
+        Ok(note)
+    }
+
+    /// Normalized URLs of pages with a note matching the lowercased query
+    async fn urls_with_matching_notes(&self, query_lower: &str) -> std::collections::HashSet<String> {
+        self.notes
+            .read()
+            .await
+            .iter()
+            .filter(|(_, page_notes)| page_notes.iter().any(|n| note_matches(n, query_lower)))
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
     // =========================================================================
     // Statistics Methods
     // =========================================================================
@@ -579,14 +1234,15 @@ impl PageUnifiedManager {
 
     /// Search unified pages by query string (in-memory search)
     ///
-    /// This performs a simple text search across titles, URLs, and keywords
-    /// of the cached unified pages. For full-text search with database
+    /// This performs a simple text search across titles, URLs, keywords and
+    /// notes of the cached unified pages. For full-text search with database
     /// support, use UnifiedSearchManager.
     ///
     /// Implements Requirement 6.5: Unified search across tabs and bookmarks
     pub async fn search_pages(&self, query: &str) -> Vec<UnifiedPageInfo> {
-        let pages = self.unified_pages.read().await;
         let query_lower = query.to_lowercase();
+        let noted = self.urls_with_matching_notes(&query_lower).await;
+        let pages = self.unified_pages.read().await;
 
         pages
             .iter()
@@ -595,6 +1251,7 @@ impl PageUnifiedManager {
                     || page.url.to_lowercase().contains(&query_lower)
                     || page.keywords.iter().any(|k| k.to_lowercase().contains(&query_lower))
                     || page.category.as_ref().map(|c| c.to_lowercase().contains(&query_lower)).unwrap_or(false)
+                    || noted.contains(&self.sync_manager.matcher().normalize_url(&page.url))
             })
             .cloned()
             .collect()
@@ -610,8 +1267,9 @@ impl PageUnifiedManager {
         include_tabs: bool,
         include_bookmarks: bool,
     ) -> Vec<UnifiedPageInfo> {
-        let pages = self.unified_pages.read().await;
         let query_lower = query.to_lowercase();
+        let noted = self.urls_with_matching_notes(&query_lower).await;
+        let pages = self.unified_pages.read().await;
 
         pages
             .iter()
@@ -619,7 +1277,8 @@ impl PageUnifiedManager {
                 // Text match
                 let text_match = page.title.to_lowercase().contains(&query_lower)
                     || page.url.to_lowercase().contains(&query_lower)
-                    || page.keywords.iter().any(|k| k.to_lowercase().contains(&query_lower));
+                    || page.keywords.iter().any(|k| k.to_lowercase().contains(&query_lower))
+                    || noted.contains(&self.sync_manager.matcher().normalize_url(&page.url));
 
                 if !text_match {
                     return false;
@@ -627,12 +1286,7 @@ impl PageUnifiedManager {
 
                 // Browser filter
                 if let Some(browser) = browser_filter {
-                    let page_browser = match &page.source_type {
-                        PageSourceType::ActiveTab { browser: b, .. } => Some(*b),
-                        PageSourceType::Bookmark { browser: b, .. } => Some(*b),
-                        _ => page.browser_info.as_ref().map(|bi| bi.browser_type),
-                    };
-                    if page_browser != Some(browser) {
+                    if page_browser(page) != Some(browser) {
                         return false;
                     }
                 }
@@ -665,6 +1319,42 @@ impl PageUnifiedManager {
     }
 }
 
+/// Whether a note's text or quote contains the lowercased query
+fn note_matches(note: &PageNote, query_lower: &str) -> bool {
+    note.content.to_lowercase().contains(query_lower)
+        || note.quote.as_ref().is_some_and(|q| q.to_lowercase().contains(query_lower))
+}
+
+/// Browser of a page's tab or bookmark, or of its browser info
+fn page_browser(page: &UnifiedPageInfo) -> Option<BrowserType> {
+    match &page.source_type {
+        PageSourceType::ActiveTab { browser, .. } => Some(*browser),
+        PageSourceType::Bookmark { browser, .. } => Some(*browser),
+        _ => page.browser_info.as_ref().map(|bi| bi.browser_type),
+    }
+}
+
+/// Bookmark each tab is associated with
+fn associated_bookmarks(cache: &HashMap<TabId, TabAssociationStatus>) -> HashMap<TabId, BookmarkId> {
+    cache
+        .iter()
+        .filter_map(|(tab_id, status)| Some((tab_id.clone(), status.matching_bookmark.as_ref()?.bookmark_id.clone())))
+        .collect()
+}
+
+/// Whether a merge changed a page beyond its access time and count
+fn page_changed(old: &UnifiedPageInfo, new: &UnifiedPageInfo) -> bool {
+    let content = |page: &UnifiedPageInfo| {
+        let mut value = serde_json::to_value(page).unwrap_or_default();
+        if let Some(fields) = value.as_object_mut() {
+            fields.remove("last_accessed");
+            fields.remove("access_count");
+        }
+        value
+    };
+    content(old) != content(new)
+}
+
 impl Default for PageUnifiedManager {
     fn default() -> Self {
         Self::new()
@@ -744,6 +1434,221 @@ mod tests {
         assert!(status.pending_changes.unwrap().title_changed);
     }
 
+    #[tokio::test]
+    async fn test_sync_conflict_resolution() {
+        let manager = PageUnifiedManager::new();
+        let tab = create_test_tab("https://example.com", "Example");
+        let bookmark = create_test_bookmark("https://example.com", "Example");
+        manager.update_all(vec![tab.clone()], vec![bookmark.clone()]).await;
+
+        let mut changed_tab = tab.clone();
+        changed_tab.title = "Example - new".to_string();
+        let mut changed_bookmark = bookmark.clone();
+        changed_bookmark.title = "My example".to_string();
+        manager.update_all(vec![changed_tab], vec![changed_bookmark]).await;
+
+        let conflicts = manager.get_sync_conflicts().await;
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(manager.pending_sync_count().await, 0);
+
+        assert!(manager.resolve_sync_conflict(&bookmark.id, ConflictResolution::UseTab).await);
+        assert!(manager.get_sync_conflicts().await.is_empty());
+        assert_eq!(manager.get_cached_bookmarks().await[0].title, "Example - new");
+        assert!(!manager.resolve_sync_conflict(&bookmark.id, ConflictResolution::UseTab).await);
+    }
+
+    #[tokio::test]
+    async fn test_apply_tab_events() {
+        let manager = PageUnifiedManager::new();
+        let kept = create_test_tab("https://rust-lang.org", "Rust");
+        let bookmark = create_test_bookmark("https://example.com/docs", "Docs");
+        manager.update_all(vec![kept.clone()], vec![bookmark.clone()]).await;
+        assert_eq!(manager.get_unified_pages().await.len(), 2);
+        let kept_page = manager.get_unified_page_by_url(&kept.url).await.unwrap();
+
+        let tab = create_test_tab("https://example.com/new", "New");
+        let now = chrono::Utc::now();
+        let updated = manager
+            .apply_tab_events(&[
+                TabEvent::Created { tab: tab.clone(), timestamp: now },
+                TabEvent::Navigated {
+                    tab_id: tab.id.clone(),
+                    browser_type: tab.browser_type,
+                    old_url: tab.url.clone(),
+                    new_url: bookmark.url.clone(),
+                    timestamp: now,
+                },
+                TabEvent::TitleChanged {
+                    tab_id: tab.id.clone(),
+                    browser_type: tab.browser_type,
+                    old_title: "New".to_string(),
+                    new_title: "Docs - updated".to_string(),
+                    timestamp: now,
+                },
+            ])
+            .await;
+
+        // The navigated-away URL has no tab or bookmark left
+        assert_eq!(updated.len(), 1);
+        let pages = manager.get_unified_pages().await;
+        assert_eq!(pages.len(), 2);
+        let docs = manager.get_unified_page_by_url(&bookmark.url).await.unwrap();
+        assert_eq!(docs.title, "Docs - updated");
+        assert!(docs.tab_info.is_some() && docs.bookmark_info.is_some());
+        assert!(manager.tab_has_bookmark(&tab.id).await);
+        assert!(manager.get_tab_association_status(&tab.id).await.unwrap().has_pending_changes);
+        // Untouched pages are left as they were
+        assert_eq!(manager.get_unified_page_by_url(&kept.url).await.unwrap().access_count, kept_page.access_count);
+
+        manager
+            .apply_tab_events(&[TabEvent::Closed {
+                tab_id: tab.id.clone(),
+                browser_type: tab.browser_type,
+                timestamp: now,
+                last_known_info: None,
+            }])
+            .await;
+        let docs = manager.get_unified_page_by_url(&bookmark.url).await.unwrap();
+        assert!(docs.tab_info.is_none() && docs.bookmark_info.is_some());
+        assert!(manager.get_tab_association_status(&tab.id).await.is_none());
+        assert_eq!(manager.get_cached_tabs().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_page_events() {
+        let manager = PageUnifiedManager::new();
+        let mut events = manager.subscribe();
+        let drain = |events: &mut broadcast::Receiver<PageEvent>| {
+            std::iter::from_fn(|| events.try_recv().ok()).collect::<Vec<_>>()
+        };
+
+        let tab = create_test_tab("https://example.com/docs", "Docs");
+        manager.update_tabs(vec![tab.clone()]).await;
+        let received = drain(&mut events);
+        assert!(matches!(&received[..], [PageEvent::Added(page)] if page.url == tab.url));
+
+        // Refreshing unchanged data sends nothing
+        manager.update_tabs(vec![tab.clone()]).await;
+        assert!(drain(&mut events).is_empty());
+
+        let bookmark = create_test_bookmark(&tab.url, "Docs");
+        manager.update_bookmarks(vec![bookmark.clone()]).await;
+        let received = drain(&mut events);
+        assert!(received
+            .iter()
+            .any(|e| matches!(e, PageEvent::Associated(info) if info.tab_id == tab.id && info.bookmark_id == bookmark.id)));
+        assert!(received
+            .iter()
+            .any(|e| matches!(e, PageEvent::Updated(page) if page.bookmark_info.is_some())));
+
+        let other = create_test_tab("https://rust-lang.org", "Rust");
+        let now = chrono::Utc::now();
+        manager.apply_tab_events(&[TabEvent::Created { tab: other.clone(), timestamp: now }]).await;
+        let received = drain(&mut events);
+        assert!(matches!(&received[..], [PageEvent::Added(page)] if page.url == other.url));
+        let added = manager.get_unified_page_by_url(&other.url).await.unwrap();
+
+        manager
+            .apply_tab_events(&[TabEvent::Closed {
+                tab_id: other.id.clone(),
+                browser_type: other.browser_type,
+                timestamp: now,
+                last_known_info: None,
+            }])
+            .await;
+        let received = drain(&mut events);
+        assert!(matches!(&received[..], [PageEvent::Removed { id, .. }] if *id == added.id));
+    }
+
+    #[tokio::test]
+    async fn test_get_pages_windows() {
+        let manager = PageUnifiedManager::new();
+        let tabs: Vec<TabInfo> = ["Delta", "alpha", "Charlie", "echo"]
+            .iter()
+            .map(|t| create_test_tab(&format!("https://{}.example.com", t.to_lowercase()), t))
+            .collect();
+        let bookmark = create_test_bookmark("https://bravo.example.com", "Bravo");
+        manager.update_all(tabs, vec![bookmark]).await;
+        let titles = |window: &UnifiedPageWindow| window.pages.iter().map(|p| p.title.clone()).collect::<Vec<_>>();
+
+        let by_title = UnifiedPageSort { key: UnifiedPageSortKey::Title, order: SortOrder::Ascending };
+        let window = manager.get_pages(1..3, by_title, &UnifiedPageFilter::default()).await;
+        assert_eq!(titles(&window), vec!["Bravo", "Charlie"]);
+        assert_eq!((window.range, window.total), (1..3, 5));
+
+        // Windows past the end are clamped
+        let descending = UnifiedPageSort { order: SortOrder::Descending, ..by_title };
+        let window = manager.get_pages(3..10, descending, &UnifiedPageFilter::default()).await;
+        assert_eq!(titles(&window), vec!["Bravo", "alpha"]);
+        assert_eq!(window.range, 3..5);
+        assert!(manager.get_pages(7..9, by_title, &UnifiedPageFilter::default()).await.pages.is_empty());
+
+        let tabs_only = UnifiedPageFilter { has_bookmark: Some(false), ..Default::default() };
+        assert_eq!(manager.get_pages(0..10, by_title, &tabs_only).await.total, 4);
+        let query = UnifiedPageFilter { query: Some(" ECHO ".to_string()), ..Default::default() };
+        assert_eq!(titles(&manager.get_pages(0..10, by_title, &query).await), vec!["echo"]);
+        let firefox = UnifiedPageFilter { browser: Some(BrowserType::Firefox), ..Default::default() };
+        assert_eq!(manager.get_pages(0..10, by_title, &firefox).await.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_persistence_across_restarts() {
+        let db = data_access::DatabaseManager::in_memory().await.unwrap();
+        let pages: Arc<dyn PageRepository> = Arc::new(db.page_repository());
+        let notes: Arc<dyn NoteRepository> = Arc::new(db.note_repository());
+        let start = || {
+            PageUnifiedManager::new()
+                .with_page_repository(pages.clone())
+                .with_note_repository(notes.clone())
+        };
+
+        // A page already in the library keeps its ID and analysis
+        let mut library = manager_page("https://example.com/guide");
+        library.bookmark_info = None;
+        library.category = Some("Guides".to_string());
+        pages.save(&library).await.unwrap();
+
+        let manager = start();
+        assert_eq!(manager.load().await.unwrap(), 0);
+        let guide = create_test_tab("https://example.com/guide", "Guide");
+        let other = create_test_tab("https://rust-lang.org", "Rust");
+        let bookmark = create_test_bookmark("https://docs.rs", "Docs");
+        manager.update_all(vec![guide.clone(), other.clone()], vec![bookmark.clone()]).await;
+        let page = manager.get_unified_page_by_url(&guide.url).await.unwrap();
+        assert_eq!(page.id, library.id);
+        assert_eq!(page.category.as_deref(), Some("Guides"));
+        let note = manager.add_note(&page.id, "Read later").await.unwrap();
+
+        // Closing a tab keeps its page in the library only
+        let closed = manager.get_unified_page_by_url(&other.url).await.unwrap();
+        manager
+            .apply_tab_events(&[TabEvent::Closed {
+                tab_id: other.id.clone(),
+                browser_type: other.browser_type,
+                timestamp: chrono::Utc::now(),
+                last_known_info: None,
+            }])
+            .await;
+        let stored = pages.get_by_id(&closed.id).await.unwrap().unwrap();
+        assert!(stored.tab_info.is_none() && stored.bookmark_info.is_none());
+
+        let restarted = start();
+        assert_eq!(restarted.load().await.unwrap(), 2);
+        let mut urls: Vec<String> = restarted.get_unified_pages().await.into_iter().map(|p| p.url).collect();
+        urls.sort();
+        assert_eq!(urls, vec![bookmark.url.clone(), guide.url.clone()]);
+        assert_eq!(restarted.get_cached_tabs().await.len(), 1);
+        assert_eq!(restarted.get_cached_bookmarks().await[0].id, bookmark.id);
+        assert_eq!(restarted.get_notes(&page.id).await.iter().map(|n| n.id).collect::<Vec<_>>(), vec![note.id]);
+
+        assert!(restarted.delete_note(&note.id).await);
+        assert!(notes.get_by_id(&note.id).await.unwrap().is_none());
+    }
+
+    fn manager_page(url: &str) -> UnifiedPageInfo {
+        PageUnifiedManager::new().sync_manager().merge_to_unified_page(None, Some(&create_test_bookmark(url, url)), None)
+    }
+
     #[tokio::test]
     async fn test_create_bookmark_from_tab() {
         let manager = PageUnifiedManager::new();
@@ -843,4 +1748,43 @@ mod tests {
         let results = manager.search_pages_filtered("programming", None, true, true).await;
         assert_eq!(results.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_notes_follow_page_to_bookmark_and_history() {
+        let manager = PageUnifiedManager::new();
+        let tab = create_test_tab("https://doc.rust-lang.org/book/ch04-01-what-is-ownership.html", "Ownership");
+        manager.update_tabs(vec![tab.clone()]).await;
+        let page = manager.get_unified_page_by_url(&tab.url).await.unwrap();
+
+        let note = manager.add_note(&page.id, "Reread before the talk").await.unwrap();
+        let highlight = manager
+            .add_highlight(&page.id, "Each value in Rust has an owner", "")
+            .await
+            .unwrap();
+        assert!(highlight.is_highlight());
+        assert!(manager.add_highlight(&page.id, "  ", "").await.is_err());
+        assert!(manager.add_note(&uuid::Uuid::new_v4(), "Lost").await.is_err());
+
+        // Notes and quotes are searched along with the page
+        assert_eq!(manager.search_pages("the talk").await.len(), 1);
+        assert_eq!(manager.search_pages_filtered("has an owner", None, true, true).await.len(), 1);
+        assert_eq!(manager.search_notes("OWNER").await[0].id, highlight.id);
+
+        // The bookmark made from the tab carries the notes
+        let (_, bookmark_page) = manager.create_bookmark_from_tab(&tab.id, vec![]).await.unwrap();
+        assert_ne!(bookmark_page.id, page.id);
+        assert_eq!(manager.get_notes(&bookmark_page.id).await.len(), 2);
+
+        // And so does the URL once the tab is closed to history
+        manager.update_all(vec![], vec![]).await;
+        assert!(manager.update_note(&note.id, "Reread after the talk").await);
+        let notes = manager.get_notes_for_url(&tab.url).await;
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].content, "Reread after the talk");
+
+        assert!(manager.delete_note(&note.id).await);
+        assert!(!manager.delete_note(&note.id).await);
+        assert!(!manager.update_note(&note.id, "Gone").await);
+        assert_eq!(manager.get_notes_for_url(&tab.url).await, vec![highlight]);
+    }
 }
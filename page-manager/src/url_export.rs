@@ -0,0 +1,253 @@
+//! URL Export Fallbacks
+//!
+//! Delivers the fallback data of migrations that could not open tabs in
+//! the target browser (Requirement 8.4): the plain-text list, JSON or HTML
+//! bookmark file is written to a chosen path for the user to import, or
+//! the list is placed on the clipboard to paste into the target browser.
+//!
+//! The clipboard is reached through the [`Clipboard`] trait, so UI shells
+//! can supply their own; [`SystemClipboard`] pipes the text to the
+//! platform's clipboard tool.
+
+use web_page_manager_core::*;
+use crate::remote_controller::{FallbackData, FallbackType, MigrationExportFormat};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Prefix of the names of export files written by [`UrlExporter::deliver`]
+pub const EXPORT_FILE_PREFIX: &str = "migrated-tabs";
+
+/// Clipboard tools with their arguments, tried in order
+#[cfg(target_os = "macos")]
+const CLIPBOARD_TOOLS: &[(&str, &[&str])] = &[("pbcopy", &[])];
+#[cfg(windows)]
+const CLIPBOARD_TOOLS: &[(&str, &[&str])] = &[("clip", &[])];
+#[cfg(all(unix, not(target_os = "macos")))]
+const CLIPBOARD_TOOLS: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+#[cfg(not(any(unix, windows)))]
+const CLIPBOARD_TOOLS: &[(&str, &[&str])] = &[];
+
+/// Where fallback data was delivered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FallbackDelivery {
+    /// Written to a file
+    File(PathBuf),
+    /// Placed on the clipboard
+    Clipboard,
+}
+
+/// A clipboard text can be placed on
+pub trait Clipboard: Send + Sync {
+    /// Replace the clipboard contents with `text`
+    fn set_text(&self, text: &str) -> Result<()>;
+}
+
+/// The system clipboard, reached through the platform's clipboard tool:
+/// `pbcopy` on macOS, `clip` on Windows, and `wl-copy`, `xclip` or `xsel`
+/// elsewhere
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClipboard;
+
+impl Clipboard for SystemClipboard {
+    fn set_text(&self, text: &str) -> Result<()> {
+        for (tool, args) in CLIPBOARD_TOOLS {
+            let mut child = match Command::new(tool)
+                .args(*args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+            {
+                Ok(child) => child,
+                Err(e) => {
+                    debug!("Clipboard tool {} is not available: {}", tool, e);
+                    continue;
+                }
+            };
+            // Dropping stdin closes it, so the tool sees the end of the text
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin
+                    .write_all(text.as_bytes())
+                    .map_err(|e| export_error(format!("Failed to write to {}: {}", tool, e)))?;
+            }
+            let status = child
+                .wait()
+                .map_err(|e| export_error(format!("Failed to run {}: {}", tool, e)))?;
+            if status.success() {
+                return Ok(());
+            }
+            debug!("Clipboard tool {} failed: {}", tool, status);
+        }
+        Err(export_error("No clipboard tool is available".to_string()))
+    }
+}
+
+/// Writes fallback data to files and the clipboard
+#[derive(Clone)]
+pub struct UrlExporter {
+    clipboard: Arc<dyn Clipboard>,
+}
+
+impl UrlExporter {
+    /// Create an exporter using the system clipboard
+    pub fn new() -> Self {
+        Self {
+            clipboard: Arc::new(SystemClipboard),
+        }
+    }
+
+    /// Use another clipboard
+    pub fn with_clipboard(mut self, clipboard: Arc<dyn Clipboard>) -> Self {
+        self.clipboard = clipboard;
+        self
+    }
+
+    /// Write fallback data to `path` in its export format, creating the
+    /// directories it is in
+    pub fn write_file(&self, data: &FallbackData, path: &Path) -> Result<()> {
+        let content = export_content(data)?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| write_error(parent, e))?;
+        }
+        std::fs::write(path, content).map_err(|e| write_error(path, e))?;
+        info!("Wrote {} exported URLs to {}", data.urls.len(), path.display());
+        Ok(())
+    }
+
+    /// Place fallback data on the clipboard
+    pub fn copy_to_clipboard(&self, data: &FallbackData) -> Result<()> {
+        self.clipboard.set_text(export_content(data)?)?;
+        info!("Copied {} exported URLs to the clipboard", data.urls.len());
+        Ok(())
+    }
+
+    /// Deliver fallback data as its type asks for: clipboard copies go to
+    /// the clipboard, and exports are written into `directory` under a new
+    /// file name of their format
+    pub fn deliver(&self, data: &FallbackData, directory: &Path) -> Result<FallbackDelivery> {
+        if data.fallback_type == FallbackType::ClipboardCopy {
+            self.copy_to_clipboard(data)?;
+            return Ok(FallbackDelivery::Clipboard);
+        }
+
+        let stamp = Utc::now().format("%Y%m%d-%H%M%S");
+        let extension = file_extension(data.format);
+        let mut path = directory.join(format!("{}-{}.{}", EXPORT_FILE_PREFIX, stamp, extension));
+        let mut n = 1;
+        while path.exists() {
+            n += 1;
+            path = directory.join(format!("{}-{}-{}.{}", EXPORT_FILE_PREFIX, stamp, n, extension));
+        }
+        self.write_file(data, &path)?;
+        Ok(FallbackDelivery::File(path))
+    }
+}
+
+impl Default for UrlExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// File extension of an export format
+pub fn file_extension(format: MigrationExportFormat) -> &'static str {
+    match format {
+        MigrationExportFormat::PlainText => "txt",
+        MigrationExportFormat::Json => "json",
+        MigrationExportFormat::Html => "html",
+    }
+}
+
+fn export_content(data: &FallbackData) -> Result<&str> {
+    data.export_content
+        .as_deref()
+        .ok_or_else(|| export_error("Fallback data has no export content".to_string()))
+}
+
+fn export_error(details: String) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration { details },
+    }
+}
+
+fn write_error(path: &Path, e: std::io::Error) -> WebPageManagerError {
+    export_error(format!("Failed to write URL export {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote_controller::RemoteTabController;
+    use std::sync::Mutex;
+
+    /// Clipboard keeping what was copied
+    #[derive(Default)]
+    struct MemoryClipboard {
+        text: Mutex<Option<String>>,
+    }
+
+    impl Clipboard for MemoryClipboard {
+        fn set_text(&self, text: &str) -> Result<()> {
+            *self.text.lock().unwrap() = Some(text.to_string());
+            Ok(())
+        }
+    }
+
+    fn tabs() -> Vec<TabInfo> {
+        ["https://a.example.com/?q=1&r=2", "https://b.example.com"]
+            .iter()
+            .map(|url| TabInfo {
+                id: TabId::new(),
+                url: url.to_string(),
+                title: format!("Tab <{}>", url),
+                favicon_url: None,
+                browser_type: BrowserType::Chrome,
+                is_private: false,
+                created_at: Utc::now(),
+                last_accessed: Utc::now(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_deliver_files_and_clipboard() {
+        let controller = RemoteTabController::new();
+        let clipboard = Arc::new(MemoryClipboard::default());
+        let exporter = UrlExporter::new().with_clipboard(clipboard.clone());
+        let dir = std::env::temp_dir().join(format!("url-export-{}", Uuid::new_v4()));
+
+        let html = controller.generate_fallback_export(&tabs(), BrowserType::Chrome, MigrationExportFormat::Html);
+        let first = exporter.deliver(&html, &dir).unwrap();
+        let second = exporter.deliver(&html, &dir).unwrap();
+        let FallbackDelivery::File(path) = &first else {
+            panic!("expected a file, got {:?}", first);
+        };
+        assert_ne!(first, second);
+        assert_eq!(path.extension().unwrap(), "html");
+        let written = std::fs::read_to_string(path).unwrap();
+        assert_eq!(Some(written.as_str()), html.export_content.as_deref());
+        assert!(written.contains("HREF=\"https://a.example.com/?q=1&amp;r=2\""));
+
+        let json = controller.generate_fallback_export(&tabs(), BrowserType::Chrome, MigrationExportFormat::Json);
+        let path = dir.join("nested").join("tabs.json");
+        exporter.write_file(&json, &path).unwrap();
+        let urls: Vec<serde_json::Value> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(urls.len(), 2);
+
+        let mut list = controller.generate_fallback_export(&tabs(), BrowserType::Chrome, MigrationExportFormat::PlainText);
+        list.fallback_type = FallbackType::ClipboardCopy;
+        assert_eq!(exporter.deliver(&list, &dir).unwrap(), FallbackDelivery::Clipboard);
+        assert_eq!(clipboard.text.lock().unwrap().as_deref(), list.export_content.as_deref());
+
+        list.export_content = None;
+        assert!(exporter.copy_to_clipboard(&list).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,434 @@
+//! WARC Export
+//!
+//! Packages archived pages into WARC 1.1 files, the standard container of
+//! web-archiving tools, so captured pages can be replayed or preserved
+//! elsewhere. Each archive becomes a `resource` record of its HTML with a
+//! `metadata` record of its title and identifiers, followed by `resource`
+//! records of its downloaded media and snapshots. Snapshots are addressed
+//! as `urn:<kind>:<url>`, so replay tools keep serving the HTML for the
+//! page's own URL.
+//!
+//! Files start with a `warcinfo` record and are rotated once they reach
+//! the configured size; an archive's records always share one file.
+
+use web_page_manager_core::*;
+use data_access::{ContentArchive, SnapshotKind, SnapshotRepository};
+use crate::content_archiver::ContentArchiver;
+use chrono::SecondsFormat;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// WARC version written in record headers
+pub const WARC_VERSION: &str = "WARC/1.1";
+
+/// Configuration for WARC export
+#[derive(Debug, Clone)]
+pub struct WarcExportConfig {
+    /// Prefix of the file names, followed by a sequence number
+    pub file_prefix: String,
+    /// Size after which a new file is started
+    pub max_file_size: u64,
+    /// Whether downloaded media files are exported
+    pub include_media: bool,
+    /// Whether MHTML, screenshot and PDF snapshots are exported
+    pub include_snapshots: bool,
+}
+
+impl Default for WarcExportConfig {
+    fn default() -> Self {
+        Self {
+            file_prefix: "web-page-manager".to_string(),
+            max_file_size: 1024 * 1024 * 1024, // 1GB
+            include_media: true,
+            include_snapshots: true,
+        }
+    }
+}
+
+/// Result of a WARC export
+#[derive(Debug, Clone, Default)]
+pub struct WarcExport {
+    /// Written files, in order
+    pub files: Vec<PathBuf>,
+    /// Number of archives exported
+    pub archives: usize,
+    /// Number of records written, `warcinfo` records included
+    pub records: usize,
+    pub bytes: u64,
+    /// Requested archives that do not exist
+    pub missing: Vec<ArchiveId>,
+}
+
+/// Exporter of archives to WARC files
+pub struct WarcExporter {
+    config: WarcExportConfig,
+    archiver: Arc<ContentArchiver>,
+    snapshots: Option<Arc<dyn SnapshotRepository>>,
+}
+
+impl WarcExporter {
+    /// Create an exporter of the archives of `archiver`
+    pub fn new(archiver: Arc<ContentArchiver>) -> Self {
+        Self {
+            config: WarcExportConfig::default(),
+            archiver,
+            snapshots: None,
+        }
+    }
+
+    /// Use a custom configuration
+    pub fn with_config(mut self, config: WarcExportConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Export the snapshots kept in `snapshots`
+    pub fn with_snapshots(mut self, snapshots: Arc<dyn SnapshotRepository>) -> Self {
+        self.snapshots = Some(snapshots);
+        self
+    }
+
+    /// Get the current configuration
+    pub fn config(&self) -> &WarcExportConfig {
+        &self.config
+    }
+
+    /// Write the archives of `archive_ids` to WARC files in `directory`
+    pub async fn export<P: AsRef<Path>>(&self, archive_ids: &[ArchiveId], directory: P) -> Result<WarcExport> {
+        let directory = directory.as_ref();
+        std::fs::create_dir_all(directory).map_err(|e| export_error(directory, e))?;
+
+        let mut export = WarcExport::default();
+        let mut file: Option<(File, PathBuf, u64)> = None;
+        for id in archive_ids {
+            let Some(archive) = self.archiver.get_archive(id).await? else {
+                export.missing.push(id.clone());
+                continue;
+            };
+            let records = self.archive_records(&archive).await?;
+            let size: u64 = records.iter().map(|r| r.len() as u64).sum();
+
+            if file.as_ref().is_some_and(|(_, _, written)| written + size > self.config.max_file_size) {
+                file = None;
+            }
+            let (out, path, written) = match &mut file {
+                Some(open) => open,
+                None => {
+                    let name = format!("{}-{:05}.warc", self.config.file_prefix, export.files.len());
+                    let path = directory.join(&name);
+                    let mut out = File::create(&path).map_err(|e| export_error(&path, e))?;
+                    let info = warcinfo_record(&name);
+                    out.write_all(&info).map_err(|e| export_error(&path, e))?;
+                    export.files.push(path.clone());
+                    export.records += 1;
+                    export.bytes += info.len() as u64;
+                    file.insert((out, path, info.len() as u64))
+                }
+            };
+            for record in &records {
+                out.write_all(record).map_err(|e| export_error(path, e))?;
+            }
+            *written += size;
+            export.records += records.len();
+            export.bytes += size;
+            export.archives += 1;
+        }
+
+        info!(
+            "Exported {} archives to {} WARC files ({} records, {} bytes)",
+            export.archives,
+            export.files.len(),
+            export.records,
+            export.bytes
+        );
+        Ok(export)
+    }
+
+    /// Records of one archive: its HTML, metadata, media and snapshots
+    async fn archive_records(&self, archive: &ContentArchive) -> Result<Vec<Vec<u8>>> {
+        let date = archive.archived_at.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let html = self.archiver.decompress_content(&archive.content_html);
+        let main_id = record_id();
+        let mut records = vec![resource_record(&main_id, &archive.url, &date, "text/html; charset=utf-8", &[], html.as_bytes())];
+
+        let mut fields = format!(
+            "title: {}\r\narchive-id: {}\r\npage-id: {}\r\n",
+            single_line(&archive.title),
+            archive.id.0,
+            archive.page_id
+        );
+        if let Some(checksum) = &archive.checksum {
+            fields.push_str(&format!("checksum: {}\r\n", checksum));
+        }
+        records.push(record(
+            "metadata",
+            &[
+                ("WARC-Record-ID", record_id()),
+                ("WARC-Target-URI", archive.url.clone()),
+                ("WARC-Date", date.clone()),
+                ("WARC-Refers-To", main_id.clone()),
+                ("WARC-Concurrent-To", main_id.clone()),
+                ("Content-Type", "application/warc-fields".to_string()),
+            ],
+            fields.as_bytes(),
+        ));
+        let concurrent = [("WARC-Concurrent-To", main_id.clone())];
+
+        if self.config.include_media && !archive.media_files.is_empty() {
+            // Media files are named after the URL they were downloaded from
+            let urls: HashMap<String, String> = self
+                .archiver
+                .extract_content(&html, &archive.url)
+                .image_urls
+                .into_iter()
+                .map(|url| (self.archiver.generate_media_filename(&url), url))
+                .collect();
+            for media in &archive.media_files {
+                let path = Path::new(media);
+                let Some(url) = path.file_name().and_then(|name| urls.get(name.to_string_lossy().as_ref())) else {
+                    warn!("No URL known for media file {}, not exported", media);
+                    continue;
+                };
+                let data = match std::fs::read(path) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        warn!("Failed to read media file {}: {}", media, e);
+                        continue;
+                    }
+                };
+                let mime_type = self
+                    .archiver
+                    .guess_mime_type(url)
+                    .unwrap_or_else(|| "application/octet-stream".to_string());
+                records.push(resource_record(&record_id(), url, &date, &mime_type, &concurrent, &data));
+            }
+        }
+
+        if let (true, Some(snapshots)) = (self.config.include_snapshots, &self.snapshots) {
+            for snapshot in snapshots.list_for_archive(&archive.id).await? {
+                let Some(data) = snapshots.read(&snapshot.id).await? else {
+                    warn!("Snapshot file of {} is missing, not exported", snapshot.id);
+                    continue;
+                };
+                let kind = match snapshot.kind {
+                    SnapshotKind::Mhtml => "mhtml",
+                    SnapshotKind::Screenshot => "screenshot",
+                    SnapshotKind::Pdf => "pdf",
+                };
+                let uri = format!("urn:{}:{}", kind, archive.url);
+                let date = snapshot.created_at.to_rfc3339_opts(SecondsFormat::Secs, true);
+                records.push(resource_record(&record_id(), &uri, &date, &snapshot.mime_type, &concurrent, &data));
+            }
+        }
+        Ok(records)
+    }
+}
+
+fn warcinfo_record(filename: &str) -> Vec<u8> {
+    let fields = format!(
+        "software: web-page-manager/{}\r\nformat: WARC File Format 1.1\r\nconformsTo: https://iipc.github.io/warc-specifications/specifications/warc-format/warc-1.1/\r\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    record(
+        "warcinfo",
+        &[
+            ("WARC-Record-ID", record_id()),
+            ("WARC-Date", Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)),
+            ("WARC-Filename", filename.to_string()),
+            ("Content-Type", "application/warc-fields".to_string()),
+        ],
+        fields.as_bytes(),
+    )
+}
+
+/// A `resource` record of `data` retrieved from `uri`
+fn resource_record(
+    id: &str,
+    uri: &str,
+    date: &str,
+    content_type: &str,
+    extra: &[(&str, String)],
+    data: &[u8],
+) -> Vec<u8> {
+    let digest = sha1_digest(data);
+    let mut headers = vec![
+        ("WARC-Record-ID", id.to_string()),
+        ("WARC-Target-URI", uri.to_string()),
+        ("WARC-Date", date.to_string()),
+        ("WARC-Block-Digest", digest.clone()),
+        ("WARC-Payload-Digest", digest),
+        ("Content-Type", content_type.to_string()),
+    ];
+    headers.extend(extra.iter().cloned());
+    record("resource", &headers, data)
+}
+
+/// A WARC record of `block` with the named headers after `WARC-Type`
+fn record(warc_type: &str, headers: &[(&str, String)], block: &[u8]) -> Vec<u8> {
+    let mut out = format!("{}\r\nWARC-Type: {}\r\n", WARC_VERSION, warc_type);
+    for (name, value) in headers {
+        out.push_str(&format!("{}: {}\r\n", name, single_line(value)));
+    }
+    out.push_str(&format!("Content-Length: {}\r\n\r\n", block.len()));
+    let mut out = out.into_bytes();
+    out.extend_from_slice(block);
+    out.extend_from_slice(b"\r\n\r\n");
+    out
+}
+
+fn record_id() -> String {
+    format!("<urn:uuid:{}>", Uuid::new_v4())
+}
+
+/// Header values cannot span lines
+fn single_line(value: &str) -> String {
+    value.split(['\r', '\n']).filter(|part| !part.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+/// `sha1:` followed by the base32 SHA-1 of `data`, as WARC digests are written
+fn sha1_digest(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut encoded = String::from("sha1:");
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in Sha1::digest(data) {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    // A SHA-1 is 160 bits, a whole number of base32 digits
+    encoded
+}
+
+fn export_error(path: &Path, e: std::io::Error) -> WebPageManagerError {
+    WebPageManagerError::System {
+        source: SystemError::Configuration {
+            details: format!("Failed to write WARC file {}: {}", path.display(), e),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_archiver::ContentArchiverConfig;
+    use crate::test_support::test_page;
+    use data_access::{DatabaseManager, PageRepository};
+
+    /// Records of a WARC file as (type, headers, block)
+    fn parse(data: &[u8]) -> Vec<(String, HashMap<String, String>, Vec<u8>)> {
+        let mut records = Vec::new();
+        let mut rest = data;
+        while !rest.is_empty() {
+            let end = rest.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+            let head = std::str::from_utf8(&rest[..end]).unwrap();
+            let mut lines = head.split("\r\n");
+            assert_eq!(lines.next(), Some(WARC_VERSION));
+            let headers: HashMap<String, String> = lines
+                .map(|l| l.split_once(": ").unwrap())
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            let length: usize = headers["Content-Length"].parse().unwrap();
+            let block = rest[end + 4..end + 4 + length].to_vec();
+            assert_eq!(&rest[end + 4 + length..end + 8 + length], b"\r\n\r\n");
+            rest = &rest[end + 8 + length..];
+            records.push((headers["WARC-Type"].clone(), headers, block));
+        }
+        records
+    }
+
+    #[test]
+    fn test_sha1_digest() {
+        assert_eq!(sha1_digest(b""), "sha1:3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ");
+        assert_eq!(sha1_digest(b"abc"), "sha1:VGMT4NSHA2AWVOR6EVYXQUGCNSONBWE5");
+    }
+
+    #[tokio::test]
+    async fn test_export_archives_to_warc() {
+        let db = DatabaseManager::in_memory().await.unwrap();
+        let dir = std::env::temp_dir().join(format!("warc-export-{}", Uuid::new_v4()));
+        let config = ContentArchiverConfig {
+            media_storage_path: dir.join("media"),
+            ..Default::default()
+        };
+        let archiver = Arc::new(
+            ContentArchiver::with_config(config).with_repository(Arc::new(db.archive_repository())),
+        );
+        let snapshots = Arc::new(db.snapshot_repository(dir.join("snapshots")));
+
+        let mut ids = Vec::new();
+        for url in ["https://example.com/guide", "https://example.com/news"] {
+            let page = test_page(url, url, &[]);
+            db.page_repository().save(&page).await.unwrap();
+            let html = format!(
+                "<html><head><title>Title of {}</title></head><body><p>Body of {}</p>\
+                 <img src=\"https://cdn.example.com/photo.png\"></body></html>",
+                url, url
+            );
+            let archived = archiver.archive_page(page.id, url, &html).await.unwrap();
+            ids.push(archived.archive.id);
+        }
+        snapshots.store(&ids[0], SnapshotKind::Mhtml, b"MIME-Version: 1.0", "multipart/related").await.unwrap();
+
+        // Everything in one file
+        let out = dir.join("warc");
+        let exporter = WarcExporter::new(archiver.clone()).with_snapshots(snapshots);
+        let missing = ArchiveId::new();
+        let export = exporter.export(&[ids[0].clone(), missing.clone(), ids[1].clone()], &out).await.unwrap();
+        assert_eq!(export.files, vec![out.join("web-page-manager-00000.warc")]);
+        assert_eq!(export.archives, 2);
+        assert_eq!(export.missing, vec![missing]);
+
+        let data = std::fs::read(&export.files[0]).unwrap();
+        assert_eq!(data.len() as u64, export.bytes);
+        let records = parse(&data);
+        assert_eq!(records.len(), export.records);
+        let types: Vec<&str> = records.iter().map(|r| r.0.as_str()).collect();
+        assert_eq!(
+            types,
+            vec!["warcinfo", "resource", "metadata", "resource", "resource", "resource", "metadata", "resource"]
+        );
+
+        let (_, html, block) = &records[1];
+        assert_eq!(html["WARC-Target-URI"], "https://example.com/guide");
+        assert_eq!(html["WARC-Block-Digest"], sha1_digest(block));
+        assert!(String::from_utf8_lossy(block).contains("Body of https://example.com/guide"));
+        let (_, metadata, block) = &records[2];
+        assert_eq!(metadata["WARC-Refers-To"], html["WARC-Record-ID"]);
+        assert!(String::from_utf8_lossy(block).contains(&format!("archive-id: {}", ids[0].0)));
+        let (_, media, block) = &records[3];
+        assert_eq!(media["WARC-Target-URI"], "https://cdn.example.com/photo.png");
+        assert_eq!(media["Content-Type"], "image/png");
+        assert_eq!(media["WARC-Concurrent-To"], html["WARC-Record-ID"]);
+        assert!(!block.is_empty());
+        let (_, snapshot, block) = &records[4];
+        assert_eq!(snapshot["WARC-Target-URI"], "urn:mhtml:https://example.com/guide");
+        assert_eq!(snapshot["Content-Type"], "multipart/related");
+        assert_eq!(block.as_slice(), b"MIME-Version: 1.0");
+
+        // Small files hold one archive each, each with its own warcinfo
+        let config = WarcExportConfig {
+            file_prefix: "small".to_string(),
+            max_file_size: 1,
+            include_media: false,
+            include_snapshots: false,
+        };
+        let export = WarcExporter::new(archiver).with_config(config).export(&ids, &out).await.unwrap();
+        assert_eq!(export.files.len(), 2);
+        for file in &export.files {
+            let records = parse(&std::fs::read(file).unwrap());
+            let types: Vec<&str> = records.iter().map(|r| r.0.as_str()).collect();
+            assert_eq!(types, vec!["warcinfo", "resource", "metadata"]);
+            assert_eq!(records[0].1["WARC-Filename"], file.file_name().unwrap().to_string_lossy());
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}